@@ -31,6 +31,7 @@ pub struct SessionConfig {
     pub precision: String,
     pub target_partitions: Option<usize>,
     pub stream_trigger_interval: Option<String>,
+    pub consistency_level: Option<String>,
     pub fmt: PrintFormat,
     pub config_options: ConfigOptions,
     pub use_ssl: bool,
@@ -51,6 +52,7 @@ impl SessionConfig {
             precision: DEFAULT_PRECISION.to_string(),
             target_partitions: None,
             stream_trigger_interval: None,
+            consistency_level: None,
             config_options,
             fmt: PrintFormat::Csv,
             use_ssl: DEFAULT_USE_SSL,
@@ -98,6 +100,11 @@ impl SessionConfig {
         self
     }
 
+    pub fn with_consistency_level(mut self, consistency_level: Option<String>) -> Self {
+        self.consistency_level = consistency_level;
+        self
+    }
+
     pub fn with_host(mut self, host: String) -> Self {
         self.connection_info.host = host;
 
@@ -215,6 +222,7 @@ impl SessionContext {
         let db = self.session_config.database.clone();
         let target_partitions = self.session_config.target_partitions;
         let stream_trigger_interval = self.session_config.stream_trigger_interval.clone();
+        let consistency_level = self.session_config.consistency_level.clone();
         let chunked = self.session_config.chunked;
         let param = SqlParam {
             tenant: Some(tenant),
@@ -222,6 +230,7 @@ impl SessionContext {
             chunked: Some(chunked),
             target_partitions,
             stream_trigger_interval,
+            consistency_level,
         };
 
         // let param = &[("db", &self.session_config.database)];
@@ -279,11 +288,14 @@ impl SessionContext {
         let tenant = self.session_config.tenant.clone();
         let db = self.session_config.database.clone();
         let precision = self.session_config.precision.clone();
+        let consistency_level = self.session_config.consistency_level.clone();
 
         let param = WriteParam {
             precision: Some(precision),
             tenant: Some(tenant),
             db: Some(db),
+            consistency_level,
+            partial_write: None,
         };
 
         let resp = self
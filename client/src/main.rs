@@ -59,6 +59,10 @@ struct Args {
     #[arg(short, long)]
     stream_trigger_interval: Option<String>,
 
+    /// Consistency level of writes, will be used as the url param 'consistency_level'.
+    #[arg(long, value_parser = PossibleValuesParser::new(["any", "one", "quorum", "all"]))]
+    consistency_level: Option<String>,
+
     /// Path to your data, default to current directory
     #[arg(long, value_parser = try_parse_data_dir)]
     data_path: Option<String>,
@@ -144,6 +148,7 @@ pub async fn main() -> Result<(), anyhow::Error> {
         .with_database(args.database)
         .with_target_partitions(args.target_partitions)
         .with_stream_trigger_interval(args.stream_trigger_interval)
+        .with_consistency_level(args.consistency_level)
         .with_result_format(args.format)
         .with_precision(args.precision)
         .with_ssl(args.use_ssl)
@@ -1,5 +1,7 @@
 // re-export const header names
-pub use reqwest::header::{HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+pub use reqwest::header::{
+    HeaderValue, ACCEPT, ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE,
+};
 
 // header
 // privateKey
@@ -14,6 +16,14 @@ pub const APPLICATION_NDJSON: &str = "application/nd-json";
 pub const APPLICATION_TABLE: &str = "application/table";
 pub const APPLICATION_STAR: &str = "application/*";
 pub const STAR_STAR: &str = "*/*";
+pub const APPLICATION_PROTOBUF: &str = "application/x-protobuf";
+pub const APPLICATION_STREAMED_PROTOBUF_CHUNKS: &str =
+    "application/x-streamed-protobuf; proto=prometheus.ChunkedReadResponse";
+pub const SNAPPY: &str = "snappy";
+pub const GZIP: &str = "gzip";
+pub const DEFLATE: &str = "deflate";
+pub const ZSTD: &str = "zstd";
+pub const IDENTITY: &str = "identity";
 
 // basic auth
 pub const BASIC_PREFIX: &str = "Basic ";
@@ -24,3 +34,4 @@ pub const TENANT: &str = "tenant";
 pub const DB: &str = "db";
 pub const TARGET_PARTITIONS: &str = "target_partitions";
 pub const STREAM_TRIGGER_INTERVAL: &str = "stream_trigger_interval";
+pub const CONSISTENCY_LEVEL: &str = "consistency_level";
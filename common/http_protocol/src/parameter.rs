@@ -9,6 +9,8 @@ pub struct SqlParam {
     // Number of partitions for query execution. Increasing partitions can increase concurrency.
     pub target_partitions: Option<usize>,
     pub stream_trigger_interval: Option<String>,
+    // Default consistency level of writes, one of: any, one, quorum, all.
+    pub consistency_level: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -17,4 +19,8 @@ pub struct WriteParam {
     pub precision: Option<String>,
     pub tenant: Option<String>,
     pub db: Option<String>,
+    pub consistency_level: Option<String>,
+    // Write the valid lines and list the rejected lines in the response, instead of
+    // rejecting the whole request.
+    pub partial_write: Option<bool>,
 }
@@ -25,6 +25,36 @@ impl ErrorResponse {
 
 impl std::error::Error for ErrorResponse {}
 
+/// Why a line is rejected by a partial write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    ParseError,
+    TypeConflict,
+    ExpiredTimestamp,
+    Limiter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct RejectedLine {
+    /// Line number in the request starting from 1, or the index of the data point
+    /// starting from 1 for OpenTSDB JSON
+    pub line: usize,
+    pub reason: RejectReason,
+    pub message: String,
+}
+
+/// Response of a partial write.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct PartialWriteResponse {
+    /// Number of the lines written
+    pub written: usize,
+    /// Rejected lines, sorted by line number
+    pub rejected: Vec<RejectedLine>,
+}
+
 impl Display for ErrorResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
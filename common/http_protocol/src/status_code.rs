@@ -11,6 +11,8 @@ pub const NOT_FOUND: StatusCode = StatusCode::NOT_FOUND;
 pub const METHOD_NOT_ALLOWED: StatusCode = StatusCode::METHOD_NOT_ALLOWED;
 /// 请求的消息体过大，超过限制
 pub const PAYLOAD_TOO_LARGE: StatusCode = StatusCode::PAYLOAD_TOO_LARGE;
+/// 请求的消息体编码不支持
+pub const UNSUPPORTED_MEDIA_TYPE: StatusCode = StatusCode::UNSUPPORTED_MEDIA_TYPE;
 /// 操作执行失败
 pub const UNPROCESSABLE_ENTITY: StatusCode = StatusCode::UNPROCESSABLE_ENTITY;
 
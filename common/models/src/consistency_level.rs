@@ -1,7 +1,12 @@
-#[allow(dead_code)]
-#[derive(Debug)]
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ConsistencyLevel {
     /// allows for hinted handoff, potentially no write happened yet.
+    #[default]
     Any,
     /// at least one data node acknowledged a write or read.
     One,
@@ -10,3 +15,64 @@ pub enum ConsistencyLevel {
     /// requires all data nodes to acknowledge a write or read.
     All,
 }
+
+impl ConsistencyLevel {
+    /// Number of replicas that must acknowledge a write,
+    /// a hinted handoff is not an acknowledgement.
+    pub fn required_acks(&self, replica: usize) -> usize {
+        match self {
+            ConsistencyLevel::Any => 0,
+            ConsistencyLevel::One => 1.min(replica),
+            ConsistencyLevel::Quorum => replica / 2 + 1,
+            ConsistencyLevel::All => replica,
+        }
+    }
+}
+
+impl Display for ConsistencyLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsistencyLevel::Any => write!(f, "ANY"),
+            ConsistencyLevel::One => write!(f, "ONE"),
+            ConsistencyLevel::Quorum => write!(f, "QUORUM"),
+            ConsistencyLevel::All => write!(f, "ALL"),
+        }
+    }
+}
+
+impl FromStr for ConsistencyLevel {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "ANY" => Ok(Self::Any),
+            "ONE" => Ok(Self::One),
+            "QUORUM" => Ok(Self::Quorum),
+            "ALL" => Ok(Self::All),
+            _ => Err(format!(
+                "invalid consistency level '{s}', expected one of: any, one, quorum, all"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConsistencyLevel;
+
+    #[test]
+    fn test_required_acks() {
+        assert_eq!(ConsistencyLevel::Any.required_acks(3), 0);
+        assert_eq!(ConsistencyLevel::One.required_acks(3), 1);
+        assert_eq!(ConsistencyLevel::Quorum.required_acks(1), 1);
+        assert_eq!(ConsistencyLevel::Quorum.required_acks(2), 2);
+        assert_eq!(ConsistencyLevel::Quorum.required_acks(3), 2);
+        assert_eq!(ConsistencyLevel::All.required_acks(3), 3);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("quorum".parse(), Ok(ConsistencyLevel::Quorum));
+        assert_eq!("ALL".parse(), Ok(ConsistencyLevel::All));
+        assert!("two".parse::<ConsistencyLevel>().is_err());
+    }
+}
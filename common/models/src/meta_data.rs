@@ -8,7 +8,7 @@ use crate::auth::role::{CustomTenantRole, TenantRoleIdentifier};
 use crate::node_info::NodeStatus;
 use crate::oid::Oid;
 use crate::predicate::domain::TimeRange;
-use crate::schema::{DatabaseSchema, TableSchema};
+use crate::schema::{DatabaseSchema, MaterializedView, TableSchema};
 
 pub type VnodeId = u32;
 pub type NodeId = u64;
@@ -28,6 +28,14 @@ pub struct ExpiredBucketInfo {
     pub bucket: BucketInfo,
 }
 
+/// A bucket older than the cold after of the database, its vnodes should be on cold nodes.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ColdBucketInfo {
+    pub tenant: String,
+    pub database: String,
+    pub bucket: BucketInfo,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct UserInfo {
     pub name: String,
@@ -67,6 +75,9 @@ pub struct NodeInfo {
     pub grpc_addr: String,
     pub http_addr: String,
     pub attribute: NodeAttribute,
+    /// The node is decommissioned by `DECOMMISSION NODE`, its vnodes are moved to other nodes
+    #[serde(default)]
+    pub decommissioned: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -77,6 +88,14 @@ pub struct NodeMetrics {
     pub status: NodeStatus,
 }
 
+/// A named lease in meta, which is held by one node until it expires.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Lease {
+    pub node_id: NodeId,
+    /// Expire time of the lease, in seconds
+    pub expire: i64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct BucketInfo {
     pub id: u32,
@@ -173,6 +192,8 @@ pub struct DatabaseInfo {
     pub schema: DatabaseSchema,
     pub buckets: Vec<BucketInfo>,
     pub tables: HashMap<String, TableSchema>,
+    #[serde(default)]
+    pub materialized_views: HashMap<String, MaterializedView>,
 }
 
 impl DatabaseInfo {
@@ -226,6 +247,27 @@ impl TenantMetaData {
         None
     }
 
+    pub fn materialized_view(&self, db: &str, name: &str) -> Option<MaterializedView> {
+        self.dbs
+            .get(db)
+            .and_then(|info| info.materialized_views.get(name))
+            .cloned()
+    }
+
+    /// Returns the materialized views which aggregate the given table.
+    pub fn materialized_views_of(&self, db: &str, base_table: &str) -> Vec<MaterializedView> {
+        self.dbs
+            .get(db)
+            .map(|info| {
+                info.materialized_views
+                    .values()
+                    .filter(|view| view.base_table == base_table)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     pub fn database_min_ts(&self, name: &str) -> Option<i64> {
         if let Some(db) = self.dbs.get(name) {
             return Some(db.time_to_expired());
@@ -266,6 +308,56 @@ impl TenantMetaData {
     }
 }
 
+// CREATE CONTINUOUS QUERY <name> ON <database>
+// RESAMPLE EVERY <duration> [FOR <duration>]
+// AS SELECT ... INTO <table> FROM ...
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContinuousQueryInfo {
+    pub name: String,
+    pub database: String,
+    /// How often the query runs, in nanoseconds
+    pub resample_every: i64,
+    /// Time range computed by each run, in nanoseconds
+    pub resample_for: i64,
+    /// The `SELECT ... INTO` statement
+    pub query: String,
+    /// The query runs with the privileges of this user
+    pub owner: String,
+    /// End of the last processed time range, exclusive
+    pub watermark: Option<i64>,
+    /// Width of the time window the query groups by, in nanoseconds
+    #[serde(default)]
+    pub window: Option<i64>,
+}
+
+impl ContinuousQueryInfo {
+    /// Returns the next time range `[start, end)` to process at `now`,
+    /// or `None` if the latest range has been processed.
+    ///
+    /// Ranges are aligned to `resample_every` and to the time window of the query,
+    /// so each window is computed from all of its data. Each run re-computes the last
+    /// `resample_for` to pick up late data. If runs were missed, e.g. the server was down,
+    /// ranges from the watermark are caught up step by step.
+    pub fn next_time_range(&self, now: i64) -> Option<(i64, i64)> {
+        let align = |ts: i64| match self.window {
+            Some(window) if window > 0 => ts - ts.rem_euclid(window),
+            _ => ts,
+        };
+
+        let end = align(now - now.rem_euclid(self.resample_every));
+        let (start, end) = match self.watermark {
+            Some(watermark) if watermark >= end => return None,
+            Some(watermark) if watermark < end - self.resample_for => {
+                (watermark, watermark + self.resample_for)
+            }
+            _ => (end - self.resample_for, end),
+        };
+        let (start, end) = (align(start), align(end));
+
+        (start < end).then_some((start, end))
+    }
+}
+
 pub fn get_time_range(ts: i64, duration: i64) -> (i64, i64) {
     if duration <= 0 {
         (std::i64::MIN, std::i64::MAX)
@@ -367,3 +459,43 @@ pub fn get_disk_info(path: &str) -> std::io::Result<u64> {
             * (disk_space_info.SectorsPerAllocationUnit * disk_space_info.BytesPerSector) as u64)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::ContinuousQueryInfo;
+
+    #[test]
+    fn test_continuous_query_next_time_range() {
+        let mut cq = ContinuousQueryInfo {
+            resample_every: 10,
+            resample_for: 30,
+            ..Default::default()
+        };
+
+        // first run
+        assert_eq!(cq.next_time_range(105), Some((70, 100)));
+        cq.watermark = Some(100);
+        assert_eq!(cq.next_time_range(109), None);
+        // re-compute the last `resample_for` for late data
+        assert_eq!(cq.next_time_range(112), Some((80, 110)));
+        cq.watermark = Some(110);
+        // catch up after a long pause
+        assert_eq!(cq.next_time_range(200), Some((110, 140)));
+        cq.watermark = Some(140);
+        assert_eq!(cq.next_time_range(200), Some((140, 170)));
+        cq.watermark = Some(170);
+        assert_eq!(cq.next_time_range(200), Some((170, 200)));
+
+        // ranges are aligned to the time window of the query
+        let mut cq = ContinuousQueryInfo {
+            resample_every: 10,
+            resample_for: 30,
+            window: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(cq.next_time_range(105), Some((60, 100)));
+        cq.watermark = Some(100);
+        assert_eq!(cq.next_time_range(115), None);
+        assert_eq!(cq.next_time_range(121), Some((80, 120)));
+    }
+}
@@ -849,10 +849,40 @@ impl EqutableValueSet {
     }
 }
 
+/// A regular expression that the value matches, or does not match if negated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegexPattern {
+    pattern: String,
+    negated: bool,
+}
+
+impl RegexPattern {
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+}
+
+/// A set of regular expressions, a value is contained if it satisfies all of them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegexValueSet {
+    patterns: Vec<RegexPattern>,
+}
+
+impl RegexValueSet {
+    pub fn patterns(&self) -> &[RegexPattern] {
+        &self.patterns
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Domain {
     Range(RangeValueSet),
     Equtable(EqutableValueSet),
+    Regex(RegexValueSet),
     None,
     All,
 }
@@ -920,6 +950,15 @@ impl Domain {
             entries,
         })
     }
+    /// Construct a set of values that match (or not match if negated) the regular expression.
+    pub fn of_regex(pattern: impl Into<String>, negated: bool) -> Domain {
+        Domain::Regex(RegexValueSet {
+            patterns: vec![RegexPattern {
+                pattern: pattern.into(),
+                negated,
+            }],
+        })
+    }
     /// Calculates the intersection of two ranges, and returns None if the intersection does not exist
     ///
     /// This method returns the new value without changing the old value
@@ -934,6 +973,26 @@ impl Domain {
             (Self::None, _) | (_, Self::None) => Ok(Self::None),
             (Self::All, _) => Ok(other.clone()),
             (_, Self::All) => Ok(self.clone()),
+            // eg. c1 > 1 and c1 != 5
+            (Self::Range(_), Self::Equtable(ref val_set)) => {
+                self.intersect(&Domain::equtable_to_ranges(val_set)?)
+            }
+            (Self::Equtable(ref val_set), Self::Range(_)) => {
+                Domain::equtable_to_ranges(val_set)?.intersect(other)
+            }
+            (Self::Regex(ref self_val_set), Self::Regex(ref other_val_set)) => {
+                let mut patterns = self_val_set.patterns.clone();
+                for pattern in other_val_set.patterns.iter() {
+                    if !patterns.contains(pattern) {
+                        patterns.push(pattern.clone());
+                    }
+                }
+                Ok(Self::Regex(RegexValueSet { patterns }))
+            }
+            // Regex can not be merged with values, keep the other side,
+            // which contains the intersection.
+            (Self::Regex(_), _) => Ok(other.clone()),
+            (_, Self::Regex(_)) => Ok(self.clone()),
             _ => Err(Error::Internal {
                 err: "mismatched ValueSet type".to_string(),
             }),
@@ -957,11 +1016,42 @@ impl Domain {
             (Self::None, _) => Ok(other.clone()),
             (_, Self::None) => Ok(self.clone()),
             (Self::All, _) | (_, Self::All) => Ok(Self::All),
+            (Self::Range(_), Self::Equtable(ref val_set)) => {
+                self.union(&Domain::equtable_to_ranges(val_set)?)
+            }
+            (Self::Equtable(ref val_set), Self::Range(_)) => {
+                Domain::equtable_to_ranges(val_set)?.union(other)
+            }
+            (Self::Regex(_), Self::Regex(_)) if self == other => Ok(self.clone()),
+            (Self::Regex(_), _) | (_, Self::Regex(_)) => Ok(Self::All),
             _ => Err(Error::Internal {
                 err: "mismatched ValueSet type".to_string(),
             }),
         }
     }
+    /// Convert an equable value set of orderable values to ranges,
+    /// so that it can be merged with a RangeValueSet.
+    ///
+    /// eg. c1 != 5 => (_, 5) ∪ (5, _)
+    fn equtable_to_ranges(val_set: &EqutableValueSet) -> Result<Domain> {
+        let data_type = &val_set.data_type;
+        if val_set.white_list {
+            let ranges: Vec<Range> = val_set
+                .entries
+                .iter()
+                .map(|e| Range::eq(data_type, &e.value))
+                .collect();
+            return Domain::of_ranges(&ranges);
+        }
+
+        val_set.entries.iter().try_fold(Domain::All, |acc, e| {
+            let excluded = Domain::of_ranges(&[
+                Range::lt(data_type, &e.value),
+                Range::gt(data_type, &e.value),
+            ])?;
+            acc.intersect(&excluded)
+        })
+    }
     /// Merge and intersect two ordered range sets
     ///
     /// This method returns the new ValueSet without changing the old value
@@ -1351,6 +1441,24 @@ impl ResolvedPredicate {
     pub fn fields_filter(&self) -> &ColumnDomains<String> {
         &self.fields_filter
     }
+
+    pub fn encode(predicate: &ResolvedPredicate) -> Result<Vec<u8>> {
+        let d = bincode::serialize(predicate).map_err(|err| Error::InvalidSerdeMessage {
+            err: err.to_string(),
+        })?;
+
+        Ok(d)
+    }
+
+    pub fn decode(buf: &[u8]) -> Result<ResolvedPredicate> {
+        let predicate = bincode::deserialize::<ResolvedPredicate>(buf).map_err(|err| {
+            Error::InvalidSerdeMessage {
+                err: err.to_string(),
+            }
+        })?;
+
+        Ok(predicate)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -1466,7 +1574,7 @@ impl QueryExpr {
     }
 }
 
-pub fn encode_agg(agg: &Option<Vec<TableColumn>>) -> Result<Vec<u8>> {
+pub fn encode_agg(agg: &Option<Vec<ColumnAggregate>>) -> Result<Vec<u8>> {
     let d = bincode::serialize(agg).map_err(|err| Error::InvalidSerdeMessage {
         err: err.to_string(),
     })?;
@@ -1474,8 +1582,8 @@ pub fn encode_agg(agg: &Option<Vec<TableColumn>>) -> Result<Vec<u8>> {
     Ok(d)
 }
 
-pub fn decode_agg(buf: &[u8]) -> Result<Option<Vec<TableColumn>>> {
-    let args = bincode::deserialize::<Option<Vec<TableColumn>>>(buf).map_err(|err| {
+pub fn decode_agg(buf: &[u8]) -> Result<Option<Vec<ColumnAggregate>>> {
+    let args = bincode::deserialize::<Option<Vec<ColumnAggregate>>>(buf).map_err(|err| {
         Error::InvalidSerdeMessage {
             err: err.to_string(),
         }
@@ -1487,6 +1595,62 @@ pub fn decode_agg(buf: &[u8]) -> Result<Option<Vec<TableColumn>>> {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PushedAggregateFunction {
     Count(String),
+    Min(String),
+    Max(String),
+    Sum(String),
+    First(String),
+    Last(String),
+}
+
+impl PushedAggregateFunction {
+    pub fn kind(&self) -> AggregateKind {
+        match self {
+            Self::Count(_) => AggregateKind::Count,
+            Self::Min(_) => AggregateKind::Min,
+            Self::Max(_) => AggregateKind::Max,
+            Self::Sum(_) => AggregateKind::Sum,
+            Self::First(_) => AggregateKind::First,
+            Self::Last(_) => AggregateKind::Last,
+        }
+    }
+
+    pub fn column_name(&self) -> &str {
+        match self {
+            Self::Count(column)
+            | Self::Min(column)
+            | Self::Max(column)
+            | Self::Sum(column)
+            | Self::First(column)
+            | Self::Last(column) => column,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AggregateKind {
+    Count,
+    Min,
+    Max,
+    Sum,
+    /// Value with the minimum timestamp, returned after the timestamp
+    /// so that the partial results can be merged.
+    First,
+    /// Value with the maximum timestamp, returned after the timestamp
+    /// so that the partial results can be merged.
+    Last,
+}
+
+/// Pushed aggregate function with the column resolved from table schema.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnAggregate {
+    pub kind: AggregateKind,
+    pub column: TableColumn,
+}
+
+impl ColumnAggregate {
+    pub fn new(kind: AggregateKind, column: TableColumn) -> Self {
+        Self { kind, column }
+    }
 }
 
 #[cfg(test)]
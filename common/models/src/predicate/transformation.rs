@@ -5,6 +5,7 @@ use datafusion::arrow::datatypes::DataType;
 use datafusion::common::tree_node::{TreeNode, TreeNodeVisitor, VisitRecursion};
 use datafusion::common::Result as DFResult;
 use datafusion::error::DataFusionError;
+use datafusion::logical_expr::expr::InList;
 use datafusion::logical_expr::{BinaryExpr, Operator};
 use datafusion::prelude::{Column, Expr};
 use datafusion::scalar::ScalarValue;
@@ -178,6 +179,10 @@ impl TreeNodeVisitor for RowExpressionToDomainsVisitor<'_> {
                     | Operator::LtEq
                     | Operator::Gt
                     | Operator::GtEq
+                    | Operator::RegexMatch
+                    | Operator::RegexIMatch
+                    | Operator::RegexNotMatch
+                    | Operator::RegexNotIMatch
                     | Operator::And
                     | Operator::Or => {
                         // support
@@ -192,6 +197,17 @@ impl TreeNodeVisitor for RowExpressionToDomainsVisitor<'_> {
                     }
                 }
             }
+            // eg. c1 not in ('a', 'b')
+            Expr::InList(InList {
+                expr,
+                list,
+                negated: true,
+            }) => {
+                let domains = Self::not_in_list_to_column_domains(expr, list)
+                    .unwrap_or_else(ColumnDomains::all);
+                self.ctx.current_domain_stack.push_back(domains);
+                Ok(VisitRecursion::Skip)
+            }
             // TODO Currently not supported, follow-up support needs to implement the corresponding expression in post_visit
             Expr::Like(_)
             | Expr::ILike(_)
@@ -231,6 +247,14 @@ impl TreeNodeVisitor for RowExpressionToDomainsVisitor<'_> {
                             self.ctx, left, op, right,
                         );
                     }
+                    Operator::RegexMatch
+                    | Operator::RegexIMatch
+                    | Operator::RegexNotMatch
+                    | Operator::RegexNotIMatch => {
+                        let domains = Self::regex_to_column_domains(left, op, right)
+                            .unwrap_or_else(ColumnDomains::all);
+                        self.ctx.current_domain_stack.push_back(domains);
+                    }
                     // The stack is domain, pop it, and generate a new domain
                     Operator::And => {
                         let domain1_opt = self.ctx.current_domain_stack.pop_back();
@@ -303,6 +327,61 @@ impl RowExpressionToDomainsVisitor<'_> {
         let val_set = Domain::of_values(&value.get_datatype(), is_eq_op, &[value]);
         ColumnDomains::of(col.to_owned(), &val_set)
     }
+    /// Convert `column not in (v1, v2, ...)` to an EqutableValueSet of black list,
+    /// return None if the list is not made up of non-null literals of the same type.
+    fn not_in_list_to_column_domains(expr: &Expr, list: &[Expr]) -> Option<ColumnDomains<Column>> {
+        let column = match expr {
+            Expr::Column(column) => column,
+            _ => return None,
+        };
+        let values = list
+            .iter()
+            .map(|e| match e {
+                Expr::Literal(value) if !value.is_null() => Some(value),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let data_type = values.first()?.get_datatype();
+        if values.iter().any(|v| v.get_datatype() != data_type) {
+            return None;
+        }
+
+        Some(ColumnDomains::of(
+            column.to_owned(),
+            &Domain::of_values(&data_type, false, &values),
+        ))
+    }
+    /// Convert `column ~ 'pattern'` to a regex domain,
+    /// return None if it is not a comparison between a column and a string literal.
+    fn regex_to_column_domains(
+        left: &Expr,
+        op: &Operator,
+        right: &Expr,
+    ) -> Option<ColumnDomains<Column>> {
+        let (column, pattern) = match (left, right) {
+            (Expr::Column(column), Expr::Literal(ScalarValue::Utf8(Some(pattern)))) => {
+                (column, pattern)
+            }
+            _ => return None,
+        };
+        let (case_insensitive, negated) = match op {
+            Operator::RegexMatch => (false, false),
+            Operator::RegexIMatch => (true, false),
+            Operator::RegexNotMatch => (false, true),
+            Operator::RegexNotIMatch => (true, true),
+            _ => return None,
+        };
+        let pattern = if case_insensitive {
+            format!("(?i){pattern}")
+        } else {
+            pattern.to_owned()
+        };
+
+        Some(ColumnDomains::of(
+            column.to_owned(),
+            &Domain::of_regex(pattern, negated),
+        ))
+    }
     /// Construct comparison operations as simple column-value comparison data structures nsc.
     ///
     /// Choose a different NscToValueSet function based on whether the data type supports sorting.
@@ -318,7 +397,8 @@ impl RowExpressionToDomainsVisitor<'_> {
     ) {
         let domains_opt = NormalizedSimpleComparison::of(left.clone(), *op, right.clone())
             .map(|ref nsc| {
-                if nsc.is_orderable() {
+                // Not equal can not be represented by a single range
+                if nsc.is_orderable() && nsc.op != Operator::NotEq {
                     return Self::nsc_to_column_domains_with_range(nsc);
                 }
                 Self::nsc_to_domains_with_equtable(nsc)
@@ -1224,6 +1304,94 @@ mod tests {
         );
     }
 
+    /// regex push down
+    /// eg.
+    ///   c1 ~ 'a.*' and c1 !~* 'ab' and c2 = 'x'
+    ///   ===>
+    ///   c1: Regex('a.*', not '(?i)ab')
+    ///   c2: 'x'
+    #[test]
+    fn test_regex_expr_to_domain() {
+        let c1_1 = binary_expr(col("c1"), Operator::RegexMatch, lit("a.*"));
+        let c1_2 = binary_expr(col("c1"), Operator::RegexNotIMatch, lit("ab"));
+        let c2 = binary_expr(col("c2"), Operator::Eq, lit("x"));
+
+        let expr = and(and(c1_1, c1_2), c2);
+
+        let column_domain = get_domains(&expr).unwrap();
+        let domains = column_domain.domains().unwrap();
+
+        match domains.get(&Column::from_name("c1")) {
+            Some(Domain::Regex(regex_set)) => {
+                let patterns = regex_set
+                    .patterns()
+                    .iter()
+                    .map(|p| (p.pattern(), p.is_negated()))
+                    .collect::<Vec<_>>();
+                assert_eq!(patterns, vec![("a.*", false), ("(?i)ab", true)]);
+            }
+            other => panic!("excepted regex domain, found {:?}", other),
+        }
+        assert!(domains.contains_key(&Column::from_name("c2")));
+
+        // regex on both sides of an OR can not be merged
+        let expr = or(
+            binary_expr(col("c1"), Operator::RegexMatch, lit("a.*")),
+            binary_expr(col("c1"), Operator::RegexMatch, lit("b.*")),
+        );
+        let column_domain = get_domains(&expr).unwrap();
+        assert_eq!(
+            column_domain
+                .domains()
+                .unwrap()
+                .get(&Column::from_name("c1")),
+            Some(&Domain::All)
+        );
+    }
+
+    /// negative push down
+    /// eg.
+    ///   c1 != 'a' and c2 not in ('b', 'c') and c3 > 1 and c3 != 5
+    ///   ===>
+    ///   c1: not 'a'
+    ///   c2: not ('b', 'c')
+    ///   c3: (1, 5) ∪ (5, _)
+    #[test]
+    fn test_negative_expr_to_domain() {
+        let c1 = binary_expr(col("c1"), Operator::NotEq, lit("a"));
+        let c2 = in_list(col("c2"), vec![lit("b"), lit("c")], true);
+        let c3_1 = binary_expr(col("c3"), Operator::Gt, lit(1));
+        let c3_2 = binary_expr(col("c3"), Operator::NotEq, lit(5));
+
+        let expr = and(and(c1, c2), and(c3_1, c3_2));
+
+        let column_domain = get_domains(&expr).unwrap();
+
+        let c1_domain = Domain::of_values(
+            &DataType::Utf8,
+            false,
+            &[&ScalarValue::Utf8(Some("a".to_string()))],
+        );
+        let c2_domain = Domain::of_values(
+            &DataType::Utf8,
+            false,
+            &[
+                &ScalarValue::Utf8(Some("b".to_string())),
+                &ScalarValue::Utf8(Some("c".to_string())),
+            ],
+        );
+        let domains = column_domain.domains().unwrap();
+        assert_eq!(domains.get(&Column::from_name("c1")), Some(&c1_domain));
+        assert_eq!(domains.get(&Column::from_name("c2")), Some(&c2_domain));
+        // c3 > 1 and c3 != 5 => (1, 5) ∪ (5, _)
+        match domains.get(&Column::from_name("c3")) {
+            Some(Domain::Range(range_set)) => {
+                assert_eq!(range_set.low_indexed_ranges().into_iter().count(), 2)
+            }
+            other => panic!("excepted range domain, found {:?}", other),
+        }
+    }
+
     /// not support push down - 1
     /// eg.
     ///   c1 > 1 or \
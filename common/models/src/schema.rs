@@ -92,6 +92,33 @@ impl TableSchema {
             Self::StreamTableSchema(e) => e.schema(),
         }
     }
+
+    /// Returns a copy of the table schema that belongs to the given tenant and database.
+    pub fn with_database(&self, tenant: &str, db: &str) -> Self {
+        match self {
+            Self::TsKvTableSchema(schema) => {
+                let mut schema = schema.as_ref().clone();
+                schema.tenant = tenant.to_string();
+                schema.db = db.to_string();
+                Self::TsKvTableSchema(Arc::new(schema))
+            }
+            Self::ExternalTableSchema(schema) => {
+                let mut schema = schema.as_ref().clone();
+                schema.tenant = tenant.to_string();
+                schema.db = db.to_string();
+                Self::ExternalTableSchema(Arc::new(schema))
+            }
+            Self::StreamTableSchema(schema) => Self::StreamTableSchema(Arc::new(StreamTable::new(
+                tenant,
+                db,
+                schema.name(),
+                schema.schema(),
+                schema.stream_type(),
+                schema.watermark().clone(),
+                schema.extra_options().clone(),
+            ))),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -238,6 +265,9 @@ impl TskvTableSchema {
             None => return,
             Some(id) => *id,
         };
+        if col_name != new_column.name {
+            self.columns_index.remove(col_name);
+        }
         self.columns_index.insert(new_column.name.clone(), id);
         self.columns[id] = new_column;
     }
@@ -371,6 +401,10 @@ pub struct TableColumn {
     pub name: String,
     pub column_type: ColumnType,
     pub encoding: Encoding,
+    /// Name of the tag if the field column was converted from a tag, values of the
+    /// series written before the conversion are read from their series keys.
+    #[serde(default)]
+    pub converted_from_tag: Option<String>,
 }
 
 impl From<TableColumn> for ArrowField {
@@ -398,6 +432,7 @@ impl TableColumn {
             name,
             column_type,
             encoding,
+            converted_from_tag: None,
         }
     }
     pub fn new_with_default(name: String, column_type: ColumnType) -> Self {
@@ -406,6 +441,7 @@ impl TableColumn {
             name,
             column_type,
             encoding: Encoding::Default,
+            converted_from_tag: None,
         }
     }
 
@@ -415,6 +451,7 @@ impl TableColumn {
             name: TIME_FIELD_NAME.to_string(),
             column_type: ColumnType::Time(time_unit),
             encoding: Encoding::Default,
+            converted_from_tag: None,
         }
     }
 
@@ -424,6 +461,7 @@ impl TableColumn {
             name,
             column_type: ColumnType::Tag,
             encoding: Encoding::Default,
+            converted_from_tag: None,
         }
     }
 
@@ -614,6 +652,23 @@ impl ColumnType {
     pub fn is_field(&self) -> bool {
         matches!(self, ColumnType::Field(_))
     }
+
+    /// Returns true if the column can be changed to the given type without losing values:
+    /// a field can only be widened from integer types to double, a tag can be converted
+    /// to a string field.
+    pub fn is_convertible_to(&self, to: &ColumnType) -> bool {
+        match (self, to) {
+            (ColumnType::Field(from), ColumnType::Field(to)) => {
+                from == to
+                    || matches!(
+                        (from, to),
+                        (ValueType::Integer | ValueType::Unsigned, ValueType::Float)
+                    )
+            }
+            (ColumnType::Tag, ColumnType::Field(ValueType::String)) => true,
+            _ => false,
+        }
+    }
 }
 
 impl From<ValueType> for ColumnType {
@@ -692,6 +747,38 @@ impl DatabaseSchema {
         };
         now - ttl
     }
+
+    // return the max timestamp value of data that should stay on local disk,
+    // None if the database has no cold duration
+    pub fn time_to_cold(&self) -> Option<i64> {
+        let cold_duration = self.config.cold_duration().as_ref()?;
+        Some(self.time_before_now(cold_duration))
+    }
+
+    // return the max end time of buckets that should be moved to cold nodes,
+    // None if the database has no cold after
+    pub fn time_to_cold_node(&self) -> Option<i64> {
+        let cold_after = self.config.cold_after().as_ref()?;
+        Some(self.time_before_now(cold_after))
+    }
+
+    fn time_before_now(&self, duration: &Duration) -> i64 {
+        let (duration, now) = match self.config.precision_or_default() {
+            Precision::MS => (
+                duration.to_millisecond(),
+                crate::utils::now_timestamp_millis(),
+            ),
+            Precision::US => (
+                duration.to_microseconds(),
+                crate::utils::now_timestamp_micros(),
+            ),
+            Precision::NS => (
+                duration.to_nanoseconds(),
+                crate::utils::now_timestamp_nanos(),
+            ),
+        };
+        now - duration
+    }
 }
 
 pub fn make_owner(tenant_name: &str, database_name: &str) -> String {
@@ -721,6 +808,12 @@ pub struct DatabaseOptions {
     replica: Option<u64>,
     // timestamp precision
     precision: Option<Precision>,
+    // data older than this is moved to cold storage
+    #[serde(default)]
+    cold_duration: Option<Duration>,
+    // vnodes of buckets older than this are moved to cold nodes
+    #[serde(default)]
+    cold_after: Option<Duration>,
 }
 
 impl DatabaseOptions {
@@ -749,6 +842,8 @@ impl DatabaseOptions {
             vnode_duration,
             replica,
             precision,
+            cold_duration: None,
+            cold_after: None,
         }
     }
 
@@ -796,6 +891,14 @@ impl DatabaseOptions {
             .unwrap_or(&DatabaseOptions::DEFAULT_PRECISION)
     }
 
+    pub fn cold_duration(&self) -> &Option<Duration> {
+        &self.cold_duration
+    }
+
+    pub fn cold_after(&self) -> &Option<Duration> {
+        &self.cold_after
+    }
+
     pub fn with_ttl(&mut self, ttl: Duration) {
         self.ttl = Some(ttl);
     }
@@ -815,6 +918,14 @@ impl DatabaseOptions {
     pub fn with_precision(&mut self, precision: Precision) {
         self.precision = Some(precision)
     }
+
+    pub fn with_cold_duration(&mut self, cold_duration: Duration) {
+        self.cold_duration = Some(cold_duration);
+    }
+
+    pub fn with_cold_after(&mut self, cold_after: Duration) {
+        self.cold_after = Some(cold_after);
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1162,6 +1273,58 @@ impl StreamTable {
     }
 }
 
+// CREATE MATERIALIZED VIEW <name> AS
+// SELECT time_window(time, <interval>), <tag>, ..., <agg>(<field>), ...
+// FROM <base_table>
+// GROUP BY time_window(time, <interval>), <tag>, ...
+/// The aggregated data of a tskv table, which is kept in the tskv table named after the view
+/// and refreshed when new data is written into the base table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MaterializedView {
+    pub tenant: String,
+    pub db: String,
+    pub name: String,
+    pub base_table: String,
+    /// Width of the time windows, in nanoseconds
+    pub interval: i64,
+    /// The grouped tags, which have the same names in the view
+    pub tags: Vec<String>,
+    pub aggregates: Vec<MaterializedViewAggregate>,
+    /// The defining query
+    pub query: String,
+    /// The view is refreshed with the privileges of this user
+    pub owner: String,
+    /// The windows before this timestamp have been computed, later windows
+    /// are read from the base table
+    #[serde(default)]
+    pub watermark: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MaterializedViewAggregate {
+    /// Lower case name of the aggregate function, e.g. `avg`
+    pub func: String,
+    /// The aggregated field of the base table
+    pub column: String,
+    /// The field of the view which stores the aggregated values
+    pub field: String,
+}
+
+impl MaterializedView {
+    pub fn aggregate(&self, func: &str, column: &str) -> Option<&MaterializedViewAggregate> {
+        self.aggregates
+            .iter()
+            .find(|agg| agg.func.eq_ignore_ascii_case(func) && agg.column == column)
+    }
+
+    /// Returns the time range `[start, end)` of the windows which contain `[min_ts, max_ts]`.
+    pub fn window_range(&self, min_ts: i64, max_ts: i64) -> (i64, i64) {
+        let start = min_ts - min_ts.rem_euclid(self.interval);
+        let end = max_ts - max_ts.rem_euclid(self.interval);
+        (start, end.saturating_add(self.interval))
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub enum ScalarValueForkDF {
     /// represents `DataType::Null` (castable to/from any other type)
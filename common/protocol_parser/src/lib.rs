@@ -27,6 +27,34 @@ pub enum Error {
     Common { content: String },
 }
 
+/// A line failed to be parsed.
+#[derive(Debug)]
+pub struct LineError {
+    /// Line number in the lines, starting from 1
+    pub line: usize,
+    pub error: Error,
+}
+
+/// Parses the lines one by one, returns the parsed lines with their line numbers, and the
+/// errors of the lines failed to be parsed. Blank lines are skipped.
+fn parse_each_line<'a>(
+    lines: &'a str,
+    parse: impl Fn(&'a str) -> Result<Vec<Line<'a>>>,
+) -> (Vec<(usize, Line<'a>)>, Vec<LineError>) {
+    let mut parsed = vec![];
+    let mut errors = vec![];
+    for (i, buf) in lines.split('\n').enumerate() {
+        if buf.trim().is_empty() {
+            continue;
+        }
+        match parse(buf) {
+            Ok(lines) => parsed.extend(lines.into_iter().map(|line| (i + 1, line))),
+            Err(error) => errors.push(LineError { line: i + 1, error }),
+        }
+    }
+    (parsed, errors)
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Line<'a> {
     hash_id: u64,
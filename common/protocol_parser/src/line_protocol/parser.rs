@@ -95,6 +95,7 @@ mod test {
 
     use protos::FieldValue;
 
+    use crate::line_protocol::line_protocol_to_lines_partial;
     use crate::line_protocol::parser::{Line, Parser};
     use crate::{next_field_set, next_measurement, next_tag_set, next_value};
 
@@ -264,4 +265,15 @@ mod test {
             ("f", FieldValue::Str("白".to_string().into_bytes().to_vec()))
         );
     }
+
+    #[test]
+    fn test_parse_partial() {
+        let lines = "ma,ta=1 fa=1 1\nma fa=2\n\nmb,tb=2 fb=3 2\n";
+        let (parsed, errors) = line_protocol_to_lines_partial(lines, -1);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!((parsed[0].0, parsed[0].1.table), (1, "ma"));
+        assert_eq!((parsed[1].0, parsed[1].1.table), (4, "mb"));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 2);
+    }
 }
@@ -1,5 +1,5 @@
 use crate::open_tsdb::parser::Parser;
-use crate::{Line, Result};
+use crate::{parse_each_line, Line, LineError, Result};
 
 pub mod parser;
 
@@ -7,3 +7,12 @@ pub fn open_tsdb_to_lines(lines: &str, default_time: i64) -> Result<Vec<Line>> {
     let parser = Parser::new(default_time);
     parser.parse(lines)
 }
+
+/// Parses the lines one by one instead of failing on the first malformed line.
+pub fn open_tsdb_to_lines_partial(
+    lines: &str,
+    default_time: i64,
+) -> (Vec<(usize, Line)>, Vec<LineError>) {
+    let parser = Parser::new(default_time);
+    parse_each_line(lines, |line| parser.parse(line))
+}
@@ -167,12 +167,56 @@ pub struct AlterColumnRequest {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteFromTableRequest {
+    #[prost(string, tag = "1")]
+    pub db: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub table: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub predicate: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint32, repeated, tag = "4")]
+    pub vnode_ids: ::prost::alloc::vec::Vec<u32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackupVnodeRequest {
+    #[prost(string, tag = "1")]
+    pub db: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub vnode_id: u32,
+    #[prost(string, tag = "3")]
+    pub location: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "4")]
+    pub options: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreVnodeRequest {
+    #[prost(string, tag = "1")]
+    pub db: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub vnode_id: u32,
+    #[prost(uint32, tag = "3")]
+    pub backup_vnode_id: u32,
+    #[prost(string, tag = "4")]
+    pub location: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "5")]
+    pub options: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AdminCommandRequest {
     #[prost(string, tag = "1")]
     pub tenant: ::prost::alloc::string::String,
     #[prost(
         oneof = "admin_command_request::Command",
-        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10"
+        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13"
     )]
     pub command: ::core::option::Option<admin_command_request::Command>,
 }
@@ -199,6 +243,12 @@ pub mod admin_command_request {
         AddColumn(super::AddColumnRequest),
         #[prost(message, tag = "10")]
         AlterColumn(super::AlterColumnRequest),
+        #[prost(message, tag = "11")]
+        DeleteFromTable(super::DeleteFromTableRequest),
+        #[prost(message, tag = "12")]
+        BackupVnode(super::BackupVnodeRequest),
+        #[prost(message, tag = "13")]
+        RestoreVnode(super::RestoreVnodeRequest),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -209,10 +259,68 @@ pub struct FetchVnodeChecksumRequest {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FetchVnodeTimeRangeChecksumRequest {
+    #[prost(uint32, tag = "1")]
+    pub vnode_id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SeriesTimeRange {
+    /// Encoded series key, with series id 0
+    #[prost(bytes = "vec", tag = "1")]
+    pub series_key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(int64, tag = "2")]
+    pub min_ts: i64,
+    #[prost(int64, tag = "3")]
+    pub max_ts: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FetchVnodeSeriesDataRequest {
+    #[prost(uint32, tag = "1")]
+    pub vnode_id: u32,
+    #[prost(message, repeated, tag = "2")]
+    pub ranges: ::prost::alloc::vec::Vec<SeriesTimeRange>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SeriesTimeRangeRepair {
+    /// Encoded series key, with series id 0
+    #[prost(bytes = "vec", tag = "1")]
+    pub series_key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(int64, tag = "2")]
+    pub min_ts: i64,
+    #[prost(int64, tag = "3")]
+    pub max_ts: i64,
+    /// Digest of the data of the time range the points are merged from
+    #[prost(string, tag = "4")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "5")]
+    pub points: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RepairVnodeSeriesDataRequest {
+    #[prost(uint32, tag = "1")]
+    pub vnode_id: u32,
+    #[prost(uint32, tag = "2")]
+    pub precision: u32,
+    #[prost(message, repeated, tag = "3")]
+    pub ranges: ::prost::alloc::vec::Vec<SeriesTimeRangeRepair>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FetchWrittenTimeRangesRequest {
+    /// Sequence of the written time ranges already fetched, 0 for none
+    #[prost(uint64, tag = "1")]
+    pub since_seq: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AdminFetchCommandRequest {
     #[prost(string, tag = "1")]
     pub tenant: ::prost::alloc::string::String,
-    #[prost(oneof = "admin_fetch_command_request::Command", tags = "8")]
+    #[prost(oneof = "admin_fetch_command_request::Command", tags = "8, 9, 10, 11, 12")]
     pub command: ::core::option::Option<admin_fetch_command_request::Command>,
 }
 /// Nested message and enum types in `AdminFetchCommandRequest`.
@@ -222,6 +330,14 @@ pub mod admin_fetch_command_request {
     pub enum Command {
         #[prost(message, tag = "8")]
         FetchVnodeChecksum(super::FetchVnodeChecksumRequest),
+        #[prost(message, tag = "9")]
+        FetchVnodeTimeRangeChecksum(super::FetchVnodeTimeRangeChecksumRequest),
+        #[prost(message, tag = "10")]
+        FetchVnodeSeriesData(super::FetchVnodeSeriesDataRequest),
+        #[prost(message, tag = "11")]
+        RepairVnodeSeriesData(super::RepairVnodeSeriesDataRequest),
+        #[prost(message, tag = "12")]
+        FetchWrittenTimeRanges(super::FetchWrittenTimeRangesRequest),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -231,6 +347,9 @@ pub struct BatchBytesResponse {
     pub code: i32,
     #[prost(bytes = "vec", tag = "2")]
     pub data: ::prost::alloc::vec::Vec<u8>,
+    /// Scan metrics sent after the last record batch of QueryRecordBatch
+    #[prost(bytes = "vec", tag = "3")]
+    pub metrics: ::prost::alloc::vec::Vec<u8>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -263,6 +382,9 @@ pub struct QueryRecordBatchRequest {
     pub expr: ::prost::alloc::vec::Vec<u8>,
     #[prost(bytes = "vec", tag = "3")]
     pub aggs: ::prost::alloc::vec::Vec<u8>,
+    /// Send the scan metrics after the last record batch
+    #[prost(bool, tag = "4")]
+    pub with_scan_metrics: bool,
 }
 /// Generated client implementations.
 pub mod tskv_service_client {
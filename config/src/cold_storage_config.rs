@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::check::{CheckConfig, CheckConfigItemResult, CheckConfigResult};
+use crate::codec::{bytes_num, duration};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ColdStorageConfig {
+    /// Where to move cold data files, like `s3://bucket/path`, `gcs://bucket/path`,
+    /// `azblob://container/path` or `file:///path`, empty to disable cold storage.
+    #[serde(default = "ColdStorageConfig::default_url")]
+    pub url: String,
+
+    #[serde(
+        with = "duration",
+        default = "ColdStorageConfig::default_check_interval"
+    )]
+    pub check_interval: Duration,
+
+    #[serde(
+        with = "bytes_num",
+        default = "ColdStorageConfig::default_cache_max_size"
+    )]
+    pub cache_max_size: u64,
+
+    /// Connection options of the object store, same as the options in `CONNECTION = (...)`.
+    #[serde(default = "ColdStorageConfig::default_options")]
+    pub options: BTreeMap<String, String>,
+}
+
+impl ColdStorageConfig {
+    fn default_url() -> String {
+        "".to_string()
+    }
+
+    fn default_check_interval() -> Duration {
+        Duration::from_secs(10 * 60)
+    }
+
+    fn default_cache_max_size() -> u64 {
+        16 * 1024 * 1024 * 1024
+    }
+
+    fn default_options() -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.url.is_empty()
+    }
+
+    /// Splits the url into (scheme, bucket, path).
+    ///
+    /// Url without scheme or with scheme `file` is a local path, it has no bucket.
+    pub fn split_url(&self) -> (&str, &str, &str) {
+        match self.url.split_once("://") {
+            Some((scheme, path)) if scheme.eq_ignore_ascii_case("file") => (scheme, "", path),
+            Some((scheme, rest)) => match rest.split_once('/') {
+                Some((bucket, path)) => (scheme, bucket, path),
+                None => (scheme, rest, ""),
+            },
+            None => ("", "", self.url.as_str()),
+        }
+    }
+
+    pub fn override_by_env(&mut self) {
+        if let Ok(url) = std::env::var("CNOSDB_COLD_STORAGE_URL") {
+            self.url = url;
+        }
+        if let Ok(interval) = std::env::var("CNOSDB_COLD_STORAGE_CHECK_INTERVAL") {
+            self.check_interval = duration::parse_duration(&interval).unwrap();
+        }
+        if let Ok(size) = std::env::var("CNOSDB_COLD_STORAGE_CACHE_MAX_SIZE") {
+            self.cache_max_size = size.parse::<u64>().unwrap();
+        }
+    }
+}
+
+impl Default for ColdStorageConfig {
+    fn default() -> Self {
+        Self {
+            url: Self::default_url(),
+            check_interval: Self::default_check_interval(),
+            cache_max_size: Self::default_cache_max_size(),
+            options: Self::default_options(),
+        }
+    }
+}
+
+impl CheckConfig for ColdStorageConfig {
+    fn check(&self, _: &crate::Config) -> Option<CheckConfigResult> {
+        let config_name = Arc::new("cold_storage".to_string());
+        let mut ret = CheckConfigResult::default();
+
+        if self.enabled() {
+            let (scheme, bucket, path) = self.split_url();
+            if !scheme.is_empty() && !scheme.eq_ignore_ascii_case("file") && bucket.is_empty() {
+                ret.add_error(CheckConfigItemResult {
+                    config: config_name.clone(),
+                    item: "url".to_string(),
+                    message: format!("'url' has no bucket: {}", self.url),
+                });
+            }
+            if (scheme.is_empty() || scheme.eq_ignore_ascii_case("file")) && path.is_empty() {
+                ret.add_error(CheckConfigItemResult {
+                    config: config_name.clone(),
+                    item: "url".to_string(),
+                    message: format!("'url' has no path: {}", self.url),
+                });
+            }
+            if self.check_interval.as_secs() == 0 {
+                ret.add_error(CheckConfigItemResult {
+                    config: config_name,
+                    item: "check_interval".to_string(),
+                    message: "'check_interval' can not be zero".to_string(),
+                });
+            }
+        }
+
+        if ret.is_empty() {
+            None
+        } else {
+            Some(ret)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ColdStorageConfig;
+
+    #[test]
+    fn test_split_url() {
+        let mut config = ColdStorageConfig {
+            url: "s3://bucket/cnosdb/cold".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config.split_url(), ("s3", "bucket", "cnosdb/cold"));
+
+        config.url = "gcs://bucket".to_string();
+        assert_eq!(config.split_url(), ("gcs", "bucket", ""));
+
+        config.url = "file:///data/cold".to_string();
+        assert_eq!(config.split_url(), ("file", "", "/data/cold"));
+
+        config.url = "/data/cold".to_string();
+        assert_eq!(config.split_url(), ("", "", "/data/cold"));
+    }
+}
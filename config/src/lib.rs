@@ -10,6 +10,7 @@ use serde::{Deserialize, Serialize};
 
 pub use crate::cache_config::*;
 pub use crate::cluster_config::*;
+pub use crate::cold_storage_config::*;
 pub use crate::deployment_config::*;
 pub use crate::heartbeat_config::*;
 pub use crate::hinted_off_config::*;
@@ -17,6 +18,8 @@ pub use crate::limiter_config::*;
 pub use crate::log_config::*;
 pub use crate::node_config::*;
 pub use crate::query_config::*;
+pub use crate::rebalance_config::*;
+pub use crate::repair_config::*;
 pub use crate::security_config::*;
 pub use crate::storage_config::*;
 pub use crate::trace::*;
@@ -26,6 +29,7 @@ mod cache_config;
 mod check;
 mod cluster_config;
 mod codec;
+mod cold_storage_config;
 mod deployment_config;
 mod heartbeat_config;
 mod hinted_off_config;
@@ -33,6 +37,8 @@ mod limiter_config;
 mod log_config;
 mod node_config;
 mod query_config;
+mod rebalance_config;
+mod repair_config;
 mod security_config;
 mod storage_config;
 mod trace;
@@ -94,6 +100,15 @@ pub struct Config {
 
     #[serde(default = "Default::default")]
     pub trace: TraceConfig,
+
+    #[serde(default = "Default::default")]
+    pub cold_storage: ColdStorageConfig,
+
+    #[serde(default = "Default::default")]
+    pub rebalance: RebalanceConfig,
+
+    #[serde(default = "Default::default")]
+    pub repair: RepairConfig,
 }
 
 impl Default for Config {
@@ -113,6 +128,9 @@ impl Default for Config {
             heartbeat: Default::default(),
             node_basic: Default::default(),
             trace: Default::default(),
+            cold_storage: Default::default(),
+            rebalance: Default::default(),
+            repair: Default::default(),
         }
     }
 }
@@ -133,6 +151,9 @@ impl Config {
         self.cache.override_by_env();
         self.query.override_by_env();
         self.node_basic.override_by_env();
+        self.cold_storage.override_by_env();
+        self.rebalance.override_by_env();
+        self.repair.override_by_env();
     }
 
     pub fn to_string_pretty(&self) -> String {
@@ -234,6 +255,15 @@ pub fn check_config(path: impl AsRef<Path>, show_warnings: bool) {
             if let Some(c) = cfg.node_basic.check(&cfg) {
                 check_results.add_all(c)
             }
+            if let Some(c) = cfg.cold_storage.check(&cfg) {
+                check_results.add_all(c)
+            }
+            if let Some(c) = cfg.rebalance.check(&cfg) {
+                check_results.add_all(c)
+            }
+            if let Some(c) = cfg.repair.check(&cfg) {
+                check_results.add_all(c)
+            }
 
             check_results.introspect();
             check_results.show_warnings = show_warnings;
@@ -22,6 +22,18 @@ pub struct QueryConfig {
     pub stream_trigger_cpu: usize,
     #[serde(default = "QueryConfig::default_stream_executor_cpu")]
     pub stream_executor_cpu: usize,
+    /// Max memory of the query result cache, 0 to disable it.
+    ///
+    /// The cache is invalidated by the writes and DDLs coordinated by this node at once, the
+    /// ones coordinated by other nodes are fetched from the data nodes once a second.
+    #[serde(default = "QueryConfig::default_result_cache_max_memory")]
+    pub result_cache_max_memory: u64,
+    #[serde(default = "QueryConfig::default_result_cache_ttl_ms")]
+    pub result_cache_ttl_ms: u64,
+    /// Consistency level of the writes that don't specify one, e.g. the writes without
+    /// the `consistency_level` parameter, the SQL inserts and the OpenTSDB tcp writes.
+    #[serde(default = "QueryConfig::default_write_consistency_level")]
+    pub write_consistency_level: String,
 }
 
 impl QueryConfig {
@@ -54,6 +66,16 @@ impl QueryConfig {
     fn default_stream_executor_cpu() -> usize {
         2
     }
+    fn default_result_cache_max_memory() -> u64 {
+        // disabled by default
+        0
+    }
+    fn default_result_cache_ttl_ms() -> u64 {
+        60 * 1000
+    }
+    fn default_write_consistency_level() -> String {
+        "any".to_string()
+    }
 
     pub fn override_by_env(&mut self) {
         if let Ok(size) = std::env::var("MAX_SERVER_CONNECTIONS") {
@@ -80,6 +102,15 @@ impl QueryConfig {
         if let Ok(size) = std::env::var("STREAM_EXECUTOR_CPU") {
             self.stream_executor_cpu = size.parse::<usize>().unwrap();
         }
+        if let Ok(size) = std::env::var("RESULT_CACHE_MAX_MEMORY") {
+            self.result_cache_max_memory = size.parse::<u64>().unwrap();
+        }
+        if let Ok(size) = std::env::var("RESULT_CACHE_TTL_MS") {
+            self.result_cache_ttl_ms = size.parse::<u64>().unwrap();
+        }
+        if let Ok(level) = std::env::var("WRITE_CONSISTENCY_LEVEL") {
+            self.write_consistency_level = level;
+        }
     }
 }
 
@@ -94,6 +125,9 @@ impl Default for QueryConfig {
             write_timeout_ms: Self::default_write_timeout_ms(),
             stream_trigger_cpu: Self::default_stream_trigger_cpu(),
             stream_executor_cpu: Self::default_stream_executor_cpu(),
+            result_cache_max_memory: Self::default_result_cache_max_memory(),
+            result_cache_ttl_ms: Self::default_result_cache_ttl_ms(),
+            write_consistency_level: Self::default_write_consistency_level(),
         }
     }
 }
@@ -150,12 +184,24 @@ impl CheckConfig for QueryConfig {
         }
         if self.stream_trigger_cpu > 1024 {
             ret.add_warn(CheckConfigItemResult {
-                config: config_name,
+                config: config_name.clone(),
                 item: "stream_trigger_cpu".to_string(),
                 message: "'stream_trigger_cpu' maybe too big(more than 1024)".to_string(),
             })
         }
 
+        if !matches!(
+            self.write_consistency_level.to_ascii_lowercase().as_str(),
+            "any" | "one" | "quorum" | "all"
+        ) {
+            ret.add_error(CheckConfigItemResult {
+                config: config_name,
+                item: "write_consistency_level".to_string(),
+                message: "'write_consistency_level' must be one of: any, one, quorum, all"
+                    .to_string(),
+            })
+        }
+
         if ret.is_empty() {
             None
         } else {
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::check::{CheckConfig, CheckConfigItemResult, CheckConfigResult};
+use crate::codec::{bytes_num, duration};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RebalanceConfig {
+    /// Whether to move vnodes from nodes having more vnodes to nodes having less,
+    /// vnodes of decommissioned nodes are always moved.
+    #[serde(default = "RebalanceConfig::default_enable")]
+    pub enable: bool,
+
+    #[serde(with = "duration", default = "RebalanceConfig::default_check_interval")]
+    pub check_interval: Duration,
+
+    /// The maximum number of vnodes being moved at the same time.
+    #[serde(default = "RebalanceConfig::default_max_concurrent_moves")]
+    pub max_concurrent_moves: usize,
+
+    /// The maximum bytes per second to download the files of a vnode moved to this node,
+    /// 0 for unlimited.
+    #[serde(with = "bytes_num", default = "RebalanceConfig::default_max_bandwidth")]
+    pub max_bandwidth: u64,
+}
+
+impl RebalanceConfig {
+    fn default_enable() -> bool {
+        false
+    }
+
+    fn default_check_interval() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn default_max_concurrent_moves() -> usize {
+        1
+    }
+
+    fn default_max_bandwidth() -> u64 {
+        0
+    }
+
+    pub fn override_by_env(&mut self) {
+        if let Ok(enable) = std::env::var("CNOSDB_REBALANCE_ENABLE") {
+            self.enable = enable.parse::<bool>().unwrap();
+        }
+        if let Ok(interval) = std::env::var("CNOSDB_REBALANCE_CHECK_INTERVAL") {
+            self.check_interval = duration::parse_duration(&interval).unwrap();
+        }
+        if let Ok(moves) = std::env::var("CNOSDB_REBALANCE_MAX_CONCURRENT_MOVES") {
+            self.max_concurrent_moves = moves.parse::<usize>().unwrap();
+        }
+        if let Ok(bandwidth) = std::env::var("CNOSDB_REBALANCE_MAX_BANDWIDTH") {
+            self.max_bandwidth = bandwidth.parse::<u64>().unwrap();
+        }
+    }
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        Self {
+            enable: Self::default_enable(),
+            check_interval: Self::default_check_interval(),
+            max_concurrent_moves: Self::default_max_concurrent_moves(),
+            max_bandwidth: Self::default_max_bandwidth(),
+        }
+    }
+}
+
+impl CheckConfig for RebalanceConfig {
+    fn check(&self, _: &crate::Config) -> Option<CheckConfigResult> {
+        let config_name = Arc::new("rebalance".to_string());
+        let mut ret = CheckConfigResult::default();
+
+        if self.check_interval.as_secs() == 0 {
+            ret.add_error(CheckConfigItemResult {
+                config: config_name.clone(),
+                item: "check_interval".to_string(),
+                message: "'check_interval' can not be zero".to_string(),
+            });
+        }
+        if self.max_concurrent_moves == 0 {
+            ret.add_error(CheckConfigItemResult {
+                config: config_name,
+                item: "max_concurrent_moves".to_string(),
+                message: "'max_concurrent_moves' can not be zero".to_string(),
+            });
+        }
+
+        if ret.is_empty() {
+            None
+        } else {
+            Some(ret)
+        }
+    }
+}
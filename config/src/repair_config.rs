@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::check::{CheckConfig, CheckConfigItemResult, CheckConfigResult};
+use crate::codec::duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RepairConfig {
+    /// Whether to repair the divergent replicas of replication sets periodically.
+    #[serde(default = "RepairConfig::default_enable")]
+    pub enable: bool,
+
+    #[serde(with = "duration", default = "RepairConfig::default_interval")]
+    pub interval: Duration,
+}
+
+impl RepairConfig {
+    fn default_enable() -> bool {
+        false
+    }
+
+    fn default_interval() -> Duration {
+        Duration::from_secs(24 * 60 * 60)
+    }
+
+    pub fn override_by_env(&mut self) {
+        if let Ok(enable) = std::env::var("CNOSDB_REPAIR_ENABLE") {
+            self.enable = enable.parse::<bool>().unwrap();
+        }
+        if let Ok(interval) = std::env::var("CNOSDB_REPAIR_INTERVAL") {
+            self.interval = duration::parse_duration(&interval).unwrap();
+        }
+    }
+}
+
+impl Default for RepairConfig {
+    fn default() -> Self {
+        Self {
+            enable: Self::default_enable(),
+            interval: Self::default_interval(),
+        }
+    }
+}
+
+impl CheckConfig for RepairConfig {
+    fn check(&self, _: &crate::Config) -> Option<CheckConfigResult> {
+        let config_name = Arc::new("repair".to_string());
+        let mut ret = CheckConfigResult::default();
+
+        if self.interval.as_secs() == 0 {
+            ret.add_error(CheckConfigItemResult {
+                config: config_name,
+                item: "interval".to_string(),
+                message: "'interval' can not be zero".to_string(),
+            });
+        }
+
+        if ret.is_empty() {
+            None
+        } else {
+            Some(ret)
+        }
+    }
+}
@@ -31,6 +31,12 @@ pub struct WalConfig {
 
     #[serde(with = "duration", default = "WalConfig::default_sync_interval")]
     pub sync_interval: Duration,
+
+    #[serde(default = "WalConfig::default_compression")]
+    pub compression: String,
+
+    #[serde(with = "duration", default = "WalConfig::default_group_commit_window")]
+    pub group_commit_window: Duration,
 }
 
 impl WalConfig {
@@ -62,6 +68,17 @@ impl WalConfig {
         Duration::from_secs(0)
     }
 
+    fn default_compression() -> String {
+        "none".to_string()
+    }
+
+    fn default_group_commit_window() -> Duration {
+        Duration::from_millis(0)
+    }
+
+    /// Compression algorithms supported by WAL entries.
+    pub const COMPRESSIONS: [&'static str; 3] = ["none", "zstd", "snappy"];
+
     pub fn override_by_env(&mut self) {
         if let Ok(enabled) = std::env::var("CNOSDB_WAL_ENABLED") {
             self.enabled = enabled.as_str() == "true";
@@ -75,6 +92,12 @@ impl WalConfig {
         if let Ok(sync) = std::env::var("CNOSDB_WAL_SYNC") {
             self.sync = sync.as_str() == sync;
         }
+        if let Ok(compression) = std::env::var("CNOSDB_WAL_COMPRESSION") {
+            self.compression = compression;
+        }
+        if let Ok(window) = std::env::var("CNOSDB_WAL_GROUP_COMMIT_WINDOW") {
+            self.group_commit_window = duration::parse_duration(&window).unwrap();
+        }
     }
 
     pub fn introspect(&mut self) {
@@ -93,6 +116,8 @@ impl Default for WalConfig {
             flush_trigger_total_file_size: Self::default_flush_trigger_total_file_size(),
             sync: Self::default_sync(),
             sync_interval: Self::default_sync_interval(),
+            compression: Self::default_compression(),
+            group_commit_window: Self::default_group_commit_window(),
         }
     }
 }
@@ -116,6 +141,23 @@ impl CheckConfig for WalConfig {
                 message: "'wal_req_channel_cap' maybe too small(less than 16)".to_string(),
             });
         }
+        if !Self::COMPRESSIONS.contains(&self.compression.to_ascii_lowercase().as_str()) {
+            ret.add_error(CheckConfigItemResult {
+                config: config_name.clone(),
+                item: "compression".to_string(),
+                message: format!(
+                    "'compression' must be one of {}",
+                    Self::COMPRESSIONS.join(", ")
+                ),
+            });
+        }
+        if self.group_commit_window > Duration::from_secs(1) {
+            ret.add_warn(CheckConfigItemResult {
+                config: config_name.clone(),
+                item: "group_commit_window".to_string(),
+                message: "'group_commit_window' maybe too large(more than 1 second)".to_string(),
+            });
+        }
         if self.sync_interval.as_nanos() < Duration::from_secs(1).as_nanos() {
             ret.add_warn(CheckConfigItemResult {
                 config: config_name,
@@ -4,6 +4,7 @@ use std::io;
 use datafusion::arrow::error::ArrowError;
 use flatbuffers::InvalidFlatbuffer;
 use meta::error::MetaError;
+use models::consistency_level::ConsistencyLevel;
 use models::error_code::{ErrorCode, ErrorCoder};
 use models::schema::Precision;
 use models::Timestamp;
@@ -172,6 +173,29 @@ pub enum CoordinatorError {
         database_min_ts: Timestamp,
         point_ts: Timestamp,
     },
+
+    #[snafu(display("Write consistency level {level} not met on ReplicationSet({id}), required {required} acks of {replica} replicas, got {acked}: {error}"))]
+    #[error_code(code = 26)]
+    WriteConsistencyNotMet {
+        id: u32,
+        level: ConsistencyLevel,
+        replica: usize,
+        required: usize,
+        acked: usize,
+        error: String,
+    },
+
+    #[snafu(display("Object store error: {}", source))]
+    #[error_code(code = 27)]
+    ObjectStore {
+        source: object_store::Error,
+    },
+}
+
+impl From<object_store::Error> for CoordinatorError {
+    fn from(source: object_store::Error) -> Self {
+        CoordinatorError::ObjectStore { source }
+    }
 }
 
 impl From<PointsError> for CoordinatorError {
@@ -1,4 +1,5 @@
 #![feature(stmt_expr_attributes)]
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -6,29 +7,37 @@ use std::sync::Arc;
 use datafusion::arrow::record_batch::RecordBatch;
 use errors::CoordinatorError;
 use futures::Stream;
+use meta::error::MetaError;
 use meta::model::{MetaClientRef, MetaRef};
 use models::consistency_level::ConsistencyLevel;
 use models::meta_data::{ReplicationSet, VnodeAllInfo};
 use models::object_reference::ResolvedTable;
-use models::predicate::domain::ResolvedPredicateRef;
+use models::predicate::domain::{ResolvedPredicate, ResolvedPredicateRef};
 use models::schema::Precision;
 use protos::kv_service::{AdminCommandRequest, WritePointsRequest};
+use tokio::sync::broadcast;
 use trace::SpanContext;
 use tskv::reader::QueryOption;
 use tskv::EngineRef;
 
 use crate::errors::CoordinatorResult;
+use crate::rebalance::VnodeMove;
+use crate::repair::RepairResult;
 use crate::service::CoordServiceMetrics;
+use crate::written_log::{WrittenLogDelta, WrittenLogRef};
 
 pub mod errors;
 pub mod file_info;
 pub mod hh_queue;
 pub mod metrics;
 pub mod reader;
+pub mod rebalance;
+pub mod repair;
 pub mod service;
 pub mod service_mock;
 pub mod vnode_mgr;
 pub mod writer;
+pub mod written_log;
 
 pub const FAILED_RESPONSE_CODE: i32 = -1;
 pub const FINISH_RESPONSE_CODE: i32 = 0;
@@ -45,6 +54,18 @@ pub struct WriteRequest {
     pub request: protos::kv_service::WritePointsRequest,
 }
 
+/// The time range of the points written into a table by a write request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenTimeRange {
+    pub tenant: String,
+    pub database: String,
+    pub table: String,
+    /// Minimum timestamp of the written points, in nanoseconds
+    pub min_ts: i64,
+    /// Maximum timestamp of the written points, in nanoseconds
+    pub max_ts: i64,
+}
+
 #[derive(Debug, Clone)]
 pub enum VnodeManagerCmdType {
     /// vnode id, dst node id
@@ -55,6 +76,10 @@ pub enum VnodeManagerCmdType {
     Drop(u32),
     /// vnode id list
     Compact(Vec<u32>),
+    /// vnode id, backup location, connection options of the location
+    Backup(u32, String, HashMap<String, String>),
+    /// vnode id, vnode id in backup, backup location, connection options of the location
+    Restore(u32, u32, String, HashMap<String, String>),
 }
 
 #[derive(Debug, Clone)]
@@ -89,15 +114,45 @@ pub trait Coordinator: Send + Sync {
         predicate: ResolvedPredicateRef,
     ) -> CoordinatorResult<Vec<ReplicationSet>>;
 
+    /// Writes the points with the consistency level, `None` for the level configured by
+    /// `query.write_consistency_level`.
     async fn write_points(
         &self,
         tenant: String,
-        level: ConsistencyLevel,
+        level: Option<ConsistencyLevel>,
         precision: Precision,
         request: WritePointsRequest,
         span_ctx: Option<&SpanContext>,
     ) -> CoordinatorResult<()>;
 
+    /// Writes the points like `write_points`, but the write rejected by the limiter
+    /// of the tenant is returned as the error of the limiter in the inner result,
+    /// so that the rejected points can be told apart from a failed write.
+    async fn write_points_or_limited(
+        &self,
+        tenant: String,
+        level: Option<ConsistencyLevel>,
+        precision: Precision,
+        request: WritePointsRequest,
+        span_ctx: Option<&SpanContext>,
+    ) -> CoordinatorResult<Result<(), MetaError>>;
+
+    /// Subscribe the time ranges written by the write requests coordinated by this node,
+    /// they are sent whether the write request succeeds or not.
+    fn subscribe_written_time_ranges(&self) -> broadcast::Receiver<WrittenTimeRange>;
+
+    /// The time ranges written into the vnodes of this node, whichever node coordinated
+    /// the writes.
+    fn written_log(&self) -> WrittenLogRef;
+
+    /// Fetch the time ranges written into the vnodes of the data node since the sequence,
+    /// 0 if no sequence is fetched yet.
+    async fn fetch_written_time_ranges(
+        &self,
+        node_id: u64,
+        since_seq: u64,
+    ) -> CoordinatorResult<WrittenLogDelta>;
+
     fn table_scan(
         &self,
         option: QueryOption,
@@ -112,6 +167,13 @@ pub trait Coordinator: Send + Sync {
 
     async fn broadcast_command(&self, req: AdminCommandRequest) -> CoordinatorResult<()>;
 
+    /// Delete data of a table that matches the predicate on all replicas.
+    async fn delete_from_table(
+        &self,
+        table: &ResolvedTable,
+        predicate: &ResolvedPredicate,
+    ) -> CoordinatorResult<()>;
+
     /// A manager to manage vnode.
     async fn vnode_manager(
         &self,
@@ -126,6 +188,20 @@ pub trait Coordinator: Send + Sync {
         cmd_type: VnodeSummarizerCmdType,
     ) -> CoordinatorResult<Vec<RecordBatch>>;
 
+    /// Mark the data node decommissioned, its vnodes are moved to other nodes by the rebalancer.
+    async fn decommission_node(&self, node_id: u64) -> CoordinatorResult<()>;
+
+    /// The vnodes being moved, and the vnodes to be moved by the rebalancer.
+    async fn rebalance_status(&self) -> CoordinatorResult<Vec<VnodeMove>>;
+
+    /// Repair the divergent replicas of the replication set, returns the repair of each
+    /// running vnode.
+    async fn repair_replication_set(
+        &self,
+        tenant: &str,
+        replication_set_id: u32,
+    ) -> CoordinatorResult<Vec<RepairResult>>;
+
     fn metrics(&self) -> &Arc<CoordServiceMetrics>;
 }
 
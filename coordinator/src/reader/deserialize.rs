@@ -1,21 +1,37 @@
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
 use datafusion::arrow::record_batch::RecordBatch;
 use futures::{ready, Stream, StreamExt};
 use models::record_batch_decode;
 use protos::kv_service::BatchBytesResponse;
 use tonic::Streaming;
+use tskv::reader::scan_metrics::ScanMetrics;
 
 use crate::errors::{CoordinatorError, CoordinatorResult};
 
 pub struct TonicRecordBatchDecoder {
     stream: Streaming<BatchBytesResponse>,
+    /// Metrics of the remote scan and the time when the request was sent
+    scan_metrics: Option<(ScanMetrics, Instant)>,
 }
 
 impl TonicRecordBatchDecoder {
     pub fn new(stream: Streaming<BatchBytesResponse>) -> Self {
-        Self { stream }
+        Self {
+            stream,
+            scan_metrics: None,
+        }
+    }
+
+    /// Merge the metrics sent by the remote node into `scan_metrics`,
+    /// and record the time from `start` to the end of the stream as remote rpc time.
+    pub fn with_scan_metrics(self, scan_metrics: ScanMetrics, start: Instant) -> Self {
+        Self {
+            scan_metrics: Some((scan_metrics, start)),
+            ..self
+        }
     }
 }
 
@@ -23,15 +39,32 @@ impl Stream for TonicRecordBatchDecoder {
     type Item = CoordinatorResult<RecordBatch>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match ready!(self.stream.poll_next_unpin(cx)) {
-            Some(Ok(received)) => match record_batch_decode(&received.data) {
-                Ok(batch) => Poll::Ready(Some(Ok(batch))),
-                Err(err) => Poll::Ready(Some(Err(err.into()))),
-            },
-            Some(Err(err)) => Poll::Ready(Some(Err(CoordinatorError::TskvError {
-                source: err.into(),
-            }))),
-            None => Poll::Ready(None),
+        loop {
+            return match ready!(self.stream.poll_next_unpin(cx)) {
+                Some(Ok(received)) => {
+                    if !received.metrics.is_empty() {
+                        if let Some((scan_metrics, _)) = &self.scan_metrics {
+                            if let Err(err) = scan_metrics.merge_encoded(&received.metrics) {
+                                return Poll::Ready(Some(Err(err.into())));
+                            }
+                        }
+                        continue;
+                    }
+                    match record_batch_decode(&received.data) {
+                        Ok(batch) => Poll::Ready(Some(Ok(batch))),
+                        Err(err) => Poll::Ready(Some(Err(err.into()))),
+                    }
+                }
+                Some(Err(err)) => Poll::Ready(Some(Err(CoordinatorError::TskvError {
+                    source: err.into(),
+                }))),
+                None => {
+                    if let Some((scan_metrics, start)) = self.scan_metrics.take() {
+                        scan_metrics.remote_rpc_time().add_elapsed(start);
+                    }
+                    Poll::Ready(None)
+                }
+            };
         }
     }
 }
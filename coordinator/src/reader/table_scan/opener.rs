@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use config::QueryConfig;
 use futures::TryStreamExt;
@@ -82,11 +82,13 @@ impl VnodeOpener for TemporaryTableScanOpener {
                 Ok(Box::pin(stream) as SendableCoordinatorRecordBatchStream)
             } else {
                 // 路由到远程的引擎
+                let start = Instant::now();
                 let mut request = {
                     let vnode_ids = vec![vnode_id];
-                    let req = option
+                    let mut req = option
                         .to_query_record_batch_request(vnode_ids)
                         .map_err(CoordinatorError::from)?;
+                    req.with_scan_metrics = true;
                     tonic::Request::new(req)
                 };
 
@@ -116,8 +118,9 @@ impl VnodeOpener for TemporaryTableScanOpener {
                         .into_inner()
                 };
 
-                Ok(Box::pin(TonicRecordBatchDecoder::new(resp_stream))
-                    as SendableCoordinatorRecordBatchStream)
+                let stream = TonicRecordBatchDecoder::new(resp_stream)
+                    .with_scan_metrics(option.scan_metrics.clone(), start);
+                Ok(Box::pin(stream) as SendableCoordinatorRecordBatchStream)
             }
         };
 
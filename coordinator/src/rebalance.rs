@@ -0,0 +1,371 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use meta::model::MetaRef;
+use models::meta_data::{
+    NodeAttribute, NodeId, NodeInfo, NodeMetrics, ReplicationSet, ReplicationSetId, VnodeId,
+    VnodeStatus,
+};
+use models::node_info::NodeStatus;
+
+use crate::errors::CoordinatorResult;
+
+/// A replication set of a database.
+#[derive(Debug, Clone)]
+pub struct Shard {
+    pub tenant: String,
+    pub database: String,
+    pub replication_set: ReplicationSet,
+    /// The bucket is older than the cold after of the database
+    pub cold: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VnodeMoveStatus {
+    /// The vnode is to be moved by the rebalancer
+    Pending,
+    /// The vnode is being copied to another node
+    Moving,
+}
+
+impl VnodeMoveStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Moving => "moving",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VnodeMove {
+    pub tenant: String,
+    pub database: String,
+    pub repl_set_id: ReplicationSetId,
+    pub vnode_id: VnodeId,
+    pub from: NodeId,
+    /// The destination, unknown if the vnode is being moved
+    pub to: Option<NodeId>,
+    pub status: VnodeMoveStatus,
+}
+
+/// Get the replication sets of all databases, sorted by tenant, database and id.
+pub async fn all_shards(meta: &MetaRef) -> CoordinatorResult<Vec<Shard>> {
+    let mut shards = vec![];
+    for tenant in meta.tenants().await? {
+        let Some(client) = meta.tenant_meta(tenant.name()).await else {
+            continue;
+        };
+        for database in client.list_databases()? {
+            let Some(info) = client.get_db_info(&database)? else {
+                continue;
+            };
+            let time_to_cold = info.schema.time_to_cold_node();
+            let time_to_expired = info.schema.time_to_expired();
+            for bucket in info.buckets {
+                let cold = time_to_cold.map_or(false, |t| {
+                    bucket.end_time < t && bucket.end_time >= time_to_expired
+                });
+                for replication_set in bucket.shard_group {
+                    shards.push(Shard {
+                        tenant: tenant.name().to_string(),
+                        database: database.clone(),
+                        replication_set,
+                        cold,
+                    });
+                }
+            }
+        }
+    }
+    shards.sort_by(|a, b| {
+        (&a.tenant, &a.database, a.replication_set.id).cmp(&(
+            &b.tenant,
+            &b.database,
+            b.replication_set.id,
+        ))
+    });
+
+    Ok(shards)
+}
+
+/// Plans the moves of vnodes, including the vnodes being moved.
+///
+/// Vnodes of cold buckets on hot nodes are always moved to cold nodes, and vnodes of
+/// decommissioned nodes are always moved off. Vnodes of nodes having more vnodes are moved
+/// to nodes having less only if `balance`. Other than cold vnodes, vnodes are moved between
+/// nodes of the same attribute. The destination is the healthy node having the least vnodes
+/// and the most free disk, which has no other vnode of the same replication set.
+pub fn plan(
+    nodes: &[NodeInfo],
+    metrics: &[NodeMetrics],
+    shards: &[Shard],
+    balance: bool,
+) -> Vec<VnodeMove> {
+    let mut moves = vec![];
+    for shard in shards.iter() {
+        for vnode in shard.replication_set.vnodes.iter() {
+            if vnode.status == VnodeStatus::Copying {
+                moves.push(VnodeMove {
+                    tenant: shard.tenant.clone(),
+                    database: shard.database.clone(),
+                    repl_set_id: shard.replication_set.id,
+                    vnode_id: vnode.id,
+                    from: vnode.node_id,
+                    to: None,
+                    status: VnodeMoveStatus::Moving,
+                });
+            }
+        }
+    }
+
+    // Planned moves are applied to the shards, moved vnodes are marked copying
+    let mut shards = shards.to_vec();
+    let hot_nodes = nodes
+        .iter()
+        .filter(|node| node.attribute == NodeAttribute::Hot)
+        .map(|node| node.id)
+        .collect::<Vec<_>>();
+    let cold_group = nodes
+        .iter()
+        .filter(|node| node.attribute == NodeAttribute::Cold)
+        .collect::<Vec<_>>();
+    let mut targets = move_targets(&cold_group, metrics, &shards);
+    for shard in shards.iter_mut().filter(|shard| shard.cold) {
+        for index in 0..shard.replication_set.vnodes.len() {
+            let vnode = &shard.replication_set.vnodes[index];
+            if vnode.status != VnodeStatus::Running || !hot_nodes.contains(&vnode.node_id) {
+                continue;
+            }
+            if let Some(to) = pick_target(&targets, &shard.replication_set) {
+                moves.push(plan_move(shard, index, to, &mut targets));
+            }
+        }
+    }
+
+    for attribute in [NodeAttribute::Hot, NodeAttribute::Cold] {
+        let group = nodes
+            .iter()
+            .filter(|node| node.attribute == attribute)
+            .collect::<Vec<_>>();
+        let mut targets = move_targets(&group, metrics, &shards);
+
+        let decommissioned = group
+            .iter()
+            .filter(|node| node.decommissioned)
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        for shard in shards.iter_mut() {
+            for index in 0..shard.replication_set.vnodes.len() {
+                let vnode = &shard.replication_set.vnodes[index];
+                if vnode.status != VnodeStatus::Running || !decommissioned.contains(&vnode.node_id)
+                {
+                    continue;
+                }
+                if let Some(to) = pick_target(&targets, &shard.replication_set) {
+                    moves.push(plan_move(shard, index, to, &mut targets));
+                }
+            }
+        }
+
+        if !balance {
+            continue;
+        }
+        // Every move decreases the variance of the numbers of vnodes, so the loop ends.
+        while let Some((from, from_count)) = targets
+            .iter()
+            .max_by_key(|(id, (count, disk_free))| (*count, Reverse(*disk_free), Reverse(**id)))
+            .map(|(id, (count, _))| (*id, *count))
+        {
+            let candidate = shards.iter().enumerate().find_map(|(i, shard)| {
+                let index = shard
+                    .replication_set
+                    .vnodes
+                    .iter()
+                    .position(|v| v.node_id == from && v.status == VnodeStatus::Running)?;
+                let to = pick_target(&targets, &shard.replication_set)?;
+                (targets[&to].0 + 1 < from_count).then_some((i, index, to))
+            });
+            let Some((i, index, to)) = candidate else {
+                break;
+            };
+            moves.push(plan_move(&mut shards[i], index, to, &mut targets));
+        }
+    }
+
+    moves
+}
+
+/// Node id -> (number of vnodes, free disk) of the nodes of the group to move vnodes to.
+fn move_targets(
+    group: &[&NodeInfo],
+    metrics: &[NodeMetrics],
+    shards: &[Shard],
+) -> HashMap<NodeId, (usize, u64)> {
+    let mut targets: HashMap<NodeId, (usize, u64)> = group
+        .iter()
+        .filter(|node| !node.decommissioned)
+        .filter_map(|node| {
+            metrics
+                .iter()
+                .find(|m| m.id == node.id && m.status == NodeStatus::Healthy)
+                .map(|m| (node.id, (0, m.disk_free)))
+        })
+        .collect();
+    for vnode in shards
+        .iter()
+        .flat_map(|shard| shard.replication_set.vnodes.iter())
+    {
+        if let Some((count, _)) = targets.get_mut(&vnode.node_id) {
+            *count += 1;
+        }
+    }
+
+    targets
+}
+
+fn pick_target(
+    targets: &HashMap<NodeId, (usize, u64)>,
+    replication_set: &ReplicationSet,
+) -> Option<NodeId> {
+    targets
+        .iter()
+        .filter(|(id, _)| !replication_set.vnodes.iter().any(|v| v.node_id == **id))
+        .min_by_key(|(id, (count, disk_free))| (*count, Reverse(*disk_free), **id))
+        .map(|(id, _)| *id)
+}
+
+fn plan_move(
+    shard: &mut Shard,
+    index: usize,
+    to: NodeId,
+    targets: &mut HashMap<NodeId, (usize, u64)>,
+) -> VnodeMove {
+    let vnode = &mut shard.replication_set.vnodes[index];
+    let from = vnode.node_id;
+    vnode.node_id = to;
+    vnode.status = VnodeStatus::Copying;
+    if let Some((count, _)) = targets.get_mut(&from) {
+        *count -= 1;
+    }
+    if let Some((count, _)) = targets.get_mut(&to) {
+        *count += 1;
+    }
+
+    VnodeMove {
+        tenant: shard.tenant.clone(),
+        database: shard.database.clone(),
+        repl_set_id: shard.replication_set.id,
+        vnode_id: vnode.id,
+        from,
+        to: Some(to),
+        status: VnodeMoveStatus::Pending,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use models::meta_data::{
+        NodeAttribute, NodeInfo, NodeMetrics, ReplicationSet, VnodeInfo, VnodeStatus,
+    };
+
+    use super::{plan, Shard, VnodeMoveStatus};
+
+    fn node(id: u64, decommissioned: bool) -> NodeInfo {
+        NodeInfo {
+            id,
+            attribute: NodeAttribute::Hot,
+            decommissioned,
+            ..Default::default()
+        }
+    }
+
+    fn cold_node(id: u64) -> NodeInfo {
+        NodeInfo {
+            attribute: NodeAttribute::Cold,
+            ..node(id, false)
+        }
+    }
+
+    fn metrics(id: u64, disk_free: u64) -> NodeMetrics {
+        NodeMetrics {
+            id,
+            disk_free,
+            ..Default::default()
+        }
+    }
+
+    fn shard(id: u32, nodes: &[u64]) -> Shard {
+        let vnodes = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node_id)| VnodeInfo::new(id * 10 + i as u32, *node_id))
+            .collect();
+        Shard {
+            tenant: "cnosdb".to_string(),
+            database: "public".to_string(),
+            replication_set: ReplicationSet::new(id, vnodes),
+            cold: false,
+        }
+    }
+
+    #[test]
+    fn test_plan_balance() {
+        let nodes = vec![node(1, false), node(2, false), node(3, false)];
+        let metrics = vec![metrics(1, 100), metrics(2, 100), metrics(3, 200)];
+        let shards = (1..=4).map(|id| shard(id, &[1])).collect::<Vec<_>>();
+
+        // Nothing is moved unless balancing
+        assert!(plan(&nodes, &metrics, &shards, false).is_empty());
+
+        let moves = plan(&nodes, &metrics, &shards, true);
+        // 4 vnodes on node 1 become 2, 1 and 1, node 3 has more free disk
+        let targets = moves.iter().map(|m| m.to.unwrap()).collect::<Vec<_>>();
+        assert_eq!(targets, vec![3, 2]);
+        assert!(moves.iter().all(|m| m.from == 1));
+        assert!(moves.iter().all(|m| m.status == VnodeMoveStatus::Pending));
+    }
+
+    #[test]
+    fn test_plan_decommission() {
+        let nodes = vec![node(1, false), node(2, false), node(3, true)];
+        let metrics = vec![metrics(1, 100), metrics(2, 100), metrics(3, 100)];
+        let mut shards = vec![shard(1, &[1, 3]), shard(2, &[3, 2])];
+        shards[1].replication_set.vnodes[1].status = VnodeStatus::Copying;
+
+        let moves = plan(&nodes, &metrics, &shards, false);
+        assert_eq!(moves.len(), 3);
+        // The vnode being copied
+        assert_eq!(moves[0].vnode_id, 21);
+        assert_eq!(moves[0].status, VnodeMoveStatus::Moving);
+        // Replicas of a replication set are on different nodes
+        assert_eq!((moves[1].vnode_id, moves[1].to), (11, Some(2)));
+        assert_eq!((moves[2].vnode_id, moves[2].to), (20, Some(1)));
+    }
+
+    #[test]
+    fn test_plan_cold() {
+        let nodes = vec![node(1, false), node(2, false), cold_node(3), cold_node(4)];
+        let metrics = vec![
+            metrics(1, 100),
+            metrics(2, 100),
+            metrics(3, 100),
+            metrics(4, 200),
+        ];
+        let mut shards = vec![shard(1, &[1, 2]), shard(2, &[1]), shard(3, &[2, 3])];
+        shards[0].cold = true;
+        shards[2].cold = true;
+
+        // Vnodes of cold buckets are moved even if not balancing, the vnode already on
+        // a cold node stays, and the other replica goes to the other cold node.
+        let moves = plan(&nodes, &metrics, &shards, false);
+        let moves = moves
+            .iter()
+            .map(|m| (m.vnode_id, m.from, m.to.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(moves, vec![(10, 1, 4), (11, 2, 3), (30, 2, 4)]);
+
+        // Nothing is moved without healthy cold nodes
+        let hot_metrics = &metrics[..2];
+        assert!(plan(&nodes, hot_metrics, &shards, false).is_empty());
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    Array, BinaryArray, BooleanArray, Float64Array, Int64Array, StringArray, UInt32Array,
+    UInt64Array,
+};
+use datafusion::arrow::record_batch::RecordBatch;
+use models::meta_data::{NodeId, VnodeId};
+use models::predicate::domain::TimeRange;
+use models::schema::TskvTableSchema;
+use models::{ColumnId, SeriesKey, Timestamp};
+use protocol_parser::lines_convert::parse_lines_to_points;
+use protocol_parser::Line;
+use protos::FieldValue;
+
+use crate::errors::{CoordinatorError, CoordinatorResult};
+
+/// Maximum number of series time ranges fetched from a replica in one request.
+pub const MAX_FETCH_RANGES: usize = 1000;
+
+/// The repair of a vnode of a replication set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairResult {
+    pub vnode_id: VnodeId,
+    pub node_id: NodeId,
+    /// Number of time ranges of series that differ between replicas
+    pub ranges: u64,
+    /// Number of values written to the vnode
+    pub points: u64,
+    /// Number of time ranges of series not repaired as they were written during the repair
+    pub skipped_ranges: u64,
+}
+
+/// A time range of a series, the series key is encoded with series id 0 and the time
+/// range is closed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SeriesRange {
+    pub series_key: Vec<u8>,
+    pub min_ts: Timestamp,
+    pub max_ts: Timestamp,
+}
+
+/// (encoded series key, column id, timestamp)
+pub type ValueKey = (Vec<u8>, ColumnId, Timestamp);
+
+fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> CoordinatorResult<&'a T> {
+    batch
+        .column_by_name(name)
+        .and_then(|array| array.as_any().downcast_ref::<T>())
+        .ok_or_else(|| CoordinatorError::CommonError {
+            msg: format!("invalid column {name} of the replica data"),
+        })
+}
+
+/// Compares the time range checksums of the replicas, returns the time ranges of series
+/// that any replica lacks or differs in any column.
+pub fn divergent_ranges(checksums: &[RecordBatch]) -> CoordinatorResult<Vec<SeriesRange>> {
+    // (encoded series key, column id, min_ts, max_ts) -> checksum of each replica
+    let mut ranges: HashMap<(&[u8], ColumnId, Timestamp, Timestamp), Vec<Option<&str>>> =
+        HashMap::new();
+    for (i, batch) in checksums.iter().enumerate() {
+        let series_keys = column::<BinaryArray>(batch, "SERIES_KEY")?;
+        let column_ids = column::<UInt32Array>(batch, "COLUMN_ID")?;
+        let min_times = column::<Int64Array>(batch, "MIN_TIME")?;
+        let max_times = column::<Int64Array>(batch, "MAX_TIME")?;
+        let check_sums = column::<StringArray>(batch, "CHECK_SUM")?;
+        for row in 0..batch.num_rows() {
+            let key = (
+                series_keys.value(row),
+                column_ids.value(row),
+                min_times.value(row),
+                max_times.value(row),
+            );
+            ranges
+                .entry(key)
+                .or_insert_with(|| vec![None; checksums.len()])[i] = Some(check_sums.value(row));
+        }
+    }
+
+    let divergent: BTreeSet<SeriesRange> = ranges
+        .into_iter()
+        .filter(|(_, sums)| sums.iter().any(|sum| sum.is_none() || *sum != sums[0]))
+        .map(|((series_key, _, min_ts, max_ts), _)| SeriesRange {
+            series_key: series_key.to_vec(),
+            min_ts,
+            max_ts: max_ts.saturating_sub(1).max(min_ts),
+        })
+        .collect();
+
+    Ok(divergent.into_iter().collect())
+}
+
+/// Values and tombstones of a replica.
+#[derive(Default)]
+struct ReplicaData {
+    values: HashMap<ValueKey, FieldValue>,
+    /// (encoded series key, column id) -> time ranges
+    tombstones: HashMap<(Vec<u8>, ColumnId), Vec<TimeRange>>,
+}
+
+impl ReplicaData {
+    fn deleted(&self, (series_key, column_id, ts): &ValueKey) -> bool {
+        self.tombstones
+            .get(&(series_key.clone(), *column_id))
+            .map(|time_ranges| time_ranges.iter().any(|tr| tr.contains(*ts)))
+            .unwrap_or(false)
+    }
+}
+
+fn read_replica(batch: &RecordBatch) -> CoordinatorResult<ReplicaData> {
+    let series_keys = column::<BinaryArray>(batch, "SERIES_KEY")?;
+    let column_ids = column::<UInt32Array>(batch, "COLUMN_ID")?;
+    let times = column::<Int64Array>(batch, "TIME")?;
+    let max_times = column::<Int64Array>(batch, "MAX_TIME")?;
+    let u64_values = column::<UInt64Array>(batch, "U64_VALUE")?;
+    let i64_values = column::<Int64Array>(batch, "I64_VALUE")?;
+    let f64_values = column::<Float64Array>(batch, "F64_VALUE")?;
+    let str_values = column::<BinaryArray>(batch, "STR_VALUE")?;
+    let bool_values = column::<BooleanArray>(batch, "BOOL_VALUE")?;
+
+    let mut data = ReplicaData::default();
+    for row in 0..batch.num_rows() {
+        let series_key = series_keys.value(row).to_vec();
+        let column_id = column_ids.value(row);
+        if max_times.is_valid(row) {
+            let time_range = TimeRange::new(times.value(row), max_times.value(row));
+            data.tombstones
+                .entry((series_key, column_id))
+                .or_default()
+                .push(time_range);
+            continue;
+        }
+        let value = if u64_values.is_valid(row) {
+            FieldValue::U64(u64_values.value(row))
+        } else if i64_values.is_valid(row) {
+            FieldValue::I64(i64_values.value(row))
+        } else if f64_values.is_valid(row) {
+            FieldValue::F64(f64_values.value(row))
+        } else if str_values.is_valid(row) {
+            FieldValue::Str(str_values.value(row).to_vec())
+        } else if bool_values.is_valid(row) {
+            FieldValue::Bool(bool_values.value(row))
+        } else {
+            continue;
+        };
+        data.values
+            .insert((series_key, column_id, times.value(row)), value);
+    }
+
+    Ok(data)
+}
+
+/// The values to write to a divergent time range of a series of a replica.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeRepair {
+    pub range: SeriesRange,
+    /// Digest of the data of the replica in the time range the values are merged from,
+    /// the values are not written if the data was changed since.
+    pub digest: String,
+    pub writes: Vec<(ValueKey, FieldValue)>,
+}
+
+/// Merges the data of the replicas in the divergent time ranges, returns the values each
+/// replica lacks in each time range.
+///
+/// Sequences of replicas are of the WAL of their nodes and can't be compared, so values
+/// are only added to the replicas missing them, values are never overwritten or deleted:
+/// - a value is added only if all replicas holding a value at the timestamp agree on it.
+/// - a value isn't added to a replica that has a tombstone covering it.
+pub fn repair_replicas(
+    ranges: &[SeriesRange],
+    replicas: &[RecordBatch],
+) -> CoordinatorResult<Vec<Vec<RangeRepair>>> {
+    let replica_data = replicas
+        .iter()
+        .map(read_replica)
+        .collect::<CoordinatorResult<Vec<_>>>()?;
+
+    // Value key -> the value if all replicas holding it agree on it
+    let mut merged: HashMap<&ValueKey, Option<&FieldValue>> = HashMap::new();
+    for data in replica_data.iter() {
+        for (key, value) in data.values.iter() {
+            let merged_value = merged.entry(key).or_insert(Some(value));
+            if *merged_value != Some(value) {
+                *merged_value = None;
+            }
+        }
+    }
+
+    let mut repairs = Vec::with_capacity(replica_data.len());
+    for (data, batch) in replica_data.iter().zip(replicas) {
+        // Index of time range -> values to write
+        let mut range_writes: BTreeMap<usize, Vec<(ValueKey, FieldValue)>> = BTreeMap::new();
+        for (key, value) in merged.iter() {
+            let Some(value) = value else {
+                continue;
+            };
+            if data.values.contains_key(*key) || data.deleted(key) {
+                continue;
+            }
+            let (series_key, _, ts) = key;
+            let Some(i) = ranges.iter().position(|range| {
+                range.series_key == *series_key && range.min_ts <= *ts && *ts <= range.max_ts
+            }) else {
+                continue;
+            };
+            range_writes
+                .entry(i)
+                .or_default()
+                .push(((*key).clone(), (*value).clone()));
+        }
+
+        let mut replica_repairs = Vec::with_capacity(range_writes.len());
+        for (i, mut writes) in range_writes {
+            let range = ranges[i].clone();
+            let digest = tskv::series_range_digest(
+                batch,
+                &range.series_key,
+                &TimeRange::new(range.min_ts, range.max_ts),
+            )?;
+            writes.sort_by(|(a, _), (b, _)| a.cmp(b));
+            replica_repairs.push(RangeRepair {
+                range,
+                digest,
+                writes,
+            });
+        }
+        repairs.push(replica_repairs);
+    }
+
+    Ok(repairs)
+}
+
+/// Returns if each time range is repaired from the result of repairing a vnode.
+pub fn repaired_ranges(result: &RecordBatch) -> CoordinatorResult<Vec<bool>> {
+    let repaired = column::<BooleanArray>(result, "REPAIRED")?;
+    Ok((0..result.num_rows()).map(|i| repaired.value(i)).collect())
+}
+
+/// Builds the points of the values, values of unknown series, tables or columns are
+/// skipped. Returns the flatbuffers points and the number of values.
+pub fn build_points(
+    db: &str,
+    schemas: &HashMap<String, Arc<TskvTableSchema>>,
+    values: &[(ValueKey, FieldValue)],
+) -> (Vec<u8>, u64) {
+    let mut series_keys: HashMap<&[u8], Option<SeriesKey>> = HashMap::new();
+    for ((series_key, _, _), _) in values.iter() {
+        series_keys
+            .entry(series_key.as_slice())
+            .or_insert_with(|| SeriesKey::decode(series_key).ok());
+    }
+
+    // The values of a row are written in a line.
+    let mut rows: HashMap<(&[u8], Timestamp), Vec<(&str, FieldValue)>> = HashMap::new();
+    let mut count = 0;
+    for ((series_key, column_id, ts), value) in values.iter() {
+        let Some(Some(key)) = series_keys.get(series_key.as_slice()) else {
+            continue;
+        };
+        let Some(column) = schemas
+            .get(key.table())
+            .and_then(|schema| schema.column_name(*column_id))
+        else {
+            continue;
+        };
+        rows.entry((series_key.as_slice(), *ts))
+            .or_default()
+            .push((column, value.clone()));
+        count += 1;
+    }
+
+    let mut lines = Vec::with_capacity(rows.len());
+    for ((series_key, ts), fields) in rows {
+        let Some(Some(key)) = series_keys.get(series_key) else {
+            continue;
+        };
+        let tags = key
+            .tags()
+            .iter()
+            .filter_map(|tag| {
+                let key = std::str::from_utf8(&tag.key).ok()?;
+                let value = std::str::from_utf8(&tag.value).ok()?;
+                Some((key, value))
+            })
+            .collect();
+        lines.push(Line::new(key.table(), tags, fields, ts));
+    }
+
+    (parse_lines_to_points(db, &lines), count)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{
+        ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, StringArray, UInt32Array,
+        UInt64Array,
+    };
+    use datafusion::arrow::record_batch::RecordBatch;
+    use models::predicate::domain::TimeRange;
+    use protos::FieldValue;
+    use tskv::{vnode_series_data_schema, vnode_time_range_checksum_schema};
+
+    use super::{divergent_ranges, repair_replicas, RangeRepair, SeriesRange};
+
+    fn checksums(rows: &[(&[u8], u32, i64, i64, &str)]) -> RecordBatch {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(BinaryArray::from_vec(rows.iter().map(|r| r.0).collect())),
+            Arc::new(UInt32Array::from_iter_values(rows.iter().map(|r| r.1))),
+            Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.2))),
+            Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.3))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.4))),
+        ];
+        RecordBatch::try_new(vnode_time_range_checksum_schema(), columns).unwrap()
+    }
+
+    /// Rows of (series key, column id, time, max time of tombstone, sequence, value)
+    fn data(rows: &[(&[u8], u32, i64, Option<i64>, u64, Option<f64>)]) -> RecordBatch {
+        let len = rows.len();
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(BinaryArray::from_vec(rows.iter().map(|r| r.0).collect())),
+            Arc::new(UInt32Array::from_iter_values(rows.iter().map(|r| r.1))),
+            Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.2))),
+            Arc::new(Int64Array::from_iter(rows.iter().map(|r| r.3))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.4))),
+            Arc::new(UInt64Array::from(vec![None; len])),
+            Arc::new(Int64Array::from(vec![None; len])),
+            Arc::new(Float64Array::from_iter(rows.iter().map(|r| r.5))),
+            Arc::new(BinaryArray::from_opt_vec(vec![None; len])),
+            Arc::new(BooleanArray::from(vec![None; len])),
+        ];
+        RecordBatch::try_new(vnode_series_data_schema(), columns).unwrap()
+    }
+
+    #[test]
+    fn test_divergent_ranges() {
+        let replica1 = checksums(&[
+            (b"a", 1, 0, 10, "x"),
+            (b"a", 2, 0, 10, "y"),
+            (b"b", 1, 0, 10, "z"),
+            (b"b", 1, 10, 20, "z"),
+        ]);
+        let replica2 = checksums(&[
+            (b"a", 1, 0, 10, "x"),
+            (b"a", 2, 0, 10, "w"),
+            (b"b", 1, 0, 10, "z"),
+        ]);
+
+        let ranges = divergent_ranges(&[replica1.clone(), replica2]).unwrap();
+        let expected = vec![
+            SeriesRange {
+                series_key: b"a".to_vec(),
+                min_ts: 0,
+                max_ts: 9,
+            },
+            SeriesRange {
+                series_key: b"b".to_vec(),
+                min_ts: 10,
+                max_ts: 19,
+            },
+        ];
+        assert_eq!(ranges, expected);
+
+        assert!(divergent_ranges(&[replica1.clone(), replica1])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_repair_replicas() {
+        let ranges = vec![SeriesRange {
+            series_key: b"a".to_vec(),
+            min_ts: 0,
+            max_ts: 9,
+        }];
+        let replica1 = data(&[
+            (b"a", 1, 1, None, 5, Some(1.0)),
+            (b"a", 1, 2, None, 5, Some(2.0)),
+            (b"a", 1, 4, None, 5, Some(5.0)),
+            // Out of the divergent time ranges.
+            (b"a", 1, 12, None, 5, Some(6.0)),
+        ]);
+        let replica2 = data(&[
+            (b"a", 1, 2, None, 80, Some(3.0)),
+            (b"a", 1, 3, None, 80, Some(4.0)),
+            (b"a", 1, 4, Some(4), 70, None),
+        ]);
+        let digest = |batch: &RecordBatch| {
+            tskv::series_range_digest(batch, b"a", &TimeRange::new(0, 9)).unwrap()
+        };
+
+        let repairs = repair_replicas(&ranges, &[replica1.clone(), replica2.clone()]).unwrap();
+        // The conflicting values at 2 are kept whatever the sequences are.
+        assert_eq!(
+            repairs[0],
+            vec![RangeRepair {
+                range: ranges[0].clone(),
+                digest: digest(&replica1),
+                writes: vec![((b"a".to_vec(), 1, 3), FieldValue::F64(4.0))],
+            }]
+        );
+        // The value at 4 isn't added to the replica that deleted it, nor deleted from
+        // the other one.
+        assert_eq!(
+            repairs[1],
+            vec![RangeRepair {
+                range: ranges[0].clone(),
+                digest: digest(&replica2),
+                writes: vec![((b"a".to_vec(), 1, 1), FieldValue::F64(1.0))],
+            }]
+        );
+
+        let repairs = repair_replicas(&ranges, &[replica1.clone(), replica1]).unwrap();
+        assert!(repairs.iter().all(|r| r.is_empty()));
+    }
+}
@@ -6,15 +6,17 @@ use std::vec;
 
 use config::{Config, HintedOffConfig};
 use datafusion::arrow::record_batch::RecordBatch;
+use futures::StreamExt;
+use meta::error::MetaError;
 use meta::model::{MetaClientRef, MetaRef};
 use metrics::count::U64Counter;
 use metrics::label::Labels;
 use metrics::metric::Metric;
 use metrics::metric_register::MetricsRegister;
 use models::consistency_level::ConsistencyLevel;
-use models::meta_data::{ExpiredBucketInfo, ReplicationSet};
+use models::meta_data::{ExpiredBucketInfo, ReplicationSet, VnodeStatus};
 use models::object_reference::ResolvedTable;
-use models::predicate::domain::{ResolvedPredicateRef, TimeRanges};
+use models::predicate::domain::{ResolvedPredicate, ResolvedPredicateRef, TimeRanges};
 use models::record_batch_decode;
 use models::schema::{Precision, DEFAULT_CATALOG};
 use protos::kv_service::admin_command_request::Command::*;
@@ -22,7 +24,7 @@ use protos::kv_service::tskv_service_client::TskvServiceClient;
 use protos::kv_service::{WritePointsRequest, *};
 use protos::models::Points;
 use tokio::runtime::Runtime;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tonic::transport::Channel;
 use tower::timeout::Timeout;
 use trace::{debug, error, info, SpanContext, SpanExt, SpanRecorder};
@@ -35,22 +37,31 @@ use crate::reader::replica_selection::{DynamicReplicaSelectioner, DynamicReplica
 use crate::reader::table_scan::opener::TemporaryTableScanOpener;
 use crate::reader::tag_scan::opener::TemporaryTagScanOpener;
 use crate::reader::{CheckFuture, CheckedCoordinatorRecordBatchStream};
+use crate::rebalance::{self, Shard, VnodeMove};
+use crate::repair::{self, RepairResult};
 use crate::writer::PointWriter;
+use crate::written_log::{WrittenLog, WrittenLogDelta, WrittenLogRef};
 use crate::{
     status_response_to_result, Coordinator, QueryOption, SendableCoordinatorRecordBatchStream,
-    VnodeManagerCmdType, VnodeSummarizerCmdType, WriteRequest,
+    VnodeManagerCmdType, VnodeSummarizerCmdType, WriteRequest, WrittenTimeRange,
 };
 
 pub type CoordinatorRef = Arc<dyn Coordinator>;
 
+/// Only the node holding this lease in meta moves vnodes between nodes.
+const REBALANCE_LEASE: &str = "vnode_rebalance";
+
 #[derive(Clone)]
 pub struct CoordService {
     node_id: u64,
     meta: MetaRef,
     config: Config,
+    /// The consistency level of the writes that don't specify one
+    write_consistency_level: ConsistencyLevel,
     runtime: Arc<Runtime>,
     kv_inst: Option<EngineRef>,
     writer: Arc<PointWriter>,
+    written_log: WrittenLogRef,
     metrics: Arc<CoordServiceMetrics>,
 
     replica_selectioner: DynamicReplicaSelectionerRef,
@@ -116,12 +127,14 @@ impl CoordService {
         metrics_register: Arc<MetricsRegister>,
     ) -> Arc<Self> {
         let (hh_sender, hh_receiver) = mpsc::channel(1024);
+        let written_log = Arc::new(WrittenLog::new());
         let point_writer = Arc::new(PointWriter::new(
             config.node_basic.node_id,
             config.query.write_timeout_ms,
             kv_inst.clone(),
             meta_manager.clone(),
             hh_sender,
+            written_log.clone(),
         ));
 
         let hh_manager = Arc::new(
@@ -130,18 +143,29 @@ impl CoordService {
         tokio::spawn(HintedOffManager::write_handoff_job(hh_manager, hh_receiver));
 
         let replica_selectioner = Arc::new(DynamicReplicaSelectioner::new(meta_manager.clone()));
+        let write_consistency_level = config
+            .query
+            .write_consistency_level
+            .parse::<ConsistencyLevel>()
+            .expect("parse query.write_consistency_level");
         let coord = Arc::new(Self {
             runtime,
             kv_inst,
             config: config.clone(),
             node_id: config.node_basic.node_id,
+            write_consistency_level,
             meta: meta_manager,
             writer: point_writer,
+            written_log,
             metrics: Arc::new(CoordServiceMetrics::new(metrics_register.as_ref())),
             replica_selectioner,
         });
 
         tokio::spawn(CoordService::db_ttl_service(coord.clone()));
+        tokio::spawn(CoordService::rebalance_service(coord.clone()));
+        if config.repair.enable {
+            tokio::spawn(CoordService::repair_service(coord.clone()));
+        }
 
         if config.node_basic.store_metrics {
             tokio::spawn(CoordService::metrics_service(
@@ -167,6 +191,91 @@ impl CoordService {
         }
     }
 
+    /// Moves the vnodes planned by the rebalancer, at most `max_concurrent_moves` at the same
+    /// time. Only the node holding the rebalance lease in meta plans and starts the moves,
+    /// vnodes of cold buckets are moved to cold nodes by it too, so a vnode is never picked
+    /// by two movers.
+    async fn rebalance_service(coord: Arc<CoordService>) {
+        let rebalance = coord.config.rebalance.clone();
+        let lease_ttl = rebalance.check_interval * 3;
+        loop {
+            tokio::time::sleep(rebalance.check_interval).await;
+
+            match coord.meta.acquire_lease(REBALANCE_LEASE, lease_ttl).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    error!("acquire the lease to rebalance vnodes fail. {e}");
+                    continue;
+                }
+            }
+
+            let moves = match coord.rebalance_plan(rebalance.enable).await {
+                Ok(moves) => moves,
+                Err(e) => {
+                    error!("plan the rebalance of vnodes fail. {e}");
+                    continue;
+                }
+            };
+            futures::stream::iter(moves.into_iter().filter_map(|m| Some((m.to?, m))))
+                .map(|(to, m)| {
+                    let coord = coord.clone();
+                    async move {
+                        // Moves are not started after the lease is lost to another node
+                        if !matches!(
+                            coord.meta.acquire_lease(REBALANCE_LEASE, lease_ttl).await,
+                            Ok(true)
+                        ) {
+                            return;
+                        }
+                        let result = coord
+                            .move_vnode_to_node(&m.tenant, m.vnode_id, m.from, to)
+                            .await;
+                        info!(
+                            "rebalance vnode {} of {}.{} from node {} to node {}: {:?}",
+                            m.vnode_id, m.tenant, m.database, m.from, to, result
+                        );
+                    }
+                })
+                .buffer_unordered(rebalance.max_concurrent_moves)
+                .collect::<Vec<_>>()
+                .await;
+        }
+    }
+
+    async fn repair_service(coord: Arc<CoordService>) {
+        let interval = coord.config.repair.interval;
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let shards = match rebalance::all_shards(&coord.meta).await {
+                Ok(shards) => shards,
+                Err(e) => {
+                    error!("get replication sets to repair fail. {e}");
+                    continue;
+                }
+            };
+            for shard in shards.iter() {
+                // A replication set is repaired by the node of its first running vnode.
+                let node_id = shard
+                    .replication_set
+                    .vnodes
+                    .iter()
+                    .find(|vnode| vnode.status == VnodeStatus::Running)
+                    .map(|vnode| vnode.node_id);
+                if node_id != Some(coord.node_id) {
+                    continue;
+                }
+
+                let result = coord.repair_shard(shard).await;
+                info!(
+                    "repair replication set {} of {}.{}: {:?}",
+                    shard.replication_set.id, shard.tenant, shard.database, result
+                );
+            }
+        }
+    }
+
     async fn metrics_service(
         coord: Arc<CoordService>,
         root_metrics_register: Arc<MetricsRegister>,
@@ -190,7 +299,7 @@ impl CoordService {
                 if let Err(e) = coord
                     .write_points(
                         DEFAULT_CATALOG.to_string(),
-                        ConsistencyLevel::Any,
+                        Some(ConsistencyLevel::Any),
                         Precision::NS,
                         req,
                         // metrics service 不采集trace
@@ -231,6 +340,193 @@ impl CoordService {
         Ok(())
     }
 
+    /// Moves the vnode from node `from` to node `to`.
+    async fn move_vnode_to_node(
+        &self,
+        tenant: &str,
+        vnode_id: u32,
+        from: u64,
+        to: u64,
+    ) -> CoordinatorResult<()> {
+        // The plan may be stale, the vnode is moved only if it is still running on `from`.
+        let meta = self
+            .tenant_meta(tenant)
+            .await
+            .ok_or(CoordinatorError::TenantNotFound {
+                name: tenant.to_string(),
+            })?;
+        match meta.get_vnode_all_info(vnode_id) {
+            Some(info) if info.node_id == from && info.status == VnodeStatus::Running => {}
+            _ => {
+                return Err(CoordinatorError::CommonError {
+                    msg: format!("vnode {vnode_id} is not running on node {from}"),
+                })
+            }
+        }
+
+        let result = self
+            .vnode_manager(tenant, VnodeManagerCmdType::Move(vnode_id, to))
+            .await;
+
+        // The vnode is left copying if the copy failed, reset it to be moved again later.
+        if result.is_err() {
+            if let Some(mut all_info) = meta.get_vnode_all_info(vnode_id) {
+                if all_info.node_id == from && all_info.status == VnodeStatus::Copying {
+                    all_info.set_status(VnodeStatus::Running);
+                    meta.update_vnode(&all_info).await?;
+                }
+            }
+        }
+
+        result
+    }
+
+    async fn rebalance_plan(&self, balance: bool) -> CoordinatorResult<Vec<VnodeMove>> {
+        let nodes = self.meta.data_nodes().await;
+        let metrics = self.meta.node_metrics().await?;
+        let shards = rebalance::all_shards(&self.meta).await?;
+
+        Ok(rebalance::plan(&nodes, &metrics, &shards, balance))
+    }
+
+    /// Repairs the running vnodes of the replication set by comparing the checksums of
+    /// the time ranges of series, the data and tombstones of divergent time ranges are
+    /// fetched from all replicas and merged by `repair::repair_replicas`. Values are written
+    /// to the replicas lacking them.
+    ///
+    /// A time range of a replica is only written if its data is still what the values were
+    /// merged from, writes to the vnode wait for the check and the write. Time ranges
+    /// written during the repair are skipped, and repaired in the next run.
+    async fn repair_shard(&self, shard: &Shard) -> CoordinatorResult<Vec<RepairResult>> {
+        let vnodes = shard
+            .replication_set
+            .vnodes
+            .iter()
+            .filter(|vnode| vnode.status == VnodeStatus::Running)
+            .collect::<Vec<_>>();
+        if vnodes.len() < 2 {
+            return Ok(vec![]);
+        }
+        let meta = self.tenant_meta(&shard.tenant).await.ok_or_else(|| {
+            CoordinatorError::TenantNotFound {
+                name: shard.tenant.clone(),
+            }
+        })?;
+
+        let checksums = futures::future::try_join_all(vnodes.iter().map(|vnode| {
+            let cmd = AdminFetchCommandRequest {
+                tenant: shard.tenant.clone(),
+                command: Some(
+                    admin_fetch_command_request::Command::FetchVnodeTimeRangeChecksum(
+                        FetchVnodeTimeRangeChecksumRequest { vnode_id: vnode.id },
+                    ),
+                ),
+            };
+            self.exec_admin_fetch_command_on_node(vnode.node_id, cmd)
+        }))
+        .await?;
+        let ranges = repair::divergent_ranges(&checksums)?;
+
+        let mut points = vec![0; vnodes.len()];
+        let mut skipped_ranges = vec![0; vnodes.len()];
+        if !ranges.is_empty() {
+            let db_schema = meta.get_db_schema(&shard.database)?.ok_or_else(|| {
+                MetaError::DatabaseNotFound {
+                    database: shard.database.clone(),
+                }
+            })?;
+            let precision = *db_schema.config.precision_or_default();
+            let mut schemas = HashMap::new();
+            for table in meta.list_tables(&shard.database)? {
+                if let Some(schema) = meta.get_tskv_table_schema(&shard.database, &table)? {
+                    schemas.insert(table, schema);
+                }
+            }
+
+            for chunk in ranges.chunks(repair::MAX_FETCH_RANGES) {
+                let ranges = chunk
+                    .iter()
+                    .map(|range| SeriesTimeRange {
+                        series_key: range.series_key.clone(),
+                        min_ts: range.min_ts,
+                        max_ts: range.max_ts,
+                    })
+                    .collect::<Vec<_>>();
+                let data = futures::future::try_join_all(vnodes.iter().map(|vnode| {
+                    let cmd = AdminFetchCommandRequest {
+                        tenant: shard.tenant.clone(),
+                        command: Some(admin_fetch_command_request::Command::FetchVnodeSeriesData(
+                            FetchVnodeSeriesDataRequest {
+                                vnode_id: vnode.id,
+                                ranges: ranges.clone(),
+                            },
+                        )),
+                    };
+                    self.exec_admin_fetch_command_on_node(vnode.node_id, cmd)
+                }))
+                .await?;
+
+                let repairs = repair::repair_replicas(chunk, &data)?;
+                for (i, repairs) in repairs.into_iter().enumerate() {
+                    let mut counts = Vec::with_capacity(repairs.len());
+                    let mut ranges = Vec::with_capacity(repairs.len());
+                    for repair in repairs {
+                        let (data, count) =
+                            repair::build_points(&shard.database, &schemas, &repair.writes);
+                        if count == 0 {
+                            continue;
+                        }
+                        counts.push(count);
+                        ranges.push(SeriesTimeRangeRepair {
+                            series_key: repair.range.series_key,
+                            min_ts: repair.range.min_ts,
+                            max_ts: repair.range.max_ts,
+                            digest: repair.digest,
+                            points: data,
+                        });
+                    }
+                    if ranges.is_empty() {
+                        continue;
+                    }
+
+                    let cmd = AdminFetchCommandRequest {
+                        tenant: shard.tenant.clone(),
+                        command: Some(admin_fetch_command_request::Command::RepairVnodeSeriesData(
+                            RepairVnodeSeriesDataRequest {
+                                vnode_id: vnodes[i].id,
+                                precision: precision as u32,
+                                ranges,
+                            },
+                        )),
+                    };
+                    let result = self
+                        .exec_admin_fetch_command_on_node(vnodes[i].node_id, cmd)
+                        .await?;
+                    let repaired = repair::repaired_ranges(&result)?;
+                    for (count, repaired) in counts.into_iter().zip(repaired) {
+                        if repaired {
+                            points[i] += count;
+                        } else {
+                            skipped_ranges[i] += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(vnodes
+            .iter()
+            .zip(points.into_iter().zip(skipped_ranges))
+            .map(|(vnode, (points, skipped_ranges))| RepairResult {
+                vnode_id: vnode.id,
+                node_id: vnode.node_id,
+                ranges: ranges.len() as u64,
+                points,
+                skipped_ranges,
+            })
+            .collect())
+    }
+
     async fn get_replication_set(
         &self,
         tenant: &str,
@@ -363,11 +659,24 @@ impl Coordinator for CoordService {
     async fn write_points(
         &self,
         tenant: String,
-        level: ConsistencyLevel,
+        level: Option<ConsistencyLevel>,
         precision: Precision,
         request: WritePointsRequest,
         span_ctx: Option<&SpanContext>,
     ) -> CoordinatorResult<()> {
+        self.write_points_or_limited(tenant, level, precision, request, span_ctx)
+            .await?
+            .map_err(CoordinatorError::from)
+    }
+
+    async fn write_points_or_limited(
+        &self,
+        tenant: String,
+        level: Option<ConsistencyLevel>,
+        precision: Precision,
+        request: WritePointsRequest,
+        span_ctx: Option<&SpanContext>,
+    ) -> CoordinatorResult<Result<(), MetaError>> {
         {
             let _span_recorder = SpanRecorder::new(span_ctx.child_span("limit check"));
 
@@ -379,8 +688,12 @@ impl Coordinator for CoordService {
 
             let write_size = points.len();
 
-            limiter.check_write().await?;
-            limiter.check_data_in(write_size).await?;
+            if let Err(err) = limiter.check_write().await {
+                return Ok(Err(err));
+            }
+            if let Err(err) = limiter.check_data_in(write_size).await {
+                return Ok(Err(err));
+            }
 
             self.metrics
                 .data_in(tenant.as_str(), db)
@@ -389,7 +702,7 @@ impl Coordinator for CoordService {
 
         let req = WriteRequest {
             tenant: tenant.clone(),
-            level,
+            level: level.unwrap_or(self.write_consistency_level),
             precision,
             request,
         };
@@ -404,7 +717,42 @@ impl Coordinator for CoordService {
             now.elapsed()
         );
 
-        res
+        res.map(Ok)
+    }
+
+    fn subscribe_written_time_ranges(&self) -> broadcast::Receiver<WrittenTimeRange> {
+        self.writer.subscribe_written_time_ranges()
+    }
+
+    fn written_log(&self) -> WrittenLogRef {
+        self.written_log.clone()
+    }
+
+    async fn fetch_written_time_ranges(
+        &self,
+        node_id: u64,
+        since_seq: u64,
+    ) -> CoordinatorResult<WrittenLogDelta> {
+        let channel = self.meta.get_node_conn(node_id).await?;
+        let timeout_channel = Timeout::new(channel, Duration::from_secs(5));
+        let mut client = TskvServiceClient::<Timeout<Channel>>::new(timeout_channel);
+        let request = tonic::Request::new(AdminFetchCommandRequest {
+            tenant: DEFAULT_CATALOG.to_string(),
+            command: Some(
+                admin_fetch_command_request::Command::FetchWrittenTimeRanges(
+                    FetchWrittenTimeRangesRequest { since_seq },
+                ),
+            ),
+        });
+
+        let response = client
+            .exec_admin_fetch_command(request)
+            .await
+            .map_err(tskv::Error::from)?
+            .into_inner();
+        let batch = record_batch_decode(&response.data)
+            .map_err(|source| CoordinatorError::ArrowError { source })?;
+        WrittenLogDelta::from_record_batch(&batch)
     }
 
     fn table_scan(
@@ -478,6 +826,48 @@ impl Coordinator for CoordService {
         Ok(())
     }
 
+    async fn delete_from_table(
+        &self,
+        table: &ResolvedTable,
+        predicate: &ResolvedPredicate,
+    ) -> CoordinatorResult<()> {
+        let time_ranges = predicate.time_ranges();
+        if time_ranges.is_empty() {
+            return Ok(());
+        }
+        let shards = self.prune_shards(table, time_ranges.as_ref()).await?;
+
+        // Group vnode ids of all replicas by node id.
+        let mut node_vnode_ids_map: HashMap<u64, Vec<u32>> = HashMap::new();
+        for vnode in shards.into_iter().flat_map(|s| s.vnodes) {
+            node_vnode_ids_map
+                .entry(vnode.node_id)
+                .or_default()
+                .push(vnode.id);
+        }
+
+        let predicate = ResolvedPredicate::encode(predicate)?;
+        let mut req_futures = vec![];
+        for (node_id, vnode_ids) in node_vnode_ids_map {
+            let cmd = AdminCommandRequest {
+                tenant: table.tenant().to_string(),
+                command: Some(DeleteFromTable(DeleteFromTableRequest {
+                    db: table.database().to_string(),
+                    table: table.table().to_string(),
+                    predicate: predicate.clone(),
+                    vnode_ids,
+                })),
+            };
+            req_futures.push(self.exec_admin_command_on_node(node_id, cmd));
+        }
+
+        for res in futures::future::join_all(req_futures).await {
+            res?
+        }
+
+        Ok(())
+    }
+
     async fn vnode_manager(
         &self,
         tenant: &str,
@@ -533,6 +923,41 @@ impl Coordinator for CoordService {
                 )
             }
 
+            VnodeManagerCmdType::Backup(vnode_id, location, options) => {
+                let all_info =
+                    crate::get_vnode_all_info(self.meta.clone(), tenant, vnode_id).await?;
+                (
+                    AdminCommandRequest {
+                        tenant: tenant.to_string(),
+                        command: Some(BackupVnode(BackupVnodeRequest {
+                            db: all_info.db_name,
+                            vnode_id,
+                            location,
+                            options,
+                        })),
+                    },
+                    all_info.node_id,
+                )
+            }
+
+            VnodeManagerCmdType::Restore(vnode_id, backup_vnode_id, location, options) => {
+                let all_info =
+                    crate::get_vnode_all_info(self.meta.clone(), tenant, vnode_id).await?;
+                (
+                    AdminCommandRequest {
+                        tenant: tenant.to_string(),
+                        command: Some(RestoreVnode(RestoreVnodeRequest {
+                            db: all_info.db_name,
+                            vnode_id,
+                            backup_vnode_id,
+                            location,
+                            options,
+                        })),
+                    },
+                    all_info.node_id,
+                )
+            }
+
             VnodeManagerCmdType::Compact(vnode_ids) => {
                 // Group vnode ids by node id.
                 let mut node_vnode_ids_map: HashMap<u64, Vec<u32>> = HashMap::new();
@@ -615,6 +1040,33 @@ impl Coordinator for CoordService {
         }
     }
 
+    async fn decommission_node(&self, node_id: u64) -> CoordinatorResult<()> {
+        self.meta.node_info_by_id(node_id).await?;
+        self.meta.decommission_data_node(node_id).await?;
+
+        Ok(())
+    }
+
+    async fn rebalance_status(&self) -> CoordinatorResult<Vec<VnodeMove>> {
+        self.rebalance_plan(self.config.rebalance.enable).await
+    }
+
+    async fn repair_replication_set(
+        &self,
+        tenant: &str,
+        replication_set_id: u32,
+    ) -> CoordinatorResult<Vec<RepairResult>> {
+        let shard = rebalance::all_shards(&self.meta)
+            .await?
+            .into_iter()
+            .find(|shard| shard.tenant == tenant && shard.replication_set.id == replication_set_id)
+            .ok_or(CoordinatorError::ReplicationSetNotFound {
+                id: replication_set_id,
+            })?;
+
+        self.repair_shard(&shard).await
+    }
+
     fn metrics(&self) -> &Arc<CoordServiceMetrics> {
         &self.metrics
     }
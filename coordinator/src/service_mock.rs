@@ -5,24 +5,30 @@ use std::sync::Arc;
 use std::todo;
 
 use datafusion::arrow::record_batch::RecordBatch;
+use meta::error::MetaError;
 use meta::model::meta_admin::AdminMeta;
 use meta::model::meta_tenant::TenantMeta;
 use meta::model::{MetaClientRef, MetaRef};
 use models::consistency_level::ConsistencyLevel;
 use models::meta_data::{ReplicationSet, VnodeInfo, VnodeStatus};
 use models::object_reference::ResolvedTable;
-use models::predicate::domain::ResolvedPredicateRef;
+use models::predicate::domain::{ResolvedPredicate, ResolvedPredicateRef};
 use models::schema::Precision;
 use protos::kv_service::{AdminCommandRequest, WritePointsRequest};
+use tokio::sync::broadcast;
 use trace::SpanContext;
 use tskv::engine_mock::MockEngine;
 use tskv::reader::QueryOption;
 use tskv::EngineRef;
 
 use crate::errors::CoordinatorResult;
+use crate::rebalance::VnodeMove;
+use crate::repair::RepairResult;
 use crate::service::CoordServiceMetrics;
+use crate::written_log::{WrittenLog, WrittenLogDelta, WrittenLogRef};
 use crate::{
     Coordinator, SendableCoordinatorRecordBatchStream, VnodeManagerCmdType, VnodeSummarizerCmdType,
+    WrittenTimeRange,
 };
 
 pub const WITH_NONEMPTY_DATABASE_FOR_TEST: &str = "with_nonempty_database";
@@ -127,7 +133,7 @@ impl Coordinator for MockCoordinator {
     async fn write_points(
         &self,
         tenant: String,
-        level: ConsistencyLevel,
+        level: Option<ConsistencyLevel>,
         precision: Precision,
         req: WritePointsRequest,
         _span_ctx: Option<&SpanContext>,
@@ -135,6 +141,37 @@ impl Coordinator for MockCoordinator {
         Ok(())
     }
 
+    async fn write_points_or_limited(
+        &self,
+        tenant: String,
+        level: Option<ConsistencyLevel>,
+        precision: Precision,
+        req: WritePointsRequest,
+        _span_ctx: Option<&SpanContext>,
+    ) -> CoordinatorResult<Result<(), MetaError>> {
+        Ok(Ok(()))
+    }
+
+    fn subscribe_written_time_ranges(&self) -> broadcast::Receiver<WrittenTimeRange> {
+        broadcast::channel(1).1
+    }
+
+    fn written_log(&self) -> WrittenLogRef {
+        Arc::new(WrittenLog::new())
+    }
+
+    async fn fetch_written_time_ranges(
+        &self,
+        node_id: u64,
+        since_seq: u64,
+    ) -> CoordinatorResult<WrittenLogDelta> {
+        Ok(WrittenLogDelta {
+            seq: since_seq,
+            reset: false,
+            events: vec![],
+        })
+    }
+
     fn table_scan(
         &self,
         option: QueryOption,
@@ -156,6 +193,14 @@ impl Coordinator for MockCoordinator {
         Ok(())
     }
 
+    async fn delete_from_table(
+        &self,
+        table: &ResolvedTable,
+        predicate: &ResolvedPredicate,
+    ) -> CoordinatorResult<()> {
+        Ok(())
+    }
+
     async fn vnode_manager(
         &self,
         tenant: &str,
@@ -172,6 +217,22 @@ impl Coordinator for MockCoordinator {
         Ok(vec![])
     }
 
+    async fn decommission_node(&self, node_id: u64) -> CoordinatorResult<()> {
+        Ok(())
+    }
+
+    async fn rebalance_status(&self) -> CoordinatorResult<Vec<VnodeMove>> {
+        Ok(vec![])
+    }
+
+    async fn repair_replication_set(
+        &self,
+        tenant: &str,
+        replication_set_id: u32,
+    ) -> CoordinatorResult<Vec<RepairResult>> {
+        Ok(vec![])
+    }
+
     fn metrics(&self) -> &Arc<CoordServiceMetrics> {
         todo!()
     }
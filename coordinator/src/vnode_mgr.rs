@@ -1,8 +1,13 @@
+use std::collections::HashMap;
 use std::path::Path;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use futures::TryStreamExt;
 use meta::model::MetaRef;
 use models::meta_data::{VnodeAllInfo, VnodeInfo, VnodeStatus};
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectMeta, ObjectStore};
 use protos::kv_service::admin_command_request::Command::DelVnode;
 use protos::kv_service::tskv_service_client::TskvServiceClient;
 use protos::kv_service::{
@@ -14,16 +19,22 @@ use tokio_stream::StreamExt;
 use tonic::transport::Channel;
 use tower::timeout::Timeout;
 use trace::info;
+use tskv::kv_option::INDEX_PATH;
 use tskv::EngineRef;
 
 use crate::errors::{CoordinatorError, CoordinatorResult};
 use crate::file_info::get_file_info;
 use crate::{status_response_to_result, SUCCESS_RESPONSE_CODE};
 
+/// Name of the object that stores summary of a vnode in backup.
+const BACKUP_SUMMARY_NAME: &str = "summary";
+
 pub struct VnodeManager {
     node_id: u64,
     meta: MetaRef,
     kv_inst: EngineRef,
+    /// Bytes per second to download the files of a vnode, 0 for unlimited
+    max_bandwidth: u64,
 }
 
 impl VnodeManager {
@@ -32,9 +43,15 @@ impl VnodeManager {
             node_id,
             meta,
             kv_inst,
+            max_bandwidth: 0,
         }
     }
 
+    pub fn with_max_bandwidth(mut self, max_bandwidth: u64) -> Self {
+        self.max_bandwidth = max_bandwidth;
+        self
+    }
+
     pub async fn move_vnode(&self, tenant: &str, vnode_id: u32) -> CoordinatorResult<()> {
         let all_info = crate::get_vnode_all_info(self.meta.clone(), tenant, vnode_id).await?;
         let db_name = all_info.db_name;
@@ -155,6 +172,123 @@ impl VnodeManager {
         Ok(())
     }
 
+    /// Backup files and summary of the vnode into `{location}/{vnode_id}/`,
+    /// tsm and delta files already in the location are not uploaded again,
+    /// objects not belonging to the vnode any more are deleted.
+    pub async fn backup_vnode(
+        &self,
+        tenant: &str,
+        db: &str,
+        vnode_id: u32,
+        object_store: Arc<dyn ObjectStore>,
+        location: &ObjectPath,
+    ) -> CoordinatorResult<()> {
+        let snapshot = self
+            .kv_inst
+            .get_vnode_snapshot(tenant, db, vnode_id)
+            .await?
+            .ok_or(CoordinatorError::VnodeNotFound { id: vnode_id })?;
+        let vnode_location = location.child(vnode_id.to_string());
+        info!(
+            "Begin backup vnode: {} to {}, {} files",
+            vnode_id,
+            vnode_location,
+            snapshot.files.len()
+        );
+
+        let mut backup_objects: HashMap<ObjectPath, usize> = object_store
+            .list(Some(&vnode_location))
+            .await?
+            .map_ok(|meta| (meta.location, meta.size))
+            .try_collect()
+            .await?;
+
+        for (path, relative_path) in snapshot.files.iter() {
+            let object = relative_path
+                .split('/')
+                .fold(vnode_location.clone(), |location, part| {
+                    location.child(part)
+                });
+            let size = tokio::fs::metadata(path).await?.len() as usize;
+            // Tsm and delta files are never modified, others may be.
+            let immutable = relative_path.ends_with(".tsm") || relative_path.ends_with(".delta");
+            if let Some(backup_size) = backup_objects.remove(&object) {
+                if immutable && backup_size == size {
+                    continue;
+                }
+            }
+            upload_object(object_store.as_ref(), path, &object).await?;
+        }
+
+        // Summary is written at last, the backup of vnode is valid only if it exists.
+        let summary_object = vnode_location.child(BACKUP_SUMMARY_NAME);
+        backup_objects.remove(&summary_object);
+        object_store
+            .put(&summary_object, snapshot.version_edit.encode()?.into())
+            .await?;
+
+        for object in backup_objects.keys() {
+            object_store.delete(object).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Restore the vnode from the backup of vnode `backup_vnode_id` in `location`,
+    /// the vnode must not exist in this node.
+    pub async fn restore_vnode(
+        &self,
+        tenant: &str,
+        db: &str,
+        vnode_id: u32,
+        backup_vnode_id: u32,
+        object_store: Arc<dyn ObjectStore>,
+        location: &ObjectPath,
+    ) -> CoordinatorResult<()> {
+        let backup_location = location.child(backup_vnode_id.to_string());
+        info!("Begin restore vnode: {} from {}", vnode_id, backup_location);
+
+        let summary_object = backup_location.child(BACKUP_SUMMARY_NAME);
+        let summary = object_store.get(&summary_object).await?.bytes().await?;
+        let ve = tskv::VersionEdit::decode(&summary)?;
+
+        let owner = models::schema::make_owner(tenant, db);
+        let path = self
+            .kv_inst
+            .get_storage_options()
+            .move_dir(&owner, vnode_id);
+        let res = async {
+            let objects: Vec<ObjectMeta> = object_store
+                .list(Some(&backup_location))
+                .await?
+                .try_collect()
+                .await?;
+            for object in objects.iter() {
+                if object.location == summary_object {
+                    continue;
+                }
+                if let Some(parts) = object.location.prefix_match(&backup_location) {
+                    let file_path = parts.fold(path.clone(), |path, part| path.join(part.as_ref()));
+                    download_object(object_store.as_ref(), &object.location, &file_path).await?;
+                }
+            }
+            // Index directory is required when applying the summary.
+            tokio::fs::create_dir_all(path.join(INDEX_PATH)).await?;
+            Ok::<(), CoordinatorError>(())
+        }
+        .await;
+        if let Err(err) = res {
+            tokio::fs::remove_dir_all(&path).await?;
+            return Err(err);
+        }
+
+        self.kv_inst
+            .apply_vnode_summary(tenant, db, vnode_id, ve)
+            .await?;
+
+        Ok(())
+    }
+
     async fn drop_vnode_remote(
         &self,
         tenant: &str,
@@ -221,14 +355,21 @@ impl VnodeManager {
         client: &mut TskvServiceClient<Timeout<Channel>>,
     ) -> CoordinatorResult<()> {
         let files_meta = self.get_vnode_files_meta(all_info, client).await?;
+        let mut throttle = Throttle::new(self.max_bandwidth);
         for info in files_meta.infos.iter() {
             let relative_filename = info
                 .name
                 .strip_prefix(&(files_meta.path.clone() + "/"))
                 .unwrap();
 
-            self.download_file(all_info, relative_filename, data_path, client)
-                .await?;
+            self.download_file(
+                all_info,
+                relative_filename,
+                data_path,
+                client,
+                &mut throttle,
+            )
+            .await?;
 
             let filename = data_path.join(relative_filename);
             let filename = filename.to_string_lossy().to_string();
@@ -270,6 +411,7 @@ impl VnodeManager {
         filename: &str,
         data_path: &Path,
         client: &mut TskvServiceClient<Timeout<Channel>>,
+        throttle: &mut Throttle,
     ) -> CoordinatorResult<()> {
         let file_path = data_path.join(filename);
         tokio::fs::create_dir_all(file_path.parent().unwrap()).await?;
@@ -305,8 +447,74 @@ impl VnodeManager {
             }
 
             file.write_all(&received.data).await?;
+            throttle.consume(received.data.len()).await;
         }
 
         Ok(())
     }
 }
+
+/// Slows down the download to at most `max_bandwidth` bytes per second on average.
+struct Throttle {
+    max_bandwidth: u64,
+    start: Instant,
+    bytes: u64,
+}
+
+impl Throttle {
+    fn new(max_bandwidth: u64) -> Self {
+        Self {
+            max_bandwidth,
+            start: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    async fn consume(&mut self, bytes: usize) {
+        if self.max_bandwidth == 0 {
+            return;
+        }
+        self.bytes += bytes as u64;
+        let expected = Duration::from_secs_f64(self.bytes as f64 / self.max_bandwidth as f64);
+        let elapsed = self.start.elapsed();
+        if expected > elapsed {
+            tokio::time::sleep(expected - elapsed).await;
+        }
+    }
+}
+
+async fn upload_object(
+    object_store: &dyn ObjectStore,
+    from: &Path,
+    to: &ObjectPath,
+) -> CoordinatorResult<()> {
+    let (multipart_id, mut writer) = object_store.put_multipart(to).await?;
+    let res = async {
+        let mut file = tokio::fs::File::open(from).await?;
+        tokio::io::copy(&mut file, &mut writer).await?;
+        writer.shutdown().await
+    }
+    .await;
+    if let Err(err) = res {
+        let _ = object_store.abort_multipart(to, &multipart_id).await;
+        return Err(err.into());
+    }
+
+    Ok(())
+}
+
+async fn download_object(
+    object_store: &dyn ObjectStore,
+    from: &ObjectPath,
+    to: &Path,
+) -> CoordinatorResult<()> {
+    tokio::fs::create_dir_all(to.parent().unwrap()).await?;
+    let mut file = tokio::fs::File::create(to).await?;
+    let mut stream = object_store.get(from).await?.into_stream();
+    while let Some(bytes) = stream.try_next().await? {
+        file.write_all(&bytes).await?;
+    }
+    file.sync_all().await?;
+
+    Ok(())
+}
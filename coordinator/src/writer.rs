@@ -2,8 +2,10 @@ use std::collections::HashMap;
 use std::time::Duration;
 
 use flatbuffers::{FlatBufferBuilder, WIPOffset};
+use futures::FutureExt;
 use meta::error::MetaError;
 use meta::model::{MetaClientRef, MetaRef};
+use models::consistency_level::ConsistencyLevel;
 use models::meta_data::*;
 use models::schema::{timestamp_convert, Precision};
 use models::utils::{now_timestamp_millis, now_timestamp_nanos};
@@ -16,7 +18,7 @@ use protos::models::{
 };
 use snafu::ResultExt;
 use tokio::sync::mpsc::Sender;
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
 use tonic::transport::Channel;
 use tonic::Code;
 use tower::timeout::Timeout;
@@ -26,7 +28,8 @@ use tskv::EngineRef;
 
 use crate::errors::*;
 use crate::hh_queue::{HintedOffBlock, HintedOffWriteReq};
-use crate::{status_response_to_result, WriteRequest};
+use crate::written_log::WrittenLogRef;
+use crate::{status_response_to_result, WriteRequest, WrittenTimeRange};
 
 pub struct VnodePoints<'a> {
     db: String,
@@ -186,6 +189,8 @@ pub struct VnodeMapping<'a> {
     /// Maps replication id to VnodePoints.
     pub points: HashMap<u32, VnodePoints<'a>>,
     pub sets: HashMap<u32, ReplicationSet>,
+    /// Maps (database, table) to the time range of the points in nanoseconds.
+    pub time_ranges: HashMap<(String, String), (i64, i64)>,
 }
 
 impl<'a> VnodeMapping<'a> {
@@ -193,9 +198,23 @@ impl<'a> VnodeMapping<'a> {
         Self {
             points: HashMap::new(),
             sets: HashMap::new(),
+            time_ranges: HashMap::new(),
         }
     }
 
+    pub fn written_time_ranges(&self, tenant: &str) -> Vec<WrittenTimeRange> {
+        self.time_ranges
+            .iter()
+            .map(|((database, table), (min_ts, max_ts))| WrittenTimeRange {
+                tenant: tenant.to_string(),
+                database: database.clone(),
+                table: table.clone(),
+                min_ts: *min_ts,
+                max_ts: *max_ts,
+            })
+            .collect()
+    }
+
     pub async fn map_point(
         &mut self,
         meta_client: MetaClientRef,
@@ -226,6 +245,14 @@ impl<'a> VnodeMapping<'a> {
             }
         }
 
+        let ts_ns = timestamp_convert(*db_precision, Precision::NS, ts).unwrap_or(ts);
+        let (min_ts, max_ts) = self
+            .time_ranges
+            .entry((db_name.to_string(), tab_name.to_string()))
+            .or_insert((ts_ns, ts_ns));
+        *min_ts = (*min_ts).min(ts_ns);
+        *max_ts = (*max_ts).max(ts_ns);
+
         let hash_id = point.hash_id_ext(tab_name, &schema)?;
 
         //let full_name = format!("{}.{}", meta_client.tenant_name(), db);
@@ -251,6 +278,17 @@ impl<'a> Default for VnodeMapping<'a> {
     }
 }
 
+/// How a replica accepted the written points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReplicaAck {
+    /// Points are written to the vnode.
+    Written,
+    /// Points are saved in the hinted handoff queue and will be written later.
+    HintedHandoff,
+}
+
+const WRITTEN_TIME_RANGE_CHANNEL_CAP: usize = 1024;
+
 #[derive(Debug)]
 pub struct PointWriter {
     node_id: u64,
@@ -258,6 +296,8 @@ pub struct PointWriter {
     kv_inst: Option<EngineRef>,
     meta_manager: MetaRef,
     hh_sender: Sender<HintedOffWriteReq>,
+    written_sender: broadcast::Sender<WrittenTimeRange>,
+    written_log: WrittenLogRef,
 }
 
 impl PointWriter {
@@ -267,16 +307,24 @@ impl PointWriter {
         kv_inst: Option<EngineRef>,
         meta_manager: MetaRef,
         hh_sender: Sender<HintedOffWriteReq>,
+        written_log: WrittenLogRef,
     ) -> Self {
+        let (written_sender, _) = broadcast::channel(WRITTEN_TIME_RANGE_CHANNEL_CAP);
         Self {
             node_id,
             kv_inst,
             timeout_ms,
             meta_manager,
             hh_sender,
+            written_sender,
+            written_log,
         }
     }
 
+    pub fn subscribe_written_time_ranges(&self) -> broadcast::Receiver<WrittenTimeRange> {
+        self.written_sender.subscribe()
+    }
+
     pub async fn write_points(
         &self,
         req: &WriteRequest,
@@ -317,13 +365,14 @@ impl PointWriter {
         let mut requests = vec![];
         {
             let _span_recorder = SpanRecorder::new(span_ctx.child_span("build requests"));
-            for (_id, points) in mapping.points.iter_mut() {
+            for (id, points) in mapping.points.iter_mut() {
                 points.finish()?;
                 if points.repl_set.vnodes.is_empty() {
                     return Err(CoordinatorError::CommonError {
                         msg: "no available vnode in replication set".to_string(),
                     });
                 }
+                let mut replica_requests = Vec::with_capacity(points.repl_set.vnodes.len());
                 for vnode in points.repl_set.vnodes.iter() {
                     debug!(
                         "Preparing write points on vnode {:?}, start at {:?}",
@@ -345,19 +394,32 @@ impl PointWriter {
                             vnode.id, vnode.node_id
                         ))),
                     );
-                    requests.push(request);
+                    replica_requests.push(request);
                 }
+                let (id, replica) = (*id, replica_requests.len());
+                let request = futures::future::join_all(replica_requests)
+                    .map(move |results| (id, replica, results));
+                requests.push(request);
             }
         }
 
-        for res in futures::future::join_all(requests).await {
+        let results = futures::future::join_all(requests).await;
+        // Points may be written to some of the replicas even if the request fails.
+        for written in mapping.written_time_ranges(&req.tenant) {
+            // Err means there is no subscriber
+            let _ = self.written_sender.send(written);
+        }
+
+        for (id, replica, results) in results {
             debug!(
-                "Parallel write points on vnode over, start at: {:?}, elapsed: {} millis, result: {:?}",
+                "Parallel write points on replication set {} of {} replicas over, start at: {:?}, elapsed: {} millis, result: {:?}",
+                id,
+                replica,
                 now,
                 now.elapsed().as_millis(),
-                res
+                results
             );
-            res?
+            check_write_consistency(req.level, id, results)?;
         }
 
         Ok(())
@@ -371,7 +433,7 @@ impl PointWriter {
         precision: Precision,
         data: Vec<u8>,
         span_recorder: SpanRecorder,
-    ) -> CoordinatorResult<()> {
+    ) -> CoordinatorResult<ReplicaAck> {
         if node_id == self.node_id && self.kv_inst.is_some() {
             let span_recorder = span_recorder.child("write to local node");
 
@@ -380,7 +442,7 @@ impl PointWriter {
                 .await;
             debug!("write data to local {}({}) {:?}", node_id, vnode_id, result);
 
-            return result;
+            return result.map(|_| ReplicaAck::Written);
         }
 
         let mut span_recorder = span_recorder.child("write to remote node");
@@ -414,7 +476,8 @@ impl PointWriter {
 
                 return self
                     .write_to_handoff(vnode_id, node_id, tenant, precision, data)
-                    .await;
+                    .await
+                    .map(|_| ReplicaAck::HintedHandoff);
             }
         }
 
@@ -426,7 +489,7 @@ impl PointWriter {
             result
         );
 
-        result
+        result.map(|_| ReplicaAck::Written)
     }
 
     async fn write_to_handoff(
@@ -532,10 +595,107 @@ impl PointWriter {
         };
 
         if let Some(kv_inst) = self.kv_inst.clone() {
-            let _ = kv_inst.write(span_ctx, vnode_id, precision, req).await?;
+            let result = kv_inst.write(span_ctx, vnode_id, precision, req).await;
+            // Points may be written even if the write fails.
+            self.written_log.record_points(tenant, precision, &data)?;
+            let _ = result?;
             Ok(())
         } else {
             Err(CoordinatorError::KvInstanceNotFound { node_id: 0 })
         }
     }
 }
+
+/// Check if enough replicas of the replication set accepted the write
+/// to satisfy the consistency level.
+fn check_write_consistency(
+    level: ConsistencyLevel,
+    id: u32,
+    results: Vec<CoordinatorResult<ReplicaAck>>,
+) -> CoordinatorResult<()> {
+    let replica = results.len();
+    let required = level.required_acks(replica);
+    let (mut acked, mut accepted) = (0, 0);
+    let mut last_error = None;
+    for res in results {
+        match res {
+            Ok(ReplicaAck::Written) => {
+                acked += 1;
+                accepted += 1;
+            }
+            Ok(ReplicaAck::HintedHandoff) => accepted += 1,
+            Err(err) => last_error = Some(err),
+        }
+    }
+
+    if acked >= required && accepted > 0 {
+        return Ok(());
+    }
+
+    match last_error {
+        // No replica accepted the write, return the original error.
+        Some(err) if accepted == 0 => Err(err),
+        err => Err(CoordinatorError::WriteConsistencyNotMet {
+            id,
+            level,
+            replica,
+            required,
+            acked,
+            error: err
+                .map(|e| e.to_string())
+                .unwrap_or_else(|| "other replicas are in hinted handoff".to_string()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use models::consistency_level::ConsistencyLevel;
+
+    use super::{check_write_consistency, ReplicaAck};
+    use crate::errors::CoordinatorError;
+
+    fn failover() -> CoordinatorError {
+        CoordinatorError::FailoverNode {
+            id: 1,
+            error: "connection refused".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check_write_consistency() {
+        use ReplicaAck::*;
+
+        let results = || vec![Ok(Written), Ok(HintedHandoff), Err(failover())];
+        assert!(check_write_consistency(ConsistencyLevel::Any, 1, results()).is_ok());
+        assert!(check_write_consistency(ConsistencyLevel::One, 1, results()).is_ok());
+        assert!(matches!(
+            check_write_consistency(ConsistencyLevel::Quorum, 1, results()),
+            Err(CoordinatorError::WriteConsistencyNotMet {
+                required: 2,
+                acked: 1,
+                ..
+            })
+        ));
+
+        let results = || vec![Ok(Written), Ok(Written), Ok(HintedHandoff)];
+        assert!(check_write_consistency(ConsistencyLevel::Quorum, 1, results()).is_ok());
+        assert!(matches!(
+            check_write_consistency(ConsistencyLevel::All, 1, results()),
+            Err(CoordinatorError::WriteConsistencyNotMet { .. })
+        ));
+
+        let results = || vec![Ok(HintedHandoff)];
+        assert!(check_write_consistency(ConsistencyLevel::Any, 1, results()).is_ok());
+        assert!(matches!(
+            check_write_consistency(ConsistencyLevel::One, 1, results()),
+            Err(CoordinatorError::WriteConsistencyNotMet { .. })
+        ));
+
+        let results = vec![Err(failover()), Err(failover())];
+        assert!(matches!(
+            check_write_consistency(ConsistencyLevel::Any, 1, results),
+            Err(CoordinatorError::FailoverNode { .. })
+        ));
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, Int64Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use models::schema::{timestamp_convert, Precision};
+use models::utils::now_timestamp_nanos;
+use parking_lot::Mutex;
+use protos::models as fb_models;
+use snafu::ResultExt;
+
+use crate::errors::{CoordinatorError, CoordinatorResult, InvalidFlatbufferSnafu};
+use crate::WrittenTimeRange;
+
+/// Max number of batches kept in the log, the nodes fetching the batches dropped from
+/// the log are told to reset.
+const MAX_BATCHES: usize = 4096;
+
+const SEQ_METADATA_KEY: &str = "seq";
+const RESET_METADATA_KEY: &str = "reset";
+
+/// A change of the data in the vnodes of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WrittenEvent {
+    /// Points written to or deleted from the time range of a table
+    Written(WrittenTimeRange),
+    /// Any data of the tenant may be changed, e.g. a database is dropped
+    TenantChanged(String),
+}
+
+/// The written events fetched since a sequence of a `WrittenLog`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenLogDelta {
+    /// Sequence to fetch the next events since
+    pub seq: u64,
+    /// The events since the sequence are not all kept, e.g. the node is restarted,
+    /// any data of the node may be changed
+    pub reset: bool,
+    pub events: Vec<WrittenEvent>,
+}
+
+/// Records the time ranges written into the vnodes of this node, whichever node
+/// coordinated the writes, so the query nodes can fetch them to invalidate their
+/// query result caches.
+///
+/// Events are merged by table into an open batch, the batch is closed with the next
+/// sequence when the log is fetched. The sequences start from the time the log is
+/// created in nanoseconds, so the sequences fetched before a restart are not valid
+/// after it.
+#[derive(Debug)]
+pub struct WrittenLog {
+    inner: Mutex<WrittenLogInner>,
+}
+
+pub type WrittenLogRef = Arc<WrittenLog>;
+
+#[derive(Debug)]
+struct WrittenLogInner {
+    /// Sequence of the last closed batch
+    seq: u64,
+    /// Sequence of the batch before the oldest kept batch
+    base_seq: u64,
+    /// (tenant, database, table) -> (min_ts, max_ts)
+    open_ranges: BTreeMap<(String, String, String), (i64, i64)>,
+    open_tenants: BTreeSet<String>,
+    batches: VecDeque<(u64, Vec<WrittenEvent>)>,
+}
+
+impl WrittenLog {
+    pub fn new() -> Self {
+        let seq = now_timestamp_nanos() as u64;
+        Self {
+            inner: Mutex::new(WrittenLogInner {
+                seq,
+                base_seq: seq,
+                open_ranges: BTreeMap::new(),
+                open_tenants: BTreeSet::new(),
+                batches: VecDeque::new(),
+            }),
+        }
+    }
+
+    pub fn record(&self, event: WrittenEvent) {
+        let mut inner = self.inner.lock();
+        match event {
+            WrittenEvent::Written(written) => {
+                let (min_ts, max_ts) = inner
+                    .open_ranges
+                    .entry((written.tenant, written.database, written.table))
+                    .or_insert((written.min_ts, written.max_ts));
+                *min_ts = (*min_ts).min(written.min_ts);
+                *max_ts = (*max_ts).max(written.max_ts);
+            }
+            WrittenEvent::TenantChanged(tenant) => {
+                inner.open_tenants.insert(tenant);
+            }
+        }
+    }
+
+    /// Record the time ranges of the points written into a vnode, the timestamps of
+    /// the points are in the precision.
+    pub fn record_points(
+        &self,
+        tenant: &str,
+        precision: Precision,
+        points: &[u8],
+    ) -> CoordinatorResult<()> {
+        let fb_points =
+            flatbuffers::root::<fb_models::Points>(points).context(InvalidFlatbufferSnafu)?;
+        let database = fb_points.db_ext()?;
+        for table in fb_points.tables_iter_ext()? {
+            let mut range: Option<(i64, i64)> = None;
+            for point in table.points_iter_ext()? {
+                let ts = point.timestamp();
+                let ts = timestamp_convert(precision, Precision::NS, ts).unwrap_or(ts);
+                range = Some(range.map_or((ts, ts), |(min_ts, max_ts)| {
+                    (min_ts.min(ts), max_ts.max(ts))
+                }));
+            }
+            if let Some((min_ts, max_ts)) = range {
+                self.record(WrittenEvent::Written(WrittenTimeRange {
+                    tenant: tenant.to_string(),
+                    database: database.to_string(),
+                    table: table.tab_ext()?.to_string(),
+                    min_ts,
+                    max_ts,
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record that all the data of the table may be changed, e.g. the table is dropped.
+    pub fn record_table(&self, tenant: &str, database: &str, table: &str) {
+        self.record(WrittenEvent::Written(WrittenTimeRange {
+            tenant: tenant.to_string(),
+            database: database.to_string(),
+            table: table.to_string(),
+            min_ts: i64::MIN,
+            max_ts: i64::MAX,
+        }));
+    }
+
+    /// Returns the events after the sequence, 0 if no sequence is fetched yet.
+    pub fn since(&self, seq: u64) -> WrittenLogDelta {
+        let mut inner = self.inner.lock();
+        inner.close_batch();
+
+        if seq < inner.base_seq || seq > inner.seq {
+            return WrittenLogDelta {
+                seq: inner.seq,
+                reset: true,
+                events: vec![],
+            };
+        }
+
+        let events = inner
+            .batches
+            .iter()
+            .filter(|(batch_seq, _)| *batch_seq > seq)
+            .flat_map(|(_, events)| events.iter().cloned())
+            .collect();
+        WrittenLogDelta {
+            seq: inner.seq,
+            reset: false,
+            events,
+        }
+    }
+}
+
+impl Default for WrittenLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WrittenLogInner {
+    fn close_batch(&mut self) {
+        if self.open_ranges.is_empty() && self.open_tenants.is_empty() {
+            return;
+        }
+
+        let mut events = Vec::with_capacity(self.open_ranges.len() + self.open_tenants.len());
+        for tenant in std::mem::take(&mut self.open_tenants) {
+            events.push(WrittenEvent::TenantChanged(tenant));
+        }
+        for ((tenant, database, table), (min_ts, max_ts)) in std::mem::take(&mut self.open_ranges) {
+            events.push(WrittenEvent::Written(WrittenTimeRange {
+                tenant,
+                database,
+                table,
+                min_ts,
+                max_ts,
+            }));
+        }
+
+        self.seq += 1;
+        self.batches.push_back((self.seq, events));
+        while self.batches.len() > MAX_BATCHES {
+            if let Some((seq, _)) = self.batches.pop_front() {
+                self.base_seq = seq;
+            }
+        }
+    }
+}
+
+fn written_log_schema(seq: u64, reset: bool) -> SchemaRef {
+    let metadata = HashMap::from([
+        (SEQ_METADATA_KEY.to_string(), seq.to_string()),
+        (RESET_METADATA_KEY.to_string(), reset.to_string()),
+    ]);
+    Arc::new(
+        Schema::new(vec![
+            Field::new("TENANT", DataType::Utf8, false),
+            Field::new("DATABASE", DataType::Utf8, true),
+            Field::new("TABLE", DataType::Utf8, true),
+            Field::new("MIN_TS", DataType::Int64, false),
+            Field::new("MAX_TS", DataType::Int64, false),
+        ])
+        .with_metadata(metadata),
+    )
+}
+
+impl WrittenLogDelta {
+    /// Encode the delta into a record batch with a row for each event, a `TenantChanged`
+    /// event has null `DATABASE` and `TABLE`.
+    pub fn to_record_batch(&self) -> CoordinatorResult<RecordBatch> {
+        let mut tenants = Vec::with_capacity(self.events.len());
+        let mut databases = Vec::with_capacity(self.events.len());
+        let mut tables = Vec::with_capacity(self.events.len());
+        let mut min_times = Vec::with_capacity(self.events.len());
+        let mut max_times = Vec::with_capacity(self.events.len());
+        for event in self.events.iter() {
+            match event {
+                WrittenEvent::Written(written) => {
+                    tenants.push(written.tenant.as_str());
+                    databases.push(Some(written.database.as_str()));
+                    tables.push(Some(written.table.as_str()));
+                    min_times.push(written.min_ts);
+                    max_times.push(written.max_ts);
+                }
+                WrittenEvent::TenantChanged(tenant) => {
+                    tenants.push(tenant.as_str());
+                    databases.push(None);
+                    tables.push(None);
+                    min_times.push(i64::MIN);
+                    max_times.push(i64::MAX);
+                }
+            }
+        }
+
+        RecordBatch::try_new(
+            written_log_schema(self.seq, self.reset),
+            vec![
+                Arc::new(StringArray::from(tenants)),
+                Arc::new(StringArray::from(databases)),
+                Arc::new(StringArray::from(tables)),
+                Arc::new(Int64Array::from(min_times)),
+                Arc::new(Int64Array::from(max_times)),
+            ],
+        )
+        .map_err(|source| CoordinatorError::ArrowError { source })
+    }
+
+    pub fn from_record_batch(batch: &RecordBatch) -> CoordinatorResult<Self> {
+        let schema = batch.schema();
+        let metadata = |key: &str| {
+            schema
+                .metadata()
+                .get(key)
+                .ok_or_else(|| CoordinatorError::CommonError {
+                    msg: format!("missing {key} of the written log"),
+                })
+        };
+        let seq = metadata(SEQ_METADATA_KEY)?.parse::<u64>().map_err(|err| {
+            CoordinatorError::CommonError {
+                msg: format!("invalid seq of the written log: {err}"),
+            }
+        })?;
+        let reset = metadata(RESET_METADATA_KEY)? == "true";
+
+        let tenants = column::<StringArray>(batch, "TENANT")?;
+        let databases = column::<StringArray>(batch, "DATABASE")?;
+        let tables = column::<StringArray>(batch, "TABLE")?;
+        let min_times = column::<Int64Array>(batch, "MIN_TS")?;
+        let max_times = column::<Int64Array>(batch, "MAX_TS")?;
+        let mut events = Vec::with_capacity(batch.num_rows());
+        for row in 0..batch.num_rows() {
+            let tenant = tenants.value(row).to_string();
+            if databases.is_null(row) || tables.is_null(row) {
+                events.push(WrittenEvent::TenantChanged(tenant));
+            } else {
+                events.push(WrittenEvent::Written(WrittenTimeRange {
+                    tenant,
+                    database: databases.value(row).to_string(),
+                    table: tables.value(row).to_string(),
+                    min_ts: min_times.value(row),
+                    max_ts: max_times.value(row),
+                }));
+            }
+        }
+
+        Ok(Self { seq, reset, events })
+    }
+}
+
+fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> CoordinatorResult<&'a T> {
+    batch
+        .column_by_name(name)
+        .and_then(|array| array.as_any().downcast_ref::<T>())
+        .ok_or_else(|| CoordinatorError::CommonError {
+            msg: format!("invalid column {name} of the written log"),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use models::record_batch_decode;
+
+    use super::*;
+
+    fn written(table: &str, min_ts: i64, max_ts: i64) -> WrittenEvent {
+        WrittenEvent::Written(WrittenTimeRange {
+            tenant: "cnosdb".to_string(),
+            database: "public".to_string(),
+            table: table.to_string(),
+            min_ts,
+            max_ts,
+        })
+    }
+
+    #[test]
+    fn test_written_log_since() {
+        let log = WrittenLog::new();
+        let first = log.since(0);
+        assert!(first.reset);
+        assert!(first.events.is_empty());
+
+        log.record(written("air", 10, 20));
+        log.record(written("air", 5, 15));
+        log.record(written("sea", 30, 30));
+        let delta = log.since(first.seq);
+        assert!(!delta.reset);
+        assert_eq!(delta.seq, first.seq + 1);
+        assert_eq!(
+            delta.events,
+            vec![written("air", 5, 20), written("sea", 30, 30)]
+        );
+
+        log.record(WrittenEvent::TenantChanged("cnosdb".to_string()));
+        let next = log.since(delta.seq);
+        assert_eq!(
+            next.events,
+            vec![WrittenEvent::TenantChanged("cnosdb".to_string())]
+        );
+        // Fetching since an older sequence returns the batches after it.
+        assert_eq!(log.since(first.seq).events.len(), 3);
+        // Nothing is written since the last fetch.
+        let empty = log.since(next.seq);
+        assert_eq!(empty.seq, next.seq);
+        assert!(!empty.reset && empty.events.is_empty());
+        // Sequences from the future, e.g. fetched before a restart.
+        assert!(log.since(next.seq + 1).reset);
+    }
+
+    #[test]
+    fn test_written_log_dropped_batches() {
+        let log = WrittenLog::new();
+        let first = log.since(0).seq;
+        for ts in 0..=MAX_BATCHES as i64 {
+            log.record(written("air", ts, ts));
+            log.since(first);
+        }
+        assert!(log.since(first).reset);
+        let last = log.since(first + 1);
+        assert!(!last.reset);
+        assert_eq!(last.events.len(), MAX_BATCHES);
+    }
+
+    #[test]
+    fn test_written_log_delta_encode() {
+        let delta = WrittenLogDelta {
+            seq: 42,
+            reset: false,
+            events: vec![
+                written("air", 1, 2),
+                WrittenEvent::TenantChanged("cnosdb".to_string()),
+            ],
+        };
+        let bytes = models::record_batch_encode(&delta.to_record_batch().unwrap()).unwrap();
+        let batch = record_batch_decode(&bytes).unwrap();
+        assert_eq!(WrittenLogDelta::from_record_batch(&batch).unwrap(), delta);
+    }
+}
@@ -1,8 +1,15 @@
 #[cfg(test)]
 pub mod test {
-    use http_protocol::header::{HeaderValue, ACCEPT, CONTENT_TYPE};
+    use std::io::{Read, Write};
+
+    use flate2::read::GzDecoder;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use http_protocol::header::{
+        HeaderValue, ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, GZIP,
+    };
     use http_protocol::http_client::HttpClient;
-    use http_protocol::response::Response;
+    use http_protocol::response::{PartialWriteResponse, RejectReason, Response};
     use http_protocol::status_code;
 
     pub fn client() -> HttpClient {
@@ -333,6 +340,114 @@ pub mod test {
         assert_eq!(resp.status(), status_code::METHOD_NOT_ALLOWED);
     }
 
+    #[tokio::test]
+    async fn test_v1_partial_write() {
+        let path = "/api/v1/write";
+        let param = &[("db", "public"), ("partial_write", "true")];
+        let username = "root";
+
+        let client = client();
+
+        let body = "test_v1_partial_write,ta=a1 fa=1\n\
+            test_v1_partial_write,ta=a1 fa=\n\
+            test_v1_partial_write,ta=a2 fa=\"x\"\n\
+            test_v1_partial_write,ta=a3 fa=2";
+
+        let resp: Response = client
+            .post(path)
+            .query(param)
+            .basic_auth::<&str, &str>(username, None)
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status_code::OK);
+
+        let resp: PartialWriteResponse = resp.json().await.unwrap();
+        assert_eq!(resp.written, 2);
+        let rejected = resp
+            .rejected
+            .iter()
+            .map(|r| (r.line, r.reason))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rejected,
+            vec![
+                (2, RejectReason::ParseError),
+                (3, RejectReason::TypeConflict)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_v1_encoded_bodies() {
+        let client = client();
+
+        execute_sql(&client, "drop table if exists test_v1_encoded_bodies").await;
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder
+            .write_all(
+                b"test_v1_encoded_bodies,ta=a1 fa=1 1\n\
+                test_v1_encoded_bodies,ta=a2 fa=2 2",
+            )
+            .unwrap();
+        let body = encoder.finish().unwrap();
+
+        let resp: Response = client
+            .post("/api/v1/write")
+            .query(&[("db", "public")])
+            .basic_auth::<&str, &str>("root", None)
+            .header(CONTENT_ENCODING, GZIP)
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status_code::OK);
+
+        // a body that isn't gzip
+        let resp: Response = client
+            .post("/api/v1/write")
+            .query(&[("db", "public")])
+            .basic_auth::<&str, &str>("root", None)
+            .header(CONTENT_ENCODING, GZIP)
+            .body("test_v1_encoded_bodies,ta=a3 fa=3 3")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status_code::BAD_REQUEST);
+
+        let resp: Response = client
+            .post("/api/v1/sql")
+            .query(&[("db", "public")])
+            .basic_auth::<&str, &str>("root", None)
+            .header(ACCEPT, "application/csv")
+            .header(ACCEPT_ENCODING, GZIP)
+            .body("select time, ta, fa from test_v1_encoded_bodies order by time")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status_code::OK);
+        assert_eq!(
+            resp.headers().get(CONTENT_ENCODING),
+            Some(&HeaderValue::from_static(GZIP))
+        );
+
+        let body = resp.bytes().await.unwrap();
+        let mut text = String::new();
+        GzDecoder::new(body.as_ref())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(
+            text,
+            "time,ta,fa\n\
+            1970-01-01T00:00:00.000000001,a1,1.0\n\
+            1970-01-01T00:00:00.000000002,a2,2.0\n"
+        );
+
+        execute_sql(&client, "drop table if exists test_v1_encoded_bodies").await;
+    }
+
     #[tokio::test]
     async fn test_v1_ping_path() {
         let path = "/api/v1/ping";
@@ -357,4 +472,126 @@ pub mod test {
         let resp: Response = client.delete(path).send().await.unwrap();
         assert_eq!(resp.status(), status_code::METHOD_NOT_ALLOWED);
     }
+
+    async fn execute_sql(client: &HttpClient, sql: &str) -> String {
+        let resp: Response = client
+            .post("/api/v1/sql")
+            .query(&[("db", "public")])
+            .basic_auth::<&str, &str>("root", None)
+            .body(sql.to_string())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status_code::OK, "{sql}");
+        resp.text().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_continuous_query_writes_rollups() {
+        let client = client();
+
+        execute_sql(
+            &client,
+            "drop continuous query if exists test_cq_rollup on public",
+        )
+        .await;
+        execute_sql(&client, "drop table if exists test_cq_source").await;
+        execute_sql(&client, "drop table if exists test_cq_rollup").await;
+        execute_sql(&client, "create table test_cq_rollup(f0 double, tags(t0))").await;
+
+        // Two points in the same 10s window, which closed 20s ago
+        const WINDOW: i64 = 10_000_000_000;
+        let now = chrono::Utc::now().timestamp_nanos();
+        let window = now - now.rem_euclid(WINDOW) - 3 * WINDOW;
+        let body = format!(
+            "test_cq_source,t0=a f0=1i {}\ntest_cq_source,t0=a f0=3i {}",
+            window + 1_000_000_000,
+            window + 2_000_000_000
+        );
+        let resp: Response = client
+            .post("/api/v1/write")
+            .query(&[("db", "public")])
+            .basic_auth::<&str, &str>("root", None)
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status_code::OK);
+
+        execute_sql(
+            &client,
+            "create continuous query test_cq_rollup on public resample every 10s for 1m as \
+            select date_bin(interval '10 seconds', time) as time, t0, avg(f0) as f0 \
+            into test_cq_rollup from test_cq_source group by 1, t0",
+        )
+        .await;
+
+        let expected = format!("ts,t0,f0\n{window},a,2.0\n");
+        let mut result = String::new();
+        for _ in 0..30 {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            result = execute_sql(
+                &client,
+                "select cast(time as bigint) as ts, t0, f0 from test_cq_rollup",
+            )
+            .await;
+            if result == expected {
+                break;
+            }
+        }
+        assert_eq!(result, expected);
+
+        execute_sql(&client, "drop continuous query test_cq_rollup on public").await;
+        execute_sql(&client, "drop table test_cq_source").await;
+        execute_sql(&client, "drop table test_cq_rollup").await;
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore_database() {
+        let client = client();
+
+        execute_sql(&client, "drop database if exists test_backup_src").await;
+        execute_sql(&client, "drop database if exists test_backup_dst").await;
+        execute_sql(&client, "create database test_backup_src").await;
+        execute_sql(
+            &client,
+            "create table test_backup_src.test_backup(f0 bigint, tags(t0))",
+        )
+        .await;
+        execute_sql(
+            &client,
+            "insert into test_backup_src.test_backup(time, t0, f0) \
+            values (1, 'a', 1), (2, 'b', 2), (3, 'a', 3)",
+        )
+        .await;
+
+        let location = format!(
+            "file:///tmp/cnosdb_e2e_backup_{}",
+            chrono::Utc::now().timestamp_nanos()
+        );
+        execute_sql(
+            &client,
+            &format!("backup database test_backup_src to '{location}'"),
+        )
+        .await;
+        execute_sql(
+            &client,
+            &format!("restore database test_backup_dst from '{location}'"),
+        )
+        .await;
+
+        let mut results = vec![];
+        for db in ["test_backup_src", "test_backup_dst"] {
+            let sql = format!(
+                "select cast(time as bigint) as ts, t0, f0 from {db}.test_backup order by ts"
+            );
+            results.push(execute_sql(&client, &sql).await);
+        }
+        let (src, dst) = (&results[0], &results[1]);
+        assert_eq!(src, "ts,t0,f0\n1,a,1\n2,b,2\n3,a,3\n");
+        assert_eq!(dst, src);
+
+        execute_sql(&client, "drop database test_backup_src").await;
+        execute_sql(&client, "drop database test_backup_dst").await;
+    }
 }
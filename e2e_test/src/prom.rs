@@ -1,11 +1,15 @@
 #[cfg(test)]
 mod test {
+    use http_protocol::header::{APPLICATION_STREAMED_PROTOBUF_CHUNKS, CONTENT_TYPE};
     use http_protocol::response::Response;
     use http_protocol::status_code;
     use models::snappy::SnappyCodec;
     use protobuf::Message;
     use protos::models_helper::{parse_proto_bytes, to_proto_bytes};
-    use protos::prompb::remote::{Query, QueryResult, ReadRequest, ReadResponse, WriteRequest};
+    use protos::prompb::remote::read_request::ResponseType;
+    use protos::prompb::remote::{
+        ChunkedReadResponse, Query, QueryResult, ReadRequest, ReadResponse, WriteRequest,
+    };
     use protos::prompb::types::{label_matcher, Label, LabelMatcher, Sample, TimeSeries};
 
     use crate::http_api_tests::test::client;
@@ -13,6 +17,9 @@ mod test {
     const SQL_PATH: &str = "/api/v1/sql";
     const PROM_WRITE_PATH: &str = "/api/v1/prom/write";
     const PROM_READ_PATH: &str = "/api/v1/prom/read";
+    const PROM_QUERY_PATH: &str = "/api/v1/query";
+    const PROM_SERIES_PATH: &str = "/api/v1/series";
+    const PROM_LABELS_PATH: &str = "/api/v1/labels";
 
     fn serialize<T: Message>(msg: T) -> Vec<u8> {
         let mut compressed = Vec::new();
@@ -67,13 +74,7 @@ mod test {
         serialize(write_request)
     }
 
-    fn test_read_req() -> Vec<u8> {
-        let table_name_label_matcher = LabelMatcher {
-            type_: label_matcher::Type::EQ.into(),
-            name: "__name__".to_string(),
-            value: "test_prom".to_string(),
-            ..Default::default()
-        };
+    fn test_read_req(table_name_label_matcher: LabelMatcher) -> Vec<u8> {
         let label_matcher = LabelMatcher {
             type_: label_matcher::Type::EQ.into(),
             name: "tag1".to_string(),
@@ -151,21 +152,322 @@ mod test {
             .unwrap();
         assert_eq!(resp.status(), status_code::OK);
 
-        // read data
-        let body = test_read_req();
+        // read data by the metric name, or by a regex matching it
+        let matchers = [
+            (label_matcher::Type::EQ, "test_prom"),
+            (label_matcher::Type::RE, "test_p.*"),
+        ];
+        for (type_, value) in matchers {
+            let body = test_read_req(LabelMatcher {
+                type_: type_.into(),
+                name: "__name__".to_string(),
+                value: value.to_string(),
+                ..Default::default()
+            });
+            let resp: Response = client
+                .post(PROM_READ_PATH)
+                .query(param)
+                .basic_auth::<&str, &str>(username, None)
+                .body(body)
+                .send()
+                .await
+                .unwrap();
+
+            assert_eq!(resp.status(), status_code::OK);
+
+            let resp: ReadResponse = deserialize(&resp.bytes().await.unwrap());
+
+            assert_eq!(resp, test_read_resp());
+        }
+
+        // instant query by PromQL
+        let resp: Response = client
+            .get(PROM_QUERY_PATH)
+            .query(&[
+                ("db", "public"),
+                ("query", "test_prom{tag1=\"todo!()\"}"),
+                ("time", "1686819777"),
+            ])
+            .basic_auth::<&str, &str>(username, None)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status_code::OK);
+        assert_eq!(
+            resp.text().await.unwrap(),
+            r#"{"status":"success","data":{"resultType":"vector","result":[{"metric":{"__name__":"test_prom","tag1":"todo!()"},"value":[1686819777.0,"1.1"]}]}}"#
+        );
+
+        // the series, label names and label values of the metric
+        let metadata = [
+            (
+                PROM_SERIES_PATH.to_string(),
+                r#"{"status":"success","data":[{"__name__":"test_prom","tag1":"todo!()"}]}"#,
+            ),
+            (
+                PROM_LABELS_PATH.to_string(),
+                r#"{"status":"success","data":["__name__","tag1"]}"#,
+            ),
+            (
+                "/api/v1/label/tag1/values".to_string(),
+                r#"{"status":"success","data":["todo!()"]}"#,
+            ),
+        ];
+        for (path, expected) in metadata {
+            let resp: Response = client
+                .get(&path)
+                .query(&[("db", "public"), ("match[]", "test_prom")])
+                .basic_auth::<&str, &str>(username, None)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), status_code::OK);
+            assert_eq!(resp.text().await.unwrap(), expected);
+        }
+
+        // the selectors of the series are required
+        let resp: Response = client
+            .get(PROM_SERIES_PATH)
+            .query(&[("db", "public")])
+            .basic_auth::<&str, &str>(username, None)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status_code::BAD_REQUEST);
+
+        // a syntax error is bad data
+        let resp: Response = client
+            .get(PROM_QUERY_PATH)
+            .query(&[("db", "public"), ("query", "sum(")])
+            .basic_auth::<&str, &str>(username, None)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status_code::BAD_REQUEST);
+    }
+
+    /// CRC-32 with the Castagnoli polynomial, which prometheus checks the frames with.
+    fn crc32c(data: &[u8]) -> u32 {
+        !data.iter().fold(!0_u32, |mut crc, b| {
+            crc ^= *b as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0x82f6_3b78
+                } else {
+                    crc >> 1
+                };
+            }
+            crc
+        })
+    }
+
+    /// Reads the frames of `uvarint(len) | big endian crc32c(message) | message`.
+    fn read_frames(mut body: &[u8]) -> Vec<ChunkedReadResponse> {
+        let mut responses = vec![];
+        while !body.is_empty() {
+            let mut len = 0_usize;
+            for shift in (0..).step_by(7) {
+                let b = body[0];
+                body = &body[1..];
+                len |= ((b & 0x7f) as usize) << shift;
+                if b < 0x80 {
+                    break;
+                }
+            }
+            let (crc, rest) = body.split_at(4);
+            let (message, rest) = rest.split_at(len);
+            assert_eq!(crc, crc32c(message).to_be_bytes());
+            responses.push(parse_proto_bytes::<ChunkedReadResponse>(message).unwrap());
+            body = rest;
+        }
+        responses
+    }
+
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+    }
+
+    impl BitReader<'_> {
+        fn read_bit(&mut self) -> bool {
+            let bit = self.bytes[self.pos / 8] >> (7 - self.pos % 8) & 1 == 1;
+            self.pos += 1;
+            bit
+        }
+
+        fn read_bits(&mut self, nbits: u32) -> u64 {
+            (0..nbits).fold(0, |v, _| v << 1 | self.read_bit() as u64)
+        }
+
+        fn read_uvarint(&mut self) -> u64 {
+            let mut value = 0;
+            for shift in (0..).step_by(7) {
+                let b = self.read_bits(8);
+                value |= (b & 0x7f) << shift;
+                if b < 0x80 {
+                    break;
+                }
+            }
+            value
+        }
+    }
+
+    /// Decodes the XOR chunk like the prometheus tsdb.
+    fn decode_xor_chunk(data: &[u8]) -> Vec<(i64, f64)> {
+        let num = u16::from_be_bytes([data[0], data[1]]) as usize;
+        let mut r = BitReader {
+            bytes: &data[2..],
+            pos: 0,
+        };
+        let mut samples = vec![];
+        let (mut t, mut v, mut t_delta) = (0_i64, 0_u64, 0_i64);
+        let (mut leading, mut trailing) = (0_u32, 0_u32);
+        for i in 0..num {
+            match i {
+                0 => {
+                    let u = r.read_uvarint();
+                    t = (u >> 1) as i64 ^ -((u & 1) as i64);
+                    v = r.read_bits(64);
+                    samples.push((t, f64::from_bits(v)));
+                    continue;
+                }
+                1 => t_delta = r.read_uvarint() as i64,
+                _ => {
+                    let mut prefix = 0;
+                    while prefix < 4 && r.read_bit() {
+                        prefix += 1;
+                    }
+                    let dod = match prefix {
+                        0 => 0,
+                        _ => {
+                            let nbits = [14, 17, 20, 64][prefix - 1];
+                            let bits = r.read_bits(nbits);
+                            if nbits < 64 && bits > 1 << (nbits - 1) {
+                                bits as i64 - (1 << nbits)
+                            } else {
+                                bits as i64
+                            }
+                        }
+                    };
+                    t_delta += dod;
+                }
+            }
+            t += t_delta;
+            if r.read_bit() {
+                if r.read_bit() {
+                    leading = r.read_bits(5) as u32;
+                    let sig = match r.read_bits(6) as u32 {
+                        0 => 64,
+                        sig => sig,
+                    };
+                    trailing = 64 - leading - sig;
+                }
+                v ^= r.read_bits(64 - leading - trailing) << trailing;
+            }
+            samples.push((t, f64::from_bits(v)));
+        }
+        samples
+    }
+
+    #[tokio::test]
+    async fn test_prom_streamed_xor_chunks() {
+        let param = &[("db", "public")];
+        let username = "root";
+
+        let client = client();
+
+        // The series {tag1="a"} has a null tag2 and an empty tag2
+        let statements = [
+            "drop table if exists test_prom_chunks;",
+            "create table test_prom_chunks(value double, tags(tag1, tag2));",
+            "insert into test_prom_chunks(time, tag1, value) values \
+                (1686819776000000000, 'a', 1.0), (1686819778000000000, 'a', 3.0);",
+            "insert into test_prom_chunks(time, tag1, tag2, value) values \
+                (1686819777000000000, 'a', '', 2.0), (1686819776500000000, 'a', 'b', 4.0);",
+        ];
+        for sql in statements {
+            let resp: Response = client
+                .post(SQL_PATH)
+                .query(param)
+                .basic_auth::<&str, &str>(username, None)
+                .body(sql)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), status_code::OK, "{sql}");
+        }
+
+        let query = Query {
+            start_timestamp_ms: 1686819776000,
+            end_timestamp_ms: 1686819779000,
+            matchers: vec![LabelMatcher {
+                type_: label_matcher::Type::EQ.into(),
+                name: "__name__".to_string(),
+                value: "test_prom_chunks".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let read_req = ReadRequest {
+            queries: vec![query],
+            accepted_response_types: vec![ResponseType::STREAMED_XOR_CHUNKS.into()],
+            ..Default::default()
+        };
         let resp: Response = client
             .post(PROM_READ_PATH)
             .query(param)
             .basic_auth::<&str, &str>(username, None)
-            .body(body)
+            .body(serialize(read_req))
             .send()
             .await
             .unwrap();
-
         assert_eq!(resp.status(), status_code::OK);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            APPLICATION_STREAMED_PROTOBUF_CHUNKS
+        );
 
-        let resp: ReadResponse = deserialize(&resp.bytes().await.unwrap());
-
-        assert_eq!(resp, test_read_resp());
+        let body = resp.bytes().await.unwrap();
+        let mut series = vec![];
+        for frame in read_frames(&body) {
+            assert_eq!(frame.query_index, 0);
+            for chunked_series in frame.chunked_series {
+                let labels = chunked_series
+                    .labels
+                    .iter()
+                    .map(|l| format!("{}={}", l.name, l.value))
+                    .collect::<Vec<_>>();
+                let samples = chunked_series
+                    .chunks
+                    .iter()
+                    .flat_map(|c| decode_xor_chunk(&c.data))
+                    .collect::<Vec<_>>();
+                series.push((labels, samples));
+            }
+        }
+        assert_eq!(
+            series,
+            vec![
+                (
+                    vec![
+                        "__name__=test_prom_chunks".to_string(),
+                        "tag1=a".to_string()
+                    ],
+                    vec![
+                        (1686819776000, 1.0),
+                        (1686819777000, 2.0),
+                        (1686819778000, 3.0)
+                    ]
+                ),
+                (
+                    vec![
+                        "__name__=test_prom_chunks".to_string(),
+                        "tag1=a".to_string(),
+                        "tag2=b".to_string()
+                    ],
+                    vec![(1686819776500, 4.0)]
+                ),
+            ]
+        );
     }
 }
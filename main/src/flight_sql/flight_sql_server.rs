@@ -22,8 +22,11 @@ use arrow_flight::{
 };
 use datafusion::arrow::datatypes::{Schema, SchemaRef, ToByteSlice};
 use futures::Stream;
-use http_protocol::header::{DB, STREAM_TRIGGER_INTERVAL, TARGET_PARTITIONS, TENANT};
+use http_protocol::header::{
+    CONSISTENCY_LEVEL, DB, STREAM_TRIGGER_INTERVAL, TARGET_PARTITIONS, TENANT,
+};
 use models::auth::user::User;
+use models::consistency_level::ConsistencyLevel;
 use models::oid::UuidGenerator;
 use moka::sync::Cache;
 use prost::bytes::Bytes;
@@ -206,11 +209,21 @@ where
                         STREAM_TRIGGER_INTERVAL, e
                     ))
                 })?;
+        let consistency_level = utils::get_value_from_header(metadata, CONSISTENCY_LEVEL, "")
+            .map(|e| e.parse::<ConsistencyLevel>())
+            .transpose()
+            .map_err(|e| {
+                Status::invalid_argument(format!(
+                    "parse {} failed, error: {}",
+                    CONSISTENCY_LEVEL, e
+                ))
+            })?;
         let ctx = ContextBuilder::new(user_info)
             .with_tenant(tenant)
             .with_database(db)
             .with_target_partitions(target_partitions)
             .with_stream_trigger_interval(stream_trigger_interval)
+            .with_consistency_level(consistency_level)
             .build();
 
         Ok(ctx)
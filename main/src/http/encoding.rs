@@ -0,0 +1,337 @@
+use std::io::{self, Read, Write};
+use std::pin::Pin;
+use std::task::Poll;
+
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use futures::{ready, Stream, StreamExt};
+use http_protocol::header::{DEFLATE, GZIP, IDENTITY, ZSTD};
+use warp::http::header::{HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH, VARY};
+use warp::hyper::body::Bytes;
+use warp::hyper::Body;
+use warp::reply::Response;
+
+use super::Error as HttpError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Identity,
+    Gzip,
+    Deflate,
+    Zstd,
+}
+
+impl ContentEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Identity => IDENTITY,
+            Self::Gzip => GZIP,
+            Self::Deflate => DEFLATE,
+            Self::Zstd => ZSTD,
+        }
+    }
+
+    /// Parses the Content-Encoding header of a request, a body encoded more than once is
+    /// not supported.
+    pub fn from_content_encoding(value: Option<&str>) -> Result<Self, HttpError> {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return Ok(Self::Identity);
+        };
+        match value.to_ascii_lowercase().as_str() {
+            IDENTITY => Ok(Self::Identity),
+            GZIP | "x-gzip" => Ok(Self::Gzip),
+            DEFLATE => Ok(Self::Deflate),
+            ZSTD => Ok(Self::Zstd),
+            _ => Err(HttpError::UnsupportedContentEncoding {
+                encoding: value.to_string(),
+            }),
+        }
+    }
+
+    /// Picks the encoding of a response by the Accept-Encoding header of the request, the
+    /// encoding of the highest quality wins, zstd is preferred to gzip and deflate on ties.
+    pub fn from_accept_encoding(value: Option<&str>) -> Self {
+        let Some(value) = value else {
+            return Self::Identity;
+        };
+        // (coding, quality) of the accepted codings
+        let accepted = value
+            .split(',')
+            .filter_map(|item| {
+                let mut params = item.split(';');
+                let coding = params.next()?.trim().to_ascii_lowercase();
+                let quality = params
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (!coding.is_empty()).then_some((coding, quality))
+            })
+            .collect::<Vec<_>>();
+        let quality = |coding: &str| {
+            accepted
+                .iter()
+                .find(|(c, _)| c == coding || (coding == GZIP && c == "x-gzip"))
+                .or_else(|| accepted.iter().find(|(c, _)| c == "*"))
+                .map_or(0.0, |(_, q)| *q)
+        };
+
+        let mut best = (Self::Identity, 0.0);
+        for encoding in [Self::Zstd, Self::Gzip, Self::Deflate] {
+            let q = quality(encoding.as_str());
+            if q > best.1 {
+                best = (encoding, q);
+            }
+        }
+        best.0
+    }
+}
+
+/// Decodes the body of a request. The decoded body is limited to `limit` bytes as well,
+/// so that a small body can't be decompressed to exhaust the memory.
+pub fn decode_body(encoding: ContentEncoding, body: Bytes, limit: u64) -> Result<Bytes, HttpError> {
+    let reader: Box<dyn Read + '_> = match encoding {
+        ContentEncoding::Identity => return Ok(body),
+        ContentEncoding::Gzip => Box::new(MultiGzDecoder::new(body.as_ref())),
+        ContentEncoding::Deflate => Box::new(ZlibDecoder::new(body.as_ref())),
+        ContentEncoding::Zstd => Box::new(
+            zstd::stream::read::Decoder::new(body.as_ref())
+                .map_err(|source| HttpError::DecodeBody { source })?,
+        ),
+    };
+
+    let mut decoded = vec![];
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut decoded)
+        .map_err(|source| HttpError::DecodeBody { source })?;
+    if decoded.len() as u64 > limit {
+        return Err(HttpError::DecodedBodyTooLarge { limit });
+    }
+
+    Ok(decoded.into())
+}
+
+/// Decodes the body of a request by [`decode_body`] on a blocking thread, so that the
+/// decompression of a large body doesn't block the other requests.
+pub async fn decode_body_blocking(
+    encoding: ContentEncoding,
+    body: Bytes,
+    limit: u64,
+) -> Result<Bytes, HttpError> {
+    if encoding == ContentEncoding::Identity {
+        return Ok(body);
+    }
+    tokio::task::spawn_blocking(move || decode_body(encoding, body, limit))
+        .await
+        .map_err(|e| HttpError::DecodeBody {
+            source: io::Error::new(io::ErrorKind::Other, e),
+        })?
+}
+
+enum Encoder {
+    Gzip(GzEncoder<Vec<u8>>),
+    Deflate(ZlibEncoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+}
+
+impl Encoder {
+    fn new(encoding: ContentEncoding) -> io::Result<Option<Self>> {
+        let encoder = match encoding {
+            ContentEncoding::Identity => return Ok(None),
+            ContentEncoding::Gzip => Self::Gzip(GzEncoder::new(vec![], Compression::fast())),
+            ContentEncoding::Deflate => {
+                Self::Deflate(ZlibEncoder::new(vec![], Compression::fast()))
+            }
+            ContentEncoding::Zstd => Self::Zstd(zstd::stream::write::Encoder::new(
+                vec![],
+                zstd::DEFAULT_COMPRESSION_LEVEL,
+            )?),
+        };
+        Ok(Some(encoder))
+    }
+
+    /// Encodes a chunk, the chunk is flushed so that a streamed response is not delayed.
+    fn encode(&mut self, chunk: &[u8]) -> io::Result<Bytes> {
+        let buf = match self {
+            Self::Gzip(e) => {
+                e.write_all(chunk)?;
+                e.flush()?;
+                e.get_mut()
+            }
+            Self::Deflate(e) => {
+                e.write_all(chunk)?;
+                e.flush()?;
+                e.get_mut()
+            }
+            Self::Zstd(e) => {
+                e.write_all(chunk)?;
+                e.flush()?;
+                e.get_mut()
+            }
+        };
+        Ok(std::mem::take(buf).into())
+    }
+
+    fn finish(self) -> io::Result<Bytes> {
+        let buf = match self {
+            Self::Gzip(e) => e.finish()?,
+            Self::Deflate(e) => e.finish()?,
+            Self::Zstd(e) => e.finish()?,
+        };
+        Ok(buf.into())
+    }
+}
+
+struct EncodedBody {
+    body: Body,
+    /// None if the body is finished
+    encoder: Option<Encoder>,
+}
+
+impl Stream for EncodedBody {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let Some(encoder) = this.encoder.as_mut() else {
+                return Poll::Ready(None);
+            };
+            match ready!(this.body.poll_next_unpin(cx)) {
+                Some(Ok(chunk)) => match encoder.encode(&chunk) {
+                    Ok(encoded) if encoded.is_empty() => continue,
+                    res => return Poll::Ready(Some(res)),
+                },
+                Some(Err(e)) => {
+                    this.encoder = None;
+                    return Poll::Ready(Some(Err(io::Error::new(io::ErrorKind::Other, e))));
+                }
+                None => {
+                    let encoder = this.encoder.take().expect("encoder of unfinished body");
+                    return Poll::Ready(Some(encoder.finish()));
+                }
+            }
+        }
+    }
+}
+
+/// Encodes the body of a response in the encoding picked by the Accept-Encoding header,
+/// the body is encoded while being sent.
+pub fn encode_response(resp: Response, accept_encoding: Option<&str>) -> Response {
+    if resp.headers().contains_key(CONTENT_ENCODING) {
+        return resp;
+    }
+    let encoding = ContentEncoding::from_accept_encoding(accept_encoding);
+    let encoder = match Encoder::new(encoding) {
+        Ok(Some(encoder)) => encoder,
+        Ok(None) => return resp,
+        Err(e) => {
+            trace::warn!("Failed to create {} encoder: {}", encoding.as_str(), e);
+            return resp;
+        }
+    };
+
+    let (mut parts, body) = resp.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(
+        CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    parts
+        .headers
+        .append(VARY, HeaderValue::from_static("accept-encoding"));
+    let body = EncodedBody {
+        body,
+        encoder: Some(encoder),
+    };
+    Response::from_parts(parts, Body::wrap_stream(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use warp::hyper::body::to_bytes;
+
+    use super::*;
+
+    fn gzip(data: &[u8]) -> Bytes {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap().into()
+    }
+
+    #[test]
+    fn test_content_encoding() {
+        assert_eq!(
+            ContentEncoding::from_content_encoding(None).unwrap(),
+            ContentEncoding::Identity
+        );
+        assert_eq!(
+            ContentEncoding::from_content_encoding(Some("GZIP")).unwrap(),
+            ContentEncoding::Gzip
+        );
+        assert!(ContentEncoding::from_content_encoding(Some("br")).is_err());
+
+        let accept = |v| ContentEncoding::from_accept_encoding(Some(v));
+        assert_eq!(
+            ContentEncoding::from_accept_encoding(None),
+            ContentEncoding::Identity
+        );
+        assert_eq!(accept("gzip, deflate, br"), ContentEncoding::Gzip);
+        assert_eq!(accept("deflate, gzip, zstd"), ContentEncoding::Zstd);
+        assert_eq!(accept("zstd;q=0.5, deflate"), ContentEncoding::Deflate);
+        assert_eq!(accept("*;q=0.8, zstd;q=0"), ContentEncoding::Gzip);
+        assert_eq!(accept("br, identity"), ContentEncoding::Identity);
+    }
+
+    #[test]
+    fn test_decode_body() {
+        let data = b"air,station=XiaoMaiDao visibility=50 1".repeat(100);
+
+        let body = gzip(&data);
+        let decoded = decode_body(ContentEncoding::Gzip, body.clone(), data.len() as u64);
+        assert_eq!(decoded.unwrap().as_ref(), data.as_slice());
+        // The decoded body is limited
+        let decoded = decode_body(ContentEncoding::Gzip, body, data.len() as u64 - 1);
+        assert!(matches!(
+            decoded,
+            Err(HttpError::DecodedBodyTooLarge { .. })
+        ));
+
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&data).unwrap();
+        let body = encoder.finish().unwrap().into();
+        let decoded = decode_body(ContentEncoding::Deflate, body, u64::MAX).unwrap();
+        assert_eq!(decoded.as_ref(), data.as_slice());
+
+        let body = zstd::encode_all(data.as_slice(), 0).unwrap().into();
+        let decoded = decode_body(ContentEncoding::Zstd, body, u64::MAX).unwrap();
+        assert_eq!(decoded.as_ref(), data.as_slice());
+
+        let decoded = decode_body(ContentEncoding::Gzip, Bytes::from_static(b"xx"), u64::MAX);
+        assert!(matches!(decoded, Err(HttpError::DecodeBody { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_encode_response() {
+        let data = b"time,station,visibility\n".repeat(100);
+
+        let resp = encode_response(Response::new(Body::from(data.clone())), Some("zstd"));
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), ZSTD);
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(zstd::decode_all(body.as_ref()).unwrap(), data);
+
+        let resp = encode_response(Response::new(Body::from(data.clone())), Some("gzip"));
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        let decoded = decode_body(ContentEncoding::Gzip, body, u64::MAX).unwrap();
+        assert_eq!(decoded.as_ref(), data.as_slice());
+
+        let resp = encode_response(Response::new(Body::from(data.clone())), None);
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(body.as_ref(), data.as_slice());
+    }
+}
@@ -11,10 +11,16 @@ use std::time::Instant;
 use chrono::Local;
 use config::TLSConfig;
 use coordinator::service::CoordinatorRef;
-use http_protocol::header::{ACCEPT, AUTHORIZATION, PRIVATE_KEY};
+use futures::StreamExt;
+use http_protocol::header::{
+    ACCEPT, APPLICATION_JSON, APPLICATION_PROTOBUF, APPLICATION_STREAMED_PROTOBUF_CHUNKS,
+    AUTHORIZATION, CONTENT_TYPE, PRIVATE_KEY, SNAPPY,
+};
 use http_protocol::parameter::{SqlParam, WriteParam};
-use http_protocol::response::ErrorResponse;
+use http_protocol::response::{ErrorResponse, PartialWriteResponse, RejectReason, RejectedLine};
+use http_protocol::status_code::{BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
 use meta::error::MetaError;
+use metrics::count::U64Counter;
 use metrics::metric_register::MetricsRegister;
 use metrics::prom_reporter::PromReporter;
 use metrics::{gather_metrics, sample_point_write_duration, sample_query_read_duration};
@@ -23,22 +29,25 @@ use models::consistency_level::ConsistencyLevel;
 use models::error_code::UnknownCodeWithMessage;
 use models::oid::{Identifier, Oid};
 use models::schema::{Precision, DEFAULT_CATALOG};
-use protocol_parser::line_protocol::line_protocol_to_lines;
+use protocol_parser::line_protocol::{line_protocol_to_lines, line_protocol_to_lines_partial};
 use protocol_parser::lines_convert::parse_lines_to_points;
-use protocol_parser::open_tsdb::open_tsdb_to_lines;
+use protocol_parser::open_tsdb::{open_tsdb_to_lines, open_tsdb_to_lines_partial};
 use protocol_parser::{DataPoint, Line};
 use protos::kv_service::WritePointsRequest;
+use query::prom::promql::{parse_duration, parse_time};
 use query::prom::remote_server::PromRemoteSqlServer;
+use serde::Serialize;
 use snafu::ResultExt;
 use spi::query::config::StreamTriggerInterval;
 use spi::server::dbms::DBMSRef;
-use spi::server::prom::PromRemoteServerRef;
+use spi::server::prom::{PromQueryTime, PromReadResponse, PromRemoteServerRef, PromSeriesQuery};
 use spi::service::protocol::{Context, ContextBuilder, Query};
 use spi::QueryError;
 use tokio::sync::oneshot;
 use trace::{debug, error, info, SpanContext, SpanExt, SpanRecorder};
 use trace_http::ctx::{SpanContextExtractor, DEFAULT_TRACE_HEADER_NAME};
 use utils::backtrace;
+use warp::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
 use warp::hyper::body::Bytes;
 use warp::hyper::Body;
 use warp::reject::{MethodNotAllowed, MissingHeader, PayloadTooLarge};
@@ -47,10 +56,11 @@ use warp::{header, reject, Filter, Rejection, Reply};
 
 use super::header::Header;
 use super::Error as HttpError;
+use crate::http::encoding::{decode_body_blocking, encode_response, ContentEncoding};
 use crate::http::metrics::HttpMetrics;
 use crate::http::response::{HttpResponse, ResponseBuilder};
 use crate::http::result_format::{get_result_format_from_header, ResultFormat};
-use crate::http::QuerySnafu;
+use crate::http::{partial_write, QuerySnafu};
 use crate::server::ServiceHandle;
 use crate::spi::service::Service;
 use crate::{server, VERSION};
@@ -157,6 +167,24 @@ impl HttpService {
         )
     }
 
+    /// The request body decoded by the Content-Encoding header, both the body and the
+    /// decoded body are limited to `limit` bytes.
+    fn decoded_body(
+        &self,
+        limit: u64,
+    ) -> impl Filter<Extract = (Bytes,), Error = warp::Rejection> + Clone {
+        warp::body::content_length_limit(limit)
+            .and(warp::body::bytes())
+            .and(header::optional::<String>(CONTENT_ENCODING.as_str()))
+            .and_then(move |body: Bytes, encoding: Option<String>| async move {
+                let encoding = ContentEncoding::from_content_encoding(encoding.as_deref())
+                    .map_err(reject::custom)?;
+                decode_body_blocking(encoding, body, limit)
+                    .await
+                    .map_err(reject::custom)
+            })
+    }
+
     fn with_dbms(&self) -> impl Filter<Extract = (DBMSRef,), Error = Infallible> + Clone {
         let dbms = self.dbms.clone();
         warp::any().map(move || dbms.clone())
@@ -204,6 +232,8 @@ impl HttpService {
             .or(self.debug_jeprof())
             .or(self.prom_remote_read())
             .or(self.prom_remote_write())
+            .or(self.prom_query())
+            .or(self.prom_metadata())
             .or(self.backtrace())
             .or(self.write_open_tsdb())
             .or(self.put_open_tsdb())
@@ -219,6 +249,8 @@ impl HttpService {
             .or(self.debug_pprof())
             .or(self.debug_jeprof())
             .or(self.prom_remote_read())
+            .or(self.prom_query())
+            .or(self.prom_metadata())
             .or(self.backtrace())
     }
 
@@ -272,6 +304,7 @@ impl HttpService {
             .and(self.with_http_metrics())
             .and(self.with_hostaddr())
             .and(self.handle_span_header())
+            .and(header::optional::<String>(ACCEPT_ENCODING.as_str()))
             // construct_query
             .and_then(
                 |req: Bytes,
@@ -280,7 +313,8 @@ impl HttpService {
                  dbms: DBMSRef,
                  metrics: Arc<HttpMetrics>,
                  addr: String,
-                 parent_span_ctx: Option<SpanContext>| async move {
+                 parent_span_ctx: Option<SpanContext>,
+                 accept_encoding: Option<String>| async move {
                     debug!(
                         "Receive http sql request, header: {:?}, param: {:?}",
                         header, param
@@ -328,7 +362,7 @@ impl HttpService {
 
                     metrics.queries_inc(tenant, user, db, addr.as_str());
 
-                    result
+                    result.map(|resp| encode_response(resp, accept_encoding.as_deref()))
                 },
             )
     }
@@ -338,8 +372,7 @@ impl HttpService {
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
         warp::path!("api" / "v1" / "write")
             .and(warp::post())
-            .and(warp::body::content_length_limit(self.write_body_limit))
-            .and(warp::body::bytes())
+            .and(self.decoded_body(self.write_body_limit))
             .and(self.handle_header())
             .and(warp::query::<WriteParam>())
             .and(self.with_dbms())
@@ -361,6 +394,7 @@ impl HttpService {
                         SpanRecorder::new(parent_span_ctx.child_span("rest line protocol write"));
                     let span_context = span_recorder.span_ctx();
 
+                    let partial_write = param.partial_write.unwrap_or_default();
                     let ctx = {
                         let mut span_recorder =
                             SpanRecorder::new(span_context.child_span("construct write context"));
@@ -378,25 +412,51 @@ impl HttpService {
                     let precision = Precision::new(ctx.precision()).unwrap_or(Precision::NS);
 
                     let req_len = req.len() as u64;
-                    let write_points_req = {
-                        let mut span_recorder = SpanRecorder::new(
-                            span_context.child_span("construct write lines points request"),
-                        );
-                        span_recorder.set_metadata("bytes", req.len());
-                        construct_write_lines_points_request(req, ctx.database())
-                            .map_err(reject::custom)?
+                    let resp = if partial_write {
+                        let (body, mut rejected) = partial_write::decode_lines(&req);
+                        let lines = {
+                            let mut span_recorder =
+                                SpanRecorder::new(span_context.child_span("parse lines partially"));
+                            span_recorder.set_metadata("bytes", req.len());
+                            let (lines, errors) = line_protocol_to_lines_partial(
+                                &body,
+                                Local::now().timestamp_nanos(),
+                            );
+                            rejected.extend(errors.into_iter().map(partial_write::parse_error));
+                            lines
+                        };
+                        write_lines_partially(
+                            &coord,
+                            &ctx,
+                            precision,
+                            lines,
+                            rejected,
+                            span_context,
+                        )
+                        .await
+                        .map(|resp| ResponseBuilder::new(OK).json(&resp))
+                    } else {
+                        let write_points_req = {
+                            let mut span_recorder = SpanRecorder::new(
+                                span_context.child_span("construct write lines points request"),
+                            );
+                            span_recorder.set_metadata("bytes", req.len());
+                            construct_write_lines_points_request(req, ctx.database())
+                                .map_err(reject::custom)?
+                        };
+
+                        coord_write_points_with_span_recorder(
+                            &coord,
+                            ctx.tenant().to_string(),
+                            ctx.session_config().consistency_level(),
+                            precision,
+                            write_points_req,
+                            span_context,
+                        )
+                        .await
+                        .map(|_| ResponseBuilder::ok())
                     };
 
-                    let resp = coord_write_points_with_span_recorder(
-                        &coord,
-                        ctx.tenant().to_string(),
-                        ConsistencyLevel::Any,
-                        precision,
-                        write_points_req,
-                        span_context,
-                    )
-                    .await;
-
                     let (tenant, db, user, addr) = (
                         ctx.tenant(),
                         ctx.database(),
@@ -413,7 +473,7 @@ impl HttpService {
                         resp.is_ok(),
                         start.elapsed().as_millis() as f64,
                     );
-                    resp.map(|_| ResponseBuilder::ok()).map_err(reject::custom)
+                    resp.map_err(reject::custom)
                 },
             )
     }
@@ -423,8 +483,7 @@ impl HttpService {
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
         warp::path!("api" / "v1" / "opentsdb" / "write")
             .and(warp::post())
-            .and(warp::body::content_length_limit(self.write_body_limit))
-            .and(warp::body::bytes())
+            .and(self.decoded_body(self.write_body_limit))
             .and(self.handle_header())
             .and(warp::query::<WriteParam>())
             .and(self.with_dbms())
@@ -446,6 +505,7 @@ impl HttpService {
                         SpanRecorder::new(parent_span_ctx.child_span("rest open tsdb write"));
                     let span_context = span_recorder.span_ctx();
 
+                    let partial_write = param.partial_write.unwrap_or_default();
                     let ctx = {
                         let mut span_recorder =
                             SpanRecorder::new(span_context.child_span("construct write context"));
@@ -463,22 +523,48 @@ impl HttpService {
                     let precision = Precision::new(ctx.precision()).unwrap_or(Precision::NS);
 
                     let req_len = req.len() as u64;
-                    let write_points_req = {
-                        let mut span_recorder = SpanRecorder::new(
-                            span_context.child_span("construct write tsdb points request"),
-                        );
-                        span_recorder.set_metadata("bytes", req.len());
-                        construct_write_tsdb_points_request(req, &ctx).map_err(reject::custom)?
+                    let resp = if partial_write {
+                        let (body, mut rejected) = partial_write::decode_lines(&req);
+                        let lines = {
+                            let mut span_recorder = SpanRecorder::new(
+                                span_context.child_span("parse tsdb lines partially"),
+                            );
+                            span_recorder.set_metadata("bytes", req.len());
+                            let (lines, errors) =
+                                open_tsdb_to_lines_partial(&body, Local::now().timestamp_nanos());
+                            rejected.extend(errors.into_iter().map(partial_write::parse_error));
+                            lines
+                        };
+                        write_lines_partially(
+                            &coord,
+                            &ctx,
+                            precision,
+                            lines,
+                            rejected,
+                            span_context,
+                        )
+                        .await
+                        .map(|resp| ResponseBuilder::new(OK).json(&resp))
+                    } else {
+                        let write_points_req = {
+                            let mut span_recorder = SpanRecorder::new(
+                                span_context.child_span("construct write tsdb points request"),
+                            );
+                            span_recorder.set_metadata("bytes", req.len());
+                            construct_write_tsdb_points_request(req, &ctx)
+                                .map_err(reject::custom)?
+                        };
+                        coord_write_points_with_span_recorder(
+                            &coord,
+                            ctx.tenant().to_string(),
+                            ctx.session_config().consistency_level(),
+                            precision,
+                            write_points_req,
+                            span_context,
+                        )
+                        .await
+                        .map(|_| ResponseBuilder::ok())
                     };
-                    let resp = coord_write_points_with_span_recorder(
-                        &coord,
-                        ctx.tenant().to_string(),
-                        ConsistencyLevel::Any,
-                        precision,
-                        write_points_req,
-                        span_context,
-                    )
-                    .await;
 
                     let (tenant, db, user, addr) = (
                         ctx.tenant(),
@@ -496,7 +582,7 @@ impl HttpService {
                         resp.is_ok(),
                         start.elapsed().as_millis() as f64,
                     );
-                    resp.map(|_| ResponseBuilder::ok()).map_err(reject::custom)
+                    resp.map_err(reject::custom)
                 },
             )
     }
@@ -506,8 +592,7 @@ impl HttpService {
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
         warp::path!("api" / "v1" / "opentsdb" / "put")
             .and(warp::post())
-            .and(warp::body::content_length_limit(self.write_body_limit))
-            .and(warp::body::bytes())
+            .and(self.decoded_body(self.write_body_limit))
             .and(self.handle_header())
             .and(warp::query::<WriteParam>())
             .and(self.with_dbms())
@@ -529,6 +614,7 @@ impl HttpService {
                         SpanRecorder::new(parent_span_ctx.child_span("rest open tsdb put"));
                     let span_context = span_recorder.span_ctx();
 
+                    let partial_write = param.partial_write.unwrap_or_default();
                     let ctx = {
                         let mut span_recorder =
                             SpanRecorder::new(span_context.child_span("construct write context"));
@@ -546,23 +632,45 @@ impl HttpService {
                     let precision = Precision::new(ctx.precision()).unwrap_or(Precision::NS);
 
                     let req_len = req.len() as u64;
-                    let write_points_req = {
-                        let mut span_recorder = SpanRecorder::new(
-                            span_context.child_span("construct write tsdb points json request"),
-                        );
-                        span_recorder.set_metadata("bytes", req.len());
-                        construct_write_tsdb_points_json_request(req, &ctx)
-                            .map_err(reject::custom)?
+                    let resp = if partial_write {
+                        let values = {
+                            let mut span_recorder = SpanRecorder::new(
+                                span_context.child_span("parse tsdb json partially"),
+                            );
+                            span_recorder.set_metadata("bytes", req.len());
+                            partial_write::parse_tsdb_json(&req).map_err(reject::custom)?
+                        };
+                        let (lines, rejected) = partial_write::tsdb_json_to_lines(&values);
+                        write_lines_partially(
+                            &coord,
+                            &ctx,
+                            precision,
+                            lines,
+                            rejected,
+                            span_context,
+                        )
+                        .await
+                        .map(|resp| ResponseBuilder::new(OK).json(&resp))
+                    } else {
+                        let write_points_req = {
+                            let mut span_recorder = SpanRecorder::new(
+                                span_context.child_span("construct write tsdb points json request"),
+                            );
+                            span_recorder.set_metadata("bytes", req.len());
+                            construct_write_tsdb_points_json_request(req, &ctx)
+                                .map_err(reject::custom)?
+                        };
+                        coord_write_points_with_span_recorder(
+                            &coord,
+                            ctx.tenant().to_string(),
+                            ctx.session_config().consistency_level(),
+                            precision,
+                            write_points_req,
+                            span_context,
+                        )
+                        .await
+                        .map(|_| ResponseBuilder::ok())
                     };
-                    let resp = coord_write_points_with_span_recorder(
-                        &coord,
-                        ctx.tenant().to_string(),
-                        ConsistencyLevel::Any,
-                        precision,
-                        write_points_req,
-                        span_context,
-                    )
-                    .await;
 
                     let (tenant, db, user, addr) = (
                         ctx.tenant(),
@@ -580,7 +688,7 @@ impl HttpService {
                         resp.is_ok(),
                         start.elapsed().as_millis() as f64,
                     );
-                    resp.map(|_| ResponseBuilder::ok()).map_err(reject::custom)
+                    resp.map_err(reject::custom)
                 },
             )
     }
@@ -724,10 +832,7 @@ impl HttpService {
                                 );
                                 reject::custom(HttpError::from(e))
                             })
-                            .map(|b| {
-                                counter.inc(b.len() as u64);
-                                b
-                            })
+                            .map(|resp| prom_read_response(resp, counter))
                     };
 
                     metrics.queries_inc(tenant_name, username, database_name, addr.as_str());
@@ -743,6 +848,229 @@ impl HttpService {
             )
     }
 
+    /// PromQL instant and range queries, with the parameters in the url or the form
+    fn prom_query(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let instant = warp::path!("api" / "v1" / "query").map(|| false);
+        let range = warp::path!("api" / "v1" / "query_range").map(|| true);
+        let params = warp::get()
+            .and(warp::query::<HashMap<String, String>>())
+            .or(warp::post()
+                .and(warp::query::<HashMap<String, String>>())
+                .and(warp::body::content_length_limit(self.query_body_limit))
+                .and(warp::body::form::<HashMap<String, String>>())
+                .map(
+                    |mut params: HashMap<String, String>, form: HashMap<String, String>| {
+                        params.extend(form);
+                        params
+                    },
+                ))
+            .unify();
+
+        instant
+            .or(range)
+            .unify()
+            .and(params)
+            .and(self.handle_header())
+            .and(self.with_dbms())
+            .and(self.with_http_metrics())
+            .and(self.with_prom_remote_server())
+            .and(self.with_hostaddr())
+            .and(self.handle_span_header())
+            .and_then(
+                |is_range: bool,
+                 mut params: HashMap<String, String>,
+                 header: Header,
+                 dbms: DBMSRef,
+                 metrics: Arc<HttpMetrics>,
+                 prs: PromRemoteServerRef,
+                 addr: String,
+                 parent_span_ctx: Option<SpanContext>| async move {
+                    let start = Instant::now();
+                    debug!(
+                        "Receive rest prom query request, header: {:?}, param: {:?}",
+                        header, params
+                    );
+                    let span_recorder =
+                        SpanRecorder::new(parent_span_ctx.child_span("rest prom query"));
+                    let span_context = span_recorder.span_ctx();
+
+                    let param = SqlParam {
+                        tenant: params.remove("tenant"),
+                        db: params.remove("db"),
+                        chunked: None,
+                        target_partitions: None,
+                        stream_trigger_interval: None,
+                        consistency_level: None,
+                    };
+                    let context = {
+                        let mut span_recorder =
+                            SpanRecorder::new(span_context.child_span("construct context"));
+                        let ctx = construct_read_context(&header, param, dbms)
+                            .await
+                            .map_err(reject::custom)?;
+                        span_recorder.record(ctx)
+                    };
+
+                    let tenant_name = context.tenant();
+                    let username = context.user_info().desc().name();
+                    let database_name = context.database();
+
+                    let counter =
+                        metrics.http_data_out(tenant_name, username, database_name, addr.as_str());
+
+                    let result = match (params.get("query"), prom_query_time(is_range, &params)) {
+                        (Some(query), Ok(time)) => {
+                            let mut span_recorder =
+                                SpanRecorder::new(span_context.child_span("promql query"));
+                            prs.query(&context, query, time, span_recorder.span_ctx())
+                                .await
+                                .map_err(|e| {
+                                    span_recorder.error(e.to_string());
+                                    trace::error!(
+                                        "Failed to handle prom query request, err: {}",
+                                        e
+                                    );
+                                    e
+                                })
+                        }
+                        (None, _) => Err(QueryError::InvalidPromQuery {
+                            reason: "missing parameter \"query\"".to_string(),
+                        }),
+                        (_, Err(e)) => Err(e),
+                    };
+
+                    metrics.queries_inc(tenant_name, username, database_name, addr.as_str());
+
+                    sample_query_read_duration(
+                        context.tenant(),
+                        context.database(),
+                        result.is_ok(),
+                        start.elapsed().as_millis() as f64,
+                    );
+                    Ok::<_, Rejection>(prom_query_response(result, counter))
+                },
+            )
+    }
+
+    /// The series, label names and label values of `/api/v1/series`, `/api/v1/labels`
+    /// and `/api/v1/label/<name>/values`, with the parameters in the url or the form
+    fn prom_metadata(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let series = warp::path!("api" / "v1" / "series").map(|| PromMetadata::Series);
+        let labels = warp::path!("api" / "v1" / "labels").map(|| PromMetadata::Labels);
+        let label_values =
+            warp::path!("api" / "v1" / "label" / String / "values").map(PromMetadata::LabelValues);
+        // `match[]` may be repeated
+        let params = warp::get()
+            .and(warp::query::<Vec<(String, String)>>())
+            .or(warp::post()
+                .and(warp::query::<Vec<(String, String)>>())
+                .and(warp::body::content_length_limit(self.query_body_limit))
+                .and(warp::body::form::<Vec<(String, String)>>())
+                .map(
+                    |mut params: Vec<(String, String)>, form: Vec<(String, String)>| {
+                        params.extend(form);
+                        params
+                    },
+                ))
+            .unify();
+
+        series
+            .or(labels)
+            .unify()
+            .or(label_values)
+            .unify()
+            .and(params)
+            .and(self.handle_header())
+            .and(self.with_dbms())
+            .and(self.with_http_metrics())
+            .and(self.with_prom_remote_server())
+            .and(self.with_hostaddr())
+            .and(self.handle_span_header())
+            .and_then(
+                |metadata: PromMetadata,
+                 params: Vec<(String, String)>,
+                 header: Header,
+                 dbms: DBMSRef,
+                 metrics: Arc<HttpMetrics>,
+                 prs: PromRemoteServerRef,
+                 addr: String,
+                 parent_span_ctx: Option<SpanContext>| async move {
+                    let start = Instant::now();
+                    debug!(
+                        "Receive rest prom metadata request, header: {:?}, param: {:?}",
+                        header, params
+                    );
+                    let span_recorder =
+                        SpanRecorder::new(parent_span_ctx.child_span("rest prom metadata"));
+                    let span_context = span_recorder.span_ctx();
+
+                    let param_value = |name: &str| {
+                        params
+                            .iter()
+                            .find(|(key, _)| key == name)
+                            .map(|(_, value)| value.clone())
+                    };
+                    let param = SqlParam {
+                        tenant: param_value("tenant"),
+                        db: param_value("db"),
+                        chunked: None,
+                        target_partitions: None,
+                        stream_trigger_interval: None,
+                        consistency_level: None,
+                    };
+                    let context = {
+                        let mut span_recorder =
+                            SpanRecorder::new(span_context.child_span("construct context"));
+                        let ctx = construct_read_context(&header, param, dbms)
+                            .await
+                            .map_err(reject::custom)?;
+                        span_recorder.record(ctx)
+                    };
+
+                    let tenant_name = context.tenant();
+                    let username = context.user_info().desc().name();
+                    let database_name = context.database();
+
+                    let counter =
+                        metrics.http_data_out(tenant_name, username, database_name, addr.as_str());
+
+                    let query = prom_series_query(&params);
+                    let span_recorder =
+                        SpanRecorder::new(span_context.child_span("promql metadata"));
+                    let span_ctx = span_recorder.span_ctx();
+                    let (is_ok, resp) = match (metadata, query) {
+                        (PromMetadata::Series, Ok(query)) => {
+                            let result = prs.series(&context, &query, span_ctx).await;
+                            (result.is_ok(), prom_query_response(result, counter))
+                        }
+                        (PromMetadata::Labels, Ok(query)) => {
+                            let result = prs.labels(&context, &query, span_ctx).await;
+                            (result.is_ok(), prom_query_response(result, counter))
+                        }
+                        (PromMetadata::LabelValues(name), Ok(query)) => {
+                            let result = prs.label_values(&context, &name, &query, span_ctx).await;
+                            (result.is_ok(), prom_query_response(result, counter))
+                        }
+                        (_, Err(e)) => (false, prom_query_response::<()>(Err(e), counter)),
+                    };
+
+                    metrics.queries_inc(tenant_name, username, database_name, addr.as_str());
+
+                    sample_query_read_duration(
+                        context.tenant(),
+                        context.database(),
+                        is_ok,
+                        start.elapsed().as_millis() as f64,
+                    );
+                    Ok::<_, Rejection>(resp)
+                },
+            )
+    }
+
     fn prom_remote_write(
         &self,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
@@ -806,7 +1134,7 @@ impl HttpService {
                     let resp = coord_write_points_with_span_recorder(
                         &coord,
                         ctx.tenant().to_string(),
-                        ConsistencyLevel::Any,
+                        ctx.session_config().consistency_level(),
                         Precision::NS,
                         write_points_req,
                         span_context,
@@ -934,6 +1262,125 @@ async fn construct_query(
     ))
 }
 
+fn prom_read_response(resp: PromReadResponse, counter: U64Counter) -> Response {
+    match resp {
+        PromReadResponse::Samples(body) => {
+            counter.inc(body.len() as u64);
+            ResponseBuilder::new(OK)
+                .insert_header((CONTENT_TYPE, APPLICATION_PROTOBUF))
+                .insert_header((CONTENT_ENCODING, SNAPPY))
+                .build(body)
+        }
+        PromReadResponse::StreamedXorChunks(frames) => {
+            let frames = frames.map(move |frame| {
+                frame.map(|frame| {
+                    counter.inc(frame.len() as u64);
+                    frame
+                })
+            });
+            ResponseBuilder::new(OK)
+                .insert_header((CONTENT_TYPE, APPLICATION_STREAMED_PROTOBUF_CHUNKS))
+                .build_stream_response(Response::new(Body::wrap_stream(frames)))
+        }
+    }
+}
+
+/// The evaluation time of `/api/v1/query`, which is now by default,
+/// or the range of `/api/v1/query_range`.
+fn prom_query_time(
+    is_range: bool,
+    params: &HashMap<String, String>,
+) -> Result<PromQueryTime, QueryError> {
+    let param = |name: &str| {
+        params
+            .get(name)
+            .ok_or_else(|| QueryError::InvalidPromQuery {
+                reason: format!("missing parameter \"{name}\""),
+            })
+    };
+
+    if !is_range {
+        return match params.get("time") {
+            Some(time) => parse_time(time).map(PromQueryTime::Instant),
+            None => Ok(PromQueryTime::Instant(Local::now().timestamp_nanos())),
+        };
+    }
+    Ok(PromQueryTime::Range {
+        start: parse_time(param("start")?)?,
+        end: parse_time(param("end")?)?,
+        step: parse_duration(param("step")?)?,
+    })
+}
+
+enum PromMetadata {
+    Series,
+    Labels,
+    LabelValues(String),
+}
+
+/// The selectors `match[]` and the time range of the series of the metadata APIs
+fn prom_series_query(params: &[(String, String)]) -> Result<PromSeriesQuery, QueryError> {
+    let mut query = PromSeriesQuery::default();
+    for (name, value) in params {
+        match name.as_str() {
+            "match[]" => query.matchers.push(value.clone()),
+            "start" => query.start = Some(parse_time(value)?),
+            "end" => query.end = Some(parse_time(value)?),
+            _ => {}
+        }
+    }
+    Ok(query)
+}
+
+/// The json response of prometheus
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum PromQueryResponse<T> {
+    Success {
+        data: T,
+    },
+    Error {
+        #[serde(rename = "errorType")]
+        error_type: &'static str,
+        error: String,
+    },
+}
+
+fn prom_query_response<T: Serialize>(
+    result: Result<T, QueryError>,
+    counter: U64Counter,
+) -> Response {
+    let (status, resp) = match result {
+        Ok(data) => (OK, PromQueryResponse::Success { data }),
+        Err(e) => {
+            let (status, error_type) = match e {
+                QueryError::InvalidPromQuery { .. } => (BAD_REQUEST, "bad_data"),
+                _ => (UNPROCESSABLE_ENTITY, "execution"),
+            };
+            (
+                status,
+                PromQueryResponse::Error {
+                    error_type,
+                    error: e.to_string(),
+                },
+            )
+        }
+    };
+
+    match serde_json::to_vec(&resp) {
+        Ok(body) => {
+            counter.inc(body.len() as u64);
+            ResponseBuilder::new(status)
+                .insert_header((CONTENT_TYPE, APPLICATION_JSON))
+                .build(body)
+        }
+        Err(e) => {
+            error!("Failed to serialize prom query response, err: {}", e);
+            ResponseBuilder::internal_server_error()
+        }
+    }
+}
+
 async fn construct_read_context(
     header: &Header,
     param: SqlParam,
@@ -961,6 +1408,7 @@ async fn construct_read_context(
                 })
                 .transpose()?,
         )
+        .with_consistency_level(parse_consistency_level(param.consistency_level)?)
         .build();
 
     Ok(context)
@@ -975,6 +1423,7 @@ async fn construct_write_context(
     let tenant = param.tenant;
     let db = param.db;
     let precision = param.precision;
+    let consistency_level = parse_consistency_level(param.consistency_level)?;
 
     let user = dbms.authenticate(&user_info, tenant.as_deref()).await?;
 
@@ -982,11 +1431,21 @@ async fn construct_write_context(
         .with_tenant(tenant)
         .with_database(db)
         .with_precision(precision)
+        .with_consistency_level(consistency_level)
         .build();
 
     Ok(context)
 }
 
+fn parse_consistency_level(level: Option<String>) -> Result<Option<ConsistencyLevel>, HttpError> {
+    level
+        .map(|ref e| {
+            e.parse::<ConsistencyLevel>()
+                .map_err(|reason| HttpError::InvalidParameter { reason })
+        })
+        .transpose()
+}
+
 fn _construct_write_db_privilege(tenant_id: Oid, database: &str) -> Privilege<Oid> {
     Privilege::TenantObject(
         TenantObjectPrivilege::Database(DatabasePrivilege::Write, Some(database.to_string())),
@@ -1097,7 +1556,7 @@ fn construct_write_tsdb_points_json_request(
 async fn coord_write_points_with_span_recorder(
     coord: &CoordinatorRef,
     tenant: String,
-    consistency_level: ConsistencyLevel,
+    consistency_level: Option<ConsistencyLevel>,
     precision: Precision,
     write_points_req: WritePointsRequest,
     span_context: Option<&SpanContext>,
@@ -1118,6 +1577,60 @@ async fn coord_write_points_with_span_recorder(
         })
 }
 
+/// Writes the lines accepted by the checks, the lines rejected by the limiter are added
+/// to the rejected lines. Other errors of the write fail the request.
+async fn write_lines_partially(
+    coord: &CoordinatorRef,
+    ctx: &Context,
+    precision: Precision,
+    lines: Vec<(usize, Line<'_>)>,
+    mut rejected: Vec<RejectedLine>,
+    span_context: Option<&SpanContext>,
+) -> Result<PartialWriteResponse, HttpError> {
+    let meta = coord
+        .tenant_meta(ctx.tenant())
+        .await
+        .ok_or_else(|| MetaError::TenantNotFound {
+            tenant: ctx.tenant().to_string(),
+        })?;
+    let lines = partial_write::check_lines(&meta, ctx.database(), precision, lines, &mut rejected)?;
+    let (numbers, lines): (Vec<_>, Vec<_>) = lines.into_iter().unzip();
+
+    let mut written = 0;
+    if !lines.is_empty() {
+        let write_points_req = WritePointsRequest {
+            version: 1,
+            meta: None,
+            points: parse_lines_to_points(ctx.database(), &lines),
+        };
+        let mut span_recorder = SpanRecorder::new(span_context.child_span("write points"));
+        let result = coord
+            .write_points_or_limited(
+                ctx.tenant().to_string(),
+                ctx.session_config().consistency_level(),
+                precision,
+                write_points_req,
+                span_recorder.span_ctx(),
+            )
+            .await
+            .map_err(|e| {
+                span_recorder.error(e.to_string());
+                HttpError::from(e)
+            })?;
+        match result {
+            Ok(()) => written = lines.len(),
+            Err(err) => rejected.extend(numbers.into_iter().map(|line| RejectedLine {
+                line,
+                reason: RejectReason::Limiter,
+                message: err.to_string(),
+            })),
+        }
+    }
+
+    rejected.sort_by_key(|r| r.line);
+    Ok(PartialWriteResponse { written, rejected })
+}
+
 async fn sql_handle(
     query: &Query,
     dbms: &DBMSRef,
@@ -1,6 +1,6 @@
 use coordinator::errors::CoordinatorError;
 use http_protocol::response::ErrorResponse;
-use http_protocol::status_code::UNPROCESSABLE_ENTITY;
+use http_protocol::status_code::{PAYLOAD_TOO_LARGE, UNPROCESSABLE_ENTITY, UNSUPPORTED_MEDIA_TYPE};
 use meta::error::MetaError;
 use models::error_code::{ErrorCode, ErrorCoder};
 use snafu::Snafu;
@@ -10,9 +10,11 @@ use warp::reply::Response;
 
 use self::response::ResponseBuilder;
 
+mod encoding;
 pub mod header;
 pub mod http_service;
 mod metrics;
+mod partial_write;
 mod response;
 mod result_format;
 
@@ -89,6 +91,30 @@ pub enum Error {
     TraceHttp {
         source: trace_http::ctx::ContextError,
     },
+
+    #[snafu(display("Invalid parameter: {}", reason))]
+    #[error_code(code = 13)]
+    InvalidParameter {
+        reason: String,
+    },
+
+    #[snafu(display("Unsupported content encoding: {}", encoding))]
+    #[error_code(code = 14)]
+    UnsupportedContentEncoding {
+        encoding: String,
+    },
+
+    #[snafu(display("Decode body, error: {}", source))]
+    #[error_code(code = 15)]
+    DecodeBody {
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Decoded body is larger than {} bytes", limit))]
+    #[error_code(code = 16)]
+    DecodedBodyTooLarge {
+        limit: u64,
+    },
 }
 
 impl From<tskv::Error> for Error {
@@ -148,8 +174,16 @@ impl From<&Error> for Response {
             | Error::Coordinator { .. } => {
                 ResponseBuilder::new(UNPROCESSABLE_ENTITY).json(&error_resp)
             }
-            Error::InvalidHeader { .. } | Error::ParseAuth { .. } | Error::TraceHttp { .. } => {
-                ResponseBuilder::bad_request(&error_resp)
+            Error::InvalidHeader { .. }
+            | Error::InvalidParameter { .. }
+            | Error::ParseAuth { .. }
+            | Error::TraceHttp { .. }
+            | Error::DecodeBody { .. } => ResponseBuilder::bad_request(&error_resp),
+            Error::UnsupportedContentEncoding { .. } => {
+                ResponseBuilder::new(UNSUPPORTED_MEDIA_TYPE).json(&error_resp)
+            }
+            Error::DecodedBodyTooLarge { .. } => {
+                ResponseBuilder::new(PAYLOAD_TOO_LARGE).json(&error_resp)
             }
             _ => ResponseBuilder::internal_server_error(),
         }
@@ -229,6 +263,20 @@ mod tests {
         assert_eq!(content_type, HeaderValue::from_static(APPLICATION_JSON));
     }
 
+    #[test]
+    fn test_invalid_parameter_error() {
+        let resp: Response = Error::InvalidParameter {
+            reason: "test".to_string(),
+        }
+        .into();
+
+        assert_eq!(resp.status(), BAD_REQUEST);
+
+        let content_type = resp.headers().get(CONTENT_TYPE).unwrap();
+
+        assert_eq!(content_type, HeaderValue::from_static(APPLICATION_JSON));
+    }
+
     #[test]
     fn test_parse_auth_error() {
         let resp: Response = Error::ParseAuth {
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use http_protocol::response::{RejectReason, RejectedLine};
+use meta::error::MetaError;
+use meta::model::MetaClientRef;
+use models::schema::{timestamp_convert, ColumnType, Precision, TskvTableSchema};
+use models::ValueType;
+use protocol_parser::{DataPoint, Error as ParseError, Line, LineError};
+use protos::FieldValue;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::Error as HttpError;
+
+pub fn parse_error(error: LineError) -> RejectedLine {
+    RejectedLine {
+        line: error.line,
+        reason: RejectReason::ParseError,
+        message: error.error.to_string(),
+    }
+}
+
+/// Decodes the lines of the body, a line of invalid UTF-8 is rejected and left blank,
+/// so that the following lines keep their line numbers.
+pub fn decode_lines(body: &[u8]) -> (String, Vec<RejectedLine>) {
+    let mut lines = vec![];
+    let mut rejected = vec![];
+    for (i, line) in body.split(|b| *b == b'\n').enumerate() {
+        match std::str::from_utf8(line) {
+            Ok(line) => lines.push(line),
+            Err(err) => {
+                rejected.push(parse_error(LineError {
+                    line: i + 1,
+                    error: ParseError::Common {
+                        content: format!("invalid UTF-8: {}", err),
+                    },
+                }));
+                lines.push("");
+            }
+        }
+    }
+    (lines.join("\n"), rejected)
+}
+
+/// Parses OpenTSDB JSON, which is a data point or an array of data points, into JSON
+/// values to be parsed one by one.
+pub fn parse_tsdb_json(body: &[u8]) -> Result<Vec<Value>, HttpError> {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(values)) => Ok(values),
+        Ok(value) => Ok(vec![value]),
+        Err(source) => Err(HttpError::ParseOpentsdbJsonProtocol { source }),
+    }
+}
+
+/// Parses the data points one by one, data points are numbered from 1.
+pub fn tsdb_json_to_lines(values: &[Value]) -> (Vec<(usize, Line)>, Vec<RejectedLine>) {
+    let mut lines = vec![];
+    let mut rejected = vec![];
+    for (i, value) in values.iter().enumerate() {
+        match DataPoint::deserialize(value) {
+            Ok(point) => lines.push((i + 1, Line::from(point))),
+            Err(e) => rejected.push(RejectedLine {
+                line: i + 1,
+                reason: RejectReason::ParseError,
+                message: e.to_string(),
+            }),
+        }
+    }
+    (lines, rejected)
+}
+
+/// Rejects the lines older than the retention of the database, and the lines whose
+/// columns conflict with the table schemas or the columns of the accepted lines.
+pub fn check_lines<'a>(
+    meta: &MetaClientRef,
+    db: &str,
+    precision: Precision,
+    lines: Vec<(usize, Line<'a>)>,
+    rejected: &mut Vec<RejectedLine>,
+) -> Result<Vec<(usize, Line<'a>)>, HttpError> {
+    let db_schema = meta
+        .get_db_schema(db)?
+        .ok_or_else(|| MetaError::DatabaseNotFound {
+            database: db.to_string(),
+        })?;
+    let mut checker = LineChecker::new(
+        precision,
+        *db_schema.config.precision_or_default(),
+        meta.database_min_ts(db),
+    );
+    for (_, line) in lines.iter() {
+        if !checker.schemas.contains_key(line.table) {
+            let schema = meta.get_tskv_table_schema(db, line.table)?;
+            checker.schemas.insert(line.table, schema);
+        }
+    }
+
+    let mut accepted = Vec::with_capacity(lines.len());
+    for (number, line) in lines {
+        match checker.check(&line) {
+            Ok(()) => accepted.push((number, line)),
+            Err((reason, message)) => rejected.push(RejectedLine {
+                line: number,
+                reason,
+                message,
+            }),
+        }
+    }
+
+    Ok(accepted)
+}
+
+fn value_type(value: &FieldValue) -> ValueType {
+    match value {
+        FieldValue::U64(_) => ValueType::Unsigned,
+        FieldValue::I64(_) => ValueType::Integer,
+        FieldValue::Str(_) => ValueType::String,
+        FieldValue::F64(_) => ValueType::Float,
+        FieldValue::Bool(_) => ValueType::Boolean,
+    }
+}
+
+struct LineChecker<'a> {
+    precision: Precision,
+    db_precision: Precision,
+    /// The earliest timestamp kept by the database, in the precision of the database
+    min_ts: Option<i64>,
+    /// Table -> schema of the table, none if the table is to be created
+    schemas: HashMap<&'a str, Option<Arc<TskvTableSchema>>>,
+    /// (table, column) -> type of the column in the accepted lines
+    columns: HashMap<(&'a str, &'a str), ColumnType>,
+}
+
+impl<'a> LineChecker<'a> {
+    fn new(precision: Precision, db_precision: Precision, min_ts: Option<i64>) -> Self {
+        Self {
+            precision,
+            db_precision,
+            min_ts,
+            schemas: HashMap::new(),
+            columns: HashMap::new(),
+        }
+    }
+
+    fn check(&mut self, line: &Line<'a>) -> Result<(), (RejectReason, String)> {
+        let ts = timestamp_convert(self.precision, self.db_precision, line.timestamp).ok_or((
+            RejectReason::ParseError,
+            format!("timestamp {} overflow", line.timestamp),
+        ))?;
+        if let Some(min_ts) = self.min_ts {
+            if ts < min_ts {
+                return Err((
+                    RejectReason::ExpiredTimestamp,
+                    format!(
+                        "timestamp {} is earlier than {}, which is kept by the database",
+                        ts, min_ts
+                    ),
+                ));
+            }
+        }
+
+        let columns = line
+            .tags
+            .iter()
+            .map(|(name, _)| (*name, ColumnType::Tag))
+            .chain(
+                line.fields
+                    .iter()
+                    .map(|(name, value)| (*name, ColumnType::Field(value_type(value)))),
+            )
+            .collect::<Vec<_>>();
+        for (name, column_type) in columns.iter() {
+            let expected = self
+                .schemas
+                .get(line.table)
+                .and_then(|schema| schema.as_ref()?.column(name))
+                .map(|column| &column.column_type)
+                .or_else(|| self.columns.get(&(line.table, *name)));
+            if let Some(expected) = expected {
+                if expected != column_type {
+                    return Err((
+                        RejectReason::TypeConflict,
+                        format!(
+                            "column '{}' of table '{}' is {}, but {} is written",
+                            name,
+                            line.table,
+                            expected.as_str(),
+                            column_type.as_str()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        for (name, column_type) in columns {
+            self.columns
+                .entry((line.table, name))
+                .or_insert(column_type);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use http_protocol::response::RejectReason;
+    use models::schema::{ColumnType, Precision, TableColumn, TskvTableSchema, DEFAULT_CATALOG};
+    use models::ValueType;
+    use protocol_parser::Line;
+    use protos::FieldValue;
+
+    use super::{decode_lines, parse_tsdb_json, tsdb_json_to_lines, LineChecker};
+
+    fn line<'a>(
+        table: &'a str,
+        tags: &[&'a str],
+        field: (&'a str, FieldValue),
+        ts: i64,
+    ) -> Line<'a> {
+        let tags = tags.iter().map(|tag| (*tag, "a")).collect();
+        Line::new(table, tags, vec![field], ts)
+    }
+
+    #[test]
+    fn test_check_lines() {
+        let schema = TskvTableSchema::new(
+            DEFAULT_CATALOG.to_string(),
+            "public".to_string(),
+            "air".to_string(),
+            vec![
+                TableColumn::new_tag_column(1, "station".to_string()),
+                TableColumn::new_with_default(
+                    "visibility".to_string(),
+                    ColumnType::Field(ValueType::Float),
+                ),
+            ],
+        );
+        let mut checker = LineChecker::new(Precision::MS, Precision::NS, Some(1_000_000));
+        checker.schemas.insert("air", Some(Arc::new(schema)));
+        checker.schemas.insert("sea", None);
+
+        let ok = line("air", &["station"], ("visibility", FieldValue::F64(1.0)), 1);
+        assert!(checker.check(&ok).is_ok());
+
+        let expired = line("air", &["station"], ("visibility", FieldValue::F64(1.0)), 0);
+        let (reason, _) = checker.check(&expired).unwrap_err();
+        assert_eq!(reason, RejectReason::ExpiredTimestamp);
+
+        // Conflicts with the table schema
+        let conflict = line("air", &["visibility"], ("station", FieldValue::F64(1.0)), 1);
+        let (reason, _) = checker.check(&conflict).unwrap_err();
+        assert_eq!(reason, RejectReason::TypeConflict);
+
+        // Conflicts with the earlier lines of a table to be created
+        let ok = line("sea", &[], ("temperature", FieldValue::I64(1)), 1);
+        assert!(checker.check(&ok).is_ok());
+        let conflict = line("sea", &[], ("temperature", FieldValue::F64(1.0)), 1);
+        let (reason, _) = checker.check(&conflict).unwrap_err();
+        assert_eq!(reason, RejectReason::TypeConflict);
+
+        assert_eq!(
+            checker.columns.get(&("sea", "temperature")),
+            Some(&ColumnType::Field(ValueType::Integer))
+        );
+    }
+
+    #[test]
+    fn test_tsdb_json_to_lines() {
+        let body = r#"[
+            {"metric": "sys.cpu", "timestamp": 1, "value": 1.5, "tags": {"host": "a"}},
+            {"metric": "sys.cpu", "timestamp": "x", "value": 1.5, "tags": {"host": "a"}}
+        ]"#;
+        let values = parse_tsdb_json(body.as_bytes()).unwrap();
+        let (lines, rejected) = tsdb_json_to_lines(&values);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].0, 1);
+        assert_eq!(lines[0].1.table, "sys.cpu");
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].line, 2);
+        assert_eq!(rejected[0].reason, RejectReason::ParseError);
+
+        assert!(parse_tsdb_json(b"{").is_err());
+        assert!(parse_tsdb_json(b"[\xff]").is_err());
+    }
+
+    #[test]
+    fn test_decode_lines() {
+        let (body, rejected) = decode_lines(b"air a=1 1\nair a=\xff 2\nair a=3 3");
+        assert_eq!(body, "air a=1 1\n\nair a=3 3");
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].line, 2);
+        assert_eq!(rejected[0].reason, RejectReason::ParseError);
+    }
+}
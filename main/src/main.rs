@@ -217,7 +217,8 @@ fn main() -> Result<(), std::io::Error> {
             DeploymentMode::Tskv => builder.build_storage_server(&mut server).await,
             DeploymentMode::Query => builder.build_query_server(&mut server).await,
             DeploymentMode::Singleton => builder.build_singleton(&mut server).await,
-        };
+        }
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
 
         info!("CnosDB server start as {} mode", deployment_mode);
         server.start().expect("CnosDB server start.");
@@ -228,8 +229,8 @@ fn main() -> Result<(), std::io::Error> {
         }
 
         println!("CnosDB is stopped.");
-    });
-    Ok(())
+        Ok(())
+    })
 }
 
 fn parse_config(config_path: Option<impl AsRef<Path>>) -> config::Config {
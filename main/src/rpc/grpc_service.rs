@@ -25,6 +25,7 @@ pub struct GrpcService {
     tls_config: Option<TLSConfig>,
     metrics_register: Arc<MetricsRegister>,
     span_context_extractor: Arc<SpanContextExtractor>,
+    max_move_bandwidth: u64,
     handle: Option<ServiceHandle<Result<(), tonic::transport::Error>>>,
 }
 
@@ -37,6 +38,7 @@ impl GrpcService {
         tls_config: Option<TLSConfig>,
         metrics_register: Arc<MetricsRegister>,
         span_context_extractor: Arc<SpanContextExtractor>,
+        max_move_bandwidth: u64,
     ) -> Self {
         Self {
             addr,
@@ -46,6 +48,7 @@ impl GrpcService {
             tls_config,
             metrics_register,
             span_context_extractor,
+            max_move_bandwidth,
             handle: None,
         }
     }
@@ -80,6 +83,7 @@ impl Service for GrpcService {
             kv_inst: self.kv_inst.clone(),
             coord: self.coord.clone(),
             metrics_register: self.metrics_register.clone(),
+            max_move_bandwidth: self.max_move_bandwidth,
         })
         .max_decoding_message_size(100 * 1024 * 1024);
 
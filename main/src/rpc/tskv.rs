@@ -4,17 +4,22 @@ use std::sync::Arc;
 use coordinator::file_info::get_files_meta;
 use coordinator::service::CoordinatorRef;
 use coordinator::vnode_mgr::VnodeManager;
+use coordinator::written_log::WrittenEvent;
 use coordinator::{FAILED_RESPONSE_CODE, SUCCESS_RESPONSE_CODE};
 use futures::{Stream, StreamExt, TryStreamExt};
 use meta::model::MetaRef;
 use metrics::metric_register::MetricsRegister;
 use models::meta_data::VnodeInfo;
-use models::predicate::domain::{self, QueryArgs, QueryExpr};
-use models::record_batch_encode;
+use models::predicate::domain::{
+    self, ColumnAggregate, QueryArgs, QueryExpr, ResolvedPredicate, TimeRange,
+};
 use models::schema::{Precision, TableColumn};
+use models::{record_batch_encode, SeriesKey};
 use protos::kv_service::tskv_service_server::TskvService;
 use protos::kv_service::*;
 use protos::models::{PingBody, PingBodyBuilder};
+use spi::query::datasource::build_location_object_store;
+use spi::query::logical_planner::map_to_sql_options;
 use tokio::io::AsyncReadExt;
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
@@ -23,9 +28,10 @@ use tonic::{Extensions, Request, Response, Status};
 use trace::{debug, error, info, SpanContext, SpanExt, SpanRecorder};
 use tskv::error::Result as TskvResult;
 use tskv::reader::query_executor::QueryExecutor;
+use tskv::reader::scan_metrics::ScanMetrics;
 use tskv::reader::serialize::TonicRecordBatchEncoder;
 use tskv::reader::{QueryOption, SendableTskvRecordBatchStream};
-use tskv::EngineRef;
+use tskv::{EngineRef, SeriesRangeRepair};
 
 type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, tonic::Status>> + Send>>;
 
@@ -35,6 +41,8 @@ pub struct TskvServiceImpl {
     pub kv_inst: EngineRef,
     pub coord: CoordinatorRef,
     pub metrics_register: Arc<MetricsRegister>,
+    /// Bytes per second to download the files of a vnode moved to this node, 0 for unlimited
+    pub max_move_bandwidth: u64,
 }
 
 impl TskvServiceImpl {
@@ -51,7 +59,11 @@ impl TskvServiceImpl {
         code: i32,
         data: Vec<u8>,
     ) -> Result<tonic::Response<BatchBytesResponse>, tonic::Status> {
-        Ok(tonic::Response::new(BatchBytesResponse { code, data }))
+        Ok(tonic::Response::new(BatchBytesResponse {
+            code,
+            data,
+            ..Default::default()
+        }))
     }
 
     fn tonic_status(&self, msg: String) -> tonic::Status {
@@ -64,6 +76,9 @@ impl TskvServiceImpl {
         request: &DropDbRequest,
     ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
         let _ = self.kv_inst.drop_database(tenant, &request.db).await;
+        self.coord
+            .written_log()
+            .record(WrittenEvent::TenantChanged(tenant.to_string()));
 
         self.status_response(SUCCESS_RESPONSE_CODE, "".to_string())
     }
@@ -77,6 +92,9 @@ impl TskvServiceImpl {
             .kv_inst
             .drop_table(tenant, &request.db, &request.table)
             .await;
+        self.coord
+            .written_log()
+            .record_table(tenant, &request.db, &request.table);
 
         self.status_response(SUCCESS_RESPONSE_CODE, "".to_string())
     }
@@ -90,6 +108,9 @@ impl TskvServiceImpl {
             .kv_inst
             .drop_table_column(tenant, &request.db, &request.table, &request.column)
             .await;
+        self.coord
+            .written_log()
+            .record_table(tenant, &request.db, &request.table);
 
         self.status_response(SUCCESS_RESPONSE_CODE, "".to_string())
     }
@@ -126,6 +147,40 @@ impl TskvServiceImpl {
             .kv_inst
             .change_table_column(tenant, &request.db, &request.table, &request.name, column)
             .await;
+        self.coord
+            .written_log()
+            .record_table(tenant, &request.db, &request.table);
+
+        self.status_response(SUCCESS_RESPONSE_CODE, "".to_string())
+    }
+
+    async fn admin_delete_from_table(
+        &self,
+        tenant: &str,
+        request: &DeleteFromTableRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let predicate = match ResolvedPredicate::decode(&request.predicate) {
+            Ok(predicate) => predicate,
+            Err(err) => return self.status_response(FAILED_RESPONSE_CODE, err.to_string()),
+        };
+
+        let mut result = Ok(());
+        for vnode_id in request.vnode_ids.iter() {
+            result = self
+                .kv_inst
+                .delete_from_table(tenant, &request.db, &request.table, *vnode_id, &predicate)
+                .await;
+            if result.is_err() {
+                break;
+            }
+        }
+        // It's not known which time ranges are deleted by the predicate.
+        self.coord
+            .written_log()
+            .record_table(tenant, &request.db, &request.table);
+        if let Err(err) = result {
+            return self.status_response(FAILED_RESPONSE_CODE, err.to_string());
+        }
 
         self.status_response(SUCCESS_RESPONSE_CODE, "".to_string())
     }
@@ -150,7 +205,8 @@ impl TskvServiceImpl {
         request: &CopyVnodeRequest,
     ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
         let meta = self.coord.meta_manager();
-        let manager = VnodeManager::new(meta, self.kv_inst.clone(), self.coord.node_id());
+        let manager = VnodeManager::new(meta, self.kv_inst.clone(), self.coord.node_id())
+            .with_max_bandwidth(self.max_move_bandwidth);
         if let Err(err) = manager.copy_vnode(tenant, request.vnode_id, true).await {
             self.status_response(FAILED_RESPONSE_CODE, err.to_string())
         } else {
@@ -164,7 +220,8 @@ impl TskvServiceImpl {
         request: &MoveVnodeRequest,
     ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
         let meta = self.coord.meta_manager();
-        let manager = VnodeManager::new(meta, self.kv_inst.clone(), self.coord.node_id());
+        let manager = VnodeManager::new(meta, self.kv_inst.clone(), self.coord.node_id())
+            .with_max_bandwidth(self.max_move_bandwidth);
         if let Err(err) = manager.move_vnode(tenant, request.vnode_id).await {
             self.status_response(FAILED_RESPONSE_CODE, err.to_string())
         } else {
@@ -184,6 +241,72 @@ impl TskvServiceImpl {
         }
     }
 
+    async fn admin_backup_vnode(
+        &self,
+        tenant: &str,
+        request: &BackupVnodeRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let (object_store, location) = match build_location_object_store(
+            &request.location,
+            map_to_sql_options(&request.options),
+        ) {
+            Ok(v) => v,
+            Err(err) => return self.status_response(FAILED_RESPONSE_CODE, err.to_string()),
+        };
+
+        let meta = self.coord.meta_manager();
+        let manager = VnodeManager::new(meta, self.kv_inst.clone(), self.coord.node_id());
+        if let Err(err) = manager
+            .backup_vnode(
+                tenant,
+                &request.db,
+                request.vnode_id,
+                object_store,
+                &location,
+            )
+            .await
+        {
+            self.status_response(FAILED_RESPONSE_CODE, err.to_string())
+        } else {
+            self.status_response(SUCCESS_RESPONSE_CODE, "".to_string())
+        }
+    }
+
+    async fn admin_restore_vnode(
+        &self,
+        tenant: &str,
+        request: &RestoreVnodeRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let (object_store, location) = match build_location_object_store(
+            &request.location,
+            map_to_sql_options(&request.options),
+        ) {
+            Ok(v) => v,
+            Err(err) => return self.status_response(FAILED_RESPONSE_CODE, err.to_string()),
+        };
+
+        let meta = self.coord.meta_manager();
+        let manager = VnodeManager::new(meta, self.kv_inst.clone(), self.coord.node_id());
+        let result = manager
+            .restore_vnode(
+                tenant,
+                &request.db,
+                request.vnode_id,
+                request.backup_vnode_id,
+                object_store,
+                &location,
+            )
+            .await;
+        self.coord
+            .written_log()
+            .record(WrittenEvent::TenantChanged(tenant.to_string()));
+        if let Err(err) = result {
+            self.status_response(FAILED_RESPONSE_CODE, err.to_string())
+        } else {
+            self.status_response(SUCCESS_RESPONSE_CODE, "".to_string())
+        }
+    }
+
     async fn admin_fetch_vnode_checksum(
         &self,
         _tenant: &str,
@@ -199,11 +322,102 @@ impl TskvServiceImpl {
         }
     }
 
+    async fn admin_fetch_vnode_time_range_checksum(
+        &self,
+        _tenant: &str,
+        request: &FetchVnodeTimeRangeChecksumRequest,
+    ) -> Result<tonic::Response<BatchBytesResponse>, tonic::Status> {
+        let record = self
+            .kv_inst
+            .get_vnode_time_range_checksum(request.vnode_id)
+            .await
+            .map_err(|err| self.tonic_status(err.to_string()))?;
+        let bytes =
+            record_batch_encode(&record).map_err(|err| self.tonic_status(err.to_string()))?;
+
+        self.bytes_response(SUCCESS_RESPONSE_CODE, bytes)
+    }
+
+    async fn admin_fetch_vnode_series_data(
+        &self,
+        _tenant: &str,
+        request: &FetchVnodeSeriesDataRequest,
+    ) -> Result<tonic::Response<BatchBytesResponse>, tonic::Status> {
+        let mut ranges = Vec::with_capacity(request.ranges.len());
+        for range in request.ranges.iter() {
+            let series_key = SeriesKey::decode(&range.series_key)
+                .map_err(|err| self.tonic_status(err.to_string()))?;
+            ranges.push((series_key, TimeRange::new(range.min_ts, range.max_ts)));
+        }
+        let record = self
+            .kv_inst
+            .read_vnode_series_data(request.vnode_id, ranges)
+            .await
+            .map_err(|err| self.tonic_status(err.to_string()))?;
+        let bytes =
+            record_batch_encode(&record).map_err(|err| self.tonic_status(err.to_string()))?;
+
+        self.bytes_response(SUCCESS_RESPONSE_CODE, bytes)
+    }
+
+    async fn admin_repair_vnode_series_data(
+        &self,
+        tenant: &str,
+        request: &RepairVnodeSeriesDataRequest,
+    ) -> Result<tonic::Response<BatchBytesResponse>, tonic::Status> {
+        let mut ranges = Vec::with_capacity(request.ranges.len());
+        for range in request.ranges.iter() {
+            let series_key = SeriesKey::decode(&range.series_key)
+                .map_err(|err| self.tonic_status(err.to_string()))?;
+            ranges.push(SeriesRangeRepair {
+                series_key,
+                time_range: TimeRange::new(range.min_ts, range.max_ts),
+                digest: range.digest.clone(),
+                points: range.points.clone(),
+            });
+        }
+        let precision = Precision::from(request.precision as u8);
+        let record = self
+            .kv_inst
+            .repair_vnode_series_data(tenant, request.vnode_id, precision, ranges)
+            .await;
+        // Points of the ranges skipped by the repair are recorded too.
+        let written_log = self.coord.written_log();
+        for range in request.ranges.iter() {
+            written_log
+                .record_points(tenant, precision, &range.points)
+                .map_err(|err| self.tonic_status(err.to_string()))?;
+        }
+        let record = record.map_err(|err| self.tonic_status(err.to_string()))?;
+        let bytes =
+            record_batch_encode(&record).map_err(|err| self.tonic_status(err.to_string()))?;
+
+        self.bytes_response(SUCCESS_RESPONSE_CODE, bytes)
+    }
+
+    async fn admin_fetch_written_time_ranges(
+        &self,
+        _tenant: &str,
+        request: &FetchWrittenTimeRangesRequest,
+    ) -> Result<tonic::Response<BatchBytesResponse>, tonic::Status> {
+        let record = self
+            .coord
+            .written_log()
+            .since(request.since_seq)
+            .to_record_batch()
+            .map_err(|err| self.tonic_status(err.to_string()))?;
+        let bytes =
+            record_batch_encode(&record).map_err(|err| self.tonic_status(err.to_string()))?;
+
+        self.bytes_response(SUCCESS_RESPONSE_CODE, bytes)
+    }
+
     fn query_record_batch_exec(
         self,
         args: QueryArgs,
         expr: QueryExpr,
-        aggs: Option<Vec<TableColumn>>,
+        aggs: Option<Vec<ColumnAggregate>>,
+        scan_metrics: ScanMetrics,
         span_ctx: Option<&SpanContext>,
     ) -> TskvResult<SendableTskvRecordBatchStream> {
         let option = QueryOption::new(
@@ -212,7 +426,8 @@ impl TskvServiceImpl {
             aggs,
             Arc::new(expr.df_schema),
             expr.table_schema,
-        );
+        )
+        .with_scan_metrics(scan_metrics);
 
         let meta = self.coord.meta_manager();
         let node_id = meta.node_id();
@@ -330,18 +545,20 @@ impl TskvService for TskvServiceImpl {
                 user: None,
                 password: None,
             }),
-            points: inner.data,
+            points: inner.data.clone(),
         };
 
-        let _ = self
+        let precision = Precision::from(inner.precision as u8);
+        let result = self
             .kv_inst
-            .write(
-                span_recorder.span_ctx(),
-                inner.vnode_id,
-                Precision::from(inner.precision as u8),
-                request,
-            )
-            .await?;
+            .write(span_recorder.span_ctx(), inner.vnode_id, precision, request)
+            .await;
+        // Points may be written even if the write fails.
+        self.coord
+            .written_log()
+            .record_points(&inner.tenant, precision, &inner.data)
+            .map_err(|err| self.tonic_status(err.to_string()))?;
+        let _ = result?;
 
         self.status_response(SUCCESS_RESPONSE_CODE, "".to_string())
     }
@@ -381,6 +598,15 @@ impl TskvService for TskvServiceImpl {
                 admin_command_request::Command::AlterColumn(command) => {
                     self.admin_alter_column(&inner.tenant, command).await
                 }
+                admin_command_request::Command::DeleteFromTable(command) => {
+                    self.admin_delete_from_table(&inner.tenant, command).await
+                }
+                admin_command_request::Command::BackupVnode(command) => {
+                    self.admin_backup_vnode(&inner.tenant, command).await
+                }
+                admin_command_request::Command::RestoreVnode(command) => {
+                    self.admin_restore_vnode(&inner.tenant, command).await
+                }
             };
 
             info!("admin command: {:?}, result: {:?}", command, resp);
@@ -402,6 +628,22 @@ impl TskvService for TskvServiceImpl {
                     self.admin_fetch_vnode_checksum(&inner.tenant, command)
                         .await
                 }
+                admin_fetch_command_request::Command::FetchVnodeTimeRangeChecksum(command) => {
+                    self.admin_fetch_vnode_time_range_checksum(&inner.tenant, command)
+                        .await
+                }
+                admin_fetch_command_request::Command::FetchVnodeSeriesData(command) => {
+                    self.admin_fetch_vnode_series_data(&inner.tenant, command)
+                        .await
+                }
+                admin_fetch_command_request::Command::RepairVnodeSeriesData(command) => {
+                    self.admin_repair_vnode_series_data(&inner.tenant, command)
+                        .await
+                }
+                admin_fetch_command_request::Command::FetchWrittenTimeRanges(command) => {
+                    self.admin_fetch_written_time_ranges(&inner.tenant, command)
+                        .await
+                }
             }
         } else {
             self.bytes_response(FAILED_RESPONSE_CODE, vec![])
@@ -483,6 +725,7 @@ impl TskvService for TskvServiceImpl {
                         .send(Ok(BatchBytesResponse {
                             code: SUCCESS_RESPONSE_CODE,
                             data: (buffer[0..len]).to_vec(),
+                            ..Default::default()
                         }))
                         .await;
                 }
@@ -523,14 +766,24 @@ impl TskvService for TskvServiceImpl {
         let encoded_stream = {
             let span_recorder = span_recorder.child("RecordBatch encorder stream");
 
+            let scan_metrics = ScanMetrics::default();
             let stream = TskvServiceImpl::query_record_batch_exec(
                 service,
                 args,
                 expr,
                 aggs,
+                scan_metrics.clone(),
                 span_recorder.span_ctx(),
             )?;
-            TonicRecordBatchEncoder::new(stream, span_recorder).map_err(Into::into)
+            let encoder = TonicRecordBatchEncoder::new(stream, span_recorder);
+            // Metrics of the scan are sent back after the last record batch if requested,
+            // a node of an older version doesn't expect them
+            let encoder = if inner.with_scan_metrics {
+                encoder.with_scan_metrics(scan_metrics)
+            } else {
+                encoder
+            };
+            encoder.map_err(Into::into)
         };
 
         Ok(tonic::Response::new(Box::pin(encoded_stream)))
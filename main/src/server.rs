@@ -8,8 +8,12 @@ use meta::model::meta_admin::AdminMeta;
 use meta::model::MetaRef;
 use metrics::metric_register::MetricsRegister;
 use models::utils::build_address;
+use query::continuous_query::ContinuousQueryScheduler;
 use query::instance::make_cnosdbms;
+use query::materialized_view::MaterializedViewScheduler;
 use snafu::{Backtrace, Snafu};
+use spi::query::datasource::{build_object_store, UriSchema};
+use spi::query::logical_planner::{map_to_sql_options, parse_connection_options};
 use spi::server::dbms::DBMSRef;
 use tokio::runtime::Runtime;
 use tokio::sync::oneshot::Sender;
@@ -17,6 +21,8 @@ use tokio::task::JoinHandle;
 use tokio::time;
 use trace::error;
 use trace_http::ctx::SpanContextExtractor;
+use tskv::cold_storage::ColdStorage;
+use tskv::kv_option::StorageOptions;
 use tskv::{EngineRef, TsKv};
 
 use crate::flight_sql::FlightSqlServiceAdapter;
@@ -44,6 +50,9 @@ pub enum Error {
 
     #[snafu(display("Server Common Error : {}", reason))]
     Common { reason: String },
+
+    #[snafu(display("Failed to build cold storage '{}': {}", url, reason))]
+    ColdStorage { url: String, reason: String },
 }
 
 impl From<tonic::transport::Error> for Error {
@@ -133,7 +142,7 @@ fn build_default_address(port: u16) -> String {
 }
 
 impl ServiceBuilder {
-    pub async fn build_storage_server(&self, server: &mut Server) -> Option<EngineRef> {
+    pub async fn build_storage_server(&self, server: &mut Server) -> Result<Option<EngineRef>> {
         let meta = self.create_meta().await;
 
         meta.add_data_node().await.unwrap();
@@ -144,7 +153,7 @@ impl ServiceBuilder {
 
         let kv_inst = self
             .create_tskv(meta.clone(), self.runtime.clone(), self.memory_pool.clone())
-            .await;
+            .await?;
         let coord = self.create_coord(meta, Some(kv_inst.clone())).await;
         let dbms = self
             .create_dbms(coord.clone(), self.memory_pool.clone())
@@ -163,10 +172,10 @@ impl ServiceBuilder {
         server.add_service(grpc_service);
         server.add_service(tcp_service);
 
-        Some(kv_inst)
+        Ok(Some(kv_inst))
     }
 
-    pub async fn build_query_server(&self, server: &mut Server) -> Option<EngineRef> {
+    pub async fn build_query_server(&self, server: &mut Server) -> Result<Option<EngineRef>> {
         let meta = self.create_meta().await;
         let coord = self.create_coord(meta, None).await;
         let dbms = self
@@ -175,14 +184,21 @@ impl ServiceBuilder {
         let http_service =
             Box::new(self.create_http(dbms.clone(), coord.clone(), ServerMode::Query));
         let flight_sql_service = Box::new(self.create_flight_sql(dbms.clone()));
+        ContinuousQueryScheduler::new(dbms.clone(), coord.meta_manager()).start();
+        MaterializedViewScheduler::new(
+            dbms.clone(),
+            coord.meta_manager(),
+            coord.subscribe_written_time_ranges(),
+        )
+        .start();
 
         server.add_service(http_service);
         server.add_service(flight_sql_service);
 
-        None
+        Ok(None)
     }
 
-    pub async fn build_query_storage(&self, server: &mut Server) -> Option<EngineRef> {
+    pub async fn build_query_storage(&self, server: &mut Server) -> Result<Option<EngineRef>> {
         let meta = self.create_meta().await;
 
         meta.add_data_node().await.unwrap();
@@ -193,7 +209,7 @@ impl ServiceBuilder {
 
         let kv_inst = self
             .create_tskv(meta.clone(), self.runtime.clone(), self.memory_pool.clone())
-            .await;
+            .await?;
         let coord = self.create_coord(meta, Some(kv_inst.clone())).await;
         let dbms = self
             .create_dbms(coord.clone(), self.memory_pool.clone())
@@ -208,16 +224,23 @@ impl ServiceBuilder {
         let http_service =
             Box::new(self.create_http(dbms.clone(), coord.clone(), ServerMode::Bundle));
         let tcp_service = Box::new(self.create_tcp(coord.clone()));
+        ContinuousQueryScheduler::new(dbms.clone(), coord.meta_manager()).start();
+        MaterializedViewScheduler::new(
+            dbms.clone(),
+            coord.meta_manager(),
+            coord.subscribe_written_time_ranges(),
+        )
+        .start();
 
         server.add_service(http_service);
         server.add_service(grpc_service);
         server.add_service(flight_sql_service);
         server.add_service(tcp_service);
 
-        Some(kv_inst)
+        Ok(Some(kv_inst))
     }
 
-    pub async fn build_singleton(&self, server: &mut Server) -> Option<EngineRef> {
+    pub async fn build_singleton(&self, server: &mut Server) -> Result<Option<EngineRef>> {
         let meta_service = MetaService::new(self.cpu, self.config.clone());
         meta_service.start().await.unwrap();
         self.build_query_storage(server).await
@@ -234,8 +257,12 @@ impl ServiceBuilder {
         meta: MetaRef,
         runtime: Arc<Runtime>,
         memory_pool: MemoryPoolRef,
-    ) -> EngineRef {
-        let options = tskv::Options::from(&self.config);
+    ) -> Result<EngineRef> {
+        let mut options = tskv::Options::from(&self.config);
+        if options.cold_storage.is_none() && self.config.cold_storage.enabled() {
+            // Cold storage on local file system is built by `Options::from`.
+            options.cold_storage = Some(build_cold_storage(&self.config, &options.storage)?);
+        }
         let kv = TsKv::open(
             meta,
             options.clone(),
@@ -248,7 +275,7 @@ impl ServiceBuilder {
 
         let kv: EngineRef = Arc::new(kv);
 
-        kv
+        Ok(kv)
     }
 
     async fn create_dbms(&self, coord: CoordinatorRef, memory_pool: MemoryPoolRef) -> DBMSRef {
@@ -333,6 +360,7 @@ impl ServiceBuilder {
             None,
             self.metrics_register.clone(),
             self.span_context_extractor.clone(),
+            self.config.rebalance.max_bandwidth,
         )
     }
 
@@ -396,3 +424,29 @@ impl ServiceBuilder {
         FlightSqlServiceAdapter::new(dbms, addr, tls_config, self.span_context_extractor.clone())
     }
 }
+
+fn build_cold_storage(
+    config: &config::Config,
+    storage_opt: &StorageOptions,
+) -> Result<Arc<ColdStorage>> {
+    let cold_storage_config = &config.cold_storage;
+    let (scheme, bucket, path) = cold_storage_config.split_url();
+    let options = map_to_sql_options(&cold_storage_config.options);
+    let cold_storage_error = |reason: String| Error::ColdStorage {
+        url: cold_storage_config.url.clone(),
+        reason,
+    };
+    let connection_options =
+        parse_connection_options(&UriSchema::from(scheme), Some(bucket), options)
+            .map_err(|e| cold_storage_error(e.to_string()))?;
+    let object_store = build_object_store(connection_options)
+        .map_err(|e| cold_storage_error(e.to_string()))?
+        .ok_or_else(|| cold_storage_error("not an object store".to_string()))?;
+
+    Ok(Arc::new(ColdStorage::new(
+        object_store,
+        path,
+        storage_opt,
+        cold_storage_config,
+    )))
+}
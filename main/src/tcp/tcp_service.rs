@@ -1,6 +1,5 @@
 use async_trait::async_trait;
 use coordinator::service::CoordinatorRef;
-use models::consistency_level::ConsistencyLevel::Any;
 use models::schema::{Precision, DEFAULT_CATALOG, DEFAULT_DATABASE};
 use models::utils::now_timestamp_millis;
 use protocol_parser::lines_convert::parse_lines_to_points;
@@ -85,7 +84,7 @@ impl Service for TcpService {
                             coord
                                 .write_points(
                                     DEFAULT_CATALOG.to_string(),
-                                    Any,
+                                    None,
                                     Precision::MS,
                                     req,
                                     None,
@@ -5,7 +5,7 @@ use coordinator::service::CoordinatorRef;
 use dateparser;
 use models::auth::privilege::{DatabasePrivilege, Privilege, TenantObjectPrivilege};
 use models::auth::user::{UserInfo, ROOT, ROOT_PWD};
-use models::consistency_level::ConsistencyLevel::Any;
+use models::consistency_level::ConsistencyLevel;
 use models::oid::Identifier;
 use models::schema::{Precision, DEFAULT_CATALOG, DEFAULT_DATABASE};
 use protocol_parser::line_protocol::parser::Parser;
@@ -31,6 +31,7 @@ const DATABASE_FIELD: &str = "_database";
 const TABLE_FIELD: &str = "_table";
 const USERNAME_FIELD: &str = "_user";
 const PASSWORD_FIELD: &str = "_password";
+const CONSISTENCY_LEVEL_FIELD: &str = "_consistency_level";
 
 const VECTOR_LOG_TABLE: &str = "__vector_log";
 const VECTOR_LOG_HOST_TAG: &str = "host";
@@ -121,6 +122,25 @@ impl VectorService {
         }
     }
 
+    pub fn get_consistency_level(event: &EventWrapper) -> Result<Option<ConsistencyLevel>, Status> {
+        let level = match &event.event {
+            Some(Event::Log(log)) => log
+                .fields
+                .get(CONSISTENCY_LEVEL_FIELD)
+                .map(|v| vector_value_to_string(v.clone())),
+            Some(Event::Metric(metric)) => metric.tags_v1.get(CONSISTENCY_LEVEL_FIELD).cloned(),
+            _ => None,
+        };
+        level
+            .map(|level| {
+                level
+                    .trim_matches('\"')
+                    .parse::<ConsistencyLevel>()
+                    .map_err(Status::invalid_argument)
+            })
+            .transpose()
+    }
+
     async fn privilege_check(
         &self,
         tenant: &str,
@@ -170,9 +190,12 @@ impl Vector for VectorService {
         let mut lines = String::new();
         let request_inner = request.into_inner();
         let event_simple = request_inner.events.first();
-        let (tenant, db) = match event_simple {
+        let (tenant, db, level) = match event_simple {
             None => return Ok(Response::new(response)),
-            Some(event) => self.get_tenant_db_and_check_privilege(event).await?,
+            Some(event) => {
+                let (tenant, db) = self.get_tenant_db_and_check_privilege(event).await?;
+                (tenant, db, Self::get_consistency_level(event)?)
+            }
         };
 
         for event in request_inner.events {
@@ -191,7 +214,7 @@ impl Vector for VectorService {
             points,
         };
         self.coord
-            .write_points(tenant, Any, Precision::NS, req, None)
+            .write_points(tenant, level, Precision::NS, req, None)
             .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
@@ -341,6 +364,7 @@ fn handle_vector_metric(mut metric: Metric) -> server::Result<String> {
     metric.tags_v1.remove(DATABASE_FIELD);
     metric.tags_v1.remove(USERNAME_FIELD);
     metric.tags_v1.remove(PASSWORD_FIELD);
+    metric.tags_v1.remove(CONSISTENCY_LEVEL_FIELD);
 
     for (key, value) in metric.tags_v1.iter() {
         line.push_str(&format!("{}={},", key, value.replace(' ', "_")));
@@ -546,6 +570,7 @@ fn handle_vector_log_trace(mut log: Log) -> server::Result<String> {
     log.fields.remove(DATABASE_FIELD);
     log.fields.remove(USERNAME_FIELD);
     log.fields.remove(PASSWORD_FIELD);
+    log.fields.remove(CONSISTENCY_LEVEL_FIELD);
     line.push_str(&format!(
         "{}={},{}={} ",
         VECTOR_LOG_HOST_TAG, host, VECTOR_TYPE_TAG_KEY, VECTOR_LOG_TYPE_TAG_VALUE
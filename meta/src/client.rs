@@ -227,6 +227,7 @@ mod test {
             attribute: NodeAttribute::Hot,
             grpc_addr: "".to_string(),
             http_addr: "127.0.0.1:8888".to_string(),
+            decommissioned: false,
         };
 
         let req = command::WriteCommand::AddDataNode(cluster.clone(), node);
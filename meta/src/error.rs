@@ -163,6 +163,22 @@ pub enum MetaError {
     #[snafu(display("Operation not support: {}", msg))]
     #[error_code(code = 34)]
     NotSupport { msg: String },
+
+    #[snafu(display("The continuous query {} already exists", name))]
+    #[error_code(code = 35)]
+    ContinuousQueryAlreadyExists { name: String },
+
+    #[snafu(display("The continuous query {} not found", name))]
+    #[error_code(code = 36)]
+    ContinuousQueryNotFound { name: String },
+
+    #[snafu(display("The materialized view {} already exists", name))]
+    #[error_code(code = 37)]
+    MaterializedViewAlreadyExists { name: String },
+
+    #[snafu(display("The materialized view {} not found", name))]
+    #[error_code(code = 38)]
+    MaterializedViewNotFound { name: String },
 }
 impl MetaError {
     pub fn error_code(&self) -> &dyn ErrorCode {
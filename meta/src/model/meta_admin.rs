@@ -4,6 +4,7 @@ use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use config::Config;
 use models::auth::user::{admin_user, User, UserDesc, UserOptions};
@@ -399,6 +400,7 @@ impl AdminMeta {
                 self.config.host.clone(),
                 self.config.cluster.http_listen_port,
             ),
+            decommissioned: false,
         };
 
         let cluster_name = self.config.cluster.name.clone();
@@ -419,6 +421,17 @@ impl AdminMeta {
         nodes
     }
 
+    /// Mark the data node decommissioned, no vnode is placed on it any more.
+    pub async fn decommission_data_node(&self, id: NodeId) -> MetaResult<()> {
+        let req = command::WriteCommand::DecommissionDataNode(self.cluster(), id);
+        self.client.write::<()>(&req).await
+    }
+
+    pub async fn node_metrics(&self) -> MetaResult<Vec<NodeMetrics>> {
+        let req = command::ReadCommand::NodeMetrics(self.cluster());
+        self.client.read::<Vec<NodeMetrics>>(&req).await
+    }
+
     pub async fn report_node_metrics(&self) -> MetaResult<()> {
         let disk_free = match get_disk_info(&self.config.storage.path) {
             Ok(size) => size,
@@ -451,6 +464,23 @@ impl AdminMeta {
 
         self.client.write::<()>(&req).await
     }
+
+    /// Tries to hold the named lease for `ttl`, returns whether this node holds it.
+    ///
+    /// The holder keeps the lease by acquiring it again before it expires,
+    /// so the background tasks which must run on only one node check it before each run.
+    pub async fn acquire_lease(&self, name: &str, ttl: Duration) -> MetaResult<bool> {
+        let req = command::WriteCommand::AcquireLease(
+            self.cluster(),
+            name.to_string(),
+            self.node_id(),
+            now_timestamp_secs(),
+            ttl.as_secs().max(1) as i64,
+        );
+
+        let holder = self.client.write::<NodeId>(&req).await?;
+        Ok(holder == self.node_id())
+    }
     /******************** Data Node Operation End *********************/
 
     /******************** User Operation Begin *********************/
@@ -663,6 +693,14 @@ impl AdminMeta {
         list
     }
 
+    pub async fn cold_bucket(&self) -> Vec<ColdBucketInfo> {
+        let mut list = vec![];
+        for (_key, val) in self.tenants.read().iter() {
+            list.append(&mut val.cold_bucket());
+        }
+        list
+    }
+
     pub fn limiter(&self, tenant: &str) -> Arc<dyn RequestLimiter> {
         match self.limiters.read().get(tenant) {
             Some(limiter) => limiter.clone(),
@@ -11,7 +11,9 @@ use models::auth::role::{CustomTenantRole, SystemTenantRole, TenantRoleIdentifie
 use models::auth::user::UserDesc;
 use models::meta_data::*;
 use models::oid::{Identifier, Oid};
-use models::schema::{DatabaseSchema, ExternalTableSchema, TableSchema, Tenant, TskvTableSchema};
+use models::schema::{
+    DatabaseSchema, ExternalTableSchema, MaterializedView, TableSchema, Tenant, TskvTableSchema,
+};
 use parking_lot::RwLock;
 use store::command;
 use trace::info;
@@ -42,6 +44,14 @@ impl TenantMeta {
         }
     }
 
+    /// A mock with the given meta data, which doesn't connect to meta.
+    pub fn mock_with_data(data: TenantMetaData) -> Self {
+        Self {
+            data: RwLock::new(data),
+            ..Self::mock()
+        }
+    }
+
     pub async fn new(cluster: String, tenant: Tenant, meta_url: String) -> MetaResult<Arc<Self>> {
         let client = Arc::new(Self {
             cluster,
@@ -381,6 +391,66 @@ impl TenantMeta {
 
     // tenant role end
 
+    // continuous query start
+
+    pub async fn create_continuous_query(&self, info: ContinuousQueryInfo) -> MetaResult<()> {
+        let req = command::WriteCommand::CreateContinuousQuery(
+            self.cluster.clone(),
+            self.tenant_name(),
+            info,
+        );
+
+        self.client.write::<()>(&req).await
+    }
+
+    pub async fn continuous_query(&self, name: &str) -> MetaResult<Option<ContinuousQueryInfo>> {
+        let req = command::ReadCommand::ContinuousQuery(
+            self.cluster.clone(),
+            self.tenant_name(),
+            name.to_string(),
+        );
+
+        self.client.read::<Option<ContinuousQueryInfo>>(&req).await
+    }
+
+    pub async fn continuous_queries(&self) -> MetaResult<Vec<ContinuousQueryInfo>> {
+        let req = command::ReadCommand::ContinuousQueries(self.cluster.clone(), self.tenant_name());
+
+        self.client.read::<Vec<ContinuousQueryInfo>>(&req).await
+    }
+
+    pub async fn update_continuous_query_watermark(
+        &self,
+        name: &str,
+        watermark: i64,
+    ) -> MetaResult<()> {
+        let req = command::WriteCommand::UpdateContinuousQueryWatermark(
+            self.cluster.clone(),
+            self.tenant_name(),
+            name.to_string(),
+            watermark,
+        );
+
+        self.client.write::<()>(&req).await
+    }
+
+    pub async fn drop_continuous_query(&self, name: &str) -> MetaResult<bool> {
+        let req = command::WriteCommand::DropContinuousQuery(
+            self.cluster.clone(),
+            self.tenant_name(),
+            name.to_string(),
+        );
+
+        let rsp = self.client.write::<bool>(&req).await;
+        if let Err(MetaError::ContinuousQueryNotFound { name: _ }) = rsp {
+            Ok(false)
+        } else {
+            rsp
+        }
+    }
+
+    // continuous query end
+
     pub async fn alter_db_schema(&self, info: &DatabaseSchema) -> MetaResult<()> {
         let req =
             command::WriteCommand::AlterDB(self.cluster.clone(), self.tenant_name(), info.clone());
@@ -443,6 +513,65 @@ impl TenantMeta {
         Ok(())
     }
 
+    /// Creates the materialized view and its table.
+    pub async fn create_materialized_view(
+        &self,
+        table: &TableSchema,
+        view: &MaterializedView,
+    ) -> MetaResult<()> {
+        let req = command::WriteCommand::CreateMaterializedView(
+            self.cluster.clone(),
+            self.tenant_name(),
+            table.clone(),
+            view.clone(),
+        );
+
+        let rsp = self.client.write::<TenantMetaData>(&req).await?;
+        {
+            let mut data = self.data.write();
+            if rsp.version > data.version {
+                *data = rsp;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_materialized_view_watermark(
+        &self,
+        db: &str,
+        name: &str,
+        watermark: i64,
+    ) -> MetaResult<()> {
+        let req = command::WriteCommand::UpdateMaterializedViewWatermark(
+            self.cluster.clone(),
+            self.tenant_name(),
+            db.to_string(),
+            name.to_string(),
+            watermark,
+        );
+
+        self.client.write::<()>(&req).await
+    }
+
+    pub fn get_materialized_view(&self, db: &str, name: &str) -> Option<MaterializedView> {
+        self.data.read().materialized_view(db, name)
+    }
+
+    /// Returns the materialized views which aggregate the given table.
+    pub fn get_materialized_views_of(&self, db: &str, base_table: &str) -> Vec<MaterializedView> {
+        self.data.read().materialized_views_of(db, base_table)
+    }
+
+    pub fn list_materialized_views(&self) -> Vec<MaterializedView> {
+        self.data
+            .read()
+            .dbs
+            .values()
+            .flat_map(|db| db.materialized_views.values().cloned())
+            .collect()
+    }
+
     pub async fn update_table(&self, schema: &TableSchema) -> MetaResult<()> {
         let req = command::WriteCommand::UpdateTable(
             self.cluster.clone(),
@@ -590,6 +719,28 @@ impl TenantMeta {
         list
     }
 
+    /// The buckets older than the cold after of their databases, expired buckets excluded.
+    pub fn cold_bucket(&self) -> Vec<ColdBucketInfo> {
+        let mut list = vec![];
+        for (key, val) in self.data.read().dbs.iter() {
+            let Some(time_to_cold) = val.schema.time_to_cold_node() else {
+                continue;
+            };
+            let time_to_expired = val.schema.time_to_expired();
+            for bucket in val.buckets.iter() {
+                if bucket.end_time < time_to_cold && bucket.end_time >= time_to_expired {
+                    list.push(ColdBucketInfo {
+                        tenant: self.tenant_name(),
+                        database: key.clone(),
+                        bucket: bucket.clone(),
+                    })
+                }
+            }
+        }
+
+        list
+    }
+
     pub fn get_vnode_all_info(&self, id: u32) -> Option<VnodeAllInfo> {
         let data = self.data.read();
         for (db_name, db_info) in data.dbs.iter() {
@@ -753,6 +904,22 @@ impl TenantMeta {
                     db.tables.remove(tab_name);
                 }
             }
+        } else if len == 8
+            && strs[6] == key_path::MATERIALIZED_VIEWS
+            && strs[4] == key_path::DBS
+            && strs[2] == key_path::TENANTS
+        {
+            let db_name = strs[5];
+            let view_name = strs[7];
+            if let Some(db) = self.data.write().dbs.get_mut(db_name) {
+                if entry.tye == command::ENTRY_LOG_TYPE_SET {
+                    if let Ok(info) = serde_json::from_str::<MaterializedView>(&entry.val) {
+                        db.materialized_views.insert(view_name.to_string(), info);
+                    }
+                } else if entry.tye == command::ENTRY_LOG_TYPE_DEL {
+                    db.materialized_views.remove(view_name);
+                }
+            }
         } else if len == 8
             && strs[6] == key_path::BUCKETS
             && strs[4] == key_path::DBS
@@ -7,7 +7,7 @@ use models::auth::role::{SystemTenantRole, TenantRoleIdentifier};
 use models::auth::user::UserOptions;
 use models::meta_data::*;
 use models::oid::Oid;
-use models::schema::{DatabaseSchema, TableSchema, TenantOptions};
+use models::schema::{DatabaseSchema, MaterializedView, TableSchema, TenantOptions};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast;
@@ -44,9 +44,15 @@ pub enum WriteCommand {
     // cluster, node info
     AddDataNode(String, NodeInfo),
 
+    // cluster, node id
+    DecommissionDataNode(String, NodeId),
+
     //cluster, node metrics
     ReportNodeMetrics(String, NodeMetrics),
 
+    // cluster, lease name, node id, now, ttl (in seconds)
+    AcquireLease(String, String, NodeId, i64, i64),
+
     // cluster, tenant, db schema
     CreateDB(String, String, DatabaseSchema),
 
@@ -108,6 +114,18 @@ pub enum WriteCommand {
     // cluster, privileges, role_name, tenant_name
     RevokePrivileges(String, Vec<(DatabasePrivilege, String)>, String, String),
 
+    // cluster, tenant_name, continuous query
+    CreateContinuousQuery(String, String, ContinuousQueryInfo),
+    // cluster, tenant_name, name
+    DropContinuousQuery(String, String, String),
+    // cluster, tenant_name, name, watermark
+    UpdateContinuousQueryWatermark(String, String, String, i64),
+
+    // cluster, tenant_name, view table, view
+    CreateMaterializedView(String, String, TableSchema, MaterializedView),
+    // cluster, tenant_name, db, name, watermark
+    UpdateMaterializedViewWatermark(String, String, String, String, i64),
+
     Set {
         key: String,
         value: String,
@@ -146,6 +164,10 @@ pub enum ReadCommand {
     Tenants(String),
     // cluster, tenant, db, table
     TableSchema(String, String, String, String),
+    // cluster, tenant_name, name
+    ContinuousQuery(String, String, String),
+    // cluster, tenant_name
+    ContinuousQueries(String, String),
 }
 
 pub const ENTRY_LOG_TYPE_SET: i32 = 1;
@@ -5,12 +5,15 @@ use models::oid::Oid;
 // **    /cluster_name/tenants/tenant ->
 // **    /cluster_name/tenants/tenant/roles/roles ->
 // **    /cluster_name/tenants/tenant/members/user_id ->
+// **    /cluster_name/tenants/tenant/continuous_queries/name -> [ContinuousQueryInfo]
 // **    /cluster_name/auto_incr_id -> id
 // **    /cluster_name/data_nodes/node_id -> [NodeInfo] 集群、数据节点等信息
+// **    /cluster_name/leases/name -> [Lease]
 
 // **    /cluster_name/tenant_name/dbs/db_name -> [DatabaseInfo] db相关信息、保留策略等
 // **    /cluster_name/tenant_name/dbs/db_name/buckets/id -> [BucketInfo] bucket相关信息
 // **    /cluster_name/tenant_name/dbs/db_name/schemas/name -> [TskvTableSchema] schema相关信息
+// **    /cluster_name/tenant_name/dbs/db_name/materialized_views/name -> [MaterializedView]
 
 pub const DBS: &str = "dbs";
 pub const USERS: &str = "users";
@@ -19,8 +22,11 @@ pub const BUCKETS: &str = "buckets";
 pub const SCHEMAS: &str = "schemas";
 pub const TENANTS: &str = "tenants";
 pub const MEMBERS: &str = "members";
+pub const CONTINUOUS_QUERIES: &str = "continuous_queries";
+pub const MATERIALIZED_VIEWS: &str = "materialized_views";
 pub const DATA_NODES: &str = "data_nodes";
 pub const AUTO_INCR_ID: &str = "auto_incr_id";
+pub const LEASES: &str = "leases";
 
 pub struct KeyPath {}
 
@@ -60,6 +66,10 @@ impl KeyPath {
         format!("/{}/data_nodes_metrics/{}", cluster, id)
     }
 
+    pub fn lease(cluster: &str, name: &str) -> String {
+        format!("/{cluster}/leases/{name}")
+    }
+
     pub fn tenant_dbs(cluster: &str, tenant: &str) -> String {
         format!("/{}/tenants/{}/dbs", cluster, tenant)
     }
@@ -91,6 +101,14 @@ impl KeyPath {
         )
     }
 
+    pub fn materialized_views(cluster: &str, tenant: &str, db: &str) -> String {
+        format!("/{cluster}/tenants/{tenant}/dbs/{db}/materialized_views")
+    }
+
+    pub fn materialized_view(cluster: &str, tenant: &str, db: &str, name: &str) -> String {
+        format!("/{cluster}/tenants/{tenant}/dbs/{db}/materialized_views/{name}")
+    }
+
     pub fn tenants(cluster: &str) -> String {
         format!("/{}/tenants/", cluster)
     }
@@ -115,6 +133,14 @@ impl KeyPath {
         format!("/{}/tenants/{}/members", cluster, tenant_name)
     }
 
+    pub fn continuous_query(cluster: &str, tenant_name: &str, name: &str) -> String {
+        format!("/{cluster}/tenants/{tenant_name}/continuous_queries/{name}")
+    }
+
+    pub fn continuous_queries(cluster: &str, tenant_name: &str) -> String {
+        format!("/{cluster}/tenants/{tenant_name}/continuous_queries")
+    }
+
     pub fn limiter(cluster: &str, tenant_name: &str) -> String {
         format!("/{cluster}/tenants/{tenant_name}/limiter")
     }
@@ -7,7 +7,7 @@ use models::auth::user::{UserDesc, UserOptions};
 use models::meta_data::*;
 use models::node_info::NodeStatus;
 use models::oid::{Identifier, Oid, UuidGenerator};
-use models::schema::{DatabaseSchema, TableSchema, Tenant, TenantOptions};
+use models::schema::{DatabaseSchema, MaterializedView, TableSchema, Tenant, TenantOptions};
 use openraft::{EffectiveMembership, LogId};
 use serde::{Deserialize, Serialize};
 use serde_json::{from_slice, from_str};
@@ -357,11 +357,15 @@ impl StateMachine {
                 .children_data::<BucketInfo>(&KeyPath::tenant_db_buckets(cluster, tenant, key))?;
             let tables =
                 self.children_data::<TableSchema>(&KeyPath::tenant_schemas(cluster, tenant, key))?;
+            let materialized_views = self.children_data::<MaterializedView>(
+                &KeyPath::materialized_views(cluster, tenant, key),
+            )?;
 
             let info = DatabaseInfo {
                 tables,
                 schema: schema.clone(),
                 buckets: buckets.into_values().collect(),
+                materialized_views,
             };
 
             meta.dbs.insert(key.clone(), info);
@@ -410,6 +414,13 @@ impl StateMachine {
                 let path = KeyPath::tenant_schema_name(cluster, tenant_name, db_name, table_name);
                 response_encode(self.get_struct::<TableSchema>(&path))
             }
+            ReadCommand::ContinuousQuery(cluster, tenant_name, name) => {
+                let path = KeyPath::continuous_query(cluster, tenant_name, name);
+                response_encode(self.get_struct::<ContinuousQueryInfo>(&path))
+            }
+            ReadCommand::ContinuousQueries(cluster, tenant_name) => {
+                response_encode(self.process_read_continuous_queries(cluster, tenant_name))
+            }
         }
     }
 
@@ -464,6 +475,21 @@ impl StateMachine {
         Ok(roles)
     }
 
+    pub fn process_read_continuous_queries(
+        &self,
+        cluster: &str,
+        tenant_name: &str,
+    ) -> MetaResult<Vec<ContinuousQueryInfo>> {
+        let path = KeyPath::continuous_queries(cluster, tenant_name);
+
+        let queries: Vec<ContinuousQueryInfo> = self
+            .children_data::<ContinuousQueryInfo>(&path)?
+            .into_values()
+            .collect();
+
+        Ok(queries)
+    }
+
     pub fn process_read_members(
         &self,
         cluster: &str,
@@ -499,9 +525,15 @@ impl StateMachine {
             WriteCommand::AddDataNode(cluster, node) => {
                 response_encode(self.process_add_date_node(cluster, node))
             }
+            WriteCommand::DecommissionDataNode(cluster, id) => {
+                response_encode(self.process_decommission_data_node(cluster, *id))
+            }
             WriteCommand::ReportNodeMetrics(cluster, node_metrics) => {
                 response_encode(self.process_add_node_metrics(cluster, node_metrics))
             }
+            WriteCommand::AcquireLease(cluster, name, node_id, now, ttl) => {
+                response_encode(self.process_acquire_lease(cluster, name, *node_id, *now, *ttl))
+            }
             WriteCommand::CreateDB(cluster, tenant, schema) => {
                 response_encode(self.process_create_db(cluster, tenant, schema))
             }
@@ -605,6 +637,41 @@ impl StateMachine {
                 response_encode(self.process_update_vnode_repl_set(args))
             }
             WriteCommand::UpdateVnode(args) => response_encode(self.process_update_vnode(args)),
+            WriteCommand::CreateContinuousQuery(cluster, tenant_name, info) => {
+                response_encode(self.process_create_continuous_query(cluster, tenant_name, info))
+            }
+            WriteCommand::DropContinuousQuery(cluster, tenant_name, name) => {
+                response_encode(self.process_drop_continuous_query(cluster, tenant_name, name))
+            }
+            WriteCommand::UpdateContinuousQueryWatermark(cluster, tenant_name, name, watermark) => {
+                response_encode(self.process_update_continuous_query_watermark(
+                    cluster,
+                    tenant_name,
+                    name,
+                    *watermark,
+                ))
+            }
+            WriteCommand::CreateMaterializedView(cluster, tenant_name, table, view) => {
+                response_encode(self.process_create_materialized_view(
+                    cluster,
+                    tenant_name,
+                    table,
+                    view,
+                ))
+            }
+            WriteCommand::UpdateMaterializedViewWatermark(
+                cluster,
+                tenant_name,
+                db,
+                name,
+                watermark,
+            ) => response_encode(self.process_update_materialized_view_watermark(
+                cluster,
+                tenant_name,
+                db,
+                name,
+                *watermark,
+            )),
             WriteCommand::LimiterRequest {
                 cluster,
                 tenant,
@@ -713,10 +780,28 @@ impl StateMachine {
             });
         }
         let key = KeyPath::data_node_id(cluster, node.id);
-        let value = value_encode(node)?;
+        // A decommissioned node is still decommissioned after restarting
+        let decommissioned = self
+            .get(&key)?
+            .and_then(|v| from_slice::<NodeInfo>(&v).ok())
+            .map_or(false, |old| old.decommissioned);
+        let value = value_encode(&NodeInfo {
+            decommissioned,
+            ..node.clone()
+        })?;
         Ok(self.insert(&key, &value)?)
     }
 
+    fn process_decommission_data_node(&self, cluster: &str, id: NodeId) -> MetaResult<()> {
+        let key = KeyPath::data_node_id(cluster, id);
+        let mut node = self
+            .get(&key)?
+            .and_then(|v| from_slice::<NodeInfo>(&v).ok())
+            .ok_or(MetaError::NotFoundNode { id })?;
+        node.decommissioned = true;
+        Ok(self.insert(&key, &value_encode(&node)?)?)
+    }
+
     fn process_add_node_metrics(
         &self,
         cluster: &str,
@@ -727,6 +812,38 @@ impl StateMachine {
         Ok(self.insert(&key, &value)?)
     }
 
+    /// Grants the lease to the node if it's free or expired, and renews it for its holder.
+    /// Returns the node holding the lease.
+    ///
+    /// The lease is only written when it changes hands or half of the ttl has passed,
+    /// so renewing it frequently doesn't flood the watchers.
+    fn process_acquire_lease(
+        &self,
+        cluster: &str,
+        name: &str,
+        node_id: NodeId,
+        now: i64,
+        ttl: i64,
+    ) -> MetaResult<NodeId> {
+        let key = KeyPath::lease(cluster, name);
+        let lease = self.get_struct::<Lease>(&key)?;
+        if let Some(lease) = &lease {
+            if lease.node_id != node_id && lease.expire > now {
+                return Ok(lease.node_id);
+            }
+            if lease.node_id == node_id && lease.expire - now > ttl / 2 {
+                return Ok(node_id);
+            }
+        }
+
+        let lease = Lease {
+            node_id,
+            expire: now + ttl,
+        };
+        self.insert(&key, &value_encode(&lease)?)?;
+        Ok(node_id)
+    }
+
     fn process_drop_db(&self, cluster: &str, tenant: &str, db_name: &str) -> MetaResult<()> {
         let key = KeyPath::tenant_db_name(cluster, tenant, db_name);
         let _ = self.remove(&key);
@@ -741,6 +858,18 @@ impl StateMachine {
             let _ = self.remove(it);
         }
 
+        let views_path = KeyPath::materialized_views(cluster, tenant, db_name);
+        for it in self.children_fullpath(&views_path)?.iter() {
+            let _ = self.remove(it);
+        }
+
+        let queries_path = KeyPath::continuous_queries(cluster, tenant);
+        for (name, info) in self.children_data::<ContinuousQueryInfo>(&queries_path)? {
+            if info.database == db_name {
+                let _ = self.remove(&KeyPath::continuous_query(cluster, tenant, &name));
+            }
+        }
+
         Ok(())
     }
 
@@ -758,6 +887,14 @@ impl StateMachine {
             });
         }
 
+        // A materialized view goes away with its own table or its base table.
+        let views_path = KeyPath::materialized_views(cluster, tenant, db_name);
+        for (name, view) in self.children_data::<MaterializedView>(&views_path)? {
+            if name == table_name || view.base_table == table_name {
+                let _ = self.remove(&KeyPath::materialized_view(cluster, tenant, db_name, &name));
+            }
+        }
+
         Ok(self.remove(&key)?)
     }
 
@@ -838,6 +975,58 @@ impl StateMachine {
         self.to_tenant_meta_data(cluster, tenant)
     }
 
+    /// Creates the table of the view together with the view.
+    fn process_create_materialized_view(
+        &self,
+        cluster: &str,
+        tenant: &str,
+        table: &TableSchema,
+        view: &MaterializedView,
+    ) -> MetaResult<TenantMetaData> {
+        let key = KeyPath::tenant_schema_name(cluster, tenant, &view.db, &view.base_table);
+        if !self.contains_key(&key)? {
+            return Err(MetaError::TableNotFound {
+                table: view.base_table.clone(),
+            });
+        }
+
+        let key = KeyPath::materialized_view(cluster, tenant, &view.db, &view.name);
+        if self.contains_key(&key)? {
+            return Err(MetaError::MaterializedViewAlreadyExists {
+                name: view.name.clone(),
+            });
+        }
+
+        self.process_create_table(cluster, tenant, table)?;
+        self.insert(&key, &value_encode(view)?)?;
+
+        self.to_tenant_meta_data(cluster, tenant)
+    }
+
+    /// The watermark only moves forward, like the watermark of continuous queries.
+    fn process_update_materialized_view_watermark(
+        &self,
+        cluster: &str,
+        tenant: &str,
+        db: &str,
+        name: &str,
+        watermark: i64,
+    ) -> MetaResult<()> {
+        let key = KeyPath::materialized_view(cluster, tenant, db, name);
+        let mut view = self.get_struct::<MaterializedView>(&key)?.ok_or_else(|| {
+            MetaError::MaterializedViewNotFound {
+                name: name.to_string(),
+            }
+        })?;
+
+        if view.watermark.map_or(true, |w| w < watermark) {
+            view.watermark = Some(watermark);
+            self.insert(&key, &value_encode(&view)?)?;
+        }
+
+        Ok(())
+    }
+
     fn process_update_table(
         &self,
         cluster: &str,
@@ -878,7 +1067,9 @@ impl StateMachine {
             .collect();
 
         let mut filter_node_list = node_info_list;
-        filter_node_list.retain(|node_info| node_info.attribute != NodeAttribute::Cold);
+        filter_node_list.retain(|node_info| {
+            node_info.attribute != NodeAttribute::Cold && !node_info.decommissioned
+        });
 
         let mut tmp_node_list: Vec<_> = filter_node_list
             .clone()
@@ -1293,6 +1484,69 @@ impl StateMachine {
         }
     }
 
+    fn process_create_continuous_query(
+        &self,
+        cluster: &str,
+        tenant_name: &str,
+        info: &ContinuousQueryInfo,
+    ) -> MetaResult<()> {
+        let key = KeyPath::tenant_db_name(cluster, tenant_name, &info.database);
+        if !self.contains_key(&key)? {
+            return Err(MetaError::DatabaseNotFound {
+                database: info.database.clone(),
+            });
+        }
+
+        let key = KeyPath::continuous_query(cluster, tenant_name, &info.name);
+        if self.contains_key(&key)? {
+            return Err(MetaError::ContinuousQueryAlreadyExists {
+                name: info.name.clone(),
+            });
+        }
+
+        Ok(self.insert(&key, &value_encode(info)?)?)
+    }
+
+    fn process_drop_continuous_query(
+        &self,
+        cluster: &str,
+        tenant_name: &str,
+        name: &str,
+    ) -> MetaResult<bool> {
+        let key = KeyPath::continuous_query(cluster, tenant_name, name);
+        if !self.contains_key(&key)? {
+            return Err(MetaError::ContinuousQueryNotFound {
+                name: name.to_string(),
+            });
+        }
+
+        self.remove(&key)?;
+        Ok(true)
+    }
+
+    /// The watermark only moves forward, so a stale run can't make windows processed again.
+    fn process_update_continuous_query_watermark(
+        &self,
+        cluster: &str,
+        tenant_name: &str,
+        name: &str,
+        watermark: i64,
+    ) -> MetaResult<()> {
+        let key = KeyPath::continuous_query(cluster, tenant_name, name);
+        let mut info = self
+            .get_struct::<ContinuousQueryInfo>(&key)?
+            .ok_or_else(|| MetaError::ContinuousQueryNotFound {
+                name: name.to_string(),
+            })?;
+
+        if info.watermark.map_or(true, |w| w < watermark) {
+            info.watermark = Some(watermark);
+            self.insert(&key, &value_encode(&info)?)?;
+        }
+
+        Ok(())
+    }
+
     fn process_limiter_request(
         &self,
         cluster: &str,
@@ -1331,9 +1585,30 @@ impl StateMachine {
 mod test {
     use std::collections::BTreeMap;
     use std::println;
+    use std::sync::Arc;
 
     use serde::{Deserialize, Serialize};
 
+    use super::StateMachine;
+
+    #[test]
+    fn test_acquire_lease() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let sm = StateMachine::new(Arc::new(db));
+
+        // The first node gets the lease, the others wait until it expires
+        assert_eq!(sm.process_acquire_lease("c", "l", 1, 100, 10).unwrap(), 1);
+        assert_eq!(sm.process_acquire_lease("c", "l", 2, 105, 10).unwrap(), 1);
+        // Renewed by its holder
+        assert_eq!(sm.process_acquire_lease("c", "l", 1, 106, 10).unwrap(), 1);
+        assert_eq!(sm.process_acquire_lease("c", "l", 2, 115, 10).unwrap(), 1);
+        // Expired
+        assert_eq!(sm.process_acquire_lease("c", "l", 2, 116, 10).unwrap(), 2);
+        assert_eq!(sm.process_acquire_lease("c", "l", 1, 117, 10).unwrap(), 2);
+        // Other leases are independent
+        assert_eq!(sm.process_acquire_lease("c", "m", 1, 117, 10).unwrap(), 1);
+    }
+
     #[test]
     fn test_btree_map() {
         let mut map = BTreeMap::new();
@@ -373,6 +373,7 @@ async fn write_data_to_meta() {
         grpc_addr: "".to_string(),
         http_addr: "127.0.0.1:8888".to_string(),
         attribute: NodeAttribute::Hot,
+        decommissioned: false,
     };
     let req = command::WriteCommand::AddDataNode("cluster_xxx".to_string(), node);
     let cli = client::MetaHttpClient::new("127.0.0.1:8901");
@@ -0,0 +1,134 @@
+//! Continuous queries, which periodically downsample data into rollup tables.
+//!
+//! A continuous query is defined as `SELECT ... INTO <table> FROM ...`, each run
+//! executes it as `INSERT INTO <table> SELECT ...` over an aligned time range, see
+//! [`ContinuousQueryInfo::next_time_range`](models::meta_data::ContinuousQueryInfo::next_time_range).
+
+mod scheduler;
+
+use datafusion::sql::sqlparser::ast::{
+    BinaryOperator, Expr, ObjectName, Query, SetExpr, TableFactor,
+};
+use datafusion::sql::sqlparser::dialect::GenericDialect;
+use datafusion::sql::sqlparser::parser::Parser;
+use models::schema::TIME_FIELD_NAME;
+pub use scheduler::ContinuousQueryScheduler;
+use spi::{QueryError, Result};
+
+/// Splits `SELECT ... INTO <table> FROM ...` into the target table and the query without `INTO`.
+pub fn split_select_into(mut query: Query) -> Result<(ObjectName, Query)> {
+    let select = match query.body.as_mut() {
+        SetExpr::Select(select) => select,
+        _ => {
+            return Err(QueryError::InvalidContinuousQuery {
+                reason: "only SELECT ... INTO ... is supported".to_string(),
+            })
+        }
+    };
+
+    let into = select
+        .into
+        .take()
+        .ok_or_else(|| QueryError::InvalidContinuousQuery {
+            reason: "the target table must be specified by SELECT ... INTO <table>".to_string(),
+        })?;
+
+    Ok((into.name, query))
+}
+
+/// Builds `INSERT INTO <table> SELECT ...` of the continuous query,
+/// which only reads source data in `[start, end)`.
+pub fn build_insert_sql(query: &str, start: i64, end: i64) -> Result<String> {
+    let dialect = GenericDialect {};
+    let query = Parser::new(&dialect).try_with_sql(query)?.parse_query()?;
+    let (target, mut query) = split_select_into(query)?;
+
+    let time_range = Parser::new(&dialect)
+        .try_with_sql(&format!(
+            "{TIME_FIELD_NAME} >= CAST({start} AS TIMESTAMP) AND {TIME_FIELD_NAME} < CAST({end} AS TIMESTAMP)"
+        ))?
+        .parse_expr()?;
+    if !add_time_range(query.body.as_mut(), &time_range) {
+        return Err(QueryError::InvalidContinuousQuery {
+            reason: "the query must read from a table".to_string(),
+        });
+    }
+
+    Ok(format!("INSERT INTO {target} {query}"))
+}
+
+/// Adds `time_range` to the selections which read from tables directly,
+/// returns false if there is no such selection.
+fn add_time_range(body: &mut SetExpr, time_range: &Expr) -> bool {
+    match body {
+        SetExpr::Select(select) => {
+            let mut read_table = false;
+            let mut read_subquery = false;
+            for table in select.from.iter_mut() {
+                let relations = std::iter::once(&mut table.relation)
+                    .chain(table.joins.iter_mut().map(|join| &mut join.relation));
+                for relation in relations {
+                    match relation {
+                        TableFactor::Table { .. } => read_table = true,
+                        TableFactor::Derived { subquery, .. } => {
+                            read_subquery |= add_time_range(subquery.body.as_mut(), time_range)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if read_table {
+                select.selection = Some(match select.selection.take() {
+                    Some(selection) => Expr::BinaryOp {
+                        left: Box::new(Expr::Nested(Box::new(selection))),
+                        op: BinaryOperator::And,
+                        right: Box::new(time_range.clone()),
+                    },
+                    None => time_range.clone(),
+                });
+            }
+            read_table || read_subquery
+        }
+        SetExpr::Query(query) => add_time_range(query.body.as_mut(), time_range),
+        SetExpr::SetOperation { left, right, .. } => {
+            let left = add_time_range(left.as_mut(), time_range);
+            let right = add_time_range(right.as_mut(), time_range);
+            left || right
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build_insert_sql;
+
+    #[test]
+    fn test_build_insert_sql() {
+        let sql = build_insert_sql(
+            "SELECT date_bin(INTERVAL '1 minute', time) AS time, t0, avg(f0) INTO t_1m FROM t WHERE f0 > 0 GROUP BY 1, t0",
+            60_000_000_000,
+            120_000_000_000,
+        )
+        .unwrap();
+        assert!(sql.starts_with("INSERT INTO t_1m SELECT"));
+        assert!(sql.contains(
+            "FROM t WHERE (f0 > 0) AND time >= CAST(60000000000 AS TIMESTAMP) AND time < CAST(120000000000 AS TIMESTAMP)"
+        ));
+
+        let sql = build_insert_sql(
+            "SELECT w.start, v INTO t_1m FROM (SELECT time_window(time, '1m') AS w, max(f0) AS v FROM t GROUP BY w)",
+            0,
+            60,
+        )
+        .unwrap();
+        assert!(sql.contains(
+            "FROM t WHERE time >= CAST(0 AS TIMESTAMP) AND time < CAST(60 AS TIMESTAMP) GROUP BY w"
+        ));
+        assert_eq!(sql.matches("WHERE").count(), 1);
+
+        assert!(build_insert_sql("SELECT 1 INTO t_1m", 0, 60).is_err());
+        assert!(build_insert_sql("SELECT f0 FROM t", 0, 60).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use meta::model::{MetaClientRef, MetaRef};
+use models::meta_data::ContinuousQueryInfo;
+use spi::server::dbms::DBMSRef;
+use spi::service::protocol::{ContextBuilder, Query};
+use spi::Result;
+use trace::{debug, warn};
+
+use super::build_insert_sql;
+
+/// How often the continuous queries are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Only the node holding this lease in meta runs the continuous queries.
+const RUN_LEASE: &str = "continuous_query_run";
+const RUN_LEASE_TTL: Duration = Duration::from_secs(10);
+
+/// Runs the continuous queries of all tenants.
+///
+/// The queries run on the node holding the run lease in meta. The processed time range of
+/// each continuous query is recorded in meta as its watermark, so a restarted server or
+/// a new lease holder continues from the watermark. Re-computing a time range only
+/// overwrites the same rollup points, so it's fine if a range is processed more than once.
+pub struct ContinuousQueryScheduler {
+    dbms: DBMSRef,
+    meta: MetaRef,
+}
+
+impl ContinuousQueryScheduler {
+    pub fn new(dbms: DBMSRef, meta: MetaRef) -> Self {
+        Self { dbms, meta }
+    }
+
+    pub fn start(self) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                self.run_once().await;
+            }
+        });
+    }
+
+    async fn run_once(&self) {
+        match self.meta.acquire_lease(RUN_LEASE, RUN_LEASE_TTL).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                warn!(
+                    "Failed to acquire the lease to run continuous queries: {}",
+                    err
+                );
+                return;
+            }
+        }
+
+        let tenants = match self.meta.tenants().await {
+            Ok(tenants) => tenants,
+            Err(err) => {
+                warn!("Failed to list tenants for continuous queries: {}", err);
+                return;
+            }
+        };
+
+        for tenant in tenants {
+            let tenant_name = tenant.name();
+            let Some(client) = self.meta.tenant_meta(tenant_name).await else {
+                continue;
+            };
+            let queries = match client.continuous_queries().await {
+                Ok(queries) => queries,
+                Err(err) => {
+                    warn!(
+                        "Failed to list continuous queries of tenant {}: {}",
+                        tenant_name, err
+                    );
+                    continue;
+                }
+            };
+
+            for cq in queries {
+                let name = cq.name.clone();
+                if let Err(err) = self.run_continuous_query(tenant_name, &client, cq).await {
+                    warn!(
+                        "Failed to run continuous query {} of tenant {}: {}",
+                        name, tenant_name, err
+                    );
+                }
+            }
+        }
+    }
+
+    async fn run_continuous_query(
+        &self,
+        tenant_name: &str,
+        client: &MetaClientRef,
+        mut cq: ContinuousQueryInfo,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().timestamp_nanos();
+        while let Some((start, end)) = cq.next_time_range(now) {
+            debug!(
+                "Run continuous query {} of tenant {} in [{}, {})",
+                cq.name, tenant_name, start, end
+            );
+
+            let sql = build_insert_sql(&cq.query, start, end)?;
+            let user = self
+                .meta
+                .user_with_privileges(&cq.owner, Some(tenant_name))
+                .await?;
+            let ctx = ContextBuilder::new(user)
+                .with_tenant(Some(tenant_name.to_string()))
+                .with_database(Some(cq.database.clone()))
+                .build();
+            let handle = self.dbms.execute(&Query::new(ctx, sql), None).await?;
+            handle.result().chunk_result().await?;
+
+            client
+                .update_continuous_query_watermark(&cq.name, end)
+                .await?;
+            cq.watermark = Some(end);
+        }
+
+        Ok(())
+    }
+}
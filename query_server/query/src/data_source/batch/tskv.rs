@@ -8,7 +8,7 @@ use datafusion::arrow::datatypes::SchemaRef;
 use datafusion::datasource::{TableProvider, TableType};
 use datafusion::error::{DataFusionError, Result};
 use datafusion::execution::context::SessionState;
-use datafusion::logical_expr::expr::AggregateFunction;
+use datafusion::logical_expr::expr::{AggregateFunction, AggregateUDF};
 use datafusion::logical_expr::logical_plan::AggWithGrouping;
 use datafusion::logical_expr::{
     aggregate_function, Expr, TableProviderAggregationPushDown, TableProviderFilterPushDown,
@@ -27,6 +27,7 @@ use crate::data_source::sink::tskv::TskvRecordBatchSinkProvider;
 use crate::data_source::split::tskv::TableLayoutHandle;
 use crate::data_source::split::SplitManagerRef;
 use crate::data_source::WriteExecExt;
+use crate::extension::expr::aggregate_function::{FIRST_UDAF_NAME, LAST_UDAF_NAME};
 use crate::extension::expr::expr_utils;
 use crate::extension::physical::plan_node::aggregate_filter_scan::AggregateFilterTskvExec;
 use crate::extension::physical::plan_node::table_writer::TableWriterExec;
@@ -37,7 +38,7 @@ use crate::extension::physical::plan_node::tskv_exec::TskvExec;
 pub struct ClusterTable {
     coord: CoordinatorRef,
     split_manager: SplitManagerRef,
-    _meta: MetaClientRef,
+    meta: MetaClientRef,
     schema: TskvTableSchemaRef,
 }
 
@@ -105,56 +106,18 @@ impl ClusterTable {
             Column::from_name(&self.schema.column_by_index(0).unwrap_unchecked().name)
         };
 
-        let aggs = agg_expr
+        let pushed_aggs = agg_expr
             .iter()
             .map(|e| match e {
-                Expr::AggregateFunction(agg) => Ok(agg),
+                Expr::AggregateFunction(agg) => pushed_aggregate_function(agg, &time_col),
+                // first(time, field) | last(time, field), see `PushDownFirstLast`
+                Expr::AggregateUDF(agg) => pushed_aggregate_udf(agg),
                 _ => Err(DataFusionError::Plan(
                     "Invalid plan, pushed aggregate functions contains unsupported".to_string(),
                 )),
             })
             .collect::<Result<Vec<_>>>()?;
 
-        let pushed_aggs = aggs
-        .into_iter()
-        .map(|agg| {
-            let AggregateFunction { fun, args, .. } = agg;
-
-            args.iter()
-                .map(|expr| {
-                    // The parameter of the aggregate function pushed down must be a column column
-                    match expr {
-                        Expr::Column(c) => Ok(c),
-                        Expr::Literal(_) => Ok(&time_col),
-                        _ => Err(DataFusionError::Internal(format!(
-                            "Pushed aggregate functions's args contains non-column or non-literal value: {expr:?}."
-                        ))),
-                    }
-                })
-                .collect::<Result<Vec<_>>>()
-                .and_then(|columns| {
-                    // Convert pushdown aggregate functions to intermediate structures
-                    match fun {
-                        aggregate_function::AggregateFunction::Count => {
-                            let column = columns
-                                .first()
-                                .ok_or_else(|| {
-                                    DataFusionError::Internal(
-                                        "Pushed aggregate functions's args is none.".to_string(),
-                                    )
-                                })?;
-                            Ok(PushedAggregateFunction::Count(column.name.to_owned()))
-                        }
-                        // aggregate_function::AggregateFunction::Max => {},
-                        // aggregate_function::AggregateFunction::Min => {},
-                        _ => Err(DataFusionError::Internal(
-                            "Pushed aggregate functions's args is none.".to_string(),
-                        )),
-                    }
-                })
-        })
-        .collect::<Result<Vec<_>>>()?;
-
         Ok(Arc::new(AggregateFilterTskvExec::new(
             self.coord.clone(),
             proj_schema,
@@ -210,7 +173,7 @@ impl ClusterTable {
         ClusterTable {
             coord,
             split_manager,
-            _meta: meta,
+            meta,
             schema,
         }
     }
@@ -219,6 +182,20 @@ impl ClusterTable {
         self.schema.clone()
     }
 
+    pub fn meta(&self) -> &MetaClientRef {
+        &self.meta
+    }
+
+    /// Returns the table of another tskv table in the same tenant.
+    pub fn with_table_schema(&self, schema: TskvTableSchemaRef) -> Self {
+        Self {
+            coord: self.coord.clone(),
+            split_manager: self.split_manager.clone(),
+            meta: self.meta.clone(),
+            schema,
+        }
+    }
+
     // Check and return the projected schema
     fn project_schema(&self, projection: Option<&Vec<usize>>) -> Result<SchemaRef> {
         valid_project(&self.schema, projection)
@@ -295,6 +272,14 @@ impl TableProvider for ClusterTable {
             return Ok(TableProviderAggregationPushDown::Unsupported);
         }
 
+        // Values of a field converted from a tag are partly kept in series keys,
+        // the field is aggregated by the query engine.
+        let is_stored_field = |c: &Column| {
+            self.schema
+                .column(&c.name)
+                .map(|col| col.column_type.is_field() && col.converted_from_tag.is_none())
+                .unwrap_or(false)
+        };
         let result = if aggr_expr.iter().all(|e| {
             match e {
                 Expr::AggregateFunction(AggregateFunction {
@@ -304,18 +289,29 @@ impl TableProvider for ClusterTable {
                     filter,
                     order_by,
                 }) => {
-                    let support_agg_func = matches!(
-                        fun,
-                        aggregate_function::AggregateFunction::Count // TODO
-                                                                     // | aggregate_function::AggregateFunction::Max
-                                                                     // | aggregate_function::AggregateFunction::Min
-                                                                     // | aggregate_function::AggregateFunction::Sum
-                    );
-
-                    support_agg_func
-                        && args.len() == 1
+                    // first(time, field) | last(time, field) are not built-in aggregate
+                    // functions, they are pushed down by `PushDownFirstLast`.
+                    let support_arg = match fun {
                         // count(*) | count(1) | count(col)
-                        && (matches!(args[0], Expr::Column(_)) || matches!(args[0], Expr::Literal(_)))
+                        aggregate_function::AggregateFunction::Count => match args.as_slice() {
+                            [Expr::Column(c)] => self
+                                .schema
+                                .column(&c.name)
+                                .map(|col| col.converted_from_tag.is_none())
+                                .unwrap_or(true),
+                            [Expr::Literal(_)] => true,
+                            _ => false,
+                        },
+                        // max(field) | min(field) | sum(field)
+                        aggregate_function::AggregateFunction::Max
+                        | aggregate_function::AggregateFunction::Min
+                        | aggregate_function::AggregateFunction::Sum => {
+                            matches!(args.as_slice(), [Expr::Column(c)] if is_stored_field(c))
+                        }
+                        _ => false,
+                    };
+
+                    support_arg
                         // not distinct
                         && !*distinct
                         && filter.is_none()
@@ -335,18 +331,23 @@ impl TableProvider for ClusterTable {
     fn push_down_projection(&self, proj: &[usize]) -> Option<Vec<usize>> {
         let mut contain_time = false;
         let mut contain_field = false;
+        let mut contain_converted_field = false;
 
         proj.iter()
             .flat_map(|i| self.schema.column_by_index(*i))
             .for_each(|c| {
-                if c.column_type.is_field() {
+                if c.converted_from_tag.is_some() {
+                    // Rows of the series written before the conversion are not
+                    // found by the field.
+                    contain_converted_field = true;
+                } else if c.column_type.is_field() {
                     contain_field = true;
                 } else if c.column_type.is_time() {
                     contain_time = true;
                 }
             });
 
-        if contain_time && !contain_field {
+        if (contain_time || contain_converted_field) && !contain_field {
             let new_projection = proj
                 .iter()
                 .cloned()
@@ -355,7 +356,7 @@ impl TableProvider for ClusterTable {
                         .columns()
                         .iter()
                         .enumerate()
-                        .filter(|(_, c)| c.column_type.is_field())
+                        .filter(|(_, c)| c.column_type.is_field() && c.converted_from_tag.is_none())
                         .map(|(i, _)| i),
                 )
                 .collect::<Vec<usize>>();
@@ -419,3 +420,65 @@ pub fn valid_project(
 
     Ok(())
 }
+
+/// Convert pushdown aggregate functions to intermediate structures
+fn pushed_aggregate_function(
+    agg: &AggregateFunction,
+    time_col: &Column,
+) -> Result<PushedAggregateFunction> {
+    let AggregateFunction { fun, args, .. } = agg;
+
+    let columns = args
+        .iter()
+        .map(|expr| {
+            // The parameter of the aggregate function pushed down must be a column column
+            match expr {
+                Expr::Column(c) => Ok(c),
+                Expr::Literal(_) => Ok(time_col),
+                _ => Err(DataFusionError::Internal(format!(
+                    "Pushed aggregate functions's args contains non-column or non-literal value: {expr:?}."
+                ))),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let column = columns
+        .first()
+        .ok_or_else(|| {
+            DataFusionError::Internal("Pushed aggregate functions's args is none.".to_string())
+        })?
+        .name
+        .to_owned();
+
+    match fun {
+        aggregate_function::AggregateFunction::Count => Ok(PushedAggregateFunction::Count(column)),
+        aggregate_function::AggregateFunction::Max => Ok(PushedAggregateFunction::Max(column)),
+        aggregate_function::AggregateFunction::Min => Ok(PushedAggregateFunction::Min(column)),
+        aggregate_function::AggregateFunction::Sum => Ok(PushedAggregateFunction::Sum(column)),
+        _ => Err(DataFusionError::Internal(format!(
+            "Pushed aggregate function {fun} is not supported."
+        ))),
+    }
+}
+
+/// Convert pushdown `first(time, field)` and `last(time, field)` to intermediate structures
+fn pushed_aggregate_udf(agg: &AggregateUDF) -> Result<PushedAggregateFunction> {
+    let AggregateUDF { fun, args, .. } = agg;
+
+    let column = match args.as_slice() {
+        [Expr::Column(_), Expr::Column(c)] => c.name.to_owned(),
+        _ => {
+            return Err(DataFusionError::Internal(format!(
+                "Pushed aggregate function {}'s args are not (time, field): {args:?}.",
+                fun.name
+            )))
+        }
+    };
+
+    match fun.name.as_str() {
+        FIRST_UDAF_NAME => Ok(PushedAggregateFunction::First(column)),
+        LAST_UDAF_NAME => Ok(PushedAggregateFunction::Last(column)),
+        name => Err(DataFusionError::Internal(format!(
+            "Pushed aggregate function {name} is not supported."
+        ))),
+    }
+}
@@ -18,6 +18,7 @@ pub struct TskvRecordBatchSink {
     coord: CoordinatorRef,
     partition: usize,
     schema: TskvTableSchemaRef,
+    consistency_level: Option<ConsistencyLevel>,
 
     metrics: TskvSinkMetrics,
     span_recorder: SpanRecorder,
@@ -65,7 +66,7 @@ impl RecordBatchSink for TskvRecordBatchSink {
         self.coord
             .write_points(
                 self.schema.tenant.clone(),
-                ConsistencyLevel::Any,
+                self.consistency_level,
                 time_unit.into(),
                 req,
                 span_recorder.span_ctx(),
@@ -111,6 +112,10 @@ impl RecordBatchSinkProvider for TskvRecordBatchSinkProvider {
         partition: usize,
     ) -> Box<dyn RecordBatchSink> {
         let parent_span_ctx = context.session_config().get_extension::<SpanContext>();
+        let consistency_level = context
+            .session_config()
+            .get_extension::<ConsistencyLevel>()
+            .map(|e| *e);
         let span_recorder = SpanRecorder::new(
             parent_span_ctx.child_span(format!("TskvRecordBatchSink ({partition})")),
         );
@@ -119,6 +124,7 @@ impl RecordBatchSinkProvider for TskvRecordBatchSinkProvider {
             coord: self.coord.clone(),
             partition,
             schema: self.schema.clone(),
+            consistency_level,
             metrics: TskvSinkMetrics::new(metrics, partition),
             span_recorder,
         })
@@ -7,19 +7,21 @@ use meta::error::MetaError;
 use meta::model::MetaClientRef;
 use models::auth::user::admin_user;
 use models::oid::Oid;
+use models::utils::now_timestamp_nanos;
 use spi::query::ast::ExtStatement;
 use spi::query::datasource::stream::StreamProviderManagerRef;
 use spi::query::dispatcher::{QueryDispatcher, QueryInfo, QueryStatus};
 use spi::query::execution::{Output, QueryStateMachine};
 use spi::query::function::FuncMetaManagerRef;
-use spi::query::logical_planner::{LogicalPlanner, Plan};
+use spi::query::logical_planner::{LogicalPlanner, Plan, QueryPlan};
 use spi::query::parser::Parser;
 use spi::query::session::{SessionCtx, SessionCtxFactory};
 use spi::service::protocol::{ContextBuilder, Query, QueryId};
 use spi::{QueryError, Result};
-use trace::{info, SpanContext, SpanExt, SpanRecorder, TraceExporter};
+use trace::{debug, info, SpanContext, SpanExt, SpanRecorder, TraceExporter};
 
 use super::query_tracker::QueryTracker;
+use super::result_cache::{CacheLookup, CacheablePlan, QueryResultCacheRef};
 use crate::data_source::split::SplitManagerRef;
 use crate::execution::factory::QueryExecutionFactoryRef;
 use crate::metadata::{
@@ -45,6 +47,7 @@ pub struct SimpleQueryDispatcher {
     func_manager: FuncMetaManagerRef,
     stream_provider_manager: StreamProviderManagerRef,
     trace_collector: Option<Arc<dyn TraceExporter>>,
+    result_cache: Option<QueryResultCacheRef>,
 }
 
 #[async_trait]
@@ -111,6 +114,35 @@ impl QueryDispatcher for SimpleQueryDispatcher {
             Some(plan) => plan,
             None => return Ok(Output::Nil(())),
         };
+
+        if let Some(result_cache) = &self.result_cache {
+            if let Plan::Query(query_plan) = &logical_plan {
+                if let Some(cacheable_plan) =
+                    result_cache.cacheable_plan(&query_state_machine.session, query_plan)
+                {
+                    return self
+                        .execute_cacheable_query(
+                            result_cache,
+                            cacheable_plan,
+                            query_plan,
+                            query_state_machine,
+                        )
+                        .await;
+                }
+            }
+            if let Plan::DDL(_) = &logical_plan {
+                // e.g. drop table, delete from table
+                // The caches of other nodes are invalidated by the written logs of the
+                // data nodes, see `QueryResultCache::start_remote_invalidation`.
+                let tenant = query_state_machine.session.tenant().to_string();
+                let result = self
+                    .execute_logical_plan(logical_plan, query_state_machine)
+                    .await;
+                result_cache.invalidate_tenant(&tenant);
+                return result;
+            }
+        }
+
         let result = self
             .execute_logical_plan(logical_plan, query_state_machine)
             .await?;
@@ -240,6 +272,50 @@ impl SimpleQueryDispatcher {
             .await
     }
 
+    async fn execute_cacheable_query(
+        &self,
+        result_cache: &QueryResultCacheRef,
+        cacheable_plan: CacheablePlan,
+        query_plan: &QueryPlan,
+        query_state_machine: Arc<QueryStateMachine>,
+    ) -> Result<Output> {
+        let write_seq = result_cache.write_seq();
+        let now_ns = now_timestamp_nanos();
+
+        match result_cache.get(&cacheable_plan, now_ns)? {
+            CacheLookup::Hit(result) => {
+                debug!("Query result cache hit: {}", query_state_machine.query_id);
+                Ok(Output::StreamData(result.into_stream()?))
+            }
+            CacheLookup::Refresh(refresh) => {
+                debug!(
+                    "Query result cache refresh: {}",
+                    query_state_machine.query_id
+                );
+                let refresh_plan = refresh.plan(query_plan)?;
+                let batches = self
+                    .execute_logical_plan(Plan::Query(refresh_plan), query_state_machine)
+                    .await?
+                    .chunk_result()
+                    .await?;
+                let result = refresh.merge(batches, write_seq, now_ns)?;
+                result_cache.insert(cacheable_plan, result.clone());
+                Ok(Output::StreamData(result.into_stream()?))
+            }
+            CacheLookup::Miss => {
+                let output = self
+                    .execute_logical_plan(Plan::Query(query_plan.clone()), query_state_machine)
+                    .await?;
+                match output {
+                    Output::StreamData(stream) => Ok(Output::StreamData(
+                        result_cache.cache_stream(cacheable_plan, stream, write_seq, now_ns),
+                    )),
+                    nil => Ok(nil),
+                }
+            }
+        }
+    }
+
     async fn build_scheme_provider(&self, session: &SessionCtx) -> Result<MetadataProvider> {
         let meta_client = self.build_current_session_meta_client(session).await?;
         let current_session_table_provider =
@@ -303,6 +379,7 @@ pub struct SimpleQueryDispatcherBuilder {
     func_manager: Option<FuncMetaManagerRef>,
     stream_provider_manager: Option<StreamProviderManagerRef>,
     trace_collector: Option<Arc<dyn TraceExporter>>,
+    result_cache: Option<QueryResultCacheRef>,
 }
 
 impl SimpleQueryDispatcherBuilder {
@@ -370,6 +447,11 @@ impl SimpleQueryDispatcherBuilder {
         self
     }
 
+    pub fn with_result_cache(mut self, result_cache: QueryResultCacheRef) -> Self {
+        self.result_cache = Some(result_cache);
+        self
+    }
+
     pub fn build(self) -> Result<SimpleQueryDispatcher> {
         let coord = self.coord.ok_or_else(|| QueryError::BuildQueryDispatcher {
             err: "lost of coord".to_string(),
@@ -430,6 +512,7 @@ impl SimpleQueryDispatcherBuilder {
                 })?;
 
         let trace_collector = self.trace_collector;
+        let result_cache = self.result_cache;
 
         Ok(SimpleQueryDispatcher {
             coord,
@@ -443,6 +526,7 @@ impl SimpleQueryDispatcherBuilder {
             func_manager,
             stream_provider_manager,
             trace_collector,
+            result_cache,
         })
     }
 }
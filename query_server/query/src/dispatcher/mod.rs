@@ -6,6 +6,7 @@ use spi::Result;
 pub mod manager;
 pub mod persister;
 pub mod query_tracker;
+pub mod result_cache;
 
 #[async_trait]
 pub trait QueryPersister {
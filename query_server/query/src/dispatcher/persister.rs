@@ -103,7 +103,17 @@ impl QueryPersister for LocalQueryPersister {
 
         let mut entries = fs::read_dir(self.root_dir()).await?;
         while let Some(entry) = entries.next_entry().await? {
-            let bytes = fs::read(entry.path().join(QUERY_INFO_FILE_NAME)).await?;
+            let bytes = match fs::read(entry.path().join(QUERY_INFO_FILE_NAME)).await {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    trace::warn!(
+                        "Failed to read query info from dir: {:?}, error: {}",
+                        entry.path(),
+                        err,
+                    );
+                    continue;
+                }
+            };
             match bincode::deserialize::<QueryInfo>(&bytes) {
                 Ok(query_info) => {
                     result.push(query_info);
@@ -0,0 +1,1096 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use coordinator::service::CoordinatorRef;
+use coordinator::written_log::WrittenEvent;
+use coordinator::WrittenTimeRange;
+use datafusion::arrow::array::{as_struct_array, Array, ArrayRef, BooleanArray, Int64Array};
+use datafusion::arrow::compute::{cast, filter_record_batch};
+use datafusion::arrow::datatypes::{DataType, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::tree_node::{TreeNode, VisitRecursion};
+use datafusion::common::{Column, DFSchemaRef, DataFusionError, Result as DFResult};
+use datafusion::logical_expr::expr::{ScalarFunction, ScalarUDF, Sort};
+use datafusion::logical_expr::{
+    BuiltinScalarFunction, GetIndexedField, LogicalPlan, LogicalPlanBuilder, Volatility,
+};
+use datafusion::physical_plan::memory::MemoryStream;
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use datafusion::prelude::{lit, Expr};
+use datafusion::scalar::ScalarValue;
+use futures::{Stream, StreamExt};
+use lru_cache::ShardedCache;
+use parking_lot::Mutex;
+use spi::query::logical_planner::QueryPlan;
+use spi::query::session::SessionCtx;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use trace::{debug, warn};
+
+use crate::data_source::source_downcast_adapter;
+use crate::data_source::table_source::TableHandle;
+use crate::extension::expr::expr_utils::{literal_interval_ns, literal_timestamp_ns};
+use crate::extension::expr::{TIME_WINDOW, WINDOW_START};
+
+pub type QueryResultCacheRef = Arc<QueryResultCache>;
+
+/// Max number of writes recorded for a table, the results computed before
+/// the writes dropped from the log are recomputed entirely.
+const MAX_WRITES_PER_TABLE: usize = 64;
+/// Interval to fetch the time ranges written into the vnodes of the data nodes.
+const REMOTE_INVALIDATION_INTERVAL: Duration = Duration::from_secs(1);
+/// The evaluation time of `now()` is not exactly the time the query starts,
+/// so the time buckets next to the shifted lower bound are recomputed with a margin.
+const NOW_SHIFT_MARGIN_NS: i64 = 1_000_000_000;
+
+/// Caches the results of the queries on tskv tables, so the dashboards which
+/// re-issue the same queries every few seconds don't scan the tables every time.
+///
+/// The cached results are keyed on the logical plan before optimization, in which
+/// `now()` is not evaluated yet, and the tenant/database of the session.
+///
+/// Writes coordinated by this node mark the results of the written tables dirty
+/// since the minimum written timestamp. The dirty results which are grouped by a
+/// time bucket (`date_bin` or tumbling `time_window`) are refreshed by recomputing
+/// the buckets after the dirty timestamp only, other dirty results are recomputed
+/// entirely. Writes coordinated by other nodes are fetched from the written logs of
+/// the data nodes periodically, so they may be missed by the cached results for up to
+/// `REMOTE_INVALIDATION_INTERVAL`. The results also expire after the ttl.
+pub struct QueryResultCache {
+    results: ShardedCache<CacheKey, CachedResult>,
+    write_log: Mutex<WriteLog>,
+    max_memory: usize,
+    ttl: Duration,
+}
+
+impl QueryResultCache {
+    pub fn new(max_memory: usize, ttl: Duration) -> Self {
+        Self {
+            results: ShardedCache::with_capacity(max_memory),
+            write_log: Default::default(),
+            max_memory,
+            ttl,
+        }
+    }
+
+    /// Mark the cached results dirty by the time ranges written through the coordinator.
+    pub fn start_invalidation(self: &Arc<Self>, mut receiver: Receiver<WrittenTimeRange>) {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(written) => cache.write_log.lock().record(written),
+                    Err(RecvError::Lagged(num)) => {
+                        warn!(
+                            "Lagged {} written time ranges, invalidate all query results",
+                            num
+                        );
+                        cache.write_log.lock().reset();
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Mark the cached results dirty by the time ranges written into the vnodes of the data
+    /// nodes, so the writes and DDLs coordinated by other nodes are observed within
+    /// `REMOTE_INVALIDATION_INTERVAL`.
+    pub fn start_remote_invalidation(self: &Arc<Self>, coord: CoordinatorRef) {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            // node id -> sequence of the written log fetched from the node
+            let mut seqs: HashMap<u64, u64> = HashMap::new();
+            loop {
+                tokio::time::sleep(REMOTE_INVALIDATION_INTERVAL).await;
+
+                let nodes = coord.meta_manager().data_nodes().await;
+                seqs.retain(|id, _| nodes.iter().any(|node| node.id == *id));
+                let deltas = futures::future::join_all(nodes.iter().map(|node| {
+                    let since_seq = seqs.get(&node.id).copied().unwrap_or(0);
+                    let coord = coord.clone();
+                    async move {
+                        if node.id == coord.node_id() && coord.store_engine().is_some() {
+                            Ok(coord.written_log().since(since_seq))
+                        } else {
+                            coord.fetch_written_time_ranges(node.id, since_seq).await
+                        }
+                    }
+                }))
+                .await;
+
+                for (node, delta) in nodes.iter().zip(deltas) {
+                    let delta = match delta {
+                        Ok(delta) => delta,
+                        Err(err) => {
+                            // The writes are fetched after the node is reachable again.
+                            debug!(
+                                "Failed to fetch written time ranges of node {}: {}",
+                                node.id, err
+                            );
+                            continue;
+                        }
+                    };
+                    seqs.insert(node.id, delta.seq);
+                    let mut write_log = cache.write_log.lock();
+                    if delta.reset {
+                        // The writes before the first fetch from the node are unknown too.
+                        write_log.reset();
+                    }
+                    for event in delta.events {
+                        match event {
+                            WrittenEvent::Written(written) => write_log.record(written),
+                            WrittenEvent::TenantChanged(tenant) => write_log.reset_tenant(&tenant),
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Mark all the cached results of the tenant dirty, e.g. after a table is dropped.
+    pub fn invalidate_tenant(&self, tenant: &str) {
+        self.write_log.lock().reset_tenant(tenant);
+    }
+
+    /// The sequence of the write log, which should be taken before the query is executed.
+    pub fn write_seq(&self) -> u64 {
+        self.write_log.lock().seq
+    }
+
+    /// Returns None if the results of the plan can't be cached, e.g. it reads tables
+    /// other than tskv tables or contains volatile functions.
+    pub fn cacheable_plan(&self, session: &SessionCtx, plan: &QueryPlan) -> Option<CacheablePlan> {
+        if plan.is_explain() {
+            return None;
+        }
+
+        let mut visitor = CacheableVisitor::default();
+        let _ = plan.df_plan.apply(&mut |plan| visitor.visit(plan));
+        if !visitor.cacheable || visitor.tables.is_empty() {
+            return None;
+        }
+
+        let bucket = TimeBucket::find(&plan.df_plan);
+        // The results depending on now() can be reused by recomputing the shifted buckets only
+        if visitor.uses_now && bucket.is_none() {
+            return None;
+        }
+
+        Some(CacheablePlan {
+            key: CacheKey {
+                tenant: session.tenant().to_string(),
+                database: session.default_database().to_string(),
+                plan: plan.df_plan.display_indent_schema().to_string(),
+            },
+            tables: visitor.tables,
+            bucket,
+            uses_now: visitor.uses_now,
+        })
+    }
+
+    pub fn get(&self, plan: &CacheablePlan, now_ns: i64) -> DFResult<CacheLookup> {
+        let cached = match self.results.get(&plan.key) {
+            Some(cached) => cached.clone(),
+            None => return Ok(CacheLookup::Miss),
+        };
+
+        if cached.computed_at.elapsed() > self.ttl {
+            self.results.remove(&plan.key);
+            return Ok(CacheLookup::Miss);
+        }
+
+        let dirty_since =
+            self.write_log
+                .lock()
+                .dirty_since(&plan.key.tenant, &plan.tables, cached.write_seq);
+        if dirty_since.is_none() && !plan.uses_now {
+            return Ok(CacheLookup::Hit(cached));
+        }
+
+        let bucket = match &plan.bucket {
+            Some(bucket) => bucket,
+            None => return Ok(CacheLookup::Miss),
+        };
+        match bucket.refresh_range(&cached, dirty_since, plan.uses_now, now_ns)? {
+            Some(range) => Ok(CacheLookup::Refresh(Refresh {
+                bucket: bucket.clone(),
+                range,
+                cached,
+            })),
+            None => Ok(CacheLookup::Miss),
+        }
+    }
+
+    pub fn insert(&self, plan: CacheablePlan, result: CachedResult) {
+        let charge = result.memory_size().max(1);
+        debug!("Cache query result of {} bytes: {}", charge, plan.key);
+        let _ = self.results.insert_opt(plan.key, result, charge, None);
+    }
+
+    /// Wrap the result stream, the result is cached after the stream is consumed entirely.
+    pub fn cache_stream(
+        self: &Arc<Self>,
+        plan: CacheablePlan,
+        stream: SendableRecordBatchStream,
+        write_seq: u64,
+        now_ns: i64,
+    ) -> SendableRecordBatchStream {
+        Box::pin(CachingRecordBatchStream {
+            inner: stream,
+            cache: self.clone(),
+            pending: Some((plan, write_seq, now_ns)),
+            batches: vec![],
+            memory_size: 0,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    tenant: String,
+    database: String,
+    plan: String,
+}
+
+impl Display for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}: {}", self.tenant, self.database, self.plan)
+    }
+}
+
+/// (tenant, database, table)
+type TableKey = (String, String, String);
+
+pub struct CacheablePlan {
+    key: CacheKey,
+    tables: Vec<TableKey>,
+    bucket: Option<TimeBucket>,
+    uses_now: bool,
+}
+
+pub enum CacheLookup {
+    Hit(CachedResult),
+    /// Only some time buckets of the cached result need to be recomputed
+    Refresh(Refresh),
+    Miss,
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedResult {
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+    /// The sequence of the write log before the result is computed
+    write_seq: u64,
+    /// The evaluation time of `now()` in nanoseconds
+    now_ns: i64,
+    computed_at: Instant,
+}
+
+impl CachedResult {
+    pub fn new(schema: SchemaRef, batches: Vec<RecordBatch>, write_seq: u64, now_ns: i64) -> Self {
+        Self {
+            schema,
+            batches,
+            write_seq,
+            now_ns,
+            computed_at: Instant::now(),
+        }
+    }
+
+    pub fn into_stream(self) -> DFResult<SendableRecordBatchStream> {
+        Ok(Box::pin(MemoryStream::try_new(
+            self.batches,
+            self.schema,
+            None,
+        )?))
+    }
+
+    fn memory_size(&self) -> usize {
+        self.batches.iter().map(|e| e.get_array_memory_size()).sum()
+    }
+}
+
+/// Time buckets out of `[leading_end, trailing_start)` need to be recomputed.
+#[derive(Debug, Clone, Copy)]
+struct RefreshRange {
+    leading_end: Option<i64>,
+    trailing_start: Option<i64>,
+}
+
+impl RefreshRange {
+    fn is_leading(&self, bucket: i64) -> bool {
+        self.leading_end.map_or(false, |end| bucket < end)
+    }
+
+    fn is_trailing(&self, bucket: i64) -> bool {
+        self.trailing_start.map_or(false, |start| bucket >= start)
+    }
+
+    fn contains(&self, bucket: i64) -> bool {
+        self.is_leading(bucket) || self.is_trailing(bucket)
+    }
+}
+
+pub struct Refresh {
+    bucket: TimeBucket,
+    range: RefreshRange,
+    cached: CachedResult,
+}
+
+impl Refresh {
+    /// The plan which computes the time buckets to be refreshed only.
+    pub fn plan(&self, plan: &QueryPlan) -> DFResult<QueryPlan> {
+        let predicate = self.bucket.refresh_predicate(&self.range)?;
+        Ok(QueryPlan {
+            df_plan: self.bucket.add_filter(&plan.df_plan, predicate)?,
+        })
+    }
+
+    /// Replace the refreshed time buckets of the cached result.
+    pub fn merge(
+        self,
+        refreshed: Vec<RecordBatch>,
+        write_seq: u64,
+        now_ns: i64,
+    ) -> DFResult<CachedResult> {
+        let Self {
+            bucket,
+            range,
+            cached,
+        } = self;
+
+        let mut leading = vec![];
+        let mut kept = vec![];
+        let mut trailing = vec![];
+        for batch in &cached.batches {
+            let mask: BooleanArray = bucket
+                .bucket_values(batch)?
+                .iter()
+                .map(|e| Some(!matches!(e, Some(v) if range.contains(*v))))
+                .collect();
+            kept.push(filter_record_batch(batch, &mask)?);
+        }
+        for batch in &refreshed {
+            let values = bucket.bucket_values(batch)?;
+            let mask: BooleanArray = values
+                .iter()
+                .map(|e| Some(matches!(e, Some(v) if range.is_leading(*v))))
+                .collect();
+            leading.push(filter_record_batch(batch, &mask)?);
+            let mask: BooleanArray = values
+                .iter()
+                .map(|e| Some(matches!(e, Some(v) if range.is_trailing(*v))))
+                .collect();
+            trailing.push(filter_record_batch(batch, &mask)?);
+        }
+
+        let batches = if bucket.descending {
+            [trailing, kept, leading].concat()
+        } else {
+            [leading, kept, trailing].concat()
+        };
+        let batches = batches.into_iter().filter(|e| e.num_rows() > 0).collect();
+
+        Ok(CachedResult::new(cached.schema, batches, write_seq, now_ns))
+    }
+}
+
+/// The time bucket which the results are grouped by.
+#[derive(Debug, Clone)]
+struct TimeBucket {
+    /// The time column in the input of the aggregate
+    time_column: Column,
+    time_data_type: DataType,
+    interval_ns: i64,
+    origin_ns: i64,
+    /// The index of the bucket column in the output
+    output_index: usize,
+    /// Whether the bucket column is the struct returned by `time_window`
+    is_window: bool,
+    /// Whether the output is sorted by the bucket column descending
+    descending: bool,
+}
+
+impl TimeBucket {
+    /// Find the time bucket in the top aggregate, which is only followed by
+    /// projections, filters, subquery aliases and a sort by the time bucket.
+    fn find(plan: &LogicalPlan) -> Option<Self> {
+        match plan {
+            LogicalPlan::Aggregate(agg) => {
+                agg.group_expr.iter().enumerate().find_map(|(idx, expr)| {
+                    Self::try_new(&expr.clone().unalias(), agg.input.schema(), idx)
+                })
+            }
+            LogicalPlan::Projection(projection) => {
+                if contains_now(&projection.expr) {
+                    return None;
+                }
+                let mut bucket = Self::find(&projection.input)?;
+                let (idx, is_window) =
+                    projection.expr.iter().enumerate().find_map(|(idx, expr)| {
+                        bucket
+                            .project(expr, projection.input.schema())
+                            .map(|is_window| (idx, is_window))
+                    })?;
+                bucket.output_index = idx;
+                bucket.is_window = is_window;
+                Some(bucket)
+            }
+            LogicalPlan::Filter(filter) => {
+                if contains_now(&[filter.predicate.clone()]) {
+                    return None;
+                }
+                Self::find(&filter.input)
+            }
+            LogicalPlan::SubqueryAlias(alias) => Self::find(&alias.input),
+            LogicalPlan::Sort(sort) => {
+                if sort.fetch.is_some() {
+                    return None;
+                }
+                let mut bucket = Self::find(&sort.input)?;
+                // The merged result is ordered by the time bucket first
+                match sort.expr.first() {
+                    Some(Expr::Sort(Sort { expr, asc, .. })) => match expr.as_ref() {
+                        Expr::Column(c)
+                            if !bucket.is_window
+                                && sort.input.schema().index_of_column(c).ok()
+                                    == Some(bucket.output_index) =>
+                        {
+                            bucket.descending = !asc;
+                            Some(bucket)
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// If the projected expression is the bucket column, returns whether it's still
+    /// the struct returned by `time_window`.
+    fn project(&self, expr: &Expr, input_schema: &DFSchemaRef) -> Option<bool> {
+        let is_bucket_column = |expr: &Expr| match expr {
+            Expr::Column(c) => input_schema.index_of_column(c).ok() == Some(self.output_index),
+            _ => false,
+        };
+
+        match expr.clone().unalias() {
+            e if is_bucket_column(&e) => Some(self.is_window),
+            // window.start
+            Expr::GetIndexedField(GetIndexedField {
+                expr,
+                key: ScalarValue::Utf8(Some(key)),
+            }) if self.is_window && key == WINDOW_START && is_bucket_column(expr.as_ref()) => {
+                Some(false)
+            }
+            _ => None,
+        }
+    }
+
+    fn try_new(expr: &Expr, input_schema: &DFSchemaRef, output_index: usize) -> Option<Self> {
+        let (time_column, interval, origin, is_window) = match expr {
+            Expr::ScalarFunction(ScalarFunction {
+                fun: BuiltinScalarFunction::DateBin,
+                args,
+            }) => match args.as_slice() {
+                [interval, Expr::Column(c)] => (c, interval, None, false),
+                [interval, Expr::Column(c), origin] => (c, interval, Some(origin), false),
+                _ => return None,
+            },
+            Expr::ScalarUDF(ScalarUDF { fun, args }) if fun.name == TIME_WINDOW => {
+                match args.as_slice() {
+                    [Expr::Column(c), window] => (c, window, None, true),
+                    // Only tumbling windows
+                    [Expr::Column(c), window, slide, rest @ ..]
+                        if rest.len() <= 1
+                            && literal_interval_ns(window)? == literal_interval_ns(slide)? =>
+                    {
+                        (c, window, rest.first(), true)
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        let time_data_type = input_schema
+            .field_from_column(time_column)
+            .ok()?
+            .data_type()
+            .clone();
+        if !matches!(time_data_type, DataType::Timestamp(_, _)) {
+            return None;
+        }
+        let interval_ns = literal_interval_ns(interval)?;
+        let origin_ns = match origin {
+            Some(origin) => literal_timestamp_ns(origin)?,
+            None => 0,
+        };
+
+        Some(Self {
+            time_column: time_column.clone(),
+            time_data_type,
+            interval_ns,
+            origin_ns,
+            output_index,
+            is_window,
+            descending: false,
+        })
+    }
+
+    fn floor(&self, ts: i64) -> i64 {
+        let origin = self.origin_ns as i128;
+        let interval = self.interval_ns as i128;
+        let floor = origin + (ts as i128 - origin).div_euclid(interval) * interval;
+        floor.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
+    /// Returns None if the cached result should be recomputed entirely.
+    fn refresh_range(
+        &self,
+        cached: &CachedResult,
+        dirty_since: Option<i64>,
+        uses_now: bool,
+        now_ns: i64,
+    ) -> DFResult<Option<RefreshRange>> {
+        let mut first_bucket = None;
+        let mut last_bucket = None;
+        for batch in &cached.batches {
+            for v in self.bucket_values(batch)?.into_iter().flatten() {
+                first_bucket = Some(first_bucket.map_or(v, |e: i64| e.min(v)));
+                last_bucket = Some(last_bucket.map_or(v, |e: i64| e.max(v)));
+            }
+        }
+        let (first_bucket, last_bucket) = match (first_bucket, last_bucket) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(None),
+        };
+
+        let mut leading_end = None;
+        let mut dirty_since = dirty_since;
+        if uses_now {
+            // The lower bound depending on now() is shifted by the elapsed time at most,
+            // it was in the first bucket, so it is before the end of the first bucket
+            // plus the elapsed time now.
+            let shift = now_ns.saturating_sub(cached.now_ns) + NOW_SHIFT_MARGIN_NS;
+            let lower_bound = first_bucket
+                .saturating_add(self.interval_ns)
+                .saturating_add(shift);
+            leading_end = Some(
+                self.floor(lower_bound.saturating_sub(1))
+                    .saturating_add(self.interval_ns),
+            );
+            // The upper bound depending on now() was in the last bucket or later
+            dirty_since = Some(dirty_since.map_or(last_bucket, |e| e.min(last_bucket)));
+        }
+        let trailing_start = dirty_since.map(|e| self.floor(e));
+
+        if let (Some(end), Some(start)) = (leading_end, trailing_start) {
+            if end >= start {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(RefreshRange {
+            leading_end,
+            trailing_start,
+        }))
+    }
+
+    fn refresh_predicate(&self, range: &RefreshRange) -> DFResult<Expr> {
+        let time = Expr::Column(self.time_column.clone());
+        let leading = range
+            .leading_end
+            .map(|end| self.time_literal(end, true).map(|end| time.clone().lt(end)))
+            .transpose()?;
+        let trailing = range
+            .trailing_start
+            .map(|start| {
+                self.time_literal(start, false)
+                    .map(|start| time.clone().gt_eq(start))
+            })
+            .transpose()?;
+
+        match (leading, trailing) {
+            (Some(leading), Some(trailing)) => Ok(leading.or(trailing)),
+            (Some(expr), None) | (None, Some(expr)) => Ok(expr),
+            (None, None) => Err(DataFusionError::Internal(
+                "Empty time range to refresh".to_string(),
+            )),
+        }
+    }
+
+    /// Convert the timestamp in nanoseconds to the literal of the time column.
+    fn time_literal(&self, ts_ns: i64, round_up: bool) -> DFResult<Expr> {
+        let (unit, tz) = match &self.time_data_type {
+            DataType::Timestamp(unit, tz) => (unit, tz.clone()),
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Expect timestamp column, but found {other}"
+                )))
+            }
+        };
+        let factor = unit_nanos(unit);
+        let mut ts = ts_ns.div_euclid(factor);
+        if round_up && ts_ns.rem_euclid(factor) != 0 {
+            ts += 1;
+        }
+        let value = match unit {
+            TimeUnit::Second => ScalarValue::TimestampSecond(Some(ts), tz),
+            TimeUnit::Millisecond => ScalarValue::TimestampMillisecond(Some(ts), tz),
+            TimeUnit::Microsecond => ScalarValue::TimestampMicrosecond(Some(ts), tz),
+            TimeUnit::Nanosecond => ScalarValue::TimestampNanosecond(Some(ts), tz),
+        };
+        Ok(lit(value))
+    }
+
+    /// Filter the input of the aggregate.
+    fn add_filter(&self, plan: &LogicalPlan, predicate: Expr) -> DFResult<LogicalPlan> {
+        match plan {
+            LogicalPlan::Aggregate(agg) => {
+                let input = LogicalPlanBuilder::from(agg.input.as_ref().clone())
+                    .filter(predicate)?
+                    .build()?;
+                plan.with_new_inputs(&[input])
+            }
+            LogicalPlan::Projection(_)
+            | LogicalPlan::Filter(_)
+            | LogicalPlan::SubqueryAlias(_)
+            | LogicalPlan::Sort(_) => {
+                let input = self.add_filter(plan.inputs()[0], predicate)?;
+                plan.with_new_inputs(&[input])
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected plan above the time bucket: {}",
+                other.display()
+            ))),
+        }
+    }
+
+    /// The start of the time buckets in nanoseconds.
+    fn bucket_values(&self, batch: &RecordBatch) -> DFResult<Vec<Option<i64>>> {
+        let mut column: ArrayRef = batch.column(self.output_index).clone();
+        if self.is_window {
+            column = as_struct_array(&column)
+                .column_by_name(WINDOW_START)
+                .cloned()
+                .ok_or_else(|| {
+                    DataFusionError::Internal(format!("Time window without {WINDOW_START}"))
+                })?;
+        }
+        let factor = match column.data_type() {
+            DataType::Timestamp(unit, _) => unit_nanos(unit),
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Expect timestamp time bucket, but found {other}"
+                )))
+            }
+        };
+        let values = cast(&column, &DataType::Int64)?;
+        let values = values
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .ok_or_else(|| DataFusionError::Internal("Expect Int64Array".to_string()))?;
+
+        Ok(values
+            .iter()
+            .map(|e| e.map(|v| v.saturating_mul(factor)))
+            .collect())
+    }
+}
+
+fn unit_nanos(unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1_000_000_000,
+        TimeUnit::Millisecond => 1_000_000,
+        TimeUnit::Microsecond => 1_000,
+        TimeUnit::Nanosecond => 1,
+    }
+}
+
+fn is_now(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::ScalarFunction(ScalarFunction {
+            fun: BuiltinScalarFunction::Now,
+            ..
+        })
+    )
+}
+
+fn contains_now(exprs: &[Expr]) -> bool {
+    exprs.iter().any(|expr| {
+        let mut found = false;
+        let _ = expr.apply(&mut |e| {
+            found = is_now(e);
+            Ok(if found {
+                VisitRecursion::Stop
+            } else {
+                VisitRecursion::Continue
+            })
+        });
+        found
+    })
+}
+
+/// Check whether the results of the plan can be cached, and collect the tables it reads.
+struct CacheableVisitor {
+    cacheable: bool,
+    uses_now: bool,
+    tables: Vec<TableKey>,
+}
+
+impl Default for CacheableVisitor {
+    fn default() -> Self {
+        Self {
+            cacheable: true,
+            uses_now: false,
+            tables: vec![],
+        }
+    }
+}
+
+impl CacheableVisitor {
+    fn visit(&mut self, plan: &LogicalPlan) -> DFResult<VisitRecursion> {
+        match plan {
+            LogicalPlan::TableScan(scan) => {
+                match source_downcast_adapter(&scan.source).map(|e| e.table_handle()) {
+                    Ok(TableHandle::Tskv(table)) => {
+                        let schema = table.table_schema();
+                        self.tables.push((
+                            schema.tenant.clone(),
+                            schema.db.clone(),
+                            schema.name.clone(),
+                        ));
+                    }
+                    // Writes of external tables, stream tables and system tables are not tracked
+                    _ => self.cacheable = false,
+                }
+            }
+            LogicalPlan::Projection(_)
+            | LogicalPlan::Filter(_)
+            | LogicalPlan::Aggregate(_)
+            | LogicalPlan::Window(_)
+            | LogicalPlan::Sort(_)
+            | LogicalPlan::Limit(_)
+            | LogicalPlan::Join(_)
+            | LogicalPlan::CrossJoin(_)
+            | LogicalPlan::Union(_)
+            | LogicalPlan::Distinct(_)
+            | LogicalPlan::SubqueryAlias(_)
+            | LogicalPlan::EmptyRelation(_)
+            | LogicalPlan::Values(_) => {}
+            // Extensions such as the table writer, DML, DDL, subqueries, etc.
+            _ => self.cacheable = false,
+        }
+
+        let is_filter = matches!(plan, LogicalPlan::Filter(_));
+        for expr in plan.expressions() {
+            expr.apply(&mut |e| {
+                match e {
+                    Expr::ScalarFunction(ScalarFunction { fun, .. }) => {
+                        if is_now(e) {
+                            self.uses_now = true;
+                            // now() only shifts the time range to filter
+                            self.cacheable &= is_filter;
+                        } else {
+                            self.cacheable &= fun.volatility() == Volatility::Immutable;
+                        }
+                    }
+                    Expr::ScalarUDF(ScalarUDF { fun, .. }) => {
+                        self.cacheable &= fun.signature.volatility == Volatility::Immutable;
+                    }
+                    Expr::Exists { .. } | Expr::InSubquery { .. } | Expr::ScalarSubquery(_) => {
+                        self.cacheable = false;
+                    }
+                    _ => {}
+                }
+                Ok(VisitRecursion::Continue)
+            })?;
+        }
+
+        Ok(if self.cacheable {
+            VisitRecursion::Continue
+        } else {
+            VisitRecursion::Stop
+        })
+    }
+}
+
+/// Records the minimum timestamps written into the tables, so the cached results can
+/// tell the time range being dirty since they are computed.
+#[derive(Default)]
+struct WriteLog {
+    seq: u64,
+    /// The results computed before this sequence are dirty entirely
+    reset_seq: u64,
+    tenant_reset_seqs: HashMap<String, u64>,
+    tables: HashMap<TableKey, TableWrites>,
+}
+
+impl WriteLog {
+    fn record(&mut self, written: WrittenTimeRange) {
+        self.seq += 1;
+        let key = (written.tenant, written.database, written.table);
+        self.tables
+            .entry(key)
+            .or_default()
+            .record(self.seq, written.min_ts);
+    }
+
+    fn reset(&mut self) {
+        self.seq += 1;
+        self.reset_seq = self.seq;
+    }
+
+    fn reset_tenant(&mut self, tenant: &str) {
+        self.seq += 1;
+        self.tenant_reset_seqs.insert(tenant.to_string(), self.seq);
+    }
+
+    /// The minimum timestamp written into the tables after `write_seq`,
+    /// `i64::MIN` if all the data may be changed.
+    fn dirty_since(&self, tenant: &str, tables: &[TableKey], write_seq: u64) -> Option<i64> {
+        let tenant_reset_seq = self.tenant_reset_seqs.get(tenant).copied().unwrap_or(0);
+        if write_seq < self.reset_seq || write_seq < tenant_reset_seq {
+            return Some(i64::MIN);
+        }
+
+        tables
+            .iter()
+            .filter_map(|table| self.tables.get(table)?.dirty_since(write_seq))
+            .min()
+    }
+}
+
+#[derive(Default)]
+struct TableWrites {
+    /// The results computed before this sequence are dirty entirely
+    truncated_seq: u64,
+    /// (seq, min_ts), the min_ts is increasing
+    writes: VecDeque<(u64, i64)>,
+}
+
+impl TableWrites {
+    fn record(&mut self, seq: u64, min_ts: i64) {
+        // The writes before with a greater timestamp are covered by this one
+        while matches!(self.writes.back(), Some((_, ts)) if *ts >= min_ts) {
+            self.writes.pop_back();
+        }
+        self.writes.push_back((seq, min_ts));
+
+        if self.writes.len() > MAX_WRITES_PER_TABLE {
+            if let Some((seq, _)) = self.writes.pop_front() {
+                self.truncated_seq = seq;
+            }
+        }
+    }
+
+    fn dirty_since(&self, write_seq: u64) -> Option<i64> {
+        if write_seq < self.truncated_seq {
+            return Some(i64::MIN);
+        }
+
+        self.writes
+            .iter()
+            .find(|(seq, _)| *seq > write_seq)
+            .map(|(_, ts)| *ts)
+    }
+}
+
+struct CachingRecordBatchStream {
+    inner: SendableRecordBatchStream,
+    cache: QueryResultCacheRef,
+    /// None if the result is not going to be cached
+    pending: Option<(CacheablePlan, u64, i64)>,
+    batches: Vec<RecordBatch>,
+    memory_size: usize,
+}
+
+impl RecordBatchStream for CachingRecordBatchStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl Stream for CachingRecordBatchStream {
+    type Item = DFResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let poll = this.inner.poll_next_unpin(cx);
+        match &poll {
+            Poll::Ready(Some(Ok(batch))) => {
+                if this.pending.is_some() {
+                    this.memory_size += batch.get_array_memory_size();
+                    if this.memory_size > this.cache.max_memory {
+                        // Too large to be cached
+                        this.pending = None;
+                        this.batches.clear();
+                    } else {
+                        this.batches.push(batch.clone());
+                    }
+                }
+            }
+            Poll::Ready(Some(Err(_))) => {
+                this.pending = None;
+                this.batches.clear();
+            }
+            Poll::Ready(None) => {
+                if let Some((plan, write_seq, now_ns)) = this.pending.take() {
+                    let batches = std::mem::take(&mut this.batches);
+                    let result = CachedResult::new(this.inner.schema(), batches, write_seq, now_ns);
+                    this.cache.insert(plan, result);
+                }
+            }
+            Poll::Pending => {}
+        }
+
+        poll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Int64Array, TimestampNanosecondArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::common::Column;
+
+    use super::{CachedResult, Refresh, TableWrites, TimeBucket, WriteLog};
+
+    const MINUTE: i64 = 60_000_000_000;
+
+    fn bucket() -> TimeBucket {
+        TimeBucket {
+            time_column: Column::from_name("time"),
+            time_data_type: DataType::Timestamp(TimeUnit::Nanosecond, None),
+            interval_ns: MINUTE,
+            origin_ns: 0,
+            output_index: 0,
+            is_window: false,
+            descending: false,
+        }
+    }
+
+    fn batch(rows: Vec<(i64, i64)>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "bucket",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("v", DataType::Int64, false),
+        ]));
+        let (buckets, values): (Vec<i64>, Vec<i64>) = rows.into_iter().unzip();
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(TimestampNanosecondArray::from(buckets)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_table_writes() {
+        let mut writes = TableWrites::default();
+        writes.record(1, 100);
+        writes.record(2, 50);
+        writes.record(3, 200);
+        assert_eq!(writes.dirty_since(0), Some(50));
+        assert_eq!(writes.dirty_since(2), Some(200));
+        assert_eq!(writes.dirty_since(3), None);
+
+        let mut log = WriteLog::default();
+        let table = ("t".to_string(), "db".to_string(), "tbl".to_string());
+        log.seq = 3;
+        log.tables.insert(table.clone(), writes);
+        log.reset_tenant("t");
+        assert_eq!(log.dirty_since("t", &[table.clone()], 3), Some(i64::MIN));
+        assert_eq!(log.dirty_since("t", &[table], 4), None);
+    }
+
+    #[test]
+    fn test_refresh_latest_bucket() {
+        let bucket = bucket();
+        let cached = CachedResult::new(
+            batch(vec![]).schema(),
+            vec![batch(vec![(0, 1), (MINUTE, 2), (2 * MINUTE, 3)])],
+            0,
+            0,
+        );
+
+        // points written into the last bucket
+        let range = bucket
+            .refresh_range(&cached, Some(2 * MINUTE + 10), false, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(range.leading_end, None);
+        assert_eq!(range.trailing_start, Some(2 * MINUTE));
+
+        let refresh = Refresh {
+            bucket,
+            range,
+            cached,
+        };
+        let merged = refresh
+            .merge(vec![batch(vec![(2 * MINUTE, 4), (3 * MINUTE, 5)])], 1, 0)
+            .unwrap();
+        let values: Vec<i64> = merged
+            .batches
+            .iter()
+            .flat_map(|e| {
+                e.column(1)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!(values, vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_refresh_range_shifted_by_now() {
+        let bucket = bucket();
+        let cached = CachedResult::new(
+            batch(vec![]).schema(),
+            vec![batch(vec![
+                (0, 1),
+                (MINUTE, 2),
+                (2 * MINUTE, 3),
+                (3 * MINUTE, 4),
+            ])],
+            0,
+            0,
+        );
+
+        // 30 seconds later, the first bucket and the last bucket are recomputed
+        let range = bucket
+            .refresh_range(&cached, None, true, MINUTE / 2)
+            .unwrap()
+            .unwrap();
+        assert_eq!(range.leading_end, Some(2 * MINUTE));
+        assert_eq!(range.trailing_start, Some(3 * MINUTE));
+        assert!(range.contains(MINUTE));
+        assert!(!range.contains(2 * MINUTE));
+
+        // too late to reuse any bucket
+        assert!(bucket
+            .refresh_range(&cached, None, true, 10 * MINUTE)
+            .unwrap()
+            .is_none());
+    }
+}
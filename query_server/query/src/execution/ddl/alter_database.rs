@@ -61,4 +61,10 @@ fn build_database_schema(database_options: &DatabaseOptions, config: &mut Databa
     if let Some(precision) = database_options.precision() {
         config.with_precision(*precision);
     }
+    if let Some(cold_duration) = database_options.cold_duration() {
+        config.with_cold_duration(cold_duration.clone());
+    }
+    if let Some(cold_after) = database_options.cold_after() {
+        config.with_cold_after(cold_after.clone());
+    }
 }
@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use coordinator::VnodeManagerCmdType;
+use meta::error::MetaError;
+use models::meta_data::{DatabaseInfo, ReplicationSet, VnodeInfo};
+use serde::{Deserialize, Serialize};
+use spi::query::datasource::build_location_object_store;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::{map_to_sql_options, BackupDatabase};
+use spi::Result;
+
+use super::DDLDefinitionTask;
+
+/// Name of the object that stores the manifest of a database backup.
+pub const BACKUP_MANIFEST_NAME: &str = "backup.json";
+
+/// Manifest of a database backup, it's written after all vnodes are backed up.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub tenant: String,
+    pub database: String,
+    /// Timestamp in nanoseconds the backup is finished.
+    pub created_at: i64,
+    /// Schema, buckets and tables of the database when backup.
+    pub database_info: DatabaseInfo,
+}
+
+/// Only the first vnode of a replication set is backed up.
+pub fn backup_vnode_of(replication_set: &ReplicationSet) -> Option<&VnodeInfo> {
+    replication_set.vnodes.first()
+}
+
+pub struct BackupDatabaseTask {
+    stmt: BackupDatabase,
+}
+
+impl BackupDatabaseTask {
+    #[inline(always)]
+    pub fn new(stmt: BackupDatabase) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for BackupDatabaseTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let BackupDatabase {
+            ref tenant_name,
+            ref database_name,
+            ref location,
+            ref connection_options,
+        } = self.stmt;
+
+        let client = query_state_machine
+            .meta
+            .tenant_meta(tenant_name)
+            .await
+            .ok_or(MetaError::TenantNotFound {
+                tenant: tenant_name.to_string(),
+            })?;
+        let database_info =
+            client
+                .get_db_info(database_name)?
+                .ok_or(MetaError::DatabaseNotFound {
+                    database: database_name.to_string(),
+                })?;
+
+        let coord = query_state_machine.coord.clone();
+        for bucket in database_info.buckets.iter() {
+            for replication_set in bucket.shard_group.iter() {
+                if let Some(vnode) = backup_vnode_of(replication_set) {
+                    let cmd_type = VnodeManagerCmdType::Backup(
+                        vnode.id,
+                        location.clone(),
+                        connection_options.clone(),
+                    );
+                    coord.vnode_manager(tenant_name, cmd_type).await?;
+                }
+            }
+        }
+
+        let manifest = BackupManifest {
+            tenant: tenant_name.clone(),
+            database: database_name.clone(),
+            created_at: chrono::Utc::now().timestamp_nanos(),
+            database_info,
+        };
+        let (object_store, path) =
+            build_location_object_store(location, map_to_sql_options(connection_options))?;
+        object_store
+            .put(
+                &path.child(BACKUP_MANIFEST_NAME),
+                serde_json::to_vec(&manifest)?.into(),
+            )
+            .await?;
+
+        Ok(Output::Nil(()))
+    }
+}
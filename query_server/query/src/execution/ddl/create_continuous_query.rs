@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use meta::error::MetaError;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::CreateContinuousQuery;
+use spi::Result;
+
+use crate::execution::ddl::DDLDefinitionTask;
+
+pub struct CreateContinuousQueryTask {
+    stmt: CreateContinuousQuery,
+}
+
+impl CreateContinuousQueryTask {
+    pub fn new(stmt: CreateContinuousQuery) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for CreateContinuousQueryTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let CreateContinuousQuery {
+            ref tenant_name,
+            ref if_not_exists,
+            ref info,
+        } = self.stmt;
+
+        let client = query_state_machine
+            .meta
+            .tenant_meta(tenant_name)
+            .await
+            .ok_or(MetaError::TenantNotFound {
+                tenant: tenant_name.to_string(),
+            })?;
+
+        let exists = client.continuous_query(&info.name).await?.is_some();
+        match (if_not_exists, exists) {
+            // do not create if exists
+            (true, true) => Ok(Output::Nil(())),
+            // Report an error if it exists
+            (false, true) => Err(MetaError::ContinuousQueryAlreadyExists {
+                name: info.name.clone(),
+            })?,
+            // does not exist, create
+            (_, false) => {
+                client.create_continuous_query(info.clone()).await?;
+                Ok(Output::Nil(()))
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use meta::error::MetaError;
+use models::schema::TableSchema;
+use snafu::ResultExt;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::CreateMaterializedView;
+use spi::{MetaSnafu, Result};
+
+use crate::execution::ddl::DDLDefinitionTask;
+
+pub struct CreateMaterializedViewTask {
+    stmt: CreateMaterializedView,
+}
+
+impl CreateMaterializedViewTask {
+    pub fn new(stmt: CreateMaterializedView) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for CreateMaterializedViewTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let CreateMaterializedView {
+            ref if_not_exists,
+            ref table,
+            ref view,
+        } = self.stmt;
+
+        let client = query_state_machine
+            .meta
+            .tenant_meta(&view.tenant)
+            .await
+            .ok_or(MetaError::TenantNotFound {
+                tenant: view.tenant.clone(),
+            })?;
+
+        if client.get_materialized_view(&view.db, &view.name).is_some() {
+            if *if_not_exists {
+                return Ok(Output::Nil(()));
+            }
+            return Err(MetaError::MaterializedViewAlreadyExists {
+                name: view.name.clone(),
+            }
+            .into());
+        }
+
+        // The table keeping the data of the view is created together with the view,
+        // the view is refreshed in the background afterwards.
+        client
+            .create_materialized_view(&TableSchema::TsKvTableSchema(Arc::new(table.clone())), view)
+            .await
+            .context(MetaSnafu)?;
+
+        Ok(Output::Nil(()))
+    }
+}
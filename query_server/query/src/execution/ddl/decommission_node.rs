@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::DecommissionNode;
+use spi::Result;
+
+use super::DDLDefinitionTask;
+
+pub struct DecommissionNodeTask {
+    stmt: DecommissionNode,
+}
+
+impl DecommissionNodeTask {
+    #[inline(always)]
+    pub fn new(stmt: DecommissionNode) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for DecommissionNodeTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let coord = query_state_machine.coord.clone();
+        coord.decommission_node(self.stmt.node_id).await?;
+
+        Ok(Output::Nil(()))
+    }
+}
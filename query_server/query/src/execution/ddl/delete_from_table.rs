@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::DeleteFromTable;
+use spi::Result;
+use trace::info;
+
+use super::DDLDefinitionTask;
+
+pub struct DeleteFromTableTask {
+    stmt: DeleteFromTable,
+}
+
+impl DeleteFromTableTask {
+    #[inline(always)]
+    pub fn new(stmt: DeleteFromTable) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for DeleteFromTableTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let DeleteFromTable {
+            ref table_name,
+            ref predicate,
+        } = self.stmt;
+
+        info!("Delete from table {}", table_name);
+        query_state_machine
+            .coord
+            .delete_from_table(table_name, predicate)
+            .await?;
+
+        Ok(Output::Nil(()))
+    }
+}
@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use meta::error::MetaError;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::DropContinuousQuery;
+use spi::Result;
+
+use crate::execution::ddl::DDLDefinitionTask;
+
+pub struct DropContinuousQueryTask {
+    stmt: DropContinuousQuery,
+}
+
+impl DropContinuousQueryTask {
+    pub fn new(stmt: DropContinuousQuery) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for DropContinuousQueryTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let DropContinuousQuery {
+            ref tenant_name,
+            ref name,
+            ref database,
+            ref if_exist,
+        } = self.stmt;
+
+        let client = query_state_machine
+            .meta
+            .tenant_meta(tenant_name)
+            .await
+            .ok_or(MetaError::TenantNotFound {
+                tenant: tenant_name.to_string(),
+            })?;
+
+        // The continuous query must belong to the database in `ON <database>`,
+        // the privilege of the statement is checked against that database.
+        let dropped = match client.continuous_query(name).await? {
+            Some(info) if &info.database == database => client.drop_continuous_query(name).await?,
+            _ => false,
+        };
+
+        if !dropped && !if_exist {
+            return Err(MetaError::ContinuousQueryNotFound { name: name.clone() }.into());
+        }
+
+        Ok(Output::Nil(()))
+    }
+}
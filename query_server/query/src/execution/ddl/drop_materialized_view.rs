@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use meta::error::MetaError;
+use protos::kv_service::admin_command_request::Command::DropTab;
+use protos::kv_service::{AdminCommandRequest, DropTableRequest};
+use snafu::ResultExt;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::DropMaterializedView;
+use spi::{MetaSnafu, Result};
+use trace::info;
+
+use crate::execution::ddl::DDLDefinitionTask;
+
+pub struct DropMaterializedViewTask {
+    stmt: DropMaterializedView,
+}
+
+impl DropMaterializedViewTask {
+    pub fn new(stmt: DropMaterializedView) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for DropMaterializedViewTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let DropMaterializedView {
+            ref tenant_name,
+            ref database,
+            ref name,
+            ref if_exist,
+        } = self.stmt;
+
+        let client = query_state_machine
+            .meta
+            .tenant_meta(tenant_name)
+            .await
+            .ok_or(MetaError::TenantNotFound {
+                tenant: tenant_name.to_string(),
+            })?;
+
+        if client.get_materialized_view(database, name).is_none() {
+            if *if_exist {
+                return Ok(Output::Nil(()));
+            }
+            return Err(MetaError::MaterializedViewNotFound { name: name.clone() }.into());
+        }
+
+        info!("Drop materialized view {}.{}", database, name);
+        let req = AdminCommandRequest {
+            tenant: tenant_name.to_string(),
+            command: Some(DropTab(DropTableRequest {
+                db: database.to_string(),
+                table: name.to_string(),
+            })),
+        };
+        query_state_machine.coord.broadcast_command(req).await?;
+
+        // Dropping the table of the view drops the view as well
+        client.drop_table(database, name).await.context(MetaSnafu)?;
+
+        Ok(Output::Nil(()))
+    }
+}
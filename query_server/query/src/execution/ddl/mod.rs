@@ -19,37 +19,55 @@ use self::drop_tenant_object::DropTenantObjectTask;
 use self::grant_revoke::GrantRevokeTask;
 use crate::execution::ddl::alter_database::AlterDatabaseTask;
 use crate::execution::ddl::alter_table::AlterTableTask;
+use crate::execution::ddl::backup_database::BackupDatabaseTask;
 use crate::execution::ddl::checksum_group::ChecksumGroupTask;
 use crate::execution::ddl::compact_vnode::CompactVnodeTask;
 use crate::execution::ddl::copy_vnode::CopyVnodeTask;
+use crate::execution::ddl::create_continuous_query::CreateContinuousQueryTask;
 use crate::execution::ddl::create_database::CreateDatabaseTask;
+use crate::execution::ddl::create_materialized_view::CreateMaterializedViewTask;
+use crate::execution::ddl::decommission_node::DecommissionNodeTask;
+use crate::execution::ddl::delete_from_table::DeleteFromTableTask;
 use crate::execution::ddl::describe_database::DescribeDatabaseTask;
 use crate::execution::ddl::describe_table::DescribeTableTask;
+use crate::execution::ddl::drop_continuous_query::DropContinuousQueryTask;
+use crate::execution::ddl::drop_materialized_view::DropMaterializedViewTask;
 use crate::execution::ddl::drop_vnode::DropVnodeTask;
 use crate::execution::ddl::move_node::MoveVnodeTask;
+use crate::execution::ddl::repair_replication_set::RepairReplicationSetTask;
+use crate::execution::ddl::restore_database::RestoreDatabaseTask;
 
 mod alter_database;
 mod alter_table;
 mod alter_tenant;
 mod alter_user;
+mod backup_database;
 mod checksum_group;
 mod compact_vnode;
 mod copy_vnode;
+mod create_continuous_query;
 mod create_database;
 mod create_external_table;
+mod create_materialized_view;
 mod create_role;
 mod create_stream_table;
 mod create_table;
 mod create_tenant;
 mod create_user;
+mod decommission_node;
+mod delete_from_table;
 mod describe_database;
 mod describe_table;
+mod drop_continuous_query;
 mod drop_database_object;
 mod drop_global_object;
+mod drop_materialized_view;
 mod drop_tenant_object;
 mod drop_vnode;
 mod grant_revoke;
 mod move_node;
+mod repair_replication_set;
+mod restore_database;
 
 /// Traits that DDL tasks should implement
 #[async_trait]
@@ -175,11 +193,39 @@ impl DDLDefinitionTaskFactory {
             DDLPlan::ChecksumGroup(sub_plan) => {
                 Box::new(ChecksumGroupTask::new(sub_plan.clone(), self.plan.schema()))
             }
+            DDLPlan::DecommissionNode(sub_plan) => {
+                Box::new(DecommissionNodeTask::new(sub_plan.clone()))
+            }
+            DDLPlan::RepairReplicationSet(sub_plan) => Box::new(RepairReplicationSetTask::new(
+                sub_plan.clone(),
+                self.plan.schema(),
+            )),
             DDLPlan::CreateStreamTable(sub_plan) => {
                 let checker = self.stream_checker_manager.checker(&sub_plan.stream_type);
 
                 Box::new(CreateStreamTableTask::new(checker, sub_plan.clone()))
             }
+            DDLPlan::DeleteFromTable(sub_plan) => {
+                Box::new(DeleteFromTableTask::new(sub_plan.clone()))
+            }
+            DDLPlan::BackupDatabase(sub_plan) => {
+                Box::new(BackupDatabaseTask::new(sub_plan.clone()))
+            }
+            DDLPlan::RestoreDatabase(sub_plan) => {
+                Box::new(RestoreDatabaseTask::new(sub_plan.clone()))
+            }
+            DDLPlan::CreateContinuousQuery(sub_plan) => {
+                Box::new(CreateContinuousQueryTask::new(sub_plan.clone()))
+            }
+            DDLPlan::DropContinuousQuery(sub_plan) => {
+                Box::new(DropContinuousQueryTask::new(sub_plan.clone()))
+            }
+            DDLPlan::CreateMaterializedView(sub_plan) => {
+                Box::new(CreateMaterializedViewTask::new(sub_plan.clone()))
+            }
+            DDLPlan::DropMaterializedView(sub_plan) => {
+                Box::new(DropMaterializedViewTask::new(sub_plan.clone()))
+            }
         }
     }
 }
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::array::{UInt32Array, UInt64Array};
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::RepairReplicationSet;
+use spi::query::recordbatch::RecordBatchStreamWrapper;
+use spi::Result;
+
+use super::DDLDefinitionTask;
+
+pub struct RepairReplicationSetTask {
+    schema: SchemaRef,
+    stmt: RepairReplicationSet,
+}
+
+impl RepairReplicationSetTask {
+    #[inline(always)]
+    pub fn new(stmt: RepairReplicationSet, schema: SchemaRef) -> Self {
+        Self { schema, stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for RepairReplicationSetTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let replication_set_id = self.stmt.replication_set_id;
+        let tenant = query_state_machine.session.tenant();
+
+        let coord = query_state_machine.coord.clone();
+        let results = coord
+            .repair_replication_set(tenant, replication_set_id)
+            .await?;
+
+        let batch = RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(UInt32Array::from_iter_values(
+                    results.iter().map(|r| r.vnode_id),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    results.iter().map(|r| r.node_id),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    results.iter().map(|r| r.ranges),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    results.iter().map(|r| r.points),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    results.iter().map(|r| r.skipped_ranges),
+                )),
+            ],
+        )?;
+        let stream = RecordBatchStreamWrapper::new(self.schema.clone(), vec![batch]);
+        Ok(Output::StreamData(Box::pin(stream)))
+    }
+}
@@ -0,0 +1,155 @@
+use async_trait::async_trait;
+use coordinator::VnodeManagerCmdType;
+use meta::error::MetaError;
+use models::meta_data::DatabaseInfo;
+use models::schema::DatabaseSchema;
+use protos::kv_service::admin_command_request::Command::DropDb;
+use protos::kv_service::{AdminCommandRequest, DropDbRequest};
+use spi::query::datasource::build_location_object_store;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::{map_to_sql_options, RestoreDatabase};
+use spi::Result;
+use trace::warn;
+
+use super::backup_database::{backup_vnode_of, BackupManifest, BACKUP_MANIFEST_NAME};
+use super::DDLDefinitionTask;
+
+pub struct RestoreDatabaseTask {
+    stmt: RestoreDatabase,
+}
+
+impl RestoreDatabaseTask {
+    #[inline(always)]
+    pub fn new(stmt: RestoreDatabase) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for RestoreDatabaseTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let RestoreDatabase {
+            ref tenant_name,
+            ref database_name,
+            ref location,
+            ref connection_options,
+        } = self.stmt;
+
+        let client = query_state_machine
+            .meta
+            .tenant_meta(tenant_name)
+            .await
+            .ok_or(MetaError::TenantNotFound {
+                tenant: tenant_name.to_string(),
+            })?;
+        if client.list_databases()?.contains(database_name) {
+            return Err(MetaError::DatabaseAlreadyExists {
+                database: database_name.clone(),
+            })?;
+        }
+
+        let (object_store, path) =
+            build_location_object_store(location, map_to_sql_options(connection_options))?;
+        let manifest = object_store
+            .get(&path.child(BACKUP_MANIFEST_NAME))
+            .await?
+            .bytes()
+            .await?;
+        let manifest: BackupManifest = serde_json::from_slice(&manifest)?;
+        let database_info = manifest.database_info;
+
+        let mut database_schema = DatabaseSchema::new(tenant_name, database_name);
+        database_schema.config = database_info.schema.config.clone();
+        client.create_db(database_schema).await?;
+
+        // A partially restored database is dropped, so the restore can be retried.
+        if let Err(err) = restore(&query_state_machine, &self.stmt, &database_info).await {
+            warn!(
+                "Failed to restore database {} of tenant {}, drop it: {}",
+                database_name, tenant_name, err
+            );
+            let req = AdminCommandRequest {
+                tenant: tenant_name.to_string(),
+                command: Some(DropDb(DropDbRequest {
+                    db: database_name.clone(),
+                })),
+            };
+            if let Err(drop_err) = query_state_machine.coord.broadcast_command(req).await {
+                warn!(
+                    "Failed to drop vnodes of database {}: {}",
+                    database_name, drop_err
+                );
+            }
+            if let Err(drop_err) = client.drop_db(database_name).await {
+                warn!("Failed to drop database {}: {}", database_name, drop_err);
+            }
+            return Err(err);
+        }
+
+        Ok(Output::Nil(()))
+    }
+}
+
+async fn restore(
+    query_state_machine: &QueryStateMachineRef,
+    stmt: &RestoreDatabase,
+    database_info: &DatabaseInfo,
+) -> Result<()> {
+    let RestoreDatabase {
+        ref tenant_name,
+        ref database_name,
+        ref location,
+        ref connection_options,
+    } = *stmt;
+
+    let client = query_state_machine
+        .meta
+        .tenant_meta(tenant_name)
+        .await
+        .ok_or(MetaError::TenantNotFound {
+            tenant: tenant_name.to_string(),
+        })?;
+    for table in database_info.tables.values() {
+        client
+            .create_table(&table.with_database(tenant_name, database_name))
+            .await?;
+    }
+
+    // Buckets are re-created with the same time range, the i-th replication set of
+    // the new bucket is restored from the i-th replication set of the backup.
+    let coord = query_state_machine.coord.clone();
+    for bucket in database_info.buckets.iter() {
+        let new_bucket = client
+            .create_bucket(database_name, bucket.start_time)
+            .await?;
+        if bucket.shard_group.len() != new_bucket.shard_group.len() {
+            return Err(MetaError::CommonError {
+                msg: format!(
+                    "bucket {} of the backup has {} replication sets, but {} are created",
+                    bucket.id,
+                    bucket.shard_group.len(),
+                    new_bucket.shard_group.len()
+                ),
+            })?;
+        }
+        for (replication_set, new_replication_set) in
+            bucket.shard_group.iter().zip(new_bucket.shard_group.iter())
+        {
+            let backup_vnode = match backup_vnode_of(replication_set) {
+                Some(vnode) => vnode,
+                None => continue,
+            };
+            for vnode in new_replication_set.vnodes.iter() {
+                let cmd_type = VnodeManagerCmdType::Restore(
+                    vnode.id,
+                    backup_vnode.id,
+                    location.clone(),
+                    connection_options.clone(),
+                );
+                coord.vnode_manager(tenant_name, cmd_type).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -2,5 +2,5 @@ mod ddl;
 pub mod factory;
 mod query;
 pub mod scheduler;
-mod stream;
+pub(crate) mod stream;
 mod sys;
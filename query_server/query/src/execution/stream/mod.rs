@@ -34,8 +34,9 @@ use crate::extension::physical::transform_rule::watermark::WatermarkPlanner;
 use crate::sql::logical::optimizer::{DefaultLogicalOptimizer, LogicalOptimizer};
 use crate::sql::physical::optimizer::PhysicalOptimizer;
 use crate::sql::physical::planner::DefaultPhysicalPlanner;
+use crate::stream::checkpoint::{Checkpoint, CheckpointLog, CheckpointLogRef};
 use crate::stream::offset_tracker::{OffsetTracker, OffsetTrackerRef};
-use crate::stream::state_store::memory::MemoryStateStoreFactory;
+use crate::stream::state_store::local::LocalStateStoreFactory;
 use crate::stream::state_store::StateStoreFactory;
 use crate::stream::watermark_tracker::{WatermarkTracker, WatermarkTrackerRef};
 
@@ -105,10 +106,22 @@ impl MicroBatchStreamExecutionBuilder {
             .unwrap_or_else(|| extract_stream_providers(plan.as_ref()));
 
         let trigger_executor = trigger_executor_factory.create(&trigger_interval);
-        let watermark_tracker = Arc::new(WatermarkTracker::try_new(
+        // Resume from the last checkpoint if the query is restarted
+        let checkpoint_log = Arc::new(CheckpointLog::try_new(
             query_state_machine.query_id,
             query_state_machine.session.dedicated_hidden_dir(),
         )?);
+        let Checkpoint {
+            version,
+            watermark_ns,
+            processed_offsets,
+        } = checkpoint_log.latest();
+        let watermark_tracker = Arc::new(WatermarkTracker::new(watermark_ns));
+        let offset_tracker = Arc::new(OffsetTracker::with_processed_offsets(processed_offsets));
+        let state_store_factory = Arc::new(LocalStateStoreFactory::new(
+            query_state_machine.session.dedicated_hidden_dir(),
+            version,
+        ));
 
         Ok(MicroBatchStreamExecution {
             query_state_machine,
@@ -117,8 +130,9 @@ impl MicroBatchStreamExecutionBuilder {
             scheduler,
             trigger_executor,
             watermark_tracker,
-            offset_tracker: Arc::new(OffsetTracker::new()),
-            state_store_factory: Arc::new(MemoryStateStoreFactory::default()),
+            offset_tracker,
+            state_store_factory,
+            checkpoint_log,
             runtime,
             abort_handle: Mutex::new(None),
         })
@@ -131,9 +145,10 @@ pub struct MicroBatchStreamExecution {
     stream_providers: Vec<StreamProviderRef>,
     scheduler: SchedulerRef,
     trigger_executor: TriggerExecutorRef,
-    state_store_factory: Arc<MemoryStateStoreFactory>,
+    state_store_factory: Arc<LocalStateStoreFactory>,
     watermark_tracker: WatermarkTrackerRef,
     offset_tracker: OffsetTrackerRef,
+    checkpoint_log: CheckpointLogRef,
     runtime: Arc<DedicatedExecutor>,
     abort_handle: Mutex<Option<Job<()>>>,
 }
@@ -153,6 +168,7 @@ impl MicroBatchStreamExecution {
         let state_store_factory = self.state_store_factory.clone();
         let runtime = self.runtime.clone();
         let offset_tracker = self.offset_tracker.clone();
+        let checkpoint_log = self.checkpoint_log.clone();
 
         let result = self.trigger_executor.schedule(
             move |current_batch_id| {
@@ -165,6 +181,7 @@ impl MicroBatchStreamExecution {
                     watermark_tracker: watermark_tracker.clone(),
                     state_store_factory: state_store_factory.clone(),
                     offset_tracker: offset_tracker.clone(),
+                    checkpoint_log: checkpoint_log.clone(),
                 };
 
                 async move {
@@ -277,6 +294,7 @@ struct IncrementalExecution<T> {
     watermark_tracker: WatermarkTrackerRef,
     state_store_factory: Arc<T>,
     offset_tracker: OffsetTrackerRef,
+    checkpoint_log: CheckpointLogRef,
 }
 
 impl<T> IncrementalExecution<T>
@@ -304,13 +322,17 @@ where
         let available_offsets = self.offset_tracker.available_offsets();
         let id = self.query_state_machine.query_id;
         let logical_plan = &self.plan.df_plan;
+        // A failed micro batch is retried with the same version of states
+        let version = self.checkpoint_log.next_version();
         trace::trace!(
-            "query_id({}), current_batch_id({}), current_watermark_ns({}), available_offsets: {:?}",
+            "query_id({}), current_batch_id({}), version({}), current_watermark_ns({}), available_offsets: {:?}",
             id,
             self.current_batch_id,
+            version,
             current_watermark_ns,
             available_offsets,
         );
+        self.state_store_factory.begin_version(version);
 
         let logical_optimizer = DefaultLogicalOptimizer::default();
         let opt_plan = logical_optimizer.optimize(logical_plan, session)?;
@@ -359,9 +381,20 @@ where
             // TODO here is for compatibility with unrealized functions of tskv, which needs to be modified later
             // After processing a batch, the watermark is updated, then submit to offset_tracker
             // If not updated, it means that the data has not been processed
+            //
+            // Persist the states version, watermark and offsets together,
+            // in order to resume from them when restoring
+            let processed_offsets = self
+                .offset_tracker
+                .processed_offsets_after_commit(after_process_watermark_ns);
+            self.checkpoint_log
+                .commit(Checkpoint {
+                    version,
+                    watermark_ns: after_process_watermark_ns,
+                    processed_offsets,
+                })
+                .await?;
             self.offset_tracker.commit(after_process_watermark_ns);
-            // Persist watermark, in order to load the last watermark when restoring
-            self.watermark_tracker.commit(self.current_batch_id).await?;
         } else {
             self.watermark_tracker
                 .update_watermark(current_watermark_ns, 0);
@@ -1,5 +1,6 @@
 mod kill_query;
 mod show_queries;
+mod show_rebalance;
 
 use std::sync::Arc;
 
@@ -11,6 +12,7 @@ use spi::Result;
 
 use self::kill_query::KillQueryTask;
 use self::show_queries::ShowQueriesTask;
+use self::show_rebalance::ShowRebalanceTask;
 use crate::dispatcher::query_tracker::QueryTracker;
 
 pub struct SystemExecution {
@@ -97,6 +99,7 @@ impl SystemTaskFactory {
             SYSPlan::KillQuery(query_id) => {
                 Box::new(KillQueryTask::new(self.query_tracker.clone(), *query_id))
             }
+            SYSPlan::ShowRebalance => Box::new(ShowRebalanceTask::new(self.plan.schema())),
         }
     }
 }
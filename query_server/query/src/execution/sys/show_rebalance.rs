@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::array::{StringBuilder, UInt32Builder, UInt64Builder};
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::record_batch::RecordBatch;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::recordbatch::RecordBatchStreamWrapper;
+use spi::Result;
+
+use super::SystemTask;
+
+pub struct ShowRebalanceTask {
+    schema: SchemaRef,
+}
+
+impl ShowRebalanceTask {
+    pub fn new(schema: SchemaRef) -> Self {
+        Self { schema }
+    }
+}
+
+#[async_trait]
+impl SystemTask for ShowRebalanceTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let mut result_builder = ShowRebalanceResultBuilder::new(self.schema.clone());
+
+        let moves = query_state_machine.coord.rebalance_status().await?;
+        for m in moves.iter() {
+            result_builder.add_column(
+                &m.tenant,
+                &m.database,
+                m.repl_set_id,
+                m.vnode_id,
+                m.from,
+                m.to,
+                m.status.as_str(),
+            );
+        }
+
+        Ok(Output::StreamData(Box::pin(RecordBatchStreamWrapper::new(
+            result_builder.schema(),
+            result_builder.build()?,
+        ))))
+    }
+}
+
+struct ShowRebalanceResultBuilder {
+    schema: SchemaRef,
+
+    tenant_names: StringBuilder,
+    database_names: StringBuilder,
+    replication_set_ids: UInt32Builder,
+    vnode_ids: UInt32Builder,
+    from_node_ids: UInt64Builder,
+    to_node_ids: UInt64Builder,
+    statuses: StringBuilder,
+}
+
+impl ShowRebalanceResultBuilder {
+    fn new(schema: SchemaRef) -> Self {
+        Self {
+            schema,
+            tenant_names: StringBuilder::new(),
+            database_names: StringBuilder::new(),
+            replication_set_ids: UInt32Builder::new(),
+            vnode_ids: UInt32Builder::new(),
+            from_node_ids: UInt64Builder::new(),
+            to_node_ids: UInt64Builder::new(),
+            statuses: StringBuilder::new(),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_column(
+        &mut self,
+        tenant_name: impl AsRef<str>,
+        database_name: impl AsRef<str>,
+        replication_set_id: u32,
+        vnode_id: u32,
+        from_node_id: u64,
+        to_node_id: Option<u64>,
+        status: impl AsRef<str>,
+    ) {
+        self.tenant_names.append_value(tenant_name.as_ref());
+        self.database_names.append_value(database_name.as_ref());
+        self.replication_set_ids.append_value(replication_set_id);
+        self.vnode_ids.append_value(vnode_id);
+        self.from_node_ids.append_value(from_node_id);
+        self.to_node_ids.append_option(to_node_id);
+        self.statuses.append_value(status.as_ref());
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn build(self) -> std::result::Result<Vec<RecordBatch>, ArrowError> {
+        let ShowRebalanceResultBuilder {
+            schema,
+            mut tenant_names,
+            mut database_names,
+            mut replication_set_ids,
+            mut vnode_ids,
+            mut from_node_ids,
+            mut to_node_ids,
+            mut statuses,
+        } = self;
+
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(tenant_names.finish()),
+                Arc::new(database_names.finish()),
+                Arc::new(replication_set_ids.finish()),
+                Arc::new(vnode_ids.finish()),
+                Arc::new(from_node_ids.finish()),
+                Arc::new(to_node_ids.finish()),
+                Arc::new(statuses.finish()),
+            ],
+        )?;
+
+        Ok(vec![batch])
+    }
+}
@@ -0,0 +1,407 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::ArrayRef;
+use datafusion::arrow::compute::sort_to_indices;
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{DataFusionError, Result as DFResult};
+use datafusion::logical_expr::type_coercion::aggregates::TIMESTAMPS;
+use datafusion::logical_expr::{
+    AccumulatorFactoryFunction, AggregateUDF, ReturnTypeFunction, Signature, StateTypeFunction,
+    TypeSignature, Volatility,
+};
+use datafusion::physical_plan::Accumulator;
+use datafusion::scalar::ScalarValue;
+use spi::query::function::FunctionMetadataManager;
+use spi::QueryError;
+
+use super::{CounterData, TSPoint};
+use crate::extension::expr::aggregate_function::{AggResult, AggState, COUNTER_AGG_UDAF_NAME};
+use crate::extension::expr::expr_utils::check_args;
+
+pub fn register_udaf(func_manager: &mut dyn FunctionMetadataManager) -> Result<(), QueryError> {
+    func_manager.register_udaf(new())?;
+    Ok(())
+}
+
+fn new() -> AggregateUDF {
+    let return_type_func: ReturnTypeFunction = Arc::new(move |input| {
+        check_args(COUNTER_AGG_UDAF_NAME, 2, input)?;
+
+        let result = CounterData::try_new_null(input[0].clone(), input[1].clone())?;
+        let date_type = result.to_scalar()?.get_datatype();
+
+        trace::trace!("return_type: {:?}", date_type);
+
+        Ok(Arc::new(date_type))
+    });
+
+    let state_type_func: StateTypeFunction = Arc::new(move |input, _| {
+        let null_state =
+            CounterDataBuilder::new(input[0].clone(), input[1].clone()).try_to_state()?;
+        let state_data_types = null_state.iter().map(|e| e.get_datatype()).collect();
+        Ok(Arc::new(state_data_types))
+    });
+
+    let accumulator: AccumulatorFactoryFunction = Arc::new(|input, output| {
+        check_args(COUNTER_AGG_UDAF_NAME, 2, input)?;
+
+        let ts_data_type = input[0].clone();
+        let value_data_type = input[1].clone();
+
+        Ok(Box::new(CounterAggAccumulator::try_new(
+            ts_data_type,
+            value_data_type,
+            output.clone(),
+        )?))
+    });
+
+    // counter_agg(
+    //     ts TIMESTAMP,
+    //     value DOUBLE
+    //   ) RETURNS CounterData
+    let type_signatures = TIMESTAMPS
+        .iter()
+        .map(|t| TypeSignature::Exact(vec![t.clone(), DataType::Float64]))
+        .collect();
+
+    AggregateUDF::new(
+        COUNTER_AGG_UDAF_NAME,
+        &Signature::one_of(type_signatures, Volatility::Immutable),
+        &return_type_func,
+        &accumulator,
+        &state_type_func,
+    )
+}
+
+#[derive(Debug)]
+struct CounterAggAccumulator {
+    state: CounterDataBuilder,
+
+    return_date_type: DataType,
+}
+
+impl CounterAggAccumulator {
+    pub fn try_new(
+        ts_data_type: DataType,
+        value_data_type: DataType,
+        return_date_type: DataType,
+    ) -> DFResult<Self> {
+        Ok(Self {
+            state: CounterDataBuilder::new(ts_data_type, value_data_type),
+            return_date_type,
+        })
+    }
+}
+
+impl Accumulator for CounterAggAccumulator {
+    fn state(&self) -> DFResult<Vec<ScalarValue>> {
+        self.state.try_to_state()
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> DFResult<()> {
+        trace::trace!("update_batch: {:?}", values);
+
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        debug_assert!(
+            values.len() == 2,
+            "counter_agg can only take 2 param, but found {}",
+            values.len()
+        );
+
+        let times_records = values[0].as_ref();
+        let value_records = values[1].as_ref();
+
+        // sort by time column, only get indices
+        let indices = sort_to_indices(times_records, None, None)?;
+
+        // every point is needed to detect counter resets,
+        // but only a summary of the sorted batch is saved.
+        let mut summary: Option<CounterData> = None;
+        for idx in indices.iter().flatten() {
+            let idx = idx as usize;
+            if value_records.is_null(idx) {
+                continue;
+            }
+
+            let ts = ScalarValue::try_from_array(times_records, idx)?;
+            let val = ScalarValue::try_from_array(value_records, idx)?;
+            let point = TSPoint { ts, val };
+            match summary.as_mut() {
+                Some(s) => s.push(point)?,
+                None => summary = Some(CounterData::new(point)),
+            }
+        }
+
+        if let Some(summary) = summary {
+            self.state.push(summary);
+        }
+
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, arrays: &[ArrayRef]) -> DFResult<()> {
+        trace::trace!("merge_batch: {:?}", arrays);
+
+        let state = CounterDataBuilder::try_from_arrays(
+            &[
+                self.state.time_data_type.clone(),
+                self.state.value_data_type.clone(),
+            ],
+            arrays,
+        )?;
+
+        if let Some(state) = state {
+            self.state.merge(state);
+        }
+
+        Ok(())
+    }
+
+    fn evaluate(&self) -> DFResult<ScalarValue> {
+        let result = match self.state.clone().build()? {
+            Some(data) => data.to_scalar()?,
+            None => ScalarValue::try_from(&self.return_date_type)?,
+        };
+
+        trace::trace!("CounterAggAccumulator evaluate result: {:?}", result);
+
+        Ok(result)
+    }
+
+    fn size(&self) -> usize {
+        let scalar_size: usize = self
+            .state
+            .summaries
+            .iter()
+            .flat_map(|e| [&e.first, &e.second, &e.penultimate, &e.last])
+            .map(|e| e.ts.size() + e.val.size())
+            .sum();
+
+        std::mem::size_of_val(self) + scalar_size - std::mem::size_of_val(&self.state.summaries)
+            + self.return_date_type.size()
+            - std::mem::size_of_val(&self.return_date_type)
+            + self.state.time_data_type.size()
+            - std::mem::size_of_val(&self.state.time_data_type)
+            + self.state.value_data_type.size()
+            - std::mem::size_of_val(&self.state.value_data_type)
+    }
+}
+
+/// Summaries of sorted batches, they are combined in time order when building [`CounterData`].
+#[derive(Debug, Clone)]
+struct CounterDataBuilder {
+    time_data_type: DataType,
+    value_data_type: DataType,
+
+    summaries: Vec<CounterData>,
+}
+
+impl CounterDataBuilder {
+    fn new(time_data_type: DataType, value_data_type: DataType) -> Self {
+        Self {
+            time_data_type,
+            value_data_type,
+            summaries: Default::default(),
+        }
+    }
+
+    fn push(&mut self, summary: CounterData) {
+        self.summaries.push(summary);
+    }
+
+    fn merge(&mut self, other: CounterDataBuilder) {
+        self.summaries.extend(other.summaries);
+    }
+
+    fn build(mut self) -> DFResult<Option<CounterData>> {
+        self.summaries.sort_unstable_by(|a, b| {
+            a.first
+                .ts
+                .partial_cmp(&b.first.ts)
+                .expect("CounterData's ts column can't be null")
+        });
+
+        let mut summaries = self.summaries.into_iter();
+        let mut result = match summaries.next() {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        for summary in summaries {
+            result.combine(summary)?;
+        }
+
+        Ok(Some(result))
+    }
+}
+
+impl AggState for CounterDataBuilder {
+    fn try_to_state(&self) -> DFResult<Vec<ScalarValue>> {
+        let CounterDataBuilder {
+            time_data_type,
+            value_data_type,
+            summaries,
+        } = self.clone();
+
+        let scalars = summaries
+            .into_iter()
+            .map(|e| e.to_scalar())
+            .collect::<DFResult<Vec<_>>>()?;
+        let child_type = CounterData::try_new_null(time_data_type, value_data_type)?
+            .to_scalar()?
+            .get_datatype();
+        let summary_list = ScalarValue::new_list(Some(scalars), child_type);
+
+        Ok(vec![summary_list])
+    }
+
+    fn try_from_arrays(
+        input_data_types: &[DataType],
+        arrays: &[ArrayRef],
+    ) -> DFResult<Option<Self>> {
+        debug_assert!(arrays.len() == 1, "CounterDataBuilder requires 1 array.");
+        debug_assert!(
+            input_data_types.len() == 2,
+            "CounterDataBuilder requires 2 input data types."
+        );
+
+        let summary_list_array = arrays[0].as_ref();
+
+        trace::trace!("CounterDataBuilder state: {:?}", arrays);
+
+        (0..summary_list_array.len())
+            .map(|idx| {
+                let summaries = match ScalarValue::try_from_array(summary_list_array, idx)? {
+                    ScalarValue::List(Some(vals), _) => vals
+                        .into_iter()
+                        .map(CounterData::try_from_scalar)
+                        .collect::<DFResult<Vec<_>>>()?,
+                    ScalarValue::List(None, _) => vec![],
+                    other => {
+                        return Err(DataFusionError::Internal(format!(
+                            "Expected list, got {:?}",
+                            other
+                        )))
+                    }
+                };
+
+                Ok(CounterDataBuilder {
+                    time_data_type: input_data_types[0].clone(),
+                    value_data_type: input_data_types[1].clone(),
+                    summaries,
+                })
+            })
+            .reduce(|l, r| {
+                let mut l = l?;
+                l.merge(r?);
+                Ok(l)
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::datatypes::{DataType, TimeUnit};
+    use datafusion::scalar::ScalarValue;
+
+    use super::CounterDataBuilder;
+    use crate::extension::expr::aggregate_function::counter::{CounterData, TSPoint};
+
+    fn point(ts: i64, val: f64) -> TSPoint {
+        TSPoint {
+            ts: ScalarValue::TimestampSecond(Some(ts), None),
+            val: ScalarValue::from(val),
+        }
+    }
+
+    fn summary(points: &[(i64, f64)]) -> CounterData {
+        let mut data = CounterData::new(point(points[0].0, points[0].1));
+        for (ts, val) in &points[1..] {
+            data.push(point(*ts, *val)).unwrap();
+        }
+        data
+    }
+
+    fn counter_data_builder() -> CounterDataBuilder {
+        let time = DataType::Timestamp(TimeUnit::Second, None);
+        let value = DataType::Float64;
+
+        CounterDataBuilder::new(time, value)
+    }
+
+    #[test]
+    fn test_counter_data_builder_empty() {
+        let builder = counter_data_builder();
+        assert_eq!(builder.build().unwrap(), None);
+    }
+
+    #[test]
+    fn test_counter_data_resets() {
+        // 10 -> 20 -> 5 (reset) -> 15 -> 3 (reset) -> 8
+        let data = summary(&[
+            (0, 10.0),
+            (10, 20.0),
+            (20, 5.0),
+            (30, 15.0),
+            (40, 3.0),
+            (50, 8.0),
+        ]);
+
+        assert_eq!(data.num_resets().unwrap(), ScalarValue::from(2_u64));
+        // 8 - 10 + (20 + 15)
+        assert_eq!(data.increase().unwrap(), ScalarValue::from(33.0));
+        assert_eq!(data.rate().unwrap(), ScalarValue::from(33.0 / 50.0));
+        assert_eq!(data.irate().unwrap(), ScalarValue::from(5.0 / 10.0));
+    }
+
+    #[test]
+    fn test_counter_data_builder_combine() {
+        let mut builder = counter_data_builder();
+        // pushed out of order, with a reset between the two summaries
+        builder.push(summary(&[(30, 1.0), (40, 6.0)]));
+        builder.push(summary(&[(0, 10.0), (10, 20.0), (20, 25.0)]));
+
+        let data = builder.build().unwrap().unwrap();
+        let expected = CounterData {
+            first: point(0, 10.0),
+            second: point(10, 20.0),
+            penultimate: point(30, 1.0),
+            last: point(40, 6.0),
+            reset_sum: 25.0,
+            num_resets: 1,
+            num_elements: 5,
+        };
+        assert_eq!(data, expected);
+        assert_eq!(
+            data,
+            summary(&[(0, 10.0), (10, 20.0), (20, 25.0), (30, 1.0), (40, 6.0)])
+        );
+    }
+
+    #[test]
+    fn test_counter_data_builder_overlap() {
+        let mut builder = counter_data_builder();
+        builder.push(summary(&[(0, 10.0), (20, 20.0)]));
+        builder.push(summary(&[(10, 1.0), (30, 6.0)]));
+
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn test_counter_data_extrapolated_rate() {
+        let data = summary(&[(10, 100.0), (20, 110.0), (30, 120.0), (40, 130.0)]);
+        let window_start = ScalarValue::TimestampSecond(Some(0), None);
+        let window_end = ScalarValue::TimestampSecond(Some(60), None);
+
+        // sampled interval: 30s, average duration between samples: 10s,
+        // extrapolated by 10s to start, 5s to end, so the increase is
+        // 30 * (45 / 30) = 45 in 60s.
+        assert_eq!(
+            data.extrapolated_rate(&window_start, &window_end).unwrap(),
+            ScalarValue::from(45.0 / 60.0)
+        );
+    }
+}
@@ -0,0 +1,359 @@
+mod counter_agg;
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::{DataType, Field, Fields};
+use datafusion::common::Result as DFResult;
+use datafusion::error::DataFusionError;
+use datafusion::scalar::ScalarValue;
+use spi::query::function::FunctionMetadataManager;
+use spi::QueryError;
+
+use super::{AggResult, TSPoint};
+
+pub fn register_udafs(func_manager: &mut dyn FunctionMetadataManager) -> Result<(), QueryError> {
+    counter_agg::register_udaf(func_manager)?;
+    Ok(())
+}
+
+/// Summary of a monotonically increasing counter.
+///
+/// If a value is less than the previous one, it's regarded as a counter reset
+/// (e.g. the process restarted), the previous value is added to `reset_sum`,
+/// so `value + reset_sum` is still monotonically increasing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CounterData {
+    first: TSPoint,
+    second: TSPoint,
+    penultimate: TSPoint,
+    last: TSPoint,
+    reset_sum: f64,
+    num_resets: u64,
+    num_elements: u64,
+}
+
+impl CounterData {
+    fn try_new_null(time_data_type: DataType, value_data_type: DataType) -> DFResult<Self> {
+        let null = TSPoint::try_new_null(time_data_type, value_data_type)?;
+        Ok(Self {
+            first: null.clone(),
+            second: null.clone(),
+            penultimate: null.clone(),
+            last: null,
+            reset_sum: 0_f64,
+            num_resets: 0,
+            num_elements: 0,
+        })
+    }
+
+    fn new(point: TSPoint) -> Self {
+        Self {
+            first: point.clone(),
+            second: point.clone(),
+            penultimate: point.clone(),
+            last: point,
+            reset_sum: 0_f64,
+            num_resets: 0,
+            num_elements: 1,
+        }
+    }
+
+    /// Append a point, it's timestamp must not be less than the last point.
+    fn push(&mut self, point: TSPoint) -> DFResult<()> {
+        let prev = point_value(&self.last)?;
+        if point_value(&point)? < prev {
+            self.reset_sum += prev;
+            self.num_resets += 1;
+        }
+
+        if self.num_elements == 1 {
+            self.second = point.clone();
+        }
+        self.penultimate = std::mem::replace(&mut self.last, point);
+        self.num_elements += 1;
+
+        Ok(())
+    }
+
+    /// Append a summary of points after all points of this summary.
+    fn combine(&mut self, other: CounterData) -> DFResult<()> {
+        if other.first.ts().partial_cmp(self.last.ts()) == Some(Ordering::Less) {
+            return Err(DataFusionError::Execution(format!(
+                "counter_agg: overlapping time range found, from {} to {} and from {} to {}, \
+                 counters of different series should be aggregated separately",
+                self.first.ts(),
+                self.last.ts(),
+                other.first.ts(),
+                other.last.ts()
+            )));
+        }
+
+        let prev = point_value(&self.last)?;
+        if point_value(&other.first)? < prev {
+            self.reset_sum += prev;
+            self.num_resets += 1;
+        }
+
+        if self.num_elements == 1 {
+            self.second = other.first.clone();
+        }
+        self.penultimate = if other.num_elements > 1 {
+            other.penultimate
+        } else {
+            self.last.clone()
+        };
+        self.last = other.last;
+        self.reset_sum += other.reset_sum;
+        self.num_resets += other.num_resets;
+        self.num_elements += other.num_elements;
+
+        Ok(())
+    }
+
+    /// Increase of the counter with resets compensated, null if there is no point.
+    pub fn increase(&self) -> DFResult<ScalarValue> {
+        if self.num_elements == 0 {
+            return Ok(ScalarValue::Float64(None));
+        }
+        Ok(ScalarValue::from(self.increase_value()?))
+    }
+
+    /// Average increase per second between the first and the last point.
+    pub fn rate(&self) -> DFResult<ScalarValue> {
+        if self.num_elements < 2 {
+            return Ok(ScalarValue::Float64(None));
+        }
+        let duration = duration_seconds(self.first.ts(), self.last.ts())?;
+        if duration <= 0_f64 {
+            return Ok(ScalarValue::Float64(None));
+        }
+        Ok(ScalarValue::from(self.increase_value()? / duration))
+    }
+
+    /// Increase per second between the last two points.
+    pub fn irate(&self) -> DFResult<ScalarValue> {
+        if self.num_elements < 2 {
+            return Ok(ScalarValue::Float64(None));
+        }
+        let duration = duration_seconds(self.penultimate.ts(), self.last.ts())?;
+        if duration <= 0_f64 {
+            return Ok(ScalarValue::Float64(None));
+        }
+        let last = point_value(&self.last)?;
+        let penultimate = point_value(&self.penultimate)?;
+        let increase = if last < penultimate {
+            last
+        } else {
+            last - penultimate
+        };
+        Ok(ScalarValue::from(increase / duration))
+    }
+
+    pub fn num_resets(&self) -> DFResult<ScalarValue> {
+        Ok(ScalarValue::from(self.num_resets))
+    }
+
+    /// Per-second rate of the counter in window `[window_start, window_end)`,
+    /// extrapolated to the window boundaries in the same way as Prometheus `rate()`.
+    pub fn extrapolated_rate(
+        &self,
+        window_start: &ScalarValue,
+        window_end: &ScalarValue,
+    ) -> DFResult<ScalarValue> {
+        if self.num_elements < 2 || window_start.is_null() || window_end.is_null() {
+            return Ok(ScalarValue::Float64(None));
+        }
+
+        let range = duration_seconds(window_start, window_end)?;
+        let sampled_interval = duration_seconds(self.first.ts(), self.last.ts())?;
+        if range <= 0_f64 || sampled_interval <= 0_f64 {
+            return Ok(ScalarValue::Float64(None));
+        }
+
+        let increase = self.increase_value()?;
+        let first_value = point_value(&self.first)?;
+        let mut duration_to_start = duration_seconds(window_start, self.first.ts())?;
+        let duration_to_end = duration_seconds(self.last.ts(), window_end)?;
+        let average_duration_between_samples = sampled_interval / (self.num_elements - 1) as f64;
+
+        // Counters can't be negative, so don't extrapolate past the time
+        // when the counter would have been zero.
+        if increase > 0_f64 && first_value >= 0_f64 {
+            let duration_to_zero = sampled_interval * (first_value / increase);
+            if duration_to_zero < duration_to_start {
+                duration_to_start = duration_to_zero;
+            }
+        }
+
+        // If the first/last samples are close to the boundaries of the range,
+        // extrapolate the result to the boundaries, otherwise extrapolate it
+        // by half of the average duration between samples.
+        let extrapolation_threshold = average_duration_between_samples * 1.1;
+        let mut extrapolate_to_interval = sampled_interval;
+        for duration in [duration_to_start, duration_to_end] {
+            if duration < extrapolation_threshold {
+                extrapolate_to_interval += duration;
+            } else {
+                extrapolate_to_interval += average_duration_between_samples / 2_f64;
+            }
+        }
+
+        Ok(ScalarValue::from(
+            increase * (extrapolate_to_interval / sampled_interval) / range,
+        ))
+    }
+
+    fn increase_value(&self) -> DFResult<f64> {
+        Ok(point_value(&self.last)? - point_value(&self.first)? + self.reset_sum)
+    }
+}
+
+fn point_value(point: &TSPoint) -> DFResult<f64> {
+    match point.val() {
+        ScalarValue::Float64(Some(v)) => Ok(*v),
+        other => Err(DataFusionError::Internal(format!(
+            "Expected non-null Float64 value of counter, got {:?}",
+            other
+        ))),
+    }
+}
+
+fn timestamp_nanos(ts: &ScalarValue) -> DFResult<i64> {
+    match ts {
+        ScalarValue::TimestampSecond(Some(v), _) => Ok(v * 1_000_000_000),
+        ScalarValue::TimestampMillisecond(Some(v), _) => Ok(v * 1_000_000),
+        ScalarValue::TimestampMicrosecond(Some(v), _) => Ok(v * 1_000),
+        ScalarValue::TimestampNanosecond(Some(v), _) => Ok(*v),
+        other => Err(DataFusionError::Execution(format!(
+            "Expected non-null timestamp, got {:?}",
+            other
+        ))),
+    }
+}
+
+/// Returns `end - start` in seconds.
+fn duration_seconds(start: &ScalarValue, end: &ScalarValue) -> DFResult<f64> {
+    let nanos = timestamp_nanos(end)? - timestamp_nanos(start)?;
+    Ok(nanos as f64 / 1_000_000_000_f64)
+}
+
+impl AggResult for CounterData {
+    fn to_scalar(self) -> DFResult<ScalarValue> {
+        let Self {
+            first,
+            second,
+            penultimate,
+            last,
+            reset_sum,
+            num_resets,
+            num_elements,
+        } = self;
+
+        let first = first.to_scalar()?;
+        let second = second.to_scalar()?;
+        let penultimate = penultimate.to_scalar()?;
+        let last = last.to_scalar()?;
+        let reset_sum = ScalarValue::from(reset_sum);
+        let num_resets = ScalarValue::from(num_resets);
+        let num_elements = ScalarValue::from(num_elements);
+
+        let first_data_type = first.get_datatype();
+        let second_data_type = second.get_datatype();
+        let penultimate_data_type = penultimate.get_datatype();
+        let last_data_type = last.get_datatype();
+
+        Ok(ScalarValue::Struct(
+            Some(vec![
+                first,
+                second,
+                penultimate,
+                last,
+                reset_sum,
+                num_resets,
+                num_elements,
+            ]),
+            Fields::from([
+                Arc::new(Field::new("first", first_data_type, true)),
+                Arc::new(Field::new("second", second_data_type, true)),
+                Arc::new(Field::new("penultimate", penultimate_data_type, true)),
+                Arc::new(Field::new("last", last_data_type, true)),
+                Arc::new(Field::new("reset_sum", DataType::Float64, true)),
+                Arc::new(Field::new("num_resets", DataType::UInt64, true)),
+                Arc::new(Field::new("num_elements", DataType::UInt64, true)),
+            ]),
+        ))
+    }
+}
+
+impl CounterData {
+    pub fn try_from_scalar(scalar: ScalarValue) -> DFResult<Self> {
+        let valid_func = |fields: &Fields| {
+            let field_names = [
+                "first",
+                "second",
+                "penultimate",
+                "last",
+                "reset_sum",
+                "num_resets",
+                "num_elements",
+            ];
+            let input_fields = fields.iter().map(|f| f.name().as_str()).collect::<Vec<_>>();
+            if !input_fields.eq(&field_names) {
+                return Err(DataFusionError::External(Box::new(QueryError::Analyzer {
+                    err: format!("Expected CounterData, got {:?}", fields),
+                })));
+            }
+
+            Ok(())
+        };
+
+        match scalar {
+            ScalarValue::Struct(Some(values), fields) => {
+                valid_func(&fields)?;
+
+                let first = TSPoint::try_from_scalar(values[0].clone())?;
+                let second = TSPoint::try_from_scalar(values[1].clone())?;
+                let penultimate = TSPoint::try_from_scalar(values[2].clone())?;
+                let last = TSPoint::try_from_scalar(values[3].clone())?;
+                let reset_sum: f64 = values[4].clone().try_into()?;
+                let num_resets: u64 = values[5].clone().try_into()?;
+                let num_elements: u64 = values[6].clone().try_into()?;
+
+                Ok(Self {
+                    first,
+                    second,
+                    penultimate,
+                    last,
+                    reset_sum,
+                    num_resets,
+                    num_elements,
+                })
+            }
+            ScalarValue::Struct(None, fields) => {
+                valid_func(&fields)?;
+
+                let first =
+                    TSPoint::try_from_scalar(ScalarValue::try_from(fields[0].data_type())?)?;
+                let second =
+                    TSPoint::try_from_scalar(ScalarValue::try_from(fields[1].data_type())?)?;
+                let penultimate =
+                    TSPoint::try_from_scalar(ScalarValue::try_from(fields[2].data_type())?)?;
+                let last = TSPoint::try_from_scalar(ScalarValue::try_from(fields[3].data_type())?)?;
+
+                Ok(Self {
+                    first,
+                    second,
+                    penultimate,
+                    last,
+                    reset_sum: 0_f64,
+                    num_resets: 0,
+                    num_elements: 0,
+                })
+            }
+            _ => Err(DataFusionError::External(Box::new(QueryError::Analyzer {
+                err: format!("Expected CounterData, got {:?}", scalar),
+            }))),
+        }
+    }
+}
@@ -1,9 +1,11 @@
+mod counter;
 #[cfg(test)]
 mod example;
 mod first;
 mod gauge;
 mod last;
 mod sample;
+mod sketch;
 mod state_agg;
 
 use std::sync::Arc;
@@ -19,9 +21,15 @@ use spi::{QueryError, Result};
 pub const SAMPLE_UDAF_NAME: &str = "sample";
 pub const COMPACT_STATE_AGG_UDAF_NAME: &str = "compact_state_agg";
 pub const GAUGE_AGG_UDAF_NAME: &str = "gauge_agg";
+pub const COUNTER_AGG_UDAF_NAME: &str = "counter_agg";
+pub const UDDSKETCH_UDAF_NAME: &str = "uddsketch";
+pub const TDIGEST_UDAF_NAME: &str = "tdigest";
+pub const ROLLUP_UDAF_NAME: &str = "rollup";
 pub const FIRST_UDAF_NAME: &str = "first";
 pub const LAST_UDAF_NAME: &str = "last";
+pub use counter::CounterData;
 pub use gauge::GaugeData;
+pub use sketch::Sketch;
 pub use state_agg::{DurationStates, StateAggData, StatePeriods, TimePeriod};
 
 pub fn register_udafs(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
@@ -31,6 +39,8 @@ pub fn register_udafs(func_manager: &mut dyn FunctionMetadataManager) -> Result<
     sample::register_udaf(func_manager)?;
     state_agg::register_udafs(func_manager)?;
     gauge::register_udafs(func_manager)?;
+    counter::register_udafs(func_manager)?;
+    sketch::register_udafs(func_manager)?;
     first::register_udaf(func_manager)?;
     last::register_udaf(func_manager)?;
     Ok(())
@@ -0,0 +1,88 @@
+mod sketch_agg;
+mod tdigest;
+mod uddsketch;
+
+use datafusion::common::Result as DFResult;
+use datafusion::error::DataFusionError;
+use serde::{Deserialize, Serialize};
+use spi::query::function::FunctionMetadataManager;
+use spi::QueryError;
+use tdigest::TDigest;
+use uddsketch::UddSketch;
+
+pub fn register_udafs(func_manager: &mut dyn FunctionMetadataManager) -> Result<(), QueryError> {
+    sketch_agg::register_udafs(func_manager)?;
+    Ok(())
+}
+
+/// A mergeable sketch of a distribution of values.
+///
+/// Sketches are encoded as JSON strings in SQL, so they can be stored in
+/// a STRING column and re-merged later by `rollup`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Sketch {
+    UddSketch(UddSketch),
+    TDigest(TDigest),
+}
+
+impl Sketch {
+    pub fn try_from_str(s: &str) -> DFResult<Self> {
+        serde_json::from_str(s)
+            .map_err(|e| DataFusionError::Execution(format!("Invalid sketch '{s}': {e}")))
+    }
+
+    pub fn try_to_string(&self) -> DFResult<String> {
+        serde_json::to_string(self)
+            .map_err(|e| DataFusionError::Execution(format!("Failed to encode sketch: {e}")))
+    }
+
+    pub fn merge(&mut self, other: Sketch) -> DFResult<()> {
+        match (self, other) {
+            (Sketch::UddSketch(a), Sketch::UddSketch(b)) => a.merge(b),
+            (Sketch::TDigest(a), Sketch::TDigest(b)) => a.merge(b),
+            (a, b) => Err(DataFusionError::Execution(format!(
+                "Can't merge {} with {}",
+                a.name(),
+                b.name()
+            ))),
+        }
+    }
+
+    /// Estimate the value at quantile `q`, `None` if the sketch is empty.
+    pub fn approx_percentile(&self, q: f64) -> DFResult<Option<f64>> {
+        if !(0_f64..=1_f64).contains(&q) {
+            return Err(DataFusionError::Execution(format!(
+                "Percentile must be in [0, 1], got {q}"
+            )));
+        }
+
+        Ok(match self {
+            Sketch::UddSketch(s) => s.quantile(q),
+            Sketch::TDigest(s) => s.quantile(q),
+        })
+    }
+
+    /// Number of values in the sketch.
+    pub fn num_vals(&self) -> u64 {
+        match self {
+            Sketch::UddSketch(s) => s.count(),
+            Sketch::TDigest(s) => s.count(),
+        }
+    }
+
+    /// Approximate heap size of the sketch.
+    pub fn size(&self) -> usize {
+        match self {
+            Sketch::UddSketch(s) => s.size(),
+            Sketch::TDigest(s) => s.size(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Sketch::UddSketch(_) => "uddsketch",
+            Sketch::TDigest(_) => "tdigest",
+        }
+    }
+}
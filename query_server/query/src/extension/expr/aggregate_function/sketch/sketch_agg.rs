@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef, Float64Array, Int64Array, StringArray};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{downcast_value, DataFusionError, Result as DFResult};
+use datafusion::logical_expr::{
+    AccumulatorFactoryFunction, AggregateUDF, ReturnTypeFunction, Signature, StateTypeFunction,
+    TypeSignature, Volatility,
+};
+use datafusion::physical_plan::Accumulator;
+use datafusion::scalar::ScalarValue;
+use spi::query::function::FunctionMetadataManager;
+use spi::QueryError;
+
+use super::{Sketch, TDigest, UddSketch};
+use crate::extension::expr::aggregate_function::{
+    ROLLUP_UDAF_NAME, TDIGEST_UDAF_NAME, UDDSKETCH_UDAF_NAME,
+};
+
+pub fn register_udafs(func_manager: &mut dyn FunctionMetadataManager) -> Result<(), QueryError> {
+    func_manager.register_udaf(new(SketchAggKind::UddSketch))?;
+    func_manager.register_udaf(new(SketchAggKind::TDigest))?;
+    func_manager.register_udaf(new(SketchAggKind::Rollup))?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SketchAggKind {
+    /// uddsketch(size BIGINT, max_error DOUBLE, value DOUBLE) RETURNS STRING
+    UddSketch,
+    /// tdigest(buckets BIGINT, value DOUBLE) RETURNS STRING
+    TDigest,
+    /// rollup(sketch STRING) RETURNS STRING
+    Rollup,
+}
+
+impl SketchAggKind {
+    fn name(&self) -> &'static str {
+        match self {
+            SketchAggKind::UddSketch => UDDSKETCH_UDAF_NAME,
+            SketchAggKind::TDigest => TDIGEST_UDAF_NAME,
+            SketchAggKind::Rollup => ROLLUP_UDAF_NAME,
+        }
+    }
+
+    fn signature(&self) -> Signature {
+        let type_signature = match self {
+            SketchAggKind::UddSketch => {
+                TypeSignature::Exact(vec![DataType::Int64, DataType::Float64, DataType::Float64])
+            }
+            SketchAggKind::TDigest => {
+                TypeSignature::Exact(vec![DataType::Int64, DataType::Float64])
+            }
+            SketchAggKind::Rollup => TypeSignature::Exact(vec![DataType::Utf8]),
+        };
+        Signature::new(type_signature, Volatility::Immutable)
+    }
+}
+
+fn new(kind: SketchAggKind) -> AggregateUDF {
+    // Sketches are encoded as strings.
+    let return_type_func: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(DataType::Utf8)));
+
+    let state_type_func: StateTypeFunction =
+        Arc::new(move |_, _| Ok(Arc::new(vec![DataType::Utf8])));
+
+    let accumulator: AccumulatorFactoryFunction =
+        Arc::new(move |_, _| Ok(Box::new(SketchAccumulator::new(kind))));
+
+    AggregateUDF::new(
+        kind.name(),
+        &kind.signature(),
+        &return_type_func,
+        &accumulator,
+        &state_type_func,
+    )
+}
+
+#[derive(Debug)]
+struct SketchAccumulator {
+    kind: SketchAggKind,
+    sketch: Option<Sketch>,
+}
+
+impl SketchAccumulator {
+    fn new(kind: SketchAggKind) -> Self {
+        Self { kind, sketch: None }
+    }
+
+    fn merge_sketch(&mut self, other: Sketch) -> DFResult<()> {
+        match self.sketch.as_mut() {
+            Some(sketch) => sketch.merge(other),
+            None => {
+                self.sketch = Some(other);
+                Ok(())
+            }
+        }
+    }
+
+    fn merge_encoded_sketches(&mut self, array: &ArrayRef) -> DFResult<()> {
+        let sketches = downcast_value!(array, StringArray);
+        for sketch in sketches.iter().flatten() {
+            self.merge_sketch(Sketch::try_from_str(sketch)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn null_parameter_error(func_name: &str) -> DataFusionError {
+    DataFusionError::External(Box::new(QueryError::Analyzer {
+        err: format!("The parameters of function {func_name} can't be null"),
+    }))
+}
+
+fn values(array: &ArrayRef) -> DFResult<impl Iterator<Item = f64> + '_> {
+    let values = downcast_value!(array, Float64Array);
+    Ok(values.iter().flatten())
+}
+
+impl Accumulator for SketchAccumulator {
+    fn state(&self) -> DFResult<Vec<ScalarValue>> {
+        Ok(vec![self.evaluate()?])
+    }
+
+    fn update_batch(&mut self, values_arrays: &[ArrayRef]) -> DFResult<()> {
+        trace::trace!("update_batch: {:?}", values_arrays);
+
+        if values_arrays.is_empty() || values_arrays[0].is_empty() {
+            return Ok(());
+        }
+
+        match self.kind {
+            SketchAggKind::UddSketch => {
+                if self.sketch.is_none() {
+                    let sizes = downcast_value!(values_arrays[0], Int64Array);
+                    let errors = downcast_value!(values_arrays[1], Float64Array);
+                    if sizes.is_null(0) || errors.is_null(0) {
+                        return Err(null_parameter_error(UDDSKETCH_UDAF_NAME));
+                    }
+                    let sketch = UddSketch::try_new(sizes.value(0), errors.value(0))?;
+                    self.sketch = Some(Sketch::UddSketch(sketch));
+                }
+                if let Some(Sketch::UddSketch(sketch)) = self.sketch.as_mut() {
+                    for value in values(&values_arrays[2])? {
+                        sketch.add(value);
+                    }
+                }
+            }
+            SketchAggKind::TDigest => {
+                if self.sketch.is_none() {
+                    let buckets = downcast_value!(values_arrays[0], Int64Array);
+                    if buckets.is_null(0) {
+                        return Err(null_parameter_error(TDIGEST_UDAF_NAME));
+                    }
+                    let digest = TDigest::try_new(buckets.value(0))?;
+                    self.sketch = Some(Sketch::TDigest(digest));
+                }
+                if let Some(Sketch::TDigest(digest)) = self.sketch.as_mut() {
+                    digest.add_values(values(&values_arrays[1])?);
+                }
+            }
+            SketchAggKind::Rollup => {
+                self.merge_encoded_sketches(&values_arrays[0])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> DFResult<()> {
+        trace::trace!("merge_batch: {:?}", states);
+
+        if states.is_empty() {
+            return Ok(());
+        }
+
+        self.merge_encoded_sketches(&states[0])
+    }
+
+    fn evaluate(&self) -> DFResult<ScalarValue> {
+        let sketch = self
+            .sketch
+            .as_ref()
+            .map(|s| s.try_to_string())
+            .transpose()?;
+        Ok(ScalarValue::Utf8(sketch))
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.sketch.as_ref().map(|s| s.size()).unwrap_or_default()
+    }
+}
@@ -0,0 +1,203 @@
+use std::f64::consts::PI;
+
+use datafusion::common::Result as DFResult;
+use datafusion::error::DataFusionError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Centroid {
+    mean: f64,
+    weight: u64,
+}
+
+/// Merging t-digest, values are clustered into centroids whose sizes are
+/// bounded by the `k1` scale function, so there are at most about `max_buckets`
+/// centroids, and the centroids near the tails are smaller.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TDigest {
+    max_buckets: u64,
+    count: u64,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    centroids: Vec<Centroid>,
+}
+
+impl TDigest {
+    pub fn try_new(max_buckets: i64) -> DFResult<Self> {
+        if max_buckets <= 0 {
+            return Err(DataFusionError::Execution(format!(
+                "tdigest: buckets must be positive, got {max_buckets}"
+            )));
+        }
+
+        Ok(Self {
+            max_buckets: max_buckets as u64,
+            count: 0,
+            sum: 0_f64,
+            min: None,
+            max: None,
+            centroids: vec![],
+        })
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn size(&self) -> usize {
+        self.centroids.capacity() * std::mem::size_of::<Centroid>()
+    }
+
+    /// Add values to the digest, non-finite values are ignored.
+    pub fn add_values(&mut self, values: impl IntoIterator<Item = f64>) {
+        let mut centroids = std::mem::take(&mut self.centroids);
+        for value in values.into_iter().filter(|v| v.is_finite()) {
+            self.count += 1;
+            self.sum += value;
+            self.min = Some(self.min.map_or(value, |min| min.min(value)));
+            self.max = Some(self.max.map_or(value, |max| max.max(value)));
+            centroids.push(Centroid {
+                mean: value,
+                weight: 1,
+            });
+        }
+        self.compress(centroids);
+    }
+
+    pub fn merge(&mut self, other: TDigest) -> DFResult<()> {
+        if self.max_buckets != other.max_buckets {
+            return Err(DataFusionError::Execution(format!(
+                "Can't merge tdigest({}) with tdigest({})",
+                self.max_buckets, other.max_buckets
+            )));
+        }
+
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = match (self.min, other.min) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max = match (self.max, other.max) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        let mut centroids = std::mem::take(&mut self.centroids);
+        centroids.extend(other.centroids);
+        self.compress(centroids);
+
+        Ok(())
+    }
+
+    /// Estimate the value at quantile `q` (in `[0, 1]`), `None` if the digest is empty.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let (min, max) = (self.min?, self.max?);
+        let total = self.count as f64;
+        let target = q * total;
+
+        // Interpolate between the centers of adjacent centroids,
+        // the min and max values are the bounds.
+        let mut prev_position = 0_f64;
+        let mut prev_mean = min;
+        let mut seen = 0_f64;
+        for centroid in self.centroids.iter() {
+            let position = seen + centroid.weight as f64 / 2_f64;
+            if target < position {
+                return Some(interpolate(
+                    prev_position,
+                    prev_mean,
+                    position,
+                    centroid.mean,
+                    target,
+                ));
+            }
+            prev_position = position;
+            prev_mean = centroid.mean;
+            seen += centroid.weight as f64;
+        }
+
+        Some(interpolate(prev_position, prev_mean, total, max, target))
+    }
+
+    fn compress(&mut self, mut centroids: Vec<Centroid>) {
+        centroids.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        let total = centroids.iter().map(|c| c.weight).sum::<u64>() as f64;
+        let delta = self.max_buckets as f64;
+        // k1 scale function and its inverse
+        let k = |q: f64| delta / (2_f64 * PI) * (2_f64 * q - 1_f64).asin();
+        let k_inv = |k: f64| ((2_f64 * PI * k / delta).min(PI / 2_f64).sin() + 1_f64) / 2_f64;
+        let weight_limit = |weight_so_far: f64| k_inv(k(weight_so_far / total) + 1_f64) * total;
+
+        let mut centroids = centroids.into_iter();
+        let mut current = match centroids.next() {
+            Some(c) => c,
+            None => return,
+        };
+        let mut compressed = Vec::with_capacity(self.max_buckets as usize);
+        let mut weight_so_far = 0_f64;
+        let mut limit = weight_limit(weight_so_far);
+        for centroid in centroids {
+            if weight_so_far + (current.weight + centroid.weight) as f64 <= limit {
+                let weight = current.weight + centroid.weight;
+                current.mean +=
+                    (centroid.mean - current.mean) * centroid.weight as f64 / weight as f64;
+                current.weight = weight;
+            } else {
+                weight_so_far += current.weight as f64;
+                limit = weight_limit(weight_so_far);
+                compressed.push(std::mem::replace(&mut current, centroid));
+            }
+        }
+        compressed.push(current);
+
+        self.centroids = compressed;
+    }
+}
+
+fn interpolate(x0: f64, y0: f64, x1: f64, y1: f64, x: f64) -> f64 {
+    if x1 <= x0 {
+        return y1;
+    }
+    y0 + (x - x0) / (x1 - x0) * (y1 - y0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TDigest;
+
+    #[test]
+    fn test_tdigest_quantile() {
+        let mut digest = TDigest::try_new(100).unwrap();
+        digest.add_values((1..=10000).map(|i| i as f64));
+
+        assert_eq!(digest.count(), 10000);
+        assert!(digest.centroids.len() <= 100);
+        assert_eq!(digest.quantile(0.0), Some(1_f64));
+        assert_eq!(digest.quantile(1.0), Some(10000_f64));
+        for (q, expected) in [(0.01, 100_f64), (0.5, 5000_f64), (0.99, 9900_f64)] {
+            let value = digest.quantile(q).unwrap();
+            assert!(
+                (value - expected).abs() <= 10000_f64 * 0.01,
+                "quantile {q} is {value}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_tdigest_merge() {
+        let mut digest1 = TDigest::try_new(100).unwrap();
+        let mut digest2 = TDigest::try_new(100).unwrap();
+        digest1.add_values((1..=5000).map(|i| i as f64));
+        digest2.add_values((5001..=10000).map(|i| i as f64));
+
+        digest1.merge(digest2).unwrap();
+        assert_eq!(digest1.count(), 10000);
+        let median = digest1.quantile(0.5).unwrap();
+        assert!((median - 5000_f64).abs() <= 100_f64, "median is {median}");
+
+        let other = TDigest::try_new(50).unwrap();
+        assert!(digest1.merge(other).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use datafusion::common::Result as DFResult;
+use datafusion::error::DataFusionError;
+use serde::{Deserialize, Serialize};
+
+/// UDDSketch, a DDSketch which uniformly collapses its buckets when
+/// the number of buckets exceeds `max_buckets`.
+///
+/// Each bucket `k` counts values in `(gamma^(k-1), gamma^k]`, where
+/// `gamma = (1 + error) / (1 - error)`. Once the buckets are collapsed,
+/// `gamma` is squared and the relative error grows to `2 * error / (1 + error^2)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UddSketch {
+    max_buckets: u64,
+    initial_error: f64,
+    compactions: u32,
+    count: u64,
+    sum: f64,
+    zero_count: u64,
+    positive: BTreeMap<i64, u64>,
+    negative: BTreeMap<i64, u64>,
+}
+
+impl UddSketch {
+    pub fn try_new(max_buckets: i64, max_error: f64) -> DFResult<Self> {
+        if max_buckets <= 0 {
+            return Err(DataFusionError::Execution(format!(
+                "uddsketch: size must be positive, got {max_buckets}"
+            )));
+        }
+        if !(max_error > 0_f64 && max_error < 1_f64) {
+            return Err(DataFusionError::Execution(format!(
+                "uddsketch: max_error must be in (0, 1), got {max_error}"
+            )));
+        }
+
+        Ok(Self {
+            max_buckets: max_buckets as u64,
+            initial_error: max_error,
+            compactions: 0,
+            count: 0,
+            sum: 0_f64,
+            zero_count: 0,
+            positive: BTreeMap::new(),
+            negative: BTreeMap::new(),
+        })
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn size(&self) -> usize {
+        // key and count of each bucket, ignores the overhead of BTreeMap
+        (self.positive.len() + self.negative.len()) * std::mem::size_of::<(i64, u64)>()
+    }
+
+    /// Current relative error of the sketch.
+    pub fn error(&self) -> f64 {
+        (0..self.compactions).fold(self.initial_error, |a, _| 2_f64 * a / (1_f64 + a * a))
+    }
+
+    fn gamma(&self) -> f64 {
+        let error = self.error();
+        (1_f64 + error) / (1_f64 - error)
+    }
+
+    fn key(&self, value: f64) -> i64 {
+        (value.ln() / self.gamma().ln()).ceil() as i64
+    }
+
+    fn bucket_value(&self, key: i64) -> f64 {
+        let gamma = self.gamma();
+        2_f64 * gamma.powf(key as f64) / (gamma + 1_f64)
+    }
+
+    fn num_buckets(&self) -> usize {
+        self.positive.len() + self.negative.len() + usize::from(self.zero_count > 0)
+    }
+
+    /// Add a value to the sketch, non-finite values are ignored.
+    pub fn add(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+
+        if value > 0_f64 {
+            *self.positive.entry(self.key(value)).or_default() += 1;
+        } else if value < 0_f64 {
+            *self.negative.entry(self.key(-value)).or_default() += 1;
+        } else {
+            self.zero_count += 1;
+        }
+        self.count += 1;
+        self.sum += value;
+
+        self.compact_to_max_buckets();
+    }
+
+    pub fn merge(&mut self, mut other: UddSketch) -> DFResult<()> {
+        if self.max_buckets != other.max_buckets || self.initial_error != other.initial_error {
+            return Err(DataFusionError::Execution(format!(
+                "Can't merge uddsketch({}, {}) with uddsketch({}, {})",
+                self.max_buckets, self.initial_error, other.max_buckets, other.initial_error
+            )));
+        }
+
+        while self.compactions < other.compactions {
+            self.compact();
+        }
+        while other.compactions < self.compactions {
+            other.compact();
+        }
+
+        for (key, count) in other.positive {
+            *self.positive.entry(key).or_default() += count;
+        }
+        for (key, count) in other.negative {
+            *self.negative.entry(key).or_default() += count;
+        }
+        self.zero_count += other.zero_count;
+        self.count += other.count;
+        self.sum += other.sum;
+
+        self.compact_to_max_buckets();
+
+        Ok(())
+    }
+
+    /// Estimate the value at quantile `q` (in `[0, 1]`), `None` if the sketch is empty.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = (q * (self.count - 1) as f64) as u64;
+        let mut seen = 0;
+        // From the smallest value to the largest value.
+        for (key, count) in self.negative.iter().rev() {
+            seen += count;
+            if seen > rank {
+                return Some(-self.bucket_value(*key));
+            }
+        }
+        seen += self.zero_count;
+        if seen > rank {
+            return Some(0_f64);
+        }
+        for (key, count) in self.positive.iter() {
+            seen += count;
+            if seen > rank {
+                return Some(self.bucket_value(*key));
+            }
+        }
+
+        self.positive
+            .keys()
+            .next_back()
+            .map(|key| self.bucket_value(*key))
+    }
+
+    fn compact_to_max_buckets(&mut self) {
+        while self.num_buckets() > self.max_buckets as usize {
+            let num_buckets = self.num_buckets();
+            self.compact();
+            if self.num_buckets() == num_buckets {
+                // Buckets can't be collapsed any more.
+                break;
+            }
+        }
+    }
+
+    /// Collapse every two adjacent buckets into one bucket.
+    fn compact(&mut self) {
+        fn collapse(buckets: &BTreeMap<i64, u64>) -> BTreeMap<i64, u64> {
+            let mut collapsed = BTreeMap::new();
+            for (key, count) in buckets {
+                // ceil(key / 2)
+                *collapsed.entry((key + 1).div_euclid(2)).or_default() += count;
+            }
+            collapsed
+        }
+
+        self.positive = collapse(&self.positive);
+        self.negative = collapse(&self.negative);
+        self.compactions += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UddSketch;
+
+    fn assert_relative_error(value: f64, expected: f64, error: f64) {
+        assert!(
+            (value - expected).abs() <= expected.abs() * error,
+            "{value} is not close to {expected} with relative error {error}"
+        );
+    }
+
+    #[test]
+    fn test_uddsketch_quantile() {
+        let mut sketch = UddSketch::try_new(200, 0.01).unwrap();
+        for i in 1..=1000 {
+            sketch.add(i as f64);
+        }
+
+        assert_eq!(sketch.count(), 1000);
+        assert_relative_error(sketch.quantile(0.5).unwrap(), 500_f64, sketch.error());
+        assert_relative_error(sketch.quantile(0.9).unwrap(), 900_f64, sketch.error());
+        assert_relative_error(sketch.quantile(1.0).unwrap(), 1000_f64, sketch.error());
+    }
+
+    #[test]
+    fn test_uddsketch_compact() {
+        let mut sketch = UddSketch::try_new(10, 0.01).unwrap();
+        for i in 1..=1000 {
+            sketch.add(i as f64);
+            sketch.add(-(i as f64));
+        }
+
+        assert!(sketch.num_buckets() <= 10);
+        assert!(sketch.error() > 0.01);
+        assert_relative_error(sketch.quantile(0.0).unwrap(), -1000_f64, sketch.error());
+        assert_relative_error(sketch.quantile(1.0).unwrap(), 1000_f64, sketch.error());
+    }
+
+    #[test]
+    fn test_uddsketch_merge() {
+        let mut sketch1 = UddSketch::try_new(20, 0.01).unwrap();
+        let mut sketch2 = UddSketch::try_new(20, 0.01).unwrap();
+        let mut expected = UddSketch::try_new(20, 0.01).unwrap();
+        for i in 1..=100 {
+            sketch1.add(i as f64);
+            expected.add(i as f64);
+        }
+        for i in 1000..=2000 {
+            sketch2.add(i as f64);
+            expected.add(i as f64);
+        }
+
+        sketch1.merge(sketch2).unwrap();
+        assert_eq!(sketch1.count(), expected.count());
+        assert_eq!(sketch1.error(), expected.error());
+        assert_eq!(sketch1.quantile(0.5), expected.quantile(0.5));
+
+        let other = UddSketch::try_new(30, 0.01).unwrap();
+        assert!(sketch1.merge(other).is_err());
+    }
+}
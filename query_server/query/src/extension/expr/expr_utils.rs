@@ -3,11 +3,15 @@ use datafusion::common::Result as DFResult;
 use datafusion::error::DataFusionError;
 use datafusion::logical_expr::utils::find_exprs_in_expr;
 use datafusion::logical_expr::{expr, BinaryExpr, Operator};
+use datafusion::physical_plan::ColumnarValue;
 use datafusion::prelude::Expr;
+use datafusion::scalar::ScalarValue;
 use models::schema::TIME_FIELD_NAME;
 use spi::QueryError;
 
+use super::extract_interval_ns;
 use super::selector_function::{BOTTOM, TOPK};
+use crate::utils::duration::parse_duration;
 
 pub fn check_args(func_name: &str, expects: usize, input: &[DataType]) -> DFResult<()> {
     if input.len() != expects {
@@ -46,6 +50,32 @@ pub fn is_time_column(expr: &Expr) -> bool {
 }
 
 /// Replace 'replace' in 'exprs' with 'with'
+/// Returns the nanoseconds of a positive interval literal, e.g. `INTERVAL '1 hour'` or `'1h'`.
+pub fn literal_interval_ns(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Literal(ScalarValue::Utf8(Some(s))) => {
+            parse_duration(s).ok()?.as_nanos().try_into().ok()
+        }
+        Expr::Literal(value) => extract_interval_ns(&ColumnarValue::Scalar(value.clone())).ok(),
+        _ => None,
+    }
+    .filter(|ns| *ns > 0)
+}
+
+/// Returns the nanoseconds of a timestamp literal or a RFC3339 string literal.
+pub fn literal_timestamp_ns(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Literal(ScalarValue::TimestampSecond(Some(v), _)) => v.checked_mul(1_000_000_000),
+        Expr::Literal(ScalarValue::TimestampMillisecond(Some(v), _)) => v.checked_mul(1_000_000),
+        Expr::Literal(ScalarValue::TimestampMicrosecond(Some(v), _)) => v.checked_mul(1_000),
+        Expr::Literal(ScalarValue::TimestampNanosecond(Some(v), _)) => Some(*v),
+        Expr::Literal(ScalarValue::Utf8(Some(s))) => chrono::DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|e| e.timestamp_nanos()),
+        _ => None,
+    }
+}
+
 pub fn replace_expr_with(exprs: &[Expr], replace: &Expr, with: &Expr) -> Vec<Expr> {
     exprs
         .iter()
@@ -2,7 +2,7 @@ use std::collections::HashSet;
 use std::sync::Arc;
 
 use datafusion::execution::FunctionRegistry;
-use datafusion::logical_expr::{AggregateUDF, ScalarUDF};
+use datafusion::logical_expr::{AggregateUDF, ScalarUDF, WindowUDF};
 use datafusion::prelude::SessionContext;
 use spi::query::function::*;
 use spi::{QueryError, Result};
@@ -37,6 +37,15 @@ impl<'a> FunctionMetadataManager for DFSessionContextFuncAdapter<'a> {
         Err(QueryError::FunctionExists { name: udaf.name })
     }
 
+    fn register_udwf(&mut self, udwf: WindowUDF) -> Result<()> {
+        if self.ctx.udwf(udwf.name.as_str()).is_err() {
+            self.ctx.register_udwf(udwf);
+            return Ok(());
+        }
+
+        Err(QueryError::FunctionExists { name: udwf.name })
+    }
+
     fn udf(&self, name: &str) -> Result<Arc<ScalarUDF>> {
         self.ctx
             .udf(name)
@@ -49,6 +58,12 @@ impl<'a> FunctionMetadataManager for DFSessionContextFuncAdapter<'a> {
             .map_err(|e| QueryError::Datafusion { source: e })
     }
 
+    fn udwf(&self, name: &str) -> Result<Arc<WindowUDF>> {
+        self.ctx
+            .udwf(name)
+            .map_err(|e| QueryError::Datafusion { source: e })
+    }
+
     fn udfs(&self) -> HashSet<String> {
         self.ctx.udfs()
     }
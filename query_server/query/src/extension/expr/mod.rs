@@ -7,6 +7,7 @@ pub mod expr_utils;
 mod scalar_function;
 mod selector_function;
 mod window;
+mod window_function;
 
 use datafusion::arrow::datatypes::{DataType, IntervalUnit};
 pub use scalar_function::{INTERPOLATE, LOCF, TIME_WINDOW_GAPFILL};
@@ -14,8 +15,9 @@ pub use selector_function::{BOTTOM, TOPK};
 use spi::query::function::FunctionMetadataManager;
 use spi::Result;
 pub use window::{
-    ceil_sliding_window, floor_sliding_window, time_window_signature, DEFAULT_TIME_WINDOW_START,
-    TIME_WINDOW, TIME_WINDOW_UDF, WINDOW_COL_NAME, WINDOW_END, WINDOW_START,
+    ceil_sliding_window, extract_interval_ns, floor_sliding_window, time_window_signature,
+    DEFAULT_TIME_WINDOW_START, TIME_WINDOW, TIME_WINDOW_UDF, WINDOW_COL_NAME, WINDOW_END,
+    WINDOW_START,
 };
 
 pub static INTERVALS: &[DataType] = &[
@@ -47,5 +49,6 @@ pub fn load_all_functions(func_manager: &mut dyn FunctionMetadataManager) -> Res
     aggregate_function::register_udafs(func_manager)?;
     selector_function::register_selector_udfs(func_manager)?;
     window::register_window_udfs(func_manager)?;
+    window_function::register_udwfs(func_manager)?;
     Ok(())
 }
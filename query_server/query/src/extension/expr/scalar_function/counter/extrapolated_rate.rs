@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{new_empty_array, ArrayRef};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::ScalarValue;
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::{ReturnTypeFunction, ScalarUDF, Signature, Volatility};
+use datafusion::physical_expr::functions::make_scalar_function;
+use spi::query::function::FunctionMetadataManager;
+use spi::{QueryError, Result};
+
+use crate::extension::expr::aggregate_function::CounterData;
+use crate::extension::expr::expr_utils::check_args;
+
+pub const EXTRAPOLATED_RATE: &str = "extrapolated_rate";
+
+pub fn register_udf(func_manager: &mut dyn FunctionMetadataManager) -> Result<ScalarUDF> {
+    let udf = new();
+    func_manager.register_udf(udf.clone())?;
+    Ok(udf)
+}
+
+fn new() -> ScalarUDF {
+    // extrapolated_rate(
+    //     counter_agg CounterData,
+    //     window_start TIMESTAMP,
+    //     window_end TIMESTAMP
+    //   ) RETURNS DOUBLE
+    let return_type_fn: ReturnTypeFunction = Arc::new(|input| {
+        check_args(EXTRAPOLATED_RATE, 3, input)?;
+
+        for data_type in &input[1..] {
+            if !matches!(data_type, DataType::Timestamp(_, _)) {
+                return Err(DataFusionError::External(Box::new(QueryError::Analyzer {
+                    err: format!(
+                        "The window bounds of function {EXTRAPOLATED_RATE} must be timestamps, got {data_type}"
+                    ),
+                })));
+            }
+        }
+
+        Ok(Arc::new(DataType::Float64))
+    });
+
+    let fun = make_scalar_function(extrapolated_rate_implement);
+
+    ScalarUDF::new(
+        EXTRAPOLATED_RATE,
+        &Signature::any(3, Volatility::Immutable),
+        &return_type_fn,
+        &fun,
+    )
+}
+
+fn extrapolated_rate_implement(input: &[ArrayRef]) -> Result<ArrayRef, DataFusionError> {
+    let array_len = input[0].len();
+    if array_len == 0 {
+        return Ok(new_empty_array(&DataType::Float64));
+    }
+    let mut res = Vec::with_capacity(array_len);
+    for i in 0..array_len {
+        let counter_agg = ScalarValue::try_from_array(input[0].as_ref(), i)?;
+        let window_start = ScalarValue::try_from_array(input[1].as_ref(), i)?;
+        let window_end = ScalarValue::try_from_array(input[2].as_ref(), i)?;
+        let counter_agg = CounterData::try_from_scalar(counter_agg)?;
+        res.push(counter_agg.extrapolated_rate(&window_start, &window_end)?);
+    }
+
+    ScalarValue::iter_to_array(res)
+}
@@ -0,0 +1,17 @@
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+mod extrapolated_rate;
+mod increase;
+mod irate;
+mod num_resets;
+mod rate;
+
+pub fn register_udfs(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+    rate::register_udf(func_manager)?;
+    irate::register_udf(func_manager)?;
+    increase::register_udf(func_manager)?;
+    num_resets::register_udf(func_manager)?;
+    extrapolated_rate::register_udf(func_manager)?;
+    Ok(())
+}
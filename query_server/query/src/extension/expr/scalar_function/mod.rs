@@ -1,3 +1,4 @@
+mod counter;
 mod duration_in;
 #[cfg(test)]
 mod example;
@@ -5,6 +6,7 @@ mod gapfill;
 mod gauge;
 mod interpolate;
 mod locf;
+mod sketch;
 mod utils;
 
 use std::sync::Arc;
@@ -26,6 +28,8 @@ pub fn register_udfs(func_manager: &mut dyn FunctionMetadataManager) -> Result<(
     locf::register_udf(func_manager)?;
     interpolate::register_udf(func_manager)?;
     gauge::register_udfs(func_manager)?;
+    counter::register_udfs(func_manager)?;
+    sketch::register_udfs(func_manager)?;
     duration_in::register_udf(func_manager)?;
     Ok(())
 }
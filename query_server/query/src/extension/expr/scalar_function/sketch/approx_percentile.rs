@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, Float64Array, StringArray};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::downcast_value;
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::{
+    ReturnTypeFunction, ScalarUDF, Signature, TypeSignature, Volatility,
+};
+use datafusion::physical_expr::functions::make_scalar_function;
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+use crate::extension::expr::aggregate_function::Sketch;
+
+pub const APPROX_PERCENTILE: &str = "approx_percentile";
+
+pub fn register_udf(func_manager: &mut dyn FunctionMetadataManager) -> Result<ScalarUDF> {
+    let udf = new();
+    func_manager.register_udf(udf.clone())?;
+    Ok(udf)
+}
+
+fn new() -> ScalarUDF {
+    // approx_percentile(
+    //     sketch STRING,
+    //     percentile DOUBLE
+    //   ) RETURNS DOUBLE
+    let return_type_fn: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+
+    let fun = make_scalar_function(approx_percentile_implement);
+
+    ScalarUDF::new(
+        APPROX_PERCENTILE,
+        &Signature::new(
+            TypeSignature::Exact(vec![DataType::Utf8, DataType::Float64]),
+            Volatility::Immutable,
+        ),
+        &return_type_fn,
+        &fun,
+    )
+}
+
+fn approx_percentile_implement(input: &[ArrayRef]) -> Result<ArrayRef, DataFusionError> {
+    let sketches = downcast_value!(input[0], StringArray);
+    let percentiles = downcast_value!(input[1], Float64Array);
+
+    let result = sketches
+        .iter()
+        .zip(percentiles.iter())
+        .map(|(sketch, percentile)| match (sketch, percentile) {
+            (Some(sketch), Some(percentile)) => {
+                Sketch::try_from_str(sketch)?.approx_percentile(percentile)
+            }
+            _ => Ok(None),
+        })
+        .collect::<Result<Float64Array, DataFusionError>>()?;
+
+    Ok(Arc::new(result))
+}
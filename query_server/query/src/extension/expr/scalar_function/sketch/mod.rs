@@ -0,0 +1,11 @@
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+mod approx_percentile;
+mod num_vals;
+
+pub fn register_udfs(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+    approx_percentile::register_udf(func_manager)?;
+    num_vals::register_udf(func_manager)?;
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, StringArray, UInt64Array};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::downcast_value;
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::{
+    ReturnTypeFunction, ScalarUDF, Signature, TypeSignature, Volatility,
+};
+use datafusion::physical_expr::functions::make_scalar_function;
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+use crate::extension::expr::aggregate_function::Sketch;
+
+pub const NUM_VALS: &str = "num_vals";
+
+pub fn register_udf(func_manager: &mut dyn FunctionMetadataManager) -> Result<ScalarUDF> {
+    let udf = new();
+    func_manager.register_udf(udf.clone())?;
+    Ok(udf)
+}
+
+fn new() -> ScalarUDF {
+    // num_vals(sketch STRING) RETURNS BIGINT UNSIGNED
+    let return_type_fn: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::UInt64)));
+
+    let fun = make_scalar_function(num_vals_implement);
+
+    ScalarUDF::new(
+        NUM_VALS,
+        &Signature::new(
+            TypeSignature::Exact(vec![DataType::Utf8]),
+            Volatility::Immutable,
+        ),
+        &return_type_fn,
+        &fun,
+    )
+}
+
+fn num_vals_implement(input: &[ArrayRef]) -> Result<ArrayRef, DataFusionError> {
+    let sketches = downcast_value!(input[0], StringArray);
+
+    let result = sketches
+        .iter()
+        .map(|sketch| {
+            sketch
+                .map(|s| Sketch::try_from_str(s).map(|s| s.num_vals()))
+                .transpose()
+        })
+        .collect::<Result<UInt64Array, DataFusionError>>()?;
+
+    Ok(Arc::new(result))
+}
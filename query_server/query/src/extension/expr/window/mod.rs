@@ -17,6 +17,6 @@ pub const WINDOW_START: &str = "start";
 pub const WINDOW_END: &str = "end";
 
 pub use time_window::{
-    ceil_sliding_window, floor_sliding_window, signature as time_window_signature,
-    DEFAULT_TIME_WINDOW_START, TIME_WINDOW_UDF,
+    ceil_sliding_window, extract_interval_ns, floor_sliding_window,
+    signature as time_window_signature, DEFAULT_TIME_WINDOW_START, TIME_WINDOW_UDF,
 };
@@ -197,7 +197,7 @@ fn sliding_window(
     Ok((window_start, window_end))
 }
 
-fn extract_interval_ns(interval: &ColumnarValue) -> DFResult<i64> {
+pub fn extract_interval_ns(interval: &ColumnarValue) -> DFResult<i64> {
     let ns = match interval {
         ColumnarValue::Scalar(ScalarValue::IntervalDayTime(Some(v))) => {
             let (days, ms) = IntervalDayTimeType::to_parts(*v);
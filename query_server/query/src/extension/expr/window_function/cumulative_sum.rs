@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef, Float64Array};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{downcast_value, Result as DFResult};
+use datafusion::logical_expr::{
+    PartitionEvaluator, PartitionEvaluatorFactory, ReturnTypeFunction, Signature, Volatility,
+    WindowUDF,
+};
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+use super::CUMULATIVE_SUM;
+
+pub fn register_udwf(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+    func_manager.register_udwf(new())?;
+    Ok(())
+}
+
+fn new() -> WindowUDF {
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+
+    let partition_evaluator_factory: PartitionEvaluatorFactory =
+        Arc::new(|| Ok(Box::new(CumulativeSumEvaluator)));
+
+    // cumulative_sum(value DOUBLE) RETURNS DOUBLE
+    let signature = Signature::exact(vec![DataType::Float64], Volatility::Immutable);
+
+    WindowUDF::new(
+        CUMULATIVE_SUM,
+        &signature,
+        &return_type,
+        &partition_evaluator_factory,
+    )
+}
+
+/// Running total of non-null values.
+#[derive(Debug)]
+struct CumulativeSumEvaluator;
+
+impl PartitionEvaluator for CumulativeSumEvaluator {
+    fn evaluate_all(&mut self, values: &[ArrayRef], num_rows: usize) -> DFResult<ArrayRef> {
+        let value_array = downcast_value!(values[0], Float64Array);
+
+        let mut sum = 0_f64;
+        let result = (0..num_rows)
+            .map(|i| {
+                if value_array.is_null(i) {
+                    return None;
+                }
+                sum += value_array.value(i);
+                Some(sum)
+            })
+            .collect::<Float64Array>();
+
+        Ok(Arc::new(result))
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef, Float64Array};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{downcast_value, Result as DFResult};
+use datafusion::logical_expr::{
+    PartitionEvaluator, PartitionEvaluatorFactory, ReturnTypeFunction, Signature, Volatility,
+    WindowUDF,
+};
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+use super::{
+    time_unit_signatures, timestamp_nanos, unit_nanos, DERIVATIVE, NON_NEGATIVE_DERIVATIVE,
+};
+
+pub fn register_udwfs(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+    func_manager.register_udwf(new(false))?;
+    func_manager.register_udwf(new(true))?;
+    Ok(())
+}
+
+fn new(non_negative: bool) -> WindowUDF {
+    let name = if non_negative {
+        NON_NEGATIVE_DERIVATIVE
+    } else {
+        DERIVATIVE
+    };
+
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+
+    let partition_evaluator_factory: PartitionEvaluatorFactory =
+        Arc::new(move || Ok(Box::new(DerivativeEvaluator { non_negative })));
+
+    // derivative(
+    //     value DOUBLE,
+    //     time TIMESTAMP
+    //     [, unit INTERVAL]
+    //   ) RETURNS DOUBLE
+    let signature = Signature::one_of(
+        time_unit_signatures(&[DataType::Float64]),
+        Volatility::Immutable,
+    );
+
+    WindowUDF::new(name, &signature, &return_type, &partition_evaluator_factory)
+}
+
+/// Rate of change between subsequent non-null values per `unit`.
+#[derive(Debug)]
+struct DerivativeEvaluator {
+    non_negative: bool,
+}
+
+impl PartitionEvaluator for DerivativeEvaluator {
+    fn evaluate_all(&mut self, values: &[ArrayRef], num_rows: usize) -> DFResult<ArrayRef> {
+        let value_array = downcast_value!(values[0], Float64Array);
+        let time_array = timestamp_nanos(&values[1])?;
+        let unit = unit_nanos(values.get(2))? as f64;
+
+        let mut prev: Option<(i64, f64)> = None;
+        let result = (0..num_rows)
+            .map(|i| {
+                if value_array.is_null(i) || time_array.is_null(i) {
+                    return None;
+                }
+                let (time, value) = (time_array.value(i), value_array.value(i));
+                let (prev_time, prev_value) = prev.replace((time, value))?;
+                if time == prev_time {
+                    return None;
+                }
+
+                let derivative = (value - prev_value) / ((time - prev_time) as f64 / unit);
+                if self.non_negative && derivative < 0_f64 {
+                    return None;
+                }
+                Some(derivative)
+            })
+            .collect::<Float64Array>();
+
+        Ok(Arc::new(result))
+    }
+}
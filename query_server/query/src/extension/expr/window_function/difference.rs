@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef, Float64Array};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{downcast_value, Result as DFResult};
+use datafusion::logical_expr::{
+    PartitionEvaluator, PartitionEvaluatorFactory, ReturnTypeFunction, Signature, Volatility,
+    WindowUDF,
+};
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+use super::{DIFFERENCE, NON_NEGATIVE_DIFFERENCE};
+
+pub fn register_udwfs(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+    func_manager.register_udwf(new(false))?;
+    func_manager.register_udwf(new(true))?;
+    Ok(())
+}
+
+fn new(non_negative: bool) -> WindowUDF {
+    let name = if non_negative {
+        NON_NEGATIVE_DIFFERENCE
+    } else {
+        DIFFERENCE
+    };
+
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+
+    let partition_evaluator_factory: PartitionEvaluatorFactory =
+        Arc::new(move || Ok(Box::new(DifferenceEvaluator { non_negative })));
+
+    // difference(value DOUBLE) RETURNS DOUBLE
+    let signature = Signature::exact(vec![DataType::Float64], Volatility::Immutable);
+
+    WindowUDF::new(name, &signature, &return_type, &partition_evaluator_factory)
+}
+
+/// Difference between subsequent non-null values.
+#[derive(Debug)]
+struct DifferenceEvaluator {
+    non_negative: bool,
+}
+
+impl PartitionEvaluator for DifferenceEvaluator {
+    fn evaluate_all(&mut self, values: &[ArrayRef], num_rows: usize) -> DFResult<ArrayRef> {
+        let value_array = downcast_value!(values[0], Float64Array);
+
+        let mut prev: Option<f64> = None;
+        let result = (0..num_rows)
+            .map(|i| {
+                if value_array.is_null(i) {
+                    return None;
+                }
+                let value = value_array.value(i);
+                let difference = value - prev.replace(value)?;
+                if self.non_negative && difference < 0_f64 {
+                    return None;
+                }
+                Some(difference)
+            })
+            .collect::<Float64Array>();
+
+        Ok(Arc::new(result))
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef, Int64Array};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::Result as DFResult;
+use datafusion::logical_expr::{
+    PartitionEvaluator, PartitionEvaluatorFactory, ReturnTypeFunction, Signature, Volatility,
+    WindowUDF,
+};
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+use super::{time_unit_signatures, timestamp_nanos, unit_nanos, ELAPSED};
+
+pub fn register_udwf(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+    func_manager.register_udwf(new())?;
+    Ok(())
+}
+
+fn new() -> WindowUDF {
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Int64)));
+
+    let partition_evaluator_factory: PartitionEvaluatorFactory =
+        Arc::new(|| Ok(Box::new(ElapsedEvaluator)));
+
+    // elapsed(
+    //     time TIMESTAMP
+    //     [, unit INTERVAL]
+    //   ) RETURNS BIGINT
+    let signature = Signature::one_of(time_unit_signatures(&[]), Volatility::Immutable);
+
+    WindowUDF::new(
+        ELAPSED,
+        &signature,
+        &return_type,
+        &partition_evaluator_factory,
+    )
+}
+
+/// Time elapsed between subsequent timestamps in `unit`.
+#[derive(Debug)]
+struct ElapsedEvaluator;
+
+impl PartitionEvaluator for ElapsedEvaluator {
+    fn evaluate_all(&mut self, values: &[ArrayRef], num_rows: usize) -> DFResult<ArrayRef> {
+        let time_array = timestamp_nanos(&values[0])?;
+        let unit = unit_nanos(values.get(1))?;
+
+        let mut prev: Option<i64> = None;
+        let result = (0..num_rows)
+            .map(|i| {
+                if time_array.is_null(i) {
+                    return None;
+                }
+                let time = time_array.value(i);
+                Some((time - prev.replace(time)?) / unit)
+            })
+            .collect::<Int64Array>();
+
+        Ok(Arc::new(result))
+    }
+}
@@ -0,0 +1,89 @@
+//! Time-aware window functions ported from InfluxQL.
+//!
+//! They must be called with an `OVER` clause, rows of each series are
+//! partitioned by tag columns and ordered by time, e.g.
+//! `derivative(f0, time, interval '1 second') OVER (PARTITION BY t0 ORDER BY time)`.
+
+mod cumulative_sum;
+mod derivative;
+mod difference;
+mod elapsed;
+mod moving_average;
+
+use datafusion::arrow::array::{Array, ArrayRef, TimestampNanosecondArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::common::{downcast_value, Result as DFResult};
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::type_coercion::aggregates::TIMESTAMPS;
+use datafusion::logical_expr::TypeSignature;
+use datafusion::physical_plan::ColumnarValue;
+use datafusion::scalar::ScalarValue;
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+use super::window::extract_interval_ns;
+use super::INTERVALS;
+
+pub const DERIVATIVE: &str = "derivative";
+pub const NON_NEGATIVE_DERIVATIVE: &str = "non_negative_derivative";
+pub const DIFFERENCE: &str = "difference";
+pub const NON_NEGATIVE_DIFFERENCE: &str = "non_negative_difference";
+pub const MOVING_AVERAGE: &str = "moving_average";
+pub const CUMULATIVE_SUM: &str = "cumulative_sum";
+pub const ELAPSED: &str = "elapsed";
+
+/// The default time unit of `derivative` and `elapsed`.
+const DEFAULT_UNIT_NS: i64 = 1_000_000_000;
+
+pub fn register_udwfs(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+    derivative::register_udwfs(func_manager)?;
+    difference::register_udwfs(func_manager)?;
+    moving_average::register_udwf(func_manager)?;
+    cumulative_sum::register_udwf(func_manager)?;
+    elapsed::register_udwf(func_manager)?;
+    Ok(())
+}
+
+/// Signatures of `func(<args>, time TIMESTAMP [, unit INTERVAL])`.
+fn time_unit_signatures(args: &[DataType]) -> Vec<TypeSignature> {
+    TIMESTAMPS
+        .iter()
+        .flat_map(|time| {
+            let mut without_unit = args.to_vec();
+            without_unit.push(time.clone());
+            let with_units = INTERVALS.iter().map(move |unit| {
+                let mut with_unit = args.to_vec();
+                with_unit.push(time.clone());
+                with_unit.push(unit.clone());
+                TypeSignature::Exact(with_unit)
+            });
+            std::iter::once(TypeSignature::Exact(without_unit)).chain(with_units)
+        })
+        .collect()
+}
+
+/// Timestamps in nanoseconds.
+fn timestamp_nanos(array: &ArrayRef) -> DFResult<TimestampNanosecondArray> {
+    let array = cast(array, &DataType::Timestamp(TimeUnit::Nanosecond, None))?;
+    Ok(downcast_value!(array, TimestampNanosecondArray).clone())
+}
+
+/// The time unit in nanoseconds, which must be a constant positive interval.
+fn unit_nanos(array: Option<&ArrayRef>) -> DFResult<i64> {
+    let array = match array {
+        Some(array) if !array.is_empty() => array,
+        _ => return Ok(DEFAULT_UNIT_NS),
+    };
+
+    let unit = extract_interval_ns(&ColumnarValue::Scalar(ScalarValue::try_from_array(
+        array, 0,
+    )?))?;
+    if unit <= 0 {
+        return Err(DataFusionError::Execution(format!(
+            "The time unit must be positive, got {unit}ns"
+        )));
+    }
+
+    Ok(unit)
+}
@@ -0,0 +1,79 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{new_empty_array, Array, ArrayRef, Float64Array, Int64Array};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{downcast_value, Result as DFResult};
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::{
+    PartitionEvaluator, PartitionEvaluatorFactory, ReturnTypeFunction, Signature, Volatility,
+    WindowUDF,
+};
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+use super::MOVING_AVERAGE;
+
+pub fn register_udwf(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+    func_manager.register_udwf(new())?;
+    Ok(())
+}
+
+fn new() -> WindowUDF {
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+
+    let partition_evaluator_factory: PartitionEvaluatorFactory =
+        Arc::new(|| Ok(Box::new(MovingAverageEvaluator)));
+
+    // moving_average(value DOUBLE, n BIGINT) RETURNS DOUBLE
+    let signature = Signature::exact(
+        vec![DataType::Float64, DataType::Int64],
+        Volatility::Immutable,
+    );
+
+    WindowUDF::new(
+        MOVING_AVERAGE,
+        &signature,
+        &return_type,
+        &partition_evaluator_factory,
+    )
+}
+
+/// Average of the last `n` non-null values, null if there are less than `n` values.
+#[derive(Debug)]
+struct MovingAverageEvaluator;
+
+impl PartitionEvaluator for MovingAverageEvaluator {
+    fn evaluate_all(&mut self, values: &[ArrayRef], num_rows: usize) -> DFResult<ArrayRef> {
+        let value_array = downcast_value!(values[0], Float64Array);
+        let n_array = downcast_value!(values[1], Int64Array);
+        if num_rows == 0 {
+            return Ok(new_empty_array(&DataType::Float64));
+        }
+        if n_array.is_null(0) || n_array.value(0) <= 0 {
+            return Err(DataFusionError::Execution(format!(
+                "The window size of {MOVING_AVERAGE} must be a positive integer"
+            )));
+        }
+        let n = n_array.value(0) as usize;
+
+        let mut window = VecDeque::with_capacity(n);
+        let mut sum = 0_f64;
+        let result = (0..num_rows)
+            .map(|i| {
+                if value_array.is_null(i) {
+                    return None;
+                }
+                let value = value_array.value(i);
+                window.push_back(value);
+                sum += value;
+                if window.len() > n {
+                    sum -= window.pop_front().unwrap_or_default();
+                }
+                (window.len() == n).then(|| sum / n as f64)
+            })
+            .collect::<Float64Array>();
+
+        Ok(Arc::new(result))
+    }
+}
@@ -1,2 +1,4 @@
+pub mod push_down_first_last;
 pub mod reject_cross_join;
+pub mod rewrite_materialized_view;
 pub mod rewrite_tag_scan;
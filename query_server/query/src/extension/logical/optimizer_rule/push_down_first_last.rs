@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use datafusion::common::{Column, DFField, DFSchema};
+use datafusion::datasource::source_as_provider;
+use datafusion::error::Result;
+use datafusion::logical_expr::expr::AggregateUDF;
+use datafusion::logical_expr::logical_plan::AggWithGrouping;
+use datafusion::logical_expr::{Aggregate, Expr, LogicalPlan, TableScan};
+use datafusion::optimizer::optimizer::ApplyOrder;
+use datafusion::optimizer::{OptimizerConfig, OptimizerRule};
+use models::schema::ColumnType;
+
+use crate::data_source::batch::tskv::ClusterTable;
+use crate::extension::expr::aggregate_function::{FIRST_UDAF_NAME, LAST_UDAF_NAME};
+
+/// Push down `first(time, field)` and `last(time, field)` to tskv
+///
+/// Triggering conditions:
+/// 1. The aggregation has no group by, and only computes `first` and `last` of the fields
+/// 2. The input of the aggregation is the table scan, so all the filters are pushed down
+///
+/// The table scan returns the timestamp and the value of the partial results,
+/// the aggregation is kept to merge the partial results by the timestamps.
+pub struct PushDownFirstLast {}
+
+impl OptimizerRule for PushDownFirstLast {
+    fn try_optimize(
+        &self,
+        plan: &LogicalPlan,
+        _optimizer_config: &dyn OptimizerConfig,
+    ) -> Result<Option<LogicalPlan>> {
+        let LogicalPlan::Aggregate(Aggregate {
+            input,
+            group_expr,
+            aggr_expr,
+            ..
+        }) = plan
+        else {
+            return Ok(None);
+        };
+        if !group_expr.is_empty() {
+            return Ok(None);
+        }
+        let LogicalPlan::TableScan(scan) = input.as_ref() else {
+            return Ok(None);
+        };
+        if scan.agg_with_grouping.is_some() || scan.fetch.is_some() {
+            return Ok(None);
+        }
+        let Ok(provider) = source_as_provider(&scan.source) else {
+            return Ok(None);
+        };
+        let Some(table) = provider.as_any().downcast_ref::<ClusterTable>() else {
+            return Ok(None);
+        };
+        let table_schema = table.table_schema();
+        let is_column_type = |column: &Column, f: fn(&ColumnType) -> bool| {
+            table_schema
+                .column(&column.name)
+                .map(|c| f(&c.column_type))
+                .unwrap_or(false)
+        };
+
+        let mut partial_fields = Vec::with_capacity(aggr_expr.len() * 2);
+        let mut final_aggr_expr = Vec::with_capacity(aggr_expr.len());
+        for expr in aggr_expr {
+            let Expr::AggregateUDF(AggregateUDF {
+                fun,
+                args,
+                filter: None,
+                order_by: None,
+            }) = expr
+            else {
+                return Ok(None);
+            };
+            if fun.name != FIRST_UDAF_NAME && fun.name != LAST_UDAF_NAME {
+                return Ok(None);
+            }
+            let [Expr::Column(time), Expr::Column(value)] = args.as_slice() else {
+                return Ok(None);
+            };
+            // Values of a field converted from a tag are partly kept in series keys.
+            if !is_column_type(time, ColumnType::is_time)
+                || !is_column_type(value, ColumnType::is_field)
+                || table_schema
+                    .column(&value.name)
+                    .map_or(true, |c| c.converted_from_tag.is_some())
+            {
+                return Ok(None);
+            }
+
+            // The partial results are named by the aggregation, the final aggregation
+            // is aliased to the name, so that the output schema is not changed.
+            let name = expr.display_name()?;
+            let time_field = DFField::new_unqualified(
+                &format!("{name}_time"),
+                input.schema().field_from_column(time)?.data_type().clone(),
+                true,
+            );
+            let value_field = DFField::new_unqualified(
+                &format!("{name}_value"),
+                input.schema().field_from_column(value)?.data_type().clone(),
+                true,
+            );
+            final_aggr_expr.push(
+                Expr::AggregateUDF(AggregateUDF {
+                    fun: fun.clone(),
+                    args: vec![
+                        Expr::Column(time_field.qualified_column()),
+                        Expr::Column(value_field.qualified_column()),
+                    ],
+                    filter: None,
+                    order_by: None,
+                })
+                .alias(name),
+            );
+            partial_fields.push(time_field);
+            partial_fields.push(value_field);
+        }
+
+        let partial_schema = Arc::new(DFSchema::new_with_metadata(partial_fields, HashMap::new())?);
+        let partial_scan = LogicalPlan::TableScan(TableScan {
+            projected_schema: partial_schema.clone(),
+            agg_with_grouping: Some(AggWithGrouping {
+                group_expr: vec![],
+                agg_expr: aggr_expr.clone(),
+                schema: partial_schema,
+            }),
+            ..scan.clone()
+        });
+
+        Ok(Some(LogicalPlan::Aggregate(Aggregate::try_new(
+            Arc::new(partial_scan),
+            vec![],
+            final_aggr_expr,
+        )?)))
+    }
+
+    fn name(&self) -> &str {
+        "push_down_first_last"
+    }
+
+    fn apply_order(&self) -> Option<ApplyOrder> {
+        Some(ApplyOrder::BottomUp)
+    }
+}
@@ -0,0 +1,522 @@
+use std::collections::HashSet;
+use std::iter;
+use std::sync::Arc;
+
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::{Column, DataFusionError, ScalarValue};
+use datafusion::error::Result;
+use datafusion::logical_expr::expr::AggregateFunction;
+use datafusion::logical_expr::{
+    aggregate_function, Aggregate, BinaryExpr, Expr, LogicalPlan, LogicalPlanBuilder, Operator,
+    TableScan, TableSource,
+};
+use datafusion::optimizer::optimizer::ApplyOrder;
+use datafusion::optimizer::utils::split_conjunction;
+use datafusion::optimizer::{OptimizerConfig, OptimizerRule};
+use datafusion::prelude::{cast, date_bin, lit, lit_timestamp_nano};
+use models::schema::{MaterializedView, TskvTableSchemaRef, TIME_FIELD_NAME};
+
+use crate::data_source::batch::tskv::ClusterTable;
+use crate::data_source::source_downcast_adapter;
+use crate::data_source::table_source::{TableHandle, TableSourceAdapter};
+use crate::extension::expr::expr_utils::literal_timestamp_ns;
+use crate::materialized_view::{time_window_interval, view_aggregate_function};
+
+/// Rewrite the aggregations of a tskv table to read the materialized views of the table
+///
+/// Triggering conditions:
+/// 1. The aggregation groups by a time window and tags, and computes aggregations the view keeps
+/// 2. The filters only reference the tags, or compare the time with the bounds of the windows
+/// 3. The windows are the windows of the view, or the tags are a subset of the tags of the view
+///    and the window is a multiple of the window of the view, when no `avg` is computed
+///
+/// The aggregation is kept and computed over the rows of the view,
+/// so `time_window` and `date_bin` are applied to the start of the windows.
+/// Only the windows before the watermark of the view have been computed, so the later windows
+/// are aggregated from the base table into the rows of the view, and read with the view.
+///
+/// Must run before the analyzer, which replaces the sources of the table scans.
+/// The query refreshing the views bins the time with an explicit origin, so it's never rewritten.
+pub struct RewriteMaterializedView {}
+
+impl OptimizerRule for RewriteMaterializedView {
+    fn try_optimize(
+        &self,
+        plan: &LogicalPlan,
+        _optimizer_config: &dyn OptimizerConfig,
+    ) -> Result<Option<LogicalPlan>> {
+        let new_plan = plan.clone().transform_up(&|plan| {
+            if let LogicalPlan::Aggregate(aggregate) = &plan {
+                if let Some(new_plan) = rewrite_aggregate(aggregate)? {
+                    return Ok(Transformed::Yes(new_plan));
+                }
+            }
+            Ok(Transformed::No(plan))
+        })?;
+
+        Ok((&new_plan != plan).then_some(new_plan))
+    }
+
+    fn name(&self) -> &str {
+        "rewrite_materialized_view"
+    }
+
+    fn apply_order(&self) -> Option<ApplyOrder> {
+        None
+    }
+}
+
+fn rewrite_aggregate(aggregate: &Aggregate) -> Result<Option<LogicalPlan>> {
+    // Aggregate -> [Filter | SubqueryAlias]* -> TableScan
+    let mut predicates = vec![];
+    let mut input = aggregate.input.as_ref();
+    let scan = loop {
+        match input {
+            LogicalPlan::Filter(filter) => {
+                predicates.extend(split_conjunction(&filter.predicate));
+                input = filter.input.as_ref();
+            }
+            LogicalPlan::SubqueryAlias(alias) => input = alias.input.as_ref(),
+            LogicalPlan::TableScan(scan) => break scan,
+            _ => return Ok(None),
+        }
+    };
+    if scan.projection.is_some() || !scan.filters.is_empty() || scan.fetch.is_some() {
+        return Ok(None);
+    }
+
+    let Ok(adapter) = source_downcast_adapter(&scan.source) else {
+        return Ok(None);
+    };
+    let TableHandle::Tskv(table) = adapter.table_handle() else {
+        return Ok(None);
+    };
+    let base = table.table_schema();
+
+    let mut interval = None;
+    let mut tags = HashSet::new();
+    for expr in &aggregate.group_expr {
+        if let Some(ns) = time_window_interval(expr) {
+            if interval.replace(ns).is_some() {
+                return Ok(None);
+            }
+            continue;
+        }
+        match expr {
+            Expr::Column(c) if is_tag(&base, &c.name) => {
+                tags.insert(c.name.as_str());
+            }
+            _ => return Ok(None),
+        }
+    }
+    let Some(interval) = interval else {
+        return Ok(None);
+    };
+
+    // (function, column) of the aggregations
+    let mut aggregations = Vec::with_capacity(aggregate.aggr_expr.len());
+    for expr in &aggregate.aggr_expr {
+        match expr {
+            Expr::AggregateFunction(AggregateFunction {
+                fun,
+                args,
+                distinct: false,
+                filter: None,
+                order_by: None,
+            }) => match (view_aggregate_function(fun), args.as_slice()) {
+                (Some(func), [Expr::Column(c)]) => aggregations.push((func, c)),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        }
+    }
+
+    let mut filter_tags = HashSet::new();
+    let mut time_bounds = vec![];
+    for predicate in &predicates {
+        if let Some(ts) = time_lower_or_upper_bound(predicate) {
+            time_bounds.push(ts);
+            continue;
+        }
+        let columns = predicate.to_columns()?;
+        if columns.is_empty() || !columns.iter().all(|c| is_tag(&base, &c.name)) {
+            return Ok(None);
+        }
+        filter_tags.extend(columns.into_iter().map(|c| c.name));
+    }
+
+    let views = table.meta().get_materialized_views_of(&base.db, &base.name);
+    let matched = views
+        .into_iter()
+        .filter(|view| {
+            let exact = view.interval == interval
+                && view.tags.len() == tags.len()
+                && view.tags.iter().all(|t| tags.contains(t.as_str()));
+            let rollup = interval % view.interval == 0
+                && tags.iter().all(|t| view.tags.iter().any(|v| v == t))
+                && aggregations.iter().all(|(func, _)| *func != "avg");
+
+            view.watermark.is_some()
+                && (exact || rollup)
+                && filter_tags.iter().all(|t| view.tags.contains(t))
+                && time_bounds
+                    .iter()
+                    .all(|ts| ts.rem_euclid(view.interval) == 0)
+                && aggregations
+                    .iter()
+                    .all(|(func, c)| view.aggregate(func, &c.name).is_some())
+        })
+        // The view with the widest windows keeps the least rows
+        .max_by_key(|view| view.interval);
+    let Some(view) = matched else {
+        return Ok(None);
+    };
+    let Some(view_schema) = table
+        .meta()
+        .get_tskv_table_schema(&view.db, &view.name)
+        .map_err(|e| DataFusionError::External(Box::new(e)))?
+    else {
+        return Ok(None);
+    };
+
+    let new_scan = view_source(scan, &table, &view, view_schema)?;
+    let new_input = replace_scan(aggregate.input.as_ref(), new_scan)?;
+
+    let aggr_expr = aggregate
+        .aggr_expr
+        .iter()
+        .zip(aggregations)
+        .map(|(expr, (func, c))| {
+            let field = view
+                .aggregate(func, &c.name)
+                .map(|agg| agg.field.clone())
+                .unwrap_or_default();
+            Ok(reaggregate(func, Column::new(c.relation.clone(), field))
+                .alias(expr.display_name()?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let new_plan = LogicalPlanBuilder::from(new_input)
+        .aggregate(aggregate.group_expr.clone(), aggr_expr)?
+        .build()?;
+
+    Ok(Some(new_plan))
+}
+
+/// Returns the timestamp of `time >= <ts>` or `time < <ts>`,
+/// which keep or drop the whole windows when `<ts>` is aligned to them.
+fn time_lower_or_upper_bound(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::BinaryExpr(BinaryExpr {
+            left,
+            op: Operator::GtEq | Operator::Lt,
+            right,
+        }) => match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(c), value) if c.name == TIME_FIELD_NAME => {
+                literal_timestamp_ns(unwrap_cast(value))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn unwrap_cast(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Cast(cast) => unwrap_cast(&cast.expr),
+        Expr::TryCast(cast) => unwrap_cast(&cast.expr),
+        _ => expr,
+    }
+}
+
+fn is_tag(schema: &TskvTableSchemaRef, name: &str) -> bool {
+    schema
+        .column(name)
+        .map_or(false, |col| col.column_type.is_tag())
+}
+
+/// The aggregation computing the same value from the rows of the view.
+fn reaggregate(func: &str, column: Column) -> Expr {
+    let fun = match func {
+        "count" | "sum" => aggregate_function::AggregateFunction::Sum,
+        "min" => aggregate_function::AggregateFunction::Min,
+        "max" => aggregate_function::AggregateFunction::Max,
+        // Each window of the view has a single row
+        _ => aggregate_function::AggregateFunction::Avg,
+    };
+    aggregate_expr(fun, Expr::Column(column))
+}
+
+fn aggregate_expr(fun: aggregate_function::AggregateFunction, arg: Expr) -> Expr {
+    Expr::AggregateFunction(AggregateFunction::new(fun, vec![arg], false, None, None))
+}
+
+/// Scans the rows of the view before its watermark, together with the later windows
+/// aggregated from the base table. Both are named after the base table, so the columns
+/// keep resolving.
+fn view_source(
+    scan: &TableScan,
+    table: &ClusterTable,
+    view: &MaterializedView,
+    view_schema: TskvTableSchemaRef,
+) -> Result<LogicalPlan> {
+    let watermark = view.watermark.unwrap_or(i64::MIN);
+    let column = |name: &str| Expr::Column(Column::new(Some(scan.table_name.clone()), name));
+
+    let view_table = Arc::new(table.with_table_schema(view_schema));
+    let source: Arc<dyn TableSource> = Arc::new(TableSourceAdapter::try_new(
+        scan.table_name.clone(),
+        view.db.clone(),
+        view.name.clone(),
+        view_table,
+    )?);
+    let computed = LogicalPlanBuilder::scan(scan.table_name.clone(), source, None)?
+        .filter(column(TIME_FIELD_NAME).lt(lit_timestamp_nano(watermark)))?
+        .build()?;
+
+    // The same rows as the query refreshing the view, see `build_refresh_sql`
+    let window = date_bin(
+        lit(ScalarValue::IntervalMonthDayNano(Some(
+            view.interval as i128,
+        ))),
+        column(TIME_FIELD_NAME),
+        lit_timestamp_nano(0),
+    );
+    let group_expr = iter::once(window)
+        .chain(view.tags.iter().map(|tag| column(tag)))
+        .collect::<Vec<_>>();
+    let aggr_expr = view
+        .aggregates
+        .iter()
+        .map(|agg| {
+            let fun = agg.func.parse::<aggregate_function::AggregateFunction>()?;
+            Ok(aggregate_expr(fun, column(&agg.column)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let tail = LogicalPlanBuilder::from(LogicalPlan::TableScan(scan.clone()))
+        .filter(column(TIME_FIELD_NAME).gt_eq(lit_timestamp_nano(watermark)))?
+        .aggregate(group_expr, aggr_expr)?
+        .build()?;
+    let projection = tail
+        .schema()
+        .fields()
+        .iter()
+        .zip(computed.schema().fields())
+        .map(|(from, to)| {
+            cast(
+                Expr::Column(from.qualified_column()),
+                to.data_type().clone(),
+            )
+            .alias(to.name())
+        })
+        .collect::<Vec<_>>();
+
+    LogicalPlanBuilder::from(computed)
+        .union(
+            LogicalPlanBuilder::from(tail)
+                .project(projection)?
+                .build()?,
+        )?
+        .alias(scan.table_name.clone())?
+        .build()
+}
+
+fn replace_scan(plan: &LogicalPlan, new_scan: LogicalPlan) -> Result<LogicalPlan> {
+    match plan {
+        LogicalPlan::TableScan(_) => Ok(new_scan),
+        _ => {
+            let input = replace_scan(plan.inputs()[0], new_scan)?;
+            plan.with_new_inputs(&[input])
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use coordinator::service_mock::MockCoordinator;
+    use datafusion::arrow::datatypes::TimeUnit;
+    use datafusion::common::ScalarValue;
+    use datafusion::error::Result;
+    use datafusion::logical_expr::expr::ScalarFunction;
+    use datafusion::logical_expr::{BuiltinScalarFunction, LogicalPlan, LogicalPlanBuilder};
+    use datafusion::optimizer::{OptimizerContext, OptimizerRule};
+    use datafusion::prelude::{avg, col, lit, max, Expr};
+    use meta::model::meta_tenant::TenantMeta;
+    use models::meta_data::{DatabaseInfo, TenantMetaData};
+    use models::schema::{
+        ColumnType, DatabaseSchema, MaterializedView, MaterializedViewAggregate, TableColumn,
+        TableSchema, TskvTableSchema,
+    };
+    use models::ValueType;
+
+    use super::RewriteMaterializedView;
+    use crate::data_source::batch::tskv::ClusterTable;
+    use crate::data_source::split;
+    use crate::data_source::table_source::TableSourceAdapter;
+
+    const HOUR: i64 = 3_600_000_000_000;
+
+    fn table_schema(name: &str, columns: Vec<TableColumn>) -> TskvTableSchema {
+        let mut columns = columns;
+        columns.insert(0, TableColumn::new_time_column(0, TimeUnit::Nanosecond));
+        for (id, column) in columns.iter_mut().enumerate() {
+            column.id = id as u32;
+        }
+        TskvTableSchema::new(
+            "cnosdb".to_string(),
+            "public".to_string(),
+            name.to_string(),
+            columns,
+        )
+    }
+
+    /// Table `cpu(time, host, usage)` with the view `cpu_1h(time, host, avg_usage)`.
+    fn cpu_scan(watermark: Option<i64>) -> Result<LogicalPlan> {
+        let cpu = Arc::new(table_schema(
+            "cpu",
+            vec![
+                TableColumn::new_tag_column(0, "host".to_string()),
+                TableColumn::new_with_default(
+                    "usage".to_string(),
+                    ColumnType::Field(ValueType::Float),
+                ),
+            ],
+        ));
+        let cpu_1h = Arc::new(table_schema(
+            "cpu_1h",
+            vec![
+                TableColumn::new_tag_column(0, "host".to_string()),
+                TableColumn::new_with_default(
+                    "avg_usage".to_string(),
+                    ColumnType::Field(ValueType::Float),
+                ),
+            ],
+        ));
+        let view = MaterializedView {
+            tenant: "cnosdb".to_string(),
+            db: "public".to_string(),
+            name: "cpu_1h".to_string(),
+            base_table: "cpu".to_string(),
+            interval: HOUR,
+            tags: vec!["host".to_string()],
+            aggregates: vec![MaterializedViewAggregate {
+                func: "avg".to_string(),
+                column: "usage".to_string(),
+                field: "avg_usage".to_string(),
+            }],
+            query: String::new(),
+            owner: "root".to_string(),
+            watermark,
+        };
+
+        let mut data = TenantMetaData::new();
+        data.dbs.insert(
+            "public".to_string(),
+            DatabaseInfo {
+                schema: DatabaseSchema::new("cnosdb", "public"),
+                buckets: vec![],
+                tables: HashMap::from([
+                    ("cpu".to_string(), TableSchema::TsKvTableSchema(cpu.clone())),
+                    ("cpu_1h".to_string(), TableSchema::TsKvTableSchema(cpu_1h)),
+                ]),
+                materialized_views: HashMap::from([("cpu_1h".to_string(), view)]),
+            },
+        );
+
+        let table = Arc::new(ClusterTable::new(
+            Arc::new(MockCoordinator::default()),
+            split::default_split_manager_ref_only_for_test(),
+            Arc::new(TenantMeta::mock_with_data(data)),
+            cpu,
+        ));
+        let source = Arc::new(TableSourceAdapter::try_new("cpu", "public", "cpu", table)?);
+        LogicalPlanBuilder::scan("cpu", source, None)?.build()
+    }
+
+    fn hour_window() -> Expr {
+        Expr::ScalarFunction(ScalarFunction {
+            fun: BuiltinScalarFunction::DateBin,
+            args: vec![
+                lit(ScalarValue::IntervalMonthDayNano(Some(HOUR as i128))),
+                col("time"),
+            ],
+        })
+    }
+
+    fn rewrite(plan: &LogicalPlan) -> Result<Option<LogicalPlan>> {
+        RewriteMaterializedView {}.try_optimize(plan, &OptimizerContext::new())
+    }
+
+    /// Names of the scanned tables, in the order of the plan.
+    fn scanned_tables(plan: &LogicalPlan) -> Vec<String> {
+        let mut tables = vec![];
+        let mut plans = vec![plan];
+        while let Some(plan) = plans.pop() {
+            if let LogicalPlan::TableScan(scan) = plan {
+                let adapter = crate::data_source::source_downcast_adapter(&scan.source).unwrap();
+                tables.push(adapter.table_name().to_string());
+            }
+            plans.extend(plan.inputs().into_iter().rev());
+        }
+        tables
+    }
+
+    #[test]
+    fn test_rewrite_before_and_after_watermark() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(cpu_scan(Some(2 * HOUR))?)
+            .filter(col("host").eq(lit("a")))?
+            .aggregate(vec![hour_window(), col("host")], vec![avg(col("usage"))])?
+            .build()?;
+
+        let new_plan = rewrite(&plan)?.expect("rewritten");
+        // The view before the watermark and the base table after it
+        assert_eq!(scanned_tables(&new_plan), vec!["cpu_1h", "cpu"]);
+        let display = format!("{}", new_plan.display_indent());
+        assert!(display.contains("Union"), "{display}");
+        assert!(display.contains("AVG(cpu.avg_usage)"), "{display}");
+        // The names of the output columns are kept
+        assert_eq!(plan.schema().field_names(), new_plan.schema().field_names());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_not_rewrite() -> Result<()> {
+        // The view has not been computed
+        let plan = LogicalPlanBuilder::from(cpu_scan(None)?)
+            .aggregate(vec![hour_window(), col("host")], vec![avg(col("usage"))])?
+            .build()?;
+        assert!(rewrite(&plan)?.is_none());
+
+        // `avg` of a coarser grouping can't be computed from the averages of the view
+        let plan = LogicalPlanBuilder::from(cpu_scan(Some(2 * HOUR))?)
+            .aggregate(vec![hour_window()], vec![avg(col("usage"))])?
+            .build()?;
+        assert!(rewrite(&plan)?.is_none());
+
+        // `max` isn't kept by the view
+        let plan = LogicalPlanBuilder::from(cpu_scan(Some(2 * HOUR))?)
+            .aggregate(vec![hour_window(), col("host")], vec![max(col("usage"))])?
+            .build()?;
+        assert!(rewrite(&plan)?.is_none());
+
+        // Filters on fields need the rows of the base table
+        let plan = LogicalPlanBuilder::from(cpu_scan(Some(2 * HOUR))?)
+            .filter(col("usage").gt(lit(0.5)))?
+            .aggregate(vec![hour_window(), col("host")], vec![avg(col("usage"))])?
+            .build()?;
+        assert!(rewrite(&plan)?.is_none());
+
+        // The time bound isn't aligned to the windows of the view
+        let plan = LogicalPlanBuilder::from(cpu_scan(Some(2 * HOUR))?)
+            .filter(col("time").lt(lit(ScalarValue::TimestampNanosecond(Some(HOUR + 1), None))))?
+            .aggregate(vec![hour_window(), col("host")], vec![avg(col("usage"))])?
+            .build()?;
+        assert!(rewrite(&plan)?.is_none());
+
+        Ok(())
+    }
+}
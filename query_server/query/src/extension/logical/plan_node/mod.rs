@@ -8,6 +8,7 @@ use crate::extension::expr::expr_rewriter::ExprReplacer;
 
 pub mod expand;
 pub mod gapfill;
+pub mod range_vector;
 pub mod stream_scan;
 pub mod table_writer;
 pub mod table_writer_merge;
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{DFField, DFSchema, DFSchemaRef};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{LogicalPlan, UserDefinedLogicalNodeCore};
+use datafusion::prelude::Expr;
+
+/// The function of the samples in each window of a [`RangeVectorNode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RangeFunction {
+    /// The latest sample, of an instant vector selector
+    Latest,
+    SumOverTime,
+    AvgOverTime,
+    MinOverTime,
+    MaxOverTime,
+    CountOverTime,
+    LastOverTime,
+    /// The per-second increase of a counter, extrapolated to the ends of the window
+    Rate,
+    /// The increase of a counter, extrapolated to the ends of the window
+    Increase,
+    /// The difference of a gauge, extrapolated to the ends of the window
+    Delta,
+}
+
+impl RangeFunction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sum_over_time" => Self::SumOverTime,
+            "avg_over_time" => Self::AvgOverTime,
+            "min_over_time" => Self::MinOverTime,
+            "max_over_time" => Self::MaxOverTime,
+            "count_over_time" => Self::CountOverTime,
+            "last_over_time" => Self::LastOverTime,
+            "rate" => Self::Rate,
+            "increase" => Self::Increase,
+            "delta" => Self::Delta,
+            _ => return None,
+        })
+    }
+}
+
+/// Evaluates a function of the samples of each series in the window
+/// `(t - offset - range, t - offset]` of the evaluation timestamps `t = start, start + step, ..., end`.
+///
+/// The input has the columns `time, <labels>..., value`, where `time` is the time of the samples
+/// in nanoseconds, and a series is identified by its labels. The output has the same columns,
+/// where `time` is the evaluation timestamp, and a window without a result has no row.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct RangeVectorNode {
+    /// The incoming logical plan
+    pub input: Arc<LogicalPlan>,
+    pub func: RangeFunction,
+    pub labels: Vec<String>,
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+    pub range: i64,
+    pub offset: i64,
+    /// The columns of the input
+    pub expressions: Vec<Expr>,
+    /// The schema description of the output
+    pub schema: DFSchemaRef,
+}
+
+impl RangeVectorNode {
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        input: Arc<LogicalPlan>,
+        func: RangeFunction,
+        labels: Vec<String>,
+        start: i64,
+        end: i64,
+        step: i64,
+        range: i64,
+        offset: i64,
+    ) -> Result<Self> {
+        let input_schema = input.schema();
+        if input_schema.fields().len() != labels.len() + 2 {
+            return Err(DataFusionError::Internal(format!(
+                "RangeVector expects the columns time, {labels:?}, value, but found: {:?}",
+                input_schema.field_names()
+            )));
+        }
+        if step <= 0 {
+            return Err(DataFusionError::Internal(format!(
+                "RangeVector expects a positive step, but found: {step}"
+            )));
+        }
+
+        let fields = [DFField::new_unqualified("time", DataType::Int64, false)]
+            .into_iter()
+            .chain(
+                labels
+                    .iter()
+                    .map(|label| DFField::new_unqualified(label, DataType::Utf8, true)),
+            )
+            .chain([DFField::new_unqualified("value", DataType::Float64, true)])
+            .collect();
+        let schema = Arc::new(DFSchema::new_with_metadata(fields, HashMap::new())?);
+
+        let expressions = input_columns(&input);
+        Ok(Self {
+            input,
+            func,
+            labels,
+            start,
+            end,
+            step,
+            range,
+            offset,
+            expressions,
+            schema,
+        })
+    }
+}
+
+impl Debug for RangeVectorNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_for_explain(f)
+    }
+}
+
+impl UserDefinedLogicalNodeCore for RangeVectorNode {
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![self.input.as_ref()]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        self.expressions.clone()
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "RangeVector: func={:?}, labels=[{}], start={}, end={}, step={}, range={}, offset={}",
+            self.func,
+            self.labels.join(", "),
+            self.start,
+            self.end,
+            self.step,
+            self.range,
+            self.offset
+        )
+    }
+
+    fn from_template(&self, _exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
+        assert_eq!(inputs.len(), 1, "input size inconsistent");
+        Self {
+            input: Arc::new(inputs[0].clone()),
+            expressions: input_columns(&inputs[0]),
+            ..self.clone()
+        }
+    }
+
+    fn name(&self) -> &str {
+        "RangeVector"
+    }
+}
+
+fn input_columns(input: &LogicalPlan) -> Vec<Expr> {
+    input
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| Expr::Column(field.qualified_column()))
+        .collect()
+}
@@ -13,7 +13,7 @@ use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricsSet};
 use datafusion::physical_plan::{
     DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
 };
-use models::predicate::domain::{PredicateRef, PushedAggregateFunction};
+use models::predicate::domain::{ColumnAggregate, PredicateRef, PushedAggregateFunction};
 use models::predicate::PlacedSplit;
 use models::schema::TskvTableSchemaRef;
 use trace::{debug, SpanContext, SpanExt, SpanRecorder};
@@ -89,12 +89,8 @@ impl ExecutionPlan for AggregateFilterTskvExec {
     ) -> Result<SendableRecordBatchStream> {
         let mut agg_columns = Vec::with_capacity(self.pushed_aggs.len());
         for agg in self.pushed_aggs.iter() {
-            match agg {
-                PushedAggregateFunction::Count(column) => {
-                    if let Some(col) = self.table_schema.column(column) {
-                        agg_columns.push(col.clone());
-                    }
-                }
+            if let Some(col) = self.table_schema.column(agg.column_name()) {
+                agg_columns.push(ColumnAggregate::new(agg.kind(), col.clone()));
             }
         }
 
@@ -111,7 +107,8 @@ impl ExecutionPlan for AggregateFilterTskvExec {
             Some(agg_columns),
             self.schema.clone(),
             (*self.table_schema).clone(),
-        );
+        )
+        .with_scan_metrics(metrics.scan_metrics().clone());
 
         let span_ctx = context.session_config().get_extension::<SpanContext>();
         let span_recorder =
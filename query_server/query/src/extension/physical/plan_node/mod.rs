@@ -3,10 +3,12 @@ use std::task::Poll;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::error::DataFusionError;
 use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, Time};
+use tskv::reader::scan_metrics::ScanMetrics;
 
 pub mod aggregate_filter_scan;
 pub mod expand;
 pub mod gapfill;
+pub mod range_vector;
 pub mod state_restore;
 pub mod state_save;
 pub mod table_writer;
@@ -20,14 +22,24 @@ pub mod watermark;
 #[derive(Debug)]
 pub struct TableScanMetrics {
     baseline_metrics: BaselineMetrics,
+    scan_metrics: ScanMetrics,
 }
 
 impl TableScanMetrics {
     /// Create new metrics
     pub fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
         let baseline_metrics = BaselineMetrics::new(metrics, partition);
+        let scan_metrics = ScanMetrics::new(metrics, partition);
 
-        Self { baseline_metrics }
+        Self {
+            baseline_metrics,
+            scan_metrics,
+        }
+    }
+
+    /// return the metrics of tskv scan, such as series, files and blocks read of the split
+    pub fn scan_metrics(&self) -> &ScanMetrics {
+        &self.scan_metrics
     }
 
     /// return the metric for cpu time spend in this operator
@@ -0,0 +1,451 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    as_primitive_array, as_string_array, Array, ArrayRef, Float64Array, Int64Array, StringArray,
+};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Float64Type, Int64Type, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_plan::common::collect;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayFormatType, Distribution, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+    Statistics,
+};
+use futures::stream;
+
+use crate::extension::logical::plan_node::range_vector::RangeFunction;
+
+/// The windows `(t - offset - range, t - offset]` of the evaluation timestamps
+/// `t = start, start + step, ..., end`, in nanoseconds
+#[derive(Debug, Clone, Copy)]
+pub struct RangeWindows {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+    pub range: i64,
+    pub offset: i64,
+}
+
+impl RangeWindows {
+    /// Evaluates the function of the samples sorted by time in each window,
+    /// returns the evaluation timestamps and the results.
+    pub fn evaluate(&self, func: RangeFunction, samples: &[(i64, f64)]) -> Vec<(i64, f64)> {
+        let mut result = vec![];
+        // The samples in the window are `samples[lo..hi]`
+        let (mut lo, mut hi) = (0, 0);
+        let mut t = self.start;
+        while t <= self.end {
+            let window_end = t - self.offset;
+            let window_start = window_end - self.range;
+            while hi < samples.len() && samples[hi].0 <= window_end {
+                hi += 1;
+            }
+            while lo < hi && samples[lo].0 <= window_start {
+                lo += 1;
+            }
+            if let Some(value) = evaluate(func, &samples[lo..hi], window_start, window_end) {
+                result.push((t, value));
+            }
+
+            match t.checked_add(self.step) {
+                Some(next) => t = next,
+                None => break,
+            }
+        }
+        result
+    }
+}
+
+fn evaluate(
+    func: RangeFunction,
+    samples: &[(i64, f64)],
+    window_start: i64,
+    window_end: i64,
+) -> Option<f64> {
+    let (_, last) = *samples.last()?;
+    let values = || samples.iter().map(|(_, v)| *v);
+    match func {
+        RangeFunction::Latest | RangeFunction::LastOverTime => Some(last),
+        RangeFunction::SumOverTime => Some(values().sum()),
+        RangeFunction::AvgOverTime => Some(values().sum::<f64>() / samples.len() as f64),
+        // A NaN is ignored unless all values are NaN
+        RangeFunction::MinOverTime => Some(values().fold(f64::NAN, f64::min)),
+        RangeFunction::MaxOverTime => Some(values().fold(f64::NAN, f64::max)),
+        RangeFunction::CountOverTime => Some(samples.len() as f64),
+        RangeFunction::Rate => extrapolated_rate(samples, window_start, window_end, true, true),
+        RangeFunction::Increase => {
+            extrapolated_rate(samples, window_start, window_end, true, false)
+        }
+        RangeFunction::Delta => extrapolated_rate(samples, window_start, window_end, false, false),
+    }
+}
+
+/// The increase of the samples extrapolated to the ends of the window, see `extrapolatedRate`
+/// of prometheus. A window of less than 2 samples has no result.
+fn extrapolated_rate(
+    samples: &[(i64, f64)],
+    window_start: i64,
+    window_end: i64,
+    is_counter: bool,
+    is_rate: bool,
+) -> Option<f64> {
+    let [(first_ts, first), .., (last_ts, last)] = samples else {
+        return None;
+    };
+    let seconds = |nanos: i64| nanos as f64 / 1e9;
+
+    let mut result = last - first;
+    if is_counter {
+        // The value before a reset is added to the increase
+        for pair in samples.windows(2) {
+            if pair[1].1 < pair[0].1 {
+                result += pair[0].1;
+            }
+        }
+    }
+
+    let mut to_start = seconds(first_ts - window_start);
+    let to_end = seconds(window_end - last_ts);
+    let sampled = seconds(last_ts - first_ts);
+    let average = sampled / (samples.len() - 1) as f64;
+
+    // A counter doesn't go below zero
+    if is_counter && result > 0.0 && *first >= 0.0 {
+        let to_zero = sampled * (first / result);
+        if to_zero < to_start {
+            to_start = to_zero;
+        }
+    }
+
+    // Extrapolates to the end of the window, or half of the average interval
+    let threshold = average * 1.1;
+    let mut interval = sampled;
+    interval += if to_start < threshold {
+        to_start
+    } else {
+        average / 2.0
+    };
+    interval += if to_end < threshold {
+        to_end
+    } else {
+        average / 2.0
+    };
+
+    result *= interval / sampled;
+    if is_rate {
+        result /= seconds(window_end - window_start);
+    }
+    Some(result)
+}
+
+/// Execution plan for a RangeVector, see [`RangeVectorNode`].
+///
+/// [`RangeVectorNode`]: crate::extension::logical::plan_node::range_vector::RangeVectorNode
+pub struct RangeVectorExec {
+    input: Arc<dyn ExecutionPlan>,
+    func: RangeFunction,
+    windows: RangeWindows,
+    schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl RangeVectorExec {
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        func: RangeFunction,
+        windows: RangeWindows,
+        schema: SchemaRef,
+    ) -> Self {
+        Self {
+            input,
+            func,
+            windows,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl Debug for RangeVectorExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RangeVectorExec")
+    }
+}
+
+impl ExecutionPlan for RangeVectorExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn required_input_distribution(&self) -> Vec<Distribution> {
+        // The samples of a series may be in any partition
+        vec![Distribution::SinglePartition]
+    }
+
+    fn output_ordering(&self) -> Option<&[datafusion::physical_expr::PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(Self::new(
+                children[0].clone(),
+                self.func,
+                self.windows,
+                self.schema.clone(),
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "RangeVectorExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if partition != 0 {
+            return Err(DataFusionError::Internal(format!(
+                "RangeVectorExec invalid partition {partition}, there can be only one partition"
+            )));
+        }
+
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let input = self.input.execute(partition, context)?;
+        let (func, windows, schema) = (self.func, self.windows, self.schema.clone());
+        let output = async move {
+            let batches = collect(input).await?;
+            let timer = baseline_metrics.elapsed_compute().timer();
+            let batch = evaluate_series(schema, func, &windows, &batches)?;
+            timer.done();
+            baseline_metrics.record_output(batch.num_rows());
+            Ok(batch)
+        };
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            stream::once(output),
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                let RangeWindows {
+                    start,
+                    end,
+                    step,
+                    range,
+                    offset,
+                } = self.windows;
+                write!(
+                    f,
+                    "RangeVectorExec: func={:?}, start={start}, end={end}, step={step}, range={range}, offset={offset}",
+                    self.func
+                )
+            }
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// Groups the samples `time, <labels>..., value` by the labels, and evaluates the function
+/// of each series. A label of the empty value is missing, like prometheus.
+fn evaluate_series(
+    schema: SchemaRef,
+    func: RangeFunction,
+    windows: &RangeWindows,
+    batches: &[RecordBatch],
+) -> Result<RecordBatch> {
+    let num_labels = schema.fields().len() - 2;
+
+    let mut series: HashMap<Vec<Option<String>>, Vec<(i64, f64)>> = HashMap::new();
+    for batch in batches {
+        let time = cast(batch.column(0), &DataType::Int64)?;
+        let time = as_primitive_array::<Int64Type>(time.as_ref());
+        let labels = (1..=num_labels)
+            .map(|i| cast(batch.column(i), &DataType::Utf8))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let labels = labels
+            .iter()
+            .map(|array| as_string_array(array.as_ref()))
+            .collect::<Vec<_>>();
+        let value = cast(batch.column(num_labels + 1), &DataType::Float64)?;
+        let value = as_primitive_array::<Float64Type>(value.as_ref());
+
+        for row in 0..batch.num_rows() {
+            if time.is_null(row) || value.is_null(row) {
+                continue;
+            }
+            let key = labels
+                .iter()
+                .map(|array| {
+                    (array.is_valid(row) && !array.value(row).is_empty())
+                        .then(|| array.value(row).to_string())
+                })
+                .collect::<Vec<_>>();
+            series
+                .entry(key)
+                .or_default()
+                .push((time.value(row), value.value(row)));
+        }
+    }
+
+    let mut series = series.into_iter().collect::<Vec<_>>();
+    series.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut times = vec![];
+    let mut label_values = vec![vec![]; num_labels];
+    let mut values = vec![];
+    for (key, mut samples) in series {
+        samples.sort_by_key(|(t, _)| *t);
+        for (t, value) in windows.evaluate(func, &samples) {
+            times.push(t);
+            for (column, label) in label_values.iter_mut().zip(&key) {
+                column.push(label.clone());
+            }
+            values.push(value);
+        }
+    }
+
+    let columns = [Arc::new(Int64Array::from(times)) as ArrayRef]
+        .into_iter()
+        .chain(
+            label_values
+                .into_iter()
+                .map(|column| Arc::new(StringArray::from(column)) as ArrayRef),
+        )
+        .chain([Arc::new(Float64Array::from(values)) as ArrayRef])
+        .collect();
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeWindows;
+    use crate::extension::logical::plan_node::range_vector::RangeFunction;
+
+    const SECOND: i64 = 1_000_000_000;
+
+    fn range_windows(start: i64, end: i64, step: i64, range: i64) -> RangeWindows {
+        RangeWindows {
+            start: start * SECOND,
+            end: end * SECOND,
+            step: step * SECOND,
+            range: range * SECOND,
+            offset: 0,
+        }
+    }
+
+    fn samples(values: &[(i64, f64)]) -> Vec<(i64, f64)> {
+        values.iter().map(|(t, v)| (t * SECOND, *v)).collect()
+    }
+
+    fn seconds(result: Vec<(i64, f64)>) -> Vec<(i64, f64)> {
+        result.into_iter().map(|(t, v)| (t / SECOND, v)).collect()
+    }
+
+    #[test]
+    fn test_over_time() {
+        let samples = samples(&[(10, 1.0), (20, 2.0), (30, 6.0), (40, f64::NAN), (50, 3.0)]);
+        // The windows are (-10, 20], (10, 40] and (30, 60]
+        let windows = range_windows(20, 60, 20, 30);
+        let eval = |func| seconds(windows.evaluate(func, &samples));
+
+        assert_eq!(eval(RangeFunction::SumOverTime)[0], (20, 3.0));
+        assert_eq!(eval(RangeFunction::AvgOverTime)[0], (20, 1.5));
+        assert_eq!(
+            eval(RangeFunction::CountOverTime),
+            vec![(20, 2.0), (40, 3.0), (60, 2.0)]
+        );
+        assert_eq!(
+            eval(RangeFunction::MinOverTime),
+            vec![(20, 1.0), (40, 2.0), (60, 3.0)]
+        );
+        assert_eq!(
+            eval(RangeFunction::MaxOverTime),
+            vec![(20, 2.0), (40, 6.0), (60, 3.0)]
+        );
+        assert_eq!(eval(RangeFunction::LastOverTime)[2], (60, 3.0));
+
+        // No sample in the window (95, 100]
+        let windows = range_windows(50, 100, 50, 5);
+        assert_eq!(
+            seconds(windows.evaluate(RangeFunction::Latest, &samples)),
+            vec![(50, 3.0)]
+        );
+        // The offset moves the windows back to (25, 30] and (75, 80]
+        let windows = RangeWindows {
+            offset: 20 * SECOND,
+            ..windows
+        };
+        assert_eq!(
+            seconds(windows.evaluate(RangeFunction::Latest, &samples)),
+            vec![(50, 6.0)]
+        );
+    }
+
+    #[test]
+    fn test_extrapolated_rate() {
+        // A counter reset at 40s, increased by 4 in 40s
+        let counter = samples(&[(10, 1.0), (20, 2.0), (30, 3.0), (40, 1.0), (50, 2.0)]);
+
+        // Extrapolated to both ends of (0, 60], which are closer than 1.1 average intervals
+        let windows = range_windows(60, 60, 60, 60);
+        let eval = |func, samples: &[(i64, f64)]| seconds(windows.evaluate(func, samples));
+        assert_eq!(eval(RangeFunction::Increase, &counter), vec![(60, 6.0)]);
+        assert_eq!(eval(RangeFunction::Rate, &counter), vec![(60, 6.0 / 60.0)]);
+
+        // Extrapolated by half of the average interval to the end of (0, 100]
+        let windows = range_windows(100, 100, 100, 100);
+        assert_eq!(
+            seconds(windows.evaluate(RangeFunction::Increase, &counter)),
+            vec![(100, 4.0 * (55.0 / 40.0))]
+        );
+
+        // The extrapolation to the start stops at zero, which is 1s before the first sample
+        let counter = samples(&[(10, 1.0), (20, 11.0), (30, 21.0)]);
+        assert_eq!(
+            eval(RangeFunction::Increase, &counter),
+            vec![(60, 20.0 * (26.0 / 20.0))]
+        );
+
+        // A gauge isn't reset
+        let gauge = samples(&[(10, 3.0), (20, 1.0), (30, 2.0), (40, 0.0), (50, -1.0)]);
+        assert_eq!(eval(RangeFunction::Delta, &gauge), vec![(60, -6.0)]);
+
+        // A single sample has no rate
+        assert!(eval(RangeFunction::Rate, &samples(&[(10, 1.0)])).is_empty());
+    }
+}
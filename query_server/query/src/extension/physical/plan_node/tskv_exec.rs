@@ -263,7 +263,8 @@ impl TableScanStream {
             None,
             proj_schema.clone(),
             proj_table_schema,
-        );
+        )
+        .with_scan_metrics(metrics.scan_metrics().clone());
 
         let span_ctx = span_recorder.span_ctx();
         let iterator = coord.table_scan(option, span_ctx)?;
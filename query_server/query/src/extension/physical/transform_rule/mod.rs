@@ -1,6 +1,7 @@
 //! logical paln to physical plan transform rule
 pub mod expand;
 pub mod gapfill;
+pub mod range_vector;
 pub mod stream_scan;
 pub mod table_writer;
 pub mod tag_scan;
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::error::Result;
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{LogicalPlan, UserDefinedLogicalNode};
+use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::physical_planner::{ExtensionPlanner, PhysicalPlanner};
+
+use crate::extension::logical::plan_node::range_vector::RangeVectorNode;
+use crate::extension::physical::plan_node::range_vector::{RangeVectorExec, RangeWindows};
+
+/// Physical planner for RangeVector nodes
+#[derive(Default)]
+pub struct RangeVectorPlanner {}
+
+impl RangeVectorPlanner {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[async_trait]
+impl ExtensionPlanner for RangeVectorPlanner {
+    /// Create a physical plan for an extension node
+    async fn plan_extension(
+        &self,
+        _planner: &dyn PhysicalPlanner,
+        node: &dyn UserDefinedLogicalNode,
+        _logical_inputs: &[&LogicalPlan],
+        physical_inputs: &[Arc<dyn ExecutionPlan>],
+        _session_state: &SessionState,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        Ok(match as_range_vector_plan_node(node) {
+            Some(RangeVectorNode {
+                func,
+                start,
+                end,
+                step,
+                range,
+                offset,
+                schema,
+                ..
+            }) => {
+                assert_eq!(1, physical_inputs.len());
+
+                let mut input = physical_inputs[0].clone();
+                if input.output_partitioning().partition_count() > 1 {
+                    input = Arc::new(CoalescePartitionsExec::new(input));
+                }
+                let windows = RangeWindows {
+                    start: *start,
+                    end: *end,
+                    step: *step,
+                    range: *range,
+                    offset: *offset,
+                };
+
+                Some(Arc::new(RangeVectorExec::new(
+                    input,
+                    *func,
+                    windows,
+                    Arc::new(schema.as_ref().into()),
+                )))
+            }
+            _ => None,
+        })
+    }
+}
+
+fn as_range_vector_plan_node(node: &dyn UserDefinedLogicalNode) -> Option<&RangeVectorNode> {
+    node.as_any().downcast_ref::<RangeVectorNode>()
+}
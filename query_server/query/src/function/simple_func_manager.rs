@@ -1,7 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use datafusion::logical_expr::{AggregateUDF, ScalarUDF};
+use datafusion::logical_expr::{AggregateUDF, ScalarUDF, WindowUDF};
 use spi::query::function::*;
 use spi::{QueryError, Result};
 
@@ -13,6 +13,8 @@ pub struct SimpleFunctionMetadataManager {
     pub scalar_functions: HashMap<String, Arc<ScalarUDF>>,
     /// Aggregate functions registered in the context
     pub aggregate_functions: HashMap<String, Arc<AggregateUDF>>,
+    /// Window functions registered in the context
+    pub window_functions: HashMap<String, Arc<WindowUDF>>,
 }
 
 impl FunctionMetadataManager for SimpleFunctionMetadataManager {
@@ -28,6 +30,12 @@ impl FunctionMetadataManager for SimpleFunctionMetadataManager {
         Ok(())
     }
 
+    fn register_udwf(&mut self, f: WindowUDF) -> Result<()> {
+        self.window_functions
+            .insert(f.name.to_uppercase(), Arc::new(f));
+        Ok(())
+    }
+
     fn udf(&self, name: &str) -> Result<Arc<ScalarUDF>> {
         let result = self.scalar_functions.get(&name.to_uppercase());
 
@@ -46,6 +54,16 @@ impl FunctionMetadataManager for SimpleFunctionMetadataManager {
             })
     }
 
+    fn udwf(&self, name: &str) -> Result<Arc<WindowUDF>> {
+        let result = self.window_functions.get(&name.to_uppercase());
+
+        result
+            .cloned()
+            .ok_or_else(|| QueryError::FunctionNotExists {
+                name: name.to_string(),
+            })
+    }
+
     fn udfs(&self) -> HashSet<String> {
         self.scalar_functions.keys().cloned().collect()
     }
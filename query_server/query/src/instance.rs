@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use coordinator::service::CoordinatorRef;
@@ -29,6 +30,7 @@ use crate::data_source::stream::tskv::factory::{TskvStreamProviderFactory, TSKV_
 use crate::dispatcher::manager::SimpleQueryDispatcherBuilder;
 use crate::dispatcher::persister::LocalQueryPersister;
 use crate::dispatcher::query_tracker::QueryTracker;
+use crate::dispatcher::result_cache::QueryResultCache;
 use crate::execution::factory::SqlQueryExecutionFactory;
 use crate::execution::scheduler::local::LocalScheduler;
 use crate::extension::expr::load_all_functions;
@@ -230,7 +232,18 @@ pub async fn make_cnosdbms(
         stream_provider_manager.clone(),
     ));
 
-    let query_dispatcher = SimpleQueryDispatcherBuilder::default()
+    let mut query_dispatcher_builder = SimpleQueryDispatcherBuilder::default();
+    if options.query.result_cache_max_memory > 0 {
+        let result_cache = Arc::new(QueryResultCache::new(
+            options.query.result_cache_max_memory as usize,
+            Duration::from_millis(options.query.result_cache_ttl_ms),
+        ));
+        result_cache.start_invalidation(coord.subscribe_written_time_ranges());
+        result_cache.start_remote_invalidation(coord.clone());
+        query_dispatcher_builder = query_dispatcher_builder.with_result_cache(result_cache);
+    }
+
+    let query_dispatcher = query_dispatcher_builder
         .with_coord(coord)
         .with_default_table_provider(default_table_provider)
         .with_split_manager(split_manager)
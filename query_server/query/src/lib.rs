@@ -3,12 +3,14 @@
 extern crate core;
 
 pub mod auth;
+pub mod continuous_query;
 mod data_source;
 pub mod dispatcher;
 mod execution;
 pub mod extension;
 pub mod function;
 pub mod instance;
+pub mod materialized_view;
 pub mod metadata;
 pub mod prom;
 pub mod sql;
@@ -0,0 +1,334 @@
+//! Materialized views, which keep the aggregated data of tskv tables in tskv tables.
+//!
+//! A view is defined as
+//! `SELECT time_window(time, <interval>), <tag>, ..., <agg>(<field>), ... FROM <table> GROUP BY ...`,
+//! its data is refreshed by [`MaterializedViewScheduler`], and queries on the base table which
+//! compute the same aggregations are rewritten to read from the view by
+//! [`RewriteMaterializedView`](crate::extension::logical::optimizer_rule::rewrite_materialized_view::RewriteMaterializedView).
+
+mod scheduler;
+
+use std::collections::{HashMap, HashSet};
+
+use datafusion::arrow::datatypes::DataType;
+use datafusion::logical_expr::expr::{AggregateFunction, ScalarFunction, ScalarUDF};
+use datafusion::logical_expr::{aggregate_function, BuiltinScalarFunction, LogicalPlan};
+use datafusion::prelude::Expr;
+use models::object_reference::ResolvedTable;
+use models::schema::{
+    MaterializedView, MaterializedViewAggregate, TableColumn, TskvTableSchema, TIME_FIELD_NAME,
+};
+use models::{ColumnId, ValueType};
+pub use scheduler::MaterializedViewScheduler;
+use spi::{QueryError, Result};
+
+use crate::data_source::source_downcast_adapter;
+use crate::data_source::table_source::TableHandle;
+use crate::extension::expr::expr_utils::literal_interval_ns;
+use crate::extension::expr::TIME_WINDOW;
+
+/// Returns the width in nanoseconds of `time_window(time, <interval>)` or
+/// `date_bin(<interval>, time)`, which are the same windows aligned to the epoch.
+pub fn time_window_interval(expr: &Expr) -> Option<i64> {
+    let (interval, time) = match expr {
+        Expr::ScalarUDF(ScalarUDF { fun, args }) if fun.name == TIME_WINDOW => {
+            match args.as_slice() {
+                [time, interval] => (interval, time),
+                _ => return None,
+            }
+        }
+        Expr::ScalarFunction(ScalarFunction {
+            fun: BuiltinScalarFunction::DateBin,
+            args,
+        }) => match args.as_slice() {
+            [interval, time] => (interval, time),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    match time {
+        Expr::Column(c) if c.name == TIME_FIELD_NAME => literal_interval_ns(interval),
+        _ => None,
+    }
+}
+
+/// Returns the lower case name of an aggregate function the views can keep.
+pub fn view_aggregate_function(fun: &aggregate_function::AggregateFunction) -> Option<&str> {
+    match fun {
+        aggregate_function::AggregateFunction::Count => Some("count"),
+        aggregate_function::AggregateFunction::Sum => Some("sum"),
+        aggregate_function::AggregateFunction::Min => Some("min"),
+        aggregate_function::AggregateFunction::Max => Some("max"),
+        aggregate_function::AggregateFunction::Avg => Some("avg"),
+        _ => None,
+    }
+}
+
+/// Builds the view and the table keeping its data from the plan of the defining query.
+pub fn build_materialized_view(
+    name: &ResolvedTable,
+    query: String,
+    owner: String,
+    plan: &LogicalPlan,
+) -> Result<(TskvTableSchema, MaterializedView)> {
+    let invalid = |reason: String| QueryError::InvalidMaterializedView { reason };
+
+    let LogicalPlan::Projection(projection) = plan else {
+        return Err(invalid("only SELECT ... GROUP BY ... is supported".to_string()));
+    };
+    let LogicalPlan::Aggregate(aggregate) = projection.input.as_ref() else {
+        return Err(invalid("the query must be an aggregation".to_string()));
+    };
+    let LogicalPlan::TableScan(scan) = aggregate.input.as_ref() else {
+        return Err(invalid(
+            "the query must aggregate a tskv table without filters".to_string(),
+        ));
+    };
+    let base = match source_downcast_adapter(&scan.source)?.table_handle() {
+        TableHandle::Tskv(table) => table.table_schema(),
+        _ => return Err(invalid("the base table must be a tskv table".to_string())),
+    };
+    if base.db != name.database() {
+        return Err(invalid(format!(
+            "the view must be in the database of its base table '{}'",
+            base.db
+        )));
+    }
+
+    let mut interval = None;
+    let mut tags = vec![];
+    for expr in &aggregate.group_expr {
+        if let Some(ns) = time_window_interval(expr) {
+            if interval.replace(ns).is_some() {
+                return Err(invalid("only one time window is supported".to_string()));
+            }
+            continue;
+        }
+        match expr {
+            Expr::Column(c)
+                if base
+                    .column(&c.name)
+                    .map_or(false, |col| col.column_type.is_tag()) =>
+            {
+                tags.push(c.name.clone())
+            }
+            _ => {
+                return Err(invalid(format!(
+                    "can't group by {expr}, only a time window and tags are supported"
+                )))
+            }
+        }
+    }
+    let interval = interval.ok_or_else(|| {
+        invalid(
+            "the query must group by time_window(time, <interval>) or date_bin(<interval>, time)"
+                .to_string(),
+        )
+    })?;
+
+    // The names given to the aggregations by the query
+    let aliases: HashMap<&str, &str> = projection
+        .expr
+        .iter()
+        .filter_map(|e| match e {
+            Expr::Alias(inner, alias) => match inner.as_ref() {
+                Expr::Column(c) => Some((c.name.as_str(), alias.as_str())),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+
+    let mut columns = vec![TableColumn::new_time_column(
+        0,
+        base.time_column_precision().into(),
+    )];
+    for tag in &tags {
+        columns.push(TableColumn::new_tag_column(0, tag.clone()));
+    }
+
+    let mut aggregates = vec![];
+    let output_fields = &aggregate.schema.fields()[aggregate.group_expr.len()..];
+    for (expr, output) in aggregate.aggr_expr.iter().zip(output_fields) {
+        let (func, column) = match expr {
+            Expr::AggregateFunction(AggregateFunction {
+                fun,
+                args,
+                distinct: false,
+                filter: None,
+                order_by: None,
+            }) => match (view_aggregate_function(fun), args.as_slice()) {
+                (Some(func), [Expr::Column(c)])
+                    if base
+                        .column(&c.name)
+                        .map_or(false, |col| col.column_type.is_field()) =>
+                {
+                    (func, c.name.clone())
+                }
+                _ => return Err(invalid(format!("unsupported aggregation {expr}"))),
+            },
+            _ => return Err(invalid(format!("unsupported aggregation {expr}"))),
+        };
+
+        let field = aliases
+            .get(output.name().as_str())
+            .map(|alias| alias.to_string())
+            .unwrap_or_else(|| format!("{func}_{column}"));
+        let value_type = match output.data_type() {
+            DataType::Float64 => ValueType::Float,
+            DataType::Int64 => ValueType::Integer,
+            DataType::UInt64 => ValueType::Unsigned,
+            DataType::Boolean => ValueType::Boolean,
+            DataType::Utf8 => ValueType::String,
+            other => {
+                return Err(invalid(format!(
+                    "can't store {expr} of type {other} in a tskv table"
+                )))
+            }
+        };
+        columns.push(TableColumn::new_with_default(
+            field.clone(),
+            value_type.into(),
+        ));
+        aggregates.push(MaterializedViewAggregate {
+            func: func.to_string(),
+            column,
+            field,
+        });
+    }
+
+    for (id, column) in columns.iter_mut().enumerate() {
+        column.id = id as ColumnId;
+    }
+    let mut names = HashSet::new();
+    for column in &columns {
+        if !names.insert(column.name.as_str()) {
+            return Err(QueryError::SameColumnName {
+                column: column.name.clone(),
+            });
+        }
+    }
+
+    let view = MaterializedView {
+        tenant: name.tenant().to_string(),
+        db: name.database().to_string(),
+        name: name.table().to_string(),
+        base_table: base.name.clone(),
+        interval,
+        tags,
+        aggregates,
+        query,
+        owner,
+        watermark: None,
+    };
+    let table = TskvTableSchema::new(
+        view.tenant.clone(),
+        view.db.clone(),
+        view.name.clone(),
+        columns,
+    );
+
+    Ok((table, view))
+}
+
+/// Builds `INSERT INTO <view> SELECT ...` which re-computes the windows of the view
+/// in `[start, end)`, or all windows before `end` if `start` is `None`.
+pub fn build_refresh_sql(view: &MaterializedView, start: Option<i64>, end: i64) -> String {
+    let quote = |name: &str| format!("\"{}\"", name.replace('"', "\"\""));
+    let window = format!(
+        "date_bin(INTERVAL '{}', {}, TIMESTAMP '1970-01-01T00:00:00Z')",
+        interval_sql(view.interval),
+        quote(TIME_FIELD_NAME)
+    );
+
+    let mut columns = vec![quote(TIME_FIELD_NAME)];
+    let mut projection = vec![window.clone()];
+    let mut group_by = vec![window];
+    for tag in &view.tags {
+        columns.push(quote(tag));
+        projection.push(quote(tag));
+        group_by.push(quote(tag));
+    }
+    for agg in &view.aggregates {
+        columns.push(quote(&agg.field));
+        projection.push(format!("{}({})", agg.func, quote(&agg.column)));
+    }
+
+    let time = quote(TIME_FIELD_NAME);
+    let filter = match start {
+        Some(start) => format!(
+            " WHERE {time} >= CAST({start} AS TIMESTAMP) AND {time} < CAST({end} AS TIMESTAMP)"
+        ),
+        None => format!(" WHERE {time} < CAST({end} AS TIMESTAMP)"),
+    };
+
+    format!(
+        "INSERT INTO {db}.{view} ({columns}) SELECT {projection} FROM {db}.{base}{filter} GROUP BY {group_by}",
+        db = quote(&view.db),
+        view = quote(&view.name),
+        base = quote(&view.base_table),
+        columns = columns.join(", "),
+        projection = projection.join(", "),
+        group_by = group_by.join(", "),
+    )
+}
+
+fn interval_sql(ns: i64) -> String {
+    const UNITS: [(i64, &str); 3] = [
+        (1_000_000_000, "second"),
+        (1_000_000, "millisecond"),
+        (1_000, "microsecond"),
+    ];
+    UNITS
+        .iter()
+        .find(|(unit, _)| ns % unit == 0)
+        .map(|(unit, name)| format!("{} {name}", ns / unit))
+        .unwrap_or_else(|| format!("{ns} nanosecond"))
+}
+
+#[cfg(test)]
+mod test {
+    use models::schema::{MaterializedView, MaterializedViewAggregate};
+
+    use super::build_refresh_sql;
+
+    #[test]
+    fn test_build_refresh_sql() {
+        let view = MaterializedView {
+            tenant: "cnosdb".to_string(),
+            db: "public".to_string(),
+            name: "cpu_1h".to_string(),
+            base_table: "cpu".to_string(),
+            interval: 3_600_000_000_000,
+            tags: vec!["host".to_string()],
+            aggregates: vec![MaterializedViewAggregate {
+                func: "avg".to_string(),
+                column: "usage".to_string(),
+                field: "avg_usage".to_string(),
+            }],
+            query: String::new(),
+            owner: "root".to_string(),
+            watermark: None,
+        };
+
+        assert_eq!(
+            build_refresh_sql(&view, Some(0), 7_200_000_000_000),
+            "INSERT INTO \"public\".\"cpu_1h\" (\"time\", \"host\", \"avg_usage\") \
+            SELECT date_bin(INTERVAL '3600 second', \"time\", TIMESTAMP '1970-01-01T00:00:00Z'), \"host\", avg(\"usage\") \
+            FROM \"public\".\"cpu\" WHERE \"time\" >= CAST(0 AS TIMESTAMP) AND \"time\" < CAST(7200000000000 AS TIMESTAMP) \
+            GROUP BY date_bin(INTERVAL '3600 second', \"time\", TIMESTAMP '1970-01-01T00:00:00Z'), \"host\""
+        );
+        assert_eq!(
+            build_refresh_sql(&view, None, 7_200_000_000_000),
+            "INSERT INTO \"public\".\"cpu_1h\" (\"time\", \"host\", \"avg_usage\") \
+            SELECT date_bin(INTERVAL '3600 second', \"time\", TIMESTAMP '1970-01-01T00:00:00Z'), \"host\", avg(\"usage\") \
+            FROM \"public\".\"cpu\" WHERE \"time\" < CAST(7200000000000 AS TIMESTAMP) \
+            GROUP BY date_bin(INTERVAL '3600 second', \"time\", TIMESTAMP '1970-01-01T00:00:00Z'), \"host\""
+        );
+        assert_eq!(
+            view.window_range(3_600_000_000_001, 3_600_000_000_001),
+            (3_600_000_000_000, 7_200_000_000_000)
+        );
+    }
+}
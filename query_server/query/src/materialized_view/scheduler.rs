@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+
+use coordinator::WrittenTimeRange;
+use meta::model::MetaRef;
+use models::runtime::executor::DedicatedExecutor;
+use models::schema::MaterializedView;
+use parking_lot::Mutex;
+use spi::query::config::StreamTriggerInterval;
+use spi::server::dbms::DBMSRef;
+use spi::service::protocol::{ContextBuilder, Query};
+use spi::Result;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use trace::{debug, warn};
+
+use super::build_refresh_sql;
+use crate::execution::stream::trigger::executor::{
+    TriggerExecutorFactory, TriggerExecutorFactoryRef,
+};
+
+/// How often the windows of the views are computed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// Only the node holding this lease in meta moves the watermarks of the views.
+const REFRESH_LEASE: &str = "materialized_view_refresh";
+const REFRESH_LEASE_TTL: Duration = Duration::from_secs(10);
+
+/// (tenant, database, view)
+type ViewKey = (String, String, String);
+
+/// Refreshes the materialized views of all tenants.
+///
+/// The node holding the refresh lease computes the windows which closed since the watermark
+/// of each view, then moves the watermark to the end of them. The watermark is kept in meta,
+/// so a restarted server or a new lease holder continues from it, and the queries rewritten
+/// to read a view read the windows after its watermark from the base table.
+///
+/// Data written into the windows before the watermark is late. Every node collects the late
+/// data it coordinated and re-computes the windows containing it, which only overwrites
+/// the same points of the view.
+pub struct MaterializedViewScheduler {
+    inner: Arc<SchedulerInner>,
+    written: Receiver<WrittenTimeRange>,
+}
+
+struct SchedulerInner {
+    dbms: DBMSRef,
+    meta: MetaRef,
+    /// Kept alive for the scheduled refreshes
+    trigger_executor_factory: TriggerExecutorFactoryRef,
+    runtime: Arc<DedicatedExecutor>,
+    late: Mutex<LateData>,
+}
+
+#[derive(Default)]
+struct LateData {
+    /// The time range `[min_ts, max_ts]` of the late data of each view
+    ranges: HashMap<ViewKey, (i64, i64)>,
+    /// Written events were missed, all windows before the watermarks are re-computed
+    missed: bool,
+}
+
+impl MaterializedViewScheduler {
+    pub fn new(dbms: DBMSRef, meta: MetaRef, written: Receiver<WrittenTimeRange>) -> Self {
+        // Only do periodic scheduling, no need for many threads
+        let runtime = Arc::new(DedicatedExecutor::new("materialized-view-refresh", 1));
+        let trigger_executor_factory = Arc::new(TriggerExecutorFactory::new(runtime.clone()));
+
+        Self {
+            inner: Arc::new(SchedulerInner {
+                dbms,
+                meta,
+                trigger_executor_factory,
+                runtime,
+                late: Mutex::new(LateData::default()),
+            }),
+            written,
+        }
+    }
+
+    pub fn start(self) {
+        let Self { inner, mut written } = self;
+
+        let collector = inner.clone();
+        tokio::spawn(async move {
+            loop {
+                match written.recv().await {
+                    Ok(written) => collector.collect_late(written).await,
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("Missed {n} written events, re-compute all materialized views");
+                        collector.late.lock().missed = true;
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let trigger_executor = inner
+            .trigger_executor_factory
+            .create(&StreamTriggerInterval::Interval(REFRESH_INTERVAL));
+        let runtime = inner.runtime.clone();
+        trigger_executor
+            .schedule(
+                move |_| {
+                    let inner = inner.clone();
+                    async move {
+                        inner.run_once().await;
+                        Ok(())
+                    }
+                },
+                runtime,
+            )
+            .detach();
+    }
+}
+
+impl SchedulerInner {
+    /// Records the written time range of the closed windows of the views of the table.
+    /// Windows closed when written are classified right after the write, so the lease
+    /// holder either computes the window after the write or the write is re-computed here.
+    async fn collect_late(&self, written: WrittenTimeRange) {
+        let Some(client) = self.meta.tenant_meta(&written.tenant).await else {
+            return;
+        };
+        let now = chrono::Utc::now().timestamp_nanos();
+
+        let mut late = self.late.lock();
+        for view in client.get_materialized_views_of(&written.database, &written.table) {
+            let open = now - now.rem_euclid(view.interval);
+            if written.min_ts >= open {
+                continue;
+            }
+            let max_ts = written.max_ts.min(open - 1);
+            let key = (view.tenant, view.db, view.name);
+            let range = late.ranges.entry(key).or_insert((written.min_ts, max_ts));
+            range.0 = range.0.min(written.min_ts);
+            range.1 = range.1.max(max_ts);
+        }
+    }
+
+    async fn run_once(&self) {
+        let tenants = match self.meta.tenants().await {
+            Ok(tenants) => tenants,
+            Err(err) => {
+                warn!("Failed to list tenants for materialized views: {}", err);
+                return;
+            }
+        };
+
+        let mut views = vec![];
+        for tenant in tenants {
+            if let Some(client) = self.meta.tenant_meta(tenant.name()).await {
+                views.append(&mut client.list_materialized_views());
+            }
+        }
+
+        self.refresh_late(&views).await;
+
+        match self
+            .meta
+            .acquire_lease(REFRESH_LEASE, REFRESH_LEASE_TTL)
+            .await
+        {
+            Ok(true) => self.advance_watermarks(&views).await,
+            Ok(false) => {}
+            Err(err) => warn!("Failed to acquire the lease to refresh views: {}", err),
+        }
+    }
+
+    async fn refresh_late(&self, views: &[MaterializedView]) {
+        let LateData { mut ranges, missed } = mem::take(&mut *self.late.lock());
+        let mut failed = false;
+
+        for view in views {
+            let key = (view.tenant.clone(), view.db.clone(), view.name.clone());
+            // Windows after the watermark are computed by the lease holder
+            let Some(watermark) = view.watermark else {
+                ranges.remove(&key);
+                continue;
+            };
+
+            let (start, end) = if missed {
+                (None, watermark)
+            } else if let Some((min_ts, max_ts)) = ranges.get(&key) {
+                let (start, end) = view.window_range(*min_ts, *max_ts);
+                (Some(start), end)
+            } else {
+                continue;
+            };
+
+            match self.refresh(view, start, end).await {
+                Ok(_) => {
+                    ranges.remove(&key);
+                }
+                Err(err) => {
+                    warn!(
+                        "Failed to refresh materialized view {}.{} of tenant {}: {}",
+                        view.db, view.name, view.tenant, err
+                    );
+                    failed = true;
+                }
+            }
+        }
+
+        // Keep the late data of the failed views to retry them, dropped views are forgotten
+        ranges.retain(|key, _| {
+            views
+                .iter()
+                .any(|v| (&v.tenant, &v.db, &v.name) == (&key.0, &key.1, &key.2))
+        });
+        let mut late = self.late.lock();
+        late.missed |= missed && failed;
+        for (key, (min_ts, max_ts)) in ranges {
+            let range = late.ranges.entry(key).or_insert((min_ts, max_ts));
+            range.0 = range.0.min(min_ts);
+            range.1 = range.1.max(max_ts);
+        }
+    }
+
+    /// Computes the windows closed since the watermark of each view and moves the watermark.
+    async fn advance_watermarks(&self, views: &[MaterializedView]) {
+        let now = chrono::Utc::now().timestamp_nanos();
+        for view in views {
+            let end = now - now.rem_euclid(view.interval);
+            if view.watermark.map_or(false, |w| w >= end) {
+                continue;
+            }
+
+            let result = self.refresh(view, view.watermark, end).await;
+            let result = match (result, self.meta.tenant_meta(&view.tenant).await) {
+                (Ok(_), Some(client)) => client
+                    .update_materialized_view_watermark(&view.db, &view.name, end)
+                    .await
+                    .map_err(Into::into),
+                (result, _) => result,
+            };
+            if let Err(err) = result {
+                warn!(
+                    "Failed to refresh materialized view {}.{} of tenant {}: {}",
+                    view.db, view.name, view.tenant, err
+                );
+            }
+        }
+    }
+
+    async fn refresh(&self, view: &MaterializedView, start: Option<i64>, end: i64) -> Result<()> {
+        debug!(
+            "Refresh materialized view {}.{} of tenant {} in [{:?}, {})",
+            view.db, view.name, view.tenant, start, end
+        );
+
+        let sql = build_refresh_sql(view, start, end);
+        let user = self
+            .meta
+            .user_with_privileges(&view.owner, Some(&view.tenant))
+            .await?;
+        let ctx = ContextBuilder::new(user)
+            .with_tenant(Some(view.tenant.clone()))
+            .with_database(Some(view.db.clone()))
+            .build();
+        let handle = self.dbms.execute(&Query::new(ctx, sql), None).await?;
+        handle.result().chunk_result().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Int64Builder, StringBuilder, UInt32Builder, UInt64Builder};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    pub static ref COLD_MIGRATION_SCHEMA: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new("tenant_name", DataType::Utf8, false),
+        Field::new("database_name", DataType::Utf8, false),
+        Field::new("bucket_id", DataType::UInt32, false),
+        Field::new("start_time", DataType::Int64, false),
+        Field::new("end_time", DataType::Int64, false),
+        Field::new("replication_set_id", DataType::UInt32, false),
+        Field::new("vnode_id", DataType::UInt32, false),
+        Field::new("node_id", DataType::UInt64, false),
+        Field::new("status", DataType::Utf8, false),
+    ]));
+}
+
+/// Builds the `cluster_schema.COLD_MIGRATIONS` table row by row
+#[derive(Default)]
+pub struct ClusterSchemaColdMigrationsBuilder {
+    tenant_names: StringBuilder,
+    database_names: StringBuilder,
+    bucket_ids: UInt32Builder,
+    start_times: Int64Builder,
+    end_times: Int64Builder,
+    replication_set_ids: UInt32Builder,
+    vnode_ids: UInt32Builder,
+    node_ids: UInt64Builder,
+    statuses: StringBuilder,
+}
+
+impl ClusterSchemaColdMigrationsBuilder {
+    #[allow(clippy::too_many_arguments)]
+    pub fn append_row(
+        &mut self,
+        tenant_name: impl AsRef<str>,
+        database_name: impl AsRef<str>,
+        bucket_id: u32,
+        start_time: i64,
+        end_time: i64,
+        replication_set_id: u32,
+        vnode_id: u32,
+        node_id: u64,
+        status: impl AsRef<str>,
+    ) {
+        // Note: append_value is actually infallable.
+        self.tenant_names.append_value(tenant_name.as_ref());
+        self.database_names.append_value(database_name.as_ref());
+        self.bucket_ids.append_value(bucket_id);
+        self.start_times.append_value(start_time);
+        self.end_times.append_value(end_time);
+        self.replication_set_ids.append_value(replication_set_id);
+        self.vnode_ids.append_value(vnode_id);
+        self.node_ids.append_value(node_id);
+        self.statuses.append_value(status.as_ref());
+    }
+}
+
+impl TryFrom<ClusterSchemaColdMigrationsBuilder> for RecordBatch {
+    type Error = DataFusionError;
+
+    fn try_from(value: ClusterSchemaColdMigrationsBuilder) -> Result<Self, Self::Error> {
+        let ClusterSchemaColdMigrationsBuilder {
+            mut tenant_names,
+            mut database_names,
+            mut bucket_ids,
+            mut start_times,
+            mut end_times,
+            mut replication_set_ids,
+            mut vnode_ids,
+            mut node_ids,
+            mut statuses,
+        } = value;
+
+        let batch = RecordBatch::try_new(
+            COLD_MIGRATION_SCHEMA.clone(),
+            vec![
+                Arc::new(tenant_names.finish()),
+                Arc::new(database_names.finish()),
+                Arc::new(bucket_ids.finish()),
+                Arc::new(start_times.finish()),
+                Arc::new(end_times.finish()),
+                Arc::new(replication_set_ids.finish()),
+                Arc::new(vnode_ids.finish()),
+                Arc::new(node_ids.finish()),
+                Arc::new(statuses.finish()),
+            ],
+        )?;
+
+        Ok(batch)
+    }
+}
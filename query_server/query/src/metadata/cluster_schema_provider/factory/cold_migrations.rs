@@ -0,0 +1,130 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result as DFResult};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::logical_plan::AggWithGrouping;
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::memory::MemoryExec;
+use datafusion::physical_plan::ExecutionPlan;
+use meta::model::MetaRef;
+use models::auth::user::User;
+use models::meta_data::{NodeAttribute, VnodeStatus};
+
+use crate::metadata::cluster_schema_provider::builder::cold_migrations::{
+    ClusterSchemaColdMigrationsBuilder, COLD_MIGRATION_SCHEMA,
+};
+use crate::metadata::cluster_schema_provider::ClusterSchemaTableFactory;
+
+const CLUSTER_SCHEMA_COLD_MIGRATIONS: &str = "COLD_MIGRATIONS";
+
+/// The vnodes of buckets older than the `COLD_AFTER` of their databases,
+/// and whether they have been moved to cold nodes.
+pub struct ClusterSchemaColdMigrationsFactory {}
+
+impl ClusterSchemaTableFactory for ClusterSchemaColdMigrationsFactory {
+    fn table_name(&self) -> &str {
+        CLUSTER_SCHEMA_COLD_MIGRATIONS
+    }
+
+    fn create(&self, user: &User, metadata: MetaRef) -> Arc<dyn TableProvider> {
+        Arc::new(ClusterSchemaColdMigrationsTable::new(
+            metadata,
+            user.clone(),
+        ))
+    }
+}
+
+pub struct ClusterSchemaColdMigrationsTable {
+    user: User,
+    metadata: MetaRef,
+}
+
+impl ClusterSchemaColdMigrationsTable {
+    pub fn new(metadata: MetaRef, user: User) -> Self {
+        Self { user, metadata }
+    }
+}
+
+#[async_trait::async_trait]
+impl TableProvider for ClusterSchemaColdMigrationsTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        COLD_MIGRATION_SCHEMA.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        _agg_with_grouping: Option<&AggWithGrouping>,
+        _limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let mut builder = ClusterSchemaColdMigrationsBuilder::default();
+
+        // Only visible to admin
+        if self.user.desc().is_admin() {
+            let cold_nodes = self
+                .metadata
+                .data_nodes()
+                .await
+                .into_iter()
+                .filter(|node| node.attribute == NodeAttribute::Cold)
+                .map(|node| node.id)
+                .collect::<Vec<_>>();
+            let tenants =
+                self.metadata.tenants().await.map_err(|e| {
+                    DataFusionError::Internal(format!("failed to list tenant {}", e))
+                })?;
+
+            for tenant in tenants.iter() {
+                let Some(client) = self.metadata.tenant_meta(tenant.name()).await else {
+                    continue;
+                };
+                for info in client.cold_bucket() {
+                    for repl_set in info.bucket.shard_group.iter() {
+                        for vnode in repl_set.vnodes.iter() {
+                            let status = if cold_nodes.contains(&vnode.node_id) {
+                                "done"
+                            } else if vnode.status == VnodeStatus::Copying {
+                                "moving"
+                            } else {
+                                "pending"
+                            };
+                            builder.append_row(
+                                &info.tenant,
+                                &info.database,
+                                info.bucket.id,
+                                info.bucket.start_time,
+                                info.bucket.end_time,
+                                repl_set.id,
+                                vnode.id,
+                                vnode.node_id,
+                                status,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        let rb: RecordBatch = builder.try_into()?;
+
+        Ok(Arc::new(MemoryExec::try_new(
+            &[vec![rb]],
+            self.schema(),
+            projection.cloned(),
+        )?))
+    }
+}
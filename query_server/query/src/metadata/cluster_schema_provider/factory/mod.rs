@@ -1,2 +1,3 @@
+pub mod cold_migrations;
 pub mod tenants;
 pub mod users;
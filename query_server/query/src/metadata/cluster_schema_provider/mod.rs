@@ -7,6 +7,7 @@ use meta::error::MetaError;
 use meta::model::MetaRef;
 use models::auth::user::User;
 
+use self::factory::cold_migrations::ClusterSchemaColdMigrationsFactory;
 use self::factory::tenants::ClusterSchemaTenantsFactory;
 use self::factory::users::ClusterSchemaUsersFactory;
 use super::CLUSTER_SCHEMA;
@@ -26,6 +27,7 @@ impl ClusterSchemaProvider {
 
         provider.register_table_factory(Box::new(ClusterSchemaTenantsFactory {}));
         provider.register_table_factory(Box::new(ClusterSchemaUsersFactory {}));
+        provider.register_table_factory(Box::new(ClusterSchemaColdMigrationsFactory {}));
 
         provider
     }
@@ -0,0 +1,376 @@
+//! Encoding of the `STREAMED_XOR_CHUNKS` response of prometheus remote read.
+//!
+//! Samples are encoded into the XOR chunks of the prometheus tsdb, and each
+//! `ChunkedReadResponse` is written as a frame of
+//! `uvarint(len) | big endian crc32c(message) | message`.
+
+use protos::prompb::types::chunk::Encoding;
+use protos::prompb::types::{Chunk, Sample};
+
+/// The number of samples prometheus keeps in a chunk
+pub const MAX_SAMPLES_PER_CHUNK: usize = 120;
+
+/// Encodes the samples, which are sorted by time, into XOR chunks.
+pub fn encode_xor_chunks(samples: &[Sample]) -> Vec<Chunk> {
+    samples
+        .chunks(MAX_SAMPLES_PER_CHUNK)
+        .map(|samples| {
+            let mut encoder = XorEncoder::default();
+            for sample in samples {
+                encoder.append(sample.timestamp, sample.value);
+            }
+            Chunk {
+                min_time_ms: samples.first().map(|s| s.timestamp).unwrap_or_default(),
+                max_time_ms: samples.last().map(|s| s.timestamp).unwrap_or_default(),
+                type_: Encoding::XOR.into(),
+                data: encoder.finish(),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Wraps an encoded `ChunkedReadResponse` into a frame.
+pub fn write_frame(message: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(message.len() + 14);
+    let mut len = message.len() as u64;
+    while len >= 0x80 {
+        frame.push(len as u8 | 0x80);
+        len >>= 7;
+    }
+    frame.push(len as u8);
+    frame.extend_from_slice(&crc32c(message).to_be_bytes());
+    frame.extend_from_slice(message);
+    frame
+}
+
+/// Encoder of the XOR chunk of prometheus tsdb.
+///
+/// The chunk starts with the number of samples in 2 bytes, followed by the first timestamp
+/// as varint and the first value in 64 bits. The second timestamp is a uvarint delta and
+/// the later ones are delta of deltas, the values are xor-ed with the previous value.
+struct XorEncoder {
+    bits: BitWriter,
+    num_samples: u16,
+    t: i64,
+    t_delta: u64,
+    v: f64,
+    leading: u8,
+    trailing: u8,
+}
+
+impl Default for XorEncoder {
+    fn default() -> Self {
+        let mut bits = BitWriter::default();
+        // Placeholder of the number of samples
+        bits.write_bits(0, 16);
+        Self {
+            bits,
+            num_samples: 0,
+            t: 0,
+            t_delta: 0,
+            v: 0.0,
+            leading: 0xff,
+            trailing: 0,
+        }
+    }
+}
+
+impl XorEncoder {
+    fn append(&mut self, t: i64, v: f64) {
+        match self.num_samples {
+            0 => {
+                self.bits.write_varint(t);
+                self.bits.write_bits(v.to_bits(), 64);
+            }
+            1 => {
+                let t_delta = t.wrapping_sub(self.t) as u64;
+                self.bits.write_uvarint(t_delta);
+                self.write_value(v);
+                self.t_delta = t_delta;
+            }
+            _ => {
+                let t_delta = t.wrapping_sub(self.t) as u64;
+                let dod = t_delta.wrapping_sub(self.t_delta) as i64;
+                match dod {
+                    0 => self.bits.write_bit(false),
+                    _ if bit_range(dod, 14) => {
+                        self.bits.write_bits(0b10, 2);
+                        self.bits.write_bits(dod as u64, 14);
+                    }
+                    _ if bit_range(dod, 17) => {
+                        self.bits.write_bits(0b110, 3);
+                        self.bits.write_bits(dod as u64, 17);
+                    }
+                    _ if bit_range(dod, 20) => {
+                        self.bits.write_bits(0b1110, 4);
+                        self.bits.write_bits(dod as u64, 20);
+                    }
+                    _ => {
+                        self.bits.write_bits(0b1111, 4);
+                        self.bits.write_bits(dod as u64, 64);
+                    }
+                }
+                self.write_value(v);
+                self.t_delta = t_delta;
+            }
+        }
+
+        self.t = t;
+        self.v = v;
+        self.num_samples += 1;
+    }
+
+    fn write_value(&mut self, v: f64) {
+        let delta = v.to_bits() ^ self.v.to_bits();
+        if delta == 0 {
+            self.bits.write_bit(false);
+            return;
+        }
+        self.bits.write_bit(true);
+
+        let leading = (delta.leading_zeros() as u8).min(31);
+        let trailing = delta.trailing_zeros() as u8;
+        if self.leading != 0xff && leading >= self.leading && trailing >= self.trailing {
+            // The meaningful bits are in the window of the previous value
+            self.bits.write_bit(false);
+            self.bits.write_bits(
+                delta >> self.trailing,
+                64 - self.leading as u32 - self.trailing as u32,
+            );
+            return;
+        }
+
+        self.leading = leading;
+        self.trailing = trailing;
+        let sig_bits = 64 - leading as u32 - trailing as u32;
+        self.bits.write_bit(true);
+        self.bits.write_bits(leading as u64, 5);
+        // 64 significant bits overflow to 0, which can't happen otherwise
+        self.bits.write_bits(sig_bits as u64, 6);
+        self.bits.write_bits(delta >> trailing, sig_bits);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut data = self.bits.bytes;
+        data[..2].copy_from_slice(&self.num_samples.to_be_bytes());
+        data
+    }
+}
+
+/// Returns whether `x` can be represented in `nbits` by the XOR chunk.
+fn bit_range(x: i64, nbits: u32) -> bool {
+    -((1 << (nbits - 1)) - 1) <= x && x <= 1 << (nbits - 1)
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// The number of bits still available in the last byte
+    available: u32,
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, bit: bool) {
+        if self.available == 0 {
+            self.bytes.push(0);
+            self.available = 8;
+        }
+        self.available -= 1;
+        if bit {
+            *self.bytes.last_mut().expect("pushed above") |= 1 << self.available;
+        }
+    }
+
+    /// Writes the lowest `nbits` of `value`, most significant first.
+    fn write_bits(&mut self, value: u64, nbits: u32) {
+        for i in (0..nbits).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    fn write_uvarint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.write_bits((value as u8 | 0x80) as u64, 8);
+            value >>= 7;
+        }
+        self.write_bits(value, 8);
+    }
+
+    fn write_varint(&mut self, value: i64) {
+        self.write_uvarint(((value << 1) ^ (value >> 63)) as u64);
+    }
+}
+
+/// CRC-32 with the Castagnoli polynomial, which prometheus checks the frames with.
+fn crc32c(data: &[u8]) -> u32 {
+    const POLY: u32 = 0x82f6_3b78;
+    const TABLE: [u32; 256] = {
+        let mut table = [0_u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut j = 0;
+            while j < 8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ POLY
+                } else {
+                    crc >> 1
+                };
+                j += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    !data.iter().fold(!0_u32, |crc, b| {
+        TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use protos::prompb::types::Sample;
+
+    use super::{crc32c, encode_xor_chunks, write_frame, MAX_SAMPLES_PER_CHUNK};
+
+    /// Decoder of the XOR chunk, following the prometheus tsdb.
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+    }
+
+    impl BitReader<'_> {
+        fn read_bit(&mut self) -> bool {
+            let bit = self.bytes[self.pos / 8] >> (7 - self.pos % 8) & 1 == 1;
+            self.pos += 1;
+            bit
+        }
+
+        fn read_bits(&mut self, nbits: u32) -> u64 {
+            (0..nbits).fold(0, |v, _| v << 1 | self.read_bit() as u64)
+        }
+
+        fn read_uvarint(&mut self) -> u64 {
+            let mut value = 0;
+            for shift in (0..).step_by(7) {
+                let b = self.read_bits(8);
+                value |= (b & 0x7f) << shift;
+                if b < 0x80 {
+                    break;
+                }
+            }
+            value
+        }
+    }
+
+    fn decode(data: &[u8]) -> Vec<(i64, f64)> {
+        let num = u16::from_be_bytes([data[0], data[1]]) as usize;
+        let mut r = BitReader {
+            bytes: &data[2..],
+            pos: 0,
+        };
+        let mut samples = vec![];
+        let (mut t, mut v, mut t_delta) = (0_i64, 0_u64, 0_i64);
+        let (mut leading, mut trailing) = (0_u32, 0_u32);
+        for i in 0..num {
+            match i {
+                0 => {
+                    let u = r.read_uvarint();
+                    t = (u >> 1) as i64 ^ -((u & 1) as i64);
+                    v = r.read_bits(64);
+                    samples.push((t, f64::from_bits(v)));
+                    continue;
+                }
+                1 => t_delta = r.read_uvarint() as i64,
+                _ => {
+                    let mut prefix = 0;
+                    while prefix < 4 && r.read_bit() {
+                        prefix += 1;
+                    }
+                    let dod = match prefix {
+                        0 => 0,
+                        _ => {
+                            let nbits = [14, 17, 20, 64][prefix - 1];
+                            let bits = r.read_bits(nbits);
+                            if nbits < 64 && bits > 1 << (nbits - 1) {
+                                bits as i64 - (1 << nbits)
+                            } else {
+                                bits as i64
+                            }
+                        }
+                    };
+                    t_delta += dod;
+                }
+            }
+            t += t_delta;
+            if r.read_bit() {
+                if r.read_bit() {
+                    leading = r.read_bits(5) as u32;
+                    let sig = match r.read_bits(6) as u32 {
+                        0 => 64,
+                        sig => sig,
+                    };
+                    trailing = 64 - leading - sig;
+                }
+                v ^= r.read_bits(64 - leading - trailing) << trailing;
+            }
+            samples.push((t, f64::from_bits(v)));
+        }
+        samples
+    }
+
+    #[test]
+    fn test_encode_xor_chunks() {
+        let mut samples = vec![];
+        let mut t = 1_673_069_176_267_i64;
+        for i in 0..300 {
+            // Irregular intervals and values
+            t += [15_000, 15_001, 14_999, 300_000, 1, 90_000_000_000][i % 6];
+            let value = match i % 4 {
+                0 => 1.0,
+                1 => i as f64 * 0.1,
+                2 => -(i as f64),
+                _ => f64::MAX,
+            };
+            samples.push(Sample {
+                value,
+                timestamp: t,
+                ..Default::default()
+            });
+        }
+
+        let chunks = encode_xor_chunks(&samples);
+        assert_eq!(chunks.len(), 3);
+
+        let decoded = chunks
+            .iter()
+            .flat_map(|chunk| decode(&chunk.data))
+            .collect::<Vec<_>>();
+        let expected = samples
+            .iter()
+            .map(|s| (s.timestamp, s.value))
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, expected);
+
+        assert_eq!(chunks[0].min_time_ms, samples[0].timestamp);
+        assert_eq!(
+            chunks[0].max_time_ms,
+            samples[MAX_SAMPLES_PER_CHUNK - 1].timestamp
+        );
+    }
+
+    #[test]
+    fn test_write_frame() {
+        // The check value of CRC-32C
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+
+        let message = vec![1_u8; 300];
+        let frame = write_frame(&message);
+        assert_eq!(&frame[..2], &[0xac, 0x02]);
+        assert_eq!(&frame[2..6], &crc32c(&message).to_be_bytes());
+        assert_eq!(&frame[6..], message.as_slice());
+    }
+}
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use datafusion::common::Column;
+use datafusion::logical_expr::{binary_expr, Operator};
+use datafusion::prelude::{lit, Expr};
+use meta::error::MetaError;
+use meta::model::MetaClientRef;
+use models::schema::TskvTableSchema;
+use protos::prompb::types::label_matcher::Type;
+use regex::Regex;
+use spi::Result;
+use trace::warn;
+
+use super::METRIC_SAMPLE_COLUMN_NAME;
+
+/// A label matcher, regular expressions are anchored at both ends like prometheus.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    pub name: String,
+    pub type_: Type,
+    pub value: String,
+    regex: Option<Regex>,
+}
+
+impl Matcher {
+    pub fn try_new(
+        name: impl Into<String>,
+        type_: Type,
+        value: impl Into<String>,
+    ) -> std::result::Result<Self, regex::Error> {
+        let value = value.into();
+        let regex = match type_ {
+            Type::RE | Type::NRE => Some(Regex::new(&anchored(&value))?),
+            Type::EQ | Type::NEQ => None,
+        };
+
+        Ok(Self {
+            name: name.into(),
+            type_,
+            value,
+            regex,
+        })
+    }
+
+    pub fn matches(&self, value: &str) -> bool {
+        match (self.type_, &self.regex) {
+            (Type::EQ, _) => self.value == value,
+            (Type::NEQ, _) => self.value != value,
+            (Type::RE, Some(regex)) => regex.is_match(value),
+            (Type::NRE, Some(regex)) => !regex.is_match(value),
+            _ => false,
+        }
+    }
+
+    /// The filter of the tag, a missing label has the empty value in prometheus.
+    pub fn to_sql(&self) -> String {
+        let column = quote_identifier(&self.name);
+        let filter = match self.type_ {
+            Type::EQ => format!("{column} = {}", quote_literal(&self.value)),
+            Type::NEQ => format!("{column} != {}", quote_literal(&self.value)),
+            Type::RE => format!("{column} ~ {}", quote_literal(&anchored(&self.value))),
+            Type::NRE => format!("{column} !~ {}", quote_literal(&anchored(&self.value))),
+        };
+
+        if self.matches("") {
+            format!("({column} IS NULL OR {filter})")
+        } else {
+            filter
+        }
+    }
+
+    /// The filter of the tag as an expression, see [`Matcher::to_sql`].
+    pub fn to_expr(&self) -> Expr {
+        let column = Expr::Column(Column::from_name(&self.name));
+        let filter = match self.type_ {
+            Type::EQ => column.clone().eq(lit(self.value.clone())),
+            Type::NEQ => column.clone().not_eq(lit(self.value.clone())),
+            Type::RE => binary_expr(
+                column.clone(),
+                Operator::RegexMatch,
+                lit(anchored(&self.value)),
+            ),
+            Type::NRE => binary_expr(
+                column.clone(),
+                Operator::RegexNotMatch,
+                lit(anchored(&self.value)),
+            ),
+        };
+
+        if self.matches("") {
+            column.is_null().or(filter)
+        } else {
+            filter
+        }
+    }
+}
+
+fn anchored(pattern: &str) -> String {
+    format!("^(?:{pattern})$")
+}
+
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+pub(crate) fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Get the schema of the table specified by `__name__ = <name>`, or of the tables matched
+/// by the metric name, sorted by name.
+///
+/// A missing table of the specified name is an error if `must_exist`, and only the tables
+/// written by prometheus, which have the field `value`, are matched by the other matchers.
+pub(crate) fn match_tables(
+    meta: &MetaClientRef,
+    database: &str,
+    name_matchers: &[&Matcher],
+    must_exist: bool,
+) -> Result<Vec<Arc<TskvTableSchema>>> {
+    if let Some(m) = name_matchers.iter().find(|m| m.type_ == Type::EQ) {
+        let table_name = &m.value;
+        let table = match meta.get_tskv_table_schema(database, table_name)? {
+            Some(table) => table,
+            None if must_exist => {
+                return Err(MetaError::TableNotFound {
+                    table: table_name.to_string(),
+                }
+                .into())
+            }
+            None => return Ok(vec![]),
+        };
+        return Ok(name_matchers
+            .iter()
+            .all(|m| m.matches(table_name))
+            .then_some(table)
+            .into_iter()
+            .collect());
+    }
+
+    let mut table_names = meta
+        .list_tables(database)?
+        .into_iter()
+        .filter(|table_name| name_matchers.iter().all(|m| m.matches(table_name)))
+        .collect::<Vec<_>>();
+    table_names.sort();
+
+    Ok(table_names
+        .iter()
+        .flat_map(
+            |table_name| match meta.get_tskv_table_schema(database, table_name) {
+                Ok(s) => s,
+                Err(_) => {
+                    warn!(
+                        "The table {} may have just been dropped, or it may be a bug.",
+                        table_name
+                    );
+                    None
+                }
+            },
+        )
+        // Only the tables written by prometheus are metrics
+        .filter(|table| {
+            table
+                .column(METRIC_SAMPLE_COLUMN_NAME)
+                .map_or(false, |col| col.column_type.is_field())
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use protos::prompb::types::label_matcher::Type;
+
+    use super::Matcher;
+
+    fn matcher(type_: Type, name: &str, value: &str) -> Matcher {
+        Matcher::try_new(name, type_, value).unwrap()
+    }
+
+    #[test]
+    fn test_label_matcher() {
+        let m = matcher(Type::RE, "__name__", "node_.*");
+        assert!(m.matches("node_cpu"));
+        // Anchored like prometheus
+        assert!(!m.matches("my_node_cpu"));
+
+        assert_eq!(
+            matcher(Type::EQ, "job", "it's").to_sql(),
+            "\"job\" = 'it''s'"
+        );
+        // A missing label has the empty value
+        assert_eq!(
+            matcher(Type::NEQ, "job", "api").to_sql(),
+            "(\"job\" IS NULL OR \"job\" != 'api')"
+        );
+        assert_eq!(
+            matcher(Type::RE, "job", "api|web").to_sql(),
+            "\"job\" ~ '^(?:api|web)$'"
+        );
+        assert_eq!(
+            matcher(Type::NRE, "job", "api.*").to_sql(),
+            "(\"job\" IS NULL OR \"job\" !~ '^(?:api.*)$')"
+        );
+        assert_eq!(
+            matcher(Type::NEQ, "job", "api").to_expr().to_string(),
+            "job IS NULL OR job != Utf8(\"api\")"
+        );
+
+        assert!(Matcher::try_new("job", Type::RE, "(").is_err());
+    }
+}
@@ -1,3 +1,6 @@
+pub mod chunk;
+pub mod matcher;
+pub mod promql;
 pub mod remote_server;
 pub mod time_series;
 
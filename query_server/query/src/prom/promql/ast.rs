@@ -0,0 +1,172 @@
+//! The syntax tree of PromQL, durations and offsets are in nanoseconds.
+
+use crate::prom::matcher::Matcher;
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    NumberLiteral(f64),
+    StringLiteral(String),
+    VectorSelector(VectorSelector),
+    /// `<selector>[<range>]`
+    MatrixSelector {
+        selector: VectorSelector,
+        range: i64,
+    },
+    Aggregate(AggregateExpr),
+    /// `<func>(<args>)`
+    Call {
+        func: String,
+        args: Vec<Expr>,
+    },
+    /// `-<expr>`
+    Negative(Box<Expr>),
+    Binary(BinaryExpr),
+}
+
+#[derive(Debug, Clone)]
+pub struct VectorSelector {
+    pub name: Option<String>,
+    /// The matchers of the labels, including `__name__` of the name
+    pub matchers: Vec<Matcher>,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AggregateExpr {
+    pub op: AggregateOp,
+    pub grouping: Option<Grouping>,
+    /// The parameter of `topk`, `bottomk`, `quantile` and `count_values`
+    pub param: Option<Box<Expr>>,
+    pub expr: Box<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateOp {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
+    Stddev,
+    Stdvar,
+    Group,
+    Topk,
+    Bottomk,
+    Quantile,
+    CountValues,
+}
+
+impl AggregateOp {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sum" => Self::Sum,
+            "avg" => Self::Avg,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "count" => Self::Count,
+            "stddev" => Self::Stddev,
+            "stdvar" => Self::Stdvar,
+            "group" => Self::Group,
+            "topk" => Self::Topk,
+            "bottomk" => Self::Bottomk,
+            "quantile" => Self::Quantile,
+            "count_values" => Self::CountValues,
+            _ => return None,
+        })
+    }
+
+    pub fn has_param(&self) -> bool {
+        matches!(
+            self,
+            Self::Topk | Self::Bottomk | Self::Quantile | Self::CountValues
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Grouping {
+    By(Vec<String>),
+    Without(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct BinaryExpr {
+    pub op: BinaryOp,
+    pub lhs: Box<Expr>,
+    pub rhs: Box<Expr>,
+    /// The `bool` modifier of the comparisons
+    pub return_bool: bool,
+    pub matching: VectorMatching,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Atan2,
+    Eql,
+    Neq,
+    Gtr,
+    Lss,
+    Gte,
+    Lte,
+    And,
+    Or,
+    Unless,
+}
+
+impl BinaryOp {
+    /// Operators of a higher precedence bind tighter
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And | Self::Unless => 2,
+            Self::Eql | Self::Neq | Self::Gtr | Self::Lss | Self::Gte | Self::Lte => 3,
+            Self::Add | Self::Sub => 4,
+            Self::Mul | Self::Div | Self::Mod | Self::Atan2 => 5,
+            Self::Pow => 6,
+        }
+    }
+
+    pub fn is_right_associative(&self) -> bool {
+        *self == Self::Pow
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Self::Eql | Self::Neq | Self::Gtr | Self::Lss | Self::Gte | Self::Lte
+        )
+    }
+
+    pub fn is_set_operator(&self) -> bool {
+        matches!(self, Self::And | Self::Or | Self::Unless)
+    }
+}
+
+/// The matching of the series of `<vector> <op> <vector>`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VectorMatching {
+    pub labels: Option<MatchingLabels>,
+    pub card: Cardinality,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchingLabels {
+    On(Vec<String>),
+    Ignoring(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Cardinality {
+    #[default]
+    OneToOne,
+    /// `group_left(<labels of the right side to include>)`
+    ManyToOne(Vec<String>),
+    /// `group_right(<labels of the left side to include>)`
+    OneToMany(Vec<String>),
+}
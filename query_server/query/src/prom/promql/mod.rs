@@ -0,0 +1,587 @@
+//! PromQL instant and range queries over the metrics written by prometheus remote write.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use chrono::DateTime;
+use coordinator::service::CoordinatorRef;
+use datafusion::arrow::array::{as_primitive_array, as_string_array, Array, StringArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Float64Type, Int64Type};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::Column;
+use datafusion::logical_expr::{LogicalPlan, LogicalPlanBuilder};
+use datafusion::prelude::Expr;
+use meta::model::MetaClientRef;
+use models::auth::privilege::{DatabasePrivilege, Privilege, TenantObjectPrivilege};
+use spi::query::logical_planner::{self, QueryPlan};
+use spi::server::dbms::DBMSRef;
+use spi::server::prom::{
+    PromInstantSeries, PromQueryResult, PromQueryTime, PromRangeSeries, PromSample, PromSeriesQuery,
+};
+use spi::service::protocol::{Context, Query};
+use spi::{QueryError, Result};
+use trace::{debug, SpanContext};
+
+use self::ast::Expr as PromExpr;
+use self::planner::{EvalRange, MetricTable, Plan, Planner, VectorPlan};
+use super::matcher::{match_tables, Matcher};
+use super::METRIC_SAMPLE_COLUMN_NAME;
+use crate::data_source::batch::tskv::ClusterTable;
+use crate::data_source::split::SplitManager;
+use crate::data_source::table_source::TableSourceAdapter;
+use crate::sql::planner::check_privilege;
+
+pub mod ast;
+pub mod parser;
+pub mod planner;
+
+pub use parser::parse_duration;
+
+/// The maximum number of evaluations of a range query
+pub const MAX_POINTS_PER_SERIES: i64 = 11_000;
+
+/// The selector of all series of `/api/v1/labels` and `/api/v1/label/<name>/values`
+const ALL_SERIES: &str = "{__name__=~\".+\"}";
+
+type Metric = BTreeMap<String, String>;
+
+/// The clients of a PromQL query
+pub struct PromContext<'a> {
+    pub db: &'a DBMSRef,
+    pub coord: &'a CoordinatorRef,
+    pub meta: &'a MetaClientRef,
+    pub ctx: &'a Context,
+    pub span_ctx: Option<&'a SpanContext>,
+}
+
+pub async fn query(
+    prom_ctx: &PromContext<'_>,
+    query: &str,
+    time: PromQueryTime,
+) -> Result<PromQueryResult> {
+    let expr = parser::parse(query)?;
+
+    let range = match time {
+        PromQueryTime::Instant(t) => EvalRange::instant(t),
+        PromQueryTime::Range { start, end, step } => {
+            if end < start {
+                return Err(invalid("end timestamp must not be before start time"));
+            }
+            if step <= 0 {
+                return Err(invalid(
+                    "zero or negative query resolution step widths are not accepted",
+                ));
+            }
+            if (end - start) / step >= MAX_POINTS_PER_SERIES {
+                return Err(invalid(
+                    "exceeded maximum resolution of 11,000 points per timeseries, try increasing the step",
+                ));
+            }
+            EvalRange { start, end, step }
+        }
+    };
+
+    let tables = |matchers: &[&Matcher]| metric_tables(prom_ctx, matchers);
+    let plan = Planner::new(range, &tables).plan(&expr)?;
+    debug!("Plan of PromQL {}: {:?}", query, plan);
+
+    match (time, plan) {
+        (PromQueryTime::Instant(t), Plan::Scalar(v)) => {
+            Ok(PromQueryResult::Scalar(PromSample::new(t, v)))
+        }
+        (PromQueryTime::Range { start, end, step }, Plan::Scalar(v)) => {
+            let mut values = vec![];
+            let mut t = start;
+            while t <= end {
+                values.push(PromSample::new(t, v));
+                t += step;
+            }
+            Ok(PromQueryResult::Matrix(vec![PromRangeSeries {
+                metric: Metric::new(),
+                values,
+            }]))
+        }
+        (PromQueryTime::Instant(_), Plan::Vector(plan)) => {
+            let series = read_series(prom_ctx, query, plan).await?;
+            Ok(PromQueryResult::Vector(
+                series
+                    .into_iter()
+                    .flat_map(|(metric, samples)| {
+                        samples
+                            .into_iter()
+                            .map(move |value| PromInstantSeries {
+                                metric: metric.clone(),
+                                value,
+                            })
+                    })
+                    .collect(),
+            ))
+        }
+        (PromQueryTime::Instant(_), Plan::Matrix(plan))
+        | (PromQueryTime::Range { .. }, Plan::Vector(plan)) => {
+            let series = read_series(prom_ctx, query, plan).await?;
+            Ok(PromQueryResult::Matrix(
+                series
+                    .into_iter()
+                    .map(|(metric, values)| PromRangeSeries { metric, values })
+                    .collect(),
+            ))
+        }
+        (PromQueryTime::Range { .. }, Plan::Matrix(_)) => Err(invalid(
+            "invalid expression type \"range vector\" for range query, must be scalar or instant vector",
+        )),
+    }
+}
+
+/// The labels of the series matched by any selector, sorted by labels
+pub async fn series(prom_ctx: &PromContext<'_>, query: &PromSeriesQuery) -> Result<Vec<Metric>> {
+    if query.matchers.is_empty() {
+        return Err(invalid("no match[] parameter provided"));
+    }
+    let selectors = query
+        .matchers
+        .iter()
+        .map(|m| match parser::parse(m)? {
+            PromExpr::VectorSelector(selector) => Ok(selector),
+            _ => Err(invalid(format!("invalid series selector \"{m}\""))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let tables = |matchers: &[&Matcher]| metric_tables(prom_ctx, matchers);
+    // The evaluation timestamps are unused
+    let planner = Planner::new(EvalRange::instant(0), &tables);
+
+    let mut series = BTreeSet::new();
+    for (selector, text) in selectors.iter().zip(&query.matchers) {
+        let Some(plan) = planner.series(selector, query.start, query.end)? else {
+            continue;
+        };
+        for batch in execute(prom_ctx, text, plan.plan).await? {
+            series.extend(row_labels(&batch, &plan.labels, 0)?);
+        }
+    }
+    Ok(series.into_iter().collect())
+}
+
+/// The label names of the series, sorted by name
+pub async fn labels(prom_ctx: &PromContext<'_>, query: &PromSeriesQuery) -> Result<Vec<String>> {
+    let series = series(prom_ctx, &all_series_by_default(query)).await?;
+    Ok(series
+        .into_iter()
+        .flat_map(|metric| metric.into_keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect())
+}
+
+/// The values of the label of the series, sorted by value
+pub async fn label_values(
+    prom_ctx: &PromContext<'_>,
+    name: &str,
+    query: &PromSeriesQuery,
+) -> Result<Vec<String>> {
+    let series = series(prom_ctx, &all_series_by_default(query)).await?;
+    Ok(series
+        .into_iter()
+        .filter_map(|mut metric| metric.remove(name))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect())
+}
+
+/// Parses a unix timestamp in seconds like `1435781451.781`, or a RFC 3339 timestamp.
+pub fn parse_time(input: &str) -> Result<i64> {
+    if let Ok(seconds) = input.parse::<f64>() {
+        let nanos = seconds * 1e9;
+        if nanos.is_finite() && nanos.abs() < i64::MAX as f64 {
+            return Ok(nanos.round() as i64);
+        }
+    }
+
+    DateTime::parse_from_rfc3339(input)
+        .map(|t| t.timestamp_nanos())
+        .map_err(|_| invalid(format!("cannot parse \"{input}\" to a valid timestamp")))
+}
+
+fn invalid(reason: impl Into<String>) -> QueryError {
+    QueryError::InvalidPromQuery {
+        reason: reason.into(),
+    }
+}
+
+fn all_series_by_default(query: &PromSeriesQuery) -> PromSeriesQuery {
+    let mut query = query.clone();
+    if query.matchers.is_empty() {
+        query.matchers.push(ALL_SERIES.to_string());
+    }
+    query
+}
+
+/// The tables of prometheus matched by the metric name
+fn metric_tables(prom_ctx: &PromContext<'_>, matchers: &[&Matcher]) -> Result<Vec<MetricTable>> {
+    let PromContext {
+        coord, meta, ctx, ..
+    } = prom_ctx;
+    let database = ctx.database();
+    let split_manager = Arc::new(SplitManager::new((*coord).clone()));
+
+    match_tables(meta, database, matchers, false)?
+        .into_iter()
+        .filter(|table| {
+            table
+                .column(METRIC_SAMPLE_COLUMN_NAME)
+                .map_or(false, |col| col.column_type.is_field())
+        })
+        .map(|table| {
+            let mut tags = table
+                .columns()
+                .iter()
+                .filter(|col| col.column_type.is_tag())
+                .map(|col| col.name.clone())
+                .collect::<Vec<_>>();
+            tags.sort();
+
+            let name = table.name.clone();
+            let cluster_table = Arc::new(ClusterTable::new(
+                (*coord).clone(),
+                split_manager.clone(),
+                (*meta).clone(),
+                table,
+            ));
+            let source =
+                TableSourceAdapter::try_new(name.clone(), database, name.as_str(), cluster_table)?;
+            Ok(MetricTable {
+                name,
+                tags,
+                source: Arc::new(source),
+            })
+        })
+        .collect()
+}
+
+/// Executes the plan with the privilege to read the database
+async fn execute(
+    prom_ctx: &PromContext<'_>,
+    query: &str,
+    df_plan: LogicalPlan,
+) -> Result<Vec<RecordBatch>> {
+    let PromContext {
+        db, ctx, span_ctx, ..
+    } = prom_ctx;
+    debug!("Execute PromQL as:\n{}", df_plan.display_indent());
+
+    let query_state_machine = db
+        .build_query_state_machine(Query::new((*ctx).clone(), query.to_string()), *span_ctx)
+        .await?;
+    let session = &query_state_machine.session;
+    check_privilege(
+        session.user(),
+        vec![Privilege::TenantObject(
+            TenantObjectPrivilege::Database(
+                DatabasePrivilege::Read,
+                Some(ctx.database().to_string()),
+            ),
+            Some(*session.tenant_id()),
+        )],
+    )?;
+
+    db.execute_logical_plan(
+        logical_planner::Plan::Query(QueryPlan { df_plan }),
+        query_state_machine,
+    )
+    .await?
+    .result()
+    .chunk_result()
+    .await
+}
+
+/// Executes the plan, returns the samples of the series sorted by labels and time
+async fn read_series(
+    prom_ctx: &PromContext<'_>,
+    query: &str,
+    plan: VectorPlan,
+) -> Result<BTreeMap<Metric, Vec<PromSample>>> {
+    let batches = execute(prom_ctx, query, sort_by_time(plan.plan)?).await?;
+    to_series(&batches, &plan.labels)
+}
+
+fn sort_by_time(plan: LogicalPlan) -> Result<LogicalPlan> {
+    Ok(LogicalPlanBuilder::from(plan)
+        .sort([Expr::Column(Column::from_name("time")).sort(true, false)])?
+        .build()?)
+}
+
+/// Groups the rows `time, <labels>..., value` by the labels
+fn to_series(
+    batches: &[RecordBatch],
+    labels: &[String],
+) -> Result<BTreeMap<Metric, Vec<PromSample>>> {
+    let mut series: BTreeMap<Metric, Vec<PromSample>> = BTreeMap::new();
+    for batch in batches {
+        let time = cast(batch.column(0), &DataType::Int64)?;
+        let time = as_primitive_array::<Int64Type>(time.as_ref());
+        let value = cast(batch.column(labels.len() + 1), &DataType::Float64)?;
+        let value = as_primitive_array::<Float64Type>(value.as_ref());
+
+        for (row, metric) in row_labels(batch, labels, 1)?.into_iter().enumerate() {
+            let v = if value.is_valid(row) {
+                value.value(row)
+            } else {
+                f64::NAN
+            };
+            series
+                .entry(metric)
+                .or_default()
+                .push(PromSample::new(time.value(row), v));
+        }
+    }
+
+    Ok(series)
+}
+
+/// The labels of each row in the columns from `first`, a label of the empty value is missing
+fn row_labels(batch: &RecordBatch, labels: &[String], first: usize) -> Result<Vec<Metric>> {
+    let arrays = (first..first + labels.len())
+        .map(|i| cast(batch.column(i), &DataType::Utf8))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let arrays = arrays
+        .iter()
+        .map(|array| as_string_array(array.as_ref()))
+        .collect::<Vec<&StringArray>>();
+
+    Ok((0..batch.num_rows())
+        .map(|row| {
+            labels
+                .iter()
+                .zip(&arrays)
+                .filter(|(_, array)| array.is_valid(row) && !array.value(row).is_empty())
+                .map(|(name, array)| (name.clone(), array.value(row).to_string()))
+                .collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Float64Array, StringArray, TimestampNanosecondArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::datasource::{provider_as_source, MemTable};
+    use datafusion::physical_plan::collect;
+    use datafusion::physical_planner::{DefaultPhysicalPlanner, PhysicalPlanner};
+    use datafusion::prelude::SessionContext;
+    use spi::server::prom::PromSample;
+
+    use super::planner::{EvalRange, MetricTable, Plan, Planner};
+    use super::{parse_time, parser, sort_by_time, to_series, Metric};
+    use crate::extension::physical::transform_rule::range_vector::RangeVectorPlanner;
+    use crate::prom::matcher::Matcher;
+
+    const SECOND: i64 = 1_000_000_000;
+
+    /// The counters `{code="200", job="api"}` increasing by 1/s and `{code="500", job="api"}`
+    /// by 0.5/s every minute, and `{job="web"}` whose `code` is null or empty
+    fn requests_table() -> MetricTable {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("code", DataType::Utf8, true),
+            Field::new("job", DataType::Utf8, true),
+            Field::new("value", DataType::Float64, true),
+        ]));
+
+        let mut rows = vec![];
+        for t in (0..=300).step_by(60) {
+            rows.push((t, Some("200"), t as f64));
+            rows.push((t, Some("500"), t as f64 / 2.0));
+        }
+        let (time, code, value): (Vec<_>, Vec<_>, Vec<_>) = rows.into_iter().fold(
+            (vec![], vec![], vec![]),
+            |(mut time, mut code, mut value), (t, c, v)| {
+                time.push(t * SECOND);
+                code.push(c);
+                value.push(v);
+                (time, code, value)
+            },
+        );
+        let job = vec![Some("api"); time.len()];
+        let api = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(TimestampNanosecondArray::from(time)),
+                Arc::new(StringArray::from(code)),
+                Arc::new(StringArray::from(job)),
+                Arc::new(Float64Array::from(value)),
+            ],
+        )
+        .unwrap();
+        let web = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(TimestampNanosecondArray::from(vec![0, 60 * SECOND])),
+                Arc::new(StringArray::from(vec![None, Some("")])),
+                Arc::new(StringArray::from(vec!["web", "web"])),
+                Arc::new(Float64Array::from(vec![0.0, 6.0])),
+            ],
+        )
+        .unwrap();
+
+        let table = MemTable::try_new(schema, vec![vec![api], vec![web]]).unwrap();
+        MetricTable {
+            name: "http_requests_total".to_string(),
+            tags: vec!["code".to_string(), "job".to_string()],
+            source: provider_as_source(Arc::new(table)),
+        }
+    }
+
+    async fn evaluate(query: &str, range: EvalRange) -> Vec<(Metric, Vec<PromSample>)> {
+        let table = requests_table();
+        let tables = |matchers: &[&Matcher]| -> spi::Result<Vec<MetricTable>> {
+            Ok(matchers
+                .iter()
+                .all(|m| m.matches(&table.name))
+                .then(|| table.clone())
+                .into_iter()
+                .collect())
+        };
+        let Plan::Vector(plan) = Planner::new(range, &tables)
+            .plan(&parser::parse(query).unwrap())
+            .unwrap() else {
+            panic!("expected a vector")
+        };
+
+        let ctx = SessionContext::new();
+        let state = ctx.state();
+        let df_plan = state.optimize(&sort_by_time(plan.plan).unwrap()).unwrap();
+        let exec = DefaultPhysicalPlanner::with_extension_planners(vec![Arc::new(
+            RangeVectorPlanner::new(),
+        )])
+        .create_physical_plan(&df_plan, &state)
+        .await
+        .unwrap();
+        let batches = collect(exec, ctx.task_ctx()).await.unwrap();
+
+        to_series(&batches, &plan.labels)
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    fn metric(labels: &[(&str, &str)]) -> Metric {
+        labels
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn samples(samples: &[(i64, f64)]) -> Vec<PromSample> {
+        samples
+            .iter()
+            .map(|(t, v)| PromSample::new(t * SECOND, *v))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_evaluate() {
+        let at = |t: i64| EvalRange::instant(t * SECOND);
+        let api = |code| metric(&[("code", code), ("job", "api")]);
+
+        assert_eq!(
+            evaluate("rate(http_requests_total[2m])", at(300)).await,
+            vec![
+                (api("200"), samples(&[(300, 1.0)])),
+                (api("500"), samples(&[(300, 0.5)])),
+            ]
+        );
+        assert_eq!(
+            evaluate("sum by (job) (rate(http_requests_total[2m]))", at(300)).await,
+            vec![(metric(&[("job", "api")]), samples(&[(300, 1.5)]))]
+        );
+        // The latest sample of each evaluation
+        let range = EvalRange {
+            start: 0,
+            end: 300 * SECOND,
+            step: 60 * SECOND,
+        };
+        assert_eq!(
+            evaluate(r#"http_requests_total{code="200"}"#, range).await,
+            vec![(
+                metric(&[
+                    ("__name__", "http_requests_total"),
+                    ("code", "200"),
+                    ("job", "api")
+                ]),
+                samples(&[
+                    (0, 0.0),
+                    (60, 60.0),
+                    (120, 120.0),
+                    (180, 180.0),
+                    (240, 240.0),
+                    (300, 300.0)
+                ]),
+            )]
+        );
+        // A null and an empty label are the same series
+        assert_eq!(
+            evaluate(
+                r#"count_over_time(http_requests_total{job="web"}[5m])"#,
+                at(60)
+            )
+            .await,
+            vec![(metric(&[("job", "web")]), samples(&[(60, 2.0)]))]
+        );
+        assert_eq!(
+            evaluate(
+                r#"http_requests_total{code="500"} / ignoring(code) http_requests_total{code="200"}"#,
+                at(300)
+            )
+            .await,
+            vec![(metric(&[("job", "api")]), samples(&[(300, 0.5)]))]
+        );
+        assert_eq!(
+            evaluate(r#"http_requests_total{job="api"} > bool 200"#, at(300)).await,
+            vec![
+                (api("200"), samples(&[(300, 1.0)])),
+                (api("500"), samples(&[(300, 0.0)])),
+            ]
+        );
+        assert_eq!(
+            evaluate(
+                r#"http_requests_total unless on(code) http_requests_total{code="200"}"#,
+                at(300)
+            )
+            .await
+            .into_iter()
+            .map(|(metric, _)| metric)
+            .collect::<Vec<_>>(),
+            vec![api("500"), metric(&[("job", "web")])]
+        );
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            parse_time("1435781451.781").unwrap(),
+            1_435_781_451_781_000_000
+        );
+        assert_eq!(
+            parse_time("2015-07-01T20:10:51.781Z").unwrap(),
+            1_435_781_451_781_000_000
+        );
+        assert!(parse_time("yesterday").is_err());
+
+        let sample = PromSample::new(1_435_781_451_781_000_000, 1.5);
+        assert_eq!(
+            serde_json::to_string(&sample).unwrap(),
+            "[1435781451.781,\"1.5\"]"
+        );
+        assert_eq!(PromSample::new(0, f64::NEG_INFINITY).1, "-Inf");
+        assert_eq!(PromSample::new(0, 3.0).1, "3");
+    }
+}
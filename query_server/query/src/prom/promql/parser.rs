@@ -0,0 +1,879 @@
+//! A recursive descent parser of PromQL.
+//!
+//! Subqueries and the `@` modifier are not supported.
+
+use std::fmt;
+
+use protos::prompb::types::label_matcher::Type;
+use spi::{QueryError, Result};
+
+use super::ast::{
+    AggregateExpr, AggregateOp, BinaryExpr, BinaryOp, Cardinality, Expr, Grouping, MatchingLabels,
+    VectorMatching, VectorSelector,
+};
+use crate::prom::matcher::Matcher;
+use crate::prom::METRIC_NAME_LABEL;
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+pub fn parse(input: &str) -> Result<Expr> {
+    let tokens = Lexer::new(input).tokenize()?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_expr(0)?;
+    if let Some(token) = parser.peek() {
+        return Err(invalid(format!("unexpected {token}")));
+    }
+
+    Ok(expr)
+}
+
+/// Parses a duration like `1h30m`, or a number of seconds like `15` or `0.5`.
+pub fn parse_duration(input: &str) -> Result<i64> {
+    if let Ok(seconds) = input.parse::<f64>() {
+        let nanos = seconds * NANOS_PER_SECOND as f64;
+        if !nanos.is_finite() || nanos.abs() >= i64::MAX as f64 {
+            return Err(invalid(format!("invalid duration \"{input}\"")));
+        }
+        return Ok(nanos.round() as i64);
+    }
+
+    let mut lexer = Lexer::new(input);
+    match lexer.next_token()? {
+        Some(Token::Duration(nanos)) if lexer.next_token()?.is_none() => Ok(nanos),
+        _ => Err(invalid(format!("invalid duration \"{input}\""))),
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> QueryError {
+    QueryError::InvalidPromQuery {
+        reason: reason.into(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    /// In nanoseconds
+    Duration(i64),
+    String(String),
+    Ident(String),
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    Colon,
+    At,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Eql,
+    Neq,
+    Gtr,
+    Lss,
+    Gte,
+    Lte,
+    Assign,
+    EqlRegex,
+    NeqRegex,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "number \"{n}\""),
+            Token::Duration(d) => write!(f, "duration \"{}s\"", *d as f64 / 1e9),
+            Token::String(s) => write!(f, "string \"{s}\""),
+            Token::Ident(i) => write!(f, "identifier \"{i}\""),
+            token => {
+                let s = match token {
+                    Token::LeftParen => "(",
+                    Token::RightParen => ")",
+                    Token::LeftBrace => "{",
+                    Token::RightBrace => "}",
+                    Token::LeftBracket => "[",
+                    Token::RightBracket => "]",
+                    Token::Comma => ",",
+                    Token::Colon => ":",
+                    Token::At => "@",
+                    Token::Add => "+",
+                    Token::Sub => "-",
+                    Token::Mul => "*",
+                    Token::Div => "/",
+                    Token::Mod => "%",
+                    Token::Pow => "^",
+                    Token::Eql => "==",
+                    Token::Neq => "!=",
+                    Token::Gtr => ">",
+                    Token::Lss => "<",
+                    Token::Gte => ">=",
+                    Token::Lte => "<=",
+                    Token::Assign => "=",
+                    Token::EqlRegex => "=~",
+                    Token::NeqRegex => "!~",
+                    _ => unreachable!(),
+                };
+                write!(f, "\"{s}\"")
+            }
+        }
+    }
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Token>> {
+        let mut tokens = vec![];
+        while let Some(token) = self.next_token()? {
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn peek_nth_char(&self, n: usize) -> Option<char> {
+        self.input[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek_char() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>> {
+        loop {
+            match self.peek_char() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                // Comments to the end of the line
+                Some('#') => while !matches!(self.bump(), Some('\n') | None) {},
+                _ => break,
+            }
+        }
+
+        let Some(c) = self.peek_char() else {
+            return Ok(None);
+        };
+        if c.is_ascii_digit()
+            || (c == '.' && self.peek_nth_char(1).map_or(false, |c| c.is_ascii_digit()))
+        {
+            return self.number_or_duration().map(Some);
+        }
+        if c.is_ascii_alphabetic() || c == '_' {
+            let start = self.pos;
+            while matches!(self.peek_char(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == ':')
+            {
+                self.bump();
+            }
+            return Ok(Some(Token::Ident(self.input[start..self.pos].to_string())));
+        }
+        if matches!(c, '"' | '\'' | '`') {
+            return self.string().map(Some);
+        }
+
+        self.bump();
+        let token = match c {
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '{' => Token::LeftBrace,
+            '}' => Token::RightBrace,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '@' => Token::At,
+            '+' => Token::Add,
+            '-' => Token::Sub,
+            '*' => Token::Mul,
+            '/' => Token::Div,
+            '%' => Token::Mod,
+            '^' => Token::Pow,
+            '=' if self.eat('=') => Token::Eql,
+            '=' if self.eat('~') => Token::EqlRegex,
+            '=' => Token::Assign,
+            '!' if self.eat('=') => Token::Neq,
+            '!' if self.eat('~') => Token::NeqRegex,
+            '>' if self.eat('=') => Token::Gte,
+            '>' => Token::Gtr,
+            '<' if self.eat('=') => Token::Lte,
+            '<' => Token::Lss,
+            c => return Err(invalid(format!("unexpected character \"{c}\""))),
+        };
+        Ok(Some(token))
+    }
+
+    fn number_or_duration(&mut self) -> Result<Token> {
+        let start = self.pos;
+        if self.input[start..].starts_with("0x") || self.input[start..].starts_with("0X") {
+            self.pos += 2;
+            let digits_start = self.pos;
+            while matches!(self.peek_char(), Some(c) if c.is_ascii_hexdigit()) {
+                self.bump();
+            }
+            return i64::from_str_radix(&self.input[digits_start..self.pos], 16)
+                .map(|n| Token::Number(n as f64))
+                .map_err(|_| invalid(format!("bad number \"{}\"", &self.input[start..self.pos])));
+        }
+
+        self.digits();
+        if matches!(self.peek_char(), Some(c) if duration_unit(c)) {
+            self.pos = start;
+            return self.duration();
+        }
+        if self.eat('.') {
+            self.digits();
+        }
+        if matches!(self.peek_char(), Some('e' | 'E')) {
+            let exponent = self.pos;
+            self.bump();
+            if !self.eat('+') {
+                self.eat('-');
+            }
+            if !matches!(self.peek_char(), Some(c) if c.is_ascii_digit()) {
+                // Not an exponent
+                self.pos = exponent;
+            }
+            self.digits();
+        }
+
+        let literal = &self.input[start..self.pos];
+        if matches!(self.peek_char(), Some(c) if c.is_ascii_alphanumeric() || c == '_') {
+            return Err(invalid(format!("bad number or duration \"{literal}\"")));
+        }
+        literal
+            .parse::<f64>()
+            .map(Token::Number)
+            .map_err(|_| invalid(format!("bad number \"{literal}\"")))
+    }
+
+    fn digits(&mut self) {
+        while matches!(self.peek_char(), Some(c) if c.is_ascii_digit()) {
+            self.bump();
+        }
+    }
+
+    /// `(<digits><unit>)+`, the units are `ms`, `s`, `m`, `h`, `d`, `w` and `y`
+    fn duration(&mut self) -> Result<Token> {
+        let start = self.pos;
+        let mut nanos: i64 = 0;
+        while matches!(self.peek_char(), Some(c) if c.is_ascii_digit()) {
+            let digits_start = self.pos;
+            self.digits();
+            let number = self.input[digits_start..self.pos].parse::<i64>().ok();
+            let unit = if self.input[self.pos..].starts_with("ms") {
+                self.pos += 2;
+                1_000_000
+            } else {
+                match self.bump() {
+                    Some('s') => NANOS_PER_SECOND,
+                    Some('m') => 60 * NANOS_PER_SECOND,
+                    Some('h') => 3_600 * NANOS_PER_SECOND,
+                    Some('d') => 86_400 * NANOS_PER_SECOND,
+                    Some('w') => 7 * 86_400 * NANOS_PER_SECOND,
+                    Some('y') => 365 * 86_400 * NANOS_PER_SECOND,
+                    _ => {
+                        return Err(invalid(format!(
+                            "bad duration \"{}\"",
+                            &self.input[start..self.pos]
+                        )))
+                    }
+                }
+            };
+            nanos = number
+                .and_then(|n| n.checked_mul(unit))
+                .and_then(|n| n.checked_add(nanos))
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "duration \"{}\" is too large",
+                        &self.input[start..self.pos]
+                    ))
+                })?;
+        }
+
+        if matches!(self.peek_char(), Some(c) if c.is_ascii_alphanumeric() || c == '_') {
+            return Err(invalid(format!(
+                "bad duration \"{}\"",
+                &self.input[start..=self.pos]
+            )));
+        }
+        Ok(Token::Duration(nanos))
+    }
+
+    fn string(&mut self) -> Result<Token> {
+        let quote = self.bump().expect("checked by the caller");
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(invalid("unterminated quoted string")),
+                Some(c) if c == quote => return Ok(Token::String(s)),
+                // Raw strings have no escapes
+                Some('\\') if quote != '`' => {
+                    let escaped = match self.bump() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('a') => '\u{07}',
+                        Some('b') => '\u{08}',
+                        Some('f') => '\u{0c}',
+                        Some('v') => '\u{0b}',
+                        Some('\\') => '\\',
+                        Some(c) if c == quote => c,
+                        Some(c) => {
+                            return Err(invalid(format!("unknown escape sequence \"\\{c}\"")))
+                        }
+                        None => return Err(invalid("unterminated quoted string")),
+                    };
+                    s.push(escaped);
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+}
+
+fn duration_unit(c: char) -> bool {
+    matches!(c, 's' | 'm' | 'h' | 'd' | 'w' | 'y')
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| invalid("unexpected end of input"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(invalid(format!("unexpected {token}, expected {expected}"))),
+        }
+    }
+
+    /// Keywords are case insensitive
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(i)) if i.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn peek_binary_op(&self) -> Option<BinaryOp> {
+        Some(match self.peek()? {
+            Token::Add => BinaryOp::Add,
+            Token::Sub => BinaryOp::Sub,
+            Token::Mul => BinaryOp::Mul,
+            Token::Div => BinaryOp::Div,
+            Token::Mod => BinaryOp::Mod,
+            Token::Pow => BinaryOp::Pow,
+            Token::Eql => BinaryOp::Eql,
+            Token::Neq => BinaryOp::Neq,
+            Token::Gtr => BinaryOp::Gtr,
+            Token::Lss => BinaryOp::Lss,
+            Token::Gte => BinaryOp::Gte,
+            Token::Lte => BinaryOp::Lte,
+            Token::Ident(i) => match i.to_ascii_lowercase().as_str() {
+                "and" => BinaryOp::And,
+                "or" => BinaryOp::Or,
+                "unless" => BinaryOp::Unless,
+                "atan2" => BinaryOp::Atan2,
+                _ => return None,
+            },
+            _ => return None,
+        })
+    }
+
+    fn parse_expr(&mut self, min_precedence: u8) -> Result<Expr> {
+        let mut lhs = self.parse_unary()?;
+        while let Some(op) = self.peek_binary_op() {
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            self.pos += 1;
+
+            let return_bool = self.eat_keyword("bool");
+            if return_bool && !op.is_comparison() {
+                return Err(invalid(
+                    "bool modifier can only be used on comparison operators",
+                ));
+            }
+            let matching = self.parse_vector_matching(op)?;
+
+            let rhs = if op.is_right_associative() {
+                self.parse_expr(precedence)?
+            } else {
+                self.parse_expr(precedence + 1)?
+            };
+            lhs = Expr::Binary(BinaryExpr {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                return_bool,
+                matching,
+            });
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_vector_matching(&mut self, op: BinaryOp) -> Result<VectorMatching> {
+        let labels = if self.eat_keyword("on") {
+            MatchingLabels::On(self.parse_labels()?)
+        } else if self.eat_keyword("ignoring") {
+            MatchingLabels::Ignoring(self.parse_labels()?)
+        } else {
+            return Ok(VectorMatching::default());
+        };
+
+        let card = if self.peek_keyword("group_left") || self.peek_keyword("group_right") {
+            if op.is_set_operator() {
+                return Err(invalid(format!(
+                    "no grouping allowed for \"{op:?}\" operation"
+                )));
+            }
+            let left = self.eat_keyword("group_left");
+            if !left {
+                self.eat_keyword("group_right");
+            }
+            let include = match self.peek() {
+                Some(Token::LeftParen) => self.parse_labels()?,
+                _ => vec![],
+            };
+            if left {
+                Cardinality::ManyToOne(include)
+            } else {
+                Cardinality::OneToMany(include)
+            }
+        } else {
+            Cardinality::OneToOne
+        };
+
+        Ok(VectorMatching {
+            labels: Some(labels),
+            card,
+        })
+    }
+
+    /// The operand of `-` binds tighter than the binary operators except `^`
+    fn parse_unary(&mut self) -> Result<Expr> {
+        match self.peek() {
+            Some(Token::Sub) => {
+                self.pos += 1;
+                match self.parse_expr(BinaryOp::Pow.precedence())? {
+                    Expr::NumberLiteral(n) => Ok(Expr::NumberLiteral(-n)),
+                    expr => Ok(Expr::Negative(Box::new(expr))),
+                }
+            }
+            Some(Token::Add) => {
+                self.pos += 1;
+                self.parse_expr(BinaryOp::Pow.precedence())
+            }
+            _ => {
+                let expr = self.parse_primary()?;
+                self.parse_postfix(expr)
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        match self.next()? {
+            Token::Number(n) => Ok(Expr::NumberLiteral(n)),
+            Token::String(s) => Ok(Expr::StringLiteral(s)),
+            Token::LeftParen => {
+                let expr = self.parse_expr(0)?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            Token::LeftBrace => {
+                let matchers = self.parse_matchers()?;
+                vector_selector(None, matchers)
+            }
+            Token::Ident(name) => {
+                if name.eq_ignore_ascii_case("inf") {
+                    return Ok(Expr::NumberLiteral(f64::INFINITY));
+                }
+                if name.eq_ignore_ascii_case("nan") {
+                    return Ok(Expr::NumberLiteral(f64::NAN));
+                }
+                if let Some(op) = AggregateOp::from_name(&name.to_ascii_lowercase()) {
+                    if matches!(self.peek(), Some(Token::LeftParen))
+                        || self.peek_keyword("by")
+                        || self.peek_keyword("without")
+                    {
+                        return self.parse_aggregate(op);
+                    }
+                }
+                if matches!(self.peek(), Some(Token::LeftParen)) {
+                    self.pos += 1;
+                    let args = self.parse_args()?;
+                    return Ok(Expr::Call { func: name, args });
+                }
+
+                let matchers = match self.peek() {
+                    Some(Token::LeftBrace) => {
+                        self.pos += 1;
+                        self.parse_matchers()?
+                    }
+                    _ => vec![],
+                };
+                vector_selector(Some(name), matchers)
+            }
+            token => Err(invalid(format!("unexpected {token}"))),
+        }
+    }
+
+    fn parse_postfix(&mut self, mut expr: Expr) -> Result<Expr> {
+        loop {
+            match self.peek() {
+                Some(Token::LeftBracket) => {
+                    self.pos += 1;
+                    let range = self.parse_duration()?;
+                    if matches!(self.peek(), Some(Token::Colon)) {
+                        return Err(invalid("subqueries are not supported"));
+                    }
+                    self.expect(Token::RightBracket)?;
+                    if range <= 0 {
+                        return Err(invalid("range must be greater than zero"));
+                    }
+                    expr = match expr {
+                        Expr::VectorSelector(selector) if selector.offset == 0 => {
+                            Expr::MatrixSelector { selector, range }
+                        }
+                        _ => return Err(invalid("ranges only allowed for vector selectors")),
+                    };
+                }
+                Some(Token::Ident(i)) if i.eq_ignore_ascii_case("offset") => {
+                    self.pos += 1;
+                    let negative = matches!(self.peek(), Some(Token::Sub));
+                    if negative {
+                        self.pos += 1;
+                    }
+                    let offset = self.parse_duration()?;
+                    let offset = if negative { -offset } else { offset };
+                    match &mut expr {
+                        Expr::VectorSelector(selector)
+                        | Expr::MatrixSelector { selector, .. }
+                            if selector.offset == 0 =>
+                        {
+                            selector.offset = offset
+                        }
+                        _ => {
+                            return Err(invalid(
+                                "offset modifier must be preceded by an instant vector selector or range vector selector",
+                            ))
+                        }
+                    }
+                }
+                Some(Token::At) => return Err(invalid("@ modifier is not supported")),
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn parse_duration(&mut self) -> Result<i64> {
+        match self.next()? {
+            Token::Duration(d) => Ok(d),
+            token => Err(invalid(format!("unexpected {token}, expected duration"))),
+        }
+    }
+
+    fn parse_aggregate(&mut self, op: AggregateOp) -> Result<Expr> {
+        let mut grouping = self.parse_grouping()?;
+
+        self.expect(Token::LeftParen)?;
+        let mut args = self.parse_args()?;
+        let expected = if op.has_param() { 2 } else { 1 };
+        if args.len() != expected {
+            return Err(invalid(format!(
+                "wrong number of arguments for aggregate expression provided, expected {expected}, got {}",
+                args.len()
+            )));
+        }
+        let expr = Box::new(args.pop().expect("checked above"));
+        let param = args.pop().map(Box::new);
+
+        if grouping.is_none() {
+            grouping = self.parse_grouping()?;
+        }
+
+        Ok(Expr::Aggregate(AggregateExpr {
+            op,
+            grouping,
+            param,
+            expr,
+        }))
+    }
+
+    fn parse_grouping(&mut self) -> Result<Option<Grouping>> {
+        if self.eat_keyword("by") {
+            Ok(Some(Grouping::By(self.parse_labels()?)))
+        } else if self.eat_keyword("without") {
+            Ok(Some(Grouping::Without(self.parse_labels()?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The arguments after `(`
+    fn parse_args(&mut self) -> Result<Vec<Expr>> {
+        let mut args = vec![];
+        if matches!(self.peek(), Some(Token::RightParen)) {
+            self.pos += 1;
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_expr(0)?);
+            match self.next()? {
+                Token::Comma => {}
+                Token::RightParen => return Ok(args),
+                token => return Err(invalid(format!("unexpected {token} in arguments"))),
+            }
+        }
+    }
+
+    /// `(<label>, ...)`
+    fn parse_labels(&mut self) -> Result<Vec<String>> {
+        self.expect(Token::LeftParen)?;
+        let mut labels = vec![];
+        loop {
+            match self.next()? {
+                Token::RightParen => return Ok(labels),
+                Token::Ident(label) => labels.push(label),
+                token => return Err(invalid(format!("unexpected {token} in grouping"))),
+            }
+            match self.next()? {
+                Token::Comma => {}
+                Token::RightParen => return Ok(labels),
+                token => return Err(invalid(format!("unexpected {token} in grouping"))),
+            }
+        }
+    }
+
+    /// The label matchers after `{`
+    fn parse_matchers(&mut self) -> Result<Vec<Matcher>> {
+        let mut matchers = vec![];
+        loop {
+            let name = match self.next()? {
+                Token::RightBrace => return Ok(matchers),
+                Token::Ident(name) => name,
+                token => return Err(invalid(format!("unexpected {token} in label matching"))),
+            };
+            let type_ = match self.next()? {
+                Token::Assign => Type::EQ,
+                Token::Neq => Type::NEQ,
+                Token::EqlRegex => Type::RE,
+                Token::NeqRegex => Type::NRE,
+                token => {
+                    return Err(invalid(format!(
+                        "unexpected {token} in label matching, expected label matching operator"
+                    )))
+                }
+            };
+            let value = match self.next()? {
+                Token::String(value) => value,
+                token => {
+                    return Err(invalid(format!(
+                        "unexpected {token} in label matching, expected string"
+                    )))
+                }
+            };
+            matchers.push(
+                Matcher::try_new(name, type_, value)
+                    .map_err(|e| invalid(format!("invalid regular expression: {e}")))?,
+            );
+
+            match self.next()? {
+                Token::Comma => {}
+                Token::RightBrace => return Ok(matchers),
+                token => return Err(invalid(format!("unexpected {token} in label matching"))),
+            }
+        }
+    }
+}
+
+fn vector_selector(name: Option<String>, mut matchers: Vec<Matcher>) -> Result<Expr> {
+    if let Some(name) = &name {
+        if matchers.iter().any(|m| m.name == METRIC_NAME_LABEL) {
+            return Err(invalid(format!(
+                "metric name must not be set twice: \"{name}\""
+            )));
+        }
+        matchers.push(
+            Matcher::try_new(METRIC_NAME_LABEL, Type::EQ, name.clone())
+                .expect("not a regular expression"),
+        );
+    } else if matchers.iter().all(|m| m.matches("")) {
+        return Err(invalid(
+            "vector selector must contain at least one non-empty matcher",
+        ));
+    }
+
+    Ok(Expr::VectorSelector(VectorSelector {
+        name,
+        matchers,
+        offset: 0,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::ast::{AggregateOp, BinaryOp, Cardinality, Expr, Grouping, MatchingLabels};
+    use super::{parse, parse_duration};
+
+    #[test]
+    fn test_parse_selector() {
+        let Expr::MatrixSelector { selector, range } =
+            parse(r#"http_requests_total{job="api", code=~"5.."}[1h30m] offset 5m"#).unwrap()
+        else {
+            panic!("expected a matrix selector");
+        };
+        assert_eq!(range, 5_400_000_000_000);
+        assert_eq!(selector.offset, 300_000_000_000);
+        assert_eq!(selector.name.as_deref(), Some("http_requests_total"));
+        let matchers = selector
+            .matchers
+            .iter()
+            .map(|m| (m.name.as_str(), m.value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matchers,
+            vec![
+                ("job", "api"),
+                ("code", "5.."),
+                ("__name__", "http_requests_total")
+            ]
+        );
+
+        assert!(parse(r#"{job=""}"#).is_err());
+        assert!(parse(r#"foo{__name__="bar"}"#).is_err());
+        assert!(parse("rate(foo[5m:1m])").is_err());
+        assert!(parse("foo @ 1609746000").is_err());
+        assert!(parse("foo[5m] offset 1m").is_ok());
+        assert!(parse("(foo + bar)[5m]").is_err());
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        // -(2 ^ 2) * 3 + 1 > bool 0
+        let Expr::Binary(cmp) = parse("-2 ^ 2 * 3 + 1 > bool 0").unwrap() else {
+            panic!("expected a comparison");
+        };
+        assert_eq!(cmp.op, BinaryOp::Gtr);
+        assert!(cmp.return_bool);
+        let Expr::Binary(add) = *cmp.lhs else {
+            panic!("expected an addition");
+        };
+        let Expr::Binary(mul) = *add.lhs else {
+            panic!("expected a multiplication");
+        };
+        assert!(matches!(*mul.lhs, Expr::Negative(_)));
+
+        // 2 ^ (3 ^ 2)
+        let Expr::Binary(pow) = parse("2 ^ 3 ^ 2").unwrap() else {
+            panic!("expected a power");
+        };
+        assert!(matches!(*pow.lhs, Expr::NumberLiteral(n) if n == 2.0));
+
+        assert!(parse("foo + bool bar").is_err());
+    }
+
+    #[test]
+    fn test_parse_aggregate_and_matching() {
+        let Expr::Aggregate(agg) =
+            parse("sum without (instance) (rate(foo[5m]))").unwrap()
+        else {
+            panic!("expected an aggregation");
+        };
+        assert_eq!(agg.op, AggregateOp::Sum);
+        assert_eq!(
+            agg.grouping,
+            Some(Grouping::Without(vec!["instance".into()]))
+        );
+        assert!(matches!(*agg.expr, Expr::Call { ref func, .. } if func == "rate"));
+
+        let Expr::Aggregate(agg) = parse("topk(3, foo) by (job)").unwrap() else {
+            panic!("expected an aggregation");
+        };
+        assert!(agg.param.is_some());
+        assert_eq!(agg.grouping, Some(Grouping::By(vec!["job".into()])));
+
+        let Expr::Binary(div) =
+            parse("foo / on(job, instance) group_left(name) bar").unwrap()
+        else {
+            panic!("expected a division");
+        };
+        assert_eq!(
+            div.matching.labels,
+            Some(MatchingLabels::On(vec!["job".into(), "instance".into()]))
+        );
+        assert_eq!(
+            div.matching.card,
+            Cardinality::ManyToOne(vec!["name".into()])
+        );
+
+        assert!(parse("foo and on(job) group_left bar").is_err());
+        // An aggregation operator can name a metric
+        assert!(matches!(parse("count").unwrap(), Expr::VectorSelector(_)));
+    }
+
+    #[test]
+    fn test_parse_literals() {
+        assert!(matches!(parse("0x1f").unwrap(), Expr::NumberLiteral(n) if n == 31.0));
+        assert!(matches!(parse("1.5e3").unwrap(), Expr::NumberLiteral(n) if n == 1500.0));
+        assert!(matches!(parse("-Inf").unwrap(), Expr::NumberLiteral(n) if n == f64::NEG_INFINITY));
+        assert!(matches!(parse(r#"'a\'b'"#).unwrap(), Expr::StringLiteral(s) if s == "a'b"));
+        assert!(matches!(parse(r#"`a\b`"#).unwrap(), Expr::StringLiteral(s) if s == "a\\b"));
+        assert!(parse("1 # comment").is_ok());
+        assert!(parse("5min").is_err());
+
+        assert_eq!(parse_duration("15").unwrap(), 15_000_000_000);
+        assert_eq!(parse_duration("0.5").unwrap(), 500_000_000);
+        assert_eq!(parse_duration("1m30s").unwrap(), 90_000_000_000);
+        assert_eq!(parse_duration("100ms").unwrap(), 100_000_000);
+        assert!(parse_duration("1x").is_err());
+    }
+}
@@ -0,0 +1,1012 @@
+//! Lowers PromQL into logical plans over the tables written by prometheus remote write.
+//!
+//! A metric is a table, its labels are the tags and the samples are the field `value`.
+//! An instant vector is a relation of `time, <labels>..., value`, the time is the evaluation
+//! timestamp in nanoseconds and a missing label is null.
+//!
+//! The samples in the window `(t - range, t]` of every evaluation timestamp `t` are evaluated
+//! by a [`RangeVectorNode`], which slides the windows over the samples of each series.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::common::{Column, ScalarValue};
+use datafusion::logical_expr::expr::AggregateFunction;
+use datafusion::logical_expr::{
+    aggregate_function, binary_expr, Extension, JoinType, LogicalPlan, LogicalPlanBuilder,
+    Operator, SubqueryAlias, TableSource,
+};
+use datafusion::prelude::{
+    abs, atan2, cast, ceil, coalesce, exp, floor, lit, ln, log10, log2, power, sqrt, Expr,
+};
+use models::schema::TIME_FIELD_NAME;
+use spi::{QueryError, Result};
+
+use super::ast::{
+    AggregateExpr, AggregateOp, BinaryExpr, BinaryOp, Cardinality, Expr as PromExpr, Grouping,
+    MatchingLabels, VectorMatching, VectorSelector,
+};
+use crate::extension::logical::plan_node::range_vector::{RangeFunction, RangeVectorNode};
+use crate::prom::matcher::Matcher;
+use crate::prom::{METRIC_NAME_LABEL, METRIC_SAMPLE_COLUMN_NAME};
+
+/// How far back the latest sample of a series is looked up
+pub const LOOKBACK_DELTA: i64 = 5 * 60 * 1_000_000_000;
+
+/// The step of instant queries, longer than the ranges, so a window has a single timestamp
+const INSTANT_STEP: i64 = 366 * 86_400 * 1_000_000_000;
+
+/// The columns of a vector
+const TIME: &str = "time";
+const VALUE: &str = "value";
+
+/// The aliases of the sides of a join
+const LEFT: &str = "l";
+const RIGHT: &str = "r";
+
+/// The evaluation timestamps `start, start + step, ..., end` in nanoseconds
+#[derive(Debug, Clone, Copy)]
+pub struct EvalRange {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+}
+
+impl EvalRange {
+    pub fn instant(time: i64) -> Self {
+        Self {
+            start: time,
+            end: time,
+            step: INSTANT_STEP,
+        }
+    }
+}
+
+/// The table of a metric, with the tags sorted by name
+#[derive(Clone)]
+pub struct MetricTable {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub source: Arc<dyn TableSource>,
+}
+
+#[derive(Debug)]
+pub enum Plan {
+    Scalar(f64),
+    Vector(VectorPlan),
+    /// The samples of a range vector selector, whose time is the time of the samples
+    Matrix(VectorPlan),
+}
+
+/// A plan of the columns `time, <labels>..., value`
+#[derive(Debug, Clone)]
+pub struct VectorPlan {
+    pub plan: LogicalPlan,
+    /// Sorted by name
+    pub labels: Vec<String>,
+}
+
+impl VectorPlan {
+    fn empty() -> Result<Self> {
+        let plan = LogicalPlanBuilder::empty(false)
+            .project(vec![lit(0_i64).alias(TIME), lit(0_f64).alias(VALUE)])?
+            .build()?;
+        Ok(Self {
+            plan,
+            labels: vec![],
+        })
+    }
+
+    /// Maps the value and keeps the labels, filtering the rows by `filter`
+    fn select(&self, labels: Vec<String>, value: Expr, filter: Option<Expr>) -> Result<Self> {
+        let mut builder = LogicalPlanBuilder::from(self.plan.clone());
+        if let Some(filter) = filter {
+            builder = builder.filter(filter)?;
+        }
+        let plan = builder
+            .project(
+                [column(TIME)]
+                    .into_iter()
+                    .chain(labels.iter().map(|label| column(label)))
+                    .chain([value.alias(VALUE)]),
+            )?
+            .build()?;
+        Ok(Self { plan, labels })
+    }
+
+    fn labels_without_name(&self) -> Vec<String> {
+        without_name(&self.labels)
+    }
+}
+
+/// A plan of the distinct labels `<labels>...` of the selected series
+#[derive(Debug, Clone)]
+pub struct SeriesPlan {
+    pub plan: LogicalPlan,
+    /// Sorted by name
+    pub labels: Vec<String>,
+}
+
+/// Resolves the tables of a metric by the matchers of `__name__`
+pub type MetricTables<'a> = dyn Fn(&[&Matcher]) -> Result<Vec<MetricTable>> + 'a;
+
+pub struct Planner<'a> {
+    range: EvalRange,
+    tables: &'a MetricTables<'a>,
+}
+
+impl<'a> Planner<'a> {
+    pub fn new(range: EvalRange, tables: &'a MetricTables<'a>) -> Self {
+        Self { range, tables }
+    }
+
+    pub fn plan(&self, expr: &PromExpr) -> Result<Plan> {
+        match expr {
+            PromExpr::NumberLiteral(n) => Ok(Plan::Scalar(*n)),
+            PromExpr::StringLiteral(_) => Err(invalid("string values are not supported")),
+            PromExpr::VectorSelector(selector) => Ok(Plan::Vector(
+                match self.range_vector(selector, LOOKBACK_DELTA, RangeFunction::Latest)? {
+                    Some(vector) => vector,
+                    None => VectorPlan::empty()?,
+                },
+            )),
+            PromExpr::MatrixSelector { selector, range } => {
+                Ok(Plan::Matrix(match self.samples(selector, *range)? {
+                    Some(samples) => samples,
+                    None => VectorPlan::empty()?,
+                }))
+            }
+            PromExpr::Aggregate(aggregate) => self.aggregate(aggregate).map(Plan::Vector),
+            PromExpr::Call { func, args } => self.call(func, args).map(Plan::Vector),
+            PromExpr::Negative(expr) => match self.plan(expr)? {
+                Plan::Scalar(n) => Ok(Plan::Scalar(-n)),
+                Plan::Vector(v) => Ok(Plan::Vector(v.select(
+                    v.labels_without_name(),
+                    Expr::Negative(Box::new(column(VALUE))),
+                    None,
+                )?)),
+                Plan::Matrix(_) => Err(invalid(
+                    "unary expression only allowed on expressions of type scalar or instant vector",
+                )),
+            },
+            PromExpr::Binary(binary) => self.binary(binary),
+        }
+    }
+
+    /// The distinct labels of the series of the selector with samples in `[start, end]`,
+    /// which are unbounded if missing
+    pub fn series(
+        &self,
+        selector: &VectorSelector,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> Result<Option<SeriesPlan>> {
+        let Some((selected, labels)) = self.select_tables(selector)? else {
+            return Ok(None);
+        };
+
+        let plans = selected
+            .into_iter()
+            .map(|(table, filters)| {
+                scan(&table, filters, start.map(|t| t - 1), end)?
+                    .project(label_columns(&table, &labels))?
+                    .build()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let plan = union_all(plans)?.distinct()?.build()?;
+
+        Ok(Some(SeriesPlan { plan, labels }))
+    }
+
+    fn vector(&self, expr: &PromExpr, context: &str) -> Result<VectorPlan> {
+        match self.plan(expr)? {
+            Plan::Vector(v) => Ok(v),
+            Plan::Scalar(_) => Err(invalid(format!(
+                "expected type instant vector in {context}, got scalar"
+            ))),
+            Plan::Matrix(_) => Err(invalid(format!(
+                "expected type instant vector in {context}, got range vector"
+            ))),
+        }
+    }
+
+    /// The tables of the selector, with the filters of the labels of each table,
+    /// and the labels of all of them sorted by name
+    #[allow(clippy::type_complexity)]
+    fn select_tables(
+        &self,
+        selector: &VectorSelector,
+    ) -> Result<Option<(Vec<(MetricTable, Vec<Expr>)>, Vec<String>)>> {
+        let (name_matchers, label_matchers): (Vec<_>, Vec<_>) = selector
+            .matchers
+            .iter()
+            .partition(|m| m.name == METRIC_NAME_LABEL);
+
+        let mut selected = vec![];
+        'tables: for table in (self.tables)(&name_matchers)? {
+            let mut filters = vec![];
+            for m in &label_matchers {
+                if table.tags.contains(&m.name) {
+                    filters.push(m.to_expr());
+                } else if !m.matches("") {
+                    // The label is missing in all series of the table
+                    continue 'tables;
+                }
+            }
+            selected.push((table, filters));
+        }
+        if selected.is_empty() {
+            return Ok(None);
+        }
+
+        let labels = selected
+            .iter()
+            .flat_map(|(table, _)| table.tags.iter().cloned())
+            .chain([METRIC_NAME_LABEL.to_string()])
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        Ok(Some((selected, labels)))
+    }
+
+    /// The samples of the selector in the windows of the evaluations
+    fn samples(&self, selector: &VectorSelector, range: i64) -> Result<Option<VectorPlan>> {
+        let Some((selected, labels)) = self.select_tables(selector)? else {
+            return Ok(None);
+        };
+
+        let offset = selector.offset;
+        let plans = selected
+            .into_iter()
+            .map(|(table, filters)| {
+                let start = self.range.start - range - offset;
+                let end = self.range.end - offset;
+                let time = cast(
+                    cast(
+                        column(TIME_FIELD_NAME),
+                        DataType::Timestamp(TimeUnit::Nanosecond, None),
+                    ),
+                    DataType::Int64,
+                );
+                let value = cast(column(METRIC_SAMPLE_COLUMN_NAME), DataType::Float64);
+
+                scan(&table, filters, Some(start), Some(end))?
+                    .project(
+                        [time.alias(TIME)]
+                            .into_iter()
+                            .chain(label_columns(&table, &labels))
+                            .chain([value.alias(VALUE)]),
+                    )?
+                    .build()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Some(VectorPlan {
+            plan: union_all(plans)?.build()?,
+            labels,
+        }))
+    }
+
+    /// Evaluates the function of the samples in the window `(t - range, t]`
+    /// of each evaluation timestamp
+    fn range_vector(
+        &self,
+        selector: &VectorSelector,
+        range: i64,
+        func: RangeFunction,
+    ) -> Result<Option<VectorPlan>> {
+        let Some(samples) = self.samples(selector, range)? else {
+            return Ok(None);
+        };
+
+        let EvalRange { start, end, step } = self.range;
+        let node = RangeVectorNode::try_new(
+            Arc::new(samples.plan),
+            func,
+            samples.labels.clone(),
+            start,
+            end,
+            step,
+            range,
+            selector.offset,
+        )?;
+
+        Ok(Some(VectorPlan {
+            plan: LogicalPlan::Extension(Extension {
+                node: Arc::new(node),
+            }),
+            labels: samples.labels,
+        }))
+    }
+
+    fn call(&self, func: &str, args: &[PromExpr]) -> Result<VectorPlan> {
+        if let Some(range_func) = RangeFunction::from_name(func) {
+            let (selector, range) = range_arg(func, args)?;
+            let Some(windows) = self.range_vector(selector, range, range_func)? else {
+                return VectorPlan::empty();
+            };
+            // Only `last_over_time` keeps the metric name
+            return match range_func {
+                RangeFunction::LastOverTime => Ok(windows),
+                _ => windows.select(windows.labels_without_name(), column(VALUE), None),
+            };
+        }
+
+        match func {
+            "abs" | "ceil" | "floor" | "exp" | "ln" | "log2" | "log10" | "sqrt" | "round" => {
+                let [arg] = args else {
+                    return Err(invalid(format!(
+                        "expected 1 argument in call to \"{func}\", got {}",
+                        args.len()
+                    )));
+                };
+                let input = self.vector(arg, &format!("call to function \"{func}\""))?;
+                let value = column(VALUE);
+                let value = match func {
+                    "abs" => abs(value),
+                    "ceil" => ceil(value),
+                    "floor" => floor(value),
+                    "exp" => exp(value),
+                    "ln" => ln(value),
+                    "log2" => log2(value),
+                    "log10" => log10(value),
+                    "sqrt" => sqrt(value),
+                    // Rounds half up like prometheus
+                    _ => floor(value + lit(0.5_f64)),
+                };
+                input.select(input.labels_without_name(), value, None)
+            }
+            _ => Err(invalid(format!("function \"{func}\" is not supported"))),
+        }
+    }
+
+    fn aggregate(&self, aggregate: &AggregateExpr) -> Result<VectorPlan> {
+        let AggregateExpr {
+            op,
+            grouping,
+            param: _,
+            expr,
+        } = aggregate;
+        // The aggregate function of the values, and the value of the result
+        let (fun, value) = match op {
+            AggregateOp::Sum => (aggregate_function::AggregateFunction::Sum, column(VALUE)),
+            AggregateOp::Avg => (aggregate_function::AggregateFunction::Avg, column(VALUE)),
+            AggregateOp::Min => (aggregate_function::AggregateFunction::Min, column(VALUE)),
+            AggregateOp::Max => (aggregate_function::AggregateFunction::Max, column(VALUE)),
+            AggregateOp::Count => (
+                aggregate_function::AggregateFunction::Count,
+                cast(column(VALUE), DataType::Float64),
+            ),
+            AggregateOp::Stddev => (
+                aggregate_function::AggregateFunction::StddevPop,
+                column(VALUE),
+            ),
+            AggregateOp::Stdvar => (
+                aggregate_function::AggregateFunction::VariancePop,
+                column(VALUE),
+            ),
+            AggregateOp::Group => (aggregate_function::AggregateFunction::Count, lit(1_f64)),
+            op => {
+                return Err(invalid(format!(
+                    "aggregation operator {op:?} is not supported"
+                )))
+            }
+        };
+        let input = self.vector(expr, "aggregation")?;
+
+        let labels = match grouping {
+            None => vec![],
+            Some(Grouping::By(by)) => input
+                .labels
+                .iter()
+                .filter(|l| by.contains(l))
+                .cloned()
+                .collect(),
+            Some(Grouping::Without(without)) => input
+                .labels_without_name()
+                .into_iter()
+                .filter(|l| !without.contains(l))
+                .collect(),
+        };
+
+        let aggregated = LogicalPlanBuilder::from(input.plan)
+            .aggregate(
+                [column(TIME)]
+                    .into_iter()
+                    .chain(labels.iter().map(|label| column(label))),
+                [Expr::AggregateFunction(AggregateFunction::new(
+                    fun,
+                    vec![column(VALUE)],
+                    false,
+                    None,
+                    None,
+                ))
+                .alias(VALUE)],
+            )?
+            .build()?;
+        VectorPlan {
+            plan: aggregated,
+            labels: labels.clone(),
+        }
+        .select(labels, value, None)
+    }
+
+    fn binary(&self, binary: &BinaryExpr) -> Result<Plan> {
+        let BinaryExpr {
+            op,
+            lhs,
+            rhs,
+            return_bool,
+            matching,
+        } = binary;
+        let (op, return_bool) = (*op, *return_bool);
+
+        match (self.plan(lhs)?, self.plan(rhs)?) {
+            (Plan::Scalar(l), Plan::Scalar(r)) => {
+                if op.is_set_operator() {
+                    return Err(invalid(format!(
+                        "set operator {op:?} not allowed in binary scalar expression"
+                    )));
+                }
+                if op.is_comparison() && !return_bool {
+                    return Err(invalid(
+                        "comparisons between scalars must use BOOL modifier",
+                    ));
+                }
+                Ok(Plan::Scalar(scalar_binary(op, l, r)))
+            }
+            (Plan::Vector(v), Plan::Scalar(s)) => {
+                vector_scalar(op, return_bool, &v, column(VALUE), lit(s))
+            }
+            (Plan::Scalar(s), Plan::Vector(v)) => {
+                vector_scalar(op, return_bool, &v, lit(s), column(VALUE))
+            }
+            (Plan::Vector(l), Plan::Vector(r)) => Ok(Plan::Vector(match op {
+                BinaryOp::And => semi_join(&l, &r, matching, JoinType::LeftSemi)?,
+                BinaryOp::Unless => semi_join(&l, &r, matching, JoinType::LeftAnti)?,
+                BinaryOp::Or => union(&l, &r, matching)?,
+                _ => vector_vector(op, return_bool, &l, &r, matching)?,
+            })),
+            _ => Err(invalid(
+                "binary expression must contain only scalar and instant vector types",
+            )),
+        }
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> QueryError {
+    QueryError::InvalidPromQuery {
+        reason: reason.into(),
+    }
+}
+
+fn range_arg<'e>(func: &str, args: &'e [PromExpr]) -> Result<(&'e VectorSelector, i64)> {
+    match args {
+        [PromExpr::MatrixSelector { selector, range }] => Ok((selector, *range)),
+        [_] => Err(invalid(format!(
+            "expected type range vector in call to function \"{func}\""
+        ))),
+        _ => Err(invalid(format!(
+            "expected 1 argument in call to \"{func}\", got {}",
+            args.len()
+        ))),
+    }
+}
+
+/// The column of the name, which isn't parsed like [`datafusion::prelude::col`]
+fn column(name: &str) -> Expr {
+    Expr::Column(Column::from_name(name))
+}
+
+fn qualified(alias: &'static str, name: &str) -> Expr {
+    Expr::Column(Column::new(Some(alias), name))
+}
+
+fn null_label() -> Expr {
+    lit(ScalarValue::Utf8(None))
+}
+
+/// Scans the table with the samples in `(start, end]`, which are unbounded if missing
+fn scan(
+    table: &MetricTable,
+    mut filters: Vec<Expr>,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> Result<LogicalPlanBuilder> {
+    let schema = table.source.schema();
+    let time_type = schema.field_with_name(TIME_FIELD_NAME)?.data_type();
+    if let Some(start) = start {
+        filters.push(column(TIME_FIELD_NAME).gt(time_literal(time_type, start)?));
+    }
+    if let Some(end) = end {
+        filters.push(column(TIME_FIELD_NAME).lt_eq(time_literal(time_type, end)?));
+    }
+
+    let mut builder = LogicalPlanBuilder::scan(table.name.as_str(), table.source.clone(), None)?;
+    if let Some(filter) = filters.into_iter().reduce(Expr::and) {
+        builder = builder.filter(filter)?;
+    }
+    Ok(builder)
+}
+
+/// The timestamp in the unit of the time column, the nanoseconds are rounded down,
+/// so the filters `time > <start>` and `time <= <end>` are kept.
+fn time_literal(data_type: &DataType, nanos: i64) -> Result<Expr> {
+    let value = match data_type {
+        DataType::Timestamp(TimeUnit::Second, tz) => {
+            ScalarValue::TimestampSecond(Some(nanos.div_euclid(1_000_000_000)), tz.clone())
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, tz) => {
+            ScalarValue::TimestampMillisecond(Some(nanos.div_euclid(1_000_000)), tz.clone())
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, tz) => {
+            ScalarValue::TimestampMicrosecond(Some(nanos.div_euclid(1_000)), tz.clone())
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, tz) => {
+            ScalarValue::TimestampNanosecond(Some(nanos), tz.clone())
+        }
+        data_type => {
+            return Err(QueryError::Internal {
+                reason: format!("unexpected type {data_type} of the time column"),
+            })
+        }
+    };
+    Ok(lit(value))
+}
+
+/// The labels of the series of the table, the metric name is the name of the table
+fn label_columns(table: &MetricTable, labels: &[String]) -> Vec<Expr> {
+    labels
+        .iter()
+        .map(|label| {
+            if table.tags.contains(label) {
+                column(label)
+            } else if label == METRIC_NAME_LABEL {
+                lit(table.name.clone())
+            } else {
+                null_label()
+            }
+            .alias(label)
+        })
+        .collect()
+}
+
+fn union_all(plans: Vec<LogicalPlan>) -> Result<LogicalPlanBuilder> {
+    let mut plans = plans.into_iter();
+    let first = plans.next().ok_or_else(|| QueryError::Internal {
+        reason: "union of no plans".to_string(),
+    })?;
+    Ok(
+        plans.try_fold(LogicalPlanBuilder::from(first), |builder, plan| {
+            builder.union(plan)
+        })?,
+    )
+}
+
+fn without_name(labels: &[String]) -> Vec<String> {
+    labels
+        .iter()
+        .filter(|l| *l != METRIC_NAME_LABEL)
+        .cloned()
+        .collect()
+}
+
+fn scalar_binary(op: BinaryOp, l: f64, r: f64) -> f64 {
+    let bool_value = |b: bool| if b { 1.0 } else { 0.0 };
+    match op {
+        BinaryOp::Add => l + r,
+        BinaryOp::Sub => l - r,
+        BinaryOp::Mul => l * r,
+        BinaryOp::Div => l / r,
+        BinaryOp::Mod => l % r,
+        BinaryOp::Pow => l.powf(r),
+        BinaryOp::Atan2 => l.atan2(r),
+        BinaryOp::Eql => bool_value(l == r),
+        BinaryOp::Neq => bool_value(l != r),
+        BinaryOp::Gtr => bool_value(l > r),
+        BinaryOp::Lss => bool_value(l < r),
+        BinaryOp::Gte => bool_value(l >= r),
+        BinaryOp::Lte => bool_value(l <= r),
+        BinaryOp::And | BinaryOp::Or | BinaryOp::Unless => f64::NAN,
+    }
+}
+
+/// The expression of the arithmetic or comparison operator
+fn binary_op(op: BinaryOp, l: Expr, r: Expr) -> Expr {
+    match op {
+        BinaryOp::Add => binary_expr(l, Operator::Plus, r),
+        BinaryOp::Sub => binary_expr(l, Operator::Minus, r),
+        BinaryOp::Mul => binary_expr(l, Operator::Multiply, r),
+        BinaryOp::Div => binary_expr(l, Operator::Divide, r),
+        BinaryOp::Mod => binary_expr(l, Operator::Modulo, r),
+        BinaryOp::Pow => power(l, r),
+        BinaryOp::Atan2 => atan2(l, r),
+        BinaryOp::Eql => l.eq(r),
+        BinaryOp::Neq => l.not_eq(r),
+        BinaryOp::Gtr => l.gt(r),
+        BinaryOp::Lss => l.lt(r),
+        BinaryOp::Gte => l.gt_eq(r),
+        BinaryOp::Lte => l.lt_eq(r),
+        BinaryOp::And | BinaryOp::Or | BinaryOp::Unless => unreachable!("set operators"),
+    }
+}
+
+/// Comparisons without `bool` filter the series and keep the value of the vector,
+/// the other operators drop the metric name.
+fn vector_scalar(
+    op: BinaryOp,
+    return_bool: bool,
+    vector: &VectorPlan,
+    l: Expr,
+    r: Expr,
+) -> Result<Plan> {
+    if op.is_set_operator() {
+        return Err(invalid(format!(
+            "set operator {op:?} not allowed in binary scalar expression"
+        )));
+    }
+
+    let expr = binary_op(op, l, r);
+    Ok(Plan::Vector(match (op.is_comparison(), return_bool) {
+        (true, false) => vector.select(vector.labels.clone(), column(VALUE), Some(expr))?,
+        (true, true) => vector.select(
+            vector.labels_without_name(),
+            cast(expr, DataType::Float64),
+            None,
+        )?,
+        (false, _) => vector.select(vector.labels_without_name(), expr, None)?,
+    }))
+}
+
+/// The labels identifying the matched series of both sides
+fn matching_labels(matching: &VectorMatching, l: &VectorPlan, r: &VectorPlan) -> Vec<String> {
+    let all = || {
+        l.labels
+            .iter()
+            .chain(r.labels.iter())
+            .filter(|label| *label != METRIC_NAME_LABEL)
+            .cloned()
+            .collect::<BTreeSet<_>>()
+    };
+    match &matching.labels {
+        Some(MatchingLabels::On(on)) => on.iter().cloned().collect::<BTreeSet<_>>(),
+        Some(MatchingLabels::Ignoring(ignoring)) => {
+            let mut labels = all();
+            labels.retain(|label| !ignoring.contains(label));
+            labels
+        }
+        None => all(),
+    }
+    .into_iter()
+    .collect()
+}
+
+/// Joins the series of `l` and `r` with the same matching labels at the same time,
+/// a missing label has the empty value
+fn join(
+    (l_alias, l): (&'static str, &VectorPlan),
+    (r_alias, r): (&'static str, &VectorPlan),
+    matching: &VectorMatching,
+    join_type: JoinType,
+) -> Result<LogicalPlanBuilder> {
+    let value = |alias, label: &str| coalesce(vec![qualified(alias, label), lit("")]);
+
+    let mut keys = (
+        vec![qualified(l_alias, TIME)],
+        vec![qualified(r_alias, TIME)],
+    );
+    // The labels of a single side match the missing label of the other side
+    let mut filters = vec![];
+    for label in matching_labels(matching, l, r) {
+        match (l.labels.contains(&label), r.labels.contains(&label)) {
+            (true, true) => {
+                keys.0.push(value(l_alias, &label));
+                keys.1.push(value(r_alias, &label));
+            }
+            (true, false) => filters.push(value(l_alias, &label).eq(lit(""))),
+            (false, true) => filters.push(value(r_alias, &label).eq(lit(""))),
+            (false, false) => {}
+        }
+    }
+
+    let right = LogicalPlan::SubqueryAlias(SubqueryAlias::try_new(r.plan.clone(), r_alias)?);
+    Ok(
+        LogicalPlanBuilder::from(LogicalPlan::SubqueryAlias(SubqueryAlias::try_new(
+            l.plan.clone(),
+            l_alias,
+        )?))
+        .join_with_expr_keys(
+            right,
+            join_type,
+            keys,
+            filters.into_iter().reduce(Expr::and),
+        )?,
+    )
+}
+
+/// The columns `time, <labels>..., value` of the joined series of the side
+fn join_columns(alias: &'static str, labels: &[(String, Expr)], value: Expr) -> Vec<Expr> {
+    [qualified(alias, TIME).alias(TIME)]
+        .into_iter()
+        .chain(labels.iter().map(|(label, expr)| expr.clone().alias(label)))
+        .chain([value.alias(VALUE)])
+        .collect()
+}
+
+/// `and` and `unless`, which keep the series of the left side
+fn semi_join(
+    l: &VectorPlan,
+    r: &VectorPlan,
+    matching: &VectorMatching,
+    join_type: JoinType,
+) -> Result<VectorPlan> {
+    let labels = l
+        .labels
+        .iter()
+        .map(|label| (label.clone(), qualified(LEFT, label)))
+        .collect::<Vec<_>>();
+    let plan = join((LEFT, l), (RIGHT, r), matching, join_type)?
+        .project(join_columns(LEFT, &labels, qualified(LEFT, VALUE)))?
+        .build()?;
+    Ok(VectorPlan {
+        plan,
+        labels: l.labels.clone(),
+    })
+}
+
+/// `or`, the series of the left side and the series of the right side not matching them
+fn union(l: &VectorPlan, r: &VectorPlan, matching: &VectorMatching) -> Result<VectorPlan> {
+    let labels = l
+        .labels
+        .iter()
+        .chain(r.labels.iter())
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let columns = |alias, plan: &VectorPlan| {
+        let labels = labels
+            .iter()
+            .map(|label| {
+                let expr = match plan.labels.contains(label) {
+                    true => qualified(alias, label),
+                    false => null_label(),
+                };
+                (label.clone(), expr)
+            })
+            .collect::<Vec<_>>();
+        join_columns(alias, &labels, qualified(alias, VALUE))
+    };
+
+    let left = LogicalPlanBuilder::from(LogicalPlan::SubqueryAlias(SubqueryAlias::try_new(
+        l.plan.clone(),
+        LEFT,
+    )?))
+    .project(columns(LEFT, l))?
+    .build()?;
+    let right = join((RIGHT, r), (LEFT, l), matching, JoinType::LeftAnti)?
+        .project(columns(RIGHT, r))?
+        .build()?;
+
+    Ok(VectorPlan {
+        plan: union_all(vec![left, right])?.build()?,
+        labels,
+    })
+}
+
+/// The arithmetic and comparison operators between vectors, the labels of the result are
+/// the labels of the side with more series, see `resultMetric` of prometheus.
+fn vector_vector(
+    op: BinaryOp,
+    return_bool: bool,
+    l: &VectorPlan,
+    r: &VectorPlan,
+    matching: &VectorMatching,
+) -> Result<VectorPlan> {
+    let ((base, base_plan), (other, other_plan), include) = match &matching.card {
+        Cardinality::OneToMany(include) => ((RIGHT, r), (LEFT, l), include.as_slice()),
+        Cardinality::ManyToOne(include) => ((LEFT, l), (RIGHT, r), include.as_slice()),
+        Cardinality::OneToOne => ((LEFT, l), (RIGHT, r), [].as_slice()),
+    };
+
+    let drop_name = !op.is_comparison() || return_bool;
+    let mut labels = base_plan
+        .labels
+        .iter()
+        .filter(|label| !(drop_name && *label == METRIC_NAME_LABEL))
+        .filter(|label| match (&matching.card, &matching.labels) {
+            (Cardinality::OneToOne, Some(MatchingLabels::On(on))) => on.contains(label),
+            (Cardinality::OneToOne, Some(MatchingLabels::Ignoring(ignoring))) => {
+                !ignoring.contains(label)
+            }
+            _ => true,
+        })
+        .map(|label| (label.clone(), qualified(base, label)))
+        .collect::<Vec<_>>();
+    // The labels of the other side are included, or removed if missing
+    for label in include {
+        labels.retain(|(l, _)| l != label);
+        if other_plan.labels.contains(label) {
+            labels.push((label.clone(), qualified(other, label)));
+        }
+    }
+    labels.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut builder = join((LEFT, l), (RIGHT, r), matching, JoinType::Inner)?;
+    let expr = binary_op(op, qualified(LEFT, VALUE), qualified(RIGHT, VALUE));
+    let value = match (op.is_comparison(), return_bool) {
+        (true, false) => {
+            builder = builder.filter(expr)?;
+            qualified(LEFT, VALUE)
+        }
+        (true, true) => cast(expr, DataType::Float64),
+        (false, _) => expr,
+    };
+    let plan = builder
+        .project(join_columns(LEFT, &labels, value))?
+        .build()?;
+
+    Ok(VectorPlan {
+        plan,
+        labels: labels.into_iter().map(|(label, _)| label).collect(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use datafusion::datasource::{provider_as_source, MemTable};
+    use spi::Result;
+
+    use super::{EvalRange, MetricTable, Plan, Planner};
+    use crate::prom::matcher::Matcher;
+    use crate::prom::promql::parser::parse;
+
+    fn metric_table(name: &str, tags: Vec<&str>) -> MetricTable {
+        let fields = [Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        )]
+        .into_iter()
+        .chain(
+            tags.iter()
+                .map(|tag| Field::new(*tag, DataType::Utf8, true)),
+        )
+        .chain([Field::new("value", DataType::Float64, true)])
+        .collect::<Vec<_>>();
+        let table = MemTable::try_new(Arc::new(Schema::new(fields)), vec![vec![]]).unwrap();
+
+        MetricTable {
+            name: name.to_string(),
+            tags: tags.into_iter().map(String::from).collect(),
+            source: provider_as_source(Arc::new(table)),
+        }
+    }
+
+    fn tables(matchers: &[&Matcher]) -> Result<Vec<MetricTable>> {
+        Ok([
+            ("http_requests_total", vec!["code", "job"]),
+            ("up", vec!["instance", "job"]),
+        ]
+        .into_iter()
+        .filter(|(name, _)| matchers.iter().all(|m| m.matches(name)))
+        .map(|(name, tags)| metric_table(name, tags))
+        .collect())
+    }
+
+    fn plan(query: &str, range: EvalRange) -> Result<Plan> {
+        Planner::new(range, &tables).plan(&parse(query)?)
+    }
+
+    fn vector_labels(query: &str) -> Vec<String> {
+        let range = EvalRange {
+            start: 0,
+            end: 600_000_000_000,
+            step: 60_000_000_000,
+        };
+        match plan(query, range).unwrap() {
+            Plan::Vector(v) => v.labels,
+            plan => panic!("expected a vector, got {plan:?}"),
+        }
+    }
+
+    #[test]
+    fn test_plan_selector() {
+        let Plan::Vector(v) = plan(
+            r#"http_requests_total{code!="200"}"#,
+            EvalRange::instant(3_600_000_000_000),
+        )
+        .unwrap() else {
+            panic!("expected a vector")
+        };
+        assert_eq!(v.labels, vec!["__name__", "code", "job"]);
+        let display = v.plan.display_indent().to_string();
+        assert!(display.contains(
+            "RangeVector: func=Latest, labels=[__name__, code, job], start=3600000000000, \
+            end=3600000000000"
+        ));
+        assert!(display.contains(
+            "http_requests_total.code IS NULL OR http_requests_total.code != Utf8(\"200\")"
+        ));
+        assert!(display.contains(
+            "time > TimestampNanosecond(3300000000000, None) \
+            AND http_requests_total.time <= TimestampNanosecond(3600000000000, None)"
+        ));
+        // The windows are evaluated over the samples, which aren't copied into each window
+        assert!(!display.contains("CrossJoin"));
+
+        let Plan::Vector(v) = plan(
+            "rate(up[5m] offset 1m)",
+            EvalRange {
+                start: 0,
+                end: 600_000_000_000,
+                step: 60_000_000_000,
+            },
+        )
+        .unwrap() else {
+            panic!("expected a vector")
+        };
+        assert!(v.plan.display_indent().to_string().contains(
+            "RangeVector: func=Rate, labels=[__name__, instance, job], start=0, \
+            end=600000000000, step=60000000000, range=300000000000, offset=60000000000"
+        ));
+
+        // Both tables are matched
+        assert_eq!(
+            vector_labels(r#"{__name__=~".+", job="api"}"#),
+            vec!["__name__", "code", "instance", "job"]
+        );
+        // No table has the label
+        let Plan::Vector(v) = plan(r#"up{env="prod"}"#, EvalRange::instant(0)).unwrap() else {
+            panic!("expected a vector")
+        };
+        assert!(v.labels.is_empty());
+    }
+
+    #[test]
+    fn test_plan_labels() {
+        assert_eq!(
+            vector_labels("rate(http_requests_total[5m])"),
+            vec!["code", "job"]
+        );
+        assert_eq!(
+            vector_labels("last_over_time(up[5m])"),
+            vec!["__name__", "instance", "job"]
+        );
+        assert_eq!(
+            vector_labels("sum by (job, missing) (rate(http_requests_total[5m]))"),
+            vec!["job"]
+        );
+        assert_eq!(
+            vector_labels("sum without (code) (http_requests_total)"),
+            vec!["job"]
+        );
+        assert_eq!(vector_labels("up > 0"), vec!["__name__", "instance", "job"]);
+        assert_eq!(vector_labels("up > bool 0"), vec!["instance", "job"]);
+        assert_eq!(
+            vector_labels("http_requests_total / on(job) group_left(instance) up"),
+            vec!["code", "instance", "job"]
+        );
+        assert_eq!(
+            vector_labels("http_requests_total / on(job) up"),
+            vec!["job"]
+        );
+        assert_eq!(
+            vector_labels("up or http_requests_total"),
+            vec!["__name__", "code", "instance", "job"]
+        );
+
+        assert!(matches!(
+            plan("2 * 3 + 1", EvalRange::instant(0)).unwrap(),
+            Plan::Scalar(n) if n == 7.0
+        ));
+        assert!(plan("1 > 2", EvalRange::instant(0)).is_err());
+        assert!(plan("rate(up)", EvalRange::instant(0)).is_err());
+        assert!(plan("sum(up[5m])", EvalRange::instant(0)).is_err());
+        assert!(plan("holt_winters(up[5m], 0.1, 0.1)", EvalRange::instant(0)).is_err());
+    }
+}
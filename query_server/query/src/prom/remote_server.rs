@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
+use async_stream::try_stream;
 use async_trait::async_trait;
 use bytes::Bytes;
 use coordinator::service::CoordinatorRef;
-use datafusion::arrow::datatypes::ToByteSlice;
+use datafusion::arrow::datatypes::{SchemaRef, ToByteSlice};
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
 use meta::error::MetaError;
 use meta::model::MetaClientRef;
 use models::schema::{TskvTableSchema, TIME_FIELD_NAME};
@@ -13,21 +16,26 @@ use protocol_parser::lines_convert::parse_lines_to_points;
 use protocol_parser::Line;
 use protos::kv_service::WritePointsRequest;
 use protos::models_helper::{parse_proto_bytes, to_proto_bytes};
+use protos::prompb::remote::read_request::ResponseType;
 use protos::prompb::remote::{
-    Query as PromQuery, QueryResult, ReadRequest, ReadResponse, WriteRequest,
+    ChunkedReadResponse, Query as PromQuery, QueryResult, ReadRequest, ReadResponse, WriteRequest,
 };
-use protos::prompb::types::label_matcher::Type;
-use protos::prompb::types::TimeSeries;
+use protos::prompb::types::{ChunkedSeries, LabelMatcher, ReadHints, TimeSeries};
 use protos::FieldValue;
-use regex::Regex;
+use spi::query::execution::Output;
 use spi::server::dbms::DBMSRef;
-use spi::server::prom::PromRemoteServer;
+use spi::server::prom::{
+    PromQueryResult, PromQueryTime, PromReadResponse, PromRemoteServer, PromSeriesQuery,
+};
 use spi::service::protocol::{Context, Query, QueryHandle};
 use spi::{QueryError, Result};
-use trace::{debug, warn, SpanContext, SpanExt, SpanRecorder};
+use trace::{debug, SpanContext, SpanExt, SpanRecorder};
 
+use super::chunk::{encode_xor_chunks, write_frame};
+use super::matcher::{match_tables, quote_identifier, Matcher};
+use super::promql::PromContext;
 use super::time_series::writer::WriterBuilder;
-use super::{METRIC_NAME_LABEL, METRIC_SAMPLE_COLUMN_NAME};
+use super::{promql, METRIC_NAME_LABEL, METRIC_SAMPLE_COLUMN_NAME};
 use crate::prom::DEFAULT_PROM_TABLE_NAME;
 
 pub struct PromRemoteSqlServer {
@@ -43,30 +51,31 @@ impl PromRemoteServer for PromRemoteSqlServer {
         ctx: &Context,
         req: Bytes,
         span_ctx: Option<&SpanContext>,
-    ) -> Result<Vec<u8>> {
-        let meta = self
-            .coord
-            .meta_manager()
-            .tenant_meta(ctx.tenant())
-            .await
-            .ok_or_else(|| MetaError::TenantNotFound {
-                tenant: ctx.tenant().to_string(),
-            })?;
+    ) -> Result<PromReadResponse> {
+        let meta = self.tenant_meta(ctx).await?;
 
         let read_request = self.deserialize_read_request(req).await?;
 
         debug!("Received remote read request: {:?}", read_request);
 
         let span_recorder = SpanRecorder::new(span_ctx.child_span("process read request"));
-        let read_response = self
-            .process_read_request(ctx, meta, read_request, span_recorder)
-            .await?;
+        match response_type(&read_request)? {
+            ResponseType::SAMPLES => {
+                let read_response = self
+                    .process_read_request(ctx, meta, read_request, span_recorder)
+                    .await?;
 
-        debug!("Return remote read response: {:?}", read_response);
+                debug!("Return remote read response: {:?}", read_response);
 
-        self.serialize_read_response(read_response).await
+                Ok(PromReadResponse::Samples(
+                    self.serialize_read_response(read_response).await?,
+                ))
+            }
+            ResponseType::STREAMED_XOR_CHUNKS => Ok(PromReadResponse::StreamedXorChunks(
+                self.stream_read_request(ctx, meta, read_request, span_recorder)?,
+            )),
+        }
     }
-
     fn remote_write(&self, ctx: &Context, req: Bytes) -> Result<WritePointsRequest> {
         let prom_write_request = self.deserialize_write_request(req)?;
         let write_points_request =
@@ -74,6 +83,68 @@ impl PromRemoteServer for PromRemoteSqlServer {
 
         Ok(write_points_request)
     }
+
+    async fn query(
+        &self,
+        ctx: &Context,
+        query: &str,
+        time: PromQueryTime,
+        span_ctx: Option<&SpanContext>,
+    ) -> Result<PromQueryResult> {
+        let meta = self.tenant_meta(ctx).await?;
+
+        debug!("Received PromQL query: {}, {:?}", query, time);
+
+        let span_recorder = SpanRecorder::new(span_ctx.child_span("process promql query"));
+        let prom_ctx = self.prom_context(&meta, ctx, span_recorder.span_ctx());
+        promql::query(&prom_ctx, query, time).await
+    }
+
+    async fn series(
+        &self,
+        ctx: &Context,
+        query: &PromSeriesQuery,
+        span_ctx: Option<&SpanContext>,
+    ) -> Result<Vec<BTreeMap<String, String>>> {
+        let meta = self.tenant_meta(ctx).await?;
+
+        debug!("Received series query: {:?}", query);
+
+        let span_recorder = SpanRecorder::new(span_ctx.child_span("process series query"));
+        let prom_ctx = self.prom_context(&meta, ctx, span_recorder.span_ctx());
+        promql::series(&prom_ctx, query).await
+    }
+
+    async fn labels(
+        &self,
+        ctx: &Context,
+        query: &PromSeriesQuery,
+        span_ctx: Option<&SpanContext>,
+    ) -> Result<Vec<String>> {
+        let meta = self.tenant_meta(ctx).await?;
+
+        debug!("Received labels query: {:?}", query);
+
+        let span_recorder = SpanRecorder::new(span_ctx.child_span("process labels query"));
+        let prom_ctx = self.prom_context(&meta, ctx, span_recorder.span_ctx());
+        promql::labels(&prom_ctx, query).await
+    }
+
+    async fn label_values(
+        &self,
+        ctx: &Context,
+        name: &str,
+        query: &PromSeriesQuery,
+        span_ctx: Option<&SpanContext>,
+    ) -> Result<Vec<String>> {
+        let meta = self.tenant_meta(ctx).await?;
+
+        debug!("Received values query of label {}: {:?}", name, query);
+
+        let span_recorder = SpanRecorder::new(span_ctx.child_span("process label values query"));
+        let prom_ctx = self.prom_context(&meta, ctx, span_recorder.span_ctx());
+        promql::label_values(&prom_ctx, name, query).await
+    }
 }
 
 impl PromRemoteSqlServer {
@@ -85,6 +156,33 @@ impl PromRemoteSqlServer {
         }
     }
 
+    async fn tenant_meta(&self, ctx: &Context) -> Result<MetaClientRef> {
+        let meta = self
+            .coord
+            .meta_manager()
+            .tenant_meta(ctx.tenant())
+            .await
+            .ok_or_else(|| MetaError::TenantNotFound {
+                tenant: ctx.tenant().to_string(),
+            })?;
+        Ok(meta)
+    }
+
+    fn prom_context<'a>(
+        &'a self,
+        meta: &'a MetaClientRef,
+        ctx: &'a Context,
+        span_ctx: Option<&'a SpanContext>,
+    ) -> PromContext<'a> {
+        PromContext {
+            db: &self.db,
+            coord: &self.coord,
+            meta,
+            ctx,
+            span_ctx,
+        }
+    }
+
     async fn deserialize_read_request(&self, req: Bytes) -> Result<ReadRequest> {
         let mut decompressed = Vec::new();
         let compressed = req.to_byte_slice();
@@ -186,28 +284,68 @@ impl PromRemoteSqlServer {
         sql: SqlWithTable,
         span_recorder: SpanRecorder,
     ) -> Result<Vec<TimeSeries>> {
-        let table_schema = sql.table;
-        let tag_name_indices = table_schema.tag_indices();
-        let sample_value_idx = table_schema
-            .column_index(METRIC_SAMPLE_COLUMN_NAME)
-            .ok_or_else(|| QueryError::ColumnNotExists {
-                table: table_schema.name.to_string(),
-                column: METRIC_SAMPLE_COLUMN_NAME.to_string(),
-            })?;
-        let sample_time_idx = table_schema.column_index(TIME_FIELD_NAME).ok_or_else(|| {
-            QueryError::ColumnNotExists {
-                table: table_schema.name.to_string(),
-                column: TIME_FIELD_NAME.to_string(),
-            }
-        })?;
-
-        let inner_query = Query::new(ctx.clone(), sql.sql);
+        let inner_query = Query::new(ctx.clone(), sql.sql.clone());
         let result = self
             .db
             .execute(&inner_query, span_recorder.span_ctx())
             .await?;
 
-        transform_time_series(result, tag_name_indices, sample_value_idx, sample_time_idx).await
+        transform_time_series(
+            result,
+            sql.tag_indices(),
+            sql.sample_value_idx(),
+            sql.sample_time_idx(),
+            Some(&sql.table.name),
+        )
+        .await
+    }
+
+    /// Reads the series of the queries one by one, each series is sent in a frame
+    /// as soon as all its samples are read.
+    fn stream_read_request(
+        &self,
+        ctx: &Context,
+        meta: MetaClientRef,
+        read_request: ReadRequest,
+        span_recorder: SpanRecorder,
+    ) -> Result<BoxStream<'static, Result<Vec<u8>>>> {
+        // Invalid matchers are reported before the response starts
+        let queries = read_request
+            .queries
+            .into_iter()
+            .map(|q| build_sql_with_table(ctx, &meta, q))
+            .collect::<Result<Vec<_>>>()?;
+
+        debug!("Prepare to execute: {:?}", queries);
+
+        let db = self.db.clone();
+        let ctx = ctx.clone();
+        let frames: BoxStream<'static, Result<Vec<u8>>> = Box::pin(try_stream! {
+            for (query_index, sqls) in queries.into_iter().enumerate() {
+                for (idx, sql) in sqls.into_iter().enumerate() {
+                    let child_recorder = span_recorder.child(format!("{query_index}.{idx}"));
+                    let inner_query = Query::new(ctx.clone(), sql.sql.clone());
+                    let output = db
+                        .execute(&inner_query, child_recorder.span_ctx())
+                        .await?
+                        .result();
+
+                    let mut splitter = sql.writer_builder(output.schema())?.build_splitter();
+                    if let Output::StreamData(mut stream) = output {
+                        while let Some(batch) = stream.try_next().await? {
+                            for series in splitter.write(&batch)? {
+                                yield chunked_series_frame(query_index, series)?;
+                            }
+                        }
+                    }
+                    if let Some(series) = splitter.finish() {
+                        yield chunked_series_frame(query_index, series)?;
+                    }
+                }
+            }
+        });
+
+        Ok(frames)
     }
 
     async fn serialize_read_response(&self, read_response: ReadResponse) -> Result<Vec<u8>> {
@@ -222,6 +360,100 @@ impl PromRemoteSqlServer {
     }
 }
 
+/// Returns the first response type accepted by the request, `SAMPLES` if none is given.
+fn response_type(read_request: &ReadRequest) -> Result<ResponseType> {
+    if read_request.accepted_response_types.is_empty() {
+        return Ok(ResponseType::SAMPLES);
+    }
+
+    read_request
+        .accepted_response_types
+        .iter()
+        .find_map(|t| t.enum_value().ok())
+        .ok_or_else(|| QueryError::InvalidRemoteReadReq {
+            source: format!(
+                "None of the accepted response types is supported: {:?}",
+                read_request.accepted_response_types
+            )
+            .into(),
+        })
+}
+
+fn chunked_series_frame(query_index: usize, series: TimeSeries) -> Result<Vec<u8>> {
+    let response = ChunkedReadResponse {
+        chunked_series: vec![ChunkedSeries {
+            chunks: encode_xor_chunks(&series.samples),
+            labels: series.labels,
+            ..Default::default()
+        }],
+        query_index: query_index as i64,
+        ..Default::default()
+    };
+    let message = to_proto_bytes(response).map_err(|source| QueryError::CommonError {
+        msg: source.to_string(),
+    })?;
+
+    Ok(write_frame(&message))
+}
+
+fn label_matcher(m: LabelMatcher) -> Result<Matcher> {
+    let type_ = m
+        .type_
+        .enum_value()
+        .map_err(|e| QueryError::InvalidRemoteReadReq {
+            source: format!("Unknown label matcher type: {e}").into(),
+        })?;
+
+    Matcher::try_new(m.name, type_, m.value).map_err(|err| QueryError::InvalidRemoteReadReq {
+        source: Box::new(err),
+    })
+}
+
+/// The aggregation of `<func>_over_time` which can be computed by the storage.
+///
+/// When the range of the function equals the step, the windows of the evaluations
+/// `(t - step, t]` don't overlap, so each window can be aggregated into a single sample
+/// at its end, and the function returns the same value from the single sample.
+/// `count_over_time` would count the samples instead, and the aggregations of the series
+/// by `grouping` are not pushed down, because the hints don't give their operator.
+#[derive(Debug)]
+struct Downsample {
+    func: &'static str,
+    step_ms: i64,
+    end_ms: i64,
+}
+
+impl Downsample {
+    fn from_hints(hints: &ReadHints) -> Option<Self> {
+        let func = match hints.func.as_str() {
+            "min_over_time" => "min",
+            "max_over_time" => "max",
+            "sum_over_time" => "sum",
+            "avg_over_time" => "avg",
+            _ => return None,
+        };
+        if hints.step_ms <= 0 || hints.range_ms != hints.step_ms {
+            return None;
+        }
+
+        Some(Self {
+            func,
+            step_ms: hints.step_ms,
+            end_ms: hints.end_ms,
+        })
+    }
+
+    /// The end of the window `(t - step, t]` of the time
+    fn window_sql(&self) -> String {
+        let time = quote_identifier(TIME_FIELD_NAME);
+        format!(
+            "date_bin(INTERVAL '{step} millisecond', {time} - INTERVAL '1 nanosecond', CAST({origin} AS TIMESTAMP)) + INTERVAL '{step} millisecond'",
+            step = self.step_ms,
+            origin = self.end_ms * 1_000_000,
+        )
+    }
+}
+
 fn build_sql_with_table(
     ctx: &Context,
     meta: &MetaClientRef,
@@ -231,116 +463,131 @@ fn build_sql_with_table(
         start_timestamp_ms,
         end_timestamp_ms,
         matchers,
-        hints: _,
+        hints,
         special_fields: _,
     } = query;
 
-    let mut tables = Vec::new();
-    let mut filters = Vec::with_capacity(matchers.len());
-
+    let mut name_matchers = vec![];
+    let mut label_matchers = vec![];
     for m in matchers {
-        let type_ = m
-            .type_
-            .enum_value()
-            .map_err(|e| QueryError::InvalidRemoteReadReq {
-                source: format!("Unknown label matcher type: {e}").into(),
-            })?;
-
-        if METRIC_NAME_LABEL == m.name {
-            match type_ {
-                Type::EQ => {
-                    // Get schema of the specified table
-                    let table_name = &m.value;
-                    let table = meta
-                        .get_tskv_table_schema(ctx.database(), table_name)?
-                        .ok_or_else(|| MetaError::TableNotFound {
-                            table: table_name.to_string(),
-                        })?;
-                    tables = vec![table];
-                }
-                Type::RE => {
-                    // Filter table names through regular expressions,
-                    // Get the schema of the remaining tables.
-                    let pattern =
-                        Regex::new(&m.value).map_err(|err| QueryError::InvalidRemoteReadReq {
-                            source: Box::new(err),
-                        })?;
-
-                    tables = meta
-                        .list_tables(ctx.database())?
-                        .iter()
-                        .filter(|e| pattern.is_match(e))
-                        .flat_map(|table_name| {
-                            if let Ok(s) = meta.get_tskv_table_schema(ctx.database(), table_name) {
-                                s
-                            } else {
-                                warn!(
-                                    "The table {} may have just been dropped, or it may be a bug.",
-                                    table_name
-                                );
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>();
-                }
-                _ => {
-                    return Err(QueryError::InvalidRemoteReadReq { source: "non-equal or regex-non-equal matchers are not supported on the metric name yet".to_string().into() });
-                }
-            }
-
-            continue;
+        let matcher = label_matcher(m)?;
+        if METRIC_NAME_LABEL == matcher.name {
+            name_matchers.push(matcher);
+        } else {
+            label_matchers.push(matcher);
         }
+    }
 
-        match type_ {
-            Type::EQ => {
-                filters.push(format!("{} = '{}'", m.name, m.value));
-            }
-            Type::NEQ => {
-                filters.push(format!("{} != '{}'", m.name, m.value));
-            }
-            Type::RE => {
-                filters.push(format!("{} ~ '{}'", m.name, m.value));
-            }
-            Type::NRE => {
-                filters.push(format!("{} !~ '{}'", m.name, m.value));
+    let name_matchers = name_matchers.iter().collect::<Vec<_>>();
+    let tables = match_tables(meta, ctx.database(), &name_matchers, true)?;
+
+    let downsample = hints.as_ref().and_then(Downsample::from_hints);
+    debug!("Downsample of the remote read: {:?}", downsample);
+
+    let mut result = Vec::with_capacity(tables.len());
+    'tables: for table in tables {
+        let mut filters = Vec::with_capacity(label_matchers.len() + 2);
+        for m in &label_matchers {
+            match table.column(&m.name) {
+                Some(col) if col.column_type.is_tag() => filters.push(m.to_sql()),
+                // The label is missing in all series of the table
+                _ if m.matches("") => {}
+                _ => continue 'tables,
             }
         }
-    }
-    // Convert to ns timestamp
-    filters.push(format!("time >= {}", start_timestamp_ms * 1_000_000));
-    filters.push(format!("time <= {}", end_timestamp_ms * 1_000_000));
+        // Convert to ns timestamp
+        filters.push(format!("time >= {}", start_timestamp_ms * 1_000_000));
+        filters.push(format!("time <= {}", end_timestamp_ms * 1_000_000));
+
+        // Labels are sorted by name, so the series are sorted by labels
+        let mut tags = table
+            .columns()
+            .iter()
+            .filter(|col| col.column_type.is_tag())
+            .map(|col| col.name.clone())
+            .collect::<Vec<_>>();
+        tags.sort();
+        let quoted_tags = tags
+            .iter()
+            .map(|tag| quote_identifier(tag))
+            .collect::<Vec<_>>();
+        // A null tag and an empty tag are the same missing label, so they are one series
+        let label_exprs = quoted_tags
+            .iter()
+            .map(|tag| format!("coalesce({tag}, '')"))
+            .collect::<Vec<_>>();
+
+        let time = quote_identifier(TIME_FIELD_NAME);
+        let value = quote_identifier(METRIC_SAMPLE_COLUMN_NAME);
+        let (projection, group_by) = match &downsample {
+            Some(downsample) => {
+                let window = downsample.window_sql();
+                let mut group_by = vec![window.clone()];
+                group_by.extend(label_exprs.iter().cloned());
+                (
+                    format!(
+                        "{window} AS {time}, {}{func}({value}) AS {value}",
+                        labels_prefix(&label_exprs, &quoted_tags),
+                        func = downsample.func
+                    ),
+                    format!(" GROUP BY {}", group_by.join(", ")),
+                )
+            }
+            None => (
+                format!(
+                    "{time}, {}{value}",
+                    labels_prefix(&label_exprs, &quoted_tags)
+                ),
+                String::new(),
+            ),
+        };
+        let mut order_by = label_exprs;
+        order_by.push(time);
 
-    let result = tables
-        .into_iter()
-        .map(|table| SqlWithTable {
+        result.push(SqlWithTable {
             sql: format!(
-                "SELECT * FROM {} WHERE {}",
-                table.name,
-                filters.join(" AND ")
+                "SELECT {projection} FROM {} WHERE {}{group_by} ORDER BY {}",
+                quote_identifier(&table.name),
+                filters.join(" AND "),
+                order_by.join(", ")
             ),
             table,
-        })
-        .collect();
+            tags,
+        });
+    }
 
     Ok(result)
 }
 
+fn labels_prefix(label_exprs: &[String], quoted_tags: &[String]) -> String {
+    label_exprs
+        .iter()
+        .zip(quoted_tags)
+        .map(|(expr, tag)| format!("{expr} AS {tag}, "))
+        .collect()
+}
+
 /// Convert the execution result of query to TimeSeries list of prometheus
 async fn transform_time_series(
     query_handle: QueryHandle,
     tag_name_indices: Vec<usize>,
     sample_value_idx: usize,
     sample_time_idx: usize,
+    metric_name: Option<&str>,
 ) -> Result<Vec<TimeSeries>> {
     let result = query_handle.result();
     let schema = result.schema();
     let batches = result.chunk_result().await?;
 
+    let mut builder =
+        WriterBuilder::try_new(tag_name_indices, sample_value_idx, sample_time_idx, schema)?;
+    if let Some(metric_name) = metric_name {
+        builder = builder.with_metric_name(metric_name);
+    }
+
     let mut timeseries = HashMap::default();
     {
-        let mut writer =
-            WriterBuilder::try_new(tag_name_indices, sample_value_idx, sample_time_idx, schema)?
-                .build(&mut timeseries);
+        let mut writer = builder.build(&mut timeseries);
 
         for batch in batches {
             writer.write(&batch)?;
@@ -350,10 +597,36 @@ async fn transform_time_series(
     Ok(timeseries.into_values().collect())
 }
 
+/// The query of a table, which returns the columns `time, <tags>..., value`
 #[derive(Debug)]
 struct SqlWithTable {
     pub sql: String,
     pub table: Arc<TskvTableSchema>,
+    pub tags: Vec<String>,
+}
+
+impl SqlWithTable {
+    fn sample_time_idx(&self) -> usize {
+        0
+    }
+
+    fn tag_indices(&self) -> Vec<usize> {
+        (1..=self.tags.len()).collect()
+    }
+
+    fn sample_value_idx(&self) -> usize {
+        self.tags.len() + 1
+    }
+
+    fn writer_builder(&self, schema: SchemaRef) -> Result<WriterBuilder> {
+        Ok(WriterBuilder::try_new(
+            self.tag_indices(),
+            self.sample_value_idx(),
+            self.sample_time_idx(),
+            schema,
+        )?
+        .with_metric_name(self.table.name.clone()))
+    }
 }
 
 #[cfg(test)]
@@ -365,12 +638,12 @@ mod test {
     use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
     use datafusion::arrow::record_batch::RecordBatch;
     use models::auth::user::{User, UserDesc, UserOptions};
-    use protos::prompb::types::{Label, Sample, TimeSeries};
+    use protos::prompb::types::{Label, LabelMatcher, ReadHints, Sample, TimeSeries};
     use spi::query::execution::Output;
     use spi::query::recordbatch::RecordBatchStreamWrapper;
     use spi::service::protocol::{ContextBuilder, Query, QueryHandle, QueryId};
 
-    use crate::prom::remote_server::transform_time_series;
+    use crate::prom::remote_server::{transform_time_series, Downsample};
 
     #[tokio::test]
     async fn test_transform_time_series() {
@@ -417,6 +690,7 @@ mod test {
             tag_name_indices,
             sample_value_idx,
             sample_time_idx,
+            None,
         )
         .await
         .unwrap();
@@ -437,4 +711,30 @@ mod test {
 
         assert_eq!(vec![expect], time_series);
     }
+
+    #[test]
+    fn test_downsample_from_hints() {
+        let hints = |func: &str, step_ms, range_ms| ReadHints {
+            step_ms,
+            func: func.to_string(),
+            start_ms: 0,
+            end_ms: 3_600_000,
+            range_ms,
+            ..Default::default()
+        };
+
+        let downsample = Downsample::from_hints(&hints("max_over_time", 60_000, 60_000)).unwrap();
+        assert_eq!(downsample.func, "max");
+        assert_eq!(
+            downsample.window_sql(),
+            "date_bin(INTERVAL '60000 millisecond', \"time\" - INTERVAL '1 nanosecond', \
+            CAST(3600000000000 AS TIMESTAMP)) + INTERVAL '60000 millisecond'"
+        );
+
+        // The windows overlap
+        assert!(Downsample::from_hints(&hints("max_over_time", 60_000, 300_000)).is_none());
+        // Counts the samples
+        assert!(Downsample::from_hints(&hints("count_over_time", 60_000, 60_000)).is_none());
+        assert!(Downsample::from_hints(&hints("rate", 60_000, 60_000)).is_none());
+    }
 }
@@ -11,26 +11,36 @@ use protos::prompb::types::{Label, Sample, TimeSeries};
 use spi::{QueryError, Result};
 use trace::debug;
 
-use crate::prom::METRIC_SAMPLE_COLUMN_NAME;
+use crate::prom::{METRIC_NAME_LABEL, METRIC_SAMPLE_COLUMN_NAME};
 
+/// Reads the labels and the sample of the rows
 #[derive(Debug)]
-pub struct Writer<'a> {
-    /// The object to write to
+struct SampleReader {
     tag_name_indices: Vec<usize>,
     // The column name of the tag_name_indices index
     tag_names: Vec<String>,
     sample_value_idx: usize,
     sample_time_idx: usize,
-    schema: SchemaRef,
-
-    labels_to_series: &'a mut HashMap<String, TimeSeries>,
+    /// The value of the `__name__` label
+    metric_name: Option<String>,
 }
 
-impl Writer<'_> {
+impl SampleReader {
+    /// Returns the labels sorted by name, the tags which are null or empty are not labels.
     fn get_labels(&self, batch: &[ArrayRef], row_index: usize) -> Result<Vec<Label>> {
-        let mut labels = Vec::with_capacity(self.tag_name_indices.len());
+        let mut labels = Vec::with_capacity(self.tag_name_indices.len() + 1);
+        if let Some(metric_name) = &self.metric_name {
+            labels.push(Label {
+                name: METRIC_NAME_LABEL.to_string(),
+                value: metric_name.clone(),
+                ..Default::default()
+            });
+        }
         for (tag_idx, tag_name) in self.tag_name_indices.iter().zip(&self.tag_names) {
             let col = &batch[*tag_idx];
+            if col.is_null(row_index) {
+                continue;
+            }
             let tag_value = match col.data_type() {
                 DataType::Utf8 => col
                     .as_any()
@@ -44,6 +54,9 @@ impl Writer<'_> {
                     });
                 }
             };
+            if tag_value.is_empty() {
+                continue;
+            }
 
             labels.push(Label {
                 name: tag_name.to_string(),
@@ -51,6 +64,7 @@ impl Writer<'_> {
                 ..Default::default()
             });
         }
+        labels.sort_by(|a, b| a.name.cmp(&b.name));
 
         Ok(labels)
     }
@@ -130,8 +144,8 @@ impl Writer<'_> {
         Ok(sample_timestamp_ms)
     }
 
-    /// Convert a record to a metric
-    fn apply(&mut self, batch: &[ArrayRef], row_index: usize) -> Result<()> {
+    /// Convert a record to the labels and the sample of a metric
+    fn read(&self, batch: &[ArrayRef], row_index: usize) -> Result<(Vec<Label>, Sample)> {
         // get labels
         let labels = self.get_labels(batch, row_index)?;
         // get sample value
@@ -145,6 +159,24 @@ impl Writer<'_> {
             ..Default::default()
         };
 
+        Ok((labels, sample))
+    }
+}
+
+#[derive(Debug)]
+pub struct Writer<'a> {
+    /// The object to write to
+    reader: SampleReader,
+    schema: SchemaRef,
+
+    labels_to_series: &'a mut HashMap<String, TimeSeries>,
+}
+
+impl Writer<'_> {
+    /// Convert a record to a metric
+    fn apply(&mut self, batch: &[ArrayRef], row_index: usize) -> Result<()> {
+        let (labels, sample) = self.reader.read(batch, row_index)?;
+
         // save Sample
         let labels_str = concat_labels(&labels);
         debug!(
@@ -177,6 +209,50 @@ impl Writer<'_> {
     }
 }
 
+/// Splits the records sorted by labels and time into time series,
+/// a time series is complete when the labels of the records change.
+#[derive(Debug)]
+pub struct SeriesSplitter {
+    reader: SampleReader,
+    schema: SchemaRef,
+    current: Option<(String, TimeSeries)>,
+}
+
+impl SeriesSplitter {
+    /// Write a record batch, returns the time series completed by it
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<Vec<TimeSeries>> {
+        debug_assert_eq!(self.schema.fields(), batch.schema().fields());
+
+        let columns = batch.columns();
+        let mut completed = vec![];
+
+        for row_index in 0..batch.num_rows() {
+            let (labels, sample) = self.reader.read(columns, row_index)?;
+            let labels_str = concat_labels(&labels);
+            match &mut self.current {
+                Some((current, series)) if *current == labels_str => series.samples.push(sample),
+                _ => {
+                    let series = TimeSeries {
+                        labels,
+                        samples: vec![sample],
+                        ..Default::default()
+                    };
+                    if let Some((_, series)) = self.current.replace((labels_str, series)) {
+                        completed.push(series);
+                    }
+                }
+            }
+        }
+
+        Ok(completed)
+    }
+
+    /// Returns the last time series
+    pub fn finish(self) -> Option<TimeSeries> {
+        self.current.map(|(_, series)| series)
+    }
+}
+
 /// A CSV writer builder
 #[derive(Debug)]
 pub struct WriterBuilder {
@@ -185,6 +261,7 @@ pub struct WriterBuilder {
     tag_names: Vec<String>,
     sample_value_idx: usize,
     sample_time_idx: usize,
+    metric_name: Option<String>,
     schema: SchemaRef,
 }
 
@@ -231,21 +308,48 @@ impl WriterBuilder {
             tag_names,
             sample_value_idx,
             sample_time_idx,
+            metric_name: None,
             schema,
         })
     }
 
+    /// Add the `__name__` label to the time series
+    pub fn with_metric_name(mut self, metric_name: impl Into<String>) -> Self {
+        self.metric_name = Some(metric_name.into());
+        self
+    }
+
+    fn reader(&self) -> SampleReader {
+        SampleReader {
+            tag_name_indices: self.tag_name_indices.clone(),
+            tag_names: self.tag_names.clone(),
+            sample_value_idx: self.sample_value_idx,
+            sample_time_idx: self.sample_time_idx,
+            // The tables written by prometheus keep the `__name__` label in a tag
+            metric_name: self
+                .metric_name
+                .clone()
+                .filter(|_| !self.tag_names.iter().any(|t| t == METRIC_NAME_LABEL)),
+        }
+    }
+
     /// Create a new `Writer`
     pub fn build(self, labels_to_series: &mut HashMap<String, TimeSeries>) -> Writer<'_> {
         Writer {
-            tag_name_indices: self.tag_name_indices,
-            tag_names: self.tag_names,
-            sample_value_idx: self.sample_value_idx,
-            sample_time_idx: self.sample_time_idx,
+            reader: self.reader(),
             schema: self.schema,
             labels_to_series,
         }
     }
+
+    /// Create a new `SeriesSplitter`
+    pub fn build_splitter(self) -> SeriesSplitter {
+        SeriesSplitter {
+            reader: self.reader(),
+            schema: self.schema,
+            current: None,
+        }
+    }
 }
 
 fn concat_labels(labels: &[Label]) -> String {
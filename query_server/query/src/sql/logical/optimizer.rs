@@ -24,12 +24,14 @@ use datafusion::optimizer::scalar_subquery_to_join::ScalarSubqueryToJoin;
 use datafusion::optimizer::simplify_expressions::SimplifyExpressions;
 use datafusion::optimizer::single_distinct_to_groupby::SingleDistinctToGroupBy;
 use datafusion::optimizer::unwrap_cast_in_comparison::UnwrapCastInComparison;
-use datafusion::optimizer::OptimizerRule;
+use datafusion::optimizer::{OptimizerContext, OptimizerRule};
 use spi::query::analyzer::AnalyzerRef;
 use spi::query::session::SessionCtx;
 use spi::Result;
 use trace::debug;
 
+use crate::extension::logical::optimizer_rule::push_down_first_last::PushDownFirstLast;
+use crate::extension::logical::optimizer_rule::rewrite_materialized_view::RewriteMaterializedView;
 use crate::extension::logical::optimizer_rule::rewrite_tag_scan::RewriteTagScan;
 use crate::sql::analyzer::DefaultAnalyzer;
 
@@ -98,6 +100,7 @@ impl Default for DefaultLogicalOptimizer {
             Arc::new(PushDownLimit::new()),
             // df default rules end
             // cnosdb rules
+            Arc::new(PushDownFirstLast {}),
             Arc::new(RewriteTagScan {}),
         ];
 
@@ -107,6 +110,12 @@ impl Default for DefaultLogicalOptimizer {
 
 impl LogicalOptimizer for DefaultLogicalOptimizer {
     fn optimize(&self, plan: &LogicalPlan, session: &SessionCtx) -> Result<LogicalPlan> {
+        // The materialized views are matched by the sources of the table scans,
+        // which are replaced by the analyzer
+        let rewritten_plan =
+            RewriteMaterializedView {}.try_optimize(plan, &OptimizerContext::new())?;
+        let plan = rewritten_plan.as_ref().unwrap_or(plan);
+
         let analyzed_plan = {
             let mut span_recorder = session.get_child_span_recorder("analyze plan");
 
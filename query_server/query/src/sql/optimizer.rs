@@ -153,10 +153,12 @@ mod test {
     use std::sync::Arc;
 
     use coordinator::service_mock::{MockCoordinator, WITH_NONEMPTY_DATABASE_FOR_TEST};
+    use datafusion::arrow::datatypes::TimeUnit;
     use datafusion::datasource::provider_as_source;
     use datafusion::error::Result;
     use datafusion::execution::context::SessionState;
     use datafusion::execution::runtime_env::RuntimeEnv;
+    use datafusion::logical_expr::expr::AggregateUDF;
     use datafusion::logical_expr::{LogicalPlan, LogicalPlanBuilder, UNNAMED_TABLE};
     use datafusion::optimizer::optimizer::Optimizer;
     use datafusion::optimizer::{OptimizerContext, OptimizerRule};
@@ -166,9 +168,15 @@ mod test {
     use meta::model::meta_tenant::TenantMeta;
     use models::schema::{ColumnType, TableColumn, TskvTableSchema};
     use models::ValueType;
+    use spi::query::function::FunctionMetadataManager;
 
     use crate::data_source::batch::tskv::ClusterTable;
     use crate::data_source::split;
+    use crate::extension::expr::aggregate_function::{
+        register_udafs, FIRST_UDAF_NAME, LAST_UDAF_NAME,
+    };
+    use crate::extension::logical::optimizer_rule::push_down_first_last::PushDownFirstLast;
+    use crate::function::simple_func_manager::SimpleFunctionMetadataManager;
 
     fn observe(_plan: &LogicalPlan, _rule: &dyn OptimizerRule) {}
 
@@ -558,4 +566,98 @@ mod test {
             \n",
         ).await
     }
+
+    #[tokio::test]
+    async fn test_first_last_without_group() -> Result<()> {
+        let mut func_manager = SimpleFunctionMetadataManager::default();
+        register_udafs(&mut func_manager).unwrap();
+        let udaf = |name: &str, value: &str| {
+            Expr::AggregateUDF(AggregateUDF {
+                fun: func_manager.udaf(name).unwrap(),
+                args: vec![col("time"), col(value)],
+                filter: None,
+                order_by: None,
+            })
+        };
+
+        let mut schema = TskvTableSchema::default();
+        schema.add_column(TableColumn::new_time_column(0, TimeUnit::Nanosecond));
+        schema.add_column(TableColumn::new_with_default(
+            "flag".to_string(),
+            ColumnType::Tag,
+        ));
+        schema.add_column(TableColumn::new_with_default(
+            "value".to_string(),
+            ColumnType::Field(ValueType::Integer),
+        ));
+        schema.db = WITH_NONEMPTY_DATABASE_FOR_TEST.to_string();
+        let provider = Arc::new(ClusterTable::new(
+            Arc::new(MockCoordinator::default()),
+            split::default_split_manager_ref_only_for_test(),
+            Arc::new(TenantMeta::mock()),
+            Arc::new(schema),
+        ));
+        let scan =
+            LogicalPlanBuilder::scan(UNNAMED_TABLE, provider_as_source(provider), None)?.build()?;
+
+        let plan = LogicalPlanBuilder::from(scan.clone())
+            .aggregate(
+                Vec::<Expr>::new(),
+                vec![
+                    udaf(FIRST_UDAF_NAME, "value"),
+                    udaf(LAST_UDAF_NAME, "value"),
+                ],
+            )?
+            .build()?;
+        let rule: Arc<dyn OptimizerRule + Send + Sync> = Arc::new(PushDownFirstLast {});
+        let opt_plan = Optimizer::with_rules(vec![rule]).optimize(
+            &optimize_plan(&plan)?,
+            &OptimizerContext::new().with_skip_failing_rules(false),
+            &observe,
+        )?;
+        // The output of the aggregation is not changed
+        assert_eq!(opt_plan.schema(), plan.schema());
+        // The timestamps and values of first and last are returned by the table scan
+        let LogicalPlan::Aggregate(aggregate) = &opt_plan else {
+            panic!("expect aggregate, but found:\n{opt_plan:?}");
+        };
+        let LogicalPlan::TableScan(scan) = aggregate.input.as_ref() else {
+            panic!("expect table scan, but found:\n{opt_plan:?}");
+        };
+        assert_eq!(scan.agg_with_grouping.as_ref().unwrap().agg_expr.len(), 2);
+        assert_eq!(scan.projected_schema.fields().len(), 4);
+
+        let physical_plan = DefaultPhysicalPlanner::default()
+            .create_physical_plan(
+                &opt_plan,
+                &SessionState::with_config_rt(
+                    SessionConfig::default().with_target_partitions(8),
+                    Arc::new(RuntimeEnv::default()),
+                ),
+            )
+            .await?;
+        let physical_plan_str = format!("{}", displayable(physical_plan.as_ref()).indent(false));
+        assert!(
+            physical_plan_str
+                .contains("AggregateFilterTskvExec: agg=[[First(\"value\"), Last(\"value\")]]"),
+            "{physical_plan_str}"
+        );
+
+        // Aggregation with a group by or a tag is not pushed down
+        for plan in [
+            LogicalPlanBuilder::from(scan.clone())
+                .aggregate(vec![col("flag")], vec![udaf(FIRST_UDAF_NAME, "value")])?
+                .build()?,
+            LogicalPlanBuilder::from(scan)
+                .aggregate(Vec::<Expr>::new(), vec![udaf(LAST_UDAF_NAME, "flag")])?
+                .build()?,
+        ] {
+            let plan = optimize_plan(&plan)?;
+            assert!(PushDownFirstLast {}
+                .try_optimize(&plan, &OptimizerContext::new())?
+                .is_none());
+        }
+
+        Ok(())
+    }
 }
@@ -6,22 +6,23 @@ use std::str::FromStr;
 use datafusion::common::parsers::CompressionTypeVariant;
 use datafusion::sql::parser::CreateExternalTable;
 use datafusion::sql::sqlparser::ast::{
-    DataType, Expr, Ident, ObjectName, Offset, OrderByExpr, SqlOption, TableFactor,
+    DataType, Expr, Ident, ObjectName, Offset, OrderByExpr, SqlOption, TableFactor, Value,
 };
 use datafusion::sql::sqlparser::dialect::keywords::Keyword;
 use datafusion::sql::sqlparser::dialect::{Dialect, GenericDialect};
 use datafusion::sql::sqlparser::parser::{IsOptional, Parser, ParserError};
-use datafusion::sql::sqlparser::tokenizer::{Token, TokenWithLocation, Tokenizer};
+use datafusion::sql::sqlparser::tokenizer::{Location, Token, Tokenizer};
 use models::codec::Encoding;
 use models::meta_data::{NodeId, ReplicationSetId, VnodeId};
 use snafu::ResultExt;
 use spi::query::ast::{
     self, parse_string_value, Action, AlterDatabase, AlterTable, AlterTableAction, AlterTenant,
-    AlterTenantOperation, AlterUser, AlterUserOperation, ChecksumGroup, ColumnOption, CompactVnode,
-    CopyIntoLocation, CopyIntoTable, CopyTarget, CopyVnode, CreateDatabase, CreateRole,
-    CreateStream, CreateTable, CreateTenant, CreateUser, DatabaseOptions, DescribeDatabase,
-    DescribeTable, DropDatabaseObject, DropGlobalObject, DropTenantObject, DropVnode, Explain,
-    ExtStatement, GrantRevoke, MoveVnode, OutputMode, Privilege, ShowSeries, ShowTagBody,
+    AlterTenantOperation, AlterUser, AlterUserOperation, BackupDatabase, ChecksumGroup,
+    ColumnOption, CompactVnode, CopyIntoLocation, CopyIntoTable, CopyTarget, CopyVnode,
+    CreateDatabase, CreateRole, CreateStream, CreateTable, CreateTenant, CreateUser,
+    DatabaseOptions, DecommissionNode, DescribeDatabase, DescribeTable, DropDatabaseObject,
+    DropGlobalObject, DropTenantObject, DropVnode, Explain, ExtStatement, GrantRevoke, MoveVnode,
+    OutputMode, Privilege, RepairReplicationSet, RestoreDatabase, ShowSeries, ShowTagBody,
     ShowTagValues, Trigger, UriLocation, With,
 };
 use spi::query::logical_planner::{DatabaseObjectType, GlobalObjectType, TenantObjectType};
@@ -53,6 +54,10 @@ enum CnosKeyWord {
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     VNODE_DURATION,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    COLD_DURATION,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    COLD_AFTER,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     REPLICA,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     PRECISION,
@@ -91,6 +96,10 @@ enum CnosKeyWord {
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     CHECKSUM,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    BACKUP,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    RESTORE,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     STREAM,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     STREAMS,
@@ -108,6 +117,23 @@ enum CnosKeyWord {
     APPEND,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     UNSET,
+
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    CONTINUOUS,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    QUERY,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    RESAMPLE,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    EVERY,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    DECOMMISSION,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    REBALANCE,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    REPAIR,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    REPLICATION,
 }
 
 impl FromStr for CnosKeyWord {
@@ -121,6 +147,8 @@ impl FromStr for CnosKeyWord {
             "TTL" => Ok(CnosKeyWord::TTL),
             "SHARD" => Ok(CnosKeyWord::SHARD),
             "VNODE_DURATION" => Ok(CnosKeyWord::VNODE_DURATION),
+            "COLD_DURATION" => Ok(CnosKeyWord::COLD_DURATION),
+            "COLD_AFTER" => Ok(CnosKeyWord::COLD_AFTER),
             "REPLICA" => Ok(CnosKeyWord::REPLICA),
             "PRECISION" => Ok(CnosKeyWord::PRECISION),
             "DATABASES" => Ok(CnosKeyWord::DATABASES),
@@ -140,6 +168,8 @@ impl FromStr for CnosKeyWord {
             "MOVE" => Ok(CnosKeyWord::MOVE),
             "COMPACT" => Ok(CnosKeyWord::COMPACT),
             "CHECKSUM" => Ok(CnosKeyWord::CHECKSUM),
+            "BACKUP" => Ok(CnosKeyWord::BACKUP),
+            "RESTORE" => Ok(CnosKeyWord::RESTORE),
             "STREAM" => Ok(CnosKeyWord::STREAM),
             "STREAMS" => Ok(CnosKeyWord::STREAMS),
             "TRIGGER" => Ok(CnosKeyWord::TRIGGER),
@@ -149,6 +179,14 @@ impl FromStr for CnosKeyWord {
             "COMPLETE" => Ok(CnosKeyWord::COMPLETE),
             "APPEND" => Ok(CnosKeyWord::APPEND),
             "UNSET" => Ok(CnosKeyWord::UNSET),
+            "CONTINUOUS" => Ok(CnosKeyWord::CONTINUOUS),
+            "QUERY" => Ok(CnosKeyWord::QUERY),
+            "RESAMPLE" => Ok(CnosKeyWord::RESAMPLE),
+            "EVERY" => Ok(CnosKeyWord::EVERY),
+            "DECOMMISSION" => Ok(CnosKeyWord::DECOMMISSION),
+            "REBALANCE" => Ok(CnosKeyWord::REBALANCE),
+            "REPAIR" => Ok(CnosKeyWord::REPAIR),
+            "REPLICATION" => Ok(CnosKeyWord::REPLICATION),
             _ => Err(ParserError::ParserError(format!(
                 "fail parse {} to CnosKeyWord",
                 s
@@ -178,20 +216,24 @@ impl CnosdbParser for DefaultParser {
 /// SQL Parser
 pub struct ExtParser<'a> {
     parser: Parser<'a>,
+    /// The source of the tokens, for the parts that can't be parsed from tokens
+    sql: &'a str,
 }
 
 impl<'a> ExtParser<'a> {
     /// Parse the specified tokens
-    pub fn new(sql: &str) -> Result<Self> {
+    pub fn new(sql: &'a str) -> Result<Self> {
         let dialect = &GenericDialect {};
         ExtParser::new_with_dialect(sql, dialect)
     }
     /// Parse the specified tokens with dialect
-    fn new_with_dialect(sql: &str, dialect: &'a dyn Dialect) -> Result<Self> {
-        let mut tokenizer = Tokenizer::new(dialect, sql);
-        let tokens = tokenizer.tokenize()?;
+    fn new_with_dialect(sql: &'a str, dialect: &'a dyn Dialect) -> Result<Self> {
+        let masked_sql = mask_slash_regexes(sql);
+        let mut tokenizer = Tokenizer::new(dialect, &masked_sql);
+        let tokens = tokenizer.tokenize_with_location()?;
         Ok(ExtParser {
-            parser: Parser::new(dialect).with_tokens(tokens),
+            parser: Parser::new(dialect).with_tokens_with_locations(tokens),
+            sql,
         })
     }
 
@@ -287,6 +329,22 @@ impl<'a> ExtParser<'a> {
                                 self.parser.next_token();
                                 self.parse_checksum()
                             }
+                            CnosKeyWord::BACKUP => {
+                                self.parser.next_token();
+                                self.parse_backup()
+                            }
+                            CnosKeyWord::RESTORE => {
+                                self.parser.next_token();
+                                self.parse_restore()
+                            }
+                            CnosKeyWord::DECOMMISSION => {
+                                self.parser.next_token();
+                                self.parse_decommission()
+                            }
+                            CnosKeyWord::REPAIR => {
+                                self.parser.next_token();
+                                self.parse_repair()
+                            }
                             _ => Ok(ExtStatement::SqlStatement(Box::new(
                                 self.parser.parse_statement()?,
                             ))),
@@ -334,9 +392,11 @@ impl<'a> ExtParser<'a> {
                 .then_some(true)
                 .unwrap_or_default();
             Ok(ExtStatement::ShowStreams(ast::ShowStreams { verbose }))
+        } else if self.parse_cnos_keyword(CnosKeyWord::REBALANCE) {
+            Ok(ExtStatement::ShowRebalance)
         } else {
             self.expected(
-                "TABLES or DATABASES or SERIES or TAG or QUERIES or STREAMS",
+                "TABLES or DATABASES or SERIES or TAG or QUERIES or STREAMS or REBALANCE",
                 self.parser.peek_token(),
             )
         }
@@ -389,8 +449,15 @@ impl<'a> ExtParser<'a> {
             .expect_keywords(&[Keyword::WITH, Keyword::KEY])?;
 
         match self.parser.next_token().token {
-            Token::Eq => Ok(With::Equal(self.parser.parse_identifier()?)),
+            Token::Eq => {
+                if self.parser.consume_token(&Token::Tilde) {
+                    Ok(With::Match(self.parse_regex()?))
+                } else {
+                    Ok(With::Equal(self.parser.parse_identifier()?))
+                }
+            }
             Token::Neq => Ok(With::UnEqual(self.parser.parse_identifier()?)),
+            Token::ExclamationMarkTilde => Ok(With::UnMatch(self.parse_regex()?)),
             Token::Word(word) => match &word.keyword {
                 Keyword::IN => {
                     self.parser.expect_token(&Token::LParen)?;
@@ -409,9 +476,37 @@ impl<'a> ExtParser<'a> {
                     self.parser.expect_token(&Token::RParen)?;
                     Ok(With::NotIn(idents))
                 }
-                _ => self.expected("=, !=, <>, IN, NOT IN", Token::Word(word)),
+                _ => self.expected("=, !=, <>, =~, !~, IN, NOT IN", Token::Word(word)),
             },
-            token => self.expected("=, !=, <>, IN, NOT IN", token),
+            token => self.expected("=, !=, <>, =~, !~, IN, NOT IN", token),
+        }
+    }
+
+    /// Parse a regular expression like `/^host.*/` or `'^host.*'`
+    ///
+    /// The pattern between slashes is read from the source as is, `\/` is a slash in
+    /// the pattern. The pattern isn't tokenized, so it may contain any character, e.g. `/it's/`.
+    fn parse_regex(&mut self) -> Result<Value> {
+        let token = self.parser.next_token();
+        match token.token {
+            Token::SingleQuotedString(pattern) => Ok(Value::SingleQuotedString(pattern)),
+            Token::Div => {
+                let start = byte_offset(self.sql, &token.location) + 1;
+                let Some((pattern, end)) = slash_delimited(&self.sql[start..]) else {
+                    return self.expected("/", Token::EOF);
+                };
+                // Skip the tokens of the pattern and the closing slash.
+                let end = start + end;
+                loop {
+                    let next = self.parser.peek_token();
+                    if next.token == Token::EOF || byte_offset(self.sql, &next.location) > end {
+                        break;
+                    }
+                    self.parser.next_token();
+                }
+                Ok(Value::SingleQuotedString(pattern))
+            }
+            token => self.expected("regular expression", token),
         }
     }
 
@@ -544,8 +639,10 @@ impl<'a> ExtParser<'a> {
             self.parse_alter_table_alter_column(table_name)
         } else if self.parser.parse_keyword(Keyword::DROP) {
             self.parse_alter_table_drop_column(table_name)
+        } else if self.parser.parse_keyword(Keyword::RENAME) {
+            self.parse_alter_table_rename_column(table_name)
         } else {
-            self.expected("ADD or ALTER or DROP", self.parser.peek_token())
+            self.expected("ADD or ALTER or DROP or RENAME", self.parser.peek_token())
         }
     }
 
@@ -576,8 +673,34 @@ impl<'a> ExtParser<'a> {
         }))
     }
 
+    /// Parse: RENAME COLUMN old_column_name TO new_column_name
+    fn parse_alter_table_rename_column(&mut self, table_name: ObjectName) -> Result<ExtStatement> {
+        self.parser.expect_keyword(Keyword::COLUMN)?;
+        let old_column_name = self.parser.parse_identifier()?;
+        self.parser.expect_keyword(Keyword::TO)?;
+        let new_column_name = self.parser.parse_identifier()?;
+        Ok(ExtStatement::AlterTable(AlterTable {
+            table_name,
+            alter_action: AlterTableAction::RenameColumn {
+                old_column_name,
+                new_column_name,
+            },
+        }))
+    }
+
     fn parse_alter_table_alter_column(&mut self, table_name: ObjectName) -> Result<ExtStatement> {
         let column_name = self.parser.parse_identifier()?;
+        // parse: TYPE data_type
+        if self.parser.parse_keyword(Keyword::TYPE) {
+            let data_type = self.parse_column_type()?;
+            return Ok(ExtStatement::AlterTable(AlterTable {
+                table_name,
+                alter_action: AlterTableAction::AlterColumnType {
+                    column_name,
+                    data_type,
+                },
+            }));
+        }
         // parse: SET CODEC(encoding_type)
         self.parser.expect_keyword(Keyword::SET)?;
 
@@ -864,6 +987,10 @@ impl<'a> ExtParser<'a> {
             options.replica = Some(self.parse_number::<u64>()?);
         } else if self.parse_cnos_keyword(CnosKeyWord::PRECISION) {
             options.precision = Some(self.parse_string_value()?);
+        } else if self.parse_cnos_keyword(CnosKeyWord::COLD_DURATION) {
+            options.cold_duration = Some(self.parse_string_value()?);
+        } else if self.parse_cnos_keyword(CnosKeyWord::COLD_AFTER) {
+            options.cold_after = Some(self.parse_string_value()?);
         } else {
             return Ok(false);
         }
@@ -1156,6 +1283,80 @@ impl<'a> ExtParser<'a> {
         }))
     }
 
+    /// e.g.
+    /// CREATE CONTINUOUS QUERY IF NOT EXISTS cq ON public
+    /// RESAMPLE EVERY 1m FOR 10m
+    /// AS SELECT date_bin(INTERVAL '1 minute', time) AS time, name, avg(elevation) AS elevation
+    ///   INTO readings_1m FROM readings GROUP BY date_bin(INTERVAL '1 minute', time), name;
+    fn parse_create_continuous_query(&mut self) -> Result<ExtStatement> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_identifier()?;
+        self.parser.expect_keyword(Keyword::ON)?;
+        let database = self.parser.parse_identifier()?;
+
+        self.expect_cnos_keyword(CnosKeyWord::RESAMPLE)?;
+        self.expect_cnos_keyword(CnosKeyWord::EVERY)?;
+        let resample_every = self.parse_duration_value()?;
+        let resample_for = if self.parser.parse_keyword(Keyword::FOR) {
+            Some(self.parse_duration_value()?)
+        } else {
+            None
+        };
+
+        self.parser.expect_keyword(Keyword::AS)?;
+        let query = Box::new(self.parser.parse_query()?);
+
+        Ok(ExtStatement::CreateContinuousQuery(
+            ast::CreateContinuousQuery {
+                if_not_exists,
+                name,
+                database,
+                resample_every,
+                resample_for,
+                query,
+            },
+        ))
+    }
+
+    /// e.g.
+    /// CREATE MATERIALIZED VIEW IF NOT EXISTS readings_1h
+    /// AS SELECT time_window(time, interval '1 hour'), name, avg(elevation) AS elevation
+    ///   FROM readings GROUP BY time_window(time, interval '1 hour'), name;
+    fn parse_create_materialized_view(&mut self) -> Result<ExtStatement> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_object_name()?;
+        self.parser.expect_keyword(Keyword::AS)?;
+        let query = Box::new(self.parser.parse_query()?);
+
+        Ok(ExtStatement::CreateMaterializedView(
+            ast::CreateMaterializedView {
+                if_not_exists,
+                name,
+                query,
+            },
+        ))
+    }
+
+    /// Parse a duration, both '10m' and 10m are accepted.
+    fn parse_duration_value(&mut self) -> Result<String> {
+        let token = self.parser.next_token();
+        match token.token {
+            Token::SingleQuotedString(s) => Ok(s),
+            Token::Number(n, _) => match self.parser.peek_token().token {
+                Token::Word(w) if w.quote_style.is_none() => {
+                    self.parser.next_token();
+                    Ok(format!("{n}{}", w.value))
+                }
+                _ => Ok(n),
+            },
+            _ => self.expected("a duration, e.g. '10m'", token),
+        }
+    }
+
     /// Parse a SQL CREATE statement
     fn parse_create(&mut self) -> Result<ExtStatement> {
         // Currently only supports the creation of external tables
@@ -1176,6 +1377,12 @@ impl<'a> ExtParser<'a> {
             self.parse_create_role()
         } else if self.parse_cnos_keyword(CnosKeyWord::STREAM) {
             self.parse_create_stream()
+        } else if self.parse_cnos_keyword(CnosKeyWord::CONTINUOUS) {
+            self.expect_cnos_keyword(CnosKeyWord::QUERY)?;
+            self.parse_create_continuous_query()
+        } else if self.parser.parse_keyword(Keyword::MATERIALIZED) {
+            self.parser.expect_keyword(Keyword::VIEW)?;
+            self.parse_create_materialized_view()
         } else {
             self.expected("an object type after CREATE", self.parser.peek_token())
         }
@@ -1405,9 +1612,25 @@ impl<'a> ExtParser<'a> {
             let if_exist = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
             let name = self.parser.parse_identifier()?;
             ExtStatement::DropStream(ast::DropStream { if_exist, name })
+        } else if self.parse_cnos_keyword(CnosKeyWord::CONTINUOUS) {
+            self.expect_cnos_keyword(CnosKeyWord::QUERY)?;
+            let if_exist = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let name = self.parser.parse_identifier()?;
+            self.parser.expect_keyword(Keyword::ON)?;
+            let database = self.parser.parse_identifier()?;
+            ExtStatement::DropContinuousQuery(ast::DropContinuousQuery {
+                if_exist,
+                name,
+                database,
+            })
+        } else if self.parser.parse_keyword(Keyword::MATERIALIZED) {
+            self.parser.expect_keyword(Keyword::VIEW)?;
+            let if_exist = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let name = self.parser.parse_object_name()?;
+            ExtStatement::DropMaterializedView(ast::DropMaterializedView { if_exist, name })
         } else {
             return self.expected(
-                "TABLE,DATABASE,TENANT,USER,ROLE,VNODE,STREAM after DROP",
+                "TABLE,DATABASE,TENANT,USER,ROLE,VNODE,STREAM,CONTINUOUS QUERY,MATERIALIZED VIEW after DROP",
                 self.parser.peek_token(),
             );
         };
@@ -1429,6 +1652,15 @@ impl<'a> ExtParser<'a> {
         }
     }
 
+    fn parse_decommission(&mut self) -> Result<ExtStatement> {
+        if self.parse_cnos_keyword(CnosKeyWord::NODE) {
+            let node_id = self.parse_number::<NodeId>()?;
+            Ok(ExtStatement::DecommissionNode(DecommissionNode { node_id }))
+        } else {
+            parser_err!("expected NODE, after DECOMMISSION")
+        }
+    }
+
     fn parse_compact(&mut self) -> Result<ExtStatement> {
         if self.parse_cnos_keyword(CnosKeyWord::VNODE) {
             let mut vnode_ids = Vec::new();
@@ -1455,6 +1687,57 @@ impl<'a> ExtParser<'a> {
         }
     }
 
+    /// Parse `REPAIR REPLICATION SET <id>`
+    fn parse_repair(&mut self) -> Result<ExtStatement> {
+        if self.parse_cnos_keyword(CnosKeyWord::REPLICATION)
+            && self.parser.parse_keyword(Keyword::SET)
+        {
+            let replication_set_id = self.parse_number::<ReplicationSetId>()?;
+            Ok(ExtStatement::RepairReplicationSet(RepairReplicationSet {
+                replication_set_id,
+            }))
+        } else {
+            parser_err!("Expected REPLICATION SET, after REPAIR")
+        }
+    }
+
+    /// Parse `BACKUP DATABASE <name> TO '<location>' [CONNECTION = (...)]`
+    fn parse_backup(&mut self) -> Result<ExtStatement> {
+        self.parser.expect_keyword(Keyword::DATABASE)?;
+        let name = self.parser.parse_identifier()?;
+        self.parser.expect_keyword(Keyword::TO)?;
+        let location = self.parse_backup_location()?;
+        Ok(ExtStatement::BackupDatabase(BackupDatabase {
+            name,
+            location,
+        }))
+    }
+
+    /// Parse `RESTORE DATABASE <name> FROM '<location>' [CONNECTION = (...)]`
+    fn parse_restore(&mut self) -> Result<ExtStatement> {
+        self.parser.expect_keyword(Keyword::DATABASE)?;
+        let name = self.parser.parse_identifier()?;
+        self.parser.expect_keyword(Keyword::FROM)?;
+        let location = self.parse_backup_location()?;
+        Ok(ExtStatement::RestoreDatabase(RestoreDatabase {
+            name,
+            location,
+        }))
+    }
+
+    fn parse_backup_location(&mut self) -> Result<UriLocation> {
+        let path = self.parser.parse_literal_string()?;
+        let connection_options = if self.parser.parse_keyword(Keyword::CONNECTION) {
+            self.parse_options()?
+        } else {
+            Default::default()
+        };
+        Ok(UriLocation {
+            path,
+            connection_options,
+        })
+    }
+
     fn consume_token(&mut self, expected: &Token) -> bool {
         if self.parser.peek_token().token == *expected {
             self.parser.next_token();
@@ -1604,6 +1887,91 @@ fn parse_file_type(s: &str) -> Result<String, ParserError> {
     Ok(s.to_uppercase())
 }
 
+/// Returns the byte offset of the location in the source, locations are counted in
+/// characters from 1.
+fn byte_offset(sql: &str, location: &Location) -> usize {
+    let (mut line, mut column) = (1, 1);
+    for (i, c) in sql.char_indices() {
+        if line == location.line && column == location.column {
+            return i;
+        }
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    sql.len()
+}
+
+/// Replaces the patterns of the regular expressions like `~ /pattern/` with spaces, so that
+/// the characters of patterns are not tokenized, e.g. the unbalanced quote in `/it's/`.
+/// Lines and columns of the other tokens are kept, the patterns are read from the source.
+fn mask_slash_regexes(sql: &str) -> String {
+    let mut masked = String::with_capacity(sql.len());
+    let mut pos = 0;
+    while let Some(c) = sql[pos..].chars().next() {
+        let rest = &sql[pos..];
+        let len = match c {
+            // A doubled quote is read as two quoted parts.
+            '\'' | '"' | '`' => rest[1..].find(c).map_or(rest.len(), |i| i + 2),
+            '-' if rest.starts_with("--") => rest.find('\n').unwrap_or(rest.len()),
+            '/' if rest.starts_with("/*") => rest.find("*/").map_or(rest.len(), |i| i + 2),
+            '~' => {
+                let regex_pos = rest.len() - rest[1..].trim_start().len();
+                let pattern = rest[regex_pos..]
+                    .strip_prefix('/')
+                    .filter(|p| !p.starts_with('*'))
+                    .and_then(slash_delimited);
+                match pattern {
+                    Some((_, end)) => {
+                        let pattern_pos = regex_pos + 1;
+                        masked.push_str(&rest[..pattern_pos]);
+                        masked.extend(rest[pattern_pos..pattern_pos + end].chars().map(|c| {
+                            if c == '\n' {
+                                c
+                            } else {
+                                ' '
+                            }
+                        }));
+                        masked.push('/');
+                        pos += pattern_pos + end + 1;
+                        continue;
+                    }
+                    None => 1,
+                }
+            }
+            c => c.len_utf8(),
+        };
+        masked.push_str(&rest[..len]);
+        pos += len;
+    }
+    masked
+}
+
+/// Returns the text before the first unescaped slash and the offset of the slash, `\/` in
+/// the text is unescaped.
+fn slash_delimited(s: &str) -> Option<(String, usize)> {
+    let mut text = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '/' => return Some((text, i)),
+            '\\' => match chars.next() {
+                Some((_, '/')) => text.push('/'),
+                Some((_, c)) => {
+                    text.push('\\');
+                    text.push(c);
+                }
+                None => text.push('\\'),
+            },
+            c => text.push(c),
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::Deref;
@@ -1924,14 +2292,14 @@ mod tests {
 
     #[test]
     fn test_create_database() {
-        let sql = "CREATE DATABASE test WITH TTl '10d' SHARD 5 VNOdE_DURATiON '3d' REPLICA 10 pRECISIOn 'us';";
+        let sql = "CREATE DATABASE test WITH TTl '10d' SHARD 5 VNOdE_DURATiON '3d' REPLICA 10 pRECISIOn 'us' COLD_DURATION '30d' COLD_AFTER '90d';";
         let statements = ExtParser::parse_sql(sql).unwrap();
         assert_eq!(statements.len(), 1);
         match statements[0] {
             ExtStatement::CreateDatabase(ref stmt) => {
                 let ans = format!("{:?}", stmt);
                 println!("{ans}");
-                let expectd = "CreateDatabase { name: Ident { value: \"test\", quote_style: None }, if_not_exists: false, options: DatabaseOptions { ttl: Some(\"10d\"), shard_num: Some(5), vnode_duration: Some(\"3d\"), replica: Some(10), precision: Some(\"us\") } }";
+                let expectd = "CreateDatabase { name: Ident { value: \"test\", quote_style: None }, if_not_exists: false, options: DatabaseOptions { ttl: Some(\"10d\"), shard_num: Some(5), vnode_duration: Some(\"3d\"), replica: Some(10), precision: Some(\"us\"), cold_duration: Some(\"30d\"), cold_after: Some(\"90d\") } }";
                 assert_eq!(ans, expectd);
             }
             _ => panic!("impossible"),
@@ -1953,6 +2321,8 @@ mod tests {
             ALTER TABLE m DROP f;
             ALTER TABLE m ALTER f SET CODEC(DEFAULT);
             ALTER TABLE m ALTER TIME SET CODEC(NULL);
+            ALTER TABLE m ALTER f TYPE DOUBLE;
+            ALTER TABLE m RENAME COLUMN f TO f1;
         "#;
         let statement = ExtParser::parse_sql(sql).unwrap();
         let statement: Vec<AlterTable> = statement
@@ -2012,6 +2382,20 @@ mod tests {
                         column_name: Ident::from("TIME"),
                         encoding: Encoding::Null
                     }
+                },
+                AlterTable {
+                    table_name: ObjectName(vec![Ident::from("m")]),
+                    alter_action: AlterTableAction::AlterColumnType {
+                        column_name: Ident::from("f"),
+                        data_type: DataType::Double
+                    }
+                },
+                AlterTable {
+                    table_name: ObjectName(vec![Ident::from("m")]),
+                    alter_action: AlterTableAction::RenameColumn {
+                        old_column_name: Ident::from("f"),
+                        new_column_name: Ident::from("f1")
+                    }
                 }
             ]
         );
@@ -2093,6 +2477,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rebalance_sql() {
+        let statement = ExtParser::parse_sql("decommission node 1001;").unwrap();
+        assert_eq!(
+            statement[0],
+            ExtStatement::DecommissionNode(DecommissionNode { node_id: 1001 })
+        );
+        let statement = ExtParser::parse_sql("show rebalance").unwrap();
+        assert_eq!(statement[0], ExtStatement::ShowRebalance);
+
+        assert!(ExtParser::parse_sql("decommission 1001").is_err());
+    }
+
+    #[test]
+    fn test_repair_replication_set() {
+        let statement = ExtParser::parse_sql("repair replication set 3;").unwrap();
+        assert_eq!(
+            statement[0],
+            ExtStatement::RepairReplicationSet(RepairReplicationSet {
+                replication_set_id: 3
+            })
+        );
+
+        assert!(ExtParser::parse_sql("repair replication 3").is_err());
+    }
+
+    #[test]
+    fn test_backup_restore_database() {
+        let sql =
+            "backup database db1 to 's3://bucket/backup/db1' connection = (region = 'us-east-1');";
+        let statement = ExtParser::parse_sql(sql).unwrap();
+        assert_eq!(
+            statement[0],
+            ExtStatement::BackupDatabase(BackupDatabase {
+                name: Ident::new("db1"),
+                location: UriLocation {
+                    path: "s3://bucket/backup/db1".to_string(),
+                    connection_options: vec![SqlOption {
+                        name: Ident::new("region"),
+                        value: Value::SingleQuotedString("us-east-1".to_string()),
+                    }],
+                },
+            })
+        );
+
+        let sql = "restore database db2 from '/tmp/backup/db1'";
+        let statement = ExtParser::parse_sql(sql).unwrap();
+        assert_eq!(
+            statement[0],
+            ExtStatement::RestoreDatabase(RestoreDatabase {
+                name: Ident::new("db2"),
+                location: UriLocation {
+                    path: "/tmp/backup/db1".to_string(),
+                    connection_options: vec![],
+                },
+            })
+        );
+
+        assert!(ExtParser::parse_sql("backup database db1 's3://bucket/backup'").is_err());
+    }
+
     #[test]
     fn test_parse_copy_into_table_no_error() {
         let sql = r#"
@@ -2202,6 +2647,77 @@ mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_create_continuous_query() {
+        let statement = parse_sql("create continuous query if not exists cq on db resample every 1m for '10m' as select avg(f) into t_1m from t group by date_bin(interval '1 minute', time);");
+
+        match statement {
+            ExtStatement::CreateContinuousQuery(s) => {
+                let ast::CreateContinuousQuery {
+                    if_not_exists,
+                    name,
+                    database,
+                    resample_every,
+                    resample_for,
+                    query,
+                } = s;
+
+                assert!(if_not_exists);
+                assert_eq!(name, Ident::new("cq"));
+                assert_eq!(database, Ident::new("db"));
+                assert_eq!(resample_every, "1m");
+                assert_eq!(resample_for, Some("10m".into()));
+                assert!(query.to_string().contains("INTO t_1m"));
+            }
+            _ => panic!("expect CreateContinuousQuery"),
+        }
+    }
+
+    #[test]
+    fn test_drop_continuous_query() {
+        let result = parse_sql("drop continuous query if exists cq on db;");
+
+        let expected = ExtStatement::DropContinuousQuery(ast::DropContinuousQuery {
+            if_exist: true,
+            name: Ident::new("cq"),
+            database: Ident::new("db"),
+        });
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_create_materialized_view() {
+        let statement = parse_sql("create materialized view if not exists db.mv as select time_window(time, interval '1 hour'), host, avg(cpu) from t group by time_window(time, interval '1 hour'), host;");
+
+        match statement {
+            ExtStatement::CreateMaterializedView(s) => {
+                let ast::CreateMaterializedView {
+                    if_not_exists,
+                    name,
+                    query,
+                } = s;
+
+                assert!(if_not_exists);
+                assert_eq!(name.to_string(), "db.mv");
+                assert!(query.to_string().contains("FROM t GROUP BY"));
+            }
+            _ => panic!("expect CreateMaterializedView"),
+        }
+    }
+
+    #[test]
+    fn test_drop_materialized_view() {
+        let result = parse_sql("drop materialized view if exists mv;");
+
+        let expected = ExtStatement::DropMaterializedView(ast::DropMaterializedView {
+            if_exist: true,
+            name: ObjectName(vec![Ident::new("mv")]),
+        });
+
+        assert_eq!(expected, result);
+    }
+
     #[test]
     fn test_show_streams() {
         let result = parse_sql("show streams verbose;");
@@ -2272,4 +2788,98 @@ mod tests {
             _ => panic!("expect CreateStream"),
         }
     }
+
+    #[test]
+    fn test_show_tag_values_with_regex() {
+        let sql = "SHOW TAG VALUES FROM tb WITH KEY =~ /^ho.*t$/";
+        match parse_sql(sql) {
+            ExtStatement::ShowTagValues(stmt) => {
+                assert_eq!(
+                    stmt.with,
+                    With::Match(Value::SingleQuotedString("^ho.*t$".to_string()))
+                );
+            }
+            _ => panic!("expect ShowTagValues"),
+        }
+
+        let sql = "SHOW TAG VALUES FROM tb WITH KEY !~ 'ho[a-z]'";
+        match parse_sql(sql) {
+            ExtStatement::ShowTagValues(stmt) => {
+                assert_eq!(
+                    stmt.with,
+                    With::UnMatch(Value::SingleQuotedString("ho[a-z]".to_string()))
+                );
+            }
+            _ => panic!("expect ShowTagValues"),
+        }
+    }
+
+    #[test]
+    fn test_show_tag_values_with_escaped_regex() {
+        let cases = [
+            (r"/^host\d+\.local$/", r"^host\d+\.local$"),
+            (r"/a  b\sc/", r"a  b\sc"),
+            (r"/^\/data\/[a-z]+/", r"^/data/[a-z]+"),
+            (r"/'(x|y)'\\/", r"'(x|y)'\\"),
+        ];
+        for (regex, pattern) in cases {
+            let sql = format!("SHOW TAG VALUES FROM tb WITH KEY =~ {regex} LIMIT 1");
+            match parse_sql(&sql) {
+                ExtStatement::ShowTagValues(stmt) => {
+                    assert_eq!(
+                        stmt.with,
+                        With::Match(Value::SingleQuotedString(pattern.to_string()))
+                    );
+                    assert!(stmt.body.limit.is_some());
+                }
+                _ => panic!("expect ShowTagValues"),
+            }
+        }
+
+        // Multi-line statement
+        let sql = "SHOW TAG VALUES\nFROM tb\nWITH KEY =~ /\\w+ \\d/";
+        match parse_sql(sql) {
+            ExtStatement::ShowTagValues(stmt) => {
+                assert_eq!(
+                    stmt.with,
+                    With::Match(Value::SingleQuotedString(r"\w+ \d".to_string()))
+                );
+            }
+            _ => panic!("expect ShowTagValues"),
+        }
+
+        let sql = "SHOW TAG VALUES FROM tb WITH KEY =~ /^host";
+        assert!(ExtParser::parse_sql(sql).is_err());
+    }
+
+    #[test]
+    fn test_show_tag_values_with_quoted_regex() {
+        let cases = [
+            (r"/it's/", r"it's"),
+            (r#"/"host/"#, r#""host"#),
+            (r"/--\/*/", r"--/*"),
+        ];
+        for (regex, pattern) in cases {
+            let sql = format!("SHOW TAG VALUES FROM tb WITH KEY =~ {regex} WHERE ta = 'it''s'");
+            match parse_sql(&sql) {
+                ExtStatement::ShowTagValues(stmt) => {
+                    assert_eq!(
+                        stmt.with,
+                        With::Match(Value::SingleQuotedString(pattern.to_string()))
+                    );
+                    assert_eq!(stmt.body.selection.unwrap().to_string(), "ta = 'it''s'");
+                }
+                _ => panic!("expect ShowTagValues"),
+            }
+        }
+
+        // Slashes in strings are not regular expressions
+        let sql = "SHOW TAG VALUES FROM tb WITH KEY =~ /^a/ WHERE ta = '~ /it'";
+        match parse_sql(sql) {
+            ExtStatement::ShowTagValues(stmt) => {
+                assert_eq!(stmt.body.selection.unwrap().to_string(), "ta = '~ /it'");
+            }
+            _ => panic!("expect ShowTagValues"),
+        }
+    }
 }
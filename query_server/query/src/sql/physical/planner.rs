@@ -25,6 +25,7 @@ use spi::Result;
 use super::optimizer::PhysicalOptimizer;
 use crate::extension::physical::transform_rule::expand::ExpandPlanner;
 use crate::extension::physical::transform_rule::gapfill::GapFillPlanner;
+use crate::extension::physical::transform_rule::range_vector::RangeVectorPlanner;
 use crate::extension::physical::transform_rule::table_writer::TableWriterPlanner;
 use crate::extension::physical::transform_rule::tag_scan::TagScanPlanner;
 
@@ -63,6 +64,7 @@ impl Default for DefaultPhysicalPlanner {
             Arc::new(TagScanPlanner {}),
             Arc::new(ExpandPlanner::new()),
             Arc::new(GapFillPlanner::new()),
+            Arc::new(RangeVectorPlanner::new()),
         ];
 
         // We need to take care of the rule ordering. They may influence each other.
@@ -23,7 +23,7 @@ use datafusion::datasource::listing::{
 use datafusion::error::DataFusionError;
 use datafusion::execution::context::SessionState;
 use datafusion::execution::runtime_env::RuntimeEnv;
-use datafusion::logical_expr::expr::{ScalarFunction, Sort};
+use datafusion::logical_expr::expr::{Between, Cast, InList, ScalarFunction, Sort, TryCast};
 use datafusion::logical_expr::logical_plan::Analyze;
 use datafusion::logical_expr::utils::expr_to_columns;
 use datafusion::logical_expr::{
@@ -31,13 +31,14 @@ use datafusion::logical_expr::{
     EmptyRelation, Explain, Expr, LogicalPlan, LogicalPlanBuilder, Operator, PlanType,
     SubqueryAlias, TableSource, ToStringifiedPlan, Union,
 };
-use datafusion::prelude::{col, SessionConfig};
+use datafusion::prelude::{and, col, or, SessionConfig};
 use datafusion::scalar::ScalarValue;
 use datafusion::sql::parser::CreateExternalTable as AstCreateExternalTable;
 use datafusion::sql::planner::{object_name_to_table_reference, PlannerContext, SqlToRel};
 use datafusion::sql::sqlparser::ast::{
     DataType as SQLDataType, Expr as ASTExpr, Ident, ObjectName, Offset, OrderByExpr, Query,
-    SqlOption, Statement, TableAlias, TableFactor, TimezoneInfo,
+    SelectItem, SetExpr, SqlOption, Statement, TableAlias, TableFactor, TableWithJoins,
+    TimezoneInfo, Value,
 };
 use datafusion::sql::sqlparser::parser::ParserError;
 use datafusion::sql::TableReference;
@@ -48,24 +49,30 @@ use models::auth::privilege::{
 };
 use models::auth::role::{SystemTenantRole, TenantRoleIdentifier};
 use models::auth::user::User;
+use models::codec::Encoding;
+use models::meta_data::ContinuousQueryInfo;
 use models::object_reference::{Resolve, ResolvedTable};
 use models::oid::{Identifier, Oid};
+use models::predicate::domain::Predicate;
 use models::schema::{
     ColumnType, DatabaseOptions, Duration, Precision, TableColumn, Tenant, TskvTableSchema,
-    TskvTableSchemaRef, Watermark, DEFAULT_CATALOG, DEFAULT_DATABASE, TIME_FIELD,
+    TskvTableSchemaRef, Watermark, DEFAULT_CATALOG, DEFAULT_DATABASE, TIME_FIELD, TIME_FIELD_NAME,
 };
 use models::utils::SeqIdGenerator;
 use models::{ColumnId, ValueType};
 use object_store::ObjectStore;
+use regex::Regex;
 use spi::query::ast;
 use spi::query::ast::{
-    AlterDatabase as ASTAlterDatabase, AlterTable as ASTAlterTable,
+    parse_string_value, AlterDatabase as ASTAlterDatabase, AlterTable as ASTAlterTable,
     AlterTableAction as ASTAlterTableAction, AlterTenantOperation, AlterUserOperation,
-    ChecksumGroup as ASTChecksumGroup, ColumnOption, CompactVnode as ASTCompactVnode,
-    CopyIntoTable, CopyTarget, CopyVnode as ASTCopyVnode, CreateDatabase as ASTCreateDatabase,
-    CreateTable as ASTCreateTable, DatabaseOptions as ASTDatabaseOptions,
+    BackupDatabase as ASTBackupDatabase, ChecksumGroup as ASTChecksumGroup, ColumnOption,
+    CompactVnode as ASTCompactVnode, CopyIntoTable, CopyTarget, CopyVnode as ASTCopyVnode,
+    CreateDatabase as ASTCreateDatabase, CreateTable as ASTCreateTable,
+    DatabaseOptions as ASTDatabaseOptions, DecommissionNode as ASTDecommissionNode,
     DescribeDatabase as DescribeDatabaseOptions, DescribeTable as DescribeTableOptions,
     DropVnode as ASTDropVnode, ExtStatement, MoveVnode as ASTMoveVnode,
+    RepairReplicationSet as ASTRepairReplicationSet, RestoreDatabase as ASTRestoreDatabase,
     ShowSeries as ASTShowSeries, ShowTagBody, ShowTagValues as ASTShowTagValues, UriLocation, With,
 };
 use spi::query::datasource::{self, UriSchema};
@@ -74,11 +81,13 @@ use spi::query::logical_planner::{
     sql_options_to_tenant_options, sql_options_to_user_options,
     unset_option_to_alter_tenant_action, AlterDatabase, AlterTable, AlterTableAction, AlterTenant,
     AlterTenantAction, AlterTenantAddUser, AlterTenantSetUser, AlterUser, AlterUserAction,
-    ChecksumGroup, CompactVnode, CopyOptions, CopyOptionsBuilder, CopyVnode, CreateDatabase,
-    CreateRole, CreateStreamTable, CreateTable, CreateTenant, CreateUser, DDLPlan,
-    DatabaseObjectType, DescribeDatabase, DescribeTable, DropDatabaseObject, DropGlobalObject,
-    DropTenantObject, DropVnode, FileFormatOptions, FileFormatOptionsBuilder, GlobalObjectType,
-    GrantRevoke, LogicalPlanner, MoveVnode, Plan, PlanWithPrivileges, QueryPlan, SYSPlan,
+    BackupDatabase, ChecksumGroup, CompactVnode, CopyOptions, CopyOptionsBuilder, CopyVnode,
+    CreateContinuousQuery, CreateDatabase, CreateMaterializedView, CreateRole, CreateStreamTable,
+    CreateTable, CreateTenant, CreateUser, DDLPlan, DatabaseObjectType, DecommissionNode,
+    DeleteFromTable, DescribeDatabase, DescribeTable, DropContinuousQuery, DropDatabaseObject,
+    DropGlobalObject, DropMaterializedView, DropTenantObject, DropVnode, FileFormatOptions,
+    FileFormatOptionsBuilder, GlobalObjectType, GrantRevoke, LogicalPlanner, MoveVnode, Plan,
+    PlanWithPrivileges, QueryPlan, RepairReplicationSet, RestoreDatabase, SYSPlan,
     TenantObjectType,
 };
 use spi::query::session::SessionCtx;
@@ -86,15 +95,18 @@ use spi::{QueryError, Result};
 use trace::{debug, warn};
 use url::Url;
 
+use crate::continuous_query::build_insert_sql;
 use crate::data_source::source_downcast_adapter;
 use crate::data_source::stream::{get_event_time_column, get_watermark_delay};
 use crate::data_source::table_source::{TableHandle, TableSourceAdapter, TEMP_LOCATION_TABLE_NAME};
 use crate::extension::logical::logical_plan_builder::LogicalPlanBuilderExt;
+use crate::materialized_view::{build_materialized_view, time_window_interval};
 use crate::metadata::{
     ContextProviderExtension, DatabaseSet, CLUSTER_SCHEMA, DATABASES_DATABASE_NAME,
     INFORMATION_SCHEMA, INFORMATION_SCHEMA_DATABASES, INFORMATION_SCHEMA_TABLES,
     TABLES_TABLE_DATABASE, TABLES_TABLE_NAME,
 };
+use crate::utils::duration::parse_duration;
 
 /// CnosDB SQL query planner
 pub struct SqlPlanner<'a, S: ContextProviderExtension> {
@@ -188,6 +200,26 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
             ExtStatement::MoveVnode(stmt) => self.move_vnode_to_plan(stmt),
             ExtStatement::CompactVnode(stmt) => self.compact_vnode_to_plan(stmt),
             ExtStatement::ChecksumGroup(stmt) => self.checksum_group_to_plan(stmt),
+            ExtStatement::DecommissionNode(stmt) => self.decommission_node_to_plan(stmt),
+            ExtStatement::RepairReplicationSet(stmt) => self.repair_replication_set_to_plan(stmt),
+            ExtStatement::ShowRebalance => Ok(PlanWithPrivileges {
+                plan: Plan::SYSTEM(SYSPlan::ShowRebalance),
+                privileges: vec![Privilege::Global(GlobalPrivilege::System)],
+            }),
+            ExtStatement::BackupDatabase(stmt) => self.backup_database_to_plan(stmt, session),
+            ExtStatement::RestoreDatabase(stmt) => self.restore_database_to_plan(stmt, session),
+            ExtStatement::CreateContinuousQuery(stmt) => {
+                self.create_continuous_query_to_plan(stmt, session)
+            }
+            ExtStatement::DropContinuousQuery(stmt) => {
+                self.drop_continuous_query_to_plan(stmt, session)
+            }
+            ExtStatement::CreateMaterializedView(stmt) => {
+                self.create_materialized_view_to_plan(stmt, session)
+            }
+            ExtStatement::DropMaterializedView(stmt) => {
+                self.drop_materialized_view_to_plan(stmt, session)
+            }
             ExtStatement::CreateStream(_) => Err(QueryError::NotImplemented {
                 err: "CreateStream Planner.".to_string(),
             }),
@@ -231,6 +263,9 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
                 self.insert_to_plan(sql_object_name, sql_column_names, source, session)
                     .await
             }
+            Statement::Delete {
+                from, selection, ..
+            } => self.delete_to_plan(from, selection, session),
             Statement::Kill { id, .. } => {
                 let plan = Plan::SYSTEM(SYSPlan::KillQuery(id.into()));
                 // TODO privileges
@@ -722,7 +757,12 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
             ASTAlterTableAction::AddColumn { column } => {
                 let mut table_column =
                     self.column_opt_to_table_column(column, ColumnId::default(), time_unit)?;
-                if table_schema.contains_column(&table_column.name) {
+                // Series keys may still have the tag that was converted to a field.
+                let is_converted_tag = table_schema
+                    .columns()
+                    .iter()
+                    .any(|c| c.converted_from_tag.as_deref() == Some(table_column.name.as_str()));
+                if table_schema.contains_column(&table_column.name) || is_converted_tag {
                     return Err(QueryError::ColumnAlreadyExists {
                         table: table_schema.name.to_string(),
                         column: table_column.name,
@@ -779,6 +819,86 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
                 let mut new_column = column.clone();
                 new_column.encoding = encoding;
 
+                AlterTableAction::AlterColumn {
+                    column_name,
+                    new_column,
+                }
+            }
+            ASTAlterTableAction::AlterColumnType {
+                column_name,
+                data_type,
+            } => {
+                let column_name = normalize_ident(column_name);
+                let column = table_schema.column(&column_name).ok_or_else(|| {
+                    QueryError::ColumnNotExists {
+                        column: column_name.to_string(),
+                        table: table_schema.name.to_string(),
+                    }
+                })?;
+                if column.column_type.is_time() {
+                    return Err(QueryError::TimeColumnAlter);
+                }
+
+                let column_type = self.make_data_type(&column_name, &data_type, time_unit)?;
+                // Values stored in files are converted to the new type when read or compacted,
+                // only conversions that never lose values are allowed.
+                if !column.column_type.is_convertible_to(&column_type) {
+                    return Err(QueryError::UnsupportedColumnTypeChange {
+                        column: column_name,
+                        from: match column.column_type {
+                            ColumnType::Tag => "TAG".to_string(),
+                            ref column_type => column_type.to_sql_type_str().to_string(),
+                        },
+                        to: column_type.to_sql_type_str().to_string(),
+                    });
+                }
+
+                let mut new_column = column.clone();
+                if column.column_type.is_tag() {
+                    // Values of the tag are kept in the series keys written before.
+                    new_column.converted_from_tag = Some(column.name.clone());
+                }
+                new_column.column_type = column_type;
+                if !new_column.encoding_valid() {
+                    new_column.encoding = Encoding::Default;
+                }
+
+                AlterTableAction::AlterColumn {
+                    column_name,
+                    new_column,
+                }
+            }
+            ASTAlterTableAction::RenameColumn {
+                old_column_name,
+                new_column_name,
+            } => {
+                let column_name = normalize_ident(old_column_name);
+                let new_column_name = normalize_ident(new_column_name);
+                let column = table_schema.column(&column_name).ok_or_else(|| {
+                    QueryError::ColumnNotExists {
+                        column: column_name.to_string(),
+                        table: table_schema.name.to_string(),
+                    }
+                })?;
+                // Tags are stored in series keys by name.
+                if column.column_type.is_tag() {
+                    return Err(QueryError::RenameTag {
+                        column: column_name,
+                    });
+                }
+                if column.column_type.is_time() {
+                    return Err(QueryError::TimeColumnAlter);
+                }
+                if table_schema.contains_column(&new_column_name) {
+                    return Err(QueryError::ColumnAlreadyExists {
+                        table: table_schema.name.to_string(),
+                        column: new_column_name,
+                    });
+                }
+
+                let mut new_column = column.clone();
+                new_column.name = new_column_name;
+
                 AlterTableAction::AlterColumn {
                     column_name,
                     new_column,
@@ -803,6 +923,72 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         })
     }
 
+    fn delete_to_plan(
+        &self,
+        from: Vec<TableWithJoins>,
+        selection: Option<ASTExpr>,
+        session: &SessionCtx,
+    ) -> Result<PlanWithPrivileges> {
+        let table_name = match from.as_slice() {
+            [TableWithJoins {
+                relation: TableFactor::Table { name, .. },
+                joins,
+            }] if joins.is_empty() => name.clone(),
+            _ => {
+                return Err(QueryError::NotImplemented {
+                    err: "DELETE only supports one table".to_string(),
+                })
+            }
+        };
+        let table_ref = normalize_sql_object_name(table_name)?;
+        let table_name = table_ref
+            .clone()
+            .resolve_object(session.tenant(), session.default_database())?;
+        let table_schema = self.get_tskv_schema(table_ref)?;
+        let df_schema = table_schema.to_arrow_schema().to_dfschema()?;
+
+        // build where
+        let selection = match selection {
+            Some(expr) => {
+                let expr =
+                    self.df_planner
+                        .sql_to_expr(expr, &df_schema, &mut Default::default())?;
+                Some(normalize_delete_expr(expr, &table_schema)?)
+            }
+            None => None,
+        };
+
+        let predicate = Predicate::default().push_down_filter(selection.as_slice(), &table_schema);
+        // A where clause must never be widened to the whole table.
+        if let Some(expr) = &selection {
+            if predicate.filter().is_all() {
+                return Err(QueryError::UnsupportedDeleteExpr {
+                    expr: expr.to_string(),
+                });
+            }
+        }
+        let predicate = predicate
+            .resolve(&table_schema)
+            .map_err(|reason| QueryError::AnalyzePushedFilter { reason })?;
+
+        let plan = Plan::DDL(DDLPlan::DeleteFromTable(DeleteFromTable {
+            table_name,
+            predicate,
+        }));
+
+        // privileges
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::TenantObject(
+                TenantObjectPrivilege::Database(
+                    DatabasePrivilege::Write,
+                    Some(table_schema.db.clone()),
+                ),
+                Some(*session.tenant_id()),
+            )],
+        })
+    }
+
     fn show_databases_to_plan(&self, session: &SessionCtx) -> Result<PlanWithPrivileges> {
         let projections = vec![col(DATABASES_DATABASE_NAME)];
         let sorts = vec![col(DATABASES_DATABASE_NAME).sort(true, true)];
@@ -1147,6 +1333,12 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
                 )),
             })?);
         }
+        if let Some(cold_duration) = options.cold_duration {
+            plan_options.with_cold_duration(self.str_to_duration(&cold_duration)?);
+        }
+        if let Some(cold_after) = options.cold_after {
+            plan_options.with_cold_after(self.str_to_duration(&cold_after)?);
+        }
         Ok(plan_options)
     }
 
@@ -1527,6 +1719,31 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         })
     }
 
+    fn decommission_node_to_plan(&self, stmt: ASTDecommissionNode) -> Result<PlanWithPrivileges> {
+        let ASTDecommissionNode { node_id } = stmt;
+
+        let plan = Plan::DDL(DDLPlan::DecommissionNode(DecommissionNode { node_id }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::Global(GlobalPrivilege::System)],
+        })
+    }
+
+    fn repair_replication_set_to_plan(
+        &self,
+        stmt: ASTRepairReplicationSet,
+    ) -> Result<PlanWithPrivileges> {
+        let ASTRepairReplicationSet { replication_set_id } = stmt;
+
+        let plan = Plan::DDL(DDLPlan::RepairReplicationSet(RepairReplicationSet {
+            replication_set_id,
+        }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::Global(GlobalPrivilege::System)],
+        })
+    }
+
     fn compact_vnode_to_plan(&self, stmt: ASTCompactVnode) -> Result<PlanWithPrivileges> {
         let ASTCompactVnode { vnode_ids } = stmt;
 
@@ -1547,6 +1764,265 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         })
     }
 
+    fn backup_database_to_plan(
+        &self,
+        stmt: ASTBackupDatabase,
+        session: &SessionCtx,
+    ) -> Result<PlanWithPrivileges> {
+        let ASTBackupDatabase { name, location } = stmt;
+        let database_name = normalize_ident(name);
+        let tenant_id = *session.tenant_id();
+
+        let plan = Plan::DDL(DDLPlan::BackupDatabase(BackupDatabase {
+            tenant_name: session.tenant().to_string(),
+            database_name: database_name.clone(),
+            location: location.path,
+            connection_options: sql_options_to_map(&location.connection_options),
+        }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::TenantObject(
+                TenantObjectPrivilege::Database(DatabasePrivilege::Full, Some(database_name)),
+                Some(tenant_id),
+            )],
+        })
+    }
+
+    fn restore_database_to_plan(
+        &self,
+        stmt: ASTRestoreDatabase,
+        session: &SessionCtx,
+    ) -> Result<PlanWithPrivileges> {
+        let ASTRestoreDatabase { name, location } = stmt;
+        let database_name = normalize_ident(name);
+        let tenant_id = *session.tenant_id();
+
+        let plan = Plan::DDL(DDLPlan::RestoreDatabase(RestoreDatabase {
+            tenant_name: session.tenant().to_string(),
+            database_name,
+            location: location.path,
+            connection_options: sql_options_to_map(&location.connection_options),
+        }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::TenantObject(
+                TenantObjectPrivilege::Database(DatabasePrivilege::Write, None),
+                Some(tenant_id),
+            )],
+        })
+    }
+
+    fn create_continuous_query_to_plan(
+        &self,
+        stmt: ast::CreateContinuousQuery,
+        session: &SessionCtx,
+    ) -> Result<PlanWithPrivileges> {
+        let ast::CreateContinuousQuery {
+            if_not_exists,
+            name,
+            database,
+            resample_every,
+            resample_for,
+            query,
+        } = stmt;
+        let database = normalize_ident(database);
+        let tenant_id = *session.tenant_id();
+
+        let resample_every = parse_resample_duration(&resample_every)?;
+        let resample_for = match resample_for {
+            Some(resample_for) => parse_resample_duration(&resample_for)?,
+            None => resample_every,
+        };
+        if resample_for < resample_every {
+            return Err(QueryError::InvalidContinuousQuery {
+                reason: "RESAMPLE FOR must not be less than RESAMPLE EVERY".to_string(),
+            });
+        }
+
+        // Each run must compute whole windows of the query
+        let window = self.continuous_query_window(&query);
+        if let Some(window) = window {
+            if resample_every % window != 0 || resample_for % window != 0 {
+                return Err(QueryError::InvalidContinuousQuery {
+                    reason: format!(
+                        "RESAMPLE EVERY and RESAMPLE FOR must be multiples of the time window {:?}",
+                        std::time::Duration::from_nanos(window as u64)
+                    ),
+                });
+            }
+        }
+
+        // Check the query now rather than on its first run.
+        let query = query.to_string();
+        build_insert_sql(&query, 0, resample_for)?;
+
+        let plan = Plan::DDL(DDLPlan::CreateContinuousQuery(CreateContinuousQuery {
+            tenant_name: session.tenant().to_string(),
+            if_not_exists,
+            info: ContinuousQueryInfo {
+                name: normalize_ident(name),
+                database: database.clone(),
+                resample_every,
+                resample_for,
+                query,
+                owner: session.user().desc().name().to_string(),
+                watermark: None,
+                window,
+            },
+        }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::TenantObject(
+                TenantObjectPrivilege::Database(DatabasePrivilege::Write, Some(database)),
+                Some(tenant_id),
+            )],
+        })
+    }
+
+    /// Returns the width of `time_window(time, <interval>)` or `date_bin(<interval>, time)`
+    /// which the query groups by.
+    fn continuous_query_window(&self, query: &Query) -> Option<i64> {
+        let SetExpr::Select(select) = query.body.as_ref() else {
+            return None;
+        };
+        let schema = DFSchema::new_with_metadata(
+            vec![DFField::new_unqualified(
+                TIME_FIELD_NAME,
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            )],
+            HashMap::new(),
+        )
+        .ok()?;
+
+        // `GROUP BY <position>` and `GROUP BY <alias>` refer to the projection
+        let projected = |expr: &ASTExpr| -> Option<ASTExpr> {
+            match expr {
+                ASTExpr::Value(Value::Number(n, _)) => {
+                    let index = n.parse::<usize>().ok()?.checked_sub(1)?;
+                    match select.projection.get(index)? {
+                        SelectItem::UnnamedExpr(expr) => Some(expr.clone()),
+                        SelectItem::ExprWithAlias { expr, .. } => Some(expr.clone()),
+                        _ => None,
+                    }
+                }
+                ASTExpr::Identifier(ident) => {
+                    select.projection.iter().find_map(|item| match item {
+                        SelectItem::ExprWithAlias { expr, alias } if alias.value == ident.value => {
+                            Some(expr.clone())
+                        }
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }
+        };
+
+        select.group_by.iter().find_map(|expr| {
+            let expr = projected(expr).unwrap_or_else(|| expr.clone());
+            // The other columns are not resolved, they can't be the time window
+            let expr = self
+                .df_planner
+                .sql_to_expr(expr, &schema, &mut Default::default())
+                .ok()?;
+            time_window_interval(&expr)
+        })
+    }
+
+    fn drop_continuous_query_to_plan(
+        &self,
+        stmt: ast::DropContinuousQuery,
+        session: &SessionCtx,
+    ) -> Result<PlanWithPrivileges> {
+        let ast::DropContinuousQuery {
+            if_exist,
+            name,
+            database,
+        } = stmt;
+        let database = normalize_ident(database);
+        let tenant_id = *session.tenant_id();
+
+        let plan = Plan::DDL(DDLPlan::DropContinuousQuery(DropContinuousQuery {
+            tenant_name: session.tenant().to_string(),
+            name: normalize_ident(name),
+            database: database.clone(),
+            if_exist,
+        }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::TenantObject(
+                TenantObjectPrivilege::Database(DatabasePrivilege::Write, Some(database)),
+                Some(tenant_id),
+            )],
+        })
+    }
+
+    fn create_materialized_view_to_plan(
+        &self,
+        stmt: ast::CreateMaterializedView,
+        session: &SessionCtx,
+    ) -> Result<PlanWithPrivileges> {
+        let ast::CreateMaterializedView {
+            if_not_exists,
+            name,
+            query,
+        } = stmt;
+        let name = object_name_to_resolved_table(session, name)?;
+        let database_name = name.database().to_string();
+
+        let sql = query.to_string();
+        let query_plan = self
+            .df_planner
+            .sql_statement_to_plan(Statement::Query(query))?;
+        let mut privileges = databases_privileges(
+            DatabasePrivilege::Read,
+            *session.tenant_id(),
+            self.schema_provider.reset_access_databases(),
+        );
+
+        let (table, view) = build_materialized_view(
+            &name,
+            sql,
+            session.user().desc().name().to_string(),
+            &query_plan,
+        )?;
+
+        let plan = Plan::DDL(DDLPlan::CreateMaterializedView(CreateMaterializedView {
+            if_not_exists,
+            table,
+            view,
+        }));
+        privileges.push(Privilege::TenantObject(
+            TenantObjectPrivilege::Database(DatabasePrivilege::Full, Some(database_name)),
+            Some(*session.tenant_id()),
+        ));
+        Ok(PlanWithPrivileges { plan, privileges })
+    }
+
+    fn drop_materialized_view_to_plan(
+        &self,
+        stmt: ast::DropMaterializedView,
+        session: &SessionCtx,
+    ) -> Result<PlanWithPrivileges> {
+        let ast::DropMaterializedView { if_exist, name } = stmt;
+        let name = object_name_to_resolved_table(session, name)?;
+        let database_name = name.database().to_string();
+
+        let plan = Plan::DDL(DDLPlan::DropMaterializedView(DropMaterializedView {
+            tenant_name: name.tenant().to_string(),
+            database: database_name.clone(),
+            name: name.table().to_string(),
+            if_exist,
+        }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::TenantObject(
+                TenantObjectPrivilege::Database(DatabasePrivilege::Full, Some(database_name)),
+                Some(*session.tenant_id()),
+            )],
+        })
+    }
+
     fn create_stream_table_to_plan(
         &self,
         stmt: Statement,
@@ -2053,6 +2529,127 @@ fn check_show_series_expr(columns: &HashSet<Column>, table_schema: &TskvTableSch
     Ok(())
 }
 
+/// Rewrite the where clause of DELETE to comparisons between tag or time column and
+/// constant, connected by AND/OR, which can be converted to domains without loss.
+fn normalize_delete_expr(expr: Expr, table_schema: &TskvTableSchema) -> Result<Expr> {
+    let unsupported = |expr: &Expr| QueryError::UnsupportedDeleteExpr {
+        expr: expr.to_string(),
+    };
+
+    match expr {
+        Expr::BinaryExpr(BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        }) => Ok(and(
+            normalize_delete_expr(*left, table_schema)?,
+            normalize_delete_expr(*right, table_schema)?,
+        )),
+        Expr::BinaryExpr(BinaryExpr {
+            ref left,
+            op: Operator::Or,
+            ref right,
+        }) => {
+            let left = normalize_delete_expr(left.as_ref().clone(), table_schema)?;
+            let right = normalize_delete_expr(right.as_ref().clone(), table_schema)?;
+            // Union of domains on different columns is not accurate.
+            let mut left_columns = HashSet::new();
+            let mut right_columns = HashSet::new();
+            expr_to_columns(&left, &mut left_columns)?;
+            expr_to_columns(&right, &mut right_columns)?;
+            if left_columns.len() != 1 || left_columns != right_columns {
+                return Err(unsupported(&expr));
+            }
+            Ok(or(left, right))
+        }
+        Expr::BinaryExpr(BinaryExpr {
+            ref left,
+            op,
+            ref right,
+        }) if matches!(
+            op,
+            Operator::Eq | Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq
+        ) =>
+        {
+            let (column, op, value) = match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(column), value) => (column, op, value),
+                (value, Expr::Column(column)) => {
+                    let op = match op {
+                        Operator::Lt => Operator::Gt,
+                        Operator::LtEq => Operator::GtEq,
+                        Operator::Gt => Operator::Lt,
+                        Operator::GtEq => Operator::LtEq,
+                        _ => op,
+                    };
+                    (column, op, value)
+                }
+                _ => return Err(unsupported(&expr)),
+            };
+
+            let table_column =
+                table_schema
+                    .column(&column.name)
+                    .ok_or_else(|| QueryError::ColumnNotExists {
+                        column: column.name.clone(),
+                        table: table_schema.name.to_string(),
+                    })?;
+            if table_column.column_type.is_field() {
+                return Err(QueryError::DeleteWhereContainsField {
+                    column: column.name.clone(),
+                });
+            }
+
+            let value = match value {
+                Expr::Literal(value) => value.clone(),
+                Expr::Cast(Cast {
+                    expr: inner,
+                    data_type,
+                })
+                | Expr::TryCast(TryCast {
+                    expr: inner,
+                    data_type,
+                }) => match inner.as_ref() {
+                    Expr::Literal(value) => value.cast_to(data_type)?,
+                    _ => return Err(unsupported(&expr)),
+                },
+                _ => return Err(unsupported(&expr)),
+            };
+            if value.is_null() {
+                return Err(unsupported(&expr));
+            }
+            let value = value.cast_to(&table_column.column_type.clone().into())?;
+
+            Ok(Expr::BinaryExpr(BinaryExpr::new(
+                Box::new(Expr::Column(column.clone())),
+                op,
+                Box::new(Expr::Literal(value)),
+            )))
+        }
+        Expr::Between(Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        }) => normalize_delete_expr(
+            and(expr.as_ref().clone().gt_eq(*low), (*expr).lt_eq(*high)),
+            table_schema,
+        ),
+        Expr::InList(InList {
+            expr,
+            list,
+            negated: false,
+        }) if !list.is_empty() => {
+            let expr = list
+                .into_iter()
+                .map(|value| expr.as_ref().clone().eq(value))
+                .reduce(or)
+                .expect("list is not empty");
+            normalize_delete_expr(expr, table_schema)
+        }
+        _ => Err(unsupported(&expr)),
+    }
+}
+
 fn show_series_projection(
     table_schema: &TskvTableSchema,
     mut plan_builder: LogicalPlanBuilder,
@@ -2094,6 +2691,14 @@ fn show_series_projection(
     Ok(plan_builder.project(iter::once(concat_ws))?.build()?)
 }
 
+fn parse_regex(value: Value) -> Result<Regex> {
+    let pattern = parse_string_value(value)?;
+    Regex::new(&pattern).map_err(|e| QueryError::InvalidRegex {
+        pattern,
+        reason: e.to_string(),
+    })
+}
+
 fn show_tag_value_projections(
     table_schema: &TskvTableSchema,
     mut plan_builder: LogicalPlanBuilder,
@@ -2121,10 +2726,13 @@ fn show_tag_value_projections(
                 .map(normalize_ident)
                 .all(|name| column.name.ne(&name))
         }),
-        _ => {
-            return Err(QueryError::NotImplemented {
-                err: "Not implemented Match, UnMatch".to_string(),
-            });
+        With::Match(value) => {
+            let regex = parse_regex(value)?;
+            Box::new(move |column| regex.is_match(&column.name))
+        }
+        With::UnMatch(value) => {
+            let regex = parse_regex(value)?;
+            Box::new(move |column| !regex.is_match(&column.name))
         }
     };
 
@@ -2184,7 +2792,20 @@ fn show_tag_value_projections(
     Ok(union_distinct)
 }
 
-fn check_privilege(user: &User, privileges: Vec<Privilege<Oid>>) -> Result<()> {
+fn parse_resample_duration(text: &str) -> Result<i64> {
+    let duration = parse_duration(text).map_err(|reason| QueryError::InvalidContinuousQuery {
+        reason: format!("invalid duration '{text}': {reason}"),
+    })?;
+    let nanos = duration.as_nanos() as i64;
+    if nanos <= 0 {
+        return Err(QueryError::InvalidContinuousQuery {
+            reason: format!("duration '{text}' must be greater than 0"),
+        });
+    }
+    Ok(nanos)
+}
+
+pub(crate) fn check_privilege(user: &User, privileges: Vec<Privilege<Oid>>) -> Result<()> {
     let privileges_str = privileges
         .iter()
         .map(|e| format!("{:?}", e))
@@ -2513,48 +3134,56 @@ mod tests {
                             name: "time".to_string(),
                             column_type: ColumnType::Time(Nanosecond),
                             encoding: Encoding::Default,
+                            converted_from_tag: None,
                         },
                         TableColumn {
                             id: 1,
                             name: "column6".to_string(),
                             column_type: ColumnType::Tag,
                             encoding: Encoding::Default,
+                            converted_from_tag: None,
                         },
                         TableColumn {
                             id: 2,
                             name: "column7".to_string(),
                             column_type: ColumnType::Tag,
                             encoding: Encoding::Default,
+                            converted_from_tag: None,
                         },
                         TableColumn {
                             id: 3,
                             name: "column1".to_string(),
                             column_type: ColumnType::Field(ValueType::Integer),
                             encoding: Encoding::Delta,
+                            converted_from_tag: None,
                         },
                         TableColumn {
                             id: 4,
                             name: "column2".to_string(),
                             column_type: ColumnType::Field(ValueType::String),
                             encoding: Encoding::Gzip,
+                            converted_from_tag: None,
                         },
                         TableColumn {
                             id: 5,
                             name: "column3".to_string(),
                             column_type: ColumnType::Field(ValueType::Unsigned),
                             encoding: Encoding::Null,
+                            converted_from_tag: None,
                         },
                         TableColumn {
                             id: 6,
                             name: "column4".to_string(),
                             column_type: ColumnType::Field(ValueType::Boolean),
                             encoding: Encoding::Default,
+                            converted_from_tag: None,
                         },
                         TableColumn {
                             id: 7,
                             name: "column5".to_string(),
                             column_type: ColumnType::Field(ValueType::Float),
                             encoding: Encoding::Gorilla,
+                            converted_from_tag: None,
                         },
                     ],
                     name: TableReference::parse_str("default_schema.test")
@@ -2570,7 +3199,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_create_database() {
-        let sql = "CREATE DATABASE test WITH TTL '10' SHARD 5 VNODE_DURATION '3d' REPLICA 10 PRECISION 'us';";
+        let sql = "CREATE DATABASE test WITH TTL '10' SHARD 5 VNODE_DURATION '3d' REPLICA 10 PRECISION 'us' COLD_DURATION '30d' COLD_AFTER '90d';";
         let mut statements = ExtParser::parse_sql(sql).unwrap();
         assert_eq!(statements.len(), 1);
         let test = MockContext {};
@@ -2582,7 +3211,7 @@ mod tests {
         if let Plan::DDL(DDLPlan::CreateDatabase(create)) = plan.plan {
             let ans = format!("{:?}", create);
             println!("{ans}");
-            let expected = r#"CreateDatabase { name: "test", if_not_exists: false, options: DatabaseOptions { ttl: Some(Duration { time_num: 10, unit: Day }), shard_num: Some(5), vnode_duration: Some(Duration { time_num: 3, unit: Day }), replica: Some(10), precision: Some(US) } }"#;
+            let expected = r#"CreateDatabase { name: "test", if_not_exists: false, options: DatabaseOptions { ttl: Some(Duration { time_num: 10, unit: Day }), shard_num: Some(5), vnode_duration: Some(Duration { time_num: 3, unit: Day }), replica: Some(10), precision: Some(US), cold_duration: Some(Duration { time_num: 30, unit: Day }), cold_after: Some(Duration { time_num: 90, unit: Day }) } }"#;
             assert_eq!(ans, expected);
         } else {
             panic!("expected create table plan")
@@ -2669,4 +3298,57 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn test_normalize_delete_expr() {
+        let table_schema = TskvTableSchema::new(
+            "cnosdb".to_string(),
+            "public".to_string(),
+            "test_tb".to_string(),
+            vec![
+                TableColumn::new_time_column(0, Nanosecond),
+                TableColumn::new_tag_column(1, "host".to_string()),
+                TableColumn::new(
+                    2,
+                    "value".to_string(),
+                    ColumnType::Field(ValueType::Float),
+                    Encoding::Default,
+                ),
+            ],
+        );
+
+        let expr = lit("a").eq(col("host")).and(col("time").gt_eq(lit(10_i64)));
+        let expected = col("host")
+            .eq(lit("a"))
+            .and(col("time").gt_eq(lit(ScalarValue::TimestampNanosecond(Some(10), None))));
+        assert_eq!(
+            normalize_delete_expr(expr, &table_schema).unwrap(),
+            expected
+        );
+
+        let expr = col("host").in_list(vec![lit("a"), lit("b")], false);
+        let expected = col("host").eq(lit("a")).or(col("host").eq(lit("b")));
+        assert_eq!(
+            normalize_delete_expr(expr, &table_schema).unwrap(),
+            expected
+        );
+
+        let expr = col("value").gt(lit(1.0));
+        assert!(matches!(
+            normalize_delete_expr(expr, &table_schema),
+            Err(QueryError::DeleteWhereContainsField { .. })
+        ));
+
+        let expr = col("host").not_eq(lit("a"));
+        assert!(matches!(
+            normalize_delete_expr(expr, &table_schema),
+            Err(QueryError::UnsupportedDeleteExpr { .. })
+        ));
+
+        let expr = col("host").eq(lit("a")).or(col("time").gt(lit(10_i64)));
+        assert!(matches!(
+            normalize_delete_expr(expr, &table_schema),
+            Err(QueryError::UnsupportedDeleteExpr { .. })
+        ));
+    }
 }
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use spi::query::datasource::stream::Offset;
+use spi::service::protocol::QueryId;
+use spi::QueryError;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+pub type CheckpointLogRef = Arc<CheckpointLog>;
+
+const CHECKPOINT_FILE_NAME: &str = "checkpoint";
+/// The extension of the checkpoint replaced by the latest one
+const PREV_EXTENSION: &str = "prev";
+/// The watermark file written before checkpoints were introduced
+const LEGACY_WATERMARK_FILE_NAME: &str = "watermark";
+
+/// The progress of a stream query after a micro batch is completed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The version of the states committed in the micro batch
+    pub version: i64,
+    pub watermark_ns: i64,
+    pub processed_offsets: HashMap<String, Offset>,
+}
+
+impl Default for Checkpoint {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            watermark_ns: i64::MIN,
+            processed_offsets: Default::default(),
+        }
+    }
+}
+
+/// Records the latest checkpoint of a stream query in a local file,
+/// from which the stream query is resumed after system restart.
+#[derive(Debug)]
+pub struct CheckpointLog {
+    latest: RwLock<Checkpoint>,
+    file_path: PathBuf,
+}
+
+impl CheckpointLog {
+    pub fn try_new(query_id: QueryId, path: impl Into<PathBuf>) -> Result<Self, QueryError> {
+        let mut path: PathBuf = path.into();
+        path.push(&format!("{}", query_id));
+        path.push(CHECKPOINT_FILE_NAME);
+
+        let prev_path = path.with_extension(PREV_EXTENSION);
+        let latest = match (read_checkpoint(&path)?, read_checkpoint(&prev_path)?) {
+            (Some(Ok(latest)), _) => latest,
+            // The previous checkpoint is complete if the latest one is missing or broken
+            (Some(Err(err)), Some(Ok(prev))) => {
+                trace::warn!("{}, resume from the previous checkpoint", err);
+                prev
+            }
+            (None, Some(Ok(prev))) => prev,
+            (Some(Err(err)), _) => return Err(err),
+            (None, Some(Err(err))) => return Err(err),
+            (None, None) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let legacy_path = path.with_file_name(LEGACY_WATERMARK_FILE_NAME);
+                match read_legacy_watermark(&legacy_path)? {
+                    Some(watermark_ns) => Checkpoint {
+                        watermark_ns,
+                        ..Default::default()
+                    },
+                    None => Checkpoint::default(),
+                }
+            }
+        };
+
+        Ok(Self {
+            latest: RwLock::new(latest),
+            file_path: path,
+        })
+    }
+
+    pub fn latest(&self) -> Checkpoint {
+        self.latest.read().clone()
+    }
+
+    /// The version of the states to be committed in next micro batch.
+    pub fn next_version(&self) -> i64 {
+        self.latest.read().version + 1
+    }
+
+    /// Persist the checkpoint into a temporary file and rename it to the checkpoint file,
+    /// so the checkpoint file is always complete. The replaced checkpoint is kept,
+    /// from which the stream query is resumed if the checkpoint file is lost or broken.
+    pub async fn commit(&self, checkpoint: Checkpoint) -> Result<(), QueryError> {
+        let body = bincode::serialize(&checkpoint)
+            .map_err(|err| QueryError::BincodeSerialize { source: err })?;
+
+        let tmp_path = self.file_path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path).await?;
+        file.write_all(&body).await?;
+        file.sync_all().await?;
+
+        let prev_path = self.file_path.with_extension(PREV_EXTENSION);
+        let result = async {
+            if fs::try_exists(&self.file_path).await? {
+                fs::rename(&self.file_path, &prev_path).await?;
+            }
+            fs::rename(&tmp_path, &self.file_path).await?;
+            // Persist the renames
+            if let Some(parent) = self.file_path.parent() {
+                fs::File::open(parent).await?.sync_all().await?;
+            }
+            Ok::<_, std::io::Error>(())
+        }
+        .await;
+        result.map_err(|err| {
+            trace::error!("Commit streaming query checkpoint, error: {:?}", err);
+            err
+        })?;
+
+        *self.latest.write() = checkpoint;
+
+        Ok(())
+    }
+}
+
+/// Reads the checkpoint file if it exists, the error of an incomplete file is returned
+/// in the inner result.
+fn read_checkpoint(path: &Path) -> Result<Option<Result<Checkpoint, QueryError>>, QueryError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let bytes = std::fs::read(path)?;
+    Ok(Some(bincode::deserialize::<Checkpoint>(&bytes).map_err(
+        |err| QueryError::Internal {
+            reason: format!("Invalid checkpoint file: {:?}, error: {}", path, err),
+        },
+    )))
+}
+
+/// Reads the watermark of a query started by an older version,
+/// which is persisted as an 8-byte big-endian i64.
+fn read_legacy_watermark(path: &Path) -> Result<Option<i64>, QueryError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let bytes = std::fs::read(path)?;
+    let buf: [u8; 8] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| QueryError::Internal {
+            reason: format!("Invalid watermark file: {:?}, content: {:?}", path, bytes),
+        })?;
+
+    Ok(Some(i64::from_be_bytes(buf)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use spi::service::protocol::QueryId;
+
+    use super::{Checkpoint, CheckpointLog, CHECKPOINT_FILE_NAME, LEGACY_WATERMARK_FILE_NAME};
+
+    fn checkpoint(version: i64) -> Checkpoint {
+        Checkpoint {
+            version,
+            watermark_ns: version * 1_000,
+            processed_offsets: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let query_id = QueryId::from(1);
+
+        let log = CheckpointLog::try_new(query_id, dir.path()).unwrap();
+        assert_eq!(log.latest(), Checkpoint::default());
+        log.commit(checkpoint(1)).await.unwrap();
+        log.commit(checkpoint(2)).await.unwrap();
+        assert_eq!(log.next_version(), 3);
+
+        let log = CheckpointLog::try_new(query_id, dir.path()).unwrap();
+        assert_eq!(log.latest(), checkpoint(2));
+
+        // A broken checkpoint file falls back to the previous checkpoint
+        let path = dir.path().join("1").join(CHECKPOINT_FILE_NAME);
+        std::fs::write(&path, [0xff]).unwrap();
+        let log = CheckpointLog::try_new(query_id, dir.path()).unwrap();
+        assert_eq!(log.latest(), checkpoint(1));
+
+        // So does a lost one
+        std::fs::remove_file(&path).unwrap();
+        let log = CheckpointLog::try_new(query_id, dir.path()).unwrap();
+        assert_eq!(log.latest(), checkpoint(1));
+        log.commit(checkpoint(2)).await.unwrap();
+        let log = CheckpointLog::try_new(query_id, dir.path()).unwrap();
+        assert_eq!(log.latest(), checkpoint(2));
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_legacy_watermark() {
+        let dir = tempfile::tempdir().unwrap();
+        let query_id = QueryId::from(1);
+        let query_dir = dir.path().join("1");
+        std::fs::create_dir_all(&query_dir).unwrap();
+        std::fs::write(
+            query_dir.join(LEGACY_WATERMARK_FILE_NAME),
+            1_000_i64.to_be_bytes(),
+        )
+        .unwrap();
+
+        let log = CheckpointLog::try_new(query_id, dir.path()).unwrap();
+        assert_eq!(log.latest().watermark_ns, 1_000);
+        assert_eq!(log.next_version(), 1);
+
+        // The checkpoint takes precedence over the legacy watermark once committed
+        log.commit(checkpoint(2)).await.unwrap();
+        let log = CheckpointLog::try_new(query_id, dir.path()).unwrap();
+        assert_eq!(log.latest(), checkpoint(2));
+
+        // An incomplete legacy watermark file is an error
+        let query_id = QueryId::from(2);
+        let query_dir = dir.path().join("2");
+        std::fs::create_dir_all(&query_dir).unwrap();
+        std::fs::write(query_dir.join(LEGACY_WATERMARK_FILE_NAME), [0x01]).unwrap();
+        assert!(CheckpointLog::try_new(query_id, dir.path()).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod checkpoint;
 pub mod offset_tracker;
 pub mod state_store;
 pub mod watermark_tracker;
@@ -21,6 +21,14 @@ impl OffsetTracker {
         }
     }
 
+    /// Create an offset tracker which resumes from the `processed_offsets` of a checkpoint.
+    pub fn with_processed_offsets(processed_offsets: HashMap<String, Offset>) -> Self {
+        Self {
+            processed_offsets: Arc::new(RwLock::new(processed_offsets)),
+            available_offsets: Default::default(),
+        }
+    }
+
     pub fn has_available_offsets(&self) -> bool {
         !self.available_offsets.read().is_empty()
     }
@@ -58,16 +66,25 @@ impl OffsetTracker {
         source_to_range
     }
 
-    pub fn commit(&self, commit_offset: Offset) {
+    /// The processed offsets after committing `commit_offset`, without changing the tracker.
+    pub fn processed_offsets_after_commit(&self, commit_offset: Offset) -> HashMap<String, Offset> {
         // TODO 因为目前tskv表使用当前时间作为最新的可用offset，所以这里需要使用watermark_ns来保证不会丢失数据
+        let mut processed_offsets = self.processed_offsets.read().clone();
         self.available_offsets
             .read()
             .iter()
             .for_each(|(id, offset)| {
                 let offset = cmp::min(commit_offset, *offset);
-                self.processed_offsets.write().insert(id.clone(), offset);
+                processed_offsets.insert(id.clone(), offset);
             });
 
+        processed_offsets
+    }
+
+    pub fn commit(&self, commit_offset: Offset) {
+        let processed_offsets = self.processed_offsets_after_commit(commit_offset);
+        *self.processed_offsets.write() = processed_offsets;
+
         self.available_offsets.write().clear();
     }
 }
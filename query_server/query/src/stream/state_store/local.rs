@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use datafusion::arrow::ipc::reader::FileReader;
+use datafusion::arrow::ipc::writer::FileWriter;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::physical_plan::expressions::NotExpr;
+use datafusion::physical_plan::PhysicalExpr;
+use parking_lot::RwLock;
+
+use super::{StateStore, StateStoreFactory};
+use crate::extension::utils::batch_filter;
+
+const STATE_DIR_NAME: &str = "state";
+const STATE_FILE_EXTENSION: &str = "arrow";
+
+/// Creates [`LocalStateStore`]s, which persist the states into local files in Arrow IPC format.
+///
+/// Each commit writes a new version of the states to `{path}/{query_id}/state/{partition_id}_{operator_id}/{version}.arrow`.
+/// The states of the last checkpointed version are restored after the system restarts.
+#[derive(Debug)]
+pub struct LocalStateStoreFactory {
+    path: PathBuf,
+    /// The version of the states being processed, states of `version - 1` are the checkpointed ones.
+    version: Arc<AtomicI64>,
+    state_store_map: RwLock<HashMap<(String, usize, usize), Arc<LocalStateStore>>>,
+}
+
+impl LocalStateStoreFactory {
+    /// `checkpointed_version` is the version of the states recorded in the last checkpoint.
+    pub fn new(path: impl Into<PathBuf>, checkpointed_version: i64) -> Self {
+        Self {
+            path: path.into(),
+            version: Arc::new(AtomicI64::new(checkpointed_version + 1)),
+            state_store_map: Default::default(),
+        }
+    }
+}
+
+impl StateStoreFactory for LocalStateStoreFactory {
+    type SS = LocalStateStore;
+
+    fn get_or_default(
+        &self,
+        query_id: String,
+        partition_id: usize,
+        operator_id: usize,
+    ) -> Result<Arc<Self::SS>> {
+        let key = (query_id, partition_id, operator_id);
+        if let Some(state_store) = self.state_store_map.read().get(&key) {
+            return Ok(state_store.clone());
+        }
+
+        let mut state_store_map = self.state_store_map.write();
+        if let Some(state_store) = state_store_map.get(&key) {
+            return Ok(state_store.clone());
+        }
+
+        let dir = self
+            .path
+            .join(&key.0)
+            .join(STATE_DIR_NAME)
+            .join(format!("{}_{}", partition_id, operator_id));
+        let state_store = Arc::new(LocalStateStore::try_new(dir, self.version.clone())?);
+        state_store_map.insert(key, state_store.clone());
+
+        Ok(state_store)
+    }
+
+    fn begin_version(&self, version: i64) {
+        self.version.store(version, Ordering::Release);
+    }
+}
+
+#[derive(Debug)]
+pub struct LocalStateStore {
+    dir: PathBuf,
+    version: Arc<AtomicI64>,
+    /// The committed states of the latest two versions
+    committed: RwLock<BTreeMap<i64, Vec<RecordBatch>>>,
+    uncommitted: RwLock<Vec<RecordBatch>>,
+}
+
+impl LocalStateStore {
+    fn try_new(dir: PathBuf, version: Arc<AtomicI64>) -> Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let checkpointed_version = version.load(Ordering::Acquire) - 1;
+        let mut committed = BTreeMap::new();
+        let path = state_file_path(&dir, checkpointed_version);
+        if path.exists() {
+            trace::debug!("Restore states from {}", path.display());
+            committed.insert(checkpointed_version, read_states(&path)?);
+        }
+
+        Ok(Self {
+            dir,
+            version,
+            committed: RwLock::new(committed),
+            uncommitted: Default::default(),
+        })
+    }
+
+    /// Remove the state files older than `version`
+    fn remove_state_files_before(&self, version: i64) -> Result<()> {
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let old_version = path
+                .file_stem()
+                .and_then(|e| e.to_str())
+                .and_then(|e| e.parse::<i64>().ok());
+            if matches!(old_version, Some(v) if v < version) {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl StateStore for LocalStateStore {
+    fn put(&self, batch: RecordBatch) -> Result<()> {
+        trace::trace!("Write batch to LocalStateStore: {:?}", batch);
+        self.uncommitted.write().push(batch);
+
+        Ok(())
+    }
+
+    fn expire(&self, predicate: Arc<dyn PhysicalExpr>) -> Result<Vec<RecordBatch>> {
+        trace::debug!("Remove batches match {} from LocalStateStore", predicate);
+        let remained: Arc<dyn PhysicalExpr> = Arc::new(NotExpr::new(predicate.clone()));
+
+        let mut uncommitted = self.uncommitted.write();
+        let expired_data = uncommitted
+            .iter()
+            .map(|e| batch_filter(e, &predicate))
+            .collect::<Result<Vec<_>>>()?;
+        *uncommitted = uncommitted
+            .iter()
+            .map(|e| batch_filter(e, &remained))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(expired_data)
+    }
+
+    /// Write the uncommitted states into the file of current version.
+    ///
+    /// The states of the previous version are kept until the next commit,
+    /// because current version is not visible before it's checkpointed.
+    fn commit(&self) -> Result<i64> {
+        let version = self.version.load(Ordering::Acquire);
+        trace::trace!("LocalStateStore commit version {}", version);
+
+        let states = std::mem::take(&mut *self.uncommitted.write());
+        write_states(&state_file_path(&self.dir, version), &states)?;
+
+        {
+            let mut committed = self.committed.write();
+            committed.insert(version, states);
+            committed.retain(|v, _| *v >= version - 1);
+        }
+        self.remove_state_files_before(version - 1)?;
+
+        Ok(version)
+    }
+
+    /// Return the states of the last checkpointed version.
+    fn state(&self) -> Result<Vec<RecordBatch>> {
+        trace::trace!("Read all states from LocalStateStore");
+        let checkpointed_version = self.version.load(Ordering::Acquire) - 1;
+
+        Ok(self
+            .committed
+            .read()
+            .get(&checkpointed_version)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+fn state_file_path(dir: &Path, version: i64) -> PathBuf {
+    dir.join(format!("{}.{}", version, STATE_FILE_EXTENSION))
+}
+
+fn read_states(path: &Path) -> Result<Vec<RecordBatch>> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(vec![]);
+    }
+
+    let reader = FileReader::try_new(file, None)?;
+    Ok(reader.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+/// Write states into a temporary file and rename it to `path`,
+/// so a crash never leaves a partially written state file.
+fn write_states(path: &Path, states: &[RecordBatch]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let file = File::create(&tmp_path)?;
+    if let Some(first) = states.first() {
+        let mut writer = FileWriter::try_new(file, first.schema().as_ref())?;
+        for batch in states {
+            writer.write(batch)?;
+        }
+        writer.finish()?;
+        writer.into_inner()?.sync_all()?;
+    } else {
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::Int64Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+
+    use super::LocalStateStoreFactory;
+    use crate::stream::state_store::{StateStore, StateStoreFactory};
+
+    fn batch(values: Vec<i64>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(values))]).unwrap()
+    }
+
+    #[test]
+    fn test_restore_checkpointed_version() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let factory = LocalStateStoreFactory::new(dir.path(), 0);
+        let store = factory.get_or_default("q".to_string(), 0, 0).unwrap();
+        assert!(store.state().unwrap().is_empty());
+        store.put(batch(vec![1, 2])).unwrap();
+        assert_eq!(store.commit().unwrap(), 1);
+
+        // version 1 is visible after it's checkpointed
+        factory.begin_version(2);
+        assert_eq!(store.state().unwrap(), vec![batch(vec![1, 2])]);
+        store.put(batch(vec![3])).unwrap();
+        assert_eq!(store.commit().unwrap(), 2);
+
+        // version 2 was not checkpointed before restarting
+        let factory = LocalStateStoreFactory::new(dir.path(), 1);
+        let store = factory.get_or_default("q".to_string(), 0, 0).unwrap();
+        assert_eq!(store.state().unwrap(), vec![batch(vec![1, 2])]);
+    }
+}
@@ -36,6 +36,8 @@ impl StateStoreFactory for MemoryStateStoreFactory {
 
         Ok(state_store)
     }
+
+    fn begin_version(&self, _version: i64) {}
 }
 
 #[derive(Debug, Default)]
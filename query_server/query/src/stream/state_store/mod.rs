@@ -5,6 +5,7 @@ use datafusion::common::Result;
 use datafusion::physical_plan::PhysicalExpr;
 
 use self::memory::MemoryStateStoreFactory;
+pub mod local;
 pub mod memory;
 
 pub fn create_memory_state_store_factory() -> Arc<MemoryStateStoreFactory> {
@@ -21,6 +22,9 @@ pub trait StateStoreFactory {
         partition_id: usize,
         operator_id: usize,
     ) -> Result<Arc<Self::SS>>;
+
+    /// The states committed after this call are of `version`.
+    fn begin_version(&self, version: i64);
 }
 
 pub type StateStoreRef = Arc<dyn StateStore>;
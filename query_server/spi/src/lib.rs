@@ -495,6 +495,71 @@ pub enum QueryError {
     AnalyzePushedFilter {
         reason: String,
     },
+
+    #[snafu(display(
+        "Semantic error: DELETE does not support where clause contains field {}",
+        column
+    ))]
+    #[error_code(code = 73)]
+    DeleteWhereContainsField {
+        column: String,
+    },
+
+    #[snafu(display(
+        "Semantic error: DELETE only supports comparisons between tag or time column and constant, found {}",
+        expr
+    ))]
+    #[error_code(code = 74)]
+    UnsupportedDeleteExpr {
+        expr: String,
+    },
+
+    #[snafu(display("Invalid regular expression '{}': {}", pattern, reason))]
+    #[error_code(code = 75)]
+    InvalidRegex {
+        pattern: String,
+        reason: String,
+    },
+
+    #[snafu(display(
+        "Semantic error: Can't change type of column {} from {} to {}",
+        column,
+        from,
+        to
+    ))]
+    #[error_code(code = 76)]
+    UnsupportedColumnTypeChange {
+        column: String,
+        from: String,
+        to: String,
+    },
+
+    #[snafu(display(
+        "Semantic error: Can't rename tag column {}, convert it to a field by changing its type to STRING first.",
+        column
+    ))]
+    #[error_code(code = 77)]
+    RenameTag {
+        column: String,
+    },
+
+    #[snafu(display("Semantic error: Invalid continuous query: {}", reason))]
+    #[error_code(code = 78)]
+    InvalidContinuousQuery {
+        reason: String,
+    },
+
+    #[snafu(display("Semantic error: Invalid materialized view: {}", reason))]
+    #[error_code(code = 79)]
+    InvalidMaterializedView {
+        reason: String,
+    },
+
+    #[snafu(display("Invalid prometheus query: {}", reason))]
+    #[error_code(code = 80)]
+    InvalidPromQuery {
+        reason: String,
+    },
 }
 
 impl From<ParserError> for QueryError {
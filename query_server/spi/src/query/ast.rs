@@ -2,8 +2,8 @@ use std::fmt;
 
 use datafusion::sql::parser::CreateExternalTable;
 use datafusion::sql::sqlparser::ast::{
-    AnalyzeFormat, DataType, Expr, Ident, ObjectName, Offset, OrderByExpr, SqlOption, Statement,
-    TableFactor, Value,
+    AnalyzeFormat, DataType, Expr, Ident, ObjectName, Offset, OrderByExpr, Query, SqlOption,
+    Statement, TableFactor, Value,
 };
 use datafusion::sql::sqlparser::parser::ParserError;
 use models::codec::Encoding;
@@ -32,6 +32,12 @@ pub enum ExtStatement {
     DropStream(DropStream),
     ShowStreams(ShowStreams),
 
+    CreateContinuousQuery(CreateContinuousQuery),
+    DropContinuousQuery(DropContinuousQuery),
+
+    CreateMaterializedView(CreateMaterializedView),
+    DropMaterializedView(DropMaterializedView),
+
     DropDatabaseObject(DropDatabaseObject),
     DropTenantObject(DropTenantObject),
     DropGlobalObject(DropGlobalObject),
@@ -48,6 +54,7 @@ pub enum ExtStatement {
 
     // system cmd
     ShowQueries,
+    ShowRebalance,
     AlterDatabase(AlterDatabase),
     AlterTable(AlterTable),
     AlterTenant(AlterTenant),
@@ -59,6 +66,24 @@ pub enum ExtStatement {
     MoveVnode(MoveVnode),
     CompactVnode(CompactVnode),
     ChecksumGroup(ChecksumGroup),
+
+    DecommissionNode(DecommissionNode),
+    RepairReplicationSet(RepairReplicationSet),
+
+    BackupDatabase(BackupDatabase),
+    RestoreDatabase(RestoreDatabase),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupDatabase {
+    pub name: Ident,
+    pub location: UriLocation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreDatabase {
+    pub name: Ident,
+    pub location: UriLocation,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -66,6 +91,16 @@ pub struct ChecksumGroup {
     pub replication_set_id: ReplicationSetId,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecommissionNode {
+    pub node_id: NodeId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairReplicationSet {
+    pub replication_set_id: ReplicationSetId,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CompactVnode {
     pub vnode_ids: Vec<VnodeId>,
@@ -143,6 +178,14 @@ pub enum AlterTableAction {
         column_name: Ident,
         encoding: Encoding,
     },
+    AlterColumnType {
+        column_name: Ident,
+        data_type: DataType,
+    },
+    RenameColumn {
+        old_column_name: Ident,
+        new_column_name: Ident,
+    },
     DropColumn {
         column_name: Ident,
     },
@@ -308,6 +351,10 @@ pub struct DatabaseOptions {
     pub replica: Option<u64>,
     // timestamp percision
     pub precision: Option<String>,
+    // data older than this is moved to cold storage
+    pub cold_duration: Option<String>,
+    // vnodes of buckets older than this are moved to cold nodes
+    pub cold_after: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -405,6 +452,44 @@ pub struct ShowStreams {
     pub verbose: bool,
 }
 
+/// CREATE CONTINUOUS QUERY [IF NOT EXISTS] cq ON db
+/// RESAMPLE EVERY '1m' [FOR '10m']
+/// AS SELECT ... INTO target FROM ...
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateContinuousQuery {
+    pub if_not_exists: bool,
+    pub name: Ident,
+    pub database: Ident,
+    pub resample_every: String,
+    pub resample_for: Option<String>,
+    pub query: Box<Query>,
+}
+
+/// DROP CONTINUOUS QUERY [IF EXISTS] cq ON db
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DropContinuousQuery {
+    pub if_exist: bool,
+    pub name: Ident,
+    pub database: Ident,
+}
+
+/// CREATE MATERIALIZED VIEW [IF NOT EXISTS] mv
+/// AS SELECT time_window(time, interval '1 hour'), tag, avg(field) FROM t
+/// GROUP BY time_window(time, interval '1 hour'), tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateMaterializedView {
+    pub if_not_exists: bool,
+    pub name: ObjectName,
+    pub query: Box<Query>,
+}
+
+/// DROP MATERIALIZED VIEW [IF EXISTS] mv
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DropMaterializedView {
+    pub if_exist: bool,
+    pub name: ObjectName,
+}
+
 impl fmt::Display for ObjectType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(match self {
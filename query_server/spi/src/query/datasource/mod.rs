@@ -1,13 +1,16 @@
 use std::sync::Arc;
 
+use datafusion::sql::sqlparser::ast::SqlOption;
 use models::oid;
 use object_store::aws::AmazonS3Builder;
 use object_store::azure::MicrosoftAzureBuilder;
 use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::local::LocalFileSystem;
 use object_store::path::Path;
 use object_store::ObjectStore;
 
-use super::logical_planner::ConnectionOptions;
+use super::logical_planner::{parse_connection_options, ConnectionOptions};
+use crate::{QueryError, Result as QueryResult};
 
 pub mod azure;
 pub mod gcs;
@@ -93,3 +96,29 @@ pub fn build_object_store(
 
     Ok(object_store)
 }
+
+/// Build object store of a location like `s3://bucket/path/to/dir`, returns the
+/// object store and path of the location in it.
+///
+/// Local directory (`file:///path/to/dir` or `/path/to/dir`) is created if not exists.
+pub fn build_location_object_store(
+    location: &str,
+    options: Vec<SqlOption>,
+) -> QueryResult<(Arc<dyn ObjectStore>, Path)> {
+    let (schema, rest) = location.split_once("://").unwrap_or(("", location));
+    let uri_schema = UriSchema::from(schema);
+    if let UriSchema::Local = uri_schema {
+        std::fs::create_dir_all(rest)?;
+        let object_store = LocalFileSystem::new_with_prefix(rest)?;
+        return Ok((Arc::new(object_store), Path::default()));
+    }
+
+    let (bucket, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let connection_options = parse_connection_options(&uri_schema, Some(bucket), options)?;
+    let object_store =
+        build_object_store(connection_options)?.ok_or_else(|| QueryError::Semantic {
+            err: format!("Unsupported location: {}", location),
+        })?;
+
+    Ok((object_store, Path::from(path)))
+}
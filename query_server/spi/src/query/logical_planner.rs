@@ -22,11 +22,13 @@ use lazy_static::lazy_static;
 use models::auth::privilege::{DatabasePrivilege, GlobalPrivilege, Privilege};
 use models::auth::role::{SystemTenantRole, TenantRoleIdentifier};
 use models::auth::user::{UserOptions, UserOptionsBuilder};
-use models::meta_data::{NodeId, ReplicationSetId, VnodeId};
+use models::meta_data::{ContinuousQueryInfo, NodeId, ReplicationSetId, VnodeId};
 use models::object_reference::ResolvedTable;
 use models::oid::{Identifier, Oid};
+use models::predicate::domain::ResolvedPredicateRef;
 use models::schema::{
-    DatabaseOptions, TableColumn, Tenant, TenantOptions, TenantOptionsBuilder, Watermark,
+    DatabaseOptions, MaterializedView, TableColumn, Tenant, TenantOptions, TenantOptionsBuilder,
+    TskvTableSchema, Watermark,
 };
 use snafu::ResultExt;
 use tempfile::NamedTempFile;
@@ -146,6 +148,24 @@ pub enum DDLPlan {
     CompactVnode(CompactVnode),
 
     ChecksumGroup(ChecksumGroup),
+
+    DecommissionNode(DecommissionNode),
+
+    RepairReplicationSet(RepairReplicationSet),
+
+    DeleteFromTable(DeleteFromTable),
+
+    BackupDatabase(BackupDatabase),
+
+    RestoreDatabase(RestoreDatabase),
+
+    CreateContinuousQuery(CreateContinuousQuery),
+
+    DropContinuousQuery(DropContinuousQuery),
+
+    CreateMaterializedView(CreateMaterializedView),
+
+    DropMaterializedView(DropMaterializedView),
 }
 
 impl DDLPlan {
@@ -168,16 +188,85 @@ impl DDLPlan {
                 Field::new("VNODE_ID", DataType::UInt32, false),
                 Field::new("CHECK_SUM", DataType::Utf8, false),
             ])),
+            DDLPlan::RepairReplicationSet(_) => Arc::new(Schema::new(vec![
+                Field::new("VNODE_ID", DataType::UInt32, false),
+                Field::new("NODE_ID", DataType::UInt64, false),
+                Field::new("DIVERGENT_RANGES", DataType::UInt64, false),
+                Field::new("REPAIRED_POINTS", DataType::UInt64, false),
+                Field::new("SKIPPED_RANGES", DataType::UInt64, false),
+            ])),
             _ => Arc::new(Schema::empty()),
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct BackupDatabase {
+    pub tenant_name: String,
+    pub database_name: String,
+    /// Path or url of the backup location, e.g. `s3://bucket/backup/db`
+    pub location: String,
+    /// Connection options of the backup location
+    pub connection_options: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RestoreDatabase {
+    pub tenant_name: String,
+    /// Database to restore into, it must not exist
+    pub database_name: String,
+    /// Path or url of the backup location, e.g. `s3://bucket/backup/db`
+    pub location: String,
+    /// Connection options of the backup location
+    pub connection_options: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateContinuousQuery {
+    pub tenant_name: String,
+    pub if_not_exists: bool,
+    pub info: ContinuousQueryInfo,
+}
+
+#[derive(Debug, Clone)]
+pub struct DropContinuousQuery {
+    pub tenant_name: String,
+    pub name: String,
+    pub database: String,
+    pub if_exist: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateMaterializedView {
+    pub if_not_exists: bool,
+    /// The table which keeps the data of the view
+    pub table: TskvTableSchema,
+    pub view: MaterializedView,
+}
+
+#[derive(Debug, Clone)]
+pub struct DropMaterializedView {
+    pub tenant_name: String,
+    pub database: String,
+    pub name: String,
+    pub if_exist: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct ChecksumGroup {
     pub replication_set_id: ReplicationSetId,
 }
 
+#[derive(Debug, Clone)]
+pub struct DecommissionNode {
+    pub node_id: NodeId,
+}
+
+#[derive(Debug, Clone)]
+pub struct RepairReplicationSet {
+    pub replication_set_id: ReplicationSetId,
+}
+
 #[derive(Debug, Clone)]
 pub struct CompactVnode {
     pub vnode_ids: Vec<VnodeId>,
@@ -204,6 +293,7 @@ pub struct DropVnode {
 pub enum SYSPlan {
     ShowQueries,
     KillQuery(QueryId),
+    ShowRebalance,
 }
 
 impl SYSPlan {
@@ -216,6 +306,15 @@ impl SYSPlan {
                 Field::new("state", DataType::Utf8, false),
                 Field::new("duration", DataType::UInt64, false),
             ])),
+            SYSPlan::ShowRebalance => Arc::new(Schema::new(vec![
+                Field::new("tenant_name", DataType::Utf8, false),
+                Field::new("database_name", DataType::Utf8, false),
+                Field::new("replication_set_id", DataType::UInt32, false),
+                Field::new("vnode_id", DataType::UInt32, false),
+                Field::new("from_node_id", DataType::UInt64, false),
+                Field::new("to_node_id", DataType::UInt64, true),
+                Field::new("status", DataType::Utf8, false),
+            ])),
             _ => Arc::new(Schema::empty()),
         }
     }
@@ -544,6 +643,12 @@ pub enum AlterTableAction {
     },
 }
 
+#[derive(Debug, Clone)]
+pub struct DeleteFromTable {
+    pub table_name: ResolvedTable,
+    pub predicate: ResolvedPredicateRef,
+}
+
 #[async_trait]
 pub trait LogicalPlanner {
     async fn create_logical_plan(
@@ -874,6 +979,22 @@ fn write_tmp_service_account_file(
 }
 
 /// Convert SqlOption s to map, and convert value to lowercase
+/// Convert options map back to sql options, "true" and "false" are converted to boolean.
+pub fn map_to_sql_options<'a>(
+    map: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> Vec<SqlOption> {
+    map.into_iter()
+        .map(|(k, v)| SqlOption {
+            name: Ident::new(k),
+            value: match v.as_str() {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                _ => Value::SingleQuotedString(v.clone()),
+            },
+        })
+        .collect()
+}
+
 pub fn sql_options_to_map(opts: &[SqlOption]) -> HashMap<String, String> {
     let mut map = HashMap::with_capacity(opts.len());
     for SqlOption { name, value } in opts {
@@ -7,6 +7,7 @@ use datafusion::execution::memory_pool::MemoryPool;
 use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
 use datafusion::prelude::{SessionConfig, SessionContext};
 use models::auth::user::User;
+use models::consistency_level::ConsistencyLevel;
 use models::oid::Oid;
 use trace::{SpanContext, SpanExt, SpanRecorder};
 
@@ -160,4 +161,15 @@ impl CnosSessionConfig {
         self.inner = self.inner.with_extension(Arc::new(interval));
         self
     }
+
+    /// Default consistency level of writes in this session
+    pub fn with_consistency_level(mut self, level: ConsistencyLevel) -> Self {
+        self.inner = self.inner.with_extension(Arc::new(level));
+        self
+    }
+
+    /// `None` if the session doesn't specify one, the configured level is used.
+    pub fn consistency_level(&self) -> Option<ConsistencyLevel> {
+        self.inner.get_extension::<ConsistencyLevel>().map(|e| *e)
+    }
 }
@@ -1,8 +1,11 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::stream::BoxStream;
 use protos::kv_service::WritePointsRequest;
+use serde::Serialize;
 use trace::SpanContext;
 
 use crate::service::protocol::Context;
@@ -10,6 +13,71 @@ use crate::Result;
 
 pub type PromRemoteServerRef = Arc<dyn PromRemoteServer + Send + Sync>;
 
+/// The response of remote read, in the first type accepted by the request.
+pub enum PromReadResponse {
+    /// Snappy compressed `ReadResponse`
+    Samples(Vec<u8>),
+    /// Frames of `ChunkedReadResponse`, which are produced while the series are read
+    StreamedXorChunks(BoxStream<'static, Result<Vec<u8>>>),
+}
+
+/// The evaluation timestamps of a PromQL query, in nanoseconds.
+#[derive(Debug, Clone, Copy)]
+pub enum PromQueryTime {
+    /// `/api/v1/query`
+    Instant(i64),
+    /// `/api/v1/query_range`, evaluated at `start, start + step, ..., end`
+    Range { start: i64, end: i64, step: i64 },
+}
+
+/// The `data` of the json response of a PromQL query.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "resultType", content = "result", rename_all = "lowercase")]
+pub enum PromQueryResult {
+    Scalar(PromSample),
+    Vector(Vec<PromInstantSeries>),
+    Matrix(Vec<PromRangeSeries>),
+}
+
+/// `[<unix timestamp in seconds>, "<value>"]`
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PromSample(pub f64, pub String);
+
+impl PromSample {
+    pub fn new(timestamp_ns: i64, value: f64) -> Self {
+        let value = if value.is_nan() {
+            "NaN".to_string()
+        } else if value.is_infinite() {
+            if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+        } else {
+            value.to_string()
+        };
+        Self((timestamp_ns / 1_000_000) as f64 / 1000.0, value)
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PromInstantSeries {
+    pub metric: BTreeMap<String, String>,
+    pub value: PromSample,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PromRangeSeries {
+    pub metric: BTreeMap<String, String>,
+    pub values: Vec<PromSample>,
+}
+
+/// The series selectors `match[]` of `/api/v1/series`, `/api/v1/labels` and
+/// `/api/v1/label/<name>/values`, and the time range of the samples in nanoseconds,
+/// which is unbounded if missing.
+#[derive(Debug, Clone, Default)]
+pub struct PromSeriesQuery {
+    pub matchers: Vec<String>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
 #[async_trait]
 pub trait PromRemoteServer {
     async fn remote_read(
@@ -17,6 +85,36 @@ pub trait PromRemoteServer {
         ctx: &Context,
         req: Bytes,
         span_ctx: Option<&SpanContext>,
-    ) -> Result<Vec<u8>>;
+    ) -> Result<PromReadResponse>;
     fn remote_write(&self, ctx: &Context, req: Bytes) -> Result<WritePointsRequest>;
+    /// Evaluates a PromQL expression.
+    async fn query(
+        &self,
+        ctx: &Context,
+        query: &str,
+        time: PromQueryTime,
+        span_ctx: Option<&SpanContext>,
+    ) -> Result<PromQueryResult>;
+    /// The labels of the series matched by any selector.
+    async fn series(
+        &self,
+        ctx: &Context,
+        query: &PromSeriesQuery,
+        span_ctx: Option<&SpanContext>,
+    ) -> Result<Vec<BTreeMap<String, String>>>;
+    /// The label names of the series, of all series if no selector is given.
+    async fn labels(
+        &self,
+        ctx: &Context,
+        query: &PromSeriesQuery,
+        span_ctx: Option<&SpanContext>,
+    ) -> Result<Vec<String>>;
+    /// The values of the label of the series, of all series if no selector is given.
+    async fn label_values(
+        &self,
+        ctx: &Context,
+        name: &str,
+        query: &PromSeriesQuery,
+        span_ctx: Option<&SpanContext>,
+    ) -> Result<Vec<String>>;
 }
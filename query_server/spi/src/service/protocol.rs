@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use models::auth::user::User;
+use models::consistency_level::ConsistencyLevel;
 use models::oid::{uuid_u64, Identifier};
 use models::schema::{DEFAULT_CATALOG, DEFAULT_DATABASE, DEFAULT_PRECISION};
 use serde::{Deserialize, Serialize};
@@ -169,6 +170,13 @@ impl ContextBuilder {
         self
     }
 
+    pub fn with_consistency_level(mut self, level: Option<ConsistencyLevel>) -> Self {
+        if let Some(level) = level {
+            self.session_config = self.session_config.with_consistency_level(level);
+        }
+        self
+    }
+
     pub fn with_chunked(mut self, chunked: Option<bool>) -> Self {
         if let Some(chunked) = chunked {
             self.chunked = chunked;
@@ -46,7 +46,10 @@ impl sqllogictest::AsyncDB for CnosDBClient {
 
         let (schema, batches) = run_query(&self.options, sql).await?;
         let types = normalize::convert_schema_to_types(schema.fields());
-        let rows = normalize::convert_batches(batches)?;
+        let mut rows = normalize::convert_batches(batches)?;
+        if normalize::is_explain_analyze(sql) {
+            rows = normalize::convert_explain_analyze(rows);
+        }
 
         if rows.is_empty() && types.is_empty() {
             Ok(DBOutput::StatementComplete(0))
@@ -50,6 +50,56 @@ pub fn value_to_string(col: &ArrayRef, row: usize) -> Result<String, ArrowError>
     }
 }
 
+pub fn is_explain_analyze(sql: &str) -> bool {
+    sql.trim_start()
+        .to_ascii_lowercase()
+        .starts_with("explain analyze")
+}
+
+/// Converts the plans with metrics of `EXPLAIN ANALYZE` to a row of each operator,
+/// `<plan_type> <operator>: <metrics>...`, whose metrics are sorted by name
+/// and the times vary between runs are masked.
+pub fn convert_explain_analyze(rows: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    rows.into_iter()
+        .flat_map(|row| {
+            let plan_type = row[0].clone();
+            row[1]
+                .lines()
+                .filter_map(convert_operator)
+                .map(|operator| vec![plan_type.clone(), operator])
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn convert_operator(line: &str) -> Option<String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    let name = line.split(':').next().unwrap_or(line);
+    let metrics = line
+        .rfind("metrics=[")
+        .and_then(|i| line[i + "metrics=[".len()..].strip_suffix(']'))
+        .unwrap_or_default();
+    let mut metrics = metrics
+        .split(", ")
+        .filter(|metric| !metric.is_empty())
+        .map(|metric| match metric.split_once('=') {
+            Some((name, value)) if is_duration(value) => format!("{name}=<time>"),
+            _ => metric.to_string(),
+        })
+        .collect::<Vec<_>>();
+    metrics.sort();
+
+    Some(format!("{name}: {}", metrics.join(", ")))
+}
+
+fn is_duration(value: &str) -> bool {
+    value.starts_with(|c: char| c.is_ascii_digit()) && value.ends_with('s')
+}
+
 /// Check two schemas for being equal for field names/types
 fn equivalent_names_and_types(schema: &SchemaRef, other: SchemaRef) -> bool {
     if schema.fields().len() != other.fields().len() {
@@ -69,3 +69,25 @@ ALTER TABLE test ALTER s0 SET CODEC(ZLIB);
 DESCRIBE TABLE test;
 
 
+ALTER TABLE test ALTER f1 TYPE DOUBLE;
+DESCRIBE TABLE test;
+SELECT * FROM test ORDER BY TIME ASC;
+
+ALTER TABLE test ALTER f1 TYPE BOOLEAN;
+ALTER TABLE test ALTER t0 TYPE DOUBLE;
+ALTER TABLE test RENAME COLUMN t0 TO t2;
+
+ALTER TABLE test RENAME COLUMN f1 TO f2;
+DESCRIBE TABLE test;
+SELECT * FROM test ORDER BY TIME ASC;
+
+
+ALTER TABLE test ALTER f2 TYPE BIGINT;
+ALTER TABLE test ALTER t0 TYPE STRING;
+DESCRIBE TABLE test;
+SELECT * FROM test ORDER BY TIME ASC;
+INSERT INTO test (TIME, t0, t1, f0) VALUES (20, '22', '21', 23);
+SELECT time, t0 FROM test ORDER BY TIME ASC;
+
+ALTER TABLE test RENAME COLUMN t0 TO f3;
+SELECT * FROM test ORDER BY TIME ASC;
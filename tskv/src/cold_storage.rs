@@ -0,0 +1,566 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use config::ColdStorageConfig;
+use futures::TryStreamExt;
+use meta::model::MetaRef;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{oneshot, RwLock};
+use trace::{debug, error, info};
+use utils::BloomFilter;
+
+use crate::error::{self, Error, Result};
+use crate::file_system::file_manager::try_exists;
+use crate::kv_option::{StorageOptions, COLD_PATH};
+use crate::summary::{CompactMeta, SummaryTask, VersionEdit};
+use crate::tseries_family::{ColumnFile, TseriesFamily};
+use crate::tsm::{self, TsmReader};
+use crate::version_set::VersionSet;
+use crate::{file_utils, ColumnFileId, TseriesFamilyId};
+
+/// Moves TSM files of vnodes older than the database's `COLD_DURATION`
+/// into an object store, the moved files are downloaded into a local
+/// cache directory (`{tsfamily_dir}/cold`) when they are read.
+///
+/// Objects are stored with the same relative path as the cached files
+/// in the data directory, under the prefix of the cold storage url.
+#[derive(Debug)]
+pub struct ColdStorage {
+    object_store: Arc<dyn ObjectStore>,
+    prefix: ObjectPath,
+    data_dir: PathBuf,
+    check_interval: Duration,
+    cache_max_size: u64,
+    /// Cached file -> time it was last read, files are evicted by it.
+    last_access: parking_lot::Mutex<HashMap<PathBuf, SystemTime>>,
+}
+
+impl ColdStorage {
+    pub fn new(
+        object_store: Arc<dyn ObjectStore>,
+        prefix: &str,
+        storage_opt: &StorageOptions,
+        config: &ColdStorageConfig,
+    ) -> Self {
+        Self {
+            object_store,
+            prefix: ObjectPath::from(prefix),
+            data_dir: storage_opt.data_dir(),
+            check_interval: config.check_interval,
+            cache_max_size: config.cache_max_size,
+            last_access: Default::default(),
+        }
+    }
+
+    /// Creates a cold storage on local file system, returns None if
+    /// cold storage is disabled or the url is not a local path.
+    pub fn new_local(storage_opt: &StorageOptions, config: &ColdStorageConfig) -> Option<Self> {
+        if !config.enabled() {
+            return None;
+        }
+        let (scheme, _, path) = config.split_url();
+        if !scheme.is_empty() && !scheme.eq_ignore_ascii_case("file") {
+            return None;
+        }
+        if let Err(e) = std::fs::create_dir_all(path) {
+            error!("Failed to create cold storage directory '{}': {}", path, e);
+            return None;
+        }
+        match LocalFileSystem::new_with_prefix(path) {
+            Ok(store) => Some(Self::new(Arc::new(store), "", storage_opt, config)),
+            Err(e) => {
+                error!("Failed to open cold storage '{}': {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Returns the object location of a cached cold file.
+    fn location(&self, path: &Path) -> Result<ObjectPath> {
+        let relative = path
+            .strip_prefix(&self.data_dir)
+            .map_err(|_| Error::CommonError {
+                reason: format!(
+                    "cold file '{}' is not in data directory '{}'",
+                    path.display(),
+                    self.data_dir.display()
+                ),
+            })?;
+        let mut location = self.prefix.clone();
+        for part in relative.iter() {
+            location = location.child(part.to_string_lossy().as_ref());
+        }
+        Ok(location)
+    }
+
+    /// Uploads local file `from` to the location of cached cold file `to`.
+    pub async fn upload(&self, from: &Path, to: &Path) -> Result<()> {
+        let location = self.location(to)?;
+        let (multipart_id, mut writer) = self
+            .object_store
+            .put_multipart(&location)
+            .await
+            .context(error::ObjectStoreSnafu)?;
+        let res = async {
+            let mut file = tokio::fs::File::open(from)
+                .await
+                .context(error::OpenFileSnafu { path: from })?;
+            tokio::io::copy(&mut file, &mut writer)
+                .await
+                .context(error::IOSnafu)?;
+            writer.shutdown().await.context(error::IOSnafu)
+        }
+        .await;
+        if res.is_err() {
+            let _ = self
+                .object_store
+                .abort_multipart(&location, &multipart_id)
+                .await;
+        }
+        res
+    }
+
+    fn is_cold_file(path: &Path) -> bool {
+        path.parent()
+            .and_then(|p| p.file_name())
+            .map(|d| d == COLD_PATH)
+            .unwrap_or(false)
+    }
+
+    /// Records that the cold file is read, does nothing if the path is not a cold file.
+    pub fn touch(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        if Self::is_cold_file(path) {
+            self.last_access
+                .lock()
+                .insert(path.to_path_buf(), SystemTime::now());
+        }
+    }
+
+    /// Downloads the cold file into cache if it's not cached,
+    /// does nothing if the path is not a cold file.
+    pub async fn fetch(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !Self::is_cold_file(path) {
+            return Ok(());
+        }
+        self.touch(path);
+        if try_exists(path) {
+            return Ok(());
+        }
+
+        debug!("Fetching cold file '{}'", path.display());
+        let location = self.location(path)?;
+        let mut stream = self
+            .object_store
+            .get(&location)
+            .await
+            .context(error::ObjectStoreSnafu)?
+            .into_stream();
+        let tmp_path = path.with_extension("tmp");
+        let mut file = tokio::fs::File::create(&tmp_path)
+            .await
+            .context(error::CreateFileSnafu { path: &tmp_path })?;
+        while let Some(bytes) = stream.try_next().await.context(error::ObjectStoreSnafu)? {
+            file.write_all(&bytes)
+                .await
+                .context(error::WriteFileSnafu { path: &tmp_path })?;
+        }
+        file.sync_all().await.context(error::SyncFileSnafu)?;
+        tokio::fs::rename(&tmp_path, path)
+            .await
+            .context(error::IOSnafu)?;
+        Ok(())
+    }
+
+    /// Reads the bloom filter of field ids of the cold TSM file, only the footer of the
+    /// object is downloaded if the file is not cached.
+    pub async fn read_field_filter(&self, path: &Path) -> Result<Arc<BloomFilter>> {
+        if try_exists(path) {
+            let tsm_reader = TsmReader::open(path).await?;
+            return Ok(tsm_reader.bloom_filter());
+        }
+
+        let location = self.location(path)?;
+        let object_meta = self
+            .object_store
+            .head(&location)
+            .await
+            .context(error::ObjectStoreSnafu)?;
+        if object_meta.size < tsm::FOOTER_SIZE {
+            return Err(Error::CommonError {
+                reason: format!(
+                    "cold file '{}' size {} is less than the footer size",
+                    path.display(),
+                    object_meta.size
+                ),
+            });
+        }
+        let footer_pos = object_meta.size - tsm::FOOTER_SIZE;
+        let bloom_filter = self
+            .object_store
+            .get_range(&location, footer_pos..footer_pos + tsm::BLOOM_FILTER_SIZE)
+            .await
+            .context(error::ObjectStoreSnafu)?;
+        Ok(Arc::new(BloomFilter::with_data(&bloom_filter)))
+    }
+
+    /// Deletes the object of a cached cold file.
+    pub async fn delete(&self, path: &Path) -> Result<()> {
+        let location = self.location(path)?;
+        match self.object_store.delete(&location).await {
+            Ok(_) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(Error::ObjectStore { source: e }),
+        }
+    }
+
+    /// Deletes all objects under a local directory, like a vnode or database directory.
+    pub async fn delete_dir(&self, dir: &Path) -> Result<()> {
+        let location = self.location(dir)?;
+        let objects: Vec<_> = self
+            .object_store
+            .list(Some(&location))
+            .await
+            .context(error::ObjectStoreSnafu)?
+            .try_collect()
+            .await
+            .context(error::ObjectStoreSnafu)?;
+        for object in objects {
+            match self.object_store.delete(&object.location).await {
+                Ok(_) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(Error::ObjectStore { source: e }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes least recently read cached files until the total size of cached files is
+    /// not greater than `cache_max_size`. Files not read since started are treated as read
+    /// when they were downloaded.
+    pub fn evict_cache(&self, paths: &[PathBuf]) {
+        let mut cached_files: Vec<(SystemTime, u64, &PathBuf)> = {
+            let last_access = self.last_access.lock();
+            paths
+                .iter()
+                .filter_map(|p| {
+                    let meta = std::fs::metadata(p).ok()?;
+                    let accessed = match last_access.get(p) {
+                        Some(accessed) => *accessed,
+                        None => meta.modified().ok()?,
+                    };
+                    Some((accessed, meta.len(), p))
+                })
+                .collect()
+        };
+        let mut total_size: u64 = cached_files.iter().map(|(_, size, _)| size).sum();
+        if total_size <= self.cache_max_size {
+            return;
+        }
+        cached_files.sort_by_key(|(accessed, _, _)| *accessed);
+        for (_, size, path) in cached_files {
+            if total_size <= self.cache_max_size {
+                break;
+            }
+            match std::fs::remove_file(path) {
+                Ok(_) => {
+                    debug!("Evicted cached cold file '{}'", path.display());
+                    self.last_access.lock().remove(path);
+                    total_size -= size;
+                }
+                Err(e) => error!("Failed to evict '{}': {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Moves TSM files in vnode to cold storage, returns the moved files.
+    async fn move_ts_family(
+        &self,
+        ts_family: Arc<RwLock<TseriesFamily>>,
+        summary_task_sender: &Sender<SummaryTask>,
+    ) -> Result<Vec<ColumnFileId>> {
+        let tsf = ts_family.read().await;
+        if !tsf.can_compaction() {
+            return Ok(vec![]);
+        }
+        let (tsf_id, database, version) = (tsf.tf_id(), tsf.database(), tsf.version());
+        drop(tsf);
+
+        let storage_opt = version.storage_opt();
+        let cold_dir = storage_opt.cold_dir(&database, tsf_id);
+        let files: Vec<Arc<ColumnFile>> = version
+            .levels_info()
+            .iter()
+            .skip(1)
+            .flat_map(|l| l.files.iter())
+            .filter(|f| !f.is_delta() && !f.is_remote() && f.mark_compacting())
+            .cloned()
+            .collect();
+        if files.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let res = self
+            .move_files(
+                tsf_id,
+                &files,
+                &cold_dir,
+                version.max_level_ts,
+                summary_task_sender,
+            )
+            .await;
+        if res.is_err() {
+            for file in files.iter() {
+                file.unmark_compacting();
+            }
+        }
+        res.map(|_| files.iter().map(|f| f.file_id()).collect())
+    }
+
+    async fn move_files(
+        &self,
+        tsf_id: TseriesFamilyId,
+        files: &[Arc<ColumnFile>],
+        cold_dir: &Path,
+        max_level_ts: i64,
+        summary_task_sender: &Sender<SummaryTask>,
+    ) -> Result<()> {
+        tokio::fs::create_dir_all(cold_dir)
+            .await
+            .context(error::IOSnafu)?;
+
+        let mut version_edit = VersionEdit::new(tsf_id);
+        let mut file_metas = HashMap::with_capacity(files.len());
+        let mut tomb_paths = Vec::new();
+        for file in files {
+            let path = file.file_path();
+            let cold_path = file_utils::make_tsm_file_name(cold_dir, file.file_id());
+            self.upload(&path, &cold_path).await?;
+
+            // Tombstone is kept in local, beside the cached file.
+            let tomb_path = file_utils::make_tsm_tombstone_file_name(
+                path.parent().unwrap_or(cold_dir),
+                file.file_id(),
+            );
+            if try_exists(&tomb_path) {
+                let cold_tomb_path =
+                    file_utils::make_tsm_tombstone_file_name(cold_dir, file.file_id());
+                tokio::fs::copy(&tomb_path, &cold_tomb_path)
+                    .await
+                    .context(error::IOSnafu)?;
+                tomb_paths.push(tomb_path);
+            }
+
+            let mut meta = CompactMeta::from(file.as_ref());
+            meta.tsf_id = tsf_id;
+            version_edit.del_file(meta.level, meta.file_id, false);
+            meta.is_remote = true;
+            version_edit.add_file(meta, max_level_ts);
+            file_metas.insert(file.file_id(), file.field_id_filter());
+        }
+
+        let (summary_tx, summary_rx) = oneshot::channel();
+        summary_task_sender
+            .send(SummaryTask::new(
+                vec![version_edit],
+                Some(file_metas),
+                None,
+                summary_tx,
+            ))
+            .await
+            .map_err(|e| Error::CommonError {
+                reason: format!("failed to send summary task: {}", e),
+            })?;
+        summary_rx.await.map_err(|e| Error::CommonError {
+            reason: format!("failed to receive summary result: {}", e),
+        })??;
+
+        for tomb_path in tomb_paths {
+            if let Err(e) = tokio::fs::remove_file(&tomb_path).await {
+                error!(
+                    "Failed to remove tombstone '{}': {}",
+                    tomb_path.display(),
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn run_job(
+    cold_storage: Arc<ColdStorage>,
+    runtime: Arc<Runtime>,
+    meta: MetaRef,
+    version_set: Arc<RwLock<VersionSet>>,
+    summary_task_sender: Sender<SummaryTask>,
+) {
+    runtime.spawn(async move {
+        let mut check_interval = tokio::time::interval(cold_storage.check_interval);
+        loop {
+            check_interval.tick().await;
+
+            let dbs: Vec<_> = version_set
+                .read()
+                .await
+                .get_all_db()
+                .values()
+                .cloned()
+                .collect();
+            let mut cached_files = Vec::new();
+            for db in dbs {
+                let db = db.read().await;
+                let ts_families: Vec<_> = db.ts_families().values().cloned().collect();
+                for tsf in ts_families.iter() {
+                    let version = tsf.read().await.version();
+                    for level in version.levels_info().iter() {
+                        for file in level.files.iter().filter(|f| f.is_remote()) {
+                            cached_files.push(file.file_path());
+                        }
+                    }
+                }
+
+                let schema = match db.get_schema() {
+                    Ok(schema) => schema,
+                    Err(e) => {
+                        error!("Failed to get schema of database {}: {}", db.owner(), e);
+                        continue;
+                    }
+                };
+                let time_to_cold = match schema.time_to_cold() {
+                    Some(t) => t,
+                    None => continue,
+                };
+                let tenant = match meta.tenant_meta(schema.tenant_name()).await {
+                    Some(tenant) => tenant,
+                    None => continue,
+                };
+                drop(db);
+
+                for tsf in ts_families {
+                    let vnode_id = tsf.read().await.tf_id();
+                    match tenant.get_vnode_all_info(vnode_id) {
+                        Some(info) if info.end_time < time_to_cold => {}
+                        _ => continue,
+                    }
+                    match cold_storage.move_ts_family(tsf, &summary_task_sender).await {
+                        Ok(files) if !files.is_empty() => {
+                            info!(
+                                "Moved files {:?} of vnode {} to cold storage",
+                                files, vnode_id
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            error!("Failed to move vnode {} to cold storage: {}", vnode_id, e);
+                        }
+                    }
+                }
+            }
+
+            cold_storage.evict_cache(&cached_files);
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use config::ColdStorageConfig;
+    use object_store::local::LocalFileSystem;
+
+    use super::ColdStorage;
+    use crate::kv_option::StorageOptions;
+
+    #[tokio::test]
+    async fn test_upload_fetch() {
+        let dir = "/tmp/test/cold_storage/upload_fetch";
+        let _ = std::fs::remove_dir_all(dir);
+        let remote_dir = format!("{dir}/remote");
+        std::fs::create_dir_all(&remote_dir).unwrap();
+        let storage_opt = StorageOptions {
+            path: format!("{dir}/local").into(),
+            ..Default::default()
+        };
+        let store = LocalFileSystem::new_with_prefix(&remote_dir).unwrap();
+        let cold_storage = ColdStorage::new(
+            Arc::new(store),
+            "cnosdb",
+            &storage_opt,
+            &ColdStorageConfig::default(),
+        );
+
+        let tsm_dir = storage_opt.tsm_dir("db", 1);
+        let cold_dir = storage_opt.cold_dir("db", 1);
+        std::fs::create_dir_all(&tsm_dir).unwrap();
+        std::fs::create_dir_all(&cold_dir).unwrap();
+        let local_path = tsm_dir.join("_000001.tsm");
+        let cold_path = cold_dir.join("_000001.tsm");
+        std::fs::write(&local_path, b"cold data").unwrap();
+
+        cold_storage.upload(&local_path, &cold_path).await.unwrap();
+        assert!(
+            std::path::Path::new(&format!("{remote_dir}/cnosdb/db/1/cold/_000001.tsm")).exists()
+        );
+
+        // Files not in cold directory are not fetched.
+        std::fs::remove_file(&local_path).unwrap();
+        cold_storage.fetch(&local_path).await.unwrap();
+        assert!(!local_path.exists());
+
+        cold_storage.fetch(&cold_path).await.unwrap();
+        assert_eq!(std::fs::read(&cold_path).unwrap(), b"cold data");
+
+        cold_storage.evict_cache(&[cold_path.clone()]);
+        assert!(cold_path.exists());
+
+        cold_storage
+            .delete_dir(&storage_opt.ts_family_dir("db", 1))
+            .await
+            .unwrap();
+        assert!(
+            !std::path::Path::new(&format!("{remote_dir}/cnosdb/db/1/cold/_000001.tsm")).exists()
+        );
+    }
+
+    #[test]
+    fn test_evict_cache_by_access() {
+        let dir = "/tmp/test/cold_storage/evict_cache_by_access";
+        let _ = std::fs::remove_dir_all(dir);
+        let remote_dir = format!("{dir}/remote");
+        std::fs::create_dir_all(&remote_dir).unwrap();
+        let storage_opt = StorageOptions {
+            path: format!("{dir}/local").into(),
+            ..Default::default()
+        };
+        let store = LocalFileSystem::new_with_prefix(&remote_dir).unwrap();
+        let config = ColdStorageConfig {
+            cache_max_size: 10,
+            ..Default::default()
+        };
+        let cold_storage = ColdStorage::new(Arc::new(store), "cnosdb", &storage_opt, &config);
+
+        let cold_dir = storage_opt.cold_dir("db", 1);
+        std::fs::create_dir_all(&cold_dir).unwrap();
+        let path_1 = cold_dir.join("_000001.tsm");
+        let path_2 = cold_dir.join("_000002.tsm");
+        std::fs::write(&path_1, b"cold 001").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(&path_2, b"cold 002").unwrap();
+
+        // The file downloaded earlier is read later, the other one is evicted.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        cold_storage.touch(&path_1);
+        cold_storage.evict_cache(&[path_1.clone(), path_2.clone()]);
+        assert!(path_1.exists());
+        assert!(!path_2.exists());
+    }
+}
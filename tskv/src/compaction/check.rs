@@ -1,26 +1,35 @@
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Display, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use blake3::Hasher;
-use datafusion::arrow::array::{StringBuilder, UInt32Array};
+use datafusion::arrow::array::{
+    Array, BinaryArray, BinaryBuilder, BooleanArray, BooleanBuilder, Float64Array, Float64Builder,
+    Int64Array, Int64Builder, StringBuilder, UInt32Array, UInt32Builder, UInt64Array,
+    UInt64Builder,
+};
 use datafusion::arrow::datatypes::{
     DataType as ArrowDataType, Field as ArrowField, Schema, SchemaRef,
 };
 use datafusion::arrow::record_batch::RecordBatch;
-use models::predicate::domain::TimeRange;
-use models::{utils as model_utils, ColumnId, FieldId, SeriesId, Timestamp};
+use models::predicate::domain::{TimeRange, TimeRanges};
+use models::{utils as model_utils, ColumnId, FieldId, SeriesId, SeriesKey, Timestamp};
 use tokio::sync::RwLock;
 
 use crate::compaction::CompactIterator;
 use crate::error::{Error, Result};
+use crate::index::ts_index::TSIndex;
+use crate::memcache::DataType;
+use crate::schema::schemas::DBschemas;
 use crate::tseries_family::TseriesFamily;
-use crate::tsm::{DataBlock, TsmReader};
+use crate::tsm::{self, DataBlock, TsmReader};
 use crate::TseriesFamilyId;
 
 const DEFAULT_DURATION: i64 = 24 * 60 * 60 * 1_000_000_000;
+const MAX_DATA_BLOCK_SIZE: usize = 1000;
 
 pub type Hash = [u8; 32];
 
@@ -218,28 +227,400 @@ pub(crate) async fn vnode_checksum(vnode: Arc<RwLock<TseriesFamily>>) -> Result<
     })
 }
 
-pub(crate) async fn vnode_hash_tree(
+pub fn vnode_time_range_checksum_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        ArrowField::new("SERIES_KEY", ArrowDataType::Binary, false),
+        ArrowField::new("COLUMN_ID", ArrowDataType::UInt32, false),
+        ArrowField::new("MIN_TIME", ArrowDataType::Int64, false),
+        ArrowField::new("MAX_TIME", ArrowDataType::Int64, false),
+        ArrowField::new("CHECK_SUM", ArrowDataType::Utf8, false),
+    ]))
+}
+
+/// Returns the checksum of each column of each series in each time range of the vnode.
+///
+/// Series ids of replicas may differ, so series are identified by the encoded series
+/// keys with series id 0. Time ranges are left-closed and right-open.
+pub(crate) async fn vnode_time_range_checksum(
     vnode: Arc<RwLock<TseriesFamily>>,
-) -> Result<VnodeHashTreeNode> {
-    const MAX_DATA_BLOCK_SIZE: u32 = 1000;
+    ts_index: Arc<TSIndex>,
+) -> Result<RecordBatch> {
+    let root_node = vnode_hash_tree(vnode).await?;
+
+    let capacity = root_node.len();
+    let mut series_key_array = BinaryBuilder::with_capacity(capacity, 64 * capacity);
+    let mut column_id_array = UInt32Builder::with_capacity(capacity);
+    let mut min_time_array = Int64Builder::with_capacity(capacity);
+    let mut max_time_array = Int64Builder::with_capacity(capacity);
+    let mut check_sum_array = StringBuilder::with_capacity(capacity, 32 * capacity);
+
+    let mut series_keys: HashMap<SeriesId, Option<Vec<u8>>> = HashMap::new();
+    for field in root_node.fields.iter() {
+        let (column_id, series_id) = field.column_series();
+        let series_key = match series_keys.entry(series_id) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let series_key = ts_index.get_series_key(series_id).await?.map(|mut key| {
+                    key.set_id(0);
+                    key.encode()
+                });
+                e.insert(series_key)
+            }
+        };
+        // The series is deleted.
+        let Some(series_key) = series_key else {
+            continue;
+        };
+        for time_range in field.time_ranges.iter() {
+            series_key_array.append_value(series_key);
+            column_id_array.append_value(column_id);
+            min_time_array.append_value(time_range.min_ts);
+            max_time_array.append_value(time_range.max_ts);
+            check_sum_array.append_value(hash_to_string(time_range.checksum()));
+        }
+    }
 
-    let (version, vnode_id) = {
-        let vnode_rlock = vnode.read().await;
-        (vnode_rlock.version(), vnode_rlock.tf_id())
+    Ok(RecordBatch::try_new(
+        vnode_time_range_checksum_schema(),
+        vec![
+            Arc::new(series_key_array.finish()),
+            Arc::new(column_id_array.finish()),
+            Arc::new(min_time_array.finish()),
+            Arc::new(max_time_array.finish()),
+            Arc::new(check_sum_array.finish()),
+        ],
+    )?)
+}
+
+pub fn vnode_series_data_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        ArrowField::new("SERIES_KEY", ArrowDataType::Binary, false),
+        ArrowField::new("COLUMN_ID", ArrowDataType::UInt32, false),
+        ArrowField::new("TIME", ArrowDataType::Int64, false),
+        ArrowField::new("MAX_TIME", ArrowDataType::Int64, true),
+        ArrowField::new("SEQUENCE", ArrowDataType::UInt64, false),
+        ArrowField::new("U64_VALUE", ArrowDataType::UInt64, true),
+        ArrowField::new("I64_VALUE", ArrowDataType::Int64, true),
+        ArrowField::new("F64_VALUE", ArrowDataType::Float64, true),
+        ArrowField::new("STR_VALUE", ArrowDataType::Binary, true),
+        ArrowField::new("BOOL_VALUE", ArrowDataType::Boolean, true),
+    ]))
+}
+
+/// Returns the values and tombstones of all fields of the series in the time ranges, one
+/// row for each value or tombstone. Series are identified by the encoded series keys with
+/// series id 0, time ranges are closed.
+///
+/// A value row has a null `MAX_TIME`, only the column of the value type is not null. A
+/// tombstone row has all value columns null, `TIME` and `MAX_TIME` are its time range.
+///
+/// `SEQUENCE` is the WAL sequence of a tombstone. Values of a cache have the last sequence
+/// written to the cache, values of files have the last sequence flushed of the vnode, they
+/// are not less than the sequences the values were written with.
+pub(crate) async fn read_vnode_series_data(
+    vnode: Arc<RwLock<TseriesFamily>>,
+    ts_index: Arc<TSIndex>,
+    schemas: Arc<DBschemas>,
+    ranges: &[(SeriesKey, TimeRange)],
+) -> Result<RecordBatch> {
+    // Series id -> (series key, time ranges)
+    let mut series: HashMap<SeriesId, (SeriesKey, Vec<TimeRange>)> = HashMap::new();
+    for (series_key, time_range) in ranges {
+        if let Some(series_id) = ts_index.get_series_id(series_key).await? {
+            let (_, time_ranges) = series
+                .entry(series_id)
+                .or_insert_with(|| (series_key.clone(), vec![]));
+            time_ranges.push(*time_range);
+        }
+    }
+
+    let (file_lock, series_tombstone_path, super_version) = {
+        let vnode = vnode.read().await;
+        (
+            vnode.file_lock(),
+            vnode.series_tombstone_path(),
+            vnode.super_version(),
+        )
     };
-    let mut readers: Vec<Arc<TsmReader>> = Vec::new();
-    let tsm_paths: Vec<PathBuf> = version
-        .levels_info()
-        .iter()
-        .flat_map(|l| l.files.iter().map(|f| f.file_path()))
-        .collect();
-    for p in tsm_paths {
-        let r = version.get_tsm_reader(p).await?;
-        readers.push(r);
+    // Tombstones are added with the data deleted at the same time.
+    let _file_guard = file_lock.read().await;
+    let tombstones = tsm::read_series_tombstones(&series_tombstone_path, |field_id| {
+        series.contains_key(&model_utils::split_id(field_id).1)
+    })
+    .await?;
+
+    let mut series_key_array = BinaryBuilder::new();
+    let mut column_id_array = UInt32Builder::new();
+    let mut time_array = Int64Builder::new();
+    let mut max_time_array = Int64Builder::new();
+    let mut seq_array = UInt64Builder::new();
+    let mut u64_array = UInt64Builder::new();
+    let mut i64_array = Int64Builder::new();
+    let mut f64_array = Float64Builder::new();
+    let mut str_array = BinaryBuilder::new();
+    let mut bool_array = BooleanBuilder::new();
+
+    let mut series_ids: Vec<SeriesId> = series.keys().cloned().collect();
+    series_ids.sort();
+    for series_id in series_ids {
+        let (series_key, time_ranges) = &series[&series_id];
+        let Some(schema) = schemas.get_table_schema(series_key.table())? else {
+            continue;
+        };
+        let encoded_series_key = {
+            let mut series_key = series_key.clone();
+            series_key.set_id(0);
+            series_key.encode()
+        };
+        let time_ranges = Arc::new(TimeRanges::new(time_ranges.clone()));
+
+        for column in schema.fields() {
+            let field_id = model_utils::unite_id(column.id, series_id);
+            // Timestamp -> (value, sequence), values read later override the former.
+            let mut values: BTreeMap<Timestamp, (DataType, u64)> = BTreeMap::new();
+
+            for level in super_version.version.levels_info().iter().rev() {
+                let mut files = level
+                    .files
+                    .iter()
+                    .filter(|f| {
+                        time_ranges.overlaps(f.time_range()) && f.contains_field_id(field_id)
+                    })
+                    .collect::<Vec<_>>();
+                files.sort_by_key(|f| f.file_id());
+                for file in files {
+                    let reader = super_version
+                        .version
+                        .get_tsm_reader(file.file_path())
+                        .await?;
+                    for idx_meta in reader.index_iterator_opt(field_id) {
+                        for blk_meta in idx_meta.block_iterator_opt(time_ranges.clone()) {
+                            let data_block = reader.get_data_block(&blk_meta).await?;
+                            for value in (0..data_block.len()).filter_map(|i| data_block.get(i)) {
+                                if time_ranges.contains(value.timestamp()) {
+                                    values.insert(
+                                        value.timestamp(),
+                                        (value, super_version.version.last_seq),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            let caches = &super_version.caches;
+            for cache in caches.immut_cache.iter().chain([&caches.mut_cache]) {
+                let cache = cache.read();
+                let seq = cache.seq_no();
+                cache.read_field_data(
+                    field_id,
+                    |ts| time_ranges.contains(ts),
+                    |_| true,
+                    |value| {
+                        values.insert(value.timestamp(), (value, seq));
+                    },
+                );
+            }
+
+            for (ts, (value, seq)) in values {
+                series_key_array.append_value(&encoded_series_key);
+                column_id_array.append_value(column.id);
+                time_array.append_value(ts);
+                max_time_array.append_null();
+                seq_array.append_value(seq);
+                u64_array.append_option(match value {
+                    DataType::U64(_, v) => Some(v),
+                    _ => None,
+                });
+                i64_array.append_option(match value {
+                    DataType::I64(_, v) => Some(v),
+                    _ => None,
+                });
+                f64_array.append_option(match value {
+                    DataType::F64(_, v) => Some(v),
+                    _ => None,
+                });
+                str_array.append_option(match &value {
+                    DataType::Str(_, v) => Some(v.as_slice()),
+                    _ => None,
+                });
+                bool_array.append_option(match value {
+                    DataType::Bool(_, v) => Some(v),
+                    _ => None,
+                });
+            }
+
+            for tombstone in tombstones
+                .iter()
+                .filter(|t| t.field_id == field_id && time_ranges.overlaps(&t.time_range))
+            {
+                series_key_array.append_value(&encoded_series_key);
+                column_id_array.append_value(column.id);
+                time_array.append_value(tombstone.time_range.min_ts);
+                max_time_array.append_value(tombstone.time_range.max_ts);
+                seq_array.append_value(tombstone.seq);
+                u64_array.append_null();
+                i64_array.append_null();
+                f64_array.append_null();
+                str_array.append_null();
+                bool_array.append_null();
+            }
+        }
+    }
+
+    Ok(RecordBatch::try_new(
+        vnode_series_data_schema(),
+        vec![
+            Arc::new(series_key_array.finish()),
+            Arc::new(column_id_array.finish()),
+            Arc::new(time_array.finish()),
+            Arc::new(max_time_array.finish()),
+            Arc::new(seq_array.finish()),
+            Arc::new(u64_array.finish()),
+            Arc::new(i64_array.finish()),
+            Arc::new(f64_array.finish()),
+            Arc::new(str_array.finish()),
+            Arc::new(bool_array.finish()),
+        ],
+    )?)
+}
+
+/// Points to write to a time range of a series of a vnode for repairing it, the points
+/// are only written if the digest of the data of the vnode in the time range is still
+/// `digest`.
+#[derive(Debug, Clone)]
+pub struct SeriesRangeRepair {
+    pub series_key: SeriesKey,
+    pub time_range: TimeRange,
+    /// Digest by `series_range_digest` of the data the points are merged from
+    pub digest: String,
+    /// Flatbuffers points
+    pub points: Vec<u8>,
+}
+
+/// The schema of the result of repairing the time ranges of series of a vnode, one row
+/// for each time range, `REPAIRED` is false if the range is skipped.
+pub fn vnode_series_repair_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        ArrowField::new("SERIES_KEY", ArrowDataType::Binary, false),
+        ArrowField::new("MIN_TIME", ArrowDataType::Int64, false),
+        ArrowField::new("MAX_TIME", ArrowDataType::Int64, false),
+        ArrowField::new("REPAIRED", ArrowDataType::Boolean, false),
+    ]))
+}
+
+/// Builds the result of repairing the time ranges of series of a vnode from the encoded
+/// series keys and time ranges, and if each range is repaired.
+pub(crate) fn vnode_series_repair_batch(
+    results: &[(Vec<u8>, TimeRange, bool)],
+) -> Result<RecordBatch> {
+    let mut series_key_array = BinaryBuilder::with_capacity(results.len(), 0);
+    let mut min_time_array = Int64Builder::with_capacity(results.len());
+    let mut max_time_array = Int64Builder::with_capacity(results.len());
+    let mut repaired_array = BooleanBuilder::with_capacity(results.len());
+    for (series_key, time_range, repaired) in results {
+        series_key_array.append_value(series_key);
+        min_time_array.append_value(time_range.min_ts);
+        max_time_array.append_value(time_range.max_ts);
+        repaired_array.append_value(*repaired);
+    }
+
+    Ok(RecordBatch::try_new(
+        vnode_series_repair_schema(),
+        vec![
+            Arc::new(series_key_array.finish()),
+            Arc::new(min_time_array.finish()),
+            Arc::new(max_time_array.finish()),
+            Arc::new(repaired_array.finish()),
+        ],
+    )?)
+}
+
+fn series_data_column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a T> {
+    batch
+        .column_by_name(name)
+        .and_then(|array| array.as_any().downcast_ref::<T>())
+        .ok_or_else(|| Error::CommonError {
+            reason: format!("invalid column {name} of the series data"),
+        })
+}
+
+/// Returns the digest of the values and tombstones of the series in the time range of the
+/// data read by `read_vnode_series_data`, the series key is encoded with series id 0 and
+/// the time range is closed.
+///
+/// Sequences are not digested, sequences of values change when caches are flushed.
+pub fn series_range_digest(
+    batch: &RecordBatch,
+    series_key: &[u8],
+    time_range: &TimeRange,
+) -> Result<String> {
+    let series_keys = series_data_column::<BinaryArray>(batch, "SERIES_KEY")?;
+    let column_ids = series_data_column::<UInt32Array>(batch, "COLUMN_ID")?;
+    let times = series_data_column::<Int64Array>(batch, "TIME")?;
+    let max_times = series_data_column::<Int64Array>(batch, "MAX_TIME")?;
+    let u64_values = series_data_column::<UInt64Array>(batch, "U64_VALUE")?;
+    let i64_values = series_data_column::<Int64Array>(batch, "I64_VALUE")?;
+    let f64_values = series_data_column::<Float64Array>(batch, "F64_VALUE")?;
+    let str_values = series_data_column::<BinaryArray>(batch, "STR_VALUE")?;
+    let bool_values = series_data_column::<BooleanArray>(batch, "BOOL_VALUE")?;
+
+    let mut hasher = Hasher::new();
+    for row in 0..batch.num_rows() {
+        if series_keys.value(row) != series_key {
+            continue;
+        }
+        let time = times.value(row);
+        if max_times.is_valid(row) {
+            // A tombstone
+            let max_time = max_times.value(row);
+            if !time_range.overlaps(&TimeRange::new(time, max_time)) {
+                continue;
+            }
+            hasher.update(&[0]);
+            hasher.update(&column_ids.value(row).to_be_bytes());
+            hasher.update(&time.to_be_bytes());
+            hasher.update(&max_time.to_be_bytes());
+            continue;
+        }
+        if !time_range.contains(time) {
+            continue;
+        }
+        hasher.update(&[1]);
+        hasher.update(&column_ids.value(row).to_be_bytes());
+        hasher.update(&time.to_be_bytes());
+        if u64_values.is_valid(row) {
+            hasher.update(&[0]);
+            hasher.update(&u64_values.value(row).to_be_bytes());
+        } else if i64_values.is_valid(row) {
+            hasher.update(&[1]);
+            hasher.update(&i64_values.value(row).to_be_bytes());
+        } else if f64_values.is_valid(row) {
+            hasher.update(&[2]);
+            hasher.update(&f64_values.value(row).to_be_bytes());
+        } else if str_values.is_valid(row) {
+            let value = str_values.value(row);
+            hasher.update(&[3]);
+            hasher.update(&(value.len() as u64).to_be_bytes());
+            hasher.update(value);
+        } else if bool_values.is_valid(row) {
+            hasher.update(&[4]);
+            hasher.update(&[bool_values.value(row) as u8]);
+        }
     }
 
+    Ok(hash_to_string(hasher.finalize().into()))
+}
+
+pub(crate) async fn vnode_hash_tree(
+    vnode: Arc<RwLock<TseriesFamily>>,
+) -> Result<VnodeHashTreeNode> {
+    let vnode_id = vnode.read().await.tf_id();
+    let readers = vnode_tsm_readers(vnode).await?;
+
     // Build a compact iterator, read data, split by time range and then calculate hash.
-    let iter = CompactIterator::new(readers, MAX_DATA_BLOCK_SIZE as usize, true);
+    let iter = CompactIterator::new(readers, MAX_DATA_BLOCK_SIZE, true);
     let mut fid_tr_hash_val_map: HashMap<FieldId, Vec<(TimeRange, Hash)>> =
         read_from_compact_iterator(iter, vnode_id, DEFAULT_DURATION).await?;
 
@@ -258,6 +639,22 @@ pub(crate) async fn vnode_hash_tree(
     Ok(vnode_hash_tree_node)
 }
 
+async fn vnode_tsm_readers(vnode: Arc<RwLock<TseriesFamily>>) -> Result<Vec<Arc<TsmReader>>> {
+    let version = vnode.read().await.version();
+    let mut readers: Vec<Arc<TsmReader>> = Vec::new();
+    let tsm_paths: Vec<PathBuf> = version
+        .levels_info()
+        .iter()
+        .flat_map(|l| l.files.iter().map(|f| f.file_path()))
+        .collect();
+    for p in tsm_paths {
+        let r = version.get_tsm_reader(p).await?;
+        readers.push(r);
+    }
+
+    Ok(readers)
+}
+
 /// Returns a time range calculated by the given `blk_min_ts1`
 /// and `split_time_range_nanosecs`.
 ///
@@ -357,91 +754,53 @@ fn hash_partial_datablock(
 }
 
 async fn read_from_compact_iterator(
-    mut _iter: CompactIterator,
-    _vnode_id: TseriesFamilyId,
-    _time_range_nanosec: i64,
+    mut iter: CompactIterator,
+    vnode_id: TseriesFamilyId,
+    time_range_nanosec: i64,
 ) -> Result<HashMap<FieldId, Vec<(TimeRange, Hash)>>> {
-    // let mut fid_tr_hash_val_map: HashMap<FieldId, Vec<(TimeRange, Hash)>> = HashMap::new();
-    // let mut last_hashed_tr_fid: Option<(TimeRange, FieldId)> = None;
-    // let mut hasher = Hasher::new();
-    // loop {
-    //     match iter.next().await {
-    //         None => break,
-    //         Some(Ok(blk)) => {
-    //             if let CompactingBlock::Decoded {
-    //                 field_id,
-    //                 data_block,
-    //                 ..
-    //             } = blk
-    //             {
-    //                 // Check if there is last hash value that not stored.
-    //                 if let Some((time_range, last_fid)) = last_hashed_tr_fid {
-    //                     if last_fid != field_id {
-    //                         fid_tr_hash_val_map
-    //                             .entry(last_fid)
-    //                             .or_default()
-    //                             .push((time_range, hasher.finalize().into()));
-    //                         hasher.reset();
-    //                     }
-    //                 }
-    //                 if let Some(blk_time_range) = data_block.time_range() {
-    //                     // Get trunced time range by DataBlock.time[0]
-    //                     // TODO: Support data block to be split into multi time ranges.
-    //                     let (min_ts, max_ts) = match calc_block_partial_time_range(
-    //                         blk_time_range.0,
-    //                         time_range_nanosec,
-    //                     ) {
-    //                         Ok(tr) => tr,
-    //                         Err(e) => return Err(e),
-    //                     };
-
-    //                     // Calculate and store the hash value of data in time range
-    //                     let hash_vec = fid_tr_hash_val_map.entry(field_id).or_default();
-    //                     if blk_time_range.1 > max_ts {
-    //                         // Time range of data block need split.
-    //                         let min_idx =
-    //                             hash_partial_datablock(&mut hasher, &data_block, 0, max_ts);
-    //                         hash_vec
-    //                             .push((TimeRange::new(min_ts, max_ts), hasher.finalize().into()));
-    //                         hasher.reset();
-    //                         hash_partial_datablock(
-    //                             &mut hasher,
-    //                             &data_block,
-    //                             min_idx,
-    //                             Timestamp::MIN,
-    //                         );
-    //                         last_hashed_tr_fid =
-    //                             Some((TimeRange::new(max_ts, blk_time_range.1), field_id));
-    //                     } else {
-    //                         hash_partial_datablock(&mut hasher, &data_block, 0, Timestamp::MIN);
-    //                         last_hashed_tr_fid = Some((TimeRange::new(min_ts, max_ts), field_id));
-    //                     }
-    //                 } else {
-    //                     // Ignore: Case argument decode_non_overlap_blocks in CompactIterator::new()
-    //                     // is set to true, we may ignore it.
-    //                 }
-    //             };
-    //         }
-    //         Some(Err(e)) => {
-    //             return Err(Error::CommonError {
-    //                 reason: format!(
-    //                     "error getting hashes for vnode {} when compacting: {:?}",
-    //                     vnode_id, e
-    //                 ),
-    //             });
-    //         }
-    //     }
-    // }
-    // if let Some((tr, last_fid)) = last_hashed_tr_fid {
-    //     fid_tr_hash_val_map
-    //         .entry(last_fid)
-    //         .or_default()
-    //         .push((tr, hasher.finalize().into()));
-    // }
-
-    // Ok(fid_tr_hash_val_map)
+    // Field id -> min_ts of time range -> hasher
+    let mut fid_tr_hasher_map: HashMap<FieldId, BTreeMap<Timestamp, Hasher>> = HashMap::new();
+    while let Some(blk_meta_group) = iter.next().await {
+        let field_id = blk_meta_group.field_id();
+        let blks = blk_meta_group
+            .merge(None, 0)
+            .await
+            .map_err(|e| Error::CommonError {
+                reason: format!(
+                    "error getting hashes for vnode {} when compacting: {:?}",
+                    vnode_id, e
+                ),
+            })?;
+        let tr_hashers = fid_tr_hasher_map.entry(field_id).or_default();
+        for blk in blks {
+            let data_block = blk.decode()?;
+            let timestamps = data_block.ts();
+            let mut idx = 0;
+            while idx < timestamps.len() {
+                let min_ts = timestamps[idx] - timestamps[idx].rem_euclid(time_range_nanosec);
+                // Hash the data in the time range, or all of the rest data if it overflows.
+                let max_ts = min_ts
+                    .checked_add(time_range_nanosec)
+                    .unwrap_or(Timestamp::MIN);
+                let hasher = tr_hashers.entry(min_ts).or_default();
+                idx = hash_partial_datablock(hasher, &data_block, idx, max_ts);
+            }
+        }
+    }
 
-    Ok(HashMap::new())
+    Ok(fid_tr_hasher_map
+        .into_iter()
+        .map(|(fid, tr_hashers)| {
+            let tr_hashes = tr_hashers
+                .into_iter()
+                .map(|(min_ts, hasher)| {
+                    let max_ts = min_ts.saturating_add(time_range_nanosec);
+                    (TimeRange::new(min_ts, max_ts), hasher.finalize().into())
+                })
+                .collect();
+            (fid, tr_hashes)
+        })
+        .collect())
 }
 
 #[cfg(test)]
@@ -4,7 +4,7 @@ use std::pin::Pin;
 use std::sync::Arc;
 
 use models::predicate::domain::TimeRange;
-use models::{FieldId, Timestamp};
+use models::{FieldId, Timestamp, ValueType};
 use snafu::ResultExt;
 use trace::{error, info, trace};
 use utils::BloomFilter;
@@ -105,19 +105,26 @@ impl CompactingBlockMeta {
 #[derive(Clone)]
 pub(crate) struct CompactingBlockMetaGroup {
     field_id: FieldId,
+    /// Type of the merged blocks, blocks in other types are converted to it.
+    field_type: ValueType,
     blk_metas: Vec<CompactingBlockMeta>,
     time_range: TimeRange,
 }
 impl CompactingBlockMetaGroup {
-    pub fn new(field_id: FieldId, blk_meta: CompactingBlockMeta) -> Self {
+    pub fn new(field_id: FieldId, field_type: ValueType, blk_meta: CompactingBlockMeta) -> Self {
         let time_range = blk_meta.time_range();
         Self {
             field_id,
+            field_type,
             blk_metas: vec![blk_meta],
             time_range,
         }
     }
 
+    pub fn field_id(&self) -> FieldId {
+        self.field_id
+    }
+
     pub fn overlaps(&self, other: &Self) -> bool {
         self.time_range.overlaps(&other.time_range)
     }
@@ -139,7 +146,10 @@ impl CompactingBlockMetaGroup {
             .sort_by(|a, b| a.reader_idx.cmp(&b.reader_idx).reverse());
 
         let merged_block;
-        if self.blk_metas.len() == 1 && !self.blk_metas[0].has_tombstone() {
+        if self.blk_metas.len() == 1
+            && !self.blk_metas[0].has_tombstone()
+            && self.blk_metas[0].meta.field_type() == self.field_type
+        {
             // Only one compacting block and has no tombstone, write as raw block.
             trace!("only one compacting block, write as raw block");
             let meta_0 = &self.blk_metas[0].meta;
@@ -182,13 +192,14 @@ impl CompactingBlockMetaGroup {
                 )]);
             }
         } else {
-            // One block with tombstone or multi compacting blocks, decode and merge these data block.
+            // One block with tombstone or in another type, or multi compacting blocks,
+            // decode and merge these data block.
             trace!(
                 "there are {} compacting blocks, need to decode and merge",
                 self.blk_metas.len()
             );
             let head = &mut self.blk_metas[0];
-            let mut head_block = head.get_data_block().await?;
+            let mut head_block = head.get_data_block().await?.convert(self.field_type)?;
 
             if let Some(compacting_block) = previous_block {
                 let mut data_block = compacting_block.decode()?;
@@ -198,7 +209,7 @@ impl CompactingBlockMetaGroup {
 
             for blk_meta in self.blk_metas[1..].iter_mut() {
                 // Merge decoded data block.
-                let blk_block = blk_meta.get_data_block().await?;
+                let blk_block = blk_meta.get_data_block().await?.convert(self.field_type)?;
                 head_block = head_block.merge(blk_block);
             }
             merged_block = head_block;
@@ -551,11 +562,19 @@ impl CompactIterator {
         }
         // Sort by field_id, min_ts and max_ts.
         blk_metas.sort();
+        // Type of the field may be changed, blocks are merged into the type in the latest file.
+        let field_type = blk_metas
+            .iter()
+            .max_by_key(|b| b.reader.file_id())
+            .map(|b| b.meta.field_type())
+            .unwrap_or(ValueType::Unknown);
 
         let mut blk_meta_groups: Vec<CompactingBlockMetaGroup> =
             Vec::with_capacity(blk_metas.len());
         for blk_meta in blk_metas {
-            blk_meta_groups.push(CompactingBlockMetaGroup::new(field_id, blk_meta));
+            blk_meta_groups.push(CompactingBlockMetaGroup::new(
+                field_id, field_type, blk_meta,
+            ));
         }
         // Compact blk_meta_groups.
         let mut i = 0;
@@ -890,6 +909,7 @@ fn new_compact_meta(
         high_seq: 0,
         low_seq: 0,
         is_delta: false,
+        is_remote: false,
     }
 }
 
@@ -1021,6 +1041,7 @@ pub mod test {
             LevelInfo::init_levels(database.clone(), 0, opt.storage.clone()),
             1000,
             Arc::new(ShardedCache::with_capacity(1)),
+            None,
         ));
         let compact_req = CompactReq {
             ts_family_id: 1,
@@ -4,7 +4,8 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use models::codec::Encoding;
-use models::{utils as model_utils, ColumnId, FieldId, SeriesId, Timestamp, ValueType};
+use models::schema::ColumnType;
+use models::{utils as model_utils, ColumnId, FieldId, SchemaId, SeriesId, Timestamp, ValueType};
 use parking_lot::RwLock;
 use snafu::ResultExt;
 use tokio::sync::mpsc::Sender;
@@ -15,7 +16,7 @@ use utils::BloomFilter;
 
 use crate::compaction::{CompactTask, FlushReq};
 use crate::context::{GlobalContext, GlobalSequenceContext};
-use crate::error::{self, Result};
+use crate::error::{self, Error, Result};
 use crate::memcache::{FieldVal, MemCache, SeriesData};
 use crate::summary::{CompactMeta, CompactMetaBuilder, SummaryTask, VersionEdit};
 use crate::tseries_family::Version;
@@ -123,8 +124,13 @@ impl FlushTask {
         let mut writer = WriterWrapper::new(self.ts_family_id, max_level_ts, max_data_block_size);
 
         let mut column_encoding_map: HashMap<ColumnId, Encoding> = HashMap::new();
-        let mut column_values_map: HashMap<ColumnId, (ValueType, Vec<(Timestamp, FieldVal)>)> =
-            HashMap::new();
+        // Values of a column may be in different types if type of the column was changed,
+        // they are written in the type of the latest schema.
+        #[allow(clippy::type_complexity)]
+        let mut column_values_map: HashMap<
+            ColumnId,
+            (SchemaId, ValueType, Vec<(Timestamp, FieldVal)>),
+        > = HashMap::new();
         for (sid, series_datas) in caches_data.iter_mut() {
             column_encoding_map.clear();
             column_values_map.clear();
@@ -132,7 +138,7 @@ impl FlushTask {
             // Iterates [ MemCache ] -> next_series_id -> [ SeriesData ]
             for series_data in series_datas.iter_mut() {
                 // Iterates SeriesData -> [ RowGroups{ schema_id, schema, [ RowData ] } ]
-                for (_sch_id, sch_cols, rows) in series_data.read().flat_groups() {
+                for (sch_id, sch_cols, rows) in series_data.read().flat_groups() {
                     for i in sch_cols.columns().iter() {
                         column_encoding_map.insert(i.id, i.encoding);
                     }
@@ -141,9 +147,17 @@ impl FlushTask {
                         // Iterates RowData -> [ Option<FieldVal>, column_id ]
                         for (val, col) in row.fields.iter().zip(sch_cols.fields().iter()) {
                             if let Some(v) = val {
-                                let (_, col_vals) = column_values_map
+                                let vtype = match col.column_type {
+                                    ColumnType::Field(t) if t != ValueType::Unknown => t,
+                                    _ => v.value_type(),
+                                };
+                                let (col_sch_id, col_type, col_vals) = column_values_map
                                     .entry(col.id)
-                                    .or_insert_with(|| (v.value_type(), Vec::with_capacity(64)));
+                                    .or_insert_with(|| (sch_id, vtype, Vec::with_capacity(64)));
+                                if *col_sch_id < sch_id {
+                                    *col_sch_id = sch_id;
+                                    *col_type = vtype;
+                                }
                                 col_vals.push((row.ts, v.clone()));
                             }
                         }
@@ -151,7 +165,20 @@ impl FlushTask {
                 }
             }
 
-            for (col_id, (value_type, values)) in column_values_map.iter_mut() {
+            for (col_id, (_, value_type, values)) in column_values_map.iter_mut() {
+                for (ts, v) in values.iter_mut() {
+                    if v.value_type() == *value_type {
+                        continue;
+                    }
+                    // Values are never dropped, the type can only be changed to a wider one.
+                    *v = v.convert(*value_type).ok_or_else(|| Error::Transform {
+                        reason: format!(
+                            "can't convert value at {ts} of column {col_id} from {} to {}",
+                            v.value_type(),
+                            value_type
+                        ),
+                    })?;
+                }
                 // Sort by timestamp.
                 values.sort_by_key(|a| a.0);
                 // Dedup by timestamp.
@@ -198,6 +225,8 @@ pub async fn run_flush_memtable_job(
 
     let mut version_edits: Vec<VersionEdit> = vec![];
     let mut file_metas: HashMap<ColumnFileId, Arc<BloomFilter>> = HashMap::new();
+    // Deleting waits until the flushed files are applied to the version.
+    let mut _file_guard = None;
 
     let get_tsf_result = version_set
         .read()
@@ -205,6 +234,9 @@ pub async fn run_flush_memtable_job(
         .get_tsfamily_by_tf_id(req.ts_family_id)
         .await;
     if let Some(tsf) = get_tsf_result {
+        let file_lock = tsf.read().await.file_lock();
+        _file_guard = Some(file_lock.read_owned().await);
+
         // todo: build path by vnode data
         let (storage_opt, version, database) = {
             let tsf_rlock = tsf.read().await;
@@ -475,6 +507,7 @@ pub mod flush_tests {
                 name: i.to_string(),
                 column_type: ColumnType::Field(ValueType::Unknown),
                 encoding: Encoding::Default,
+                converted_from_tag: None,
             })
             .collect();
 
@@ -529,6 +562,7 @@ pub mod flush_tests {
             max_level_ts: test_case.max_level_ts_before,
             levels_info: LevelInfo::init_levels(database, 0, options.storage),
             tsm_reader_cache: Arc::new(ShardedCache::with_capacity(1)),
+            cold_storage: None,
         });
         let flush_task =
             FlushTask::new(test_case.caches(), 1, global_context, &tsm_dir, &delta_dir);
@@ -122,10 +122,13 @@ pub fn run(
                                 }
                             }
 
+                            // Deleting waits until the compacted files are applied to the version.
+                            let file_lock = tsf.read().await.file_lock();
+                            let _file_guard = file_lock.read().await;
                             match super::run_compaction_job(req, ctx_inner).await {
                                 Ok(Some((version_edit, file_metas))) => {
                                     metrics::incr_compaction_success();
-                                    let (summary_tx, _summary_rx) = oneshot::channel();
+                                    let (summary_tx, summary_rx) = oneshot::channel();
                                     let _ = summary_task_sender_inner
                                         .send(SummaryTask::new(
                                             vec![version_edit],
@@ -134,6 +137,12 @@ pub fn run(
                                             summary_tx,
                                         ))
                                         .await;
+                                    if let Ok(Err(e)) = summary_rx.await {
+                                        error!(
+                                            "Failed to apply compaction of vnode {}: {:?}",
+                                            vnode_id, e
+                                        );
+                                    }
 
                                     metrics::sample_tskv_compaction_duration(
                                         database.as_str(),
@@ -273,6 +273,10 @@ impl LevelCompactionPicker {
         let mut picking_file_size = 0_u64;
         let mut picking_time_range = TimeRange::none();
         for file in src_files.iter() {
+            if file.is_remote() {
+                // Files in cold storage are not compacted.
+                continue;
+            }
             if file.is_compacting() || !file.mark_compacting() {
                 // If file already compacting, continue to next file.
                 continue;
@@ -375,6 +379,7 @@ mod test {
             level_infos,
             1000,
             Arc::new(ShardedCache::with_capacity(1)),
+            None,
         ));
         let (flush_task_sender, _) = mpsc::channel(opt.storage.flush_req_channel_cap);
         let (compactt_task_sender, _) = mpsc::channel(COMPACT_REQ_CHANNEL_CAP);
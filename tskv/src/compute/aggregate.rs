@@ -0,0 +1,476 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use models::predicate::domain::{AggregateKind, TimeRange, TimeRanges};
+use models::{utils as model_utils, ColumnId, FieldId, SeriesId, Timestamp, ValueType};
+use tokio::runtime::Runtime;
+use trace::trace;
+
+use crate::memcache::DataType;
+use crate::tseries_family::{ColumnFile, SuperVersion};
+use crate::tsm::{BlockMeta, BlockStatistics, DataBlock, TsmReader};
+use crate::{Error, Result};
+
+/// Compute pushed down aggregate:
+///
+/// `SELECT min|max|sum|first|last(<field>) FROM <table> WHERE <time_range_predicates>`
+///
+/// Returns None if there is no value in the time ranges.
+pub async fn aggregate_field_values(
+    runtime: Arc<Runtime>,
+    super_version: Arc<SuperVersion>,
+    series_ids: Arc<Vec<SeriesId>>,
+    column_id: ColumnId,
+    value_type: ValueType,
+    kind: AggregateKind,
+    time_ranges: Arc<TimeRanges>,
+) -> Result<Option<DataType>> {
+    match kind {
+        AggregateKind::Count => {
+            return Err(Error::CommonError {
+                reason: "count is not computed by aggregate_field_values".to_string(),
+            });
+        }
+        AggregateKind::Sum
+            if !matches!(
+                value_type,
+                ValueType::Integer | ValueType::Unsigned | ValueType::Float
+            ) =>
+        {
+            return Err(Error::CommonError {
+                reason: format!("sum is not supported for {value_type} values"),
+            });
+        }
+        _ => {}
+    }
+
+    // Files ordered by priority, data in latter files overwrites the former ones.
+    let column_files: Vec<Arc<ColumnFile>> = super_version
+        .version
+        .levels_info
+        .iter()
+        .rev()
+        .filter(|lv| time_ranges.overlaps(&lv.time_range))
+        .flat_map(|lv| lv.files.iter())
+        .filter(|cf| time_ranges.overlaps(cf.time_range()))
+        .cloned()
+        .collect();
+    let column_files = Arc::new(column_files);
+
+    let mut jh_vec = Vec::with_capacity(series_ids.len());
+    for series_id in series_ids.iter() {
+        let field_id = model_utils::unite_id(column_id, *series_id);
+        jh_vec.push(runtime.spawn(aggregate_field_values_inner(
+            super_version.clone(),
+            field_id,
+            value_type,
+            kind,
+            column_files.clone(),
+            time_ranges.clone(),
+        )));
+    }
+
+    let mut accumulator = Accumulator::new(kind);
+    for jh in jh_vec {
+        // JoinHandle returns JoinError if task was paniced.
+        if let Some(data) = jh.await.map_err(|e| Error::IO { source: e.into() })?? {
+            accumulator.update(data);
+        }
+    }
+
+    Ok(accumulator.finish())
+}
+
+/// Aggregate values in time ranges of a field.
+async fn aggregate_field_values_inner(
+    super_version: Arc<SuperVersion>,
+    field_id: FieldId,
+    value_type: ValueType,
+    kind: AggregateKind,
+    column_files: Arc<Vec<Arc<ColumnFile>>>,
+    time_ranges: Arc<TimeRanges>,
+) -> Result<Option<DataType>> {
+    let mut accumulator = Accumulator::new(kind);
+
+    // Data in caches overwrites data in files.
+    let time_predicate = |ts| time_ranges.is_boundless() || time_ranges.contains(ts);
+    let mut cached_data: BTreeMap<Timestamp, DataType> = BTreeMap::new();
+    super_version.caches.read_field_data(
+        field_id,
+        time_predicate,
+        |_| true,
+        |d| {
+            let d = d.convert(value_type).unwrap_or_else(|d| d);
+            cached_data.insert(d.timestamp(), d);
+        },
+    );
+    let cached_time_range = match (cached_data.keys().next(), cached_data.keys().next_back()) {
+        (Some(min_ts), Some(max_ts)) => TimeRange::new(*min_ts, *max_ts),
+        _ => TimeRange::none(),
+    };
+    for data in cached_data.values() {
+        accumulator.update(data.clone());
+    }
+
+    let read_tasks =
+        create_file_read_tasks(&super_version, &column_files, field_id, &time_ranges).await?;
+    let mut groups = group_overlapped_read_tasks(read_tasks);
+
+    // Blocks of first or last values are enough.
+    if kind == AggregateKind::Last {
+        groups.reverse();
+    }
+    for (group_time_range, group) in groups {
+        if let Some(ts) = accumulator.value().map(|v| v.timestamp()) {
+            match kind {
+                AggregateKind::First if ts < group_time_range.min_ts => break,
+                AggregateKind::Last if ts > group_time_range.max_ts => break,
+                _ => {}
+            }
+        }
+
+        let cache_overlapped = cached_time_range.overlaps(&group_time_range);
+        if group.len() == 1 && !cache_overlapped {
+            // Only 1 block in group, no need to deduplicate.
+            let read_task = &group[0];
+            if let Some(stats) = read_task.full_block_statistics(value_type) {
+                if accumulator.update_by_statistics(&stats, read_task.time_range.min_ts) {
+                    trace!(
+                        "Aggregate single block by statistics: {}",
+                        &read_task.time_range
+                    );
+                    continue;
+                }
+            }
+            trace!("Aggregate single block: {}", &read_task.time_range);
+            let blk = read_task
+                .tsm_reader
+                .get_data_block(&read_task.block_meta)
+                .await?
+                .convert(value_type)?;
+            for_each_value(&blk, |data| {
+                if !read_task.time_range_intersected || time_predicate(data.timestamp()) {
+                    accumulator.update(data);
+                }
+            });
+        } else {
+            // Blocks are overlapped, decode and deduplicate by timestamp.
+            trace!("Aggregate {} overlapped blocks", group.len());
+            let mut merged_data: BTreeMap<Timestamp, DataType> = BTreeMap::new();
+            let mut group = group;
+            group.sort_by_key(|t| t.priority);
+            for read_task in group {
+                let blk = read_task
+                    .tsm_reader
+                    .get_data_block(&read_task.block_meta)
+                    .await?
+                    .convert(value_type)?;
+                for_each_value(&blk, |data| {
+                    let ts = data.timestamp();
+                    if time_predicate(ts) && !cached_data.contains_key(&ts) {
+                        merged_data.insert(ts, data);
+                    }
+                });
+            }
+            for data in merged_data.into_values() {
+                accumulator.update(data);
+            }
+        }
+    }
+
+    Ok(accumulator.finish())
+}
+
+struct ReadTask {
+    /// Reader for a file.
+    tsm_reader: Arc<TsmReader>,
+    /// BlockMeta in a file.
+    block_meta: BlockMeta,
+    /// Time range by BlockMeta::time_range() .
+    time_range: TimeRange,
+    /// Is time_range is intersected with time range predicates.
+    time_range_intersected: bool,
+    /// Data in read task with greater priority overwrites the others.
+    priority: usize,
+}
+
+impl ReadTask {
+    /// Returns statistics of the block if all values in the block are to be aggregated,
+    /// which means the block is in time ranges and not deleted by tombstones, and the
+    /// block is not written before type of the field was changed.
+    fn full_block_statistics(&self, value_type: ValueType) -> Option<BlockStatistics> {
+        if self.time_range_intersected
+            || self.block_meta.field_type() != value_type
+            || self
+                .tsm_reader
+                .get_block_tombstone_time_ranges(&self.block_meta)
+                .is_some()
+        {
+            return None;
+        }
+        self.block_meta.statistics()
+    }
+}
+
+/// Filter block metas in files by time ranges, open files and then create file read tasks.
+async fn create_file_read_tasks(
+    super_version: &SuperVersion,
+    files: &[Arc<ColumnFile>],
+    field_id: FieldId,
+    time_ranges: &TimeRanges,
+) -> Result<Vec<ReadTask>> {
+    let mut read_tasks: Vec<ReadTask> = Vec::new();
+    for (priority, cf) in files.iter().enumerate() {
+        if !cf.contains_field_id(field_id) {
+            continue;
+        }
+        let reader = super_version.version.get_tsm_reader(cf.file_path()).await?;
+        for idx in reader.index_iterator_opt(field_id) {
+            for blk_meta in idx.block_iterator() {
+                let blk_tr = blk_meta.time_range();
+                if !time_ranges.overlaps(&blk_tr) {
+                    continue;
+                }
+                read_tasks.push(ReadTask {
+                    tsm_reader: reader.clone(),
+                    time_range_intersected: !time_ranges.includes(&blk_tr),
+                    time_range: blk_tr,
+                    block_meta: blk_meta,
+                    priority,
+                });
+            }
+        }
+    }
+
+    read_tasks.sort_by(|a, b| a.time_range.cmp(&b.time_range));
+    Ok(read_tasks)
+}
+
+/// Group read tasks sorted by time range, read tasks in different groups have no overlapped time range.
+fn group_overlapped_read_tasks(read_tasks: Vec<ReadTask>) -> Vec<(TimeRange, Vec<ReadTask>)> {
+    let mut groups: Vec<(TimeRange, Vec<ReadTask>)> = Vec::new();
+    for read_task in read_tasks {
+        match groups.last_mut() {
+            Some((group_tr, group)) if group_tr.overlaps(&read_task.time_range) => {
+                group_tr.merge(&read_task.time_range);
+                group.push(read_task);
+            }
+            _ => groups.push((read_task.time_range, vec![read_task])),
+        }
+    }
+    groups
+}
+
+fn for_each_value(blk: &DataBlock, mut f: impl FnMut(DataType)) {
+    for i in 0..blk.len() {
+        if let Some(data) = blk.get(i) {
+            f(data);
+        }
+    }
+}
+
+/// Compare values of two data, returns None if types are different or not comparable.
+fn compare_value(a: &DataType, b: &DataType) -> Option<Ordering> {
+    match (a, b) {
+        (DataType::U64(_, a), DataType::U64(_, b)) => Some(a.cmp(b)),
+        (DataType::I64(_, a), DataType::I64(_, b)) => Some(a.cmp(b)),
+        (DataType::F64(_, a), DataType::F64(_, b)) => a.partial_cmp(b),
+        (DataType::Str(_, a), DataType::Str(_, b)) => Some(a.as_slice().cmp(b.as_slice())),
+        (DataType::Bool(_, a), DataType::Bool(_, b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+struct Accumulator {
+    kind: AggregateKind,
+    value: Option<DataType>,
+}
+
+impl Accumulator {
+    fn new(kind: AggregateKind) -> Self {
+        Self { kind, value: None }
+    }
+
+    fn value(&self) -> Option<&DataType> {
+        self.value.as_ref()
+    }
+
+    fn update(&mut self, data: DataType) {
+        let current = match self.value.as_mut() {
+            Some(current) => current,
+            None => {
+                self.value = Some(data);
+                return;
+            }
+        };
+        let replace = match self.kind {
+            AggregateKind::Min => compare_value(&data, current) == Some(Ordering::Less),
+            AggregateKind::Max => compare_value(&data, current) == Some(Ordering::Greater),
+            AggregateKind::First => data.timestamp() < current.timestamp(),
+            AggregateKind::Last => data.timestamp() > current.timestamp(),
+            AggregateKind::Sum => {
+                match (current, &data) {
+                    (DataType::U64(_, sum), DataType::U64(_, v)) => *sum = sum.wrapping_add(*v),
+                    (DataType::I64(_, sum), DataType::I64(_, v)) => *sum = sum.wrapping_add(*v),
+                    (DataType::F64(_, sum), DataType::F64(_, v)) => *sum += *v,
+                    _ => {}
+                }
+                false
+            }
+            AggregateKind::Count => false,
+        };
+        if replace {
+            self.value = Some(data);
+        }
+    }
+
+    /// Update by statistics of a block, returns false if the block still needs to be read.
+    fn update_by_statistics(&mut self, stats: &BlockStatistics, ts: Timestamp) -> bool {
+        match self.kind {
+            AggregateKind::Sum => {
+                let data = match stats {
+                    BlockStatistics::Unsigned { sum, .. } => DataType::U64(ts, *sum),
+                    BlockStatistics::Integer { sum, .. } => DataType::I64(ts, *sum),
+                    BlockStatistics::Float { sum, .. } => DataType::F64(ts, *sum),
+                    BlockStatistics::String { .. } => return false,
+                };
+                self.update(data);
+                true
+            }
+            // Values in block can't replace the current one, block is not needed.
+            AggregateKind::Min => match (stats, self.value.as_ref()) {
+                (BlockStatistics::Unsigned { min, .. }, Some(DataType::U64(_, v))) => min >= v,
+                (BlockStatistics::Integer { min, .. }, Some(DataType::I64(_, v))) => min >= v,
+                (BlockStatistics::Float { min, .. }, Some(DataType::F64(_, v))) => min >= v,
+                _ => false,
+            },
+            AggregateKind::Max => match (stats, self.value.as_ref()) {
+                (BlockStatistics::Unsigned { max, .. }, Some(DataType::U64(_, v))) => max <= v,
+                (BlockStatistics::Integer { max, .. }, Some(DataType::I64(_, v))) => max <= v,
+                (BlockStatistics::Float { max, .. }, Some(DataType::F64(_, v))) => max <= v,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn finish(self) -> Option<DataType> {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use memory_pool::{GreedyMemoryPool, MemoryPoolRef};
+    use models::predicate::domain::{AggregateKind, TimeRanges};
+    use models::{utils as model_utils, SeriesId, ValueType};
+    use parking_lot::RwLock;
+    use tokio::runtime::Runtime;
+
+    use super::aggregate_field_values;
+    use crate::compaction::test::write_data_blocks_to_column_file;
+    use crate::memcache::{DataType, MemCache};
+    use crate::tseries_family::test_tseries_family::build_version_by_column_files;
+    use crate::tseries_family::{CacheGroup, SuperVersion};
+    use crate::tsm::codec::DataBlockEncoding;
+    use crate::tsm::DataBlock;
+    use crate::{Options, Result};
+
+    struct TestHelper {
+        runtime: Arc<Runtime>,
+        super_version: Arc<SuperVersion>,
+    }
+
+    impl TestHelper {
+        fn run(
+            &self,
+            series_ids: &[SeriesId],
+            kind: AggregateKind,
+            time_ranges: impl Into<TimeRanges>,
+        ) -> Result<Option<DataType>> {
+            let time_ranges = Arc::new(time_ranges.into());
+            self.runtime.block_on(aggregate_field_values(
+                self.runtime.clone(),
+                self.super_version.clone(),
+                Arc::new(series_ids.to_vec()),
+                1,
+                ValueType::Integer,
+                kind,
+                time_ranges,
+            ))
+        }
+    }
+
+    #[test]
+    fn test_super_version_aggregate_file() {
+        let dir = "/tmp/test/ts_family/super_version_aggregate_file";
+        let mut global_config = config::get_config_for_test();
+        global_config.storage.path = dir.to_string();
+
+        // The second file overwrites value of sid=1 at ts=4.
+        #[rustfmt::skip]
+        let data = vec![
+            HashMap::from([
+                (model_utils::unite_id(1, 1), vec![DataBlock::I64 { ts: vec![1, 2, 3, 4], val: vec![5, 3, 8, 100], enc: DataBlockEncoding::default() }]),
+                (model_utils::unite_id(1, 2), vec![DataBlock::I64 { ts: vec![10, 20, 30, 40], val: vec![1, 2, 3, 4], enc: DataBlockEncoding::default() }]),
+            ]),
+            HashMap::from([
+                (model_utils::unite_id(1, 1), vec![DataBlock::I64 { ts: vec![4, 5, 6], val: vec![4, -1, 6], enc: DataBlockEncoding::default() }]),
+            ]),
+            HashMap::from([
+                (model_utils::unite_id(1, 1), vec![DataBlock::I64 { ts: vec![7, 8, 9], val: vec![7, 9, 2], enc: DataBlockEncoding::default() }]),
+            ]),
+        ];
+
+        let opt = Arc::new(Options::from(&global_config));
+        let database = Arc::new("dba".to_string());
+        let ts_family_id = 1;
+        let dir = opt.storage.tsm_dir(&database, 1);
+        let runtime = Arc::new(
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+
+        let (_, files) = runtime.block_on(write_data_blocks_to_column_file(&dir, data));
+        let version =
+            build_version_by_column_files(opt.storage.clone(), database, ts_family_id, files);
+        let pool: MemoryPoolRef = Arc::new(GreedyMemoryPool::new(1024 * 1024 * 1024));
+        let test_helper = TestHelper {
+            runtime,
+            super_version: Arc::new(SuperVersion::new(
+                ts_family_id,
+                opt.storage.clone(),
+                CacheGroup {
+                    mut_cache: Arc::new(RwLock::new(MemCache::new(ts_family_id, 1, 2, 1, &pool))),
+                    immut_cache: vec![],
+                },
+                Arc::new(version),
+                1,
+            )),
+        };
+
+        #[rustfmt::skip]
+        let _skip_fmt = {
+            assert_eq!(test_helper.run(&[1], AggregateKind::Max, (i64::MIN, i64::MAX)).unwrap(), Some(DataType::I64(8, 9)));
+            assert_eq!(test_helper.run(&[1], AggregateKind::Min, (i64::MIN, i64::MAX)).unwrap(), Some(DataType::I64(5, -1)));
+            assert!(matches!(test_helper.run(&[1], AggregateKind::Sum, (i64::MIN, i64::MAX)).unwrap(), Some(DataType::I64(_, 43))));
+            assert_eq!(test_helper.run(&[1], AggregateKind::First, (i64::MIN, i64::MAX)).unwrap(), Some(DataType::I64(1, 5)));
+            assert_eq!(test_helper.run(&[1], AggregateKind::Last, (i64::MIN, i64::MAX)).unwrap(), Some(DataType::I64(9, 2)));
+
+            assert_eq!(test_helper.run(&[1], AggregateKind::Max, (2, 4)).unwrap(), Some(DataType::I64(3, 8)));
+            assert_eq!(test_helper.run(&[1], AggregateKind::First, (2, 4)).unwrap(), Some(DataType::I64(2, 3)));
+            assert_eq!(test_helper.run(&[1], AggregateKind::Last, (2, 4)).unwrap(), Some(DataType::I64(4, 4)));
+            assert_eq!(test_helper.run(&[1], AggregateKind::Max, (100, 200)).unwrap(), None);
+
+            assert_eq!(test_helper.run(&[1, 2], AggregateKind::Last, (i64::MIN, i64::MAX)).unwrap(), Some(DataType::I64(40, 4)));
+            assert_eq!(test_helper.run(&[1, 2], AggregateKind::Min, (i64::MIN, i64::MAX)).unwrap(), Some(DataType::I64(5, -1)));
+            "skip_fmt"
+        };
+    }
+}
@@ -165,6 +165,7 @@ impl Database {
             Arc::new(ShardedCache::with_capacity(
                 self.opt.storage.max_cached_readers,
             )),
+            self.opt.cold_storage.clone(),
         ));
         let tf = TseriesFamily::new(
             tsf_id,
@@ -6,7 +6,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use datafusion::arrow::record_batch::RecordBatch;
 use models::meta_data::VnodeId;
-use models::predicate::domain::{ColumnDomains, TimeRange};
+use models::predicate::domain::{ColumnDomains, ResolvedPredicate, TimeRange};
 use models::schema::{Precision, TableColumn};
 use models::{ColumnId, SeriesId, SeriesKey};
 use protos::kv_service::{WritePointsRequest, WritePointsResponse};
@@ -16,8 +16,8 @@ use trace::{debug, SpanContext};
 use crate::error::Result;
 use crate::kv_option::StorageOptions;
 use crate::summary::VersionEdit;
-use crate::tseries_family::SuperVersion;
-use crate::{Engine, TseriesFamilyId};
+use crate::tseries_family::{SuperVersion, VnodeSnapshot};
+use crate::{Engine, SeriesRangeRepair, TseriesFamilyId};
 
 #[derive(Debug, Default)]
 pub struct MockEngine {}
@@ -82,11 +82,24 @@ impl Engine for MockEngine {
         &self,
         tenant: &str,
         database: &str,
+        vnode_id: VnodeId,
         series_ids: &[SeriesId],
-        field_ids: &[ColumnId],
-        time_range: &TimeRange,
+        column_ids: &[ColumnId],
+        time_ranges: &[TimeRange],
     ) -> Result<()> {
-        todo!()
+        Ok(())
+    }
+
+    async fn delete_from_table(
+        &self,
+        tenant: &str,
+        database: &str,
+        table: &str,
+        vnode_id: VnodeId,
+        predicate: &ResolvedPredicate,
+    ) -> Result<()> {
+        println!("delete_from_table db:{:?}, table:{:?}", database, table);
+        Ok(())
     }
 
     async fn get_series_id_by_filter(
@@ -138,6 +151,15 @@ impl Engine for MockEngine {
         todo!()
     }
 
+    async fn get_vnode_snapshot(
+        &self,
+        tenant: &str,
+        database: &str,
+        vnode_id: u32,
+    ) -> Result<Option<VnodeSnapshot>> {
+        todo!()
+    }
+
     // fn alter_database(&self, schema: &DatabaseSchema) -> Result<()> {
     //     todo!()
     // }
@@ -189,6 +211,28 @@ impl Engine for MockEngine {
         todo!()
     }
 
+    async fn get_vnode_time_range_checksum(&self, vnode_id: VnodeId) -> Result<RecordBatch> {
+        todo!()
+    }
+
+    async fn read_vnode_series_data(
+        &self,
+        vnode_id: VnodeId,
+        ranges: Vec<(SeriesKey, TimeRange)>,
+    ) -> Result<RecordBatch> {
+        todo!()
+    }
+
+    async fn repair_vnode_series_data(
+        &self,
+        tenant: &str,
+        vnode_id: VnodeId,
+        precision: Precision,
+        ranges: Vec<SeriesRangeRepair>,
+    ) -> Result<RecordBatch> {
+        todo!()
+    }
+
     async fn close(&self) {}
 
     async fn prepare_copy_vnode(&self, tenant: &str, database: &str, vnode_id: u32) -> Result<()> {
@@ -126,6 +126,11 @@ pub enum Error {
         message: String,
     },
 
+    #[snafu(display("Object store error: {}", source))]
+    ObjectStore {
+        source: object_store::Error,
+    },
+
     /// Failed to send someting to a channel
     #[snafu(display("{source}"))]
     ChannelSend {
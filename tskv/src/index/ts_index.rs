@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::fmt;
-use std::ops::{BitAnd, BitOr, Bound, RangeBounds};
+use std::ops::{BitAnd, BitOr, Bound, RangeBounds, Sub};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
@@ -11,6 +11,7 @@ use datafusion::scalar::ScalarValue;
 use models::predicate::domain::{utf8_from, Domain, Range};
 use models::tag::TagFromParts;
 use models::{utils, SeriesKey, Tag};
+use regex::Regex;
 use tokio::sync::RwLock;
 use trace::{debug, info};
 
@@ -244,6 +245,28 @@ impl TSIndex {
         Ok(())
     }
 
+    /// Copies the files of the index into `dir`. Series are not added or deleted
+    /// until the copy is finished, so the copied files are a consistent index.
+    pub async fn snapshot_to(&self, dir: impl AsRef<Path>) -> IndexResult<()> {
+        let dir = dir.as_ref();
+        let mut storage_w = self.storage.write().await;
+        let _binlog_w = self.binlog.write().await;
+
+        let id_bytes = self.incr_id.load(Ordering::Relaxed).to_be_bytes();
+        storage_w.set(AUTO_INCR_ID_KEY.as_bytes(), &id_bytes)?;
+        storage_w.flush()?;
+
+        if file_manager::try_exists(dir) {
+            std::fs::remove_dir_all(dir)?;
+        }
+        std::fs::create_dir_all(dir)?;
+        for filename in file_manager::list_file_names(&self.path) {
+            std::fs::copy(self.path.join(&filename), dir.join(&filename))?;
+        }
+
+        Ok(())
+    }
+
     pub async fn del_series_info(&self, sid: u32) -> IndexResult<()> {
         // first write binlog
         let block = SeriesKeyBlock {
@@ -397,9 +420,55 @@ impl TSIndex {
                         };
                     }
                 } else {
-                    // Does not contain a given value, that is, a value other than a given value
-                    // TODO will not deal with this situation for the time being
-                    bitmap = self.get_series_id_bitmap(tab, &[]).await?;
+                    // Does not contain a given value, that is, all series of the tag
+                    // minus the series of the given values
+                    let prefix = encode_inverted_index_key(tab, tag_key.as_bytes(), &[]);
+                    for item in storage_r.prefix(&prefix)? {
+                        let item =
+                            item.map_err(|e| IndexError::IndexStroage { msg: e.to_string() })?;
+                        let rb = storage_r.load_rb(&item.1)?;
+                        bitmap = bitmap.bitor(rb);
+                    }
+
+                    for entry in val.entries().into_iter() {
+                        let index_key = tag_value_to_index_key(tab, tag_key, entry.value());
+                        if let Some(rb) = storage_r.get_rb(&index_key)? {
+                            bitmap = bitmap.sub(rb);
+                        };
+                    }
+                }
+            }
+            Domain::Regex(regex_set) => {
+                let regexes = regex_set
+                    .patterns()
+                    .iter()
+                    .map(|p| Regex::new(p.pattern()).map(|r| (r, p.is_negated())))
+                    .collect::<Result<Vec<_>, _>>();
+                let regexes = match regexes {
+                    Ok(regexes) => regexes,
+                    Err(e) => {
+                        // The filter will be applied again by the query engine
+                        debug!("Invalid tag regex, scan all series: {}", e);
+                        return self.get_series_id_bitmap(tab, &[]).await;
+                    }
+                };
+
+                // Scan all values of the tag, and merge the sid lists of the matched values
+                let storage_r = self.storage.read().await;
+                let prefix = encode_inverted_index_key(tab, tag_key.as_bytes(), &[]);
+                for item in storage_r.prefix(&prefix)? {
+                    let item = item.map_err(|e| IndexError::IndexStroage { msg: e.to_string() })?;
+                    let tag_val = match std::str::from_utf8(&item.0.as_ref()[prefix.len()..]) {
+                        Ok(tag_val) => tag_val,
+                        Err(_) => continue,
+                    };
+                    if regexes
+                        .iter()
+                        .all(|(regex, negated)| regex.is_match(tag_val) != *negated)
+                    {
+                        let rb = storage_r.load_rb(&item.1)?;
+                        bitmap = bitmap.bitor(rb);
+                    }
                 }
             }
             Domain::None => {
@@ -580,6 +649,8 @@ pub fn encode_series_id_list(list: &[u32]) -> Vec<u8> {
 #[cfg(test)]
 mod test {
     use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::scalar::ScalarValue;
+    use models::predicate::domain::Domain;
     use models::schema::ExternalTableSchema;
     use models::{SeriesId, SeriesKey, Tag};
 
@@ -750,6 +821,95 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn test_series_ids_by_regex() {
+        let dir = "/tmp/test/ts_index/regex";
+        let _ = std::fs::remove_dir_all(dir);
+        let database = "db_test";
+        #[rustfmt::skip]
+        let series_keys_desc: Vec<SeriesKeyDesc> = vec![
+            (0, database, "table_test", vec![("loc", "bj"), ("host", "h1")]),
+            (0, database, "table_test", vec![("loc", "nj"), ("host", "h2")]),
+            (0, database, "table_test", vec![("loc", "xbj"), ("host", "h3")]),
+            (0, database, "table_test", vec![("loc", "BJ"), ("host", "h4")]),
+            (0, database, "table_test_2", vec![("loc", "bj")]),
+        ];
+        let series_keys = build_series_keys(&series_keys_desc);
+        let ts_index = TSIndex::new(dir).await.unwrap();
+        let mut sids = Vec::with_capacity(series_keys.len());
+        for series_key in series_keys.iter() {
+            sids.push(ts_index.add_series_if_not_exists(series_key).await.unwrap());
+        }
+
+        let query = |pattern: &str, negated: bool| {
+            let domain = Domain::of_regex(pattern, negated);
+            let ts_index = &ts_index;
+            async move {
+                let mut list = ts_index
+                    .get_series_ids_by_domain("table_test", "loc", &domain)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                list.sort();
+                list
+            }
+        };
+
+        assert_eq!(query("^.?bj$", false).await, vec![sids[0], sids[2]]);
+        assert_eq!(query("(?i)^bj$", false).await, vec![sids[0], sids[3]]);
+        assert_eq!(query("bj", true).await, vec![sids[1], sids[3]]);
+        assert!(query("^sh", false).await.is_empty());
+        // Invalid regex will not filter any series.
+        assert_eq!(query("(", false).await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_series_ids_by_black_list() {
+        let dir = "/tmp/test/ts_index/black_list";
+        let _ = std::fs::remove_dir_all(dir);
+        let database = "db_test";
+        #[rustfmt::skip]
+        let series_keys_desc: Vec<SeriesKeyDesc> = vec![
+            (0, database, "table_test", vec![("loc", "bj"), ("host", "h1")]),
+            (0, database, "table_test", vec![("loc", "nj"), ("host", "h2")]),
+            (0, database, "table_test", vec![("loc", "sh"), ("host", "h3")]),
+            (0, database, "table_test", vec![("host", "h4")]),
+        ];
+        let series_keys = build_series_keys(&series_keys_desc);
+        let ts_index = TSIndex::new(dir).await.unwrap();
+        let mut sids = Vec::with_capacity(series_keys.len());
+        for series_key in series_keys.iter() {
+            sids.push(ts_index.add_series_if_not_exists(series_key).await.unwrap());
+        }
+
+        let query = |values: &[&str]| {
+            let values = values
+                .iter()
+                .map(|v| ScalarValue::Utf8(Some(v.to_string())))
+                .collect::<Vec<_>>();
+            let domain =
+                Domain::of_values(&DataType::Utf8, false, &values.iter().collect::<Vec<_>>());
+            let ts_index = &ts_index;
+            async move {
+                let mut list = ts_index
+                    .get_series_ids_by_domain("table_test", "loc", &domain)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                list.sort();
+                list
+            }
+        };
+
+        // loc != 'bj', series without tag 'loc' is excluded.
+        assert_eq!(query(&["bj"]).await, vec![sids[1], sids[2]]);
+        // loc not in ('bj', 'sh', 'xj')
+        assert_eq!(query(&["bj", "sh", "xj"]).await, vec![sids[1]]);
+        assert_eq!(query(&[]).await, vec![sids[0], sids[1], sids[2]]);
+    }
+
     #[test]
     fn test_serde() {
         let schema = Schema::new(vec![
@@ -6,6 +6,8 @@ use std::time::Duration;
 
 use config::Config;
 
+use crate::cold_storage::ColdStorage;
+use crate::wal::WalCompression;
 use crate::TseriesFamilyId;
 
 const SUMMARY_PATH: &str = "summary";
@@ -14,6 +16,8 @@ const DATA_PATH: &str = "data";
 pub const TSM_PATH: &str = "tsm";
 pub const DELTA_PATH: &str = "delta";
 pub const MOVE_PATH: &str = "move";
+pub const COLD_PATH: &str = "cold";
+pub const SNAPSHOT_PATH: &str = "snapshot";
 
 #[derive(Debug, Clone)]
 pub struct Options {
@@ -21,15 +25,19 @@ pub struct Options {
     pub wal: Arc<WalOptions>,
     pub cache: Arc<CacheOptions>,
     pub query: Arc<QueryOptions>,
+    pub cold_storage: Option<Arc<ColdStorage>>,
 }
 
 impl From<&Config> for Options {
     fn from(config: &Config) -> Self {
+        let storage = Arc::new(StorageOptions::from(config));
+        let cold_storage = ColdStorage::new_local(&storage, &config.cold_storage).map(Arc::new);
         Self {
-            storage: Arc::new(StorageOptions::from(config)),
+            storage,
             wal: Arc::new(WalOptions::from(config)),
             cache: Arc::new(CacheOptions::from(config)),
             query: Arc::new(QueryOptions::from(config)),
+            cold_storage,
         }
     }
 }
@@ -62,8 +70,12 @@ impl StorageOptions {
         self.path.join(SUMMARY_PATH)
     }
 
+    pub fn data_dir(&self) -> PathBuf {
+        self.path.join(DATA_PATH)
+    }
+
     pub fn database_dir(&self, database: &str) -> PathBuf {
-        self.path.join(DATA_PATH).join(database)
+        self.data_dir().join(database)
     }
 
     pub fn ts_family_dir(&self, database: &str, ts_family_id: TseriesFamilyId) -> PathBuf {
@@ -94,6 +106,20 @@ impl StorageOptions {
             .join(DELTA_PATH)
     }
 
+    /// Directory to cache files those moved to cold storage.
+    pub fn cold_dir(&self, database: &str, ts_family_id: TseriesFamilyId) -> PathBuf {
+        self.database_dir(database)
+            .join(ts_family_id.to_string())
+            .join(COLD_PATH)
+    }
+
+    /// Directory to copy the index into when a snapshot of the vnode is taken.
+    pub fn snapshot_dir(&self, database: &str, ts_family_id: TseriesFamilyId) -> PathBuf {
+        self.database_dir(database)
+            .join(ts_family_id.to_string())
+            .join(SNAPSHOT_PATH)
+    }
+
     pub fn tsfamily_dir(&self, database: &str, ts_family_id: TseriesFamilyId) -> PathBuf {
         self.database_dir(database).join(ts_family_id.to_string())
     }
@@ -127,6 +153,8 @@ pub struct QueryOptions {
     pub write_timeout_ms: u64,
     pub stream_trigger_cpu: usize,
     pub stream_executor_cpu: usize,
+    pub result_cache_max_memory: u64,
+    pub result_cache_ttl_ms: u64,
 }
 
 impl From<&Config> for QueryOptions {
@@ -140,6 +168,8 @@ impl From<&Config> for QueryOptions {
             write_timeout_ms: config.query.write_timeout_ms,
             stream_trigger_cpu: config.query.stream_trigger_cpu,
             stream_executor_cpu: config.query.stream_executor_cpu,
+            result_cache_max_memory: config.query.result_cache_max_memory,
+            result_cache_ttl_ms: config.query.result_cache_ttl_ms,
         }
     }
 }
@@ -153,6 +183,8 @@ pub struct WalOptions {
     pub flush_trigger_total_file_size: u64,
     pub sync: bool,
     pub sync_interval: Duration,
+    pub compression: WalCompression,
+    pub group_commit_window: Duration,
 }
 
 impl From<&Config> for WalOptions {
@@ -165,6 +197,8 @@ impl From<&Config> for WalOptions {
             flush_trigger_total_file_size: config.wal.flush_trigger_total_file_size,
             sync: config.wal.sync,
             sync_interval: config.wal.sync_interval,
+            compression: WalCompression::from(config.wal.compression.as_str()),
+            group_commit_window: config.wal.group_commit_window,
         }
     }
 }
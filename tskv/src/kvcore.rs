@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use std::panic;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -9,11 +10,11 @@ use meta::model::MetaRef;
 use metrics::metric_register::MetricsRegister;
 use models::codec::Encoding;
 use models::meta_data::{VnodeId, VnodeStatus};
-use models::predicate::domain::{ColumnDomains, TimeRange};
+use models::predicate::domain::{ColumnDomains, ResolvedPredicate, TimeRange};
 use models::schema::{make_owner, DatabaseSchema, Precision, TableColumn};
 use models::utils::unite_id;
-use models::{ColumnId, SeriesId, SeriesKey, Timestamp};
-use protos::kv_service::{WritePointsRequest, WritePointsResponse};
+use models::{ColumnId, FieldId, SeriesId, SeriesKey, Timestamp};
+use protos::kv_service::{Meta, WritePointsRequest, WritePointsResponse};
 use protos::models as fb_models;
 use snafu::ResultExt;
 use tokio::runtime::Runtime;
@@ -29,15 +30,18 @@ use crate::context::{self, GlobalContext, GlobalSequenceContext, GlobalSequenceT
 use crate::database::Database;
 use crate::error::{self, Result};
 use crate::file_system::file_manager;
-use crate::index::ts_index;
-use crate::kv_option::{Options, StorageOptions};
+use crate::index::ts_index::{self, TSIndex};
+use crate::kv_option::{Options, StorageOptions, DELTA_PATH, INDEX_PATH, TSM_PATH};
 use crate::schema::error::SchemaError;
 use crate::summary::{Summary, SummaryProcessor, SummaryTask, VersionEdit};
-use crate::tseries_family::{SuperVersion, TseriesFamily};
+use crate::tseries_family::{SuperVersion, TseriesFamily, VnodeSnapshot};
 use crate::tsm::codec::get_str_codec;
 use crate::version_set::VersionSet;
-use crate::wal::{self, WalDecoder, WalEntry, WalManager, WalTask};
-use crate::{file_utils, tenant_name_from_request, Engine, Error, TseriesFamilyId};
+use crate::wal::{self, WalCompression, WalDecoder, WalEntry, WalManager, WalTask};
+use crate::{
+    cold_storage, file_utils, tenant_name_from_request, tsm, Engine, Error, SeriesRangeRepair,
+    TseriesFamilyId,
+};
 
 // TODO: A small summay channel capacity can cause a block
 pub const COMPACT_REQ_CHANNEL_CAP: usize = 1024;
@@ -61,6 +65,8 @@ pub struct TsKv {
     global_seq_task_sender: Sender<GlobalSequenceTask>,
     close_sender: BroadcastSender<Sender<()>>,
     metrics: Arc<MetricsRegister>,
+    /// Vnode id -> fence of writes and deletions of the vnode, see `vnode_write_fence`
+    vnode_write_fences: Arc<parking_lot::Mutex<HashMap<VnodeId, Arc<RwLock<()>>>>>,
 }
 
 impl TsKv {
@@ -112,6 +118,7 @@ impl TsKv {
             global_seq_task_sender: global_seq_task_sender.clone(),
             close_sender,
             metrics,
+            vnode_write_fences: Default::default(),
         };
 
         let wal_manager = core.recover_wal().await;
@@ -133,6 +140,15 @@ impl TsKv {
             summary.version_set(),
             summary_task_sender.clone(),
         );
+        if let Some(cold_storage) = shared_options.cold_storage.clone() {
+            cold_storage::run_job(
+                cold_storage,
+                core.runtime.clone(),
+                core.meta_manager.clone(),
+                summary.version_set(),
+                summary_task_sender.clone(),
+            );
+        }
         core.run_summary_job(summary, summary_task_receiver);
         context::run_global_context_job(
             core.runtime.clone(),
@@ -142,6 +158,15 @@ impl TsKv {
         Ok(core)
     }
 
+    /// Removes files moved to cold storage under a vnode or database directory.
+    async fn remove_cold_files(&self, dir: &Path) {
+        if let Some(cold_storage) = self.options.cold_storage.as_ref() {
+            if let Err(e) = cold_storage.delete_dir(dir).await {
+                error!("Failed to remove cold files of '{}': {}", dir.display(), e);
+            }
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn recover_summary(
         runtime: Arc<Runtime>,
@@ -240,6 +265,39 @@ impl TsKv {
                                     trace::error!("Recover: failed to delete table: {e}");
                                 }
                             }
+                            WalEntry::DeleteSeries(blk) => {
+                                let vnode_id = blk.vnode_id();
+                                if let Some(tsf_last_seq) = vnode_last_seq_map.get(&vnode_id) {
+                                    // If `seq_no` of TsFamily is greater than or equal to `seq`,
+                                    // it means that deletion was applied before data writen to tsm.
+                                    if *tsf_last_seq >= seq {
+                                        continue;
+                                    }
+                                }
+                                if let Err(e) = self.delete_series_from_wal(&blk, seq).await {
+                                    // Ignore delete series error.
+                                    trace::error!("Recover: failed to delete series: {e}");
+                                }
+                            }
+                            WalEntry::ChangeColumn(blk) => {
+                                // Skip the storage units that applied the change before data
+                                // writen to tsm.
+                                let skip_vnodes = flushed_vnodes(&vnode_last_seq_map, seq);
+                                if let Err(e) =
+                                    self.change_column_from_wal(&blk, &skip_vnodes).await
+                                {
+                                    // Ignore change column error.
+                                    trace::error!("Recover: failed to change column: {e}");
+                                }
+                            }
+                            WalEntry::DropColumn(blk) => {
+                                let skip_vnodes = flushed_vnodes(&vnode_last_seq_map, seq);
+                                if let Err(e) = self.drop_column_from_wal(&blk, &skip_vnodes).await
+                                {
+                                    // Ignore drop column error.
+                                    trace::error!("Recover: failed to drop column: {e}");
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -261,8 +319,28 @@ impl TsKv {
     }
 
     pub(crate) fn run_wal_job(&self, mut wal_manager: WalManager, mut receiver: Receiver<WalTask>) {
-        async fn on_write(wal_manager: &mut WalManager, wal_task: WalTask) {
-            wal_manager.write(wal_task).await;
+        async fn on_write(
+            wal_manager: &mut WalManager,
+            receiver: &mut Receiver<WalTask>,
+            wal_task: WalTask,
+            max_batch_size: usize,
+        ) {
+            // Group commit: tasks already in channel or arriving in the window
+            // are written as a batch, and synced once.
+            let mut wal_tasks = vec![wal_task];
+            let group_commit_window = wal_manager.group_commit_window();
+            let deadline = tokio::time::Instant::now() + group_commit_window;
+            while wal_tasks.len() < max_batch_size {
+                match receiver.try_recv() {
+                    Ok(t) => wal_tasks.push(t),
+                    Err(_) if group_commit_window.is_zero() => break,
+                    Err(_) => match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                        Ok(Some(t)) => wal_tasks.push(t),
+                        _ => break,
+                    },
+                }
+            }
+            wal_manager.write_batch(wal_tasks).await;
         }
 
         async fn on_tick_sync(wal_manager: &WalManager) {
@@ -304,6 +382,7 @@ impl TsKv {
             info!("Job 'WAL' started.");
 
             let sync_interval = wal_manager.sync_interval();
+            let max_batch_size = wal_manager.max_batch_size();
             let mut check_total_size_ticker = tokio::time::interval(Duration::from_secs(10));
             let check_to_flush_duration = Duration::from_secs(60);
             let mut check_to_flush_instant = Instant::now();
@@ -313,7 +392,7 @@ impl TsKv {
                         wal_task = receiver.recv() => {
                             match wal_task {
                                 Some(t) => {
-                                    on_write(&mut wal_manager, t).await
+                                    on_write(&mut wal_manager, &mut receiver, t, max_batch_size).await
                                 },
                                 _ => break
                             }
@@ -337,7 +416,7 @@ impl TsKv {
                         wal_task = receiver.recv() => {
                             match wal_task {
                                 Some(t) => {
-                                    on_write(&mut wal_manager, t).await
+                                    on_write(&mut wal_manager, &mut receiver, t, max_batch_size).await
                                 },
                                 _ => break
                             }
@@ -529,6 +608,7 @@ impl TsKv {
         database: Arc<RwLock<Database>>,
         table: &str,
         column_ids: &[ColumnId],
+        skip_vnodes: &HashSet<VnodeId>,
     ) -> Result<()> {
         // TODO Create global DropTable flag for droping the same table at the same time.
         let db_rlock = database.read().await;
@@ -550,6 +630,9 @@ impl TsKv {
                 max_ts: Timestamp::MAX,
             };
             for (ts_family_id, ts_family) in database.read().await.ts_families().iter() {
+                if skip_vnodes.contains(ts_family_id) {
+                    continue;
+                }
                 // TODO: Concurrent delete on ts_family.
                 // TODO: Limit parallel delete to 1.
                 if let Some(ts_index) = db_rlock.get_ts_index(*ts_family_id) {
@@ -580,6 +663,89 @@ impl TsKv {
         Ok(())
     }
 
+    /// Delete data of the series in the storage unit that in the time ranges,
+    /// rows in caches are removed, and tombstones are added to related files.
+    async fn delete_series_in_tsfamily(
+        &self,
+        database: Arc<RwLock<Database>>,
+        vnode_id: VnodeId,
+        series_ids: &[SeriesId],
+        column_ids: &[ColumnId],
+        time_ranges: &[TimeRange],
+        seq: u64,
+    ) -> Result<()> {
+        let ts_family = match database.read().await.get_tsfamily(vnode_id) {
+            Some(tsf) => tsf,
+            None => return Ok(()),
+        };
+
+        let field_ids: Vec<FieldId> = series_ids
+            .iter()
+            .flat_map(|sid| column_ids.iter().map(|cid| unite_id(*cid, *sid)))
+            .collect();
+        info!(
+            "Delete series: vnode {vnode_id} deleting {} fields in {} time ranges",
+            field_ids.len(),
+            time_ranges.len()
+        );
+
+        // Running flush and compaction jobs are waited, and next jobs wait for the deletion,
+        // so that the tombstones are added to all files of the vnode.
+        let (file_lock, series_tombstone_path) = {
+            let ts_family = ts_family.read().await;
+            (ts_family.file_lock(), ts_family.series_tombstone_path())
+        };
+        let _file_guard = file_lock.write().await;
+        for time_range in time_ranges {
+            tsm::append_series_tombstones(&series_tombstone_path, &field_ids, time_range, seq)
+                .await?;
+            ts_family
+                .write()
+                .await
+                .delete_series(series_ids, time_range);
+
+            let version = ts_family.read().await.super_version();
+            for column_file in version.version.column_files(&field_ids, time_range) {
+                column_file.add_tombstone(&field_ids, time_range).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Change the column in caches of all storage units of the database, values in
+    /// caches are converted to the new type, values in files are converted when
+    /// they are read or compacted.
+    async fn change_column_in_tsfamilies(
+        &self,
+        database: Arc<RwLock<Database>>,
+        table: &str,
+        column_name: &str,
+        new_column: &TableColumn,
+        skip_vnodes: &HashSet<VnodeId>,
+    ) -> Result<()> {
+        let db_rlock = database.read().await;
+        for (ts_family_id, ts_family) in db_rlock.ts_families().iter() {
+            if skip_vnodes.contains(ts_family_id) {
+                continue;
+            }
+            if let Some(ts_index) = db_rlock.get_ts_index(*ts_family_id) {
+                let series_ids = ts_index.get_series_id_list(table, &[]).await?;
+                info!(
+                    "Change column: vnode {ts_family_id} changing column '{column_name}' of {} series in table: {}.{table}",
+                    series_ids.len(),
+                    db_rlock.owner(),
+                );
+                ts_family
+                    .read()
+                    .await
+                    .change_column(&series_ids, column_name, new_column);
+            }
+        }
+
+        Ok(())
+    }
+
     async fn write_wal(
         &self,
         vnode_id: VnodeId,
@@ -591,8 +757,13 @@ impl TsKv {
             return Ok(0);
         }
 
+        // If WAL entries are compressed, points needn't to be compressed again.
+        let points_encoding = match self.options.wal.compression {
+            WalCompression::None => Encoding::Zstd,
+            _ => Encoding::Null,
+        };
         let mut enc_points = Vec::with_capacity(points.len() / 2);
-        get_str_codec(Encoding::Zstd)
+        get_str_codec(points_encoding)
             .encode(&[&points], &mut enc_points)
             .with_context(|_| error::EncodeSnafu)?;
         drop(points);
@@ -694,52 +865,19 @@ impl TsKv {
         Ok(())
     }
 
-    /// Remove the storage unit(caches and files) managed by TsKv,
-    /// then remove directory of the storage unit.
-    ///
-    /// Data is from the WAL(write-ahead-log), so won't write back to WAL.
-    async fn remove_tsfamily_from_wal(&self, block: &wal::DeleteVnodeBlock) -> Result<()> {
-        let vnode_id = block.vnode_id();
-        let tenant = block.tenant_utf8()?;
-        let database = block.database_utf8()?;
-        trace::info!(
-            "Recover: delete vnode, tenant: {}, database: {}, vnode_id: {vnode_id}",
-            &tenant,
-            &database
-        );
-
-        if let Some(db) = self.version_set.read().await.get_db(tenant, database) {
-            let mut db_wlock = db.write().await;
-            db_wlock.del_ts_index(vnode_id);
-            db_wlock
-                .del_tsfamily(vnode_id, self.summary_task_sender.clone())
-                .await;
-
-            let ts_dir = self
-                .options
-                .storage
-                .ts_family_dir(&make_owner(tenant, database), vnode_id);
-            match std::fs::remove_dir_all(&ts_dir) {
-                Ok(()) => {
-                    info!("Removed TsFamily directory '{}'", ts_dir.display());
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to remove TsFamily directory '{}': {}",
-                        ts_dir.display(),
-                        e
-                    );
-                }
-            }
-        }
-
-        Ok(())
+    /// Returns the fence of writes and deletions of the vnode. They hold the fence shared,
+    /// a repair holds it exclusively so that the data of the vnode isn't changed between
+    /// checking and writing it.
+    fn vnode_write_fence(&self, vnode_id: VnodeId) -> Arc<RwLock<()>> {
+        self.vnode_write_fences
+            .lock()
+            .entry(vnode_id)
+            .or_default()
+            .clone()
     }
-}
 
-#[async_trait::async_trait]
-impl Engine for TsKv {
-    async fn write(
+    /// Writes the points into the vnode, the fence of the vnode is held by callers.
+    async fn write_points(
         &self,
         span_ctx: Option<&SpanContext>,
         vnode_id: TseriesFamilyId,
@@ -803,6 +941,197 @@ impl Engine for TsKv {
         res
     }
 
+    /// Delete data of the series in the storage unit.
+    ///
+    /// Data is from the WAL(write-ahead-log), so won't write back to WAL.
+    async fn delete_series_from_wal(&self, block: &wal::DeleteSeriesBlock, seq: u64) -> Result<()> {
+        let vnode_id = block.vnode_id();
+        let tenant = block.tenant_utf8()?;
+        let database = block.database_utf8()?;
+        trace::info!(
+            "Recover: delete series, tenant: {}, database: {}, vnode_id: {vnode_id}",
+            &tenant,
+            &database
+        );
+        if let Some(db) = self.version_set.read().await.get_db(tenant, database) {
+            return self
+                .delete_series_in_tsfamily(
+                    db,
+                    vnode_id,
+                    &block.series_ids(),
+                    &block.column_ids(),
+                    &block.time_ranges(),
+                    seq,
+                )
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Change a column of a table.
+    ///
+    /// Data is from the WAL(write-ahead-log), so won't write back to WAL.
+    async fn change_column_from_wal(
+        &self,
+        block: &wal::ChangeColumnBlock,
+        skip_vnodes: &HashSet<VnodeId>,
+    ) -> Result<()> {
+        let tenant = block.tenant_utf8()?;
+        let database = block.database_utf8()?;
+        let table = block.table_utf8()?;
+        let column_name = block.column_name_utf8()?;
+        let new_column = TableColumn::decode(block.new_column()).map_err(|e| Error::Decode {
+            source: Box::new(e),
+        })?;
+        trace::info!(
+            "Recover: change column, tenant: {}, database: {}, table: {}, column: {}",
+            &tenant,
+            &database,
+            &table,
+            &column_name
+        );
+        if let Some(db) = self.version_set.read().await.get_db(tenant, database) {
+            return self
+                .change_column_in_tsfamilies(db, table, column_name, &new_column, skip_vnodes)
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Delete all data of a column.
+    ///
+    /// Data is from the WAL(write-ahead-log), so won't write back to WAL.
+    async fn drop_column_from_wal(
+        &self,
+        block: &wal::DropColumnBlock,
+        skip_vnodes: &HashSet<VnodeId>,
+    ) -> Result<()> {
+        let tenant = block.tenant_utf8()?;
+        let database = block.database_utf8()?;
+        let table = block.table_utf8()?;
+        let column_id = block.column_id();
+        trace::info!(
+            "Recover: drop column, tenant: {}, database: {}, table: {}, column_id: {}",
+            &tenant,
+            &database,
+            &table,
+            column_id
+        );
+        if let Some(db) = self.version_set.read().await.get_db(tenant, database) {
+            return self
+                .delete_columns(db, table, &[column_id], skip_vnodes)
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Remove the storage unit(caches and files) managed by TsKv,
+    /// then remove directory of the storage unit.
+    ///
+    /// Data is from the WAL(write-ahead-log), so won't write back to WAL.
+    async fn remove_tsfamily_from_wal(&self, block: &wal::DeleteVnodeBlock) -> Result<()> {
+        let vnode_id = block.vnode_id();
+        let tenant = block.tenant_utf8()?;
+        let database = block.database_utf8()?;
+        trace::info!(
+            "Recover: delete vnode, tenant: {}, database: {}, vnode_id: {vnode_id}",
+            &tenant,
+            &database
+        );
+
+        if let Some(db) = self.version_set.read().await.get_db(tenant, database) {
+            let mut db_wlock = db.write().await;
+            db_wlock.del_ts_index(vnode_id);
+            db_wlock
+                .del_tsfamily(vnode_id, self.summary_task_sender.clone())
+                .await;
+
+            let ts_dir = self
+                .options
+                .storage
+                .ts_family_dir(&make_owner(tenant, database), vnode_id);
+            self.remove_cold_files(&ts_dir).await;
+            match std::fs::remove_dir_all(&ts_dir) {
+                Ok(()) => {
+                    info!("Removed TsFamily directory '{}'", ts_dir.display());
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to remove TsFamily directory '{}': {}",
+                        ts_dir.display(),
+                        e
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flush caches of the vnode into files without compaction, for checking the data
+    /// in files. Returns the vnode and it's index, or None if the vnode is not found.
+    async fn flush_vnode_for_check(
+        &self,
+        vnode_id: VnodeId,
+    ) -> Result<Option<(Arc<RwLock<TseriesFamily>>, Arc<TSIndex>)>> {
+        for database in self.version_set.read().await.get_all_db().values() {
+            let db = database.read().await;
+            if let Some(vnode) = db.ts_families().get(&vnode_id).cloned() {
+                let Some(ts_index) = db.get_ts_index(vnode_id) else {
+                    return Ok(None);
+                };
+                drop(db);
+                let request = {
+                    let mut tsfamily = vnode.write().await;
+                    tsfamily.switch_to_immutable();
+                    tsfamily.build_flush_req(true)
+                };
+
+                if let Some(req) = request {
+                    // Run flush job but do not trigger compaction.
+                    run_flush_memtable_job(
+                        req,
+                        self.global_ctx.clone(),
+                        self.global_seq_ctx.clone(),
+                        self.version_set.clone(),
+                        self.summary_task_sender.clone(),
+                        None,
+                    )
+                    .await?
+                }
+                return Ok(Some((vnode, ts_index)));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Returns the storage units whose `seq_no` is greater than or equal to `seq`,
+/// they have applied the WAL entry of `seq` before data writen to tsm.
+fn flushed_vnodes(vnode_last_seq_map: &HashMap<VnodeId, u64>, seq: u64) -> HashSet<VnodeId> {
+    vnode_last_seq_map
+        .iter()
+        .filter(|(_, last_seq)| **last_seq >= seq)
+        .map(|(vnode_id, _)| *vnode_id)
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl Engine for TsKv {
+    async fn write(
+        &self,
+        span_ctx: Option<&SpanContext>,
+        vnode_id: TseriesFamilyId,
+        precision: Precision,
+        write_batch: WritePointsRequest,
+    ) -> Result<WritePointsResponse> {
+        let fence = self.vnode_write_fence(vnode_id);
+        let _fence_guard = fence.read().await;
+        self.write_points(span_ctx, vnode_id, precision, write_batch)
+            .await
+    }
+
     async fn drop_database(&self, tenant: &str, database: &str) -> Result<()> {
         if let Some(db) = self.version_set.write().await.delete_db(tenant, database) {
             let mut db_wlock = db.write().await;
@@ -823,6 +1152,7 @@ impl Engine for TsKv {
             .options
             .storage
             .database_dir(&make_owner(tenant, database));
+        self.remove_cold_files(&db_dir).await;
         if let Err(e) = std::fs::remove_dir_all(&db_dir) {
             error!("Failed to remove dir '{}', e: {}", db_dir.display(), e);
         }
@@ -881,6 +1211,7 @@ impl Engine for TsKv {
                 .options
                 .storage
                 .ts_family_dir(&make_owner(tenant, database), vnode_id);
+            self.remove_cold_files(&ts_dir).await;
             match std::fs::remove_dir_all(&ts_dir) {
                 Ok(()) => {
                     info!("Removed TsFamily directory '{}'", ts_dir.display());
@@ -966,7 +1297,6 @@ impl Engine for TsKv {
         table: &str,
         column_name: &str,
     ) -> Result<()> {
-        // TODO(zipper): Store this action in WAL.
         let db = self.get_db(tenant, database).await?;
         let schema =
             db.read()
@@ -984,63 +1314,145 @@ impl Engine for TsKv {
                 field: column_name.to_string(),
             })?
             .id;
-        self.delete_columns(db, table, &[column_id]).await?;
+
+        // Store this action in WAL.
+        let (wal_task, rx) = WalTask::new_drop_column(
+            tenant.to_string(),
+            database.to_string(),
+            table.to_string(),
+            column_id,
+        );
+        self.wal_sender
+            .send(wal_task)
+            .await
+            .map_err(|_| Error::ChannelSend {
+                source: error::ChannelSendError::WalTask,
+            })?;
+        // Receive WAL write action result.
+        let _ = rx.await.map_err(|e| Error::ChannelReceive {
+            source: error::ChannelReceiveError::WriteWalResult { source: e },
+        })??;
+
+        self.delete_columns(db, table, &[column_id], &HashSet::new())
+            .await?;
         Ok(())
     }
 
     async fn change_table_column(
         &self,
-        _tenant: &str,
-        _database: &str,
-        _table: &str,
-        _column_name: &str,
-        _new_column: TableColumn,
+        tenant: &str,
+        database: &str,
+        table: &str,
+        column_name: &str,
+        new_column: TableColumn,
     ) -> Result<()> {
-        // let db = self.get_db(tenant, database).await?;
-        // let db = db.read().await;
-        // let sids = db.get_table_sids(table).await?;
+        let db = self.get_db(tenant, database).await?;
+        let new_column_bytes = new_column.encode().map_err(|e| Error::Encode {
+            source: Box::new(e),
+        })?;
 
-        // for (_ts_family_id, ts_family) in db.ts_families().iter() {
-        //     ts_family
-        //         .read()
-        //         .await
-        //         .change_column(&sids, column_name, &new_column);
-        // }
-        Ok(())
+        // Store this action in WAL.
+        let (wal_task, rx) = WalTask::new_change_column(
+            tenant.to_string(),
+            database.to_string(),
+            table.to_string(),
+            column_name.to_string(),
+            new_column_bytes,
+        );
+        self.wal_sender
+            .send(wal_task)
+            .await
+            .map_err(|_| Error::ChannelSend {
+                source: error::ChannelSendError::WalTask,
+            })?;
+        // Receive WAL write action result.
+        let _ = rx.await.map_err(|e| Error::ChannelReceive {
+            source: error::ChannelReceiveError::WriteWalResult { source: e },
+        })??;
+
+        self.change_column_in_tsfamilies(db, table, column_name, &new_column, &HashSet::new())
+            .await
     }
 
     async fn delete_series(
         &self,
-        _tenant: &str,
-        _database: &str,
-        _series_ids: &[SeriesId],
-        _field_ids: &[ColumnId],
-        _time_range: &TimeRange,
+        tenant: &str,
+        database: &str,
+        vnode_id: VnodeId,
+        series_ids: &[SeriesId],
+        column_ids: &[ColumnId],
+        time_ranges: &[TimeRange],
     ) -> Result<()> {
-        // let storage_field_ids: Vec<u64> = series_ids
-        //     .iter()
-        //     .flat_map(|sid| field_ids.iter().map(|fid| unite_id(*fid, *sid)))
-        //     .collect();
-        // let res = self.version_set.read().get_db(tenant, database);
-        // if let Some(db) = res {
-        //     let readable_db = db.read();
-        //     for (ts_family_id, ts_family) in readable_db.ts_families() {
-        //         // TODO Cancel current and prevent next flush or compaction in TseriesFamily provisionally.
-        //         ts_family
-        //             .write()
-        //             .delete_series(&storage_field_ids, time_range);
-
-        //         let version = ts_family.read().super_version();
-        //         for column_file in version.version.column_files(&storage_field_ids, time_range) {
-        //             self.runtime
-        //                 .block_on(column_file.add_tombstone(&storage_field_ids, time_range))?;
-        //         }
-        //         // TODO Start next flush or compaction.
-        //     }
-        // }
+        if series_ids.is_empty() || column_ids.is_empty() || time_ranges.is_empty() {
+            return Ok(());
+        }
+        let fence = self.vnode_write_fence(vnode_id);
+        let _fence_guard = fence.read().await;
+        if let Some(db) = self.version_set.read().await.get_db(tenant, database) {
+            // Store this action in WAL.
+            let (wal_task, rx) = WalTask::new_delete_series(
+                tenant.to_string(),
+                database.to_string(),
+                vnode_id,
+                series_ids.to_vec(),
+                column_ids.to_vec(),
+                time_ranges.to_vec(),
+            );
+            self.wal_sender
+                .send(wal_task)
+                .await
+                .map_err(|_| Error::ChannelSend {
+                    source: error::ChannelSendError::WalTask,
+                })?;
+            // Receive WAL write action result.
+            let (seq, _) = rx.await.map_err(|e| Error::ChannelReceive {
+                source: error::ChannelReceiveError::WriteWalResult { source: e },
+            })??;
+
+            return self
+                .delete_series_in_tsfamily(db, vnode_id, series_ids, column_ids, time_ranges, seq)
+                .await;
+        }
+
         Ok(())
     }
 
+    async fn delete_from_table(
+        &self,
+        tenant: &str,
+        database: &str,
+        table: &str,
+        vnode_id: VnodeId,
+        predicate: &ResolvedPredicate,
+    ) -> Result<()> {
+        let db = match self.version_set.read().await.get_db(tenant, database) {
+            Some(db) => db,
+            None => return Ok(()),
+        };
+        let column_ids: Vec<ColumnId> = match db.read().await.get_table_schema(table)? {
+            Some(schema) => schema.columns().iter().map(|c| c.id).collect(),
+            None => return Ok(()),
+        };
+        let series_ids = self
+            .get_series_id_by_filter(tenant, database, table, vnode_id, predicate.tags_filter())
+            .await?;
+        info!(
+            "Delete from table: vnode {vnode_id} deleting {} series in table: {tenant}.{database}.{table}",
+            series_ids.len()
+        );
+
+        let time_ranges = predicate.time_ranges();
+        self.delete_series(
+            tenant,
+            database,
+            vnode_id,
+            &series_ids,
+            &column_ids,
+            time_ranges.time_ranges(),
+        )
+        .await
+    }
+
     async fn get_series_id_by_filter(
         &self,
         tenant: &str,
@@ -1189,6 +1601,85 @@ impl Engine for TsKv {
         Ok(())
     }
 
+    async fn get_vnode_snapshot(
+        &self,
+        tenant: &str,
+        database: &str,
+        vnode_id: VnodeId,
+    ) -> Result<Option<VnodeSnapshot>> {
+        self.flush_tsfamily(tenant, database, vnode_id).await?;
+
+        let db = match self.version_set.read().await.get_db(tenant, database) {
+            Some(db) => db,
+            None => {
+                return Err(SchemaError::DatabaseNotFound {
+                    database: format!("{}.{}", tenant, database),
+                }
+                .into())
+            }
+        };
+        let db = db.read().await;
+        let tsf = match db.get_tsfamily(vnode_id) {
+            Some(tsf) => tsf,
+            None => return Ok(None),
+        };
+        let owner = db.owner();
+        let ts_index = db.get_ts_index(vnode_id);
+        let (super_version, mut version_edit) = {
+            let tsf = tsf.read().await;
+            let mut file_metas = HashMap::new();
+            (
+                tsf.super_version(),
+                tsf.snapshot(owner.clone(), &mut file_metas),
+            )
+        };
+        drop(db);
+
+        let storage_opt = &self.options.storage;
+        let mut files = Vec::with_capacity(version_edit.add_files.len() * 2);
+        for meta in version_edit.add_files.iter_mut() {
+            let path = meta.file_path(storage_opt, &owner, vnode_id);
+            if meta.is_remote {
+                // Files in cold storage are snapshotted as local files.
+                if let Some(cold_storage) = self.options.cold_storage.as_ref() {
+                    cold_storage.fetch(&path).await?;
+                }
+                meta.is_remote = false;
+            }
+            let dir_name = if meta.is_delta { DELTA_PATH } else { TSM_PATH };
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            files.push((path.clone(), format!("{dir_name}/{file_name}")));
+
+            let tombstone_path =
+                file_utils::make_tsm_tombstone_file_name(path.parent().unwrap(), meta.file_id);
+            if file_manager::try_exists(&tombstone_path) {
+                let file_name = tombstone_path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                files.push((tombstone_path.clone(), format!("{dir_name}/{file_name}")));
+            }
+        }
+
+        // The live index is written concurrently, copy it under its locks first.
+        let index_dir = storage_opt.snapshot_dir(&owner, vnode_id).join(INDEX_PATH);
+        if let Some(ts_index) = ts_index {
+            ts_index.snapshot_to(&index_dir).await?;
+        }
+        for entry in walkdir::WalkDir::new(&index_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            if let Ok(relative) = entry.path().strip_prefix(&index_dir) {
+                let relative = format!("{}/{}", INDEX_PATH, relative.to_string_lossy());
+                files.push((entry.path().to_path_buf(), relative));
+            }
+        }
+
+        Ok(Some(VnodeSnapshot::new(version_edit, files, super_version)))
+    }
+
     async fn drop_vnode(&self, vnode_id: TseriesFamilyId) -> Result<()> {
         let r_version_set = self.version_set.read().await;
         let all_db = r_version_set.get_all_db();
@@ -1204,6 +1695,7 @@ impl Engine for TsKv {
                     .await;
             }
             let tsf_dir = self.options.storage.tsfamily_dir(db_name, vnode_id);
+            self.remove_cold_files(&tsf_dir).await;
             if let Err(e) = std::fs::remove_dir_all(&tsf_dir) {
                 error!("Failed to remove dir '{}', e: {}", tsf_dir.display(), e);
             }
@@ -1250,12 +1742,15 @@ impl Engine for TsKv {
                     }
                 }
 
+                // Deleting waits until the compacted files are applied to the version.
+                let file_lock = ts_family.read().await.file_lock();
+                let _file_guard = file_lock.read().await;
                 let picker = LevelCompactionPicker::new(self.options.storage.clone());
                 let version = ts_family.read().await.version();
                 if let Some(req) = picker.pick_compaction(version) {
                     match compaction::run_compaction_job(req, self.global_ctx.clone()).await {
                         Ok(Some((version_edit, file_metas))) => {
-                            let (summary_tx, _summary_rx) = oneshot::channel();
+                            let (summary_tx, summary_rx) = oneshot::channel();
                             let _ = self
                                 .summary_task_sender
                                 .send(SummaryTask::new(
@@ -1265,8 +1760,9 @@ impl Engine for TsKv {
                                     summary_tx,
                                 ))
                                 .await;
-
-                            // let _ = summary_rx.await;
+                            if let Ok(Err(e)) = summary_rx.await {
+                                error!("Failed to apply compaction of vnode {}: {:?}", vnode_id, e);
+                            }
                         }
                         Ok(None) => {
                             info!("There is nothing to compact.");
@@ -1283,33 +1779,83 @@ impl Engine for TsKv {
     }
 
     async fn get_vnode_hash_tree(&self, vnode_id: VnodeId) -> Result<RecordBatch> {
+        match self.flush_vnode_for_check(vnode_id).await? {
+            Some((vnode, _)) => check::vnode_checksum(vnode).await,
+            None => Ok(RecordBatch::new_empty(check::vnode_table_checksum_schema())),
+        }
+    }
+
+    async fn get_vnode_time_range_checksum(&self, vnode_id: VnodeId) -> Result<RecordBatch> {
+        match self.flush_vnode_for_check(vnode_id).await? {
+            Some((vnode, ts_index)) => check::vnode_time_range_checksum(vnode, ts_index).await,
+            None => Ok(RecordBatch::new_empty(
+                check::vnode_time_range_checksum_schema(),
+            )),
+        }
+    }
+
+    async fn read_vnode_series_data(
+        &self,
+        vnode_id: VnodeId,
+        ranges: Vec<(SeriesKey, TimeRange)>,
+    ) -> Result<RecordBatch> {
         for database in self.version_set.read().await.get_all_db().values() {
             let db = database.read().await;
-            if let Some(vnode) = db.ts_families().get(&vnode_id).cloned() {
-                drop(db);
-                let request = {
-                    let mut tsfamily = vnode.write().await;
-                    tsfamily.switch_to_immutable();
-                    tsfamily.build_flush_req(true)
-                };
+            let Some(vnode) = db.get_tsfamily(vnode_id) else {
+                continue;
+            };
+            let Some(ts_index) = db.get_ts_index(vnode_id) else {
+                break;
+            };
+            let schemas = db.get_schemas();
+            drop(db);
+            return check::read_vnode_series_data(vnode, ts_index, schemas, &ranges).await;
+        }
 
-                if let Some(req) = request {
-                    // Run flush job but do not trigger compaction.
-                    run_flush_memtable_job(
-                        req,
-                        self.global_ctx.clone(),
-                        self.global_seq_ctx.clone(),
-                        self.version_set.clone(),
-                        self.summary_task_sender.clone(),
-                        None,
-                    )
-                    .await?
-                }
-                return check::vnode_checksum(vnode).await;
+        Ok(RecordBatch::new_empty(check::vnode_series_data_schema()))
+    }
+
+    async fn repair_vnode_series_data(
+        &self,
+        tenant: &str,
+        vnode_id: VnodeId,
+        precision: Precision,
+        ranges: Vec<SeriesRangeRepair>,
+    ) -> Result<RecordBatch> {
+        let fence = self.vnode_write_fence(vnode_id);
+        let _fence_guard = fence.write().await;
+
+        let mut results = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            let series_key = {
+                let mut series_key = range.series_key.clone();
+                series_key.set_id(0);
+                series_key.encode()
+            };
+            // Points written to the range after the points to repair were merged are not
+            // overwritten by them.
+            let data = self
+                .read_vnode_series_data(vnode_id, vec![(range.series_key, range.time_range)])
+                .await?;
+            let digest = check::series_range_digest(&data, &series_key, &range.time_range)?;
+            let repaired = digest == range.digest;
+            if repaired {
+                let request = WritePointsRequest {
+                    version: 1,
+                    meta: Some(Meta {
+                        tenant: tenant.to_string(),
+                        user: None,
+                        password: None,
+                    }),
+                    points: range.points,
+                };
+                self.write_points(None, vnode_id, precision, request)
+                    .await?;
             }
+            results.push((series_key, range.time_range, repaired));
         }
 
-        Ok(RecordBatch::new_empty(check::vnode_table_checksum_schema()))
+        check::vnode_series_repair_batch(&results)
     }
 
     async fn close(&self) {
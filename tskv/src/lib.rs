@@ -5,10 +5,13 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-pub use compaction::check::vnode_table_checksum_schema;
+pub use compaction::check::{
+    series_range_digest, vnode_series_data_schema, vnode_series_repair_schema,
+    vnode_table_checksum_schema, vnode_time_range_checksum_schema, SeriesRangeRepair,
+};
 use datafusion::arrow::record_batch::RecordBatch;
 use models::meta_data::VnodeId;
-use models::predicate::domain::{ColumnDomains, TimeRange};
+use models::predicate::domain::{ColumnDomains, ResolvedPredicate, TimeRange};
 use models::schema::{Precision, TableColumn};
 use models::{ColumnId, SeriesId, SeriesKey};
 use protos::kv_service::{WritePointsRequest, WritePointsResponse};
@@ -20,10 +23,12 @@ use crate::kv_option::StorageOptions;
 pub use crate::kvcore::TsKv;
 pub use crate::summary::{print_summary_statistics, Summary, VersionEdit};
 use crate::tseries_family::SuperVersion;
+pub use crate::tseries_family::VnodeSnapshot;
 pub use crate::tsm::print_tsm_statistics;
 pub use crate::wal::print_wal_statistics;
 
 pub mod byte_utils;
+pub mod cold_storage;
 mod compaction;
 mod compute;
 mod context;
@@ -129,14 +134,35 @@ pub trait Engine: Send + Sync + Debug {
         new_column: TableColumn,
     ) -> Result<()>;
 
-    // TODO this method is not completed,
+    /// Delete data of the given series and columns in the storage unit
+    /// that in the time ranges.
+    ///
+    /// - vnode_id - ID of the storage unit(caches and files).
+    /// - series_ids - IDs of the series to delete.
+    /// - column_ids - IDs of the columns of the series to delete.
+    /// - time_ranges - Time ranges of the data to delete.
+    ///
+    /// Rows are removed from caches and tombstones are added to files,
+    /// this action will be written to the write-ahead-log first.
     async fn delete_series(
         &self,
         tenant: &str,
         database: &str,
+        vnode_id: VnodeId,
         series_ids: &[SeriesId],
-        field_ids: &[ColumnId],
-        time_range: &TimeRange,
+        column_ids: &[ColumnId],
+        time_ranges: &[TimeRange],
+    ) -> Result<()>;
+
+    /// Delete data of a table in the storage unit that matches the predicate,
+    /// only tags filter and time ranges of the predicate are used.
+    async fn delete_from_table(
+        &self,
+        tenant: &str,
+        database: &str,
+        table: &str,
+        vnode_id: VnodeId,
+        predicate: &ResolvedPredicate,
     ) -> Result<()>;
 
     /// Read index of a storage unit, find series ids that matches the filter.
@@ -188,6 +214,15 @@ pub trait Engine: Send + Sync + Debug {
         summary: VersionEdit,
     ) -> Result<()>;
 
+    /// Flush caches of the storage unit, then get a snapshot of it's files,
+    /// cold files are downloaded into local cache first.
+    async fn get_vnode_snapshot(
+        &self,
+        tenant: &str,
+        database: &str,
+        vnode_id: u32,
+    ) -> Result<Option<VnodeSnapshot>>;
+
     // TODO this method is the same as remove_tsfamily and not be referenced,
     // we can delete it.
     #[deprecated]
@@ -200,6 +235,29 @@ pub trait Engine: Send + Sync + Debug {
     /// Get a compressed hash_tree(ID and checksum of each vnode) of engine.
     async fn get_vnode_hash_tree(&self, vnode_id: VnodeId) -> Result<RecordBatch>;
 
+    /// Get checksums of each column of each series in each time range of the vnode,
+    /// for finding the data different from other replicas.
+    async fn get_vnode_time_range_checksum(&self, vnode_id: VnodeId) -> Result<RecordBatch>;
+
+    /// Read data and tombstones of the series in the time ranges of the vnode with their
+    /// sequence numbers, for repairing other replicas.
+    async fn read_vnode_series_data(
+        &self,
+        vnode_id: VnodeId,
+        ranges: Vec<(SeriesKey, TimeRange)>,
+    ) -> Result<RecordBatch>;
+
+    /// Write points to the time ranges of series of the vnode for repairing it, a time range
+    /// is skipped if its data was changed since the points were merged. Writes and
+    /// deletions of the vnode wait for the repair, returns if each range is repaired.
+    async fn repair_vnode_series_data(
+        &self,
+        tenant: &str,
+        vnode_id: VnodeId,
+        precision: Precision,
+        ranges: Vec<SeriesRangeRepair>,
+    ) -> Result<RecordBatch>;
+
     /// Close all background jobs of engine.
     async fn close(&self);
 }
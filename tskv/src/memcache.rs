@@ -7,7 +7,7 @@ use std::sync::Arc;
 use memory_pool::{MemoryConsumer, MemoryPoolRef, MemoryReservation};
 use minivec::{mini_vec, MiniVec};
 use models::predicate::domain::TimeRange;
-use models::schema::{timestamp_convert, Precision, TableColumn, TskvTableSchema};
+use models::schema::{timestamp_convert, ColumnType, Precision, TableColumn, TskvTableSchema};
 use models::utils::split_id;
 use models::{ColumnId, FieldId, RwLockRef, SchemaId, SeriesId, Timestamp, ValueType};
 use parking_lot::RwLock;
@@ -75,6 +75,19 @@ impl FieldVal {
         }
     }
 
+    /// Convert the value to the given type, used when type of the column was changed.
+    ///
+    /// Only widening conversions from integer types to float are supported, returns None
+    /// for other types, see [`ColumnType::is_convertible_to`].
+    pub fn convert(&self, vtype: ValueType) -> Option<FieldVal> {
+        match (self, vtype) {
+            (v, vtype) if v.value_type() == vtype => Some(v.clone()),
+            (FieldVal::Integer(val), ValueType::Float) => Some(FieldVal::Float(*val as f64)),
+            (FieldVal::Unsigned(val), ValueType::Float) => Some(FieldVal::Float(*val as f64)),
+            _ => None,
+        }
+    }
+
     pub fn heap_size(&self) -> usize {
         if let FieldVal::Bytes(val) = self {
             val.capacity()
@@ -167,7 +180,15 @@ impl RowData {
                                 if !field_nullbit.get(idx) {
                                     continue;
                                 }
-                                fields[*index] = Some(FieldVal::new(val, (*field_type).into()));
+                                let val = FieldVal::new(val, (*field_type).into());
+                                // Points in WAL may be written before type of the column was changed,
+                                // a value that can't be converted is kept as it is.
+                                fields[*index] = match field.column_type {
+                                    ColumnType::Field(vtype) if vtype != val.value_type() => {
+                                        Some(val.convert(vtype).unwrap_or(val))
+                                    }
+                                    _ => Some(val),
+                                };
                             }
                         },
                     }
@@ -268,8 +289,31 @@ impl SeriesData {
 
     pub fn change_column(&mut self, column_name: &str, new_column: &TableColumn) {
         for item in self.groups.iter_mut() {
+            let is_tag = item
+                .schema
+                .column(column_name)
+                .map(|c| c.column_type.is_tag())
+                .unwrap_or(false);
             let mut schema_t = item.schema.as_ref().clone();
             schema_t.change_column(column_name, new_column.clone());
+            if let ColumnType::Field(vtype) = new_column.column_type {
+                let index = schema_t.fields_id().get(&new_column.id).copied();
+                if let Some(index) = index {
+                    for row in item.rows.iter_mut() {
+                        if is_tag {
+                            // Fields of rows are ordered by column id, values of the tag
+                            // are still in the series key.
+                            if index <= row.fields.len() {
+                                row.fields.insert(index, None);
+                            }
+                        } else if let Some(Some(val)) = row.fields.get_mut(index) {
+                            if let Some(converted) = val.convert(vtype) {
+                                *val = converted;
+                            }
+                        }
+                    }
+                }
+            }
             schema_t.schema_id += 1;
             item.schema = Arc::new(schema_t)
         }
@@ -583,6 +627,22 @@ impl DataType {
         }
     }
 
+    /// Convert the value to the given type, see [`FieldVal::convert`].
+    /// Returns the value itself as the error if it can't be converted.
+    pub fn convert(self, vtype: ValueType) -> std::result::Result<Self, Self> {
+        let converted = match self {
+            DataType::I64(ts, val) if vtype == ValueType::Float => DataType::F64(ts, val as f64),
+            DataType::U64(ts, val) if vtype == ValueType::Float => DataType::F64(ts, val as f64),
+            DataType::U64(..) if vtype == ValueType::Unsigned => self,
+            DataType::I64(..) if vtype == ValueType::Integer => self,
+            DataType::Str(..) if vtype == ValueType::String => self,
+            DataType::F64(..) if vtype == ValueType::Float => self,
+            DataType::Bool(..) if vtype == ValueType::Boolean => self,
+            _ => return Err(self),
+        };
+        Ok(converted)
+    }
+
     #[cfg(test)]
     pub fn to_bytes(&self) -> MiniVec<u8> {
         match self {
@@ -643,7 +703,7 @@ pub(crate) mod test {
     use models::{SchemaId, SeriesId, Timestamp};
     use parking_lot::RwLock;
 
-    use super::{FieldVal, MemCache, RowData, RowGroup};
+    use super::{FieldVal, MemCache, RowData, RowGroup, SeriesData};
 
     pub fn put_rows_to_cache(
         cache: &MemCache,
@@ -721,7 +781,7 @@ mod test_memcache {
     use models::schema::{ColumnType, TableColumn, TskvTableSchema};
     use models::{SeriesId, ValueType};
 
-    use super::{FieldVal, MemCache, RowData, RowGroup};
+    use super::{FieldVal, MemCache, RowData, RowGroup, SeriesData};
 
     #[test]
     fn test_write_group() {
@@ -802,4 +862,47 @@ mod test_memcache {
             assert_eq!(row_group_2, series_data.groups[1]);
         }
     }
+
+    #[test]
+    fn test_series_data_change_column() {
+        #[rustfmt::skip]
+        let schema = TskvTableSchema::new(
+            "test_tenant".to_string(), "test_db".to_string(), "test_table".to_string(),
+            vec![
+                TableColumn::new_time_column(1, TimeUnit::Nanosecond),
+                TableColumn::new_tag_column(2, "tag_col_1".to_string()),
+                TableColumn::new(3, "f_col_1".to_string(), ColumnType::Field(ValueType::Integer), Default::default()),
+            ],
+        );
+        let mut series_data = SeriesData::new(1);
+        series_data.write(RowGroup {
+            schema: Arc::new(schema.clone()),
+            range: TimeRange::new(1, 1),
+            rows: vec![RowData {
+                ts: 1,
+                fields: vec![Some(FieldVal::Integer(-1))],
+            }],
+            size: 10,
+        });
+
+        // Integer is widened to float.
+        let mut f_col_1 = schema.column("f_col_1").unwrap().clone();
+        f_col_1.column_type = ColumnType::Field(ValueType::Float);
+        series_data.change_column("f_col_1", &f_col_1);
+        assert_eq!(
+            series_data.groups[0].rows[0].fields,
+            vec![Some(FieldVal::Float(-1.0))]
+        );
+
+        // Values of the tag are in the series key, fields are ordered by column id.
+        let mut tag_col_1 = schema.column("tag_col_1").unwrap().clone();
+        tag_col_1.column_type = ColumnType::Field(ValueType::String);
+        tag_col_1.converted_from_tag = Some("tag_col_1".to_string());
+        series_data.change_column("tag_col_1", &tag_col_1);
+        assert_eq!(
+            series_data.groups[0].rows[0].fields,
+            vec![None, Some(FieldVal::Float(-1.0))]
+        );
+        assert_eq!(series_data.groups[0].schema.fields_id().get(&2), Some(&0));
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use datafusion::arrow::array::{
@@ -13,9 +14,11 @@ use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::physical_plan::metrics::{self, ExecutionPlanMetricsSet, MetricBuilder};
 use minivec::MiniVec;
 use models::meta_data::VnodeId;
-use models::predicate::domain::{self, QueryArgs, QueryExpr, TimeRanges};
+use models::predicate::domain::{
+    self, AggregateKind, ColumnAggregate, Domain, QueryArgs, QueryExpr, TimeRanges,
+};
 use models::predicate::PlacedSplit;
-use models::schema::{ColumnType, TableColumn, TskvTableSchema};
+use models::schema::{ColumnType, TskvTableSchema};
 use models::utils::{min_num, unite_id};
 use models::{FieldId, SeriesId, Timestamp, ValueType};
 use protos::kv_service::QueryRecordBatchRequest;
@@ -25,12 +28,14 @@ use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 use trace::{debug, error, SpanRecorder};
 
+use crate::compute::aggregate::aggregate_field_values;
 use crate::compute::count::count_column_non_null_values;
 use crate::error::Result;
 use crate::memcache::DataType;
+use crate::reader::scan_metrics::ScanMetrics;
 use crate::reader::Cursor;
 use crate::tseries_family::SuperVersion;
-use crate::tsm::{BlockMetaIterator, DataBlock, TsmReader};
+use crate::tsm::{BlockMeta, BlockMetaIterator, DataBlock, TsmReader};
 use crate::{EngineRef, Error};
 
 pub type CursorPtr = Box<dyn Cursor>;
@@ -70,6 +75,15 @@ impl ArrayBuilderPtr {
         }
     }
 
+    pub fn append_null_timestamp(&mut self, unit: &TimeUnit) {
+        match unit {
+            TimeUnit::Second => self.append_primitive_null::<TimestampSecondType>(),
+            TimeUnit::Millisecond => self.append_primitive_null::<TimestampMillisecondType>(),
+            TimeUnit::Microsecond => self.append_primitive_null::<TimestampMicrosecondType>(),
+            TimeUnit::Nanosecond => self.append_primitive_null::<TimestampNanosecondType>(),
+        }
+    }
+
     pub fn append_value(
         &mut self,
         value_type: ValueType,
@@ -339,7 +353,9 @@ pub struct QueryOption {
     pub split: PlacedSplit,
     pub df_schema: SchemaRef,
     pub table_schema: TskvTableSchema,
-    pub aggregates: Option<Vec<TableColumn>>, // TODO: Use PushedAggregateFunction
+    pub aggregates: Option<Vec<ColumnAggregate>>,
+    /// Metrics of the scan, not sent to remote nodes.
+    pub scan_metrics: ScanMetrics,
 }
 
 impl QueryOption {
@@ -347,7 +363,7 @@ impl QueryOption {
     pub fn new(
         batch_size: usize,
         split: PlacedSplit,
-        aggregates: Option<Vec<TableColumn>>, // TODO: Use PushedAggregateFunction
+        aggregates: Option<Vec<ColumnAggregate>>,
         df_schema: SchemaRef,
         table_schema: TskvTableSchema,
     ) -> Self {
@@ -357,6 +373,14 @@ impl QueryOption {
             aggregates,
             df_schema,
             table_schema,
+            scan_metrics: ScanMetrics::default(),
+        }
+    }
+
+    pub fn with_scan_metrics(self, scan_metrics: ScanMetrics) -> Self {
+        Self {
+            scan_metrics,
+            ..self
         }
     }
 
@@ -387,6 +411,7 @@ impl QueryOption {
             args: args_bytes,
             expr: expr_bytes,
             aggs: aggs_bytes,
+            with_scan_metrics: false,
         })
     }
 }
@@ -395,6 +420,12 @@ pub struct FieldFileLocation {
     reader: Arc<TsmReader>,
     block_meta_iter: BlockMetaIterator,
     time_ranges: Arc<TimeRanges>,
+    /// Offsets of blocks that have no value matching the field predicate.
+    skipped_blocks: HashSet<u64>,
+    /// Current type of the field, blocks written before type of the field
+    /// was changed are converted to this type.
+    value_type: ValueType,
+    scan_metrics: ScanMetrics,
 
     data_block: DataBlock,
     /// The first index of a DataType in a DataBlock
@@ -410,12 +441,17 @@ impl FieldFileLocation {
         reader: Arc<TsmReader>,
         time_ranges: Arc<TimeRanges>,
         block_meta_iter: BlockMetaIterator,
+        skipped_blocks: HashSet<u64>,
         vtype: ValueType,
+        scan_metrics: ScanMetrics,
     ) -> Self {
         Self {
             reader,
             block_meta_iter,
             time_ranges,
+            skipped_blocks,
+            value_type: vtype,
+            scan_metrics,
             // TODO: can here use unsafe api MaybeUninit<DataBLock> ?
             data_block: DataBlock::new(0, vtype),
             // Let data block index > end index when init to make it load from reader
@@ -451,15 +487,32 @@ impl FieldFileLocation {
         let mut has_next_block = false;
 
         // Get next BlockMeta to locate the next DataBlock from file.
-        while let Some(meta) = self.block_meta_iter.next() {
+        loop {
+            let meta = self.block_meta_iter.next();
+            self.scan_metrics
+                .blocks_pruned_by_time_range()
+                .add(self.block_meta_iter.take_pruned_blocks());
+            let Some(meta) = meta else {
+                break;
+            };
             if meta.count() == 0 {
                 continue;
             }
+            if self.skipped_blocks.contains(&meta.offset()) {
+                self.scan_metrics.blocks_pruned_by_statistics().add(1);
+                continue;
+            }
             let time_range = meta.time_range();
             // Check if the time range of the BlockMeta intersected with the given time ranges.
             if let Some(intersected_tr) = self.time_ranges.intersect(&time_range) {
                 // Load a DataBlock from reader by BlockMeta.
-                self.data_block = self.reader.get_data_block(&meta).await?;
+                self.scan_metrics.blocks_read().add(1);
+                self.scan_metrics.bytes_read().add(meta.size() as usize);
+                self.data_block = self
+                    .reader
+                    .get_data_block(&meta)
+                    .await?
+                    .convert(self.value_type)?;
                 self.intersected_time_ranges = intersected_tr;
                 self.intersected_time_ranges_i = 0;
                 if self.next_intersected_index_range() {
@@ -467,6 +520,8 @@ impl FieldFileLocation {
                     has_next_block = true;
                     break;
                 }
+            } else {
+                self.scan_metrics.blocks_pruned_by_time_range().add(1);
             }
         }
 
@@ -518,6 +573,47 @@ impl std::fmt::Debug for FieldFileLocation {
     }
 }
 
+/// Find blocks in each location that have no value matching the field domain by block
+/// statistics, returns offsets of the blocks.
+///
+/// A block is skipped only if no data in cache or other locations overlaps with it,
+/// otherwise the values it overwrites would be read.
+fn find_unmatched_blocks(
+    locations: &[Vec<BlockMeta>],
+    cache_data: &[DataType],
+    domain: &Domain,
+    value_type: ValueType,
+) -> Vec<HashSet<u64>> {
+    let mut unmatched_blocks = vec![HashSet::new(); locations.len()];
+    for (i, blocks) in locations.iter().enumerate() {
+        for blk in blocks.iter() {
+            // Statistics of blocks written before the field type changed are not comparable.
+            if blk.field_type() != value_type {
+                continue;
+            }
+            match blk.statistics() {
+                Some(stats) if !stats.may_match(domain) => {}
+                _ => continue,
+            }
+            let time_range = blk.time_range();
+            let cache_i = cache_data.partition_point(|d| d.timestamp() < time_range.min_ts);
+            if matches!(cache_data.get(cache_i), Some(d) if d.timestamp() <= time_range.max_ts) {
+                continue;
+            }
+            let overlapped = locations.iter().enumerate().any(|(j, other_blocks)| {
+                j != i
+                    && other_blocks
+                        .iter()
+                        .any(|b| b.time_range().overlaps(&time_range))
+            });
+            if !overlapped {
+                unmatched_blocks[i].insert(blk.offset());
+            }
+        }
+    }
+    unmatched_blocks
+}
+
 //-----------Time Cursor----------------
 pub struct TimeCursor {
     ts: i64,
@@ -737,6 +833,14 @@ impl RowIterator {
             series_ids.len()
         );
         let metrics_set = ExecutionPlanMetricsSet::new();
+        // Number of series left after pruning by the tag filter
+        MetricBuilder::new(&metrics_set)
+            .global_counter("series_scanned")
+            .add(series_ids.len());
+        query_option
+            .scan_metrics
+            .series_matched()
+            .add(series_ids.len());
         let query_option = Arc::new(query_option);
         let series_len = series_ids.len();
         let (tx, rx) = channel(1);
@@ -851,11 +955,25 @@ impl RowIterator {
         // Get builders for aggregating.
         if let Some(aggregates) = query_option.aggregates.as_ref() {
             let mut builders: Vec<ArrayBuilderPtr> = Vec::with_capacity(aggregates.len());
-            for _ in 0..aggregates.len() {
-                builders.push(ArrayBuilderPtr::new(
-                    Box::new(Int64Builder::with_capacity(query_option.batch_size)),
-                    ColumnType::Field(ValueType::Integer),
-                ));
+            for agg in aggregates.iter() {
+                // First and last are merged by timestamps, so the timestamp is returned
+                // in a column before the value.
+                if matches!(agg.kind, AggregateKind::First | AggregateKind::Last) {
+                    let column_type = query_option.table_schema.time_column().column_type;
+                    let builder_item =
+                        Self::new_column_builder(&column_type, query_option.batch_size)?;
+                    builders.push(ArrayBuilderPtr::new(builder_item, column_type));
+                }
+                // Count and sum of integers are Int64, others are the same as the column.
+                let column_type = match (agg.kind, &agg.column.column_type) {
+                    (AggregateKind::Count, _)
+                    | (AggregateKind::Sum, ColumnType::Field(ValueType::Integer)) => {
+                        ColumnType::Field(ValueType::Integer)
+                    }
+                    (_, column_type) => column_type.clone(),
+                };
+                let builder_item = Self::new_column_builder(&column_type, query_option.batch_size)?;
+                builders.push(ArrayBuilderPtr::new(builder_item, column_type));
             }
             return Ok(builders);
         }
@@ -1113,6 +1231,12 @@ impl SeriesGroupRowIterator {
                     "Building series columns: sid={:02X}, column={:?}",
                     series_id, item
                 );
+                // Series written before the tag was converted to the field keep
+                // the values in their series keys.
+                let converted_tag_val = item
+                    .converted_from_tag
+                    .as_ref()
+                    .and_then(|tag| key.tag_val(tag));
                 let column_cursor: CursorPtr = match item.column_type {
                     ColumnType::Time(ref unit) => {
                         Box::new(TimeCursor::new(0, item.name.clone(), unit.clone()))
@@ -1123,6 +1247,10 @@ impl SeriesGroupRowIterator {
                         Box::new(TagCursor::new(item.name.clone(), tag_val))
                     }
 
+                    ColumnType::Field(_) if converted_tag_val.is_some() => {
+                        Box::new(TagCursor::new(item.name.clone(), converted_tag_val))
+                    }
+
                     ColumnType::Field(vtype) => match vtype {
                         ValueType::Unknown => {
                             error!("Unknown field type of column {}", &item.name);
@@ -1165,6 +1293,7 @@ impl SeriesGroupRowIterator {
             None => return Ok(FieldCursor::empty(field_type, field_name)),
         };
 
+        let scan_metrics = &self.query_option.scan_metrics;
         let time_ranges_ref = self.query_option.split.time_ranges();
         let time_predicate = |ts| time_ranges_ref.is_boundless() || time_ranges_ref.contains(ts);
         debug!("Pushed down time range filter: {:?}", time_ranges_ref);
@@ -1177,9 +1306,10 @@ impl SeriesGroupRowIterator {
             field_id,
             time_predicate,
             |_| true,
-            |d| cache_data.push(d),
+            |d| cache_data.push(d.convert(field_type).unwrap_or_else(|d| d)),
         );
         cache_data.sort_by_key(|data| data.timestamp());
+        scan_metrics.memcache_rows().add(cache_data.len());
 
         timer.done();
 
@@ -1193,14 +1323,21 @@ impl SeriesGroupRowIterator {
 
         // Get data from level info, find time range overlapped files and file locations.
         // TODO: Init locations in parallel with other fields.
-        let mut locations = vec![];
+        let mut index_metas = vec![];
         for level in super_version.version.levels_info.iter().rev() {
             if !time_ranges_ref.overlaps(&level.time_range) {
+                scan_metrics
+                    .files_pruned_by_time_range()
+                    .add(level.files.len());
                 continue;
             }
             for file in level.files.iter() {
-                if !time_ranges_ref.overlaps(file.time_range()) || !file.contains_field_id(field_id)
-                {
+                if !time_ranges_ref.overlaps(file.time_range()) {
+                    scan_metrics.files_pruned_by_time_range().add(1);
+                    continue;
+                }
+                if !file.contains_field_id(field_id) {
+                    scan_metrics.files_pruned_by_bloom_filter().add(1);
                     continue;
                 }
                 let path = file.file_path();
@@ -1219,17 +1356,46 @@ impl SeriesGroupRowIterator {
                 }
 
                 let tsm_reader = super_version.version.get_tsm_reader(path).await?;
+                scan_metrics.files_opened().add(1);
                 for idx_meta in tsm_reader.index_iterator_opt(field_id) {
-                    let location = FieldFileLocation::new(
-                        tsm_reader.clone(),
-                        time_ranges_ref.clone(),
-                        idx_meta.block_iterator_opt(time_ranges_ref.clone()),
-                        field_type,
-                    );
-                    locations.push(location);
+                    index_metas.push((tsm_reader.clone(), idx_meta));
                 }
             }
         }
+
+        let mut skipped_blocks = vec![HashSet::new(); index_metas.len()];
+        let field_domain = self
+            .query_option
+            .split
+            .fields_filter()
+            .domains()
+            .and_then(|domains| domains.get(&field_name));
+        if let Some(domain) = field_domain.filter(|d| !matches!(d, Domain::All)) {
+            let blocks: Vec<Vec<BlockMeta>> = index_metas
+                .iter()
+                .map(|(_, idx_meta)| {
+                    idx_meta
+                        .block_iterator_opt(time_ranges_ref.clone())
+                        .collect()
+                })
+                .collect();
+            skipped_blocks = find_unmatched_blocks(&blocks, &cache_data, domain, field_type);
+        }
+
+        let locations: Vec<FieldFileLocation> = index_metas
+            .into_iter()
+            .zip(skipped_blocks)
+            .map(|((tsm_reader, idx_meta), skipped)| {
+                FieldFileLocation::new(
+                    tsm_reader,
+                    time_ranges_ref.clone(),
+                    idx_meta.block_iterator_opt(time_ranges_ref.clone()),
+                    skipped,
+                    field_type,
+                    scan_metrics.clone(),
+                )
+            })
+            .collect();
         debug!("Building FieldCursor: locations: {:?}", &locations);
         timer.done();
 
@@ -1329,10 +1495,19 @@ impl SeriesGroupRowIterator {
             self.query_option.aggregates.as_ref(),
         ) {
             (Some(version), Some(aggregates)) => {
-                for (i, item) in aggregates.iter().enumerate() {
-                    match item.column_type {
-                        ColumnType::Tag => todo!("collect count for tag"),
-                        ColumnType::Time(_) => {
+                let time_column_type = self.query_option.table_schema.time_column().column_type;
+                // Index of the builder of the aggregate.
+                let mut i = 0;
+                for agg in aggregates.iter() {
+                    let item = &agg.column;
+                    match (agg.kind, &item.column_type) {
+                        (_, ColumnType::Tag) => todo!("collect aggregate for tag"),
+                        (_, ColumnType::Field(ValueType::Unknown)) => {
+                            return Err(Error::CommonError {
+                                reason: format!("unknown type of {}", item.name),
+                            });
+                        }
+                        (AggregateKind::Count, ColumnType::Time(_)) => {
                             let agg_ret = count_column_non_null_values(
                                 self.runtime.clone(),
                                 version.clone(),
@@ -1343,25 +1518,53 @@ impl SeriesGroupRowIterator {
                             .await?;
                             builder[i].append_primitive::<Int64Type>(agg_ret as i64);
                         }
-                        ColumnType::Field(vtype) => match vtype {
-                            ValueType::Unknown => {
-                                return Err(Error::CommonError {
-                                    reason: format!("unknown type of {}", item.name),
-                                });
-                            }
-                            _ => {
-                                let agg_ret = count_column_non_null_values(
-                                    self.runtime.clone(),
-                                    version.clone(),
-                                    self.series_ids.clone(),
-                                    Some(item.id),
-                                    self.query_option.split.time_ranges(),
-                                )
-                                .await?;
-                                builder[i].append_primitive::<Int64Type>(agg_ret as i64);
+                        (AggregateKind::Count, ColumnType::Field(_)) => {
+                            let agg_ret = count_column_non_null_values(
+                                self.runtime.clone(),
+                                version.clone(),
+                                self.series_ids.clone(),
+                                Some(item.id),
+                                self.query_option.split.time_ranges(),
+                            )
+                            .await?;
+                            builder[i].append_primitive::<Int64Type>(agg_ret as i64);
+                        }
+                        (_, ColumnType::Time(_)) => {
+                            return Err(Error::CommonError {
+                                reason: format!(
+                                    "aggregate {:?} is not supported for time column",
+                                    agg.kind
+                                ),
+                            });
+                        }
+                        (kind, ColumnType::Field(vtype)) => {
+                            let agg_ret = aggregate_field_values(
+                                self.runtime.clone(),
+                                version.clone(),
+                                self.series_ids.clone(),
+                                item.id,
+                                *vtype,
+                                kind,
+                                self.query_option.split.time_ranges(),
+                            )
+                            .await?;
+                            if let (
+                                AggregateKind::First | AggregateKind::Last,
+                                ColumnType::Time(unit),
+                            ) = (kind, &time_column_type)
+                            {
+                                match agg_ret.as_ref() {
+                                    Some(data) => {
+                                        builder[i].append_timestamp(unit, data.timestamp())
+                                    }
+                                    None => builder[i].append_null_timestamp(unit),
+                                }
+                                i += 1;
                             }
-                        },
+                            builder[i].append_value(*vtype, agg_ret, &item.name)?;
+                        }
                     };
+                    i += 1;
                 }
 
                 Ok(Some(()))
@@ -10,6 +10,7 @@ use crate::{Error, Result};
 
 mod iterator;
 pub mod query_executor;
+pub mod scan_metrics;
 pub mod serialize;
 pub mod status_listener;
 pub mod table_scan;
@@ -0,0 +1,175 @@
+use datafusion::physical_plan::metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder, Time};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Metrics about the scan of a vnode split, reported by `EXPLAIN ANALYZE`.
+///
+/// The metrics are shared by all iterators reading the split. When the vnode is on
+/// another node, the remote node sends its metrics back after the last record batch,
+/// see [`ScanMetrics::encode`] and [`ScanMetrics::merge_encoded`].
+#[derive(Debug, Clone, Default)]
+pub struct ScanMetrics {
+    /// Number of series matched from the index by the tag filter
+    series_matched: Count,
+    /// Number of TSM files opened for reading
+    files_opened: Count,
+    /// Number of TSM files skipped because their time range doesn't overlap the query
+    files_pruned_by_time_range: Count,
+    /// Number of TSM files skipped because their bloom filter doesn't contain the field
+    files_pruned_by_bloom_filter: Count,
+    /// Number of TSM blocks read
+    blocks_read: Count,
+    /// Number of TSM blocks skipped because their time range doesn't overlap the query
+    blocks_pruned_by_time_range: Count,
+    /// Number of TSM blocks skipped because their statistics don't match the field filter
+    blocks_pruned_by_statistics: Count,
+    /// Size in bytes of the TSM blocks read from files, before they are decoded
+    bytes_read: Count,
+    /// Number of rows merged from memcaches
+    memcache_rows: Count,
+    /// Time spent waiting on the remote node
+    remote_rpc_time: Time,
+}
+
+impl ScanMetrics {
+    /// Create new metrics
+    pub fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
+        let counter = |name: &'static str| MetricBuilder::new(metrics).counter(name, partition);
+
+        Self {
+            series_matched: counter("series_matched"),
+            files_opened: counter("files_opened"),
+            files_pruned_by_time_range: counter("files_pruned_by_time_range"),
+            files_pruned_by_bloom_filter: counter("files_pruned_by_bloom_filter"),
+            blocks_read: counter("blocks_read"),
+            blocks_pruned_by_time_range: counter("blocks_pruned_by_time_range"),
+            blocks_pruned_by_statistics: counter("blocks_pruned_by_statistics"),
+            bytes_read: counter("bytes_read"),
+            memcache_rows: counter("memcache_rows"),
+            remote_rpc_time: MetricBuilder::new(metrics).subset_time("remote_rpc_time", partition),
+        }
+    }
+
+    pub fn series_matched(&self) -> &Count {
+        &self.series_matched
+    }
+
+    pub fn files_opened(&self) -> &Count {
+        &self.files_opened
+    }
+
+    pub fn files_pruned_by_time_range(&self) -> &Count {
+        &self.files_pruned_by_time_range
+    }
+
+    pub fn files_pruned_by_bloom_filter(&self) -> &Count {
+        &self.files_pruned_by_bloom_filter
+    }
+
+    pub fn blocks_read(&self) -> &Count {
+        &self.blocks_read
+    }
+
+    pub fn blocks_pruned_by_time_range(&self) -> &Count {
+        &self.blocks_pruned_by_time_range
+    }
+
+    pub fn blocks_pruned_by_statistics(&self) -> &Count {
+        &self.blocks_pruned_by_statistics
+    }
+
+    pub fn bytes_read(&self) -> &Count {
+        &self.bytes_read
+    }
+
+    pub fn memcache_rows(&self) -> &Count {
+        &self.memcache_rows
+    }
+
+    pub fn remote_rpc_time(&self) -> &Time {
+        &self.remote_rpc_time
+    }
+
+    /// Encode the counters, to be sent back to the node which requests the scan.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let values = ScanMetricsValues {
+            series_matched: self.series_matched.value(),
+            files_opened: self.files_opened.value(),
+            files_pruned_by_time_range: self.files_pruned_by_time_range.value(),
+            files_pruned_by_bloom_filter: self.files_pruned_by_bloom_filter.value(),
+            blocks_read: self.blocks_read.value(),
+            blocks_pruned_by_time_range: self.blocks_pruned_by_time_range.value(),
+            blocks_pruned_by_statistics: self.blocks_pruned_by_statistics.value(),
+            bytes_read: self.bytes_read.value(),
+            memcache_rows: self.memcache_rows.value(),
+        };
+        bincode::serialize(&values).map_err(|err| Error::Encode { source: err })
+    }
+
+    /// Add the counters encoded by [`ScanMetrics::encode`] to these metrics.
+    pub fn merge_encoded(&self, bytes: &[u8]) -> Result<()> {
+        let values: ScanMetricsValues =
+            bincode::deserialize(bytes).map_err(|err| Error::Decode { source: err })?;
+        self.series_matched.add(values.series_matched);
+        self.files_opened.add(values.files_opened);
+        self.files_pruned_by_time_range
+            .add(values.files_pruned_by_time_range);
+        self.files_pruned_by_bloom_filter
+            .add(values.files_pruned_by_bloom_filter);
+        self.blocks_read.add(values.blocks_read);
+        self.blocks_pruned_by_time_range
+            .add(values.blocks_pruned_by_time_range);
+        self.blocks_pruned_by_statistics
+            .add(values.blocks_pruned_by_statistics);
+        self.bytes_read.add(values.bytes_read);
+        self.memcache_rows.add(values.memcache_rows);
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScanMetricsValues {
+    series_matched: usize,
+    files_opened: usize,
+    files_pruned_by_time_range: usize,
+    files_pruned_by_bloom_filter: usize,
+    blocks_read: usize,
+    blocks_pruned_by_time_range: usize,
+    blocks_pruned_by_statistics: usize,
+    bytes_read: usize,
+    memcache_rows: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+
+    use super::ScanMetrics;
+
+    #[test]
+    fn test_merge_encoded() {
+        let remote = ScanMetrics::default();
+        remote.series_matched().add(3);
+        remote.blocks_read().add(5);
+        remote.bytes_read().add(1024);
+
+        let metrics_set = ExecutionPlanMetricsSet::new();
+        let local = ScanMetrics::new(&metrics_set, 0);
+        local.blocks_read().add(1);
+        local.merge_encoded(&remote.encode().unwrap()).unwrap();
+
+        assert_eq!(local.series_matched().value(), 3);
+        assert_eq!(local.blocks_read().value(), 6);
+        assert_eq!(local.bytes_read().value(), 1024);
+        assert_eq!(
+            metrics_set
+                .clone_inner()
+                .sum_by_name("blocks_read")
+                .unwrap()
+                .as_usize(),
+            6
+        );
+    }
+}
@@ -7,10 +7,13 @@ use protos::kv_service::BatchBytesResponse;
 use trace::SpanRecorder;
 
 use crate::error::{Error as TskvError, Result};
+use crate::reader::scan_metrics::ScanMetrics;
 use crate::reader::SendableTskvRecordBatchStream;
 
 pub struct TonicRecordBatchEncoder {
     input: SendableTskvRecordBatchStream,
+    /// Sent after the last record batch if exists.
+    scan_metrics: Option<ScanMetrics>,
     #[allow(unused)]
     span_recorder: SpanRecorder,
 }
@@ -19,9 +22,17 @@ impl TonicRecordBatchEncoder {
     pub fn new(input: SendableTskvRecordBatchStream, span_recorder: SpanRecorder) -> Self {
         Self {
             input,
+            scan_metrics: None,
             span_recorder,
         }
     }
+
+    pub fn with_scan_metrics(self, scan_metrics: ScanMetrics) -> Self {
+        Self {
+            scan_metrics: Some(scan_metrics),
+            ..self
+        }
+    }
 }
 
 impl Stream for TonicRecordBatchEncoder {
@@ -43,7 +54,16 @@ impl Stream for TonicRecordBatchEncoder {
                 }
             },
             Some(Err(err)) => Poll::Ready(Some(Err(err))),
-            None => Poll::Ready(None),
+            None => match self.scan_metrics.take() {
+                Some(scan_metrics) => {
+                    let resp = scan_metrics.encode().map(|metrics| BatchBytesResponse {
+                        metrics,
+                        ..Default::default()
+                    });
+                    Poll::Ready(Some(resp))
+                }
+                None => Poll::Ready(None),
+            },
         }
     }
 }
@@ -42,6 +42,8 @@ pub struct CompactMeta {
     pub high_seq: u64,
     pub low_seq: u64,
     pub is_delta: bool,
+    /// The file has been moved to cold storage.
+    pub is_remote: bool,
 }
 
 impl Default for CompactMeta {
@@ -56,6 +58,7 @@ impl Default for CompactMeta {
             high_seq: u64::MIN,
             low_seq: u64::MIN,
             is_delta: false,
+            is_remote: false,
         }
     }
 }
@@ -73,6 +76,7 @@ impl From<&ColumnFile> for CompactMeta {
             min_ts: file.time_range().min_ts,
             max_ts: file.time_range().max_ts,
             is_delta: file.is_delta(),
+            is_remote: file.is_remote(),
             ..Default::default()
         }
     }
@@ -88,6 +92,9 @@ impl CompactMeta {
         if self.is_delta {
             let base_dir = storage_opt.delta_dir(database, ts_family_id);
             file_utils::make_delta_file_name(base_dir, self.file_id)
+        } else if self.is_remote {
+            let base_dir = storage_opt.cold_dir(database, ts_family_id);
+            file_utils::make_tsm_file_name(base_dir, self.file_id)
         } else {
             let base_dir = storage_opt.tsm_dir(database, ts_family_id);
             file_utils::make_tsm_file_name(base_dir, self.file_id)
@@ -119,7 +126,16 @@ impl CompactMeta {
             file_utils::make_tsm_file_name(base_dir, file_id)
         };
         trace::info!("rename file from {:?} to {:?}", &old_name, &new_name);
-        file_utils::rename(old_name, &new_name).await?;
+        file_utils::rename(&old_name, &new_name).await?;
+
+        // Tombstone of the file is moved too.
+        if let (Some(old_dir), Some(new_dir)) = (old_name.parent(), new_name.parent()) {
+            let old_tombstone = file_utils::make_tsm_tombstone_file_name(old_dir, self.file_id);
+            if try_exists(&old_tombstone) {
+                let new_tombstone = file_utils::make_tsm_tombstone_file_name(new_dir, file_id);
+                file_utils::rename(old_tombstone, new_tombstone).await?;
+            }
+        }
         self.file_id = file_id;
         Ok(new_name)
     }
@@ -478,8 +494,13 @@ impl Summary {
             for meta in files.into_values() {
                 let field_filter = if load_field_filter {
                     let tsm_path = meta.file_path(opt.storage.as_ref(), &database, tsf_id);
-                    let tsm_reader = TsmReader::open(tsm_path).await?;
-                    tsm_reader.bloom_filter()
+                    match opt.cold_storage.as_ref() {
+                        // Cold files are not downloaded for recovering.
+                        Some(cold_storage) if meta.is_remote => {
+                            cold_storage.read_field_filter(&tsm_path).await?
+                        }
+                        _ => TsmReader::open(tsm_path).await?.bloom_filter(),
+                    }
                 } else {
                     Arc::new(BloomFilter::default())
                 };
@@ -487,6 +508,7 @@ impl Summary {
                     &meta,
                     field_filter,
                     weak_tsm_reader_cache.clone(),
+                    opt.cold_storage.clone(),
                 );
             }
             let ver = Version::new(
@@ -497,6 +519,7 @@ impl Summary {
                 levels,
                 max_level_ts,
                 tsm_reader_cache,
+                opt.cold_storage.clone(),
             );
             versions.insert(tsf_id, Arc::new(ver));
         }
@@ -1291,6 +1314,7 @@ mod test {
                 &meta,
                 Arc::new(BloomFilter::default()),
                 tsm_reader_cache,
+                None,
             );
             tsf.write().await.new_version(version, None);
             edit.add_file(meta, 1);
@@ -20,13 +20,15 @@ use tokio_util::sync::CancellationToken;
 use trace::{debug, error, info, warn};
 use utils::BloomFilter;
 
+use crate::cold_storage::ColdStorage;
 use crate::compaction::{CompactTask, FlushReq};
 use crate::error::Result;
+use crate::file_system::file_manager::try_exists;
 use crate::file_utils::{make_delta_file_name, make_tsm_file_name};
 use crate::kv_option::{CacheOptions, StorageOptions};
 use crate::memcache::{DataType, FieldVal, MemCache, RowGroup};
 use crate::summary::{CompactMeta, VersionEdit};
-use crate::tsm::{DataBlock, TsmReader, TsmTombstone};
+use crate::tsm::{self, DataBlock, TsmReader, TsmTombstone};
 use crate::Error::CommonError;
 use crate::{ColumnFileId, LevelId, TseriesFamilyId};
 
@@ -40,9 +42,12 @@ pub struct ColumnFile {
     field_id_filter: Arc<BloomFilter>,
     deleted: AtomicBool,
     compacting: AtomicBool,
+    /// If true, the file is stored in cold storage, `path` is where it's cached.
+    is_remote: bool,
 
     path: PathBuf,
     tsm_reader_cache: Weak<ShardedCache<String, Arc<TsmReader>>>,
+    cold_storage: Option<Arc<ColdStorage>>,
 }
 
 impl ColumnFile {
@@ -51,6 +56,7 @@ impl ColumnFile {
         path: impl AsRef<Path>,
         field_id_filter: Arc<BloomFilter>,
         tsm_reader_cache: Weak<ShardedCache<String, Arc<TsmReader>>>,
+        cold_storage: Option<Arc<ColdStorage>>,
     ) -> Self {
         Self {
             file_id: meta.file_id,
@@ -61,8 +67,10 @@ impl ColumnFile {
             field_id_filter,
             deleted: AtomicBool::new(false),
             compacting: AtomicBool::new(false),
+            is_remote: meta.is_remote,
             path: path.as_ref().into(),
             tsm_reader_cache,
+            cold_storage,
         }
     }
 
@@ -90,6 +98,14 @@ impl ColumnFile {
         self.path.clone()
     }
 
+    pub fn is_remote(&self) -> bool {
+        self.is_remote
+    }
+
+    pub fn field_id_filter(&self) -> Arc<BloomFilter> {
+        self.field_id_filter.clone()
+    }
+
     pub fn overlap(&self, time_range: &TimeRange) -> bool {
         self.time_range.overlaps(time_range)
     }
@@ -113,6 +129,11 @@ impl ColumnFile {
         let mut tombstone = TsmTombstone::open(dir, self.file_id).await?;
         tombstone.add_range(field_ids, time_range).await?;
         tombstone.flush().await?;
+        // Cached reader holds the old tombstone, remove it to reload the new one.
+        if let Some(cache) = self.tsm_reader_cache.upgrade() {
+            let k = format!("{}", self.file_path().display());
+            cache.remove(&k).await;
+        }
         Ok(())
     }
 }
@@ -153,6 +174,21 @@ impl Drop for ColumnFile {
                 });
             }
 
+            if self.is_remote {
+                if let Some(cold_storage) = self.cold_storage.clone() {
+                    let (file_id, path) = (self.file_id, path.clone());
+                    tokio::spawn(async move {
+                        if let Err(e) = cold_storage.delete(&path).await {
+                            error!("Error when removing cold file {}: {}", file_id, e);
+                        }
+                    });
+                }
+                if !try_exists(&path) {
+                    // Cached file of a cold file may be evicted.
+                    return;
+                }
+            }
+
             if let Err(e) = std::fs::remove_file(&path) {
                 error!(
                     "Error when removing file {} at '{}': {}",
@@ -186,8 +222,10 @@ impl ColumnFile {
             field_id_filter: Arc::new(BloomFilter::default()),
             deleted: AtomicBool::new(false),
             compacting: AtomicBool::new(false),
+            is_remote: false,
             path: path.as_ref().into(),
             tsm_reader_cache: Weak::new(),
+            cold_storage: None,
         }
     }
 
@@ -250,8 +288,12 @@ impl LevelInfo {
         compact_meta: &CompactMeta,
         field_filter: Arc<BloomFilter>,
         tsm_reader_cache: Weak<ShardedCache<String, Arc<TsmReader>>>,
+        cold_storage: Option<Arc<ColdStorage>>,
     ) {
-        let file_path = if compact_meta.is_delta {
+        let file_path = if compact_meta.is_remote {
+            let base_dir = self.storage_opt.cold_dir(&self.database, self.tsf_id);
+            make_tsm_file_name(base_dir, compact_meta.file_id)
+        } else if compact_meta.is_delta {
             let base_dir = self.storage_opt.delta_dir(&self.database, self.tsf_id);
             make_delta_file_name(base_dir, compact_meta.file_id)
         } else {
@@ -263,6 +305,7 @@ impl LevelInfo {
             file_path,
             field_filter,
             tsm_reader_cache,
+            cold_storage,
         )));
         self.tsf_id = compact_meta.tsf_id;
         self.cur_size += compact_meta.file_size;
@@ -333,7 +376,11 @@ impl LevelInfo {
     }
 
     pub fn disk_storage(&self) -> u64 {
-        self.files.iter().map(|f| f.size).sum()
+        self.files
+            .iter()
+            .filter(|f| !f.is_remote)
+            .map(|f| f.size)
+            .sum()
     }
 
     pub fn level(&self) -> u32 {
@@ -352,6 +399,7 @@ pub struct Version {
     pub max_level_ts: i64,
     pub levels_info: [LevelInfo; 5],
     pub tsm_reader_cache: Arc<ShardedCache<String, Arc<TsmReader>>>,
+    pub cold_storage: Option<Arc<ColdStorage>>,
 }
 
 impl Version {
@@ -364,6 +412,7 @@ impl Version {
         levels_info: [LevelInfo; 5],
         max_level_ts: i64,
         tsm_reader_cache: Arc<ShardedCache<String, Arc<TsmReader>>>,
+        cold_storage: Option<Arc<ColdStorage>>,
     ) -> Self {
         Self {
             ts_family_id,
@@ -373,6 +422,7 @@ impl Version {
             max_level_ts,
             levels_info,
             tsm_reader_cache,
+            cold_storage,
         }
     }
 
@@ -418,6 +468,7 @@ impl Version {
                     file,
                     field_filter,
                     weak_tsm_reader_cache.clone(),
+                    self.cold_storage.clone(),
                 );
             }
             new_levels[level.level as usize].update_time_range();
@@ -431,6 +482,7 @@ impl Version {
             max_level_ts: self.max_level_ts,
             levels_info: new_levels,
             tsm_reader_cache: self.tsm_reader_cache.clone(),
+            cold_storage: self.cold_storage.clone(),
         };
         new_version.update_max_level_ts();
         new_version
@@ -492,6 +544,9 @@ impl Version {
     }
 
     pub async fn get_tsm_reader(&self, path: impl AsRef<Path>) -> Result<Arc<TsmReader>> {
+        if let Some(cold_storage) = self.cold_storage.as_ref() {
+            cold_storage.touch(&path);
+        }
         let path = format!("{}", path.as_ref().display());
         let tsm_reader = match self.tsm_reader_cache.get(&path).await {
             Some(val) => val.clone(),
@@ -500,6 +555,10 @@ impl Version {
                 match lock.get(&path) {
                     Some(val) => val.clone(),
                     None => {
+                        if let Some(cold_storage) = self.cold_storage.as_ref() {
+                            // Download the cold file if it's not cached.
+                            cold_storage.fetch(&path).await?;
+                        }
                         let tsm_reader = TsmReader::open(&path).await?;
                         lock.insert(path, Arc::new(tsm_reader)).unwrap().clone()
                     }
@@ -601,6 +660,33 @@ impl SuperVersion {
     }
 }
 
+/// Files of a storage unit at a point in time, they won't be deleted
+/// until the snapshot is dropped.
+#[derive(Debug)]
+pub struct VnodeSnapshot {
+    /// Summary of the storage unit, can be used to re-build it by
+    /// `Engine::apply_vnode_summary()`.
+    pub version_edit: VersionEdit,
+    /// Local path and the path relative to the storage unit directory
+    /// (e.g. `tsm/_000001.tsm`) of tsm, delta, tombstone and index files.
+    pub files: Vec<(PathBuf, String)>,
+    super_version: Arc<SuperVersion>,
+}
+
+impl VnodeSnapshot {
+    pub fn new(
+        version_edit: VersionEdit,
+        files: Vec<(PathBuf, String)>,
+        super_version: Arc<SuperVersion>,
+    ) -> Self {
+        Self {
+            version_edit,
+            files,
+            super_version,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TsfMetrics {
     vnode_disk_storage: U64Gauge,
@@ -658,6 +744,10 @@ pub struct TseriesFamily {
     memory_pool: MemoryPoolRef,
     tsf_metrics: TsfMetrics,
     status: VnodeStatus,
+    /// Held shared by the flush and compaction jobs until their files are applied to the
+    /// version, held exclusively while deleting, so that no file is written without the
+    /// tombstones of a deletion.
+    file_lock: Arc<tokio::sync::RwLock<()>>,
 }
 
 impl TseriesFamily {
@@ -704,6 +794,7 @@ impl TseriesFamily {
             memory_pool,
             tsf_metrics: TsfMetrics::new(register, database.as_str(), tf_id as u64),
             status: VnodeStatus::Running,
+            file_lock: Arc::new(tokio::sync::RwLock::new(())),
         }
     }
 
@@ -949,6 +1040,17 @@ impl TseriesFamily {
         self.database.clone()
     }
 
+    pub fn file_lock(&self) -> Arc<tokio::sync::RwLock<()>> {
+        self.file_lock.clone()
+    }
+
+    /// Path of the file of tombstones of all deletions of the vnode.
+    pub fn series_tombstone_path(&self) -> PathBuf {
+        tsm::make_series_tombstone_file_name(
+            self.storage_opt.tsfamily_dir(&self.database, self.tf_id),
+        )
+    }
+
     pub fn cache(&self) -> &Arc<RwLock<MemCache>> {
         &self.mut_cache
     }
@@ -1098,7 +1200,7 @@ pub mod test_tseries_family {
         ];
         let tsm_reader_cache = Arc::new(ShardedCache::with_capacity(16));
         #[rustfmt::skip]
-            let version = Version::new(1, database, opt.storage.clone(), 1, levels, 3100, tsm_reader_cache);
+            let version = Version::new(1, database, opt.storage.clone(), 1, levels, 3100, tsm_reader_cache, None);
         let mut version_edits = Vec::new();
         let mut ve = VersionEdit::new(1);
         #[rustfmt::skip]
@@ -1113,6 +1215,7 @@ pub mod test_tseries_family {
                 high_seq: 2,
                 low_seq: 2,
                 is_delta: false,
+                is_remote: false,
             },
             3100,
         );
@@ -1194,7 +1297,7 @@ pub mod test_tseries_family {
         ];
         let tsm_reader_cache = Arc::new(ShardedCache::with_capacity(16));
         #[rustfmt::skip]
-            let version = Version::new(1, database, opt.storage.clone(), 1, levels, 3150, tsm_reader_cache);
+            let version = Version::new(1, database, opt.storage.clone(), 1, levels, 3150, tsm_reader_cache, None);
 
         let mut version_edits = Vec::new();
         let mut ve = VersionEdit::new(1);
@@ -1210,6 +1313,7 @@ pub mod test_tseries_family {
                 high_seq: 2,
                 low_seq: 2,
                 is_delta: false,
+                is_remote: false,
             },
             3150,
         );
@@ -1225,6 +1329,7 @@ pub mod test_tseries_family {
                 high_seq: 2,
                 low_seq: 2,
                 is_delta: false,
+                is_remote: false,
             },
             3150,
         );
@@ -1286,6 +1391,7 @@ pub mod test_tseries_family {
             levels,
             max_level_ts,
             tsm_reader_cache,
+            None,
         )
     }
 
@@ -1313,6 +1419,7 @@ pub mod test_tseries_family {
                 LevelInfo::init_levels(database, 0, opt.storage.clone()),
                 0,
                 Arc::new(ShardedCache::with_capacity(1)),
+                None,
             )),
             opt.cache.clone(),
             opt.storage.clone(),
@@ -12,6 +12,7 @@ use crate::tsm::codec::{
     get_bool_codec, get_encoding, get_f64_codec, get_i64_codec, get_str_codec, get_ts_codec,
     get_u64_codec, DataBlockEncoding,
 };
+use crate::tsm::BlockStatistics;
 
 pub trait ByTimeRange {
     fn time_range(&self) -> Option<TimeRange>;
@@ -428,6 +429,31 @@ impl DataBlock {
         blk
     }
 
+    /// Convert values to the given type, see [`crate::memcache::FieldVal::convert`].
+    /// Returns self if the field type is already the given type, returns an error
+    /// instead of dropping the values if the block can't be converted.
+    pub fn convert(self, field_type: ValueType) -> crate::Result<Self> {
+        if self.field_type() == field_type {
+            return Ok(self);
+        }
+        let mut blk = Self::new(self.len(), field_type);
+        for i in 0..self.len() {
+            if let Some(data) = self.get(i) {
+                let data = data
+                    .convert(field_type)
+                    .map_err(|_| crate::Error::Transform {
+                        reason: format!(
+                            "can't convert data block from {} to {}",
+                            self.field_type(),
+                            field_type
+                        ),
+                    })?;
+                blk.insert(data);
+            }
+        }
+        Ok(blk)
+    }
+
     /// Merges one or many `DataBlock`s into some `DataBlock` with fixed length,
     /// sorted by timestamp, if many (timestamp, value) conflict with the same
     /// timestamp, use the last value.
@@ -844,6 +870,7 @@ pub struct EncodedDataBlock {
     pub count: u32,
     pub field_type: ValueType,
     pub time_range: Option<TimeRange>,
+    pub stats: Option<BlockStatistics>,
 }
 
 impl Display for EncodedDataBlock {
@@ -874,6 +901,7 @@ impl EncodedDataBlock {
             count: (end - start) as u32,
             field_type: data_block.field_type(),
             time_range: Some(TimeRange::new(min_ts, max_ts)),
+            stats: BlockStatistics::with_data_block(data_block, start, end),
         })
     }
 
@@ -887,6 +915,7 @@ pub mod test {
 
     use minivec::mini_vec;
     use models::predicate::domain::TimeRange;
+    use models::ValueType;
 
     use crate::memcache::DataType;
     use crate::tsm::codec::DataBlockEncoding;
@@ -917,6 +946,31 @@ pub mod test {
         ]);
     }
 
+    #[test]
+    fn test_data_block_convert() {
+        #[rustfmt::skip]
+        let blk = DataBlock::I64 { ts: vec![1, 2, 3], val: vec![-1, 0, 10], enc: DataBlockEncoding::default() };
+        #[rustfmt::skip]
+        assert_eq!(blk.clone().convert(ValueType::Float).unwrap(), DataBlock::F64 {
+            ts: vec![1, 2, 3], val: vec![-1.0, 0.0, 10.0], enc: DataBlockEncoding::default(),
+        });
+        assert_eq!(blk.clone().convert(ValueType::Integer).unwrap(), blk);
+        // Narrowing conversions are not supported, values are never dropped.
+        assert!(blk.clone().convert(ValueType::Unsigned).is_err());
+        assert!(blk.convert(ValueType::String).is_err());
+
+        #[rustfmt::skip]
+        let blk = DataBlock::U64 { ts: vec![1, 2], val: vec![0, u64::MAX], enc: DataBlockEncoding::default() };
+        #[rustfmt::skip]
+        assert_eq!(blk.convert(ValueType::Float).unwrap(), DataBlock::F64 {
+            ts: vec![1, 2], val: vec![0.0, u64::MAX as f64], enc: DataBlockEncoding::default(),
+        });
+
+        #[rustfmt::skip]
+        let blk = DataBlock::F64 { ts: vec![1, 2], val: vec![1.9, f64::NAN], enc: DataBlockEncoding::default() };
+        assert!(blk.convert(ValueType::Integer).is_err());
+    }
+
     #[test]
     fn test_data_block_exclude_1() {
         #[rustfmt::skip]
@@ -2,13 +2,18 @@ mod block;
 pub mod codec;
 mod index;
 mod reader;
+mod statistics;
 mod tombstone;
 mod writer;
 
 pub use block::*;
 pub use index::*;
 pub use reader::*;
-pub use tombstone::{Tombstone, TsmTombstone};
+pub use statistics::BlockStatistics;
+pub use tombstone::{
+    append_series_tombstones, make_series_tombstone_file_name, read_series_tombstones,
+    SeriesTombstone, Tombstone, TsmTombstone,
+};
 pub use writer::*;
 
 // MAX_BLOCK_VALUES is the maximum number of values a TSM block can store.
@@ -17,9 +22,24 @@ pub(crate) const MAX_BLOCK_VALUES: u32 = 1000;
 const HEADER_SIZE: usize = 5;
 const INDEX_META_SIZE: usize = 11;
 const BLOCK_META_SIZE: usize = 44;
-const BLOOM_FILTER_SIZE: usize = 64;
+const BLOCK_STATS_SIZE: usize = 29;
+pub(crate) const BLOOM_FILTER_SIZE: usize = 64;
 const BLOOM_FILTER_BITS: u64 = 512; // 64 * 8
-const FOOTER_SIZE: usize = BLOOM_FILTER_SIZE + 8; // 72
+pub(crate) const FOOTER_SIZE: usize = BLOOM_FILTER_SIZE + 8; // 72
+
+/// TSM files of version 1 have no block statistics.
+const TSM_VERSION_1: u8 = 1;
+/// TSM files of version 2 store `BlockStatistics` after each block meta.
+const TSM_VERSION_2: u8 = 2;
+
+/// Returns size of a block meta in the index of a TSM file of the version.
+fn block_meta_size(version: u8) -> usize {
+    if version >= TSM_VERSION_2 {
+        BLOCK_META_SIZE + BLOCK_STATS_SIZE
+    } else {
+        BLOCK_META_SIZE
+    }
+}
 
 pub trait BlockReader {
     fn decode(&mut self, block: &BlockMeta) -> crate::error::Result<DataBlock>;
@@ -20,9 +20,9 @@ use crate::tsm::codec::{
 };
 use crate::tsm::tombstone::TsmTombstone;
 use crate::tsm::{
-    get_data_block_meta_unchecked, get_index_meta_unchecked, BlockEntry, BlockMeta, DataBlock,
-    Index, IndexEntry, IndexMeta, BLOCK_META_SIZE, BLOOM_FILTER_SIZE, FOOTER_SIZE, INDEX_META_SIZE,
-    MAX_BLOCK_VALUES,
+    block_meta_size, get_data_block_meta_unchecked, get_index_meta_unchecked, BlockEntry,
+    BlockMeta, DataBlock, Index, IndexEntry, IndexMeta, BLOOM_FILTER_SIZE, FOOTER_SIZE,
+    HEADER_SIZE, INDEX_META_SIZE, MAX_BLOCK_VALUES, TSM_VERSION_1, TSM_VERSION_2,
 };
 
 pub type ReadTsmResult<T, E = ReadTsmError> = std::result::Result<T, E>;
@@ -65,11 +65,12 @@ pub struct IndexFile {
     reader: Arc<AsyncFile>,
     bloom_filter: BloomFilter,
     idx_meta_buf: [u8; INDEX_META_SIZE],
-    blk_meta_buf: [u8; BLOCK_META_SIZE],
+    blk_meta_buf: Vec<u8>,
 
     index_offset: u64,
     pos: u64,
     end_pos: u64,
+    index_field_type: ValueType,
     index_block_idx: usize,
     index_block_count: usize,
 }
@@ -77,6 +78,7 @@ pub struct IndexFile {
 impl IndexFile {
     pub(crate) async fn open(reader: Arc<AsyncFile>) -> ReadTsmResult<Self> {
         let file_len = reader.len();
+        let version = read_version(&reader).await?;
         let mut footer = [0_u8; FOOTER_SIZE];
         reader
             .read_at(file_len - FOOTER_SIZE as u64, &mut footer)
@@ -88,10 +90,11 @@ impl IndexFile {
             reader,
             bloom_filter,
             idx_meta_buf: [0_u8; INDEX_META_SIZE],
-            blk_meta_buf: [0_u8; BLOCK_META_SIZE],
+            blk_meta_buf: vec![0_u8; block_meta_size(version)],
             index_offset,
             pos: index_offset,
             end_pos: file_len - FOOTER_SIZE as u64,
+            index_field_type: ValueType::Unknown,
             index_block_idx: 0,
             index_block_count: 0,
         })
@@ -108,6 +111,7 @@ impl IndexFile {
             .context(ReadIOSnafu)?;
         self.pos += INDEX_META_SIZE as u64;
         let (entry, blk_count) = IndexEntry::decode(&self.idx_meta_buf);
+        self.index_field_type = entry.field_type;
         self.index_block_idx = 0;
         self.index_block_count = blk_count as usize;
 
@@ -123,8 +127,8 @@ impl IndexFile {
             .read_at(self.pos, &mut self.blk_meta_buf[..])
             .await
             .context(ReadIOSnafu)?;
-        self.pos += BLOCK_META_SIZE as u64;
-        let entry = BlockEntry::decode(&self.blk_meta_buf);
+        self.pos += self.blk_meta_buf.len() as u64;
+        let entry = BlockEntry::decode(&self.blk_meta_buf, self.index_field_type);
         self.index_block_idx += 1;
 
         Ok(Some(entry))
@@ -142,8 +146,8 @@ pub async fn print_tsm_statistics(path: impl AsRef<Path>, show_tombstone: bool)
         for blk in idx.block_iterator() {
             buffer.push_str(
                 format!(
-                    "\tBlock | FieldId: {}, MinTime: {}, MaxTime: {}, Count: {}, Offset: {}, Size: {}, ValOffset: {}\n",
-                    blk.field_id(), blk.min_ts(), blk.max_ts(), blk.count(), blk.offset(), blk.size(), blk.val_off()
+                    "\tBlock | FieldId: {}, MinTime: {}, MaxTime: {}, Count: {}, Offset: {}, Size: {}, ValOffset: {}, Statistics: {:?}\n",
+                    blk.field_id(), blk.min_ts(), blk.max_ts(), blk.count(), blk.offset(), blk.size(), blk.val_off(), blk.statistics()
                 ).as_str()
             );
             points_cnt += blk.count() as usize;
@@ -188,16 +192,32 @@ pub async fn print_tsm_statistics(path: impl AsRef<Path>, show_tombstone: bool)
     println!("PointsCount: {}", points_cnt);
 }
 
+/// Reads the version in header of a TSM file.
+async fn read_version(reader: &AsyncFile) -> ReadTsmResult<u8> {
+    let mut header = [0_u8; HEADER_SIZE];
+    reader.read_at(0, &mut header).await.context(ReadIOSnafu)?;
+    let version = header[HEADER_SIZE - 1];
+    if !(TSM_VERSION_1..=TSM_VERSION_2).contains(&version) {
+        return Err(ReadTsmError::Invalid {
+            reason: format!("unsupported TSM file version {}", version),
+        });
+    }
+    Ok(version)
+}
+
 pub async fn load_index(tsm_id: u64, reader: Arc<AsyncFile>) -> ReadTsmResult<Index> {
     let len = reader.len();
-    if len < FOOTER_SIZE as u64 {
+    if len < (HEADER_SIZE + FOOTER_SIZE) as u64 {
         return Err(ReadTsmError::Invalid {
             reason: format!(
-                "TSM file ({}) size less than FOOTER_SIZE({})",
-                tsm_id, FOOTER_SIZE
+                "TSM file ({}) size less than HEADER_SIZE + FOOTER_SIZE({})",
+                tsm_id,
+                HEADER_SIZE + FOOTER_SIZE
             ),
         });
     }
+    let version = read_version(&reader).await?;
+    let blk_meta_size = block_meta_size(version);
     let mut buf = [0u8; FOOTER_SIZE];
 
     // Read index data offset
@@ -225,12 +245,12 @@ pub async fn load_index(tsm_id: u64, reader: Arc<AsyncFile>) -> ReadTsmResult<In
         .context(ReadIOSnafu)?;
 
     // Decode index data
-    let assumed_field_count = (data_len / (INDEX_META_SIZE + BLOCK_META_SIZE)) + 1;
+    let assumed_field_count = (data_len / (INDEX_META_SIZE + blk_meta_size)) + 1;
     let mut field_id_offs: Vec<(FieldId, usize)> = Vec::with_capacity(assumed_field_count);
     let mut pos = 0_usize;
     while pos < data_len {
         field_id_offs.push((decode_be_u64(&data[pos..pos + 8]), pos));
-        pos += INDEX_META_SIZE + BLOCK_META_SIZE * decode_be_u16(&data[pos + 9..pos + 11]) as usize;
+        pos += INDEX_META_SIZE + blk_meta_size * decode_be_u16(&data[pos + 9..pos + 11]) as usize;
     }
 
     // Sort by field id
@@ -239,6 +259,7 @@ pub async fn load_index(tsm_id: u64, reader: Arc<AsyncFile>) -> ReadTsmResult<In
 
     Ok(Index::new(
         tsm_id,
+        version,
         Arc::new(bloom_filter),
         data,
         field_id_offs,
@@ -344,6 +365,8 @@ pub struct BlockMetaIterator {
     block_meta_idx: usize,
     /// The max number of iterations
     block_meta_idx_end: usize,
+    /// Number of `BlockMeta`s skipped by `time_ranges` since last taken
+    pruned_blocks: usize,
 }
 
 impl BlockMetaIterator {
@@ -364,11 +387,24 @@ impl BlockMetaIterator {
             time_ranges: None,
             block_meta_idx_end: block_count as usize,
             block_meta_idx: 0,
+            pruned_blocks: 0,
         }
     }
 
     /// Set iterator start & end position by time range
     pub(crate) fn filter_time_range(&mut self, time_ranges: Arc<TimeRanges>) {
+        self.seek_time_range(time_ranges);
+        // Blocks before the start position and after the end position are skipped
+        self.pruned_blocks +=
+            self.block_count as usize - (self.block_meta_idx_end - self.block_meta_idx);
+    }
+
+    /// Number of `BlockMeta`s skipped by the time ranges since last called.
+    pub fn take_pruned_blocks(&mut self) -> usize {
+        std::mem::take(&mut self.pruned_blocks)
+    }
+
+    fn seek_time_range(&mut self, time_ranges: Arc<TimeRanges>) {
         if time_ranges.is_boundless() {
             self.time_ranges = Some(time_ranges);
             return;
@@ -384,15 +420,16 @@ impl BlockMetaIterator {
             return;
         }
         self.time_ranges = Some(time_ranges);
+        let blk_meta_size = self.index_ref.block_meta_size();
         let base = self.index_offset + INDEX_META_SIZE;
-        let sli = &self.index_ref.data()[base..base + self.block_count as usize * BLOCK_META_SIZE];
+        let sli = &self.index_ref.data()[base..base + self.block_count as usize * blk_meta_size];
         let mut pos = 0_usize;
         let mut idx = 0_usize;
         // Find `idx` of index blocks that time_range.min_ts <= block.max_ts .
         while pos < sli.len() {
             if min_ts > decode_be_i64(&sli[pos + 8..pos + 16]) {
                 // If time_range.min_ts > block.max_ts, go on to check next block.
-                pos += BLOCK_META_SIZE;
+                pos += blk_meta_size;
                 idx += 1;
             } else {
                 // If time_range.min_ts <= block.max_ts, This block may be the start block.
@@ -419,7 +456,7 @@ impl BlockMetaIterator {
             } else {
                 // If time_range.max_ts >= block.max_ts, go on to check next block.
                 self.block_meta_idx_end += 1;
-                pos += BLOCK_META_SIZE;
+                pos += blk_meta_size;
             }
         }
     }
@@ -443,11 +480,12 @@ impl Iterator for BlockMetaIterator {
                     self.field_type,
                 );
                 self.block_meta_idx += 1;
-                self.block_offset += BLOCK_META_SIZE;
+                self.block_offset += self.index_ref.block_meta_size();
                 if time_ranges.overlaps(&(block_meta.min_ts(), block_meta.max_ts()).into()) {
                     ret = Some(block_meta);
                     break;
                 }
+                self.pruned_blocks += 1;
             }
         } else {
             let block_meta = get_data_block_meta_unchecked(
@@ -458,7 +496,7 @@ impl Iterator for BlockMetaIterator {
                 self.field_type,
             );
             self.block_meta_idx += 1;
-            self.block_offset += BLOCK_META_SIZE;
+            self.block_offset += self.index_ref.block_meta_size();
             ret = Some(block_meta);
         }
 
@@ -734,12 +772,17 @@ pub mod tsm_reader_tests {
     use models::{FieldId, Timestamp};
     use snafu::ResultExt;
 
+    use crate::byte_utils::{decode_be_u16, decode_be_u64};
     use crate::error::{self, Error, Result};
     use crate::file_system::file_manager::{self};
     use crate::file_utils;
     use crate::tsm::codec::DataBlockEncoding;
     use crate::tsm::tsm_writer_tests::write_to_tsm;
-    use crate::tsm::{BlockEntry, DataBlock, IndexEntry, IndexFile, TsmReader, TsmTombstone};
+    use crate::tsm::{
+        BlockEntry, BlockStatistics, DataBlock, IndexEntry, IndexFile, TsmReader, TsmTombstone,
+        BLOCK_META_SIZE, BLOCK_STATS_SIZE, BLOOM_FILTER_SIZE, FOOTER_SIZE, HEADER_SIZE,
+        INDEX_META_SIZE, TSM_VERSION_1,
+    };
 
     async fn prepare(dir: impl AsRef<Path>) -> Result<(PathBuf, PathBuf)> {
         if file_manager::try_exists(&dir) {
@@ -848,10 +891,18 @@ pub mod tsm_reader_tests {
         ));
         let mut read_data: HashMap<FieldId, Vec<DataBlock>> = HashMap::new();
         for idx in reader.index_iterator_opt(field_id) {
-            for blk in idx.block_iterator_opt(time_ranges.clone()) {
+            let mut blocks = idx.block_iterator_opt(time_ranges.clone());
+            let mut read_blocks = 0;
+            for blk in blocks.by_ref() {
                 let data_blk = reader.get_data_block(&blk).await.unwrap();
                 read_data.entry(idx.field_id()).or_default().push(data_blk);
+                read_blocks += 1;
             }
+            // Every block is either read or pruned by the time range
+            assert_eq!(
+                read_blocks + blocks.take_pruned_blocks(),
+                idx.block_count() as usize
+            );
         }
         assert_eq!(expected_data.len(), read_data.len());
         for (field_id, data_blks) in read_data.iter() {
@@ -1000,4 +1051,79 @@ pub mod tsm_reader_tests {
         assert_eq!(blk_metas[3].max_ts, 12);
         assert_eq!(blk_metas[3].count, 4);
     }
+
+    /// Rewrite a TSM file to version 1 by removing the block statistics.
+    fn downgrade_to_version_1(path: impl AsRef<Path>) {
+        let data = std::fs::read(&path).unwrap();
+        let footer_beg = data.len() - FOOTER_SIZE;
+        let index_offset = decode_be_u64(&data[footer_beg + BLOOM_FILTER_SIZE..]) as usize;
+        let mut new_data = data[..index_offset].to_vec();
+        new_data[HEADER_SIZE - 1] = TSM_VERSION_1;
+        let mut pos = index_offset;
+        while pos < footer_beg {
+            let blk_count = decode_be_u16(&data[pos + 9..pos + 11]) as usize;
+            new_data.extend_from_slice(&data[pos..pos + INDEX_META_SIZE]);
+            pos += INDEX_META_SIZE;
+            for _ in 0..blk_count {
+                new_data.extend_from_slice(&data[pos..pos + BLOCK_META_SIZE]);
+                pos += BLOCK_META_SIZE + BLOCK_STATS_SIZE;
+            }
+        }
+        new_data.extend_from_slice(&data[footer_beg..]);
+        std::fs::write(&path, new_data).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tsm_reader_version_1() {
+        let (tsm_file, _) = prepare("/tmp/test/tsm_reader/4").await.unwrap();
+        {
+            let reader = TsmReader::open(&tsm_file).await.unwrap();
+            let idx = reader.index_iterator_opt(2).next().unwrap();
+            let stats: Vec<Option<BlockStatistics>> =
+                idx.block_iterator().map(|b| b.statistics()).collect();
+            assert_eq!(stats.len(), 3);
+            assert_eq!(
+                stats[1],
+                Some(BlockStatistics::Unsigned {
+                    min: 105,
+                    max: 108,
+                    sum: 426,
+                    null_count: 0
+                })
+            );
+        }
+
+        downgrade_to_version_1(&tsm_file);
+        let reader = TsmReader::open(&tsm_file).await.unwrap();
+        for idx in reader.index_iterator() {
+            for blk in idx.block_iterator() {
+                assert!(blk.statistics().is_none());
+            }
+        }
+
+        #[rustfmt::skip]
+        let expected_data: HashMap<FieldId, Vec<DataBlock>> = HashMap::from([
+            (1, vec![DataBlock::U64 { ts: vec![1], val: vec![11], enc: DataBlockEncoding::default() }]
+            ),
+            (2, vec![
+                DataBlock::U64 { ts: vec![1, 2, 3, 4], val: vec![101, 102, 103, 104], enc: DataBlockEncoding::default() },
+                DataBlock::U64 { ts: vec![5, 6, 7, 8], val: vec![105, 106, 107, 108], enc: DataBlockEncoding::default() },
+                DataBlock::U64 { ts: vec![9, 10, 11, 12], val: vec![109, 110, 111, 112], enc: DataBlockEncoding::default() },
+            ]),
+            (3, vec![
+                DataBlock::U64 { ts: vec![5], val: vec![105], enc: DataBlockEncoding::default() },
+                DataBlock::U64 { ts: vec![9], val: vec![109], enc: DataBlockEncoding::default() },
+            ]),
+        ]);
+        read_and_check(&reader, &expected_data).await.unwrap();
+
+        #[rustfmt::skip]
+        let expected_data = HashMap::from([
+            (2, vec![
+                DataBlock::U64 { ts: vec![5, 6, 7, 8], val: vec![105, 106, 107, 108], enc: DataBlockEncoding::default() },
+                DataBlock::U64 { ts: vec![9, 10, 11, 12], val: vec![109, 110, 111, 112], enc: DataBlockEncoding::default() },
+            ])
+        ]);
+        read_opt_and_check(&reader, 2, (6, 10), &expected_data).await;
+    }
 }
@@ -0,0 +1,363 @@
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+
+use datafusion::scalar::ScalarValue;
+use models::predicate::domain::Domain;
+use models::ValueType;
+
+use crate::byte_utils::{decode_be_f64, decode_be_i64, decode_be_u32, decode_be_u64};
+use crate::tsm::{DataBlock, BLOCK_STATS_SIZE};
+
+/// Length of the prefixes of string values stored as min and max.
+const STR_PREFIX_LEN: usize = 8;
+
+const FLAG_NONE: u8 = 0;
+const FLAG_PRESENT: u8 = 1;
+
+/// Value statistics of a block, stored after each block meta since TSM version 2.
+///
+/// ```text
+/// +-------------+---------+
+/// | flag        | 1 bytes |
+/// | null_count  | 4 bytes |
+/// | min         | 8 bytes |
+/// | max         | 8 bytes |
+/// | sum         | 8 bytes |
+/// +-------------+---------+
+/// ```
+///
+/// For string blocks, min and max are the first 8 bytes (zero padded) of the min and
+/// max values, and sum is unused. If flag is 0, the block has no statistics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockStatistics {
+    Unsigned {
+        min: u64,
+        max: u64,
+        sum: u64,
+        null_count: u32,
+    },
+    Integer {
+        min: i64,
+        max: i64,
+        sum: i64,
+        null_count: u32,
+    },
+    Float {
+        min: f64,
+        max: f64,
+        sum: f64,
+        null_count: u32,
+    },
+    String {
+        min: [u8; STR_PREFIX_LEN],
+        max: [u8; STR_PREFIX_LEN],
+        null_count: u32,
+    },
+}
+
+impl BlockStatistics {
+    /// Computes statistics of values in `data_block[start..end]`.
+    ///
+    /// Returns None if the range is empty, the block is a boolean block,
+    /// or it's a float block containing NaN.
+    pub fn with_data_block(data_block: &DataBlock, start: usize, end: usize) -> Option<Self> {
+        if start >= end || end > data_block.len() {
+            return None;
+        }
+        // Values in a DataBlock are never null.
+        let null_count = 0_u32;
+        match data_block {
+            DataBlock::U64 { val, .. } => {
+                let val = &val[start..end];
+                Some(Self::Unsigned {
+                    min: *val.iter().min()?,
+                    max: *val.iter().max()?,
+                    sum: val.iter().fold(0_u64, |s, v| s.wrapping_add(*v)),
+                    null_count,
+                })
+            }
+            DataBlock::I64 { val, .. } => {
+                let val = &val[start..end];
+                Some(Self::Integer {
+                    min: *val.iter().min()?,
+                    max: *val.iter().max()?,
+                    sum: val.iter().fold(0_i64, |s, v| s.wrapping_add(*v)),
+                    null_count,
+                })
+            }
+            DataBlock::F64 { val, .. } => {
+                let val = &val[start..end];
+                if val.iter().any(|v| v.is_nan()) {
+                    return None;
+                }
+                Some(Self::Float {
+                    min: val.iter().copied().fold(f64::INFINITY, f64::min),
+                    max: val.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                    sum: val.iter().sum(),
+                    null_count,
+                })
+            }
+            DataBlock::Str { val, .. } => {
+                let val = &val[start..end];
+                let min = val.iter().min()?;
+                let max = val.iter().max()?;
+                Some(Self::String {
+                    min: str_prefix(min),
+                    max: str_prefix(max),
+                    null_count,
+                })
+            }
+            DataBlock::Bool { .. } => None,
+        }
+    }
+
+    pub fn null_count(&self) -> u32 {
+        match self {
+            Self::Unsigned { null_count, .. }
+            | Self::Integer { null_count, .. }
+            | Self::Float { null_count, .. }
+            | Self::String { null_count, .. } => *null_count,
+        }
+    }
+
+    /// Encodes optional statistics into `buf`, which is at least `BLOCK_STATS_SIZE` long.
+    pub fn encode(stats: Option<&Self>, buf: &mut [u8]) {
+        assert!(buf.len() >= BLOCK_STATS_SIZE);
+        buf[..BLOCK_STATS_SIZE].fill(0);
+        let stats = match stats {
+            Some(s) => s,
+            None => {
+                buf[0] = FLAG_NONE;
+                return;
+            }
+        };
+        buf[0] = FLAG_PRESENT;
+        buf[1..5].copy_from_slice(&stats.null_count().to_be_bytes()[..]);
+        match stats {
+            Self::Unsigned { min, max, sum, .. } => {
+                buf[5..13].copy_from_slice(&min.to_be_bytes()[..]);
+                buf[13..21].copy_from_slice(&max.to_be_bytes()[..]);
+                buf[21..29].copy_from_slice(&sum.to_be_bytes()[..]);
+            }
+            Self::Integer { min, max, sum, .. } => {
+                buf[5..13].copy_from_slice(&min.to_be_bytes()[..]);
+                buf[13..21].copy_from_slice(&max.to_be_bytes()[..]);
+                buf[21..29].copy_from_slice(&sum.to_be_bytes()[..]);
+            }
+            Self::Float { min, max, sum, .. } => {
+                buf[5..13].copy_from_slice(&min.to_be_bytes()[..]);
+                buf[13..21].copy_from_slice(&max.to_be_bytes()[..]);
+                buf[21..29].copy_from_slice(&sum.to_be_bytes()[..]);
+            }
+            Self::String { min, max, .. } => {
+                buf[5..13].copy_from_slice(&min[..]);
+                buf[13..21].copy_from_slice(&max[..]);
+            }
+        }
+    }
+
+    /// Decodes statistics of a block of `field_type`, returns None if there is no statistics.
+    pub fn decode(data: &[u8], field_type: ValueType) -> Option<Self> {
+        assert!(data.len() >= BLOCK_STATS_SIZE);
+        if data[0] != FLAG_PRESENT {
+            return None;
+        }
+        let null_count = decode_be_u32(&data[1..5]);
+        match field_type {
+            ValueType::Unsigned => Some(Self::Unsigned {
+                min: decode_be_u64(&data[5..13]),
+                max: decode_be_u64(&data[13..21]),
+                sum: decode_be_u64(&data[21..29]),
+                null_count,
+            }),
+            ValueType::Integer => Some(Self::Integer {
+                min: decode_be_i64(&data[5..13]),
+                max: decode_be_i64(&data[13..21]),
+                sum: decode_be_i64(&data[21..29]),
+                null_count,
+            }),
+            ValueType::Float => Some(Self::Float {
+                min: decode_be_f64(&data[5..13]),
+                max: decode_be_f64(&data[13..21]),
+                sum: decode_be_f64(&data[21..29]),
+                null_count,
+            }),
+            ValueType::String => {
+                let mut min = [0_u8; STR_PREFIX_LEN];
+                let mut max = [0_u8; STR_PREFIX_LEN];
+                min.copy_from_slice(&data[5..13]);
+                max.copy_from_slice(&data[13..21]);
+                Some(Self::String {
+                    min,
+                    max,
+                    null_count,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns false if no value in the block can be contained in the domain,
+    /// so that the block can be skipped.
+    pub fn may_match(&self, domain: &Domain) -> bool {
+        match domain {
+            Domain::Range(range_set) => range_set
+                .low_indexed_ranges()
+                .into_iter()
+                .any(|(_, range)| self.may_overlap(range)),
+            Domain::Equtable(val_set) if val_set.is_white_list() => {
+                val_set.entries().into_iter().any(|entry| {
+                    let v = entry.value();
+                    self.may_overlap(&(Bound::Included(v), Bound::Included(v)))
+                })
+            }
+            _ => true,
+        }
+    }
+
+    fn may_overlap(&self, range: &impl RangeBounds<ScalarValue>) -> bool {
+        let below_low = match range.start_bound() {
+            Bound::Included(v) => self.compare_max(v) == Some(Ordering::Less),
+            Bound::Excluded(v) => {
+                matches!(self.compare_max(v), Some(Ordering::Less | Ordering::Equal))
+            }
+            Bound::Unbounded => false,
+        };
+        let above_high = match range.end_bound() {
+            Bound::Included(v) => self.compare_min(v) == Some(Ordering::Greater),
+            Bound::Excluded(v) => matches!(
+                self.compare_min(v),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+            Bound::Unbounded => false,
+        };
+        !below_low && !above_high
+    }
+
+    /// Compares min value with `v`, returns None if the result is unknown.
+    fn compare_min(&self, v: &ScalarValue) -> Option<Ordering> {
+        match (self, v) {
+            (Self::Unsigned { min, .. }, ScalarValue::UInt64(Some(v))) => Some(min.cmp(v)),
+            (Self::Integer { min, .. }, ScalarValue::Int64(Some(v))) => Some(min.cmp(v)),
+            (Self::Float { min, .. }, ScalarValue::Float64(Some(v))) => min.partial_cmp(v),
+            (Self::String { min, .. }, ScalarValue::Utf8(Some(v))) => {
+                compare_str_prefix(min, v.as_bytes())
+            }
+            _ => None,
+        }
+    }
+
+    /// Compares max value with `v`, returns None if the result is unknown.
+    fn compare_max(&self, v: &ScalarValue) -> Option<Ordering> {
+        match (self, v) {
+            (Self::Unsigned { max, .. }, ScalarValue::UInt64(Some(v))) => Some(max.cmp(v)),
+            (Self::Integer { max, .. }, ScalarValue::Int64(Some(v))) => Some(max.cmp(v)),
+            (Self::Float { max, .. }, ScalarValue::Float64(Some(v))) => max.partial_cmp(v),
+            (Self::String { max, .. }, ScalarValue::Utf8(Some(v))) => {
+                compare_str_prefix(max, v.as_bytes())
+            }
+            _ => None,
+        }
+    }
+}
+
+fn str_prefix(v: &[u8]) -> [u8; STR_PREFIX_LEN] {
+    let mut prefix = [0_u8; STR_PREFIX_LEN];
+    let len = v.len().min(STR_PREFIX_LEN);
+    prefix[..len].copy_from_slice(&v[..len]);
+    prefix
+}
+
+/// Prefixes keep the order of strings (`a <= b` means `prefix(a) <= prefix(b)`),
+/// but equal prefixes say nothing about the order of strings.
+fn compare_str_prefix(prefix: &[u8; STR_PREFIX_LEN], v: &[u8]) -> Option<Ordering> {
+    match prefix.cmp(&str_prefix(v)) {
+        Ordering::Equal => None,
+        ord => Some(ord),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::arrow::datatypes::DataType as ArrowDataType;
+    use datafusion::scalar::ScalarValue;
+    use minivec::MiniVec;
+    use models::predicate::domain::{Domain, Range};
+    use models::ValueType;
+
+    use super::BlockStatistics;
+    use crate::tsm::codec::DataBlockEncoding;
+    use crate::tsm::{DataBlock, BLOCK_STATS_SIZE};
+
+    #[test]
+    fn test_statistics_encode_decode() {
+        let blk = DataBlock::I64 {
+            ts: vec![1, 2, 3, 4],
+            val: vec![5, -3, 8, 100],
+            enc: DataBlockEncoding::default(),
+        };
+        let stats = BlockStatistics::with_data_block(&blk, 0, 4).unwrap();
+        assert_eq!(
+            stats,
+            BlockStatistics::Integer {
+                min: -3,
+                max: 100,
+                sum: 110,
+                null_count: 0
+            }
+        );
+        let mut buf = [0_u8; BLOCK_STATS_SIZE];
+        BlockStatistics::encode(Some(&stats), &mut buf);
+        assert_eq!(
+            BlockStatistics::decode(&buf, ValueType::Integer),
+            Some(stats)
+        );
+        BlockStatistics::encode(None, &mut buf);
+        assert_eq!(BlockStatistics::decode(&buf, ValueType::Integer), None);
+
+        let blk = DataBlock::F64 {
+            ts: vec![1, 2],
+            val: vec![1.0, f64::NAN],
+            enc: DataBlockEncoding::default(),
+        };
+        assert_eq!(BlockStatistics::with_data_block(&blk, 0, 2), None);
+    }
+
+    #[test]
+    fn test_statistics_may_match() {
+        let dt = ArrowDataType::Int64;
+        let stats = BlockStatistics::Integer {
+            min: -3,
+            max: 100,
+            sum: 110,
+            null_count: 0,
+        };
+        let gt = |v: i64| Domain::of_ranges(&[Range::gt(&dt, &ScalarValue::Int64(Some(v)))]);
+        let lt = |v: i64| Domain::of_ranges(&[Range::lt(&dt, &ScalarValue::Int64(Some(v)))]);
+        assert!(stats.may_match(&gt(99).unwrap()));
+        assert!(!stats.may_match(&gt(100).unwrap()));
+        assert!(stats.may_match(&lt(-2).unwrap()));
+        assert!(!stats.may_match(&lt(-3).unwrap()));
+        assert!(stats.may_match(&Domain::All));
+
+        let blk = DataBlock::Str {
+            ts: vec![1, 2],
+            val: vec![
+                MiniVec::from("beijing".as_bytes()),
+                MiniVec::from("shanghai_pudong".as_bytes()),
+            ],
+            enc: DataBlockEncoding::default(),
+        };
+        let stats = BlockStatistics::with_data_block(&blk, 0, 2).unwrap();
+        let dt = ArrowDataType::Utf8;
+        let eq = |v: &str| {
+            Domain::of_ranges(&[Range::eq(&dt, &ScalarValue::Utf8(Some(v.to_string())))]).unwrap()
+        };
+        assert!(stats.may_match(&eq("beijing")));
+        assert!(stats.may_match(&eq("shanghai_pudong")));
+        // Values with the same prefix as max may be greater than it.
+        assert!(stats.may_match(&eq("shanghai_zzz")));
+        assert!(!stats.may_match(&eq("alpha")));
+        assert!(!stats.may_match(&eq("tianjin")));
+    }
+}
@@ -10,6 +10,21 @@
 //! |  field_id  | min_timestamp | max_timestamp |
 //! +------------+---------------+---------------+
 //! ```
+//!
+//! # Series tombstone file
+//!
+//! Deletions of a vnode are also appended to the series tombstone file of the vnode with
+//! the WAL sequence of the deletion, they are kept after the data is compacted, so that
+//! replicas missing the deletion can be repaired.
+//!
+//! ## Record Data
+//! ```text
+//! +------------+---------------+---------------+-------------+
+//! | 0: 8 bytes | 8: 8 bytes    | 16: 8 bytes   | 24: 8 bytes |
+//! +------------+---------------+---------------+-------------+
+//! |  field_id  | min_timestamp | max_timestamp |  sequence   |
+//! +------------+---------------+---------------+-------------+
+//! ```
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -27,6 +42,8 @@ const TOMBSTONE_FILE_SUFFIX: &str = ".tombstone";
 const FOOTER_MAGIC_NUMBER: u32 = u32::from_be_bytes([b'r', b'o', b'm', b'b']);
 const FOOTER_MAGIC_NUMBER_LEN: usize = 4;
 const ENTRY_LEN: usize = 24; // 8 + 8 + 8
+const SERIES_TOMBSTONE_FILE_NAME: &str = "series.tombstone";
+const SERIES_ENTRY_LEN: usize = 32; // 8 + 8 + 8 + 8
 
 #[derive(Debug, Clone, Copy)]
 pub struct Tombstone {
@@ -204,13 +221,98 @@ impl TsmTombstone {
     }
 }
 
+/// A deletion of a field in a time range, with the WAL sequence of the deletion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SeriesTombstone {
+    pub field_id: FieldId,
+    pub time_range: TimeRange,
+    pub seq: u64,
+}
+
+/// Make a path for the series tombstone file of a vnode by the directory of the vnode.
+pub fn make_series_tombstone_file_name(path: impl AsRef<Path>) -> PathBuf {
+    path.as_ref().join(SERIES_TOMBSTONE_FILE_NAME)
+}
+
+/// Appends the deletion of the fields in the time range to the series tombstone file.
+pub async fn append_series_tombstones(
+    path: impl AsRef<Path>,
+    field_ids: &[FieldId],
+    time_range: &TimeRange,
+    seq: u64,
+) -> Result<()> {
+    let mut writer = record_file::Writer::open(path, RecordDataType::Tombstone).await?;
+    let mut write_buf = [0_u8; SERIES_ENTRY_LEN];
+    for field_id in field_ids.iter() {
+        write_buf[0..8].copy_from_slice((*field_id).to_be_bytes().as_slice());
+        write_buf[8..16].copy_from_slice(time_range.min_ts.to_be_bytes().as_slice());
+        write_buf[16..24].copy_from_slice(time_range.max_ts.to_be_bytes().as_slice());
+        write_buf[24..32].copy_from_slice(seq.to_be_bytes().as_slice());
+        writer
+            .write_record(
+                RecordDataVersion::V1 as u8,
+                RecordDataType::Tombstone as u8,
+                &[&write_buf],
+            )
+            .await?;
+    }
+    writer.close().await
+}
+
+/// Reads the series tombstones of the fields from the series tombstone file, tombstones
+/// of a deletion replayed from WAL more than once are returned once.
+pub async fn read_series_tombstones(
+    path: impl AsRef<Path>,
+    mut field_filter: impl FnMut(FieldId) -> bool,
+) -> Result<Vec<SeriesTombstone>> {
+    let path = path.as_ref();
+    if !file_manager::try_exists(path) {
+        return Ok(vec![]);
+    }
+    let mut reader = record_file::Reader::open(path).await?;
+    let mut tombstones = Vec::new();
+    loop {
+        let data = match reader.read_record().await {
+            Ok(r) => r.data,
+            Err(Error::Eof) => break,
+            Err(e) => return Err(e),
+        };
+        if data.len() < SERIES_ENTRY_LEN {
+            error!(
+                "Error reading series tombstone: block length too small: {}",
+                data.len()
+            );
+            break;
+        }
+        let field_id = byte_utils::decode_be_u64(&data[0..8]);
+        if !field_filter(field_id) {
+            continue;
+        }
+        tombstones.push(SeriesTombstone {
+            field_id,
+            time_range: TimeRange::new(
+                byte_utils::decode_be_i64(&data[8..16]),
+                byte_utils::decode_be_i64(&data[16..24]),
+            ),
+            seq: byte_utils::decode_be_u64(&data[24..32]),
+        });
+    }
+    tombstones.sort_by_key(|t| (t.field_id, t.seq, t.time_range.min_ts, t.time_range.max_ts));
+    tombstones.dedup();
+
+    Ok(tombstones)
+}
+
 #[cfg(test)]
 mod test {
     use std::path::PathBuf;
 
     use models::predicate::domain::TimeRange;
 
-    use super::TsmTombstone;
+    use super::{
+        append_series_tombstones, make_series_tombstone_file_name, read_series_tombstones,
+        SeriesTombstone, TsmTombstone,
+    };
     use crate::file_system::file_manager;
 
     #[tokio::test]
@@ -325,4 +427,42 @@ mod test {
             }
         ));
     }
+
+    #[tokio::test]
+    async fn test_series_tombstones() {
+        let dir = PathBuf::from("/tmp/test/tombstone/series".to_string());
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = make_series_tombstone_file_name(&dir);
+
+        append_series_tombstones(&path, &[1, 2], &TimeRange::new(1, 100), 3)
+            .await
+            .unwrap();
+        append_series_tombstones(&path, &[1], &TimeRange::new(5, 10), 7)
+            .await
+            .unwrap();
+        // Replayed from WAL.
+        append_series_tombstones(&path, &[1], &TimeRange::new(5, 10), 7)
+            .await
+            .unwrap();
+
+        let tombstones = read_series_tombstones(&path, |field_id| field_id == 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            tombstones,
+            vec![
+                SeriesTombstone {
+                    field_id: 1,
+                    time_range: TimeRange::new(1, 100),
+                    seq: 3,
+                },
+                SeriesTombstone {
+                    field_id: 1,
+                    time_range: TimeRange::new(5, 10),
+                    seq: 7,
+                },
+            ]
+        );
+    }
 }
@@ -13,8 +13,8 @@ use crate::file_system::file::IFile;
 use crate::file_system::file_manager;
 use crate::file_utils;
 use crate::tsm::{
-    BlockEntry, BlockMeta, DataBlock, IndexEntry, BLOCK_META_SIZE, BLOOM_FILTER_BITS,
-    INDEX_META_SIZE,
+    block_meta_size, BlockEntry, BlockMeta, DataBlock, IndexEntry, BLOOM_FILTER_BITS,
+    INDEX_META_SIZE, TSM_VERSION_2,
 };
 
 // A TSM file is composed for four sections: header, blocks, index and the footer.
@@ -40,12 +40,14 @@ use crate::tsm::{
 // │ 4 bytes │ N bytes │ 4 bytes │ N bytes │
 // └─────────┴─────────┴─────────┴─────────┴
 //
-// ┌───────────────────────────────────────────────────────────────────────────────┐
-// │                               Index                                           │
-// ├─────────┬──────┬───────┬─────────┬─────────┬────────┬────────┬────────┬───────┤
-// │ fieldId │ Type │ Count │Min Time │Max Time │ count  │ Offset │  Size  │Valoff │
-// │ 8 bytes │1 byte│2 bytes│ 8 bytes │ 8 bytes │4 bytes │8 bytes │8 bytes │8 bytes│
-// └─────────┴──────┴───────┴─────────┴─────────┴────────┴────────┴────────┴───────┘
+// ┌───────────────────────────────────────────────────────────────────────────────┬─────────┐
+// │                               Index                                           │         │
+// ├─────────┬──────┬───────┬─────────┬─────────┬────────┬────────┬────────┬───────┼─────────┤
+// │ fieldId │ Type │ Count │Min Time │Max Time │ count  │ Offset │  Size  │Valoff │  Stats  │
+// │ 8 bytes │1 byte│2 bytes│ 8 bytes │ 8 bytes │4 bytes │8 bytes │8 bytes │8 bytes│29 bytes │
+// └─────────┴──────┴───────┴─────────┴─────────┴────────┴────────┴────────┴───────┴─────────┘
+//
+// Stats (see `BlockStatistics`) are written since version 2, files of version 1 have no stats.
 //
 // ┌─────────────────────────┐
 // │ Footer                  │
@@ -56,7 +58,7 @@ use crate::tsm::{
 
 const HEADER_LEN: u64 = 5;
 const TSM_MAGIC: [u8; 4] = 0x01346613_u32.to_be_bytes();
-const VERSION: [u8; 1] = [1];
+const VERSION: [u8; 1] = [TSM_VERSION_2];
 
 pub type WriteTsmResult<T, E = WriteTsmError> = std::result::Result<T, E>;
 
@@ -119,7 +121,8 @@ impl IndexBuf {
     pub async fn write_to(&self, writer: &mut FileCursor) -> WriteTsmResult<usize> {
         let mut size = 0_usize;
 
-        let mut buf = vec![0_u8; BLOCK_META_SIZE];
+        let blk_meta_size = block_meta_size(TSM_VERSION_2);
+        let mut buf = vec![0_u8; blk_meta_size];
         for (_, idx) in self.buf.iter() {
             idx.encode(&mut buf[..INDEX_META_SIZE])?;
             writer
@@ -130,7 +133,7 @@ impl IndexBuf {
             for blk in idx.blocks.iter() {
                 blk.encode(&mut buf);
                 writer.write(&buf[..]).await.context(WriteIOSnafu)?;
-                size += BLOCK_META_SIZE;
+                size += blk_meta_size;
             }
         }
 
@@ -504,6 +507,7 @@ async fn write_encoded_block_to(
             size: size as u64,
             // Encoded timestamps block need a 4-byte crc checksum together.
             val_offset: offset + block.ts.len() as u64 + 4,
+            stats: block.stats,
         },
     );
 
@@ -24,6 +24,49 @@
 //! +------------+------------+-------------+---------------+-----------------+---------------+---------+
 //! |    type    |  sequence  | tenant_size | database_size |  tenant         |  database     | table   |
 //! +------------+------------+-------------+---------------+-----------------+---------------+---------+
+//!
+//! # type = DeleteSeries
+//! +------------+------------+------------+-------------+---------------+--------------+--------------+
+//! | 0: 1 byte  | 1: 8 bytes | 9: 4 bytes | 13: 8 bytes | 21: 4 bytes   | 25: 4 bytes  | 29: 4 bytes  |
+//! +------------+------------+------------+-------------+---------------+--------------+--------------+
+//! |    type    |  sequence  |  vnode_id  | tenant_size | database_size | series_count | column_count |
+//! +------------+------------+------------+-------------+---------------+--------------+--------------+
+//! +-----------------+---------------+------------------+------------------+---------------------+
+//! | 33: tenant_size | database_size | series_count * 4 | column_count * 4 | n * 16 bytes        |
+//! +-----------------+---------------+------------------+------------------+---------------------+
+//! |  tenant         |  database     |  series_ids      |  column_ids      | (min_ts, max_ts)... |
+//! +-----------------+---------------+------------------+------------------+---------------------+
+//!
+//! # type = ChangeColumn
+//! +------------+------------+-------------+---------------+-------------+-------------+
+//! | 0: 1 byte  | 1: 8 bytes | 9: 8 bytes  | 17: 4 bytes   | 21: 4 bytes | 25: 4 bytes |
+//! +------------+------------+-------------+---------------+-------------+-------------+
+//! |    type    |  sequence  | tenant_size | database_size | table_size  | column_size |
+//! +------------+------------+-------------+---------------+-------------+-------------+
+//! +-----------------+---------------+------------+-------------+------------+
+//! | 29: tenant_size | database_size | table_size | column_size | n bytes    |
+//! +-----------------+---------------+------------+-------------+------------+
+//! |  tenant         |  database     |  table     |  column     | new_column |
+//! +-----------------+---------------+------------+-------------+------------+
+//!
+//! # type = DropColumn
+//! +------------+------------+-------------+---------------+-------------+
+//! | 0: 1 byte  | 1: 8 bytes | 9: 8 bytes  | 17: 4 bytes   | 21: 4 bytes |
+//! +------------+------------+-------------+---------------+-------------+
+//! |    type    |  sequence  | tenant_size | database_size |  column_id  |
+//! +------------+------------+-------------+---------------+-------------+
+//! +-----------------+---------------+---------+
+//! | 25: tenant_size | database_size | n bytes |
+//! +-----------------+---------------+---------+
+//! |  tenant         |  database     | table   |
+//! +-----------------+---------------+---------+
+//!
+//! # compressed entry of any type, the highest bit of type is set
+//! +-------------+------------+-------------+------------------------------+
+//! | 0: 1 byte   | 1: 8 bytes | 9: 1 byte   | 10: n bytes                  |
+//! +-------------+------------+-------------+------------------------------+
+//! | type | 0x80 |  sequence  | compression | compressed data after header |
+//! +-------------+------------+-------------+------------------------------+
 //! ```
 //!
 //! ## Footer
@@ -46,7 +89,9 @@ use std::sync::Arc;
 use minivec::MiniVec;
 use models::codec::Encoding;
 use models::meta_data::VnodeId;
+use models::predicate::domain::TimeRange;
 use models::schema::Precision;
+use models::{ColumnId, SeriesId};
 use snafu::ResultExt;
 use tokio::sync::oneshot;
 
@@ -56,20 +101,31 @@ use crate::file_system::file_manager;
 use crate::kv_option::WalOptions;
 use crate::tsm::codec::{get_str_codec, StringCodec};
 pub use crate::wal::reader::{
-    print_wal_statistics, DeleteTableBlock, DeleteVnodeBlock, WalEntry, WriteBlock,
+    print_wal_statistics, ChangeColumnBlock, DeleteSeriesBlock, DeleteTableBlock, DeleteVnodeBlock,
+    DropColumnBlock, WalEntry, WriteBlock,
 };
-use crate::{error, file_utils, Result};
+use crate::{error, file_utils, Error, Result};
 
 const ENTRY_TYPE_LEN: usize = 1;
 const ENTRY_SEQUENCE_LEN: usize = 8;
 /// 9 = type(1) + sequence(8)
 const ENTRY_HEADER_LEN: usize = 9;
+/// Flag bit in the type of a compressed entry.
+const ENTRY_COMPRESSED_FLAG: u8 = 0b1000_0000;
+const ENTRY_COMPRESSION_LEN: usize = 1;
 
 const ENTRY_VNODE_ID_LEN: usize = 4;
 const ENTRY_PRECISION_LEN: usize = 1;
 const ENTRY_TENANT_SIZE_LEN: usize = 8;
 const ENTRY_DATABASE_SIZE_LEN: usize = 4;
 const ENTRY_TABLE_SIZE_LEN: usize = 4;
+const ENTRY_COLUMN_SIZE_LEN: usize = 4;
+const ENTRY_SERIES_COUNT_LEN: usize = 4;
+const ENTRY_COLUMN_COUNT_LEN: usize = 4;
+const ENTRY_SERIES_ID_LEN: usize = 4;
+const ENTRY_COLUMN_ID_LEN: usize = 4;
+/// 16 = min_ts(8) + max_ts(8)
+const ENTRY_TIME_RANGE_LEN: usize = 16;
 
 const FOOTER_MAGIC_NUMBER: u32 = u32::from_be_bytes([b'w', b'a', b'l', b'o']);
 const FOOTER_MAGIC_NUMBER_LEN: usize = 4;
@@ -86,6 +142,9 @@ pub enum WalEntryType {
     Write = 1,
     DeleteVnode = 11,
     DeleteTable = 21,
+    DeleteSeries = 31,
+    ChangeColumn = 41,
+    DropColumn = 51,
     Unknown = 127,
 }
 
@@ -95,6 +154,9 @@ impl From<u8> for WalEntryType {
             1 => WalEntryType::Write,
             11 => WalEntryType::DeleteVnode,
             21 => WalEntryType::DeleteTable,
+            31 => WalEntryType::DeleteSeries,
+            41 => WalEntryType::ChangeColumn,
+            51 => WalEntryType::DropColumn,
             _ => WalEntryType::Unknown,
         }
     }
@@ -106,11 +168,96 @@ impl Display for WalEntryType {
             WalEntryType::Write => write!(f, "write"),
             WalEntryType::DeleteVnode => write!(f, "delete_vnode"),
             WalEntryType::DeleteTable => write!(f, "delete_table"),
+            WalEntryType::DeleteSeries => write!(f, "delete_series"),
+            WalEntryType::ChangeColumn => write!(f, "change_column"),
+            WalEntryType::DropColumn => write!(f, "drop_column"),
             WalEntryType::Unknown => write!(f, "unknown"),
         }
     }
 }
 
+/// Compression algorithm of WAL entries.
+#[repr(u8)]
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum WalCompression {
+    None = 0,
+    Zstd = 1,
+    Snappy = 2,
+}
+
+impl From<&str> for WalCompression {
+    fn from(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "zstd" => WalCompression::Zstd,
+            "snappy" => WalCompression::Snappy,
+            _ => WalCompression::None,
+        }
+    }
+}
+
+impl WalCompression {
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(WalCompression::None),
+            1 => Some(WalCompression::Zstd),
+            2 => Some(WalCompression::Snappy),
+            _ => None,
+        }
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            WalCompression::None => Ok(data.to_vec()),
+            WalCompression::Zstd => zstd::stream::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(|e| Error::Encode {
+                    source: Box::new(e),
+                }),
+            WalCompression::Snappy => {
+                snap::raw::Encoder::new()
+                    .compress_vec(data)
+                    .map_err(|e| Error::Encode {
+                        source: Box::new(e),
+                    })
+            }
+        }
+    }
+
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            WalCompression::None => Ok(data.to_vec()),
+            WalCompression::Zstd => zstd::stream::decode_all(data).map_err(|e| Error::Decode {
+                source: Box::new(e),
+            }),
+            WalCompression::Snappy => {
+                snap::raw::Decoder::new()
+                    .decompress_vec(data)
+                    .map_err(|e| Error::Decode {
+                        source: Box::new(e),
+                    })
+            }
+        }
+    }
+}
+
+/// Decompresses data of the entry if it's compressed, returns the entry with
+/// the compressed flag cleared, or the original data if it's not compressed.
+pub fn decompress_entry(data: Vec<u8>) -> Result<Vec<u8>> {
+    if data.len() < ENTRY_HEADER_LEN + ENTRY_COMPRESSION_LEN || data[0] & ENTRY_COMPRESSED_FLAG == 0
+    {
+        return Ok(data);
+    }
+    let compression =
+        WalCompression::from_u8(data[ENTRY_HEADER_LEN]).ok_or_else(|| Error::InvalidParam {
+            reason: format!("unknown WAL compression: {}", data[ENTRY_HEADER_LEN]),
+        })?;
+    let entry_data = compression.decompress(&data[ENTRY_HEADER_LEN + ENTRY_COMPRESSION_LEN..])?;
+    let mut entry = Vec::with_capacity(ENTRY_HEADER_LEN + entry_data.len());
+    entry.push(data[0] & !ENTRY_COMPRESSED_FLAG);
+    entry.extend_from_slice(&data[ENTRY_TYPE_LEN..ENTRY_HEADER_LEN]);
+    entry.extend_from_slice(&entry_data);
+    Ok(entry)
+}
+
 pub enum WalTask {
     Write {
         tenant: String,
@@ -131,6 +278,31 @@ pub enum WalTask {
         table: String,
         cb: WriteResultSender,
     },
+    DeleteSeries {
+        tenant: String,
+        database: String,
+        vnode_id: VnodeId,
+        series_ids: Vec<SeriesId>,
+        column_ids: Vec<ColumnId>,
+        time_ranges: Vec<TimeRange>,
+        cb: WriteResultSender,
+    },
+    ChangeColumn {
+        tenant: String,
+        database: String,
+        table: String,
+        column_name: String,
+        /// Encoded `TableColumn`.
+        new_column: Vec<u8>,
+        cb: WriteResultSender,
+    },
+    DropColumn {
+        tenant: String,
+        database: String,
+        table: String,
+        column_id: ColumnId,
+        cb: WriteResultSender,
+    },
 }
 
 impl WalTask {
@@ -187,11 +359,77 @@ impl WalTask {
         )
     }
 
+    pub fn new_delete_series(
+        tenant: String,
+        database: String,
+        vnode_id: VnodeId,
+        series_ids: Vec<SeriesId>,
+        column_ids: Vec<ColumnId>,
+        time_ranges: Vec<TimeRange>,
+    ) -> (WalTask, WriteResultReceiver) {
+        let (cb, rx) = oneshot::channel();
+        (
+            WalTask::DeleteSeries {
+                tenant,
+                database,
+                vnode_id,
+                series_ids,
+                column_ids,
+                time_ranges,
+                cb,
+            },
+            rx,
+        )
+    }
+
+    pub fn new_change_column(
+        tenant: String,
+        database: String,
+        table: String,
+        column_name: String,
+        new_column: Vec<u8>,
+    ) -> (WalTask, WriteResultReceiver) {
+        let (cb, rx) = oneshot::channel();
+        (
+            WalTask::ChangeColumn {
+                tenant,
+                database,
+                table,
+                column_name,
+                new_column,
+                cb,
+            },
+            rx,
+        )
+    }
+
+    pub fn new_drop_column(
+        tenant: String,
+        database: String,
+        table: String,
+        column_id: ColumnId,
+    ) -> (WalTask, WriteResultReceiver) {
+        let (cb, rx) = oneshot::channel();
+        (
+            WalTask::DropColumn {
+                tenant,
+                database,
+                table,
+                column_id,
+                cb,
+            },
+            rx,
+        )
+    }
+
     pub fn wal_entry_type(&self) -> WalEntryType {
         match self {
             WalTask::Write { .. } => WalEntryType::Write,
             WalTask::DeleteVnode { .. } => WalEntryType::DeleteVnode,
             WalTask::DeleteTable { .. } => WalEntryType::DeleteTable,
+            WalTask::DeleteSeries { .. } => WalEntryType::DeleteSeries,
+            WalTask::ChangeColumn { .. } => WalEntryType::ChangeColumn,
+            WalTask::DropColumn { .. } => WalEntryType::DropColumn,
         }
     }
 
@@ -200,6 +438,9 @@ impl WalTask {
             WalTask::Write { cb, .. } => cb,
             WalTask::DeleteVnode { cb, .. } => cb,
             WalTask::DeleteTable { cb, .. } => cb,
+            WalTask::DeleteSeries { cb, .. } => cb,
+            WalTask::ChangeColumn { cb, .. } => cb,
+            WalTask::DropColumn { cb, .. } => cb,
         }
     }
 
@@ -366,14 +607,52 @@ impl WalManager {
 
     /// Checks if wal file is full then writes data. Return data sequence and data size.
     pub async fn write(&mut self, wal_task: WalTask) {
-        if let Err(e) = self.roll_wal_file(self.config.max_file_size).await {
-            trace::error!("Failed to roll WAL file: {}", e);
-            if wal_task.fail(e).is_err() {
-                trace::error!("Failed to send roll WAL error to tskv");
+        self.write_batch(vec![wal_task]).await
+    }
+
+    /// Writes a batch of tasks, if sync is enabled, WAL file is synced once after all tasks
+    /// are written. Each task receives it's own data sequence and data size.
+    pub async fn write_batch(&mut self, wal_tasks: Vec<WalTask>) {
+        let mut results = Vec::with_capacity(wal_tasks.len());
+        for wal_task in wal_tasks {
+            if let Err(e) = self.roll_wal_file(self.config.max_file_size).await {
+                trace::error!("Failed to roll WAL file: {}", e);
+                if wal_task.fail(e).is_err() {
+                    trace::error!("Failed to send roll WAL error to tskv");
+                }
+                continue;
+            }
+            results.push(self.write_task(wal_task).await);
+        }
+
+        let sync_ret = if self.config.sync {
+            self.current_file.sync().await
+        } else {
+            Ok(())
+        };
+        for (write_ret, cb) in results {
+            let send_ret = match (write_ret, &sync_ret) {
+                (Ok((seq, size)), Ok(())) => {
+                    self.total_file_size += size as u64;
+                    cb.send(Ok((seq, size)))
+                }
+                (Ok((_, size)), Err(e)) => {
+                    self.total_file_size += size as u64;
+                    cb.send(Err(Error::CommonError {
+                        reason: format!("Failed to sync WAL file: {e}"),
+                    }))
+                }
+                (Err(e), _) => cb.send(Err(e)),
+            };
+            if let Err(e) = send_ret {
+                // WAL job closed, leaving this write request.
+                trace::warn!("send WAL write result failed: {:?}", e);
             }
-            return;
         }
-        let (write_ret, cb) = match wal_task {
+    }
+
+    async fn write_task(&mut self, wal_task: WalTask) -> (Result<(u64, usize)>, WriteResultSender) {
+        match wal_task {
             WalTask::Write {
                 tenant,
                 vnode_id,
@@ -408,17 +687,52 @@ impl WalManager {
                     .await,
                 cb,
             ),
-        };
-        let send_ret = match write_ret {
-            Ok((seq, size)) => {
-                self.total_file_size += size as u64;
-                cb.send(Ok((seq, size)))
-            }
-            Err(e) => cb.send(Err(e)),
-        };
-        if let Err(e) = send_ret {
-            // WAL job closed, leaving this write request.
-            trace::warn!("send WAL write result failed: {:?}", e);
+            WalTask::DeleteSeries {
+                tenant,
+                database,
+                vnode_id,
+                series_ids,
+                column_ids,
+                time_ranges,
+                cb,
+            } => (
+                self.current_file
+                    .delete_series(
+                        tenant,
+                        database,
+                        vnode_id,
+                        &series_ids,
+                        &column_ids,
+                        &time_ranges,
+                    )
+                    .await,
+                cb,
+            ),
+            WalTask::ChangeColumn {
+                tenant,
+                database,
+                table,
+                column_name,
+                new_column,
+                cb,
+            } => (
+                self.current_file
+                    .change_column(tenant, database, table, column_name, &new_column)
+                    .await,
+                cb,
+            ),
+            WalTask::DropColumn {
+                tenant,
+                database,
+                table,
+                column_id,
+                cb,
+            } => (
+                self.current_file
+                    .drop_column(tenant, database, table, column_id)
+                    .await,
+                cb,
+            ),
         }
     }
 
@@ -462,6 +776,15 @@ impl WalManager {
         self.config.sync_interval
     }
 
+    pub fn group_commit_window(&self) -> std::time::Duration {
+        self.config.group_commit_window
+    }
+
+    /// Max count of tasks to be written by `write_batch()`.
+    pub fn max_batch_size(&self) -> usize {
+        self.config.wal_req_channel_cap.max(1)
+    }
+
     pub fn is_total_file_size_exceed(&self) -> bool {
         self.total_file_size >= self.config.flush_trigger_total_file_size
     }
@@ -473,6 +796,7 @@ impl WalManager {
 
 pub struct WalDecoder {
     buffer: Vec<MiniVec<u8>>,
+    encoding: Encoding,
     decoder: Box<dyn StringCodec + Send + Sync>,
 }
 
@@ -480,10 +804,20 @@ impl WalDecoder {
     pub fn new() -> Self {
         Self {
             buffer: Vec::new(),
+            encoding: Encoding::Zstd,
             decoder: get_str_codec(Encoding::Zstd),
         }
     }
     pub fn decode(&mut self, data: &[u8]) -> Result<Option<MiniVec<u8>>> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        // The first byte is the encoding of points.
+        let encoding = Encoding::from(data[0]);
+        if encoding != self.encoding {
+            self.encoding = encoding;
+            self.decoder = get_str_codec(encoding);
+        }
         self.buffer.truncate(0);
         self.decoder
             .decode(data, &mut self.buffer)
@@ -613,6 +947,9 @@ mod test {
                             }
                             WalEntry::DeleteVnode(_) => todo!(),
                             WalEntry::DeleteTable(_) => todo!(),
+                            WalEntry::DeleteSeries(_) => todo!(),
+                            WalEntry::ChangeColumn(_) => todo!(),
+                            WalEntry::DropColumn(_) => todo!(),
                             WalEntry::Unknown => todo!(),
                         }
                     }
@@ -736,6 +1073,63 @@ mod test {
         check_wal_files(dir, data_vec, true).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_write_batch() {
+        let dir = "/tmp/test/wal/4".to_string();
+        let _ = std::fs::remove_dir_all(dir.clone()); // Ignore errors
+        let mut global_config = config::get_config_for_test();
+        global_config.wal.path = dir.clone();
+        global_config.wal.sync = true;
+        global_config.wal.compression = "zstd".to_string();
+        let wal_config = WalOptions::from(&global_config);
+
+        let tenant = "cnosdb".to_string();
+        let mut mgr = WalManager::open(Arc::new(wal_config), GlobalSequenceContext::empty())
+            .await
+            .unwrap();
+
+        // Tasks are sent by concurrent writers, and collected as the WAL job does.
+        let (task_sender, mut task_receiver) = tokio::sync::mpsc::channel(10);
+        let mut writers = Vec::with_capacity(10);
+        for i in 0..10_u8 {
+            let task_sender = task_sender.clone();
+            let tenant = tenant.clone();
+            writers.push(tokio::spawn(async move {
+                // Large enough to be compressed.
+                let data = vec![i; 1024];
+                let (wal_task, rx) = WalTask::new_write(tenant, 0, Precision::NS, data.clone());
+                task_sender.send(wal_task).await.unwrap();
+                let (seq, size) = rx.await.unwrap().unwrap();
+                assert!(size < data.len());
+                (seq, data)
+            }));
+        }
+        drop(task_sender);
+        let mut wal_tasks = Vec::with_capacity(10);
+        while let Some(wal_task) = task_receiver.recv().await {
+            wal_tasks.push(wal_task);
+        }
+        assert_eq!(wal_tasks.len(), 10);
+        assert!(writers.iter().all(|w| !w.is_finished()));
+
+        // All tasks are acknowledged after the WAL file is synced.
+        mgr.write_batch(wal_tasks).await;
+        let mut written = Vec::with_capacity(10);
+        for writer in writers {
+            written.push(writer.await.unwrap());
+        }
+        written.sort_by_key(|(seq, _)| *seq);
+        assert_eq!(
+            written.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(),
+            (1..=10).collect::<Vec<_>>()
+        );
+        mgr.close().await.unwrap();
+
+        // Compressed entries are replayed in the order of sequence.
+        let data_vec = written.into_iter().map(|(_, data)| data).collect();
+        check_wal_files(&dir, data_vec, false).await.unwrap();
+    }
+
     async fn write_points_to_wal(
         max_ts: i64,
         tenant: String,
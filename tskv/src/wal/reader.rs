@@ -2,15 +2,19 @@ use std::path::{Path, PathBuf};
 
 use models::codec::Encoding;
 use models::meta_data::VnodeId;
-use models::schema::Precision;
+use models::predicate::domain::TimeRange;
+use models::schema::{Precision, TableColumn};
+use models::{ColumnId, SeriesId};
 use protos::models_helper::print_points;
 use snafu::ResultExt;
 
 use super::{
-    WalEntryType, ENTRY_DATABASE_SIZE_LEN, ENTRY_HEADER_LEN, ENTRY_PRECISION_LEN,
-    ENTRY_TENANT_SIZE_LEN, ENTRY_VNODE_ID_LEN, FOOTER_MAGIC_NUMBER,
+    decompress_entry, WalEntryType, ENTRY_COLUMN_COUNT_LEN, ENTRY_COLUMN_ID_LEN,
+    ENTRY_COLUMN_SIZE_LEN, ENTRY_DATABASE_SIZE_LEN, ENTRY_HEADER_LEN, ENTRY_PRECISION_LEN,
+    ENTRY_SERIES_COUNT_LEN, ENTRY_SERIES_ID_LEN, ENTRY_TABLE_SIZE_LEN, ENTRY_TENANT_SIZE_LEN,
+    ENTRY_TIME_RANGE_LEN, ENTRY_VNODE_ID_LEN, FOOTER_MAGIC_NUMBER,
 };
-use crate::byte_utils::{decode_be_u32, decode_be_u64};
+use crate::byte_utils::{decode_be_i64, decode_be_u32, decode_be_u64};
 use crate::file_system::file_manager;
 use crate::tsm::codec::get_str_codec;
 use crate::{error, record_file, Error, Result};
@@ -74,6 +78,7 @@ impl WalReader {
                 return Err(Error::WalTruncated);
             }
         };
+        let data = decompress_entry(data)?;
         Ok(Some(WalEntryBlock::new(data)))
     }
 
@@ -126,6 +131,9 @@ impl WalEntryBlock {
             WalEntryType::Write => WalEntry::Write(WriteBlock::new(buf)),
             WalEntryType::DeleteVnode => WalEntry::DeleteVnode(DeleteVnodeBlock::new(buf)),
             WalEntryType::DeleteTable => WalEntry::DeleteTable(DeleteTableBlock::new(buf)),
+            WalEntryType::DeleteSeries => WalEntry::DeleteSeries(DeleteSeriesBlock::new(buf)),
+            WalEntryType::ChangeColumn => WalEntry::ChangeColumn(ChangeColumnBlock::new(buf)),
+            WalEntryType::DropColumn => WalEntry::DropColumn(DropColumnBlock::new(buf)),
             WalEntryType::Unknown => WalEntry::Unknown,
         };
         Self {
@@ -141,6 +149,9 @@ pub enum WalEntry {
     Write(WriteBlock),
     DeleteVnode(DeleteVnodeBlock),
     DeleteTable(DeleteTableBlock),
+    DeleteSeries(DeleteSeriesBlock),
+    ChangeColumn(ChangeColumnBlock),
+    DropColumn(DropColumnBlock),
     Unknown,
 }
 
@@ -328,11 +339,292 @@ impl DeleteTableBlock {
     }
 }
 
+/// buf:
+/// - header: ENTRY_HEADER_LEN
+/// - vnode_id: ENTRY_VNODE_ID_LEN
+/// - tenant_size: ENTRY_TENANT_SIZE_LEN
+/// - database_size: ENTRY_DATABASE_SIZE_LEN
+/// - series_count: ENTRY_SERIES_COUNT_LEN
+/// - column_count: ENTRY_COLUMN_COUNT_LEN
+/// - tenant: tenant_size
+/// - database: database_size
+/// - series_ids: series_count * ENTRY_SERIES_ID_LEN
+/// - column_ids: column_count * ENTRY_COLUMN_ID_LEN
+/// - time_ranges: ..
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeleteSeriesBlock {
+    buf: Vec<u8>,
+    tenant_len: usize,
+    database_len: usize,
+    series_count: usize,
+    column_count: usize,
+}
+
+impl DeleteSeriesBlock {
+    const TENANT_SIZE_POS: usize = ENTRY_HEADER_LEN + ENTRY_VNODE_ID_LEN;
+    const DATABASE_SIZE_POS: usize = Self::TENANT_SIZE_POS + ENTRY_TENANT_SIZE_LEN;
+    const SERIES_COUNT_POS: usize = Self::DATABASE_SIZE_POS + ENTRY_DATABASE_SIZE_LEN;
+    const COLUMN_COUNT_POS: usize = Self::SERIES_COUNT_POS + ENTRY_SERIES_COUNT_LEN;
+    const TENANT_POS: usize = Self::COLUMN_COUNT_POS + ENTRY_COLUMN_COUNT_LEN;
+
+    pub fn new(buf: Vec<u8>) -> DeleteSeriesBlock {
+        let tenant_len = decode_be_u64(
+            &buf[Self::TENANT_SIZE_POS..Self::TENANT_SIZE_POS + ENTRY_TENANT_SIZE_LEN],
+        ) as usize;
+        let database_len = decode_be_u32(
+            &buf[Self::DATABASE_SIZE_POS..Self::DATABASE_SIZE_POS + ENTRY_DATABASE_SIZE_LEN],
+        ) as usize;
+        let series_count = decode_be_u32(
+            &buf[Self::SERIES_COUNT_POS..Self::SERIES_COUNT_POS + ENTRY_SERIES_COUNT_LEN],
+        ) as usize;
+        let column_count = decode_be_u32(
+            &buf[Self::COLUMN_COUNT_POS..Self::COLUMN_COUNT_POS + ENTRY_COLUMN_COUNT_LEN],
+        ) as usize;
+        Self {
+            buf,
+            tenant_len,
+            database_len,
+            series_count,
+            column_count,
+        }
+    }
+
+    pub fn check_buf_size(size: usize) -> bool {
+        size >= Self::TENANT_POS
+    }
+
+    pub fn vnode_id(&self) -> VnodeId {
+        decode_be_u32(&self.buf[ENTRY_HEADER_LEN..ENTRY_HEADER_LEN + ENTRY_VNODE_ID_LEN])
+    }
+
+    pub fn tenant(&self) -> &[u8] {
+        &self.buf[Self::TENANT_POS..Self::TENANT_POS + self.tenant_len]
+    }
+
+    pub fn tenant_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self.tenant()).with_context(|_| error::InvalidUtf8Snafu {
+            message: "wal::DeleteSeriesBlock::tenant",
+        })
+    }
+
+    pub fn database(&self) -> &[u8] {
+        let database_pos = Self::TENANT_POS + self.tenant_len;
+        &self.buf[database_pos..database_pos + self.database_len]
+    }
+
+    pub fn database_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self.database()).with_context(|_| error::InvalidUtf8Snafu {
+            message: "wal::DeleteSeriesBlock::database",
+        })
+    }
+
+    pub fn series_ids(&self) -> Vec<SeriesId> {
+        let series_ids_pos = Self::TENANT_POS + self.tenant_len + self.database_len;
+        self.buf[series_ids_pos..series_ids_pos + self.series_count * ENTRY_SERIES_ID_LEN]
+            .chunks_exact(ENTRY_SERIES_ID_LEN)
+            .map(decode_be_u32)
+            .collect()
+    }
+
+    pub fn column_ids(&self) -> Vec<ColumnId> {
+        let column_ids_pos = Self::TENANT_POS
+            + self.tenant_len
+            + self.database_len
+            + self.series_count * ENTRY_SERIES_ID_LEN;
+        self.buf[column_ids_pos..column_ids_pos + self.column_count * ENTRY_COLUMN_ID_LEN]
+            .chunks_exact(ENTRY_COLUMN_ID_LEN)
+            .map(decode_be_u32)
+            .collect()
+    }
+
+    pub fn time_ranges(&self) -> Vec<TimeRange> {
+        let time_ranges_pos = Self::TENANT_POS
+            + self.tenant_len
+            + self.database_len
+            + self.series_count * ENTRY_SERIES_ID_LEN
+            + self.column_count * ENTRY_COLUMN_ID_LEN;
+        self.buf[time_ranges_pos..]
+            .chunks_exact(ENTRY_TIME_RANGE_LEN)
+            .map(|c| TimeRange::new(decode_be_i64(&c[0..8]), decode_be_i64(&c[8..16])))
+            .collect()
+    }
+}
+
+/// buf:
+/// - header: ENTRY_HEADER_LEN
+/// - tenant_size: ENTRY_TENANT_SIZE_LEN
+/// - database_size: ENTRY_DATABASE_SIZE_LEN
+/// - table_size: ENTRY_TABLE_SIZE_LEN
+/// - column_size: ENTRY_COLUMN_SIZE_LEN
+/// - tenant: tenant_size
+/// - database: database_size
+/// - table: table_size
+/// - column: column_size
+/// - new_column: ..
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeColumnBlock {
+    buf: Vec<u8>,
+    tenant_len: usize,
+    database_len: usize,
+    table_len: usize,
+    column_len: usize,
+}
+
+impl ChangeColumnBlock {
+    const DATABASE_SIZE_POS: usize = ENTRY_HEADER_LEN + ENTRY_TENANT_SIZE_LEN;
+    const TABLE_SIZE_POS: usize = Self::DATABASE_SIZE_POS + ENTRY_DATABASE_SIZE_LEN;
+    const COLUMN_SIZE_POS: usize = Self::TABLE_SIZE_POS + ENTRY_TABLE_SIZE_LEN;
+    const TENANT_POS: usize = Self::COLUMN_SIZE_POS + ENTRY_COLUMN_SIZE_LEN;
+
+    pub fn new(buf: Vec<u8>) -> ChangeColumnBlock {
+        let tenant_len = decode_be_u64(&buf[ENTRY_HEADER_LEN..Self::DATABASE_SIZE_POS]) as usize;
+        let database_len =
+            decode_be_u32(&buf[Self::DATABASE_SIZE_POS..Self::TABLE_SIZE_POS]) as usize;
+        let table_len = decode_be_u32(&buf[Self::TABLE_SIZE_POS..Self::COLUMN_SIZE_POS]) as usize;
+        let column_len = decode_be_u32(&buf[Self::COLUMN_SIZE_POS..Self::TENANT_POS]) as usize;
+        Self {
+            buf,
+            tenant_len,
+            database_len,
+            table_len,
+            column_len,
+        }
+    }
+
+    pub fn check_buf_size(size: usize) -> bool {
+        size >= Self::TENANT_POS
+    }
+
+    pub fn tenant(&self) -> &[u8] {
+        &self.buf[Self::TENANT_POS..Self::TENANT_POS + self.tenant_len]
+    }
+
+    pub fn tenant_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self.tenant()).with_context(|_| error::InvalidUtf8Snafu {
+            message: "wal::ChangeColumnBlock::tenant",
+        })
+    }
+
+    pub fn database(&self) -> &[u8] {
+        let database_pos = Self::TENANT_POS + self.tenant_len;
+        &self.buf[database_pos..database_pos + self.database_len]
+    }
+
+    pub fn database_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self.database()).with_context(|_| error::InvalidUtf8Snafu {
+            message: "wal::ChangeColumnBlock::database",
+        })
+    }
+
+    pub fn table(&self) -> &[u8] {
+        let table_pos = Self::TENANT_POS + self.tenant_len + self.database_len;
+        &self.buf[table_pos..table_pos + self.table_len]
+    }
+
+    pub fn table_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self.table()).with_context(|_| error::InvalidUtf8Snafu {
+            message: "wal::ChangeColumnBlock::table",
+        })
+    }
+
+    pub fn column_name(&self) -> &[u8] {
+        let column_pos = Self::TENANT_POS + self.tenant_len + self.database_len + self.table_len;
+        &self.buf[column_pos..column_pos + self.column_len]
+    }
+
+    pub fn column_name_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self.column_name()).with_context(|_| error::InvalidUtf8Snafu {
+            message: "wal::ChangeColumnBlock::column_name",
+        })
+    }
+
+    /// Encoded `TableColumn`.
+    pub fn new_column(&self) -> &[u8] {
+        let new_column_pos = Self::TENANT_POS
+            + self.tenant_len
+            + self.database_len
+            + self.table_len
+            + self.column_len;
+        &self.buf[new_column_pos..]
+    }
+}
+
+/// buf:
+/// - header: ENTRY_HEADER_LEN
+/// - tenant_size: ENTRY_TENANT_SIZE_LEN
+/// - database_size: ENTRY_DATABASE_SIZE_LEN
+/// - column_id: ENTRY_COLUMN_ID_LEN
+/// - tenant: tenant_size
+/// - database: database_size
+/// - table: ..
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropColumnBlock {
+    buf: Vec<u8>,
+    tenant_len: usize,
+    database_len: usize,
+}
+
+impl DropColumnBlock {
+    const DATABASE_SIZE_POS: usize = ENTRY_HEADER_LEN + ENTRY_TENANT_SIZE_LEN;
+    const COLUMN_ID_POS: usize = Self::DATABASE_SIZE_POS + ENTRY_DATABASE_SIZE_LEN;
+    const TENANT_POS: usize = Self::COLUMN_ID_POS + ENTRY_COLUMN_ID_LEN;
+
+    pub fn new(buf: Vec<u8>) -> DropColumnBlock {
+        let tenant_len = decode_be_u64(&buf[ENTRY_HEADER_LEN..Self::DATABASE_SIZE_POS]) as usize;
+        let database_len =
+            decode_be_u32(&buf[Self::DATABASE_SIZE_POS..Self::COLUMN_ID_POS]) as usize;
+        Self {
+            buf,
+            tenant_len,
+            database_len,
+        }
+    }
+
+    pub fn check_buf_size(size: usize) -> bool {
+        size >= Self::TENANT_POS
+    }
+
+    pub fn column_id(&self) -> ColumnId {
+        decode_be_u32(&self.buf[Self::COLUMN_ID_POS..Self::TENANT_POS])
+    }
+
+    pub fn tenant(&self) -> &[u8] {
+        &self.buf[Self::TENANT_POS..Self::TENANT_POS + self.tenant_len]
+    }
+
+    pub fn tenant_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self.tenant()).with_context(|_| error::InvalidUtf8Snafu {
+            message: "wal::DropColumnBlock::tenant",
+        })
+    }
+
+    pub fn database(&self) -> &[u8] {
+        let database_pos = Self::TENANT_POS + self.tenant_len;
+        &self.buf[database_pos..database_pos + self.database_len]
+    }
+
+    pub fn database_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self.database()).with_context(|_| error::InvalidUtf8Snafu {
+            message: "wal::DropColumnBlock::database",
+        })
+    }
+
+    pub fn table(&self) -> &[u8] {
+        let table_pos = Self::TENANT_POS + self.tenant_len + self.database_len;
+        &self.buf[table_pos..]
+    }
+
+    pub fn table_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self.table()).with_context(|_| error::InvalidUtf8Snafu {
+            message: "wal::DropColumnBlock::table",
+        })
+    }
+}
+
 pub async fn print_wal_statistics(path: impl AsRef<Path>) {
     use protos::models as fb_models;
 
     let mut reader = WalReader::open(path).await.unwrap();
-    let decoder = get_str_codec(Encoding::Zstd);
     loop {
         match reader.next_wal_entry().await {
             Ok(Some(entry_block)) => {
@@ -348,7 +640,10 @@ pub async fn print_wal_statistics(path: impl AsRef<Path>) {
                         );
                         let ety_points = blk.points();
                         let mut data_buf = Vec::with_capacity(ety_points.len());
-                        decoder.decode(ety_points, &mut data_buf).unwrap();
+                        // The first byte is the encoding of points.
+                        get_str_codec(Encoding::from(ety_points[0]))
+                            .decode(ety_points, &mut data_buf)
+                            .unwrap();
                         match flatbuffers::root::<fb_models::Points>(&data_buf[0]) {
                             Ok(points) => {
                                 print_points(points);
@@ -372,6 +667,36 @@ pub async fn print_wal_statistics(path: impl AsRef<Path>) {
                             std::str::from_utf8(blk.table()).unwrap(),
                         );
                     }
+                    WalEntry::DeleteSeries(blk) => {
+                        println!(
+                            "Tenant: {}, Database: {}, VnodeId: {}, Series: {:?}, Columns: {:?}, TimeRanges: {:?}",
+                            std::str::from_utf8(blk.tenant()).unwrap(),
+                            std::str::from_utf8(blk.database()).unwrap(),
+                            blk.vnode_id(),
+                            blk.series_ids(),
+                            blk.column_ids(),
+                            blk.time_ranges(),
+                        );
+                    }
+                    WalEntry::ChangeColumn(blk) => {
+                        println!(
+                            "Tenant: {}, Database: {}, Table: {}, Column: {}, NewColumn: {:?}",
+                            std::str::from_utf8(blk.tenant()).unwrap(),
+                            std::str::from_utf8(blk.database()).unwrap(),
+                            std::str::from_utf8(blk.table()).unwrap(),
+                            std::str::from_utf8(blk.column_name()).unwrap(),
+                            TableColumn::decode(blk.new_column()),
+                        );
+                    }
+                    WalEntry::DropColumn(blk) => {
+                        println!(
+                            "Tenant: {}, Database: {}, Table: {}, ColumnId: {}",
+                            std::str::from_utf8(blk.tenant()).unwrap(),
+                            std::str::from_utf8(blk.database()).unwrap(),
+                            std::str::from_utf8(blk.table()).unwrap(),
+                            blk.column_id(),
+                        );
+                    }
                     WalEntry::Unknown => {
                         println!("Unknown WAL entry type.");
                     }
@@ -396,9 +721,14 @@ pub async fn print_wal_statistics(path: impl AsRef<Path>) {
 #[cfg(test)]
 mod test {
     use models::meta_data::VnodeId;
+    use models::predicate::domain::TimeRange;
     use models::schema::Precision;
+    use models::{ColumnId, SeriesId};
 
-    use crate::wal::reader::{DeleteTableBlock, DeleteVnodeBlock, WriteBlock};
+    use crate::wal::reader::{
+        ChangeColumnBlock, DeleteSeriesBlock, DeleteTableBlock, DeleteVnodeBlock, DropColumnBlock,
+        WriteBlock,
+    };
     use crate::wal::WalEntryType;
 
     impl WriteBlock {
@@ -465,6 +795,107 @@ mod test {
         }
     }
 
+    impl DeleteSeriesBlock {
+        pub fn build(
+            seq: u64,
+            tenant: &str,
+            database: &str,
+            vnode_id: VnodeId,
+            series_ids: &[SeriesId],
+            column_ids: &[ColumnId],
+            time_ranges: &[TimeRange],
+        ) -> Self {
+            let mut buf = Vec::new();
+            let tenant_bytes = tenant.as_bytes();
+            let database_bytes = database.as_bytes();
+            buf.push(WalEntryType::DeleteSeries as u8);
+            buf.extend_from_slice(&seq.to_be_bytes());
+            buf.extend_from_slice(&vnode_id.to_be_bytes());
+            buf.extend_from_slice(&(tenant_bytes.len() as u64).to_be_bytes());
+            buf.extend_from_slice(&(database_bytes.len() as u32).to_be_bytes());
+            buf.extend_from_slice(&(series_ids.len() as u32).to_be_bytes());
+            buf.extend_from_slice(&(column_ids.len() as u32).to_be_bytes());
+            buf.extend_from_slice(tenant_bytes);
+            buf.extend_from_slice(database_bytes);
+            for sid in series_ids {
+                buf.extend_from_slice(&sid.to_be_bytes());
+            }
+            for cid in column_ids {
+                buf.extend_from_slice(&cid.to_be_bytes());
+            }
+            for tr in time_ranges {
+                buf.extend_from_slice(&tr.min_ts.to_be_bytes());
+                buf.extend_from_slice(&tr.max_ts.to_be_bytes());
+            }
+
+            Self {
+                buf,
+                tenant_len: tenant_bytes.len(),
+                database_len: database_bytes.len(),
+                series_count: series_ids.len(),
+                column_count: column_ids.len(),
+            }
+        }
+    }
+
+    impl ChangeColumnBlock {
+        pub fn build(
+            seq: u64,
+            tenant: &str,
+            database: &str,
+            table: &str,
+            column_name: &str,
+            new_column: &[u8],
+        ) -> Self {
+            let mut buf = Vec::new();
+            buf.push(WalEntryType::ChangeColumn as u8);
+            buf.extend_from_slice(&seq.to_be_bytes());
+            buf.extend_from_slice(&(tenant.len() as u64).to_be_bytes());
+            buf.extend_from_slice(&(database.len() as u32).to_be_bytes());
+            buf.extend_from_slice(&(table.len() as u32).to_be_bytes());
+            buf.extend_from_slice(&(column_name.len() as u32).to_be_bytes());
+            buf.extend_from_slice(tenant.as_bytes());
+            buf.extend_from_slice(database.as_bytes());
+            buf.extend_from_slice(table.as_bytes());
+            buf.extend_from_slice(column_name.as_bytes());
+            buf.extend_from_slice(new_column);
+
+            Self {
+                buf,
+                tenant_len: tenant.len(),
+                database_len: database.len(),
+                table_len: table.len(),
+                column_len: column_name.len(),
+            }
+        }
+    }
+
+    impl DropColumnBlock {
+        pub fn build(
+            seq: u64,
+            tenant: &str,
+            database: &str,
+            table: &str,
+            column_id: ColumnId,
+        ) -> Self {
+            let mut buf = Vec::new();
+            buf.push(WalEntryType::DropColumn as u8);
+            buf.extend_from_slice(&seq.to_be_bytes());
+            buf.extend_from_slice(&(tenant.len() as u64).to_be_bytes());
+            buf.extend_from_slice(&(database.len() as u32).to_be_bytes());
+            buf.extend_from_slice(&column_id.to_be_bytes());
+            buf.extend_from_slice(tenant.as_bytes());
+            buf.extend_from_slice(database.as_bytes());
+            buf.extend_from_slice(table.as_bytes());
+
+            Self {
+                buf,
+                tenant_len: tenant.len(),
+                database_len: database.len(),
+            }
+        }
+    }
+
     #[test]
     fn test_wal_blocks() {
         {
@@ -490,5 +921,41 @@ mod test {
             assert_eq!(block.table(), b"table");
             assert_eq!(block.table_utf8().unwrap(), "table");
         }
+        {
+            let time_ranges = vec![TimeRange::new(1, 10), TimeRange::new(20, 30)];
+            let block = DeleteSeriesBlock::build(
+                6,
+                "tenant",
+                "database",
+                7,
+                &[1, 2, 3],
+                &[4, 5],
+                &time_ranges,
+            );
+            assert_eq!(block.tenant(), b"tenant");
+            assert_eq!(block.tenant_utf8().unwrap(), "tenant");
+            assert_eq!(block.database(), b"database");
+            assert_eq!(block.database_utf8().unwrap(), "database");
+            assert_eq!(block.vnode_id(), 7);
+            assert_eq!(block.series_ids(), vec![1, 2, 3]);
+            assert_eq!(block.column_ids(), vec![4, 5]);
+            assert_eq!(block.time_ranges(), time_ranges);
+        }
+        {
+            let block =
+                ChangeColumnBlock::build(8, "tenant", "database", "table", "column", &[1, 2, 3]);
+            assert_eq!(block.tenant_utf8().unwrap(), "tenant");
+            assert_eq!(block.database_utf8().unwrap(), "database");
+            assert_eq!(block.table_utf8().unwrap(), "table");
+            assert_eq!(block.column_name_utf8().unwrap(), "column");
+            assert_eq!(block.new_column(), &[1, 2, 3]);
+        }
+        {
+            let block = DropColumnBlock::build(9, "tenant", "database", "table", 10);
+            assert_eq!(block.tenant_utf8().unwrap(), "tenant");
+            assert_eq!(block.database_utf8().unwrap(), "database");
+            assert_eq!(block.table_utf8().unwrap(), "table");
+            assert_eq!(block.column_id(), 10);
+        }
     }
 }
@@ -2,10 +2,14 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use models::meta_data::VnodeId;
+use models::predicate::domain::TimeRange;
 use models::schema::Precision;
+use models::{ColumnId, SeriesId};
 
 use super::reader::WalReader;
-use super::{WalEntryType, FOOTER_MAGIC_NUMBER};
+use super::{
+    WalCompression, WalEntryType, ENTRY_COMPRESSED_FLAG, ENTRY_COMPRESSION_LEN, FOOTER_MAGIC_NUMBER,
+};
 use crate::file_system::file_manager;
 use crate::kv_option::WalOptions;
 use crate::record_file::{RecordDataType, RecordDataVersion};
@@ -19,6 +23,49 @@ fn build_footer(min_sequence: u64, max_sequence: u64) -> [u8; record_file::FILE_
     footer
 }
 
+/// Writes an entry, the data after entry header is compressed if compression is enabled
+/// and the compressed data is smaller. Returns the written data size.
+async fn write_entry(
+    writer: &mut record_file::Writer,
+    compression: WalCompression,
+    typ: WalEntryType,
+    seq: u64,
+    data: &[&[u8]],
+) -> Result<usize> {
+    let seq = seq.to_be_bytes();
+    if compression != WalCompression::None {
+        let data = data.concat();
+        let compressed_data = compression.compress(&data)?;
+        if compressed_data.len() + ENTRY_COMPRESSION_LEN < data.len() {
+            return writer
+                .write_record(
+                    RecordDataVersion::V1 as u8,
+                    RecordDataType::Wal as u8,
+                    &[
+                        &[typ as u8 | ENTRY_COMPRESSED_FLAG],
+                        &seq,
+                        &[compression as u8],
+                        &compressed_data,
+                    ],
+                )
+                .await;
+        }
+    }
+
+    let typ = [typ as u8];
+    let mut record: Vec<&[u8]> = Vec::with_capacity(data.len() + 2);
+    record.push(&typ);
+    record.push(&seq);
+    record.extend_from_slice(data);
+    writer
+        .write_record(
+            RecordDataVersion::V1 as u8,
+            RecordDataType::Wal as u8,
+            &record,
+        )
+        .await
+}
+
 pub struct WalWriter {
     id: u64,
     inner: record_file::Writer,
@@ -83,28 +130,22 @@ impl WalWriter {
         let seq = self.max_sequence;
         let tenant_len = tenant.len() as u64;
 
-        let written_size = self
-            .inner
-            .write_record(
-                RecordDataVersion::V1 as u8,
-                RecordDataType::Wal as u8,
-                [
-                    &[WalEntryType::Write as u8][..],
-                    &seq.to_be_bytes(),
-                    &vnode_id.to_be_bytes(),
-                    &(precision as u8).to_be_bytes(),
-                    &tenant_len.to_be_bytes(),
-                    tenant.as_bytes(),
-                    &points,
-                ]
-                .as_slice(),
-            )
-            .await?;
-
-        if self.config.sync {
-            self.inner.sync().await?;
-        }
-        // write & fsync succeed
+        let written_size = write_entry(
+            &mut self.inner,
+            self.config.compression,
+            WalEntryType::Write,
+            seq,
+            &[
+                &vnode_id.to_be_bytes(),
+                &(precision as u8).to_be_bytes(),
+                &tenant_len.to_be_bytes(),
+                tenant.as_bytes(),
+                &points,
+            ],
+        )
+        .await?;
+
+        // write succeed
         self.max_sequence += 1;
         self.size += written_size as u64;
         Ok((seq, written_size))
@@ -119,27 +160,21 @@ impl WalWriter {
         let seq = self.max_sequence;
         let tenant_len = tenant.len() as u64;
 
-        let written_size = self
-            .inner
-            .write_record(
-                RecordDataVersion::V1 as u8,
-                RecordDataType::Wal as u8,
-                [
-                    &[WalEntryType::DeleteVnode as u8][..],
-                    &seq.to_be_bytes(),
-                    &vnode_id.to_be_bytes(),
-                    &tenant_len.to_be_bytes(),
-                    tenant.as_bytes(),
-                    database.as_bytes(),
-                ]
-                .as_slice(),
-            )
-            .await?;
-
-        if self.config.sync {
-            self.inner.sync().await?;
-        }
-        // write & fsync succeed
+        let written_size = write_entry(
+            &mut self.inner,
+            self.config.compression,
+            WalEntryType::DeleteVnode,
+            seq,
+            &[
+                &vnode_id.to_be_bytes(),
+                &tenant_len.to_be_bytes(),
+                tenant.as_bytes(),
+                database.as_bytes(),
+            ],
+        )
+        .await?;
+
+        // write succeed
         self.max_sequence += 1;
         self.size += written_size as u64;
         Ok((seq, written_size))
@@ -155,28 +190,145 @@ impl WalWriter {
         let tenant_len = tenant.len() as u64;
         let database_len = database.len() as u32;
 
-        let written_size = self
-            .inner
-            .write_record(
-                RecordDataVersion::V1 as u8,
-                RecordDataType::Wal as u8,
-                [
-                    &[WalEntryType::DeleteTable as u8][..],
-                    &seq.to_be_bytes(),
-                    &tenant_len.to_be_bytes(),
-                    &database_len.to_be_bytes(),
-                    tenant.as_bytes(),
-                    database.as_bytes(),
-                    table.as_bytes(),
-                ]
-                .as_slice(),
-            )
-            .await?;
+        let written_size = write_entry(
+            &mut self.inner,
+            self.config.compression,
+            WalEntryType::DeleteTable,
+            seq,
+            &[
+                &tenant_len.to_be_bytes(),
+                &database_len.to_be_bytes(),
+                tenant.as_bytes(),
+                database.as_bytes(),
+                table.as_bytes(),
+            ],
+        )
+        .await?;
+
+        // write succeed
+        self.max_sequence += 1;
+        self.size += written_size as u64;
+        Ok((seq, written_size))
+    }
+
+    pub async fn delete_series(
+        &mut self,
+        tenant: String,
+        database: String,
+        vnode_id: VnodeId,
+        series_ids: &[SeriesId],
+        column_ids: &[ColumnId],
+        time_ranges: &[TimeRange],
+    ) -> Result<(u64, usize)> {
+        let seq = self.max_sequence;
+        let tenant_len = tenant.len() as u64;
+        let database_len = database.len() as u32;
+        let series_count = series_ids.len() as u32;
+        let column_count = column_ids.len() as u32;
 
-        if self.config.sync {
-            self.inner.sync().await?;
+        self.buf.clear();
+        for sid in series_ids {
+            self.buf.extend_from_slice(&sid.to_be_bytes());
+        }
+        for cid in column_ids {
+            self.buf.extend_from_slice(&cid.to_be_bytes());
+        }
+        for tr in time_ranges {
+            self.buf.extend_from_slice(&tr.min_ts.to_be_bytes());
+            self.buf.extend_from_slice(&tr.max_ts.to_be_bytes());
         }
-        // write & fsync succeed
+
+        let written_size = write_entry(
+            &mut self.inner,
+            self.config.compression,
+            WalEntryType::DeleteSeries,
+            seq,
+            &[
+                &vnode_id.to_be_bytes(),
+                &tenant_len.to_be_bytes(),
+                &database_len.to_be_bytes(),
+                &series_count.to_be_bytes(),
+                &column_count.to_be_bytes(),
+                tenant.as_bytes(),
+                database.as_bytes(),
+                &self.buf,
+            ],
+        )
+        .await?;
+
+        // write succeed
+        self.max_sequence += 1;
+        self.size += written_size as u64;
+        Ok((seq, written_size))
+    }
+
+    pub async fn change_column(
+        &mut self,
+        tenant: String,
+        database: String,
+        table: String,
+        column_name: String,
+        new_column: &[u8],
+    ) -> Result<(u64, usize)> {
+        let seq = self.max_sequence;
+        let tenant_len = tenant.len() as u64;
+        let database_len = database.len() as u32;
+        let table_len = table.len() as u32;
+        let column_len = column_name.len() as u32;
+
+        let written_size = write_entry(
+            &mut self.inner,
+            self.config.compression,
+            WalEntryType::ChangeColumn,
+            seq,
+            &[
+                &tenant_len.to_be_bytes(),
+                &database_len.to_be_bytes(),
+                &table_len.to_be_bytes(),
+                &column_len.to_be_bytes(),
+                tenant.as_bytes(),
+                database.as_bytes(),
+                table.as_bytes(),
+                column_name.as_bytes(),
+                new_column,
+            ],
+        )
+        .await?;
+
+        // write succeed
+        self.max_sequence += 1;
+        self.size += written_size as u64;
+        Ok((seq, written_size))
+    }
+
+    pub async fn drop_column(
+        &mut self,
+        tenant: String,
+        database: String,
+        table: String,
+        column_id: ColumnId,
+    ) -> Result<(u64, usize)> {
+        let seq = self.max_sequence;
+        let tenant_len = tenant.len() as u64;
+        let database_len = database.len() as u32;
+
+        let written_size = write_entry(
+            &mut self.inner,
+            self.config.compression,
+            WalEntryType::DropColumn,
+            seq,
+            &[
+                &tenant_len.to_be_bytes(),
+                &database_len.to_be_bytes(),
+                &column_id.to_be_bytes(),
+                tenant.as_bytes(),
+                database.as_bytes(),
+                table.as_bytes(),
+            ],
+        )
+        .await?;
+
+        // write succeed
         self.max_sequence += 1;
         self.size += written_size as u64;
         Ok((seq, written_size))
@@ -224,10 +376,14 @@ mod test {
     use std::path::PathBuf;
     use std::sync::Arc;
 
+    use models::predicate::domain::TimeRange;
     use models::schema::Precision;
 
     use crate::kv_option::WalOptions;
-    use crate::wal::reader::{DeleteTableBlock, DeleteVnodeBlock, WalEntry, WalReader, WriteBlock};
+    use crate::wal::reader::{
+        ChangeColumnBlock, DeleteSeriesBlock, DeleteTableBlock, DeleteVnodeBlock, DropColumnBlock,
+        WalEntry, WalReader, WriteBlock,
+    };
     use crate::wal::writer::WalWriter;
     use crate::Error;
 
@@ -247,6 +403,13 @@ mod test {
             )),
             WalEntry::DeleteVnode(DeleteVnodeBlock::build(2, "cnosdb", "public", 6)),
             WalEntry::DeleteTable(DeleteTableBlock::build(3, "cnosdb", "public", "table")),
+            WalEntry::DeleteSeries(DeleteSeriesBlock::build(
+                4, "cnosdb", "public", 6, &[1, 2], &[0, 3], &[TimeRange::new(1, 100)],
+            )),
+            WalEntry::ChangeColumn(ChangeColumnBlock::build(
+                5, "cnosdb", "public", "table", "column", &[1, 2, 3],
+            )),
+            WalEntry::DropColumn(DropColumnBlock::build(6, "cnosdb", "public", "table", 3)),
         ];
 
         let wal_path = PathBuf::from(dir).join("1.wal");
@@ -275,6 +438,40 @@ mod test {
                         let table = String::from_utf8(d.table().to_vec()).unwrap();
                         writer.delete_table(tenant, database, table).await.unwrap();
                     }
+                    WalEntry::DeleteSeries(d) => {
+                        let tenant = String::from_utf8(d.tenant().to_vec()).unwrap();
+                        let database = String::from_utf8(d.database().to_vec()).unwrap();
+                        writer
+                            .delete_series(
+                                tenant,
+                                database,
+                                d.vnode_id(),
+                                &d.series_ids(),
+                                &d.column_ids(),
+                                &d.time_ranges(),
+                            )
+                            .await
+                            .unwrap();
+                    }
+                    WalEntry::ChangeColumn(d) => {
+                        let tenant = String::from_utf8(d.tenant().to_vec()).unwrap();
+                        let database = String::from_utf8(d.database().to_vec()).unwrap();
+                        let table = String::from_utf8(d.table().to_vec()).unwrap();
+                        let column = String::from_utf8(d.column_name().to_vec()).unwrap();
+                        writer
+                            .change_column(tenant, database, table, column, d.new_column())
+                            .await
+                            .unwrap();
+                    }
+                    WalEntry::DropColumn(d) => {
+                        let tenant = String::from_utf8(d.tenant().to_vec()).unwrap();
+                        let database = String::from_utf8(d.database().to_vec()).unwrap();
+                        let table = String::from_utf8(d.table().to_vec()).unwrap();
+                        writer
+                            .drop_column(tenant, database, table, d.column_id())
+                            .await
+                            .unwrap();
+                    }
                     WalEntry::Unknown => {
                         // ignore
                     }
@@ -298,4 +495,50 @@ mod test {
             i += 1;
         }
     }
+
+    #[tokio::test]
+    async fn test_write_compressed() {
+        for (i, compression) in ["zstd", "snappy"].into_iter().enumerate() {
+            let dir = format!("/tmp/test/wal_writer/compressed_{i}");
+            let _ = std::fs::remove_dir_all(&dir);
+
+            let mut global_config = config::get_config_for_test();
+            global_config.wal.path = dir.clone();
+            global_config.wal.compression = compression.to_string();
+            let wal_config = Arc::new(WalOptions::from(&global_config));
+
+            let entries = vec![
+                WalEntry::Write(WriteBlock::build(
+                    1,
+                    "cnosdb",
+                    3,
+                    Precision::NS,
+                    vec![1; 1024],
+                )),
+                // Too short to be compressed.
+                WalEntry::DeleteVnode(DeleteVnodeBlock::build(2, "cnosdb", "public", 6)),
+            ];
+
+            let wal_path = PathBuf::from(&dir).join("1.wal");
+            let wal_path = {
+                let mut writer = WalWriter::open(wal_config, 1, wal_path, 1).await.unwrap();
+                let (_, written_size) = writer
+                    .write("cnosdb".to_string(), 3, Precision::NS, vec![1; 1024])
+                    .await
+                    .unwrap();
+                assert!(written_size < 1024);
+                writer
+                    .delete_vnode("cnosdb".to_string(), "public".to_string(), 6)
+                    .await
+                    .unwrap();
+                writer.path
+            };
+
+            let mut reader = WalReader::open(&wal_path).await.unwrap();
+            for entry in entries.iter() {
+                let blk = reader.next_wal_entry().await.unwrap().unwrap();
+                assert_eq!(&blk.entry, entry);
+            }
+        }
+    }
 }
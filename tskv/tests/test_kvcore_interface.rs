@@ -1,22 +1,30 @@
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::path::{Path, PathBuf};
     use std::sync::Arc;
     use std::time::{Duration, Instant};
 
+    use datafusion::arrow::array::{
+        Array, BinaryArray, BooleanArray, Int64Array, UInt32Array, UInt64Array,
+    };
+    use datafusion::arrow::record_batch::RecordBatch;
     use memory_pool::GreedyMemoryPool;
     use meta::model::meta_admin::AdminMeta;
     use meta::model::MetaRef;
     use metrics::metric_register::MetricsRegister;
+    use models::predicate::domain::{ColumnDomains, ResolvedPredicate, TimeRange, TimeRanges};
     use models::schema::{Precision, TenantOptions};
+    use models::SeriesKey;
     use protos::kv_service::Meta;
-    use protos::{kv_service, models_helper};
+    use protos::models::FieldType;
+    use protos::{kv_service, models_helper, FbSchema};
     use serial_test::serial;
     use tokio::runtime;
     use tokio::runtime::Runtime;
     use trace::{debug, error, info, init_default_global_tracing, warn};
     use tskv::file_system::file_manager;
-    use tskv::{kv_option, Engine, TsKv};
+    use tskv::{kv_option, series_range_digest, Engine, SeriesRangeRepair, TsKv};
 
     /// Initializes a TsKv instance in specified directory, with an optional runtime,
     /// returns the TsKv and runtime.
@@ -312,6 +320,381 @@ mod tests {
         assert!(!cached_data.is_empty());
     }
 
+    fn write_request(tenant: &str, database: &str, table: &str) -> kv_service::WritePointsRequest {
+        let mut fbb = flatbuffers::FlatBufferBuilder::new();
+        let points =
+            models_helper::create_random_points_include_delta(&mut fbb, database, table, 20);
+        fbb.finish(points, None);
+        kv_service::WritePointsRequest {
+            version: 1,
+            meta: Some(Meta {
+                tenant: tenant.to_string(),
+                user: None,
+                password: None,
+            }),
+            points: fbb.finished_data().to_vec(),
+        }
+    }
+
+    /// Returns the keys of all series of the table in the vnode.
+    async fn series_keys(
+        tskv: &TsKv,
+        tenant: &str,
+        database: &str,
+        table: &str,
+        vnode_id: u32,
+    ) -> Vec<(SeriesKey, TimeRange)> {
+        let series_ids = tskv
+            .get_series_id_by_filter(tenant, database, table, vnode_id, &ColumnDomains::all())
+            .await
+            .unwrap();
+        let mut keys = vec![];
+        for series_id in series_ids {
+            let key = tskv
+                .get_series_key(tenant, database, vnode_id, series_id)
+                .await
+                .unwrap()
+                .unwrap();
+            keys.push((key, TimeRange::all()));
+        }
+        keys
+    }
+
+    /// Returns the timestamps of all values of the series.
+    async fn read_timestamps(
+        tskv: &TsKv,
+        vnode_id: u32,
+        keys: Vec<(SeriesKey, TimeRange)>,
+    ) -> Vec<i64> {
+        let batch = tskv.read_vnode_series_data(vnode_id, keys).await.unwrap();
+        let times = batch
+            .column_by_name("TIME")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        // Rows of tombstones have the max time.
+        let max_times = batch
+            .column_by_name("MAX_TIME")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        (0..batch.num_rows())
+            .filter(|i| max_times.is_null(*i))
+            .map(|i| times.value(i))
+            .collect()
+    }
+
+    #[test]
+    #[serial]
+    fn test_kvcore_delete_recover() {
+        let dir = PathBuf::from("/tmp/test/kvcore/kvcore_delete_recover");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        init_default_global_tracing(dir.join("log"), "tskv.log", "debug");
+        let tenant = "cnosdb";
+        let database = "db_delete_recover";
+        let table = "kvcore_delete_recover";
+        let vnode_id = 11;
+
+        let (runtime, keys) = {
+            let (runtime, tskv) = get_tskv(&dir, None);
+            let request = write_request(tenant, database, table);
+            runtime
+                .block_on(tskv.write(None, vnode_id, Precision::NS, request))
+                .unwrap();
+            let keys = runtime.block_on(series_keys(&tskv, tenant, database, table, vnode_id));
+            assert!(!keys.is_empty());
+
+            let predicate = ResolvedPredicate::new(
+                Arc::new(TimeRanges::all()),
+                ColumnDomains::all(),
+                ColumnDomains::all(),
+            );
+            runtime
+                .block_on(tskv.delete_from_table(tenant, database, table, vnode_id, &predicate))
+                .unwrap();
+            runtime.block_on(tskv.close());
+
+            (runtime, keys)
+        };
+
+        // Both the write and the deletion are replayed from WAL.
+        let (runtime, tskv) = get_tskv(&dir, Some(runtime));
+        let timestamps = runtime.block_on(read_timestamps(&tskv, vnode_id, keys));
+        assert!(timestamps.is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn test_kvcore_delete_compact() {
+        let dir = PathBuf::from("/tmp/test/kvcore/kvcore_delete_compact");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        init_default_global_tracing(dir.join("log"), "tskv.log", "debug");
+        let tenant = "cnosdb";
+        let database = "db_delete_compact";
+        let table = "kvcore_delete_compact";
+        let vnode_id = 12;
+
+        let (runtime, tskv) = get_tskv(&dir, None);
+        // Data is in several files.
+        for _ in 0..3 {
+            let request = write_request(tenant, database, table);
+            runtime
+                .block_on(tskv.write(None, vnode_id, Precision::NS, request))
+                .unwrap();
+            runtime
+                .block_on(tskv.flush_tsfamily(tenant, database, vnode_id))
+                .unwrap();
+        }
+        let keys = runtime.block_on(series_keys(&tskv, tenant, database, table, vnode_id));
+
+        // Half of the points are at i64::MIN, delete them.
+        let predicate = ResolvedPredicate::new(
+            Arc::new(TimeRanges::new(vec![TimeRange::new(i64::MIN, i64::MIN)])),
+            ColumnDomains::all(),
+            ColumnDomains::all(),
+        );
+        runtime
+            .block_on(tskv.delete_from_table(tenant, database, table, vnode_id, &predicate))
+            .unwrap();
+        runtime.block_on(tskv.compact(vec![vnode_id])).unwrap();
+
+        let timestamps = runtime.block_on(read_timestamps(&tskv, vnode_id, keys.clone()));
+        assert!(!timestamps.is_empty());
+        assert!(timestamps.iter().all(|ts| *ts != i64::MIN));
+
+        // Deleted data doesn't come back after reopening.
+        runtime.block_on(tskv.close());
+        let (runtime, tskv) = get_tskv(&dir, Some(runtime));
+        let timestamps = runtime.block_on(read_timestamps(&tskv, vnode_id, keys));
+        assert!(!timestamps.is_empty());
+        assert!(timestamps.iter().all(|ts| *ts != i64::MIN));
+    }
+
+    #[test]
+    #[serial]
+    fn test_kvcore_series_data_concurrent_write_delete() {
+        let dir = PathBuf::from("/tmp/test/kvcore/kvcore_series_data_concurrent_write_delete");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        init_default_global_tracing(dir.join("log"), "tskv.log", "debug");
+        let tenant = "cnosdb";
+        let database = "db_series_data";
+        let table = "kvcore_series_data";
+        let vnode_id = 13;
+
+        let (runtime, tskv) = get_tskv(&dir, None);
+        let request = write_request(tenant, database, table);
+        runtime
+            .block_on(tskv.write(None, vnode_id, Precision::NS, request))
+            .unwrap();
+        runtime
+            .block_on(tskv.flush_tsfamily(tenant, database, vnode_id))
+            .unwrap();
+
+        // Points are written while the points at i64::MIN are deleted.
+        let predicate = ResolvedPredicate::new(
+            Arc::new(TimeRanges::new(vec![TimeRange::new(i64::MIN, i64::MIN)])),
+            ColumnDomains::all(),
+            ColumnDomains::all(),
+        );
+        runtime.block_on(async {
+            let writes = (0..10).map(|_| async {
+                let request = write_request(tenant, database, table);
+                tskv.write(None, vnode_id, Precision::NS, request)
+                    .await
+                    .unwrap();
+            });
+            let deletes = (0..5).map(|_| async {
+                tskv.delete_from_table(tenant, database, table, vnode_id, &predicate)
+                    .await
+                    .unwrap();
+            });
+            futures::join!(
+                futures::future::join_all(writes),
+                futures::future::join_all(deletes)
+            );
+        });
+
+        let keys = runtime.block_on(series_keys(&tskv, tenant, database, table, vnode_id));
+        let batch = runtime
+            .block_on(tskv.read_vnode_series_data(vnode_id, keys.clone()))
+            .unwrap();
+        let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+        let (series_keys, column_ids, times, max_times, seqs) = (
+            column("SERIES_KEY"),
+            column("COLUMN_ID"),
+            column("TIME"),
+            column("MAX_TIME"),
+            column("SEQUENCE"),
+        );
+        let series_keys = series_keys.as_any().downcast_ref::<BinaryArray>().unwrap();
+        let column_ids = column_ids.as_any().downcast_ref::<UInt32Array>().unwrap();
+        let times = times.as_any().downcast_ref::<Int64Array>().unwrap();
+        let max_times = max_times.as_any().downcast_ref::<Int64Array>().unwrap();
+        let seqs = seqs.as_any().downcast_ref::<UInt64Array>().unwrap();
+
+        let (values, tombstones): (Vec<usize>, Vec<usize>) =
+            (0..batch.num_rows()).partition(|i| max_times.is_null(*i));
+        assert!(!values.is_empty());
+        assert!(!tombstones.is_empty());
+        // A value at i64::MIN is only kept if it's written after all deletions of it.
+        for v in values.iter() {
+            for t in tombstones.iter() {
+                if series_keys.value(*v) == series_keys.value(*t)
+                    && column_ids.value(*v) == column_ids.value(*t)
+                    && times.value(*t) <= times.value(*v)
+                    && times.value(*v) <= max_times.value(*t)
+                {
+                    assert!(seqs.value(*v) > seqs.value(*t));
+                }
+            }
+        }
+    }
+
+    /// Write request of `num` points of the series `ta=a,tb={tb}` from timestamp `start`,
+    /// the value of field `fa` is `fa`.
+    fn series_write_request(
+        tenant: &str,
+        database: &str,
+        table: &str,
+        tb: &str,
+        fa: i64,
+        start: i64,
+        num: usize,
+    ) -> kv_service::WritePointsRequest {
+        let schema = FbSchema::new(
+            HashMap::from([("ta", 0), ("tb", 1)]),
+            HashMap::from([("fa", 0)]),
+            vec![FieldType::Integer],
+        );
+        let mut fbb = flatbuffers::FlatBufferBuilder::new();
+        let points = models_helper::create_const_points(
+            &mut fbb,
+            schema,
+            database,
+            table,
+            vec![("ta", "a"), ("tb", tb)],
+            vec![("fa", &fa.to_be_bytes())],
+            start,
+            num,
+        );
+        fbb.finish(points, None);
+        kv_service::WritePointsRequest {
+            version: 1,
+            meta: Some(Meta {
+                tenant: tenant.to_string(),
+                user: None,
+                password: None,
+            }),
+            points: fbb.finished_data().to_vec(),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_kvcore_repair_series_data_concurrent_write() {
+        let dir = PathBuf::from("/tmp/test/kvcore/kvcore_repair_series_data_concurrent_write");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        init_default_global_tracing(dir.join("log"), "tskv.log", "debug");
+        let tenant = "cnosdb";
+        let database = "db_repair";
+        let table = "kvcore_repair";
+        let vnode_id = 14;
+
+        let (runtime, tskv) = get_tskv(&dir, None);
+        for tb in ["b", "c"] {
+            let request = series_write_request(tenant, database, table, tb, 1, 1, 10);
+            runtime
+                .block_on(tskv.write(None, vnode_id, Precision::NS, request))
+                .unwrap();
+        }
+        let time_range = TimeRange::new(0, 100);
+        let keys = runtime
+            .block_on(series_keys(&tskv, tenant, database, table, vnode_id))
+            .into_iter()
+            .map(|(key, _)| (key, time_range))
+            .collect::<Vec<_>>();
+        assert_eq!(keys.len(), 2);
+        let encode = |key: &SeriesKey| {
+            let mut key = key.clone();
+            key.set_id(0);
+            key.encode()
+        };
+        let tb = |key: &SeriesKey| {
+            key.tags()
+                .iter()
+                .find(|tag| tag.key == b"tb")
+                .map(|tag| tag.value.clone())
+                .unwrap()
+        };
+
+        // The data to repair from is fetched.
+        let fetched = runtime
+            .block_on(tskv.read_vnode_series_data(vnode_id, keys.clone()))
+            .unwrap();
+
+        // A point is written to series tb=b before the repair is applied.
+        let request = series_write_request(tenant, database, table, "b", 2, 5, 1);
+        runtime
+            .block_on(tskv.write(None, vnode_id, Precision::NS, request))
+            .unwrap();
+        let written = runtime
+            .block_on(tskv.read_vnode_series_data(vnode_id, keys.clone()))
+            .unwrap();
+
+        // The repair writes the fetched value at 5 to tb=b, and a value at 11 to tb=c.
+        let ranges = keys
+            .iter()
+            .map(|(key, time_range)| {
+                let tb = String::from_utf8(tb(key)).unwrap();
+                let (fa, start) = if tb == "b" { (1, 5) } else { (1, 11) };
+                SeriesRangeRepair {
+                    series_key: key.clone(),
+                    time_range: *time_range,
+                    digest: series_range_digest(&fetched, &encode(key), time_range).unwrap(),
+                    points: series_write_request(tenant, database, table, &tb, fa, start, 1).points,
+                }
+            })
+            .collect::<Vec<_>>();
+        let result = runtime
+            .block_on(tskv.repair_vnode_series_data(tenant, vnode_id, Precision::NS, ranges))
+            .unwrap();
+        let repaired = result
+            .column_by_name("REPAIRED")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap();
+        for (i, (key, _)) in keys.iter().enumerate() {
+            assert_eq!(repaired.value(i), tb(key) == b"c");
+        }
+
+        let repaired_data = runtime
+            .block_on(tskv.read_vnode_series_data(vnode_id, keys.clone()))
+            .unwrap();
+        for (key, time_range) in keys.iter() {
+            let digest =
+                |batch: &RecordBatch| series_range_digest(batch, &encode(key), time_range).unwrap();
+            if tb(key) == b"b" {
+                // The written point isn't overwritten by the repair.
+                assert_eq!(digest(&repaired_data), digest(&written));
+            } else {
+                assert_ne!(digest(&repaired_data), digest(&written));
+                let timestamps = runtime.block_on(read_timestamps(
+                    &tskv,
+                    vnode_id,
+                    vec![(key.clone(), *time_range)],
+                ));
+                assert!(timestamps.contains(&11));
+            }
+        }
+    }
+
     async fn async_func1() {
         // println!("run async func1");
         async_func3().await;